@@ -0,0 +1,699 @@
+//! `act`: run a short list of steps and return the resulting page state.
+//!
+//! Agent frameworks typically ask for "do these things, then show me the
+//! page". `act` validates every step with [`parse_command`] before anything
+//! is sent, runs them in order over one daemon connection, stops at the
+//! first failure, and finishes with an interactive snapshot (unless
+//! `--no-final-snapshot`) so the caller gets fresh refs in the same call.
+//! A check that does not pass (a false `is`, a `get count` comparison, `get
+//! url --query`) also stops the run, and `act` then exits with the code the
+//! command would exit with on its own.
+
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::color;
+use crate::commands::{parse_command, shell_words_split};
+use crate::connection::Response;
+use crate::flags::Flags;
+use crate::progress::{ProgressEmitter, StepError};
+use crate::redact::{CommandRedactor, Secrets};
+
+/// Actions that cannot run as a step: they are orchestrated by the CLI
+/// itself rather than executed by the daemon.
+const NESTED_ACTIONS: &[&str] = &["act", "batch"];
+
+pub const STEPS_HINT: &str = r#"Expected a JSON array of step objects, e.g. [{"command": "click @e1"}, {"command": ["fill", "@e2", "hello"]}]"#;
+
+/// A validated step: the CLI words (for display, with secrets hidden) and
+/// the parsed command.
+pub struct Step {
+    pub words: Vec<String>,
+    pub command: Value,
+    secrets: Secrets,
+}
+
+/// Read step objects from JSON. Each step is `{"command": "<cli words>"}` or
+/// `{"command": ["<word>", ...]}`; the array form avoids shell quoting.
+pub fn parse_steps(input: &str) -> Result<Vec<Vec<String>>, String> {
+    let value: Value =
+        serde_json::from_str(input).map_err(|e| format!("Invalid JSON: {}. {}", e, STEPS_HINT))?;
+    let items = value
+        .as_array()
+        .ok_or_else(|| format!("Steps must be a JSON array. {}", STEPS_HINT))?;
+    if items.is_empty() {
+        return Err(format!("No steps given. {}", STEPS_HINT));
+    }
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let words = match item.get("command") {
+                Some(Value::String(line)) => shell_words_split(line),
+                Some(Value::Array(parts)) => parts
+                    .iter()
+                    .map(|p| p.as_str().map(String::from))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| {
+                        format!("Step {}: \"command\" array must contain strings", i + 1)
+                    })?,
+                _ => {
+                    return Err(format!(
+                        "Step {}: expected an object with a \"command\" string or array",
+                        i + 1
+                    ))
+                }
+            };
+            if words.is_empty() {
+                return Err(format!("Step {}: empty command", i + 1));
+            }
+            Ok(words)
+        })
+        .collect()
+}
+
+/// Parse every step up front so a typo in step 3 fails before steps 1 and 2
+/// have touched the page.
+pub fn validate_steps(steps: Vec<Vec<String>>, flags: &Flags) -> Result<Vec<Step>, String> {
+    let redactor = CommandRedactor::new(flags.redact_patterns.as_deref());
+    steps
+        .into_iter()
+        .enumerate()
+        .map(|(i, words)| {
+            let command = parse_command(&words, flags)
+                .map_err(|e| format!("Step {} ({}): {}", i + 1, words.join(" "), e.format()))?;
+            let action = command.get("action").and_then(|v| v.as_str());
+            if let Some(action) = action.filter(|a| NESTED_ACTIONS.contains(a)) {
+                return Err(format!(
+                    "Step {} ({}): {} cannot be used inside act",
+                    i + 1,
+                    words.join(" "),
+                    action
+                ));
+            }
+            let secrets = redactor.secrets(&command);
+            Ok(Step {
+                words: secrets.words(&words),
+                command,
+                secrets,
+            })
+        })
+        .collect()
+}
+
+pub struct StepResult {
+    pub words: Vec<String>,
+    pub result: Result<Response, String>,
+    /// The step ran, but its check did not pass: the exit code the
+    /// check asked for.
+    pub check_exit_code: Option<i32>,
+}
+
+impl StepResult {
+    fn succeeded(&self) -> bool {
+        matches!(self.result, Ok(ref resp) if resp.success)
+    }
+
+    fn error(&self) -> Option<String> {
+        match &self.result {
+            Ok(resp) if resp.success => None,
+            Ok(resp) => Some(
+                resp.error
+                    .clone()
+                    .unwrap_or_else(|| "Command failed".to_string()),
+            ),
+            Err(e) => Some(e.clone()),
+        }
+    }
+}
+
+pub struct ActOutcome {
+    pub steps: Vec<StepResult>,
+    /// Steps not run because an earlier one failed or failed its check.
+    pub skipped: Vec<Vec<String>>,
+    pub snapshot: Option<Result<Response, String>>,
+}
+
+impl ActOutcome {
+    pub fn success(&self) -> bool {
+        let snapshot_ok = match &self.snapshot {
+            Some(Ok(resp)) => resp.success,
+            Some(Err(_)) => false,
+            None => true,
+        };
+        snapshot_ok && self.steps.iter().all(StepResult::succeeded)
+    }
+
+    /// The exit code of the check that stopped the run, if any.
+    pub fn check_exit_code(&self) -> Option<i32> {
+        self.steps.iter().find_map(|step| step.check_exit_code)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut steps: Vec<Value> = self
+            .steps
+            .iter()
+            .map(|step| {
+                let mut entry = json!({
+                    "command": step.words.join(" "),
+                    "success": step.succeeded(),
+                    "result": step.result.as_ref().ok().and_then(|r| r.data.clone()),
+                    "error": step.error(),
+                });
+                if let Ok(resp) = &step.result {
+                    if let Some(echo) = &resp.action_echo {
+                        entry["_action"] = echo.clone();
+                    }
+                    if let Some(kind) = &resp.error_kind {
+                        entry["errorKind"] = json!(kind);
+                        entry["type"] = json!(resp.error_type);
+                    }
+                }
+                entry
+            })
+            .collect();
+        steps.extend(
+            self.skipped.iter().map(
+                |words| json!({ "command": words.join(" "), "success": false, "skipped": true }),
+            ),
+        );
+
+        let (snapshot, snapshot_error) = match &self.snapshot {
+            Some(Ok(resp)) if resp.success => (resp.data.clone(), None),
+            Some(Ok(resp)) => (None, resp.error.clone()),
+            Some(Err(e)) => (None, Some(e.clone())),
+            None => (None, None),
+        };
+        let error = self
+            .steps
+            .iter()
+            .find_map(StepResult::error)
+            .or_else(|| snapshot_error.clone());
+
+        json!({
+            "success": self.success(),
+            "data": {
+                "steps": steps,
+                "snapshot": snapshot,
+                "snapshotError": snapshot_error,
+            },
+            "error": error,
+        })
+    }
+
+    /// Text mode: one indicator line per step. The snapshot itself is printed
+    /// by the caller with the regular snapshot formatter.
+    pub fn step_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| match step.error() {
+                None if step.check_exit_code.is_some() => format!(
+                    "{} {}: check did not pass",
+                    color::error_indicator(),
+                    step.words.join(" ")
+                ),
+                None => format!("{} {}", color::success_indicator(), step.words.join(" ")),
+                Some(e) => format!(
+                    "{} {}: {}",
+                    color::error_indicator(),
+                    step.words.join(" "),
+                    e
+                ),
+            })
+            .collect();
+        lines.extend(
+            self.skipped
+                .iter()
+                .map(|words| color::dim(&format!("- {} (skipped)", words.join(" ")))),
+        );
+        lines
+    }
+}
+
+/// Send the steps in order, stopping at the first failure, then take the
+/// final snapshot if one was requested. The snapshot runs even after a
+/// failure so the caller can see what state the page was left in. Each step,
+/// but not the snapshot, is reported to `progress`. Every response goes
+/// through `postprocess`, which returns the exit code a step's check
+/// asks for.
+pub fn run_steps<F, P, W>(
+    steps: Vec<Step>,
+    final_snapshot: Option<Value>,
+    progress: &mut ProgressEmitter<W>,
+    mut send: F,
+    mut postprocess: P,
+) -> ActOutcome
+where
+    F: FnMut(&Value) -> Result<Response, String>,
+    P: FnMut(&Value, &mut Response, Duration) -> Option<i32>,
+    W: Write,
+{
+    let mut results = Vec::new();
+    let mut skipped = Vec::new();
+    let mut failed = false;
+    for (index, step) in steps.into_iter().enumerate() {
+        if failed {
+            skipped.push(step.words);
+            continue;
+        }
+        progress.step_start(
+            index,
+            step.command["action"].as_str().unwrap_or(&step.words[0]),
+        );
+        let started = Instant::now();
+        let mut result = send(&step.command)
+            .map(|mut resp| {
+                resp.error = resp.error.map(|e| step.secrets.text(&e));
+                resp
+            })
+            .map_err(|e| step.secrets.text(&e));
+        let elapsed = started.elapsed();
+        progress.step_end(StepError::from_result(&result).as_ref());
+        let check_exit_code = match result {
+            Ok(ref mut resp) => postprocess(&step.command, resp, elapsed).filter(|&code| code != 0),
+            Err(_) => None,
+        };
+        let step = StepResult {
+            words: step.words,
+            result,
+            check_exit_code,
+        };
+        failed = !step.succeeded() || step.check_exit_code.is_some();
+        results.push(step);
+    }
+    ActOutcome {
+        steps: results,
+        skipped,
+        snapshot: final_snapshot.map(|cmd| {
+            let started = Instant::now();
+            send(&cmd).map(|mut resp| {
+                postprocess(&cmd, &mut resp, started.elapsed());
+                resp
+            })
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{
+        apply_action_echo, apply_count_comparison, apply_error_kind, apply_url_parts,
+        is_check_exit_code,
+    };
+    use crate::test_utils::ok_response;
+
+    fn flags() -> Flags {
+        crate::flags::parse_flags(&[])
+    }
+
+    fn ok(data: Value) -> Result<Response, String> {
+        Ok(ok_response(data))
+    }
+
+    /// The checks, standing in for the CLI's full post-processing.
+    fn checks(cmd: &Value, resp: &mut Response, _elapsed: Duration) -> Option<i32> {
+        let code = apply_count_comparison(cmd, resp).or_else(|| apply_url_parts(cmd, resp));
+        is_check_exit_code(cmd["action"].as_str(), resp, false).or(code)
+    }
+
+    fn quiet() -> ProgressEmitter<Vec<u8>> {
+        ProgressEmitter::new(None, Vec::new())
+    }
+
+    fn snapshot_cmd() -> Value {
+        parse_command(&["snapshot".to_string(), "-i".to_string()], &flags()).unwrap()
+    }
+
+    fn steps(json: &str) -> Vec<Step> {
+        validate_steps(parse_steps(json).unwrap(), &flags()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_steps_accepts_string_and_array_commands() {
+        let parsed =
+            parse_steps(r#"[{"command": "click @e1"}, {"command": ["fill", "@e2", "a b"]}]"#)
+                .unwrap();
+        assert_eq!(
+            parsed,
+            vec![vec!["click", "@e1"], vec!["fill", "@e2", "a b"]]
+        );
+    }
+
+    #[test]
+    fn test_parse_steps_rejects_bad_shapes() {
+        assert!(parse_steps("not json")
+            .unwrap_err()
+            .contains("Invalid JSON"));
+        assert!(parse_steps(r#"{"command": "click"}"#).is_err());
+        assert!(parse_steps("[]").unwrap_err().contains("No steps"));
+        assert!(parse_steps(r#"[{"cmd": "click @e1"}]"#)
+            .unwrap_err()
+            .starts_with("Step 1"));
+        assert!(
+            parse_steps(r#"[{"command": "click @e1"}, {"command": [1]}]"#)
+                .unwrap_err()
+                .starts_with("Step 2")
+        );
+    }
+
+    #[test]
+    fn test_validate_steps_uses_parse_command() {
+        let valid = steps(r#"[{"command": "open example.com"}, {"command": "click @e1"}]"#);
+        assert_eq!(valid[0].command["action"], "navigate");
+        assert_eq!(valid[1].command["action"], "click");
+
+        let err = validate_steps(
+            parse_steps(r#"[{"command": "click @e1"}, {"command": "clack @e2"}]"#).unwrap(),
+            &flags(),
+        )
+        .err()
+        .unwrap();
+        assert!(err.starts_with("Step 2 (clack @e2)"));
+
+        let err = validate_steps(parse_steps(r#"[{"command": "batch"}]"#).unwrap(), &flags())
+            .err()
+            .unwrap();
+        assert!(err.contains("cannot be used inside act"));
+    }
+
+    #[test]
+    fn test_run_steps_appends_snapshot_after_success() {
+        let mut sent = Vec::new();
+        let outcome = run_steps(
+            steps(r#"[{"command": "click @e1"}, {"command": "press Enter"}]"#),
+            Some(snapshot_cmd()),
+            &mut quiet(),
+            |cmd| {
+                sent.push(cmd["action"].as_str().unwrap().to_string());
+                ok(json!({ "snapshot": "- button \"Go\" [ref=e1]" }))
+            },
+            checks,
+        );
+        assert_eq!(sent, vec!["click", "press", "snapshot"]);
+        assert!(outcome.success());
+        let out = outcome.to_json();
+        assert_eq!(out["data"]["steps"][1]["command"], "press Enter");
+        assert_eq!(
+            out["data"]["snapshot"]["snapshot"],
+            "- button \"Go\" [ref=e1]"
+        );
+        assert!(out["error"].is_null());
+    }
+
+    #[test]
+    fn test_run_steps_short_circuits_but_still_snapshots() {
+        let mut sent = Vec::new();
+        let outcome = run_steps(
+            steps(
+                r#"[{"command": "click @e1"}, {"command": "click @e9"}, {"command": "press Enter"}]"#,
+            ),
+            Some(snapshot_cmd()),
+            &mut quiet(),
+            |cmd| {
+                sent.push(cmd["action"].as_str().unwrap().to_string());
+                if cmd["selector"] == "@e9" {
+                    Ok(Response {
+                        success: false,
+                        data: None,
+                        error: Some("Element not found".to_string()),
+                        warning: None,
+                        ..Default::default()
+                    })
+                } else {
+                    ok(json!({}))
+                }
+            },
+            checks,
+        );
+        assert_eq!(sent, vec!["click", "click", "snapshot"]);
+        assert!(!outcome.success());
+        let out = outcome.to_json();
+        assert_eq!(out["error"], "Element not found");
+        assert_eq!(out["data"]["steps"][2]["skipped"], true);
+        assert!(out["data"]["snapshot"].is_object());
+        assert_eq!(outcome.step_lines().len(), 3);
+    }
+
+    #[test]
+    fn test_run_steps_stops_on_failed_check() {
+        let mut sent = Vec::new();
+        let outcome = run_steps(
+            steps(r#"[{"command": "get count li --gt 3"}, {"command": "click @e1"}]"#),
+            Some(snapshot_cmd()),
+            &mut quiet(),
+            |cmd| {
+                sent.push(cmd["action"].as_str().unwrap().to_string());
+                ok(json!({ "count": 2 }))
+            },
+            checks,
+        );
+        assert_eq!(sent, vec!["count", "snapshot"]);
+        assert!(outcome.success());
+        assert_eq!(outcome.check_exit_code(), Some(3));
+        let out = outcome.to_json();
+        assert_eq!(
+            out["data"]["steps"][0]["result"]["comparison"]["holds"],
+            false
+        );
+        assert_eq!(out["data"]["steps"][1]["skipped"], true);
+        assert!(outcome.step_lines()[0].contains("check did not pass"));
+
+        let outcome = run_steps(
+            steps(r#"[{"command": "get count li --gte 2"}]"#),
+            None,
+            &mut quiet(),
+            |_| ok(json!({ "count": 2 })),
+            checks,
+        );
+        assert!(outcome.success() && outcome.check_exit_code().is_none());
+
+        let outcome = run_steps(
+            steps(r#"[{"command": "get url --query tag"}]"#),
+            None,
+            &mut quiet(),
+            |_| ok(json!({ "url": "https://example.com/?page=2" })),
+            checks,
+        );
+        assert_eq!(outcome.check_exit_code(), Some(2));
+        assert!(outcome.to_json()["data"]["steps"][0]["result"]["param"]["value"].is_null());
+
+        let mut sent = Vec::new();
+        let outcome = run_steps(
+            steps(r#"[{"command": "is visible #banner"}, {"command": "click @e1"}]"#),
+            None,
+            &mut quiet(),
+            |cmd| {
+                sent.push(cmd["action"].as_str().unwrap().to_string());
+                ok(json!({ "visible": false }))
+            },
+            checks,
+        );
+        assert_eq!(sent, vec!["isvisible"]);
+        assert_eq!(outcome.check_exit_code(), Some(3));
+    }
+
+    #[test]
+    fn test_step_json_carries_action_echo_and_error_kind() {
+        let json_flags = crate::flags::parse_flags(&["--json".to_string()]);
+        let steps = validate_steps(
+            parse_steps(r#"[{"command": "click @e1"}, {"command": "click @e9"}]"#).unwrap(),
+            &json_flags,
+        )
+        .unwrap();
+        let outcome = run_steps(
+            steps,
+            None,
+            &mut quiet(),
+            |cmd| {
+                if cmd["selector"] == "@e9" {
+                    Ok(Response {
+                        success: false,
+                        data: None,
+                        error: Some("Element not found".to_string()),
+                        warning: None,
+                        ..Default::default()
+                    })
+                } else {
+                    ok(json!({ "target": { "role": "button", "name": "Go" } }))
+                }
+            },
+            |cmd, resp, elapsed| {
+                apply_action_echo(cmd, resp, elapsed);
+                apply_error_kind(resp, None);
+                None
+            },
+        );
+        let out = outcome.to_json();
+        assert_eq!(out["data"]["steps"][0]["_action"]["action"], "click");
+        assert!(out["data"]["steps"][0].get("errorKind").is_none());
+        assert_eq!(out["data"]["steps"][1]["errorKind"], "page");
+        assert_eq!(out["data"]["steps"][1]["type"], "page_error");
+    }
+
+    #[test]
+    fn test_run_steps_without_final_snapshot() {
+        let outcome = run_steps(
+            steps(r#"[{"command": "click @e1"}]"#),
+            None,
+            &mut quiet(),
+            |_| Err("Failed to connect".to_string()),
+            checks,
+        );
+        assert!(!outcome.success());
+        let out = outcome.to_json();
+        assert!(out["data"]["snapshot"].is_null());
+        assert_eq!(out["error"], "Failed to connect");
+    }
+
+    #[test]
+    fn test_run_steps_hides_secrets_in_echoed_steps() {
+        let outcome = run_steps(
+            steps(r#"[{"command": ["storage", "local", "set", "authToken", "s3cr3t-jwt"]}]"#),
+            None,
+            &mut quiet(),
+            |cmd| {
+                // The daemon still receives the real value.
+                assert_eq!(cmd["value"], "s3cr3t-jwt");
+                Ok(Response {
+                    success: false,
+                    data: None,
+                    error: Some("Quota exceeded writing s3cr3t-jwt".to_string()),
+                    warning: None,
+                    ..Default::default()
+                })
+            },
+            checks,
+        );
+        let out = outcome.to_json();
+        assert_eq!(
+            out["data"]["steps"][0]["command"],
+            "storage local set authToken ***"
+        );
+        assert_eq!(out["error"], "Quota exceeded writing ***");
+    }
+
+    /// All steps and the snapshot travel over a single daemon connection.
+    #[cfg(unix)]
+    #[test]
+    fn test_run_steps_over_one_mock_socket() {
+        use crate::connection::DaemonConnection;
+        use crate::test_utils::EnvGuard;
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+        let listener = UnixListener::bind(dir.path().join("act.sock")).unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut actions = Vec::new();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                let request: Value = serde_json::from_str(&line).unwrap();
+                actions.push(request["action"].as_str().unwrap().to_string());
+                let reply = json!({ "success": true, "data": { "n": actions.len() } });
+                writeln!(writer, "{}", reply).unwrap();
+                line.clear();
+            }
+            actions
+        });
+
+        let mut conn = DaemonConnection::open("act").unwrap();
+        let outcome = run_steps(
+            steps(r#"[{"command": "click @e1"}, {"command": "fill @e2 hi"}]"#),
+            Some(snapshot_cmd()),
+            &mut quiet(),
+            |cmd| conn.send(cmd),
+            checks,
+        );
+        drop(conn);
+
+        assert_eq!(server.join().unwrap(), vec!["click", "fill", "snapshot"]);
+        assert!(outcome.success());
+        assert_eq!(outcome.to_json()["data"]["snapshot"]["n"], 3);
+    }
+
+    /// `--progress jsonl` reports each step as it starts and ends, in order,
+    /// and says nothing about skipped steps or the final snapshot.
+    #[cfg(unix)]
+    #[test]
+    fn test_run_steps_progress_events_over_mock_socket() {
+        use crate::connection::DaemonConnection;
+        use crate::progress::ProgressFormat;
+        use crate::test_utils::EnvGuard;
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+        let listener = UnixListener::bind(dir.path().join("progress.sock")).unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                let request: Value = serde_json::from_str(&line).unwrap();
+                let reply = if request["action"] == "fill" {
+                    json!({ "success": false, "error": "Element not found" })
+                } else {
+                    json!({ "success": true, "data": {} })
+                };
+                writeln!(writer, "{}", reply).unwrap();
+                line.clear();
+            }
+        });
+
+        let mut conn = DaemonConnection::open("progress").unwrap();
+        let mut progress = ProgressEmitter::new(Some(ProgressFormat::Jsonl), Vec::new());
+        let outcome = run_steps(
+            steps(
+                r#"[{"command": "click @e1"}, {"command": "fill @e2 hi"}, {"command": "press Enter"}]"#,
+            ),
+            Some(snapshot_cmd()),
+            &mut progress,
+            |cmd| conn.send(cmd),
+            checks,
+        );
+        drop(conn);
+        server.join().unwrap();
+        assert!(!outcome.success());
+
+        let events: Vec<Value> = String::from_utf8(progress.into_inner())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let order: Vec<(&str, u64, &str)> = events
+            .iter()
+            .map(|e| {
+                (
+                    e["event"].as_str().unwrap(),
+                    e["index"].as_u64().unwrap(),
+                    e["action"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("step_start", 0, "click"),
+                ("step_end", 0, "click"),
+                ("step_start", 1, "fill"),
+                ("step_end", 1, "fill"),
+            ]
+        );
+        assert_eq!(events[1]["success"], true);
+        assert_eq!(events[3]["success"], false);
+        assert_eq!(events[3]["error"]["code"], "command_failed");
+        assert_eq!(events[3]["error"]["message"], "Element not found");
+    }
+}
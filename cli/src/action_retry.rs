@@ -0,0 +1,327 @@
+//! Opt-in retry of flaky actions (`--auto-retry-actions [n]` or
+//! AGENT_BROWSER_AUTO_RETRY_ACTIONS).
+//!
+//! Re-renders detach the element an action resolved, and toasts or banners
+//! briefly cover its click point. Both usually clear up within a second, so
+//! an agent that re-issues the identical command succeeds. With retries on,
+//! a [`RETRYABLE_ACTIONS`] failure whose message [`classify`] recognizes is
+//! re-sent after a short backoff, up to `n` times. A retry after a detached
+//! `@ref` asks the daemon to look the ref up again by its recorded role and
+//! name instead of reusing the stale node. If every retry fails, the original
+//! error is reported with the retry history appended.
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::commands::gen_id;
+use crate::connection::Response;
+
+/// Actions that are safe to repeat when they failed before touching the page.
+pub const RETRYABLE_ACTIONS: &[&str] = &["click", "fill", "check", "select"];
+
+/// Retries for a bare `--auto-retry-actions` with no count.
+pub const DEFAULT_RETRIES: u32 = 2;
+
+/// Why a failed action is worth retrying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryCause {
+    /// The element was removed or replaced between resolving and acting.
+    Detached,
+    /// Another element covered the element's click point.
+    Intercepted,
+}
+
+impl RetryCause {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RetryCause::Detached => "detached",
+            RetryCause::Intercepted => "covered",
+        }
+    }
+}
+
+/// Lowercase substrings of CDP, Playwright, and agent-browser errors for an
+/// element that went away under the action. Navigation errors ("execution
+/// context was destroyed") are left out on purpose: the action that caused
+/// the navigation may already have happened.
+const DETACHED_PATTERNS: &[&str] = &[
+    "element is not attached to the dom",
+    "element is detached",
+    "detached from document",
+    "node is detached",
+    "no node with given id",
+    "could not find node with given id",
+    "node with given id does not belong to the document",
+];
+
+/// Lowercase substrings of errors for an element covered by another one.
+const INTERCEPTED_PATTERNS: &[&str] = &[
+    "intercepts pointer events",
+    "is covered by <",
+    "another element is covering the target element",
+    "other element would receive the click",
+];
+
+/// Recognize a detachment or interception failure from its message.
+pub fn classify(error: &str) -> Option<RetryCause> {
+    let lower = error.to_lowercase();
+    let matches = |patterns: &[&str]| patterns.iter().any(|p| lower.contains(p));
+    if matches(DETACHED_PATTERNS) {
+        Some(RetryCause::Detached)
+    } else if matches(INTERCEPTED_PATTERNS) {
+        Some(RetryCause::Intercepted)
+    } else {
+        None
+    }
+}
+
+/// Whether `cmd`, which failed with `error` after `retries_done` retries,
+/// should be sent again under a limit of `max_retries`.
+pub fn should_retry(
+    cmd: &Value,
+    error: &str,
+    retries_done: u32,
+    max_retries: u32,
+) -> Option<RetryCause> {
+    let action = cmd.get("action").and_then(|v| v.as_str())?;
+    if retries_done >= max_retries || !RETRYABLE_ACTIONS.contains(&action) {
+        return None;
+    }
+    classify(error)
+}
+
+/// Pause before retry number `attempt` (1-based): 250ms, doubling, at most 1s.
+pub fn retry_delay(attempt: u32) -> Duration {
+    let ms = 250u64.saturating_mul(1 << attempt.saturating_sub(1).min(3));
+    Duration::from_millis(ms.min(1000))
+}
+
+/// The command to send for a retry: a fresh id, and after a detachment a
+/// request to re-resolve an `@ref` instead of reusing its cached node.
+pub fn retry_command(cmd: &Value, cause: RetryCause) -> Value {
+    let mut retry = cmd.clone();
+    retry["id"] = json!(gen_id());
+    let is_ref = cmd
+        .get("selector")
+        .and_then(|v| v.as_str())
+        .is_some_and(|s| s.starts_with('@'));
+    if cause == RetryCause::Detached && is_ref {
+        retry["refreshRef"] = json!(true);
+    }
+    retry
+}
+
+/// "(retried 2 times: detached, covered)"
+pub fn retry_history(causes: &[RetryCause]) -> String {
+    let names: Vec<&str> = causes.iter().map(|c| c.as_str()).collect();
+    format!(
+        "(retried {} {}: {})",
+        causes.len(),
+        if causes.len() == 1 { "time" } else { "times" },
+        names.join(", ")
+    )
+}
+
+/// Send `cmd`, retrying as [`should_retry`] allows. A success after retries
+/// carries a warning; a final failure reports the first error plus the
+/// history, since later attempts usually fail the same way or worse.
+pub fn send_with_retries(
+    cmd: &Value,
+    max_retries: u32,
+    mut send: impl FnMut(Value) -> Result<Response, String>,
+    mut sleep: impl FnMut(Duration),
+) -> Result<Response, String> {
+    let mut resp = send(cmd.clone())?;
+    let original_error = resp.error.clone();
+    let mut causes = Vec::new();
+    while !resp.success {
+        let error = resp.error.as_deref().unwrap_or_default();
+        let Some(cause) = should_retry(cmd, error, causes.len() as u32, max_retries) else {
+            break;
+        };
+        causes.push(cause);
+        sleep(retry_delay(causes.len() as u32));
+        resp = send(retry_command(cmd, cause))?;
+    }
+    if causes.is_empty() {
+        return Ok(resp);
+    }
+    if resp.success {
+        resp.warnings.push(format!(
+            "Succeeded after a retry {}",
+            retry_history(&causes)
+        ));
+    } else {
+        let error = original_error.or(resp.error.take()).unwrap_or_default();
+        resp.error = Some(format!("{} {}", error, retry_history(&causes)));
+    }
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(success: bool, error: Option<&str>) -> Response {
+        Response {
+            success,
+            data: success.then(|| json!({})),
+            error: error.map(String::from),
+            warning: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_classify_real_world_errors() {
+        let cases: &[(&str, Option<RetryCause>)] = &[
+            (
+                "elementHandle.click: Element is not attached to the DOM",
+                Some(RetryCause::Detached),
+            ),
+            (
+                "locator.fill: Target element is detached from document",
+                Some(RetryCause::Detached),
+            ),
+            ("Node is detached from document", Some(RetryCause::Detached)),
+            ("No node with given id found", Some(RetryCause::Detached)),
+            // A click that navigated may already have done its job.
+            (
+                "Execution context was destroyed, most likely because of a navigation",
+                None,
+            ),
+            (
+                "<div class=\"toast\">Saved</div> intercepts pointer events",
+                Some(RetryCause::Intercepted),
+            ),
+            (
+                "Element '@e3' is covered by <div.cookie-banner> at its click point, so the input would land on that element instead.",
+                Some(RetryCause::Intercepted),
+            ),
+            (
+                "Another element is covering the target element. Try scrolling or closing overlays.",
+                Some(RetryCause::Intercepted),
+            ),
+            (
+                "Element is not clickable at point (120, 40). Other element would receive the click: <div class=\"overlay\">",
+                Some(RetryCause::Intercepted),
+            ),
+            ("Element not found: #missing", None),
+            ("Operation timed out. The page may still be loading.", None),
+            ("Unknown ref: e9", None),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(classify(error), *expected, "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_should_retry_policy() {
+        let click = json!({ "action": "click", "selector": "@e1" });
+        let detached = "Node is detached from document";
+        assert_eq!(
+            should_retry(&click, detached, 0, 2),
+            Some(RetryCause::Detached)
+        );
+        assert_eq!(
+            should_retry(&click, detached, 1, 2),
+            Some(RetryCause::Detached)
+        );
+        assert_eq!(should_retry(&click, detached, 2, 2), None);
+        assert_eq!(should_retry(&click, detached, 0, 0), None);
+        assert_eq!(should_retry(&click, "Element not found: @e1", 0, 2), None);
+        for action in ["fill", "check", "select"] {
+            let cmd = json!({ "action": action, "selector": "#x" });
+            assert!(should_retry(&cmd, detached, 0, 1).is_some(), "{}", action);
+        }
+        let hover = json!({ "action": "hover", "selector": "#x" });
+        assert_eq!(should_retry(&hover, detached, 0, 2), None);
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_to_a_cap() {
+        let ms: Vec<u128> = (1..=5).map(|n| retry_delay(n).as_millis()).collect();
+        assert_eq!(ms, vec![250, 500, 1000, 1000, 1000]);
+    }
+
+    #[test]
+    fn test_retry_command_refreshes_detached_refs_only() {
+        let cmd = json!({ "id": "1", "action": "click", "selector": "@e4" });
+        let retry = retry_command(&cmd, RetryCause::Detached);
+        assert_eq!(retry["refreshRef"], true);
+        assert_ne!(retry["id"], "1");
+        assert!(retry_command(&cmd, RetryCause::Intercepted)
+            .get("refreshRef")
+            .is_none());
+        let css = json!({ "id": "1", "action": "click", "selector": "#go" });
+        assert!(retry_command(&css, RetryCause::Detached)
+            .get("refreshRef")
+            .is_none());
+    }
+
+    #[test]
+    fn test_send_with_retries_recovers_and_warns() {
+        let cmd = json!({ "id": "1", "action": "click", "selector": "@e2" });
+        let mut replies = vec![
+            response(false, Some("Node is detached from document")),
+            response(true, None),
+        ]
+        .into_iter();
+        let mut sent = Vec::new();
+        let mut slept = Vec::new();
+        let resp = send_with_retries(
+            &cmd,
+            3,
+            |c| {
+                sent.push(c);
+                Ok(replies.next().unwrap())
+            },
+            |d| slept.push(d),
+        )
+        .unwrap();
+        assert!(resp.success);
+        assert_eq!(
+            resp.warnings,
+            vec!["Succeeded after a retry (retried 1 time: detached)"]
+        );
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1]["refreshRef"], true);
+        assert_eq!(slept, vec![Duration::from_millis(250)]);
+    }
+
+    #[test]
+    fn test_send_with_retries_reports_original_error_with_history() {
+        let cmd = json!({ "id": "1", "action": "click", "selector": "#save" });
+        let mut replies = vec![
+            response(false, Some("<div> intercepts pointer events")),
+            response(false, Some("Node is detached from document")),
+            response(false, Some("<div> intercepts pointer events")),
+        ]
+        .into_iter();
+        let resp = send_with_retries(&cmd, 2, |_| Ok(replies.next().unwrap()), |_| {}).unwrap();
+        assert!(!resp.success);
+        assert_eq!(
+            resp.error.as_deref(),
+            Some("<div> intercepts pointer events (retried 2 times: covered, detached)")
+        );
+
+        // Off, or an error the classifier does not know: one attempt only.
+        let mut calls = 0;
+        let resp = send_with_retries(
+            &cmd,
+            0,
+            |_| {
+                calls += 1;
+                Ok(response(false, Some("Node is detached from document")))
+            },
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(calls, 1);
+        assert_eq!(
+            resp.error.as_deref(),
+            Some("Node is detached from document")
+        );
+    }
+}
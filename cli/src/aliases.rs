@@ -0,0 +1,178 @@
+//! User-defined command aliases from the `aliases` config key.
+//!
+//! An alias maps a name to the argument words it stands for, e.g.
+//! `"snapi": ["snapshot", "-i"]`. Only the first command word is expanded,
+//! any arguments the user typed after it are appended, and a built-in
+//! command always wins over an alias of the same name.
+
+use std::collections::BTreeMap;
+
+use serde_json::json;
+
+use crate::color;
+use crate::commands::is_top_level_command;
+
+/// Longest chain of aliases expanding to other aliases.
+pub const MAX_ALIAS_DEPTH: usize = 8;
+
+/// Expand the leading alias in `args`, following aliases that expand to
+/// other aliases. Errors on a cycle, a chain deeper than
+/// [`MAX_ALIAS_DEPTH`], or an alias with an empty expansion.
+pub fn expand(
+    args: &[String],
+    aliases: &BTreeMap<String, Vec<String>>,
+) -> Result<Vec<String>, String> {
+    let mut args = args.to_vec();
+    let mut chain: Vec<String> = Vec::new();
+    loop {
+        let Some(first) = args.first() else {
+            return Ok(args);
+        };
+        if is_top_level_command(first) {
+            return Ok(args);
+        }
+        let Some(expansion) = aliases.get(first) else {
+            return Ok(args);
+        };
+        if chain.contains(first) {
+            chain.push(first.clone());
+            return Err(format!("Alias cycle: {}", chain.join(" -> ")));
+        }
+        if chain.len() == MAX_ALIAS_DEPTH {
+            return Err(format!(
+                "Alias '{}' nests more than {} levels deep",
+                chain[0], MAX_ALIAS_DEPTH
+            ));
+        }
+        if expansion.is_empty() {
+            return Err(format!("Alias '{}' expands to nothing", first));
+        }
+        chain.push(first.clone());
+        let mut expanded = expansion.clone();
+        expanded.extend(args.drain(1..));
+        args = expanded;
+    }
+}
+
+/// `agent-browser alias [list]`.
+pub fn run_alias(args: &[String], aliases: &BTreeMap<String, Vec<String>>, json_output: bool) {
+    match args.get(1).map(|s| s.as_str()).unwrap_or("list") {
+        "list" => print_alias_list(aliases, json_output),
+        other => {
+            let message = format!("Unknown alias subcommand: {}. Valid options: list", other);
+            if json_output {
+                println!("{}", json!({ "success": false, "error": message }));
+            } else {
+                eprintln!("{} {}", color::error_indicator(), message);
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_alias_list(aliases: &BTreeMap<String, Vec<String>>, json_output: bool) {
+    if json_output {
+        println!("{}", json!({ "aliases": aliases }));
+        return;
+    }
+    if aliases.is_empty() {
+        println!("No aliases configured");
+        return;
+    }
+    for (name, expansion) in aliases {
+        let shadowed = if is_top_level_command(name) {
+            format!("  {}", color::dim("(shadowed by built-in command)"))
+        } else {
+            String::new()
+        };
+        println!("{} -> {}{}", name, expansion.join(" "), shadowed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    fn aliases(entries: &[(&str, &str)]) -> BTreeMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(name, expansion)| (name.to_string(), words(expansion)))
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_appends_user_args() {
+        let aliases = aliases(&[("snapi", "snapshot -i"), ("home", "open example.com")]);
+        assert_eq!(
+            expand(&words("snapi -c"), &aliases).unwrap(),
+            words("snapshot -i -c")
+        );
+        assert_eq!(
+            expand(&words("home"), &aliases).unwrap(),
+            words("open example.com")
+        );
+        // Only the first word is an alias candidate.
+        assert_eq!(
+            expand(&words("fill snapi x"), &aliases).unwrap(),
+            words("fill snapi x")
+        );
+        assert_eq!(
+            expand(&words("unknown"), &aliases).unwrap(),
+            words("unknown")
+        );
+        assert!(expand(&[], &aliases).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_expand_follows_nested_aliases() {
+        let aliases = aliases(&[("si", "snapi -c"), ("snapi", "snapshot -i")]);
+        assert_eq!(
+            expand(&words("si -d 2"), &aliases).unwrap(),
+            words("snapshot -i -c -d 2")
+        );
+    }
+
+    #[test]
+    fn test_expand_rejects_cycles_and_deep_chains() {
+        let cyclic = aliases(&[("a", "b x"), ("b", "a y")]);
+        assert_eq!(
+            expand(&words("a"), &cyclic).unwrap_err(),
+            "Alias cycle: a -> b -> a"
+        );
+        let selfref = aliases(&[("a", "a")]);
+        assert_eq!(
+            expand(&words("a"), &selfref).unwrap_err(),
+            "Alias cycle: a -> a"
+        );
+
+        let mut deep = BTreeMap::new();
+        for i in 0..=MAX_ALIAS_DEPTH {
+            deep.insert(format!("a{}", i), vec![format!("a{}", i + 1)]);
+        }
+        let err = expand(&words("a0"), &deep).unwrap_err();
+        assert!(err.contains("more than 8 levels"), "got: {}", err);
+
+        let empty = aliases(&[("nothing", "")]);
+        assert!(expand(&words("nothing"), &empty).is_err());
+    }
+
+    #[test]
+    fn test_builtin_commands_shadow_aliases() {
+        let aliases = aliases(&[("open", "open example.com"), ("x", "click @e1")]);
+        assert_eq!(
+            expand(&words("open other.com"), &aliases).unwrap(),
+            words("open other.com")
+        );
+        // An alias may expand to a built-in command of the same name as
+        // another alias; the built-in is not expanded again.
+        let chained = self::aliases(&[("go", "open"), ("open", "close")]);
+        assert_eq!(
+            expand(&words("go site.com"), &chained).unwrap(),
+            words("open site.com")
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Overlap detection for `screenshot --annotate` labels.
+//!
+//! On dense pages the numbered labels drawn over the screenshot land on top
+//! of each other, and a vision model reading the image can pair a number
+//! with the wrong element. The daemon reports each annotation's element box;
+//! from it we rebuild where the overlay put the label and list every pair of
+//! labels that collide, so the legend and `--json` output can flag them.
+
+use serde_json::Value;
+
+/// Height of an overlay label (`font: bold 11px/14px monospace`).
+const LABEL_HEIGHT: f64 = 14.0;
+/// Width of one digit in the 11px monospace label font.
+const LABEL_CHAR_WIDTH: f64 = 6.6;
+/// Left plus right label padding (`padding: 0 4px`).
+const LABEL_PADDING: f64 = 8.0;
+/// Element border width; the label is positioned inside it.
+const BOX_BORDER: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Whether two rectangles share any area. Rectangles that only touch along
+/// an edge or at a corner do not overlap.
+pub fn rects_overlap(a: &Rect, b: &Rect) -> bool {
+    a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+}
+
+/// Where the overlay in `native/screenshot.rs` draws the label for
+/// `number` on an element at `element`: just above its top-left corner,
+/// or just inside it when the element is too close to the top edge.
+pub fn label_rect(element: &Rect, number: u64) -> Rect {
+    let digits = number.to_string().len() as f64;
+    let y = if element.y < LABEL_HEIGHT {
+        element.y + BOX_BORDER + 2.0
+    } else {
+        element.y + BOX_BORDER - LABEL_HEIGHT
+    };
+    Rect {
+        x: element.x,
+        y,
+        width: digits * LABEL_CHAR_WIDTH + LABEL_PADDING,
+        height: LABEL_HEIGHT,
+    }
+}
+
+/// Every pair of labels that overlap, as `[lower, higher]` label numbers in
+/// ascending order.
+pub fn overlapping_labels(labels: &[(u64, Rect)]) -> Vec<[u64; 2]> {
+    let mut pairs = Vec::new();
+    for (i, (a_num, a)) in labels.iter().enumerate() {
+        for (b_num, b) in &labels[i + 1..] {
+            if rects_overlap(a, b) {
+                pairs.push([*a_num.min(b_num), *a_num.max(b_num)]);
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs
+}
+
+/// Label overlaps for the `annotations` array of a screenshot response.
+/// Annotations without a `box` are skipped.
+pub fn annotation_overlaps(annotations: &[Value]) -> Vec<[u64; 2]> {
+    let labels: Vec<(u64, Rect)> = annotations
+        .iter()
+        .filter_map(|ann| {
+            let number = ann.get("number")?.as_u64()?;
+            let b = ann.get("box")?;
+            let element = Rect {
+                x: b.get("x")?.as_f64()?,
+                y: b.get("y")?.as_f64()?,
+                width: b.get("width")?.as_f64()?,
+                height: b.get("height")?.as_f64()?,
+            };
+            Some((number, label_rect(&element, number)))
+        })
+        .collect();
+    overlapping_labels(&labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_rects_overlap_cases() {
+        let base = rect(10.0, 10.0, 20.0, 20.0);
+        // Partial overlap.
+        assert!(rects_overlap(&base, &rect(25.0, 25.0, 20.0, 20.0)));
+        // Containment, both ways round.
+        assert!(rects_overlap(&base, &rect(15.0, 15.0, 5.0, 5.0)));
+        assert!(rects_overlap(&rect(15.0, 15.0, 5.0, 5.0), &base));
+        // Touching along an edge or at a corner.
+        assert!(!rects_overlap(&base, &rect(30.0, 10.0, 20.0, 20.0)));
+        assert!(!rects_overlap(&base, &rect(10.0, 30.0, 20.0, 20.0)));
+        assert!(!rects_overlap(&base, &rect(30.0, 30.0, 5.0, 5.0)));
+        // Disjoint.
+        assert!(!rects_overlap(&base, &rect(100.0, 100.0, 5.0, 5.0)));
+        assert!(!rects_overlap(&base, &rect(10.0, 50.0, 20.0, 5.0)));
+    }
+
+    #[test]
+    fn test_label_rect_follows_overlay_placement() {
+        let above = label_rect(&rect(40.0, 100.0, 80.0, 30.0), 7);
+        assert_eq!(above, rect(40.0, 88.0, 14.6, 14.0));
+        // Too close to the top: drawn inside the element instead.
+        let inside = label_rect(&rect(40.0, 5.0, 80.0, 30.0), 12);
+        assert_eq!(inside.y, 9.0);
+        assert!((inside.width - 21.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_overlapping_labels_pairs() {
+        let labels = [
+            (3, rect(0.0, 0.0, 15.0, 14.0)),
+            (7, rect(10.0, 5.0, 15.0, 14.0)),
+            (1, rect(12.0, 0.0, 15.0, 14.0)),
+            (9, rect(200.0, 0.0, 15.0, 14.0)),
+            (4, rect(15.0, 50.0, 15.0, 14.0)),
+        ];
+        assert_eq!(overlapping_labels(&labels), vec![[1, 3], [1, 7], [3, 7]]);
+        assert!(overlapping_labels(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_annotation_overlaps_from_response() {
+        let annotations = vec![
+            json!({ "ref": "e3", "number": 3, "box": { "x": 100, "y": 200, "width": 40, "height": 20 } }),
+            json!({ "ref": "e7", "number": 7, "box": { "x": 105, "y": 204, "width": 40, "height": 20 } }),
+            json!({ "ref": "e8", "number": 8, "box": { "x": 400, "y": 200, "width": 40, "height": 20 } }),
+            json!({ "ref": "e9", "number": 9 }),
+        ];
+        assert_eq!(annotation_overlaps(&annotations), vec![[3, 7]]);
+    }
+}
@@ -0,0 +1,263 @@
+//! `--artifacts-dir`: one folder for everything a session writes.
+//!
+//! Screenshots, PDFs, traces, HARs, recordings and downloads each default
+//! to their own location. With an artifacts directory set, a path-taking
+//! command given a bare filename writes it into that directory, and one
+//! given no path gets a timestamped name there. Absolute paths and paths
+//! with a directory part are left alone, so callers can still opt out per
+//! command.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde_json::{json, Value};
+
+/// The kinds of file a command can write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    Screenshot,
+    Pdf,
+    Trace,
+    Har,
+    Video,
+    Download,
+}
+
+impl ArtifactKind {
+    /// The kind written by a parsed command's action, if it writes one.
+    pub fn for_action(action: &str) -> Option<Self> {
+        match action {
+            "screenshot" => Some(Self::Screenshot),
+            "pdf" => Some(Self::Pdf),
+            "trace_stop" => Some(Self::Trace),
+            "har_start" | "har_stop" => Some(Self::Har),
+            "recording_start" | "recording_restart" => Some(Self::Video),
+            "download" => Some(Self::Download),
+            _ => None,
+        }
+    }
+
+    fn stem(self) -> &'static str {
+        match self {
+            Self::Screenshot => "screenshot",
+            Self::Pdf => "page",
+            Self::Trace => "trace",
+            Self::Har => "network",
+            Self::Video => "recording",
+            Self::Download => "download",
+        }
+    }
+
+    /// Extension for a generated name. Screenshots follow the requested
+    /// format; downloads keep whatever name the server suggests, so they
+    /// never get a generated one.
+    fn extension(self, cmd: &Value) -> Option<&'static str> {
+        match self {
+            Self::Screenshot if cmd.get("format").and_then(|v| v.as_str()) == Some("jpeg") => {
+                Some("jpg")
+            }
+            Self::Screenshot => Some("png"),
+            Self::Pdf => Some("pdf"),
+            Self::Trace => Some("json"),
+            Self::Har => Some("har"),
+            Self::Video => Some("webm"),
+            Self::Download => None,
+        }
+    }
+}
+
+/// A name that is only a file name: no directory part and no `~`.
+pub fn is_bare_filename(path: &str) -> bool {
+    !path.is_empty() && !path.contains(['/', '\\']) && !path.starts_with('~')
+}
+
+/// `screenshot-20260131-140500.png`, or `screenshot-20260131-140500-2.png`
+/// and so on while `exists` reports the name as taken.
+pub fn default_artifact_name(
+    kind: ArtifactKind,
+    extension: &str,
+    now: DateTime<Local>,
+    exists: impl Fn(&str) -> bool,
+) -> String {
+    let base = format!("{}-{}", kind.stem(), now.format("%Y%m%d-%H%M%S"));
+    let mut name = format!("{}.{}", base, extension);
+    let mut n = 2;
+    while exists(&name) {
+        name = format!("{}-{}.{}", base, n, extension);
+        n += 1;
+    }
+    name
+}
+
+/// Where a command of `kind` should write under `dir`, given the path the
+/// user typed, if any. `None` means the command keeps its own path: an
+/// absolute path, one with a directory part, or a download without a name.
+pub fn resolve_artifact_path(
+    dir: &Path,
+    kind: ArtifactKind,
+    given: Option<&str>,
+    extension: Option<&str>,
+    now: DateTime<Local>,
+) -> Option<PathBuf> {
+    match given {
+        Some(path) if is_bare_filename(path) => Some(dir.join(path)),
+        Some(_) => None,
+        None => {
+            let name = default_artifact_name(kind, extension?, now, |name| dir.join(name).exists());
+            Some(dir.join(name))
+        }
+    }
+}
+
+/// Rewrite the `path` of a path-taking command to live under `dir`
+/// (already absolute), creating the directory first. Screenshots with
+/// their own `--screenshot-dir` keep it.
+pub fn apply_artifacts_dir(cmd: &mut Value, dir: &str) -> Result<(), String> {
+    let Some(kind) = cmd
+        .get("action")
+        .and_then(|v| v.as_str())
+        .and_then(ArtifactKind::for_action)
+    else {
+        return Ok(());
+    };
+    if kind == ArtifactKind::Screenshot && cmd.get("screenshotDir").is_some() {
+        return Ok(());
+    }
+    let given = cmd.get("path").and_then(|v| v.as_str()).map(String::from);
+    // `har start` only names the file a later `har stop` writes; without a
+    // name there is nothing to place yet.
+    if given.is_none() && cmd.get("action").and_then(|v| v.as_str()) == Some("har_start") {
+        return Ok(());
+    }
+    let dir = Path::new(dir);
+    let Some(path) = resolve_artifact_path(
+        dir,
+        kind,
+        given.as_deref(),
+        kind.extension(cmd),
+        Local::now(),
+    ) else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("--artifacts-dir: cannot create '{}': {}", dir.display(), e))?;
+    cmd["path"] = json!(path.to_string_lossy());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 1, 31, 14, 5, 0).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_each_kind_without_a_path() {
+        let dir = Path::new("/tmp/ab-artifacts-test-missing");
+        let cases = [
+            ("screenshot", json!({}), "screenshot-20260131-140500.png"),
+            (
+                "screenshot",
+                json!({ "format": "jpeg" }),
+                "screenshot-20260131-140500.jpg",
+            ),
+            ("pdf", json!({}), "page-20260131-140500.pdf"),
+            ("trace_stop", json!({}), "trace-20260131-140500.json"),
+            ("har_stop", json!({}), "network-20260131-140500.har"),
+            (
+                "recording_start",
+                json!({}),
+                "recording-20260131-140500.webm",
+            ),
+            (
+                "recording_restart",
+                json!({}),
+                "recording-20260131-140500.webm",
+            ),
+        ];
+        for (action, cmd, expected) in cases {
+            let kind = ArtifactKind::for_action(action).unwrap();
+            let path = resolve_artifact_path(dir, kind, None, kind.extension(&cmd), now());
+            assert_eq!(path, Some(dir.join(expected)), "{}", action);
+        }
+        // Downloads are named by the caller, never generated.
+        let kind = ArtifactKind::for_action("download").unwrap();
+        assert_eq!(
+            resolve_artifact_path(dir, kind, None, kind.extension(&json!({})), now()),
+            None
+        );
+        assert_eq!(ArtifactKind::for_action("click"), None);
+    }
+
+    #[test]
+    fn test_resolve_keeps_explicit_paths() {
+        let dir = Path::new("/srv/artifacts");
+        for kind in [
+            ArtifactKind::Screenshot,
+            ArtifactKind::Pdf,
+            ArtifactKind::Trace,
+            ArtifactKind::Har,
+            ArtifactKind::Video,
+            ArtifactKind::Download,
+        ] {
+            assert_eq!(
+                resolve_artifact_path(dir, kind, Some("report.bin"), Some("bin"), now()),
+                Some(dir.join("report.bin")),
+                "{:?}",
+                kind
+            );
+            for explicit in ["/abs/out.bin", "./out.bin", "sub/out.bin", "~/out.bin"] {
+                assert_eq!(
+                    resolve_artifact_path(dir, kind, Some(explicit), Some("bin"), now()),
+                    None,
+                    "{:?} {}",
+                    kind,
+                    explicit
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_name_avoids_collisions() {
+        let taken = ["trace-20260131-140500.json", "trace-20260131-140500-2.json"];
+        let name =
+            default_artifact_name(ArtifactKind::Trace, "json", now(), |n| taken.contains(&n));
+        assert_eq!(name, "trace-20260131-140500-3.json");
+        let name = default_artifact_name(ArtifactKind::Har, "har", now(), |_| false);
+        assert_eq!(name, "network-20260131-140500.har");
+    }
+
+    #[test]
+    fn test_apply_artifacts_dir_creates_dir_and_rewrites_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("ci/artifacts");
+        let dir_str = dir.to_str().unwrap();
+
+        let mut cmd = json!({ "action": "pdf", "path": null });
+        apply_artifacts_dir(&mut cmd, dir_str).unwrap();
+        let path = cmd["path"].as_str().unwrap();
+        assert!(dir.is_dir());
+        assert!(path.starts_with(dir_str), "{}", path);
+        assert!(path.ends_with(".pdf"), "{}", path);
+
+        let mut cmd = json!({ "action": "download", "selector": "#dl", "path": "report.csv" });
+        apply_artifacts_dir(&mut cmd, dir_str).unwrap();
+        assert_eq!(cmd["path"], json!(dir.join("report.csv").to_string_lossy()));
+
+        let mut cmd = json!({ "action": "screenshot", "path": "/tmp/x.png" });
+        apply_artifacts_dir(&mut cmd, dir_str).unwrap();
+        assert_eq!(cmd["path"], "/tmp/x.png");
+
+        let mut cmd = json!({ "action": "screenshot", "path": null, "screenshotDir": "/shots" });
+        apply_artifacts_dir(&mut cmd, dir_str).unwrap();
+        assert!(cmd["path"].is_null());
+
+        let mut cmd = json!({ "action": "click", "selector": "#a" });
+        apply_artifacts_dir(&mut cmd, dir_str).unwrap();
+        assert!(cmd.get("path").is_none());
+    }
+}
@@ -0,0 +1,178 @@
+//! Crash-safe file writes. Contents go to a temp file next to the destination
+//! (`<path>.tmp-<pid>-<n>`), are fsynced, and then renamed over the
+//! destination, so an interrupted write (Ctrl-C, a full disk, a crash) leaves
+//! either the old file or the new one, never a truncated mix. Every file the
+//! CLI or daemon writes for the user or for later runs should go through here.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Atomic replacement for [`std::fs::write`].
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_with(path.as_ref(), contents.as_ref(), false, replace)
+}
+
+/// Like [`write_atomic`], but the file is owner-only (0600 on Unix) from the
+/// moment it is created, for keys, credentials, and auth profiles.
+pub fn write_private(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_with(path.as_ref(), contents.as_ref(), true, replace)
+}
+
+/// Temp file the contents are staged in before the rename. The counter keeps
+/// concurrent writes to the same path from one process apart.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".tmp-{}-{}",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+fn write_with(
+    path: &Path,
+    contents: &[u8],
+    private: bool,
+    rename: impl FnOnce(&Path, &Path) -> io::Result<()>,
+) -> io::Result<()> {
+    let tmp = temp_path(path);
+    let result = stage(&tmp, contents, private).and_then(|()| rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+        return result;
+    }
+    sync_parent(path);
+    Ok(())
+}
+
+fn stage(tmp: &Path, contents: &[u8], private: bool) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    let mut file = options.open(tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Move `tmp` over `dest`. Windows refuses to replace a destination that is
+/// read-only or held open without delete sharing, so on failure the
+/// destination is removed and the rename retried once.
+fn replace(tmp: &Path, dest: &Path) -> io::Result<()> {
+    match fs::rename(tmp, dest) {
+        Err(e) if cfg!(windows) && dest.exists() => {
+            fs::remove_file(dest).map_err(|_| e)?;
+            fs::rename(tmp, dest)
+        }
+        other => other,
+    }
+}
+
+/// Persist the rename itself. Best effort: not every filesystem lets a
+/// directory be opened and synced.
+#[cfg(unix)]
+fn sync_parent(path: &Path) {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        if let Ok(dir) = fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leftovers(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.contains(".tmp-"))
+            .collect()
+    }
+
+    #[test]
+    fn test_write_atomic_creates_and_replaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert!(leftovers(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_failure_before_rename_leaves_new_destination_absent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.har");
+        let err = write_with(&path, b"{\"log\":{}}", false, |tmp, _| {
+            assert_eq!(fs::read(tmp).unwrap(), b"{\"log\":{}}");
+            Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"))
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+        assert!(!path.exists());
+        assert!(leftovers(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_failure_before_rename_keeps_existing_destination_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auth.json");
+        write_atomic(&path, "old complete contents").unwrap();
+        let result = write_with(&path, b"new contents", false, |_, _| {
+            Err(io::Error::other("killed"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old complete contents");
+        assert!(leftovers(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_failed_staging_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing-dir").join("out.png");
+        assert!(write_atomic(&path, "x").is_err());
+        assert!(!path.exists());
+        assert!(leftovers(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_temp_paths_are_siblings_and_unique() {
+        let path = Path::new("/tmp/out/report.json");
+        let (a, b) = (temp_path(path), temp_path(path));
+        assert_ne!(a, b);
+        assert_eq!(a.parent(), path.parent());
+        let prefix = format!("report.json.tmp-{}-", std::process::id());
+        assert!(a
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(&prefix));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".encryption-key");
+        write_private(&path, "abc\n").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
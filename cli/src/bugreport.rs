@@ -0,0 +1,296 @@
+//! `bugreport`: gather what a maintainer needs to reproduce an issue (CLI and
+//! daemon versions, platform, env, merged config, daemon state, and the tail
+//! of the session's daemon log) into one redacted JSON file.
+
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::exit;
+
+use crate::atomic_write::write_atomic;
+use crate::color;
+use crate::commands::gen_id;
+use crate::connection::{get_socket_dir, send_command, walk_daemons};
+use crate::flags::load_config;
+use crate::redact::{is_secret_name, redact_text, redact_value};
+
+const DEFAULT_LOG_LINES: usize = 200;
+
+/// Non-`AGENT_BROWSER_*` variables that change how the CLI behaves.
+const EXTRA_ENV_VARS: &[&str] = &[
+    "AGENTCORE_BROWSER_ID",
+    "AGENTCORE_PROFILE_ID",
+    "AGENTCORE_REGION",
+    "AI_GATEWAY_API_KEY",
+    "AWS_ACCESS_KEY_ID",
+    "AWS_DEFAULT_REGION",
+    "AWS_PROFILE",
+    "AWS_REGION",
+    "AWS_SECRET_ACCESS_KEY",
+    "AWS_SESSION_TOKEN",
+    "BROWSERBASE_API_KEY",
+    "BROWSERLESS_API_KEY",
+    "BROWSER_USE_API_KEY",
+    "DISPLAY",
+    "KERNEL_API_KEY",
+    "KERNEL_PROFILE_NAME",
+    "WAYLAND_DISPLAY",
+    "XDG_RUNTIME_DIR",
+];
+
+struct BugreportArgs {
+    out: Option<PathBuf>,
+    log_lines: usize,
+}
+
+fn parse_args(args: &[String]) -> Result<BugreportArgs, String> {
+    let mut parsed = BugreportArgs {
+        out: None,
+        log_lines: DEFAULT_LOG_LINES,
+    };
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--out" => {
+                i += 1;
+                let path = args.get(i).ok_or("--out requires a file path")?;
+                parsed.out = Some(PathBuf::from(path));
+            }
+            "--lines" => {
+                i += 1;
+                parsed.log_lines = args
+                    .get(i)
+                    .and_then(|v| v.parse().ok())
+                    .ok_or("--lines requires a non-negative number")?;
+            }
+            other => {
+                return Err(format!(
+                    "Unknown option '{}'. Usage: bugreport [--out <path>] [--lines <n>]",
+                    other
+                ))
+            }
+        }
+        i += 1;
+    }
+    Ok(parsed)
+}
+
+/// Relevant environment variables. Secret-named variables are reported by
+/// name only; other values are still run through the redactor because
+/// proxy and CDP URLs can embed credentials.
+fn env_snapshot(vars: impl Iterator<Item = (String, String)>) -> Value {
+    let mut values = BTreeMap::new();
+    let mut secret_names = Vec::new();
+    for (name, value) in vars {
+        if !name.starts_with("AGENT_BROWSER_") && !EXTRA_ENV_VARS.contains(&name.as_str()) {
+            continue;
+        }
+        if is_secret_name(&name) {
+            secret_names.push(name);
+        } else {
+            values.insert(name, redact_text(&value));
+        }
+    }
+    secret_names.sort();
+    json!({ "values": values, "redactedNames": secret_names })
+}
+
+/// Last `n` lines of `text`, redacted.
+fn tail_lines(text: &str, n: usize) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(n)..]
+        .iter()
+        .map(|line| redact_text(line))
+        .collect()
+}
+
+/// Drop unset (`null`) config fields so the report shows only what is
+/// actually configured.
+fn strip_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect::<Map<_, _>>(),
+        ),
+        other => other,
+    }
+}
+
+fn daemon_snapshot(session: &str) -> Value {
+    let inventory = walk_daemons();
+    let Some(active) = inventory.sessions.iter().find(|s| s.name == session) else {
+        return json!({ "running": false });
+    };
+    let info = send_command(json!({ "id": gen_id(), "action": "session_info" }), session);
+    let (data, error) = match info {
+        Ok(resp) if resp.success => (resp.data, None),
+        Ok(resp) => (None, resp.error),
+        Err(e) => (None, Some(e)),
+    };
+    json!({
+        "running": true,
+        "pid": active.pid,
+        "version": active.version,
+        "info": data,
+        "infoError": error,
+    })
+}
+
+fn collect_report(raw_args: &[String], session: &str, log_lines: usize) -> Value {
+    let mut config = match load_config(raw_args) {
+        Ok(config) => strip_nulls(serde_json::to_value(config).unwrap_or(Value::Null)),
+        Err(e) => json!({ "error": e }),
+    };
+    // `headers` is stored as a JSON string; expand it so header values are
+    // redacted by name like any other object.
+    if let Some(headers) = config.get("headers").and_then(|v| v.as_str()) {
+        config["headers"] = serde_json::from_str(headers).unwrap_or(json!("(unparseable)"));
+    }
+
+    let log_path = get_socket_dir().join(format!("{}.log", session));
+    let mut notes = Vec::new();
+    let daemon_log = match fs::read_to_string(&log_path) {
+        Ok(text) => Some(tail_lines(&text, log_lines)),
+        Err(_) => {
+            notes.push(
+                "No daemon log for this session. Re-run the failing command with --debug (or AGENT_BROWSER_DEBUG=1) after closing the session to capture one.",
+            );
+            None
+        }
+    };
+
+    let mut report = json!({
+        "generatedAt": chrono::Utc::now().to_rfc3339(),
+        "cli": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "os": env::consts::OS,
+            "arch": env::consts::ARCH,
+        },
+        "session": session,
+        "socketDir": get_socket_dir().to_string_lossy(),
+        "daemon": daemon_snapshot(session),
+        "config": config,
+        "env": env_snapshot(env::vars()),
+        "daemonLog": daemon_log,
+        "notes": notes,
+    });
+    redact_value(&mut report);
+    report
+}
+
+pub fn run_bugreport(args: &[String], raw_args: &[String], session: &str, json_mode: bool) {
+    let fail = |message: String| -> ! {
+        if json_mode {
+            println!(
+                "{}",
+                serde_json::to_string(&json!({ "success": false, "error": message }))
+                    .unwrap_or_default()
+            );
+        } else {
+            eprintln!("{} {}", color::error_indicator(), message);
+        }
+        exit(1);
+    };
+
+    let parsed = parse_args(args).unwrap_or_else(|e| fail(e));
+    let report = collect_report(raw_args, session, parsed.log_lines);
+    let path = parsed.out.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "agent-browser-bugreport-{}-{}.json",
+            session,
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        ))
+    });
+    let body = serde_json::to_string_pretty(&report).unwrap_or_default();
+    if let Err(e) = write_atomic(&path, body) {
+        fail(format!("Failed to write {}: {}", path.display(), e));
+    }
+
+    let shown = path.canonicalize().unwrap_or(path);
+    if json_mode {
+        println!(
+            "{}",
+            serde_json::to_string(&json!({
+                "success": true,
+                "data": { "path": shown.to_string_lossy() }
+            }))
+            .unwrap_or_default()
+        );
+    } else {
+        println!(
+            "{} Bug report written to {}",
+            color::success_indicator(),
+            shown.display()
+        );
+        println!(
+            "{}",
+            color::dim("Secrets are redacted, but review the file before sharing it.")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_snapshot_keeps_secret_names_only() {
+        let vars = vec![
+            ("AGENT_BROWSER_SESSION".to_string(), "work".to_string()),
+            (
+                "AGENT_BROWSER_PROXY".to_string(),
+                "http://user:pw@proxy:8080".to_string(),
+            ),
+            ("BROWSERBASE_API_KEY".to_string(), "bb_live_123".to_string()),
+            ("AGENT_BROWSER_ENCRYPTION_KEY".to_string(), "ab".repeat(32)),
+            ("HOME".to_string(), "/home/me".to_string()),
+        ];
+        let snapshot = env_snapshot(vars.into_iter());
+        assert_eq!(snapshot["values"]["AGENT_BROWSER_SESSION"], "work");
+        assert_eq!(
+            snapshot["values"]["AGENT_BROWSER_PROXY"],
+            "http://proxy:8080/"
+        );
+        assert!(snapshot["values"].get("HOME").is_none());
+        assert_eq!(
+            snapshot["redactedNames"],
+            json!(["AGENT_BROWSER_ENCRYPTION_KEY", "BROWSERBASE_API_KEY"])
+        );
+        assert!(!snapshot.to_string().contains("bb_live_123"));
+    }
+
+    #[test]
+    fn test_tail_lines_redacts_and_limits() {
+        let log = "one\ntwo\nconnect wss://cdp.example.com/?apiKey=k\n";
+        assert_eq!(
+            tail_lines(log, 2),
+            vec!["two", "connect wss://cdp.example.com/?apiKey=REDACTED"]
+        );
+        assert_eq!(tail_lines(log, 10).len(), 3);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args: Vec<String> = ["bugreport", "--out", "r.json", "--lines", "50"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let parsed = parse_args(&args).unwrap();
+        assert_eq!(parsed.out, Some(PathBuf::from("r.json")));
+        assert_eq!(parsed.log_lines, 50);
+        assert!(parse_args(&["bugreport".to_string(), "--lines".to_string()]).is_err());
+        assert!(parse_args(&["bugreport".to_string(), "--bogus".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_strip_nulls() {
+        assert_eq!(
+            strip_nulls(json!({ "headed": true, "proxy": null })),
+            json!({ "headed": true })
+        );
+    }
+}
@@ -13,17 +13,112 @@ fn env_is_truthy(name: &str) -> Option<bool> {
         .map(|val| !matches!(val.to_lowercase().as_str(), "0" | "false" | "no"))
 }
 
+/// Result of one console setup step at startup.
+#[cfg_attr(not(windows), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Probe {
+    /// The handle is redirected to a file or pipe; nothing to set up.
+    NotConsole,
+    Enabled,
+    Failed,
+}
+
+/// What the attached console can render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleSupport {
+    /// ANSI escape sequences are interpreted.
+    pub ansi: bool,
+    /// UTF-8 glyphs such as ✓ and → render correctly.
+    pub unicode: bool,
+}
+
+impl ConsoleSupport {
+    pub const FULL: Self = Self {
+        ansi: true,
+        unicode: true,
+    };
+}
+
+/// Combine the startup probes: virtual terminal processing on stdout and
+/// stderr, and switching the output code page to UTF-8. A redirected handle
+/// never degrades output, since the bytes go to a file or pipe unchanged.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn console_support(vt_stdout: Probe, vt_stderr: Probe, utf8: Probe) -> ConsoleSupport {
+    ConsoleSupport {
+        ansi: vt_stdout != Probe::Failed && vt_stderr != Probe::Failed,
+        unicode: utf8 != Probe::Failed,
+    }
+}
+
+static CONSOLE: OnceLock<ConsoleSupport> = OnceLock::new();
+
+/// Enable ANSI and UTF-8 output on a Windows console. Call once at startup,
+/// before anything is printed; elsewhere this is a no-op.
+pub fn init_console() {
+    #[cfg(windows)]
+    let _ = CONSOLE.set(probe_windows_console());
+}
+
+fn console() -> ConsoleSupport {
+    *CONSOLE.get().unwrap_or(&ConsoleSupport::FULL)
+}
+
+#[cfg(windows)]
+fn probe_windows_console() -> ConsoleSupport {
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, SetConsoleMode, SetConsoleOutputCP,
+        ENABLE_VIRTUAL_TERMINAL_PROCESSING, STD_ERROR_HANDLE, STD_HANDLE, STD_OUTPUT_HANDLE,
+    };
+    const CP_UTF8: u32 = 65001;
+
+    fn enable_vt(which: STD_HANDLE) -> Probe {
+        // SAFETY: plain Win32 calls on the process's own standard handles.
+        unsafe {
+            let handle = GetStdHandle(which);
+            let mut mode = 0;
+            if GetConsoleMode(handle, &mut mode) == 0 {
+                return Probe::NotConsole;
+            }
+            if mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+            {
+                Probe::Enabled
+            } else {
+                Probe::Failed
+            }
+        }
+    }
+
+    let vt_stdout = enable_vt(STD_OUTPUT_HANDLE);
+    let vt_stderr = enable_vt(STD_ERROR_HANDLE);
+    let utf8 = if vt_stdout == Probe::NotConsole && vt_stderr == Probe::NotConsole {
+        Probe::NotConsole
+    } else {
+        // SAFETY: sets the output code page of this process's console only.
+        match unsafe { SetConsoleOutputCP(CP_UTF8) } {
+            0 => Probe::Failed,
+            _ => Probe::Enabled,
+        }
+    };
+    console_support(vt_stdout, vt_stderr, utf8)
+}
+
+/// Whether colors are on, given the environment's request and the console.
+fn colors_enabled(requested: bool, support: ConsoleSupport) -> bool {
+    requested && support.ansi
+}
+
 /// Returns true if color output is enabled.
 ///
 /// Priority: `NO_COLOR` (presence disables, per spec) >
-/// `AGENT_BROWSER_COLOR` (truthy enables) > default (off).
+/// `AGENT_BROWSER_COLOR` (truthy enables) > default (off). Colors stay off
+/// on a console that could not enable ANSI escape processing.
 pub fn is_enabled() -> bool {
     static COLORS_ENABLED: OnceLock<bool> = OnceLock::new();
     *COLORS_ENABLED.get_or_init(|| {
-        if env::var_os("NO_COLOR").is_some() {
-            return false;
-        }
-        env_is_truthy("AGENT_BROWSER_COLOR").unwrap_or(false)
+        let requested = env::var_os("NO_COLOR").is_none()
+            && env_is_truthy("AGENT_BROWSER_COLOR").unwrap_or(false);
+        colors_enabled(requested, console())
     })
 }
 
@@ -81,40 +176,45 @@ pub fn dim(text: &str) -> String {
     }
 }
 
+/// A glyph in the given ANSI color, with an ASCII stand-in for consoles
+/// that cannot render UTF-8.
+fn render_glyph(
+    support: ConsoleSupport,
+    colors: bool,
+    code: &str,
+    glyph: &str,
+    ascii: &str,
+) -> String {
+    let text = if support.unicode { glyph } else { ascii };
+    if colors {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
 /// Red X error indicator
 pub fn error_indicator() -> &'static str {
     static INDICATOR: OnceLock<String> = OnceLock::new();
-    INDICATOR.get_or_init(|| {
-        if is_enabled() {
-            "\x1b[31m✗\x1b[0m".to_string()
-        } else {
-            "✗".to_string()
-        }
-    })
+    INDICATOR.get_or_init(|| render_glyph(console(), is_enabled(), "31", "✗", "x"))
 }
 
 /// Green checkmark success indicator
 pub fn success_indicator() -> &'static str {
     static INDICATOR: OnceLock<String> = OnceLock::new();
-    INDICATOR.get_or_init(|| {
-        if is_enabled() {
-            "\x1b[32m✓\x1b[0m".to_string()
-        } else {
-            "✓".to_string()
-        }
-    })
+    INDICATOR.get_or_init(|| render_glyph(console(), is_enabled(), "32", "✓", "+"))
 }
 
 /// Yellow warning indicator
 pub fn warning_indicator() -> &'static str {
     static INDICATOR: OnceLock<String> = OnceLock::new();
-    INDICATOR.get_or_init(|| {
-        if is_enabled() {
-            "\x1b[33m⚠\x1b[0m".to_string()
-        } else {
-            "⚠".to_string()
-        }
-    })
+    INDICATOR.get_or_init(|| render_glyph(console(), is_enabled(), "33", "⚠", "!"))
+}
+
+/// Cyan arrow marking the current tab or session in lists
+pub fn current_marker() -> &'static str {
+    static INDICATOR: OnceLock<String> = OnceLock::new();
+    INDICATOR.get_or_init(|| render_glyph(console(), is_enabled(), "36", "→", ">"))
 }
 
 /// Get console log color prefix by level
@@ -170,4 +270,52 @@ mod tests {
         assert!(success_indicator().contains('✓'));
         assert!(warning_indicator().contains('⚠'));
     }
+
+    #[test]
+    fn test_console_support_from_probes() {
+        use Probe::*;
+        assert_eq!(
+            console_support(Enabled, Enabled, Enabled),
+            ConsoleSupport::FULL
+        );
+        // Redirected handles never degrade output.
+        assert_eq!(
+            console_support(NotConsole, NotConsole, NotConsole),
+            ConsoleSupport::FULL
+        );
+        assert_eq!(
+            console_support(Enabled, Failed, Enabled),
+            ConsoleSupport {
+                ansi: false,
+                unicode: true
+            }
+        );
+        assert_eq!(
+            console_support(NotConsole, Enabled, Failed),
+            ConsoleSupport {
+                ansi: true,
+                unicode: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_failed_console_falls_back_to_plain_ascii() {
+        let degraded = console_support(Probe::Failed, Probe::Failed, Probe::Failed);
+        assert!(!colors_enabled(true, degraded));
+        assert!(colors_enabled(true, ConsoleSupport::FULL));
+        assert!(!colors_enabled(false, ConsoleSupport::FULL));
+
+        let colors = colors_enabled(true, degraded);
+        assert_eq!(render_glyph(degraded, colors, "32", "✓", "+"), "+");
+        assert_eq!(render_glyph(degraded, colors, "36", "→", ">"), ">");
+        assert_eq!(
+            render_glyph(ConsoleSupport::FULL, true, "32", "✓", "+"),
+            "\x1b[32m✓\x1b[0m"
+        );
+        assert_eq!(
+            render_glyph(ConsoleSupport::FULL, false, "31", "✗", "x"),
+            "✗"
+        );
+    }
 }
@@ -1,10 +1,12 @@
 use base64::{engine::general_purpose::STANDARD, Engine};
 use serde_json::{json, Value};
-use std::io::{self, BufRead};
+use std::io::{self, Read};
 
 use crate::color;
+use crate::command_registry;
 use crate::flags::Flags;
-use crate::validation::{is_valid_session_name, session_name_error};
+use crate::native::state::{validate_checkpoint_label, validate_state_arg};
+use crate::suggest;
 
 /// Error type for command parsing with contextual information
 #[derive(Debug)]
@@ -26,23 +28,52 @@ pub enum ParseError {
         message: String,
         usage: &'static str,
     },
-    /// Invalid session name (path traversal or invalid characters)
-    InvalidSessionName { name: String },
 }
 
 impl ParseError {
+    /// Machine-readable error type, as used in `--json` error output.
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            ParseError::UnknownCommand { .. } => "unknown_command",
+            ParseError::UnknownSubcommand { .. } => "unknown_subcommand",
+            ParseError::MissingArguments { .. } => "missing_arguments",
+            ParseError::InvalidValue { .. } => "invalid_value",
+        }
+    }
+
+    /// Close matches for an unknown command or subcommand, nearest first.
+    pub fn suggestions(&self) -> Vec<&'static str> {
+        match self {
+            ParseError::UnknownCommand { command } => suggest::suggestions(
+                command,
+                &command_registry::command_names(),
+                &command_registry::command_aliases(),
+            ),
+            ParseError::UnknownSubcommand {
+                subcommand,
+                valid_options,
+            } => suggest::suggestions(subcommand, valid_options, &[]),
+            _ => Vec::new(),
+        }
+    }
+
     pub fn format(&self) -> String {
+        let did_you_mean = || match self.suggestions().as_slice() {
+            [] => String::new(),
+            names => format!(". Did you mean: {}?", names.join(", ")),
+        };
         match self {
             ParseError::UnknownCommand { command } => {
-                format!("Unknown command: {}", command)
+                format!("Unknown command '{}'{}", command, did_you_mean())
             }
             ParseError::UnknownSubcommand {
                 subcommand,
                 valid_options,
             } => {
                 format!(
-                    "Unknown subcommand: {}\nValid options: {}",
+                    "Unknown subcommand '{}'{}\nValid options: {}",
                     subcommand,
+                    did_you_mean(),
                     valid_options.join(", ")
                 )
             }
@@ -55,7 +86,6 @@ impl ParseError {
             ParseError::InvalidValue { message, usage } => {
                 format!("{}\nUsage: agent-browser {}", message, usage)
             }
-            ParseError::InvalidSessionName { name } => session_name_error(name),
         }
     }
 }
@@ -71,111 +101,180 @@ pub fn gen_id() -> String {
     )
 }
 
+/// Where a parsed command carries a secret, so anything that echoes the
+/// command can hide it (see [`crate::redact::CommandRedactor`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensitiveField {
+    /// Always a secret, e.g. a password.
+    Always(&'static str),
+    /// `value` is a secret when the sibling `name` field (a cookie name or
+    /// storage key) matches a redaction pattern. With `list`, the rule
+    /// applies to each object in that array instead of the command itself.
+    NamedValue {
+        list: Option<&'static str>,
+        name: &'static str,
+        value: &'static str,
+    },
+    /// An object (HTTP headers) whose values are secrets when their key
+    /// matches a redaction pattern.
+    KeyedValues(&'static str),
+}
+
+/// The secret-bearing fields of each action's payload.
+pub fn sensitive_fields(action: &str) -> &'static [SensitiveField] {
+    match action {
+        "credentials" | "auth_save" => &[SensitiveField::Always("password")],
+        "cookies_set" => &[SensitiveField::NamedValue {
+            list: Some("cookies"),
+            name: "name",
+            value: "value",
+        }],
+        "storage_set" => &[SensitiveField::NamedValue {
+            list: None,
+            name: "key",
+            value: "value",
+        }],
+        "navigate" | "headers" => &[SensitiveField::KeyedValues("headers")],
+        _ => &[],
+    }
+}
+
 /// Normalize browser navigation inputs while preserving schemes Chrome can
-/// open directly. Bare hostnames use HTTPS, matching the `open` command.
+/// open directly. Bare hostnames get the `open` command's default prefix
+/// (HTTPS, or HTTP for loopback hosts).
 fn normalize_navigation_url(url: &str) -> String {
+    apply_default_scheme(url, "https").unwrap_or_else(|_| url.to_string())
+}
+
+fn has_navigation_scheme(url: &str) -> bool {
     let url_lower = url.to_lowercase();
-    if url_lower.starts_with("http://")
+    url_lower.starts_with("http://")
         || url_lower.starts_with("https://")
         || url_lower.starts_with("about:")
         || url_lower.starts_with("data:")
         || url_lower.starts_with("file:")
+        || url_lower.starts_with("view-source:")
         || url_lower.starts_with("chrome-extension://")
-        || url_lower.starts_with("chrome://")
-    {
-        url.to_string()
+        || url_lower.starts_with("chrome:")
+}
+
+/// Whether a scheme-less URL points at a loopback or `*.localhost` host,
+/// where dev servers almost always speak plain HTTP.
+fn is_local_dev_host(url: &str) -> bool {
+    let authority = url.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit('@').next().unwrap_or_default();
+    let host = if host_port.starts_with('[') {
+        host_port.split(']').next().map(|h| format!("{}]", h))
     } else {
-        format!("https://{}", url)
+        host_port.split(':').next().map(String::from)
+    }
+    .unwrap_or_default()
+    .to_lowercase();
+    if host == "localhost" || host.ends_with(".localhost") || host == "[::1]" {
+        return true;
+    }
+    let octets: Vec<&str> = host.split('.').collect();
+    octets.len() == 4 && octets[0] == "127" && octets.iter().all(|o| o.parse::<u8>().is_ok())
+}
+
+/// Add the `--default-scheme` prefix to a URL typed without a scheme:
+/// `https` (the default) or `http`, with loopback hosts always on http.
+/// `none` refuses to guess.
+fn apply_default_scheme(url: &str, mode: &str) -> Result<String, String> {
+    if has_navigation_scheme(url) {
+        return Ok(url.to_string());
+    }
+    let scheme = match mode {
+        "none" => {
+            return Err(format!(
+                "'{}' has no scheme and --default-scheme is none. Write http://{} or https://{}",
+                url, url, url
+            ))
+        }
+        "http" => "http",
+        _ if is_local_dev_host(url) => "http",
+        _ => "https",
+    };
+    Ok(format!("{}://{}", scheme, url))
+}
+
+/// Reject navigations the browser would refuse anyway: local files without
+/// `--allow-file-access`, and Chrome's internal pages on remote providers,
+/// which block them. `view-source:` is checked by the URL it wraps.
+fn check_navigation_target(url: &str, flags: &Flags) -> Result<(), ParseError> {
+    let lower = url.to_lowercase();
+    let target = lower.strip_prefix("view-source:").unwrap_or(&lower);
+    if target.starts_with("file:") && !flags.allow_file_access {
+        return Err(ParseError::InvalidValue {
+            message: format!(
+                "Opening {} needs --allow-file-access (file:// URLs are blocked by default)",
+                url
+            ),
+            usage: "agent-browser --allow-file-access open file:///path/to/file",
+        });
+    }
+    if target.starts_with("chrome:") {
+        if let Some(provider) = flags.provider.as_deref() {
+            return Err(ParseError::InvalidValue {
+                message: format!(
+                    "Cannot open {} with --provider {}: remote browser providers block chrome:// pages",
+                    url, provider
+                ),
+                usage: "open <url>",
+            });
+        }
     }
+    Ok(())
 }
 
 pub fn is_top_level_command(value: &str) -> bool {
-    matches!(
-        value,
-        "open"
-            | "goto"
-            | "navigate"
-            | "back"
-            | "forward"
-            | "reload"
-            | "read"
-            | "click"
-            | "dblclick"
-            | "fill"
-            | "type"
-            | "hover"
-            | "focus"
-            | "check"
-            | "uncheck"
-            | "select"
-            | "drag"
-            | "upload"
-            | "download"
-            | "press"
-            | "key"
-            | "keydown"
-            | "keyup"
-            | "keyboard"
-            | "scroll"
-            | "scrollintoview"
-            | "scrollinto"
-            | "wait"
-            | "screenshot"
-            | "pdf"
-            | "snapshot"
-            | "eval"
-            | "close"
-            | "quit"
-            | "exit"
-            | "inspect"
-            | "auth"
-            | "confirm"
-            | "deny"
-            | "connect"
-            | "stream"
-            | "get"
-            | "is"
-            | "find"
-            | "mouse"
-            | "set"
-            | "network"
-            | "storage"
-            | "cookies"
-            | "tab"
-            | "window"
-            | "frame"
-            | "dialog"
-            | "trace"
-            | "profiler"
-            | "record"
-            | "console"
-            | "errors"
-            | "highlight"
-            | "clipboard"
-            | "state"
-            | "tap"
-            | "swipe"
-            | "device"
-            | "diff"
-            | "batch"
-            | "react"
-            | "vitals"
-            | "web-vitals"
-            | "a11y"
-            | "pushstate"
-            | "removeinitscript"
-            | "session"
-            | "mcp"
-            | "doctor"
-            | "install"
-            | "upgrade"
-            | "profiles"
-            | "skills"
-            | "dashboard"
-            | "plugin"
-            | "plugins"
-            | "chat"
-    )
+    command_registry::find(value).is_some()
+}
+
+const COOKIES_SET_USAGE: &str = "cookies set <name> <value> [--url <url>] [--domain <domain> --path <path>] [--httpOnly] [--secure] [--sameSite <Strict|Lax|None>] [--expires <timestamp>]\n  or:  cookies set --curl <file> [--domain <domain>] [--url <url>]";
+
+/// `--sameSite`, case-insensitively, in the spelling CDP expects.
+fn parse_cookie_same_site(value: &str) -> Result<&'static str, ParseError> {
+    ["Strict", "Lax", "None"]
+        .into_iter()
+        .find(|v| v.eq_ignore_ascii_case(value))
+        .ok_or_else(|| ParseError::InvalidValue {
+            message: format!(
+                "Invalid --sameSite value '{}'. Expected Strict, Lax, or None",
+                value
+            ),
+            usage: COOKIES_SET_USAGE,
+        })
+}
+
+/// `--expires` as Unix seconds. Millisecond timestamps (what `Date.now()`
+/// returns) are rejected rather than silently set thousands of years out.
+fn parse_cookie_expires(value: &str) -> Result<Value, ParseError> {
+    let invalid = |message: String| ParseError::InvalidValue {
+        message,
+        usage: COOKIES_SET_USAGE,
+    };
+    let seconds = value
+        .parse::<f64>()
+        .ok()
+        .filter(|s| s.is_finite() && *s >= 0.0)
+        .ok_or_else(|| {
+            invalid(format!(
+                "Invalid --expires value '{}'. Expected a Unix timestamp in seconds",
+                value
+            ))
+        })?;
+    if seconds >= 1e11 {
+        return Err(invalid(format!(
+            "--expires {} looks like milliseconds. Use seconds since the epoch (e.g. {})",
+            value,
+            (seconds / 1000.0).floor()
+        )));
+    }
+    Ok(match value.parse::<i64>() {
+        Ok(whole) => json!(whole),
+        Err(_) => json!(seconds),
+    })
 }
 
 /// Parse a cookies file in one of three auto-detected formats:
@@ -330,6 +429,22 @@ fn parse_cookie_header(header: &str) -> Result<Vec<Value>, String> {
 pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError> {
     let mut result = parse_command_inner(args, flags)?;
 
+    // Catch typos in Playwright pseudo-classes and selectors the shell split
+    // apart here, rather than as a DOMException from the page.
+    if let Some(selector) = result.get("selector").and_then(|s| s.as_str()) {
+        crate::selector::validate_selector(selector).map_err(|message| {
+            ParseError::InvalidValue {
+                message,
+                usage: SELECTOR_USAGE,
+            }
+        })?;
+        // Send `@e5 >> button` in one spelling whatever the spacing or
+        // `css=` prefix it was typed with.
+        if let Some(Ok((ref_part, sub))) = crate::selector::parse_ref_scope(selector) {
+            result["selector"] = json!(format!("{} >> {}", ref_part, sub));
+        }
+    }
+
     // Inject AGENT_BROWSER_DEFAULT_TIMEOUT into any wait-family command that
     // doesn't already carry an explicit timeout. Centralised here so that new
     // wait variants automatically inherit the default without per-variant wiring.
@@ -341,9 +456,103 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
         }
     }
 
+    if let Some(ref prefix) = flags.ref_prefix {
+        apply_ref_prefix(&mut result, prefix);
+    }
+
+    if let Some(ref dir) = flags.artifacts_dir {
+        crate::artifacts::apply_artifacts_dir(&mut result, dir).map_err(|message| {
+            ParseError::InvalidValue {
+                message,
+                usage: "--artifacts-dir <dir>",
+            }
+        })?;
+    }
+
+    // In JSON mode, ask the daemon to describe the element a mutating
+    // action resolved to, for the `_action` record.
+    if flags.json && describes_target(&result) {
+        result["describe"] = json!(true);
+    }
+
     Ok(result)
 }
 
+/// Longest prefix `--ref-prefix` accepts.
+pub const MAX_REF_PREFIX_LEN: usize = 8;
+
+/// `--ref-prefix` values are 1 to 8 ASCII letters or digits, so prefixed
+/// refs such as `@ab12-e3` stay unambiguous.
+pub fn validate_ref_prefix(prefix: &str) -> Result<(), String> {
+    if prefix.is_empty()
+        || prefix.len() > MAX_REF_PREFIX_LEN
+        || !prefix.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(format!(
+            "invalid value '{}': expected 1 to {} letters or digits",
+            prefix, MAX_REF_PREFIX_LEN
+        ));
+    }
+    Ok(())
+}
+
+/// The plain ref (`@e3`) behind a ref carrying `prefix`: `@ab12-e3`,
+/// `ref=ab12-e3`, or `ab12-e3`. Anything else is not a prefixed ref.
+pub fn strip_ref_prefix(selector: &str, prefix: &str) -> Option<String> {
+    let trimmed = selector.trim();
+    let rest = trimmed
+        .strip_prefix('@')
+        .or_else(|| trimmed.strip_prefix("ref="))
+        .unwrap_or(trimmed);
+    let id = rest.strip_prefix(prefix)?.strip_prefix('-')?;
+    let digits = id.strip_prefix('e')?;
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("@{}", id))
+}
+
+/// Forward `--ref-prefix` to `snapshot` and `diff snapshot` and turn prefixed
+/// refs in selector arguments back into the plain refs the daemon resolves.
+/// Snapshot scopes (`-s @ab12-e5`) are unprefixed while parsing.
+fn apply_ref_prefix(cmd: &mut Value, prefix: &str) {
+    if matches!(
+        cmd.get("action").and_then(|a| a.as_str()),
+        Some("snapshot" | "diff_snapshot")
+    ) {
+        cmd["refPrefix"] = json!(prefix);
+    }
+    for key in ["selector", "source", "target"] {
+        let Some(value) = cmd.get(key).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let plain = match crate::selector::parse_ref_scope(value) {
+            Some(Ok((ref_part, sub))) => {
+                strip_ref_prefix(&ref_part, prefix).map(|r| format!("{} >> {}", r, sub))
+            }
+            _ => strip_ref_prefix(value, prefix),
+        };
+        if let Some(plain) = plain {
+            cmd[key] = json!(plain);
+        }
+    }
+}
+
+/// Actions that change the page through a single targeted element.
+const DESCRIBED_ACTIONS: &[&str] = &[
+    "click", "dblclick", "tap", "fill", "type", "check", "uncheck", "select", "upload",
+];
+
+/// Whether `cmd` is a mutating action on a selector, which `--json` output
+/// echoes under `_action`.
+pub fn describes_target(cmd: &Value) -> bool {
+    cmd.get("selector").is_some_and(|s| s.is_string())
+        && cmd
+            .get("action")
+            .and_then(|a| a.as_str())
+            .is_some_and(|a| DESCRIBED_ACTIONS.contains(&a))
+}
+
 fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseError> {
     if args.is_empty() {
         return Err(ParseError::MissingArguments {
@@ -363,6 +572,14 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
         );
     }
 
+    // The command registry is the list of commands: a name it does not hold,
+    // or holds as handled by main, is unknown here.
+    if crate::command_registry::find(cmd).is_none_or(|spec| spec.local) {
+        return Err(ParseError::UnknownCommand {
+            command: cmd.to_string(),
+        });
+    }
+
     match cmd {
         // === Navigation ===
         // Maps to "navigate" action in protocol; reflected in ACTION_CATEGORIES in action-policy.ts
@@ -372,6 +589,7 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
             // scripts before the first real navigation (see `batch`).
             // `goto` and `navigate` still require a URL since those verbs
             // imply the navigation itself.
+            let (extra_headers, rest) = extract_header_args(&rest, OPEN_HEADER_USAGE)?;
             let first_url = rest.iter().find(|a| !a.starts_with("--"));
             let url = match first_url {
                 Some(u) => *u,
@@ -385,19 +603,18 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                     });
                 }
             };
-            let url = normalize_navigation_url(url);
+            let mode = flags.default_scheme.as_deref().unwrap_or("https");
+            let url =
+                apply_default_scheme(url, mode).map_err(|message| ParseError::InvalidValue {
+                    message,
+                    usage: "open <url>",
+                })?;
+            check_navigation_target(&url, flags)?;
             let mut nav_cmd = json!({ "id": id, "action": "navigate", "url": url });
             if flags.provider.is_some() {
                 nav_cmd["waitUntil"] = json!("none");
             }
-            if let Some(ref headers_json) = flags.headers {
-                let headers =
-                    serde_json::from_str::<serde_json::Value>(headers_json).map_err(|_| {
-                        ParseError::InvalidValue {
-                            message: format!("Invalid JSON for --headers: {}", headers_json),
-                            usage: "open <url> --headers '{\"Key\": \"Value\"}'",
-                        }
-                    })?;
+            if let Some(headers) = headers_payload(flags, extra_headers, OPEN_HEADER_USAGE)? {
                 nav_cmd["headers"] = headers;
             }
             // Include iOS device info if specified (needed for auto-launch with existing daemon)
@@ -415,19 +632,39 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
 
         // === Core Actions ===
         "click" => {
-            let new_tab = rest.contains(&"--new-tab");
-            let sel = rest
-                .iter()
-                .find(|arg| **arg != "--new-tab")
-                .ok_or_else(|| ParseError::MissingArguments {
-                    context: "click".to_string(),
-                    usage: "click <selector> [--new-tab]",
-                })?;
+            let mut new_tab = false;
+            let mut popup = PopupOptions::default();
+            let mut sel: Option<&str> = None;
+            let mut i = 0;
+            while i < rest.len() {
+                match rest[i] {
+                    "--new-tab" => new_tab = true,
+                    "--wait-for-popup" => popup.wait = true,
+                    "--popup-timeout" => {
+                        i += 1;
+                        popup.timeout_ms = Some(parse_popup_timeout(rest.get(i), CLICK_USAGE)?);
+                    }
+                    token => {
+                        sel.get_or_insert(token);
+                    }
+                }
+                i += 1;
+            }
+            let sel = sel.ok_or_else(|| ParseError::MissingArguments {
+                context: "click".to_string(),
+                usage: CLICK_USAGE,
+            })?;
+            if new_tab && popup.wait {
+                return Err(ParseError::InvalidValue {
+                    message: "--new-tab and --wait-for-popup cannot be combined".to_string(),
+                    usage: CLICK_USAGE,
+                });
+            }
+            let mut cmd = json!({ "id": id, "action": "click", "selector": sel });
             if new_tab {
-                Ok(json!({ "id": id, "action": "click", "selector": sel, "newTab": true }))
-            } else {
-                Ok(json!({ "id": id, "action": "click", "selector": sel }))
+                cmd["newTab"] = json!(true);
             }
+            popup.apply(cmd, CLICK_USAGE)
         }
         "dblclick" => {
             let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
@@ -437,11 +674,16 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
             Ok(json!({ "id": id, "action": "dblclick", "selector": sel }))
         }
         "fill" => {
-            let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "fill".to_string(),
-                usage: "fill <selector> <text>",
-            })?;
-            Ok(json!({ "id": id, "action": "fill", "selector": sel, "value": rest[1..].join(" ") }))
+            // An explicit "" clears the field; leaving the text out is a mistake.
+            if rest.len() < 2 {
+                return Err(ParseError::MissingArguments {
+                    context: "fill".to_string(),
+                    usage: "fill <selector> <text>",
+                });
+            }
+            Ok(
+                json!({ "id": id, "action": "fill", "selector": rest[0], "value": rest[1..].join(" ") }),
+            )
         }
         "type" => {
             let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
@@ -530,56 +772,139 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                 Ok(json!({ "id": id, "action": "select", "selector": sel, "values": values }))
             }
         }
-        "drag" => {
-            let src = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "drag".to_string(),
-                usage: "drag <source> <target>",
-            })?;
-            let tgt = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "drag".to_string(),
-                usage: "drag <source> <target>",
-            })?;
-            Ok(json!({ "id": id, "action": "drag", "source": src, "target": tgt }))
+        "drag" => parse_drag(&rest, &id),
+        "upload" => parse_upload(&rest, &id),
+        "download" => {
+            const USAGE: &str = "download <selector> <path> [--timeout <ms>]";
+            let mut positional: Vec<&str> = Vec::new();
+            let mut timeout_ms: Option<u64> = None;
+            let mut i = 0;
+            while i < rest.len() {
+                match rest[i] {
+                    "--timeout" => {
+                        let raw = rest
+                            .get(i + 1)
+                            .ok_or_else(|| ParseError::MissingArguments {
+                                context: "download --timeout".to_string(),
+                                usage: USAGE,
+                            })?;
+                        timeout_ms =
+                            Some(raw.parse::<u64>().ok().filter(|ms| *ms > 0).ok_or_else(
+                                || ParseError::InvalidValue {
+                                    message: format!(
+                                    "--timeout expects a positive number of milliseconds, got '{}'",
+                                    raw
+                                ),
+                                    usage: USAGE,
+                                },
+                            )?);
+                        i += 2;
+                    }
+                    other if positional.len() < 2 => {
+                        positional.push(other);
+                        i += 1;
+                    }
+                    other => {
+                        return Err(ParseError::InvalidValue {
+                            message: format!("Unexpected argument '{}'", other),
+                            usage: USAGE,
+                        });
+                    }
+                }
+            }
+            let [sel, path] = positional[..] else {
+                return Err(ParseError::MissingArguments {
+                    context: "download".to_string(),
+                    usage: USAGE,
+                });
+            };
+            // The daemon's working directory is not ours, so send an
+            // absolute path that means what the user typed. A bare filename
+            // under --artifacts-dir is placed by `parse_command` instead.
+            let keep_for_artifacts =
+                flags.artifacts_dir.is_some() && crate::artifacts::is_bare_filename(path);
+            let path = match std::env::current_dir() {
+                Ok(cwd) if !keep_for_artifacts => {
+                    crate::flags::normalize_path_value(path, &cwd, dirs::home_dir().as_deref())
+                }
+                _ => path.to_string(),
+            };
+            let mut cmd = json!({ "id": id, "action": "download", "selector": sel, "path": path });
+            if let Some(ms) = timeout_ms {
+                cmd["timeout"] = json!(ms);
+            }
+            Ok(cmd)
         }
-        "upload" => {
-            let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "upload".to_string(),
-                usage: "upload <selector> <files...>",
-            })?;
-            Ok(json!({ "id": id, "action": "upload", "selector": sel, "files": &rest[1..] }))
+        "downloads" => {
+            const VALID: &[&str] = &["list", "clear", "open"];
+            const OPEN_USAGE: &str = "downloads open <index> [--reveal]";
+            match rest.first().copied() {
+                None | Some("list") => Ok(json!({ "id": id, "action": "downloads_list" })),
+                Some("clear") => Ok(json!({ "id": id, "action": "downloads_clear" })),
+                Some("open") => {
+                    let raw = rest
+                        .iter()
+                        .skip(1)
+                        .find(|a| !a.starts_with("--"))
+                        .ok_or_else(|| ParseError::MissingArguments {
+                            context: "downloads open".to_string(),
+                            usage: OPEN_USAGE,
+                        })?;
+                    let index = raw
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| ParseError::InvalidValue {
+                            message: format!(
+                                "Invalid download index '{}': expected a positive integer from `downloads list`",
+                                raw
+                            ),
+                            usage: OPEN_USAGE,
+                        })?;
+                    let reveal = rest.contains(&"--reveal");
+                    Ok(json!({
+                        "id": id,
+                        "action": "downloads_open",
+                        "index": index,
+                        "reveal": reveal
+                    }))
+                }
+                Some(sub) => Err(ParseError::UnknownSubcommand {
+                    subcommand: sub.to_string(),
+                    valid_options: VALID,
+                }),
+            }
         }
-        "download" => {
-            let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "download".to_string(),
-                usage: "download <selector> <path>",
-            })?;
-            let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "download".to_string(),
-                usage: "download <selector> <path>",
-            })?;
-            Ok(json!({ "id": id, "action": "download", "selector": sel, "path": path }))
+
+        "extension" => {
+            const VALID: &[&str] = &["list", "id"];
+            match rest.first().copied() {
+                None | Some("list") => Ok(json!({ "id": id, "action": "extension_list" })),
+                Some("id") => {
+                    let name = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                        context: "extension id".to_string(),
+                        usage: "extension id <name>",
+                    })?;
+                    Ok(json!({ "id": id, "action": "extension_list", "name": name }))
+                }
+                Some(sub) => Err(ParseError::UnknownSubcommand {
+                    subcommand: sub.to_string(),
+                    valid_options: VALID,
+                }),
+            }
         }
 
         // === Keyboard ===
         "press" | "key" => {
-            let key = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "press".to_string(),
-                usage: "press <key>",
-            })?;
+            let key = parse_key_arg(rest.first(), "press", "press <key>")?;
             Ok(json!({ "id": id, "action": "press", "key": key }))
         }
         "keydown" => {
-            let key = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "keydown".to_string(),
-                usage: "keydown <key>",
-            })?;
+            let key = parse_key_arg(rest.first(), "keydown", "keydown <key>")?;
             Ok(json!({ "id": id, "action": "keydown", "key": key }))
         }
         "keyup" => {
-            let key = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "keyup".to_string(),
-                usage: "keyup <key>",
-            })?;
+            let key = parse_key_arg(rest.first(), "keyup", "keyup <key>")?;
             Ok(json!({ "id": id, "action": "keyup", "key": key }))
         }
         "keyboard" => {
@@ -589,16 +914,51 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
             })?;
             match *sub {
                 "type" => {
-                    let text: String = rest[1..].join(" ");
+                    let mut delay: Option<u64> = None;
+                    let mut text_parts: Vec<&str> = Vec::new();
+                    let mut i = 1;
+                    while i < rest.len() {
+                        if rest[i] == "--delay" {
+                            let raw =
+                                rest.get(i + 1)
+                                    .ok_or_else(|| ParseError::MissingArguments {
+                                        context: "keyboard type --delay".to_string(),
+                                        usage: KEYBOARD_TYPE_USAGE,
+                                    })?;
+                            delay =
+                                Some(raw.parse::<u64>().map_err(|_| ParseError::InvalidValue {
+                                    message: format!(
+                                        "--delay expects a number in ms, got '{}'",
+                                        raw
+                                    ),
+                                    usage: KEYBOARD_TYPE_USAGE,
+                                })?);
+                            i += 1;
+                        } else {
+                            text_parts.push(rest[i]);
+                        }
+                        i += 1;
+                    }
+                    let text = text_parts.join(" ");
                     if text.is_empty() {
                         return Err(ParseError::MissingArguments {
                             context: "keyboard type".to_string(),
-                            usage: "keyboard type <text>",
+                            usage: KEYBOARD_TYPE_USAGE,
                         });
                     }
-                    Ok(json!({ "id": id, "action": "keyboard", "subaction": "type", "text": text }))
+                    let mut cmd = json!({ "id": id, "action": "keyboard", "subaction": "type", "text": text });
+                    if let Some(ms) = delay {
+                        cmd["delay"] = json!(ms);
+                    }
+                    Ok(cmd)
                 }
                 "inserttext" | "insertText" => {
+                    if rest[1..].contains(&"--delay") {
+                        return Err(ParseError::InvalidValue {
+                            message: "--delay applies to keyboard type; inserttext inserts the whole text at once".to_string(),
+                            usage: "keyboard inserttext <text>",
+                        });
+                    }
                     let text: String = rest[1..].join(" ");
                     if text.is_empty() {
                         return Err(ParseError::MissingArguments {
@@ -640,6 +1000,15 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                     _ => {
                         match positional_index {
                             0 => {
+                                if !matches!(rest[i], "up" | "down" | "left" | "right") {
+                                    return Err(ParseError::InvalidValue {
+                                        message: format!(
+                                            "Invalid scroll direction: {}. Expected up, down, left, or right",
+                                            rest[i]
+                                        ),
+                                        usage: "scroll [direction] [amount] [--selector <sel>]",
+                                    });
+                                }
                                 obj.insert("direction".to_string(), json!(rest[i]));
                             }
                             1 => {
@@ -685,11 +1054,11 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                     .get(idx + 1)
                     .ok_or_else(|| ParseError::MissingArguments {
                         context: "wait --timeout".to_string(),
-                        usage: "wait <selector|ms|--url|--load|--fn|--text> [--timeout <ms>]",
+                        usage: WAIT_USAGE,
                     })?;
                 timeout_ms = Some(raw.parse::<u64>().map_err(|_| ParseError::InvalidValue {
                     message: format!("--timeout expects a number in ms, got '{}'", raw),
-                    usage: "wait <selector|ms|--url|--load|--fn|--text> [--timeout <ms>]",
+                    usage: WAIT_USAGE,
                 })?);
                 rest.drain(idx..=idx + 1);
             }
@@ -700,6 +1069,19 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                 cmd
             };
 
+            // The modes are exclusive; before this check the first one in
+            // the order below won and the others were silently dropped.
+            let modes = wait_modes(&rest);
+            if modes.len() > 1 {
+                return Err(ParseError::InvalidValue {
+                    message: format!(
+                        "wait {} cannot be combined; use one of --url, --load, --fn, --text, or --download",
+                        modes.join(" and ")
+                    ),
+                    usage: WAIT_USAGE,
+                });
+            }
+
             // Check for --url flag: wait --url "**/dashboard"
             if let Some(idx) = rest.iter().position(|&s| s == "--url" || s == "-u") {
                 let url = rest
@@ -719,8 +1101,18 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                     .get(idx + 1)
                     .ok_or_else(|| ParseError::MissingArguments {
                         context: "wait --load".to_string(),
-                        usage: "wait --load <state>",
+                        usage: WAIT_LOAD_USAGE,
                     })?;
+                if !WAIT_LOAD_STATES.contains(state) {
+                    return Err(ParseError::InvalidValue {
+                        message: format!(
+                            "Unknown load state '{}'; expected {}",
+                            state,
+                            WAIT_LOAD_STATES.join(", ")
+                        ),
+                        usage: WAIT_LOAD_USAGE,
+                    });
+                }
                 return Ok(with_timeout(
                     json!({ "id": id, "action": "waitforloadstate", "state": state }),
                 ));
@@ -739,17 +1131,49 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                 ));
             }
 
-            // Check for --text flag: wait --text "Welcome"
-            if let Some(idx) = rest.iter().position(|&s| s == "--text" || s == "-t") {
+            // Check for --text flag: wait --text "Welcome" [-s <scope>] [--exact] [--not]
+            let text_idx = rest.iter().position(|&s| s == "--text" || s == "-t");
+            let exact = rest.contains(&"--exact");
+            let negate = rest.contains(&"--not");
+            let scope_idx = rest.iter().position(|&s| s == "--selector" || s == "-s");
+            if text_idx.is_none() && (exact || negate || scope_idx.is_some()) {
+                let flag = if negate {
+                    "--not"
+                } else if exact {
+                    "--exact"
+                } else {
+                    "--selector"
+                };
+                return Err(ParseError::InvalidValue {
+                    message: format!("wait {} requires --text", flag),
+                    usage: WAIT_TEXT_USAGE,
+                });
+            }
+            if let Some(idx) = text_idx {
                 let text = rest
                     .get(idx + 1)
                     .ok_or_else(|| ParseError::MissingArguments {
                         context: "wait --text".to_string(),
-                        usage: "wait --text <text>",
+                        usage: WAIT_TEXT_USAGE,
                     })?;
-                return Ok(with_timeout(
-                    json!({ "id": id, "action": "wait", "text": text }),
-                ));
+                let mut cmd = json!({ "id": id, "action": "wait", "text": text });
+                if let Some(idx) = scope_idx {
+                    let scope = rest
+                        .get(idx + 1)
+                        .filter(|s| !s.starts_with("--"))
+                        .ok_or_else(|| ParseError::MissingArguments {
+                            context: "wait --text --selector".to_string(),
+                            usage: WAIT_TEXT_USAGE,
+                        })?;
+                    cmd["scope"] = json!(scope);
+                }
+                if exact {
+                    cmd["exact"] = json!(true);
+                }
+                if negate {
+                    cmd["negate"] = json!(true);
+                }
+                return Ok(with_timeout(cmd));
             }
 
             // Check for --download flag: wait --download [path]
@@ -780,28 +1204,61 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
             } else {
                 Err(ParseError::MissingArguments {
                     context: "wait".to_string(),
-                    usage: "wait <selector|ms|--url|--load|--fn|--text>",
+                    usage: WAIT_USAGE,
                 })
             }
         }
 
         // === Screenshot/PDF ===
         "screenshot" => {
-            // screenshot [selector] [path] [--full/-f]
+            // screenshot [selector] [path] [--full/-f [true|false]] [--annotate]
+            //            [--interactive-only] [--selector <sel>] [--clip x,y,w,h]
             // selector: @ref or CSS selector
             // path: file path (contains / or . or ends with known extension)
-            let mut full_page = false;
-            let positional: Vec<&str> = rest
-                .iter()
-                .filter(|arg| match **arg {
+            let mut full_page = flags.full;
+            let mut explicit_full = false;
+            let mut annotate = flags.annotate;
+            let mut interactive_only = false;
+            let mut selector_flag: Option<&str> = None;
+            let mut clip: Option<Value> = None;
+            let mut positional: Vec<&str> = Vec::new();
+            let mut args = rest.iter().peekable();
+            while let Some(arg) = args.next() {
+                match *arg {
                     "--full" | "-f" => {
-                        full_page = true;
-                        false
+                        full_page = args.peek() != Some(&&"false");
+                        explicit_full = full_page;
+                        if matches!(args.peek(), Some(&&"true") | Some(&&"false")) {
+                            args.next();
+                        }
                     }
-                    _ => true,
-                })
-                .copied()
-                .collect();
+                    "--full=true" => (full_page, explicit_full) = (true, true),
+                    "--full=false" => full_page = false,
+                    "--annotate" => annotate = true,
+                    "--interactive-only" => interactive_only = true,
+                    "--selector" => {
+                        let sel = args.next().ok_or_else(|| ParseError::MissingArguments {
+                            context: "screenshot --selector".to_string(),
+                            usage: SCREENSHOT_USAGE,
+                        })?;
+                        selector_flag = Some(*sel);
+                    }
+                    "--clip" => clip = Some(parse_screenshot_clip(args.next().copied())?),
+                    other => positional.push(other),
+                }
+            }
+            if let Some(sel) = selector_flag {
+                if positional.len() > 1 {
+                    return Err(ParseError::InvalidValue {
+                        message: format!(
+                            "screenshot got --selector {} and a positional selector '{}'; use one",
+                            sel, positional[0]
+                        ),
+                        usage: SCREENSHOT_USAGE,
+                    });
+                }
+                positional.insert(0, sel);
+            }
             let (selector, path) = match (positional.first(), positional.get(1)) {
                 (Some(first), Some(second)) => {
                     // Two args: first is selector, second is path
@@ -827,10 +1284,28 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                 }
                 _ => (None, None),
             };
+            if clip.is_some() {
+                let conflict = if selector.is_some() {
+                    Some("a selector")
+                } else if explicit_full {
+                    Some("--full")
+                } else {
+                    None
+                };
+                if let Some(conflict) = conflict {
+                    return Err(ParseError::InvalidValue {
+                        message: format!("--clip cannot be combined with {}", conflict),
+                        usage: SCREENSHOT_USAGE,
+                    });
+                }
+                // A clip is a viewport region; a configured full-page default
+                // does not apply to it.
+                full_page = false;
+            }
             let mut cmd = json!({
                 "id": id, "action": "screenshot",
-                "path": path, "selector": selector,
-                "fullPage": full_page, "annotate": flags.annotate
+                "path": path, "selector": selector, "clip": clip,
+                "fullPage": full_page, "annotate": annotate
             });
             if let Some(ref fmt) = flags.screenshot_format {
                 cmd["format"] = json!(fmt);
@@ -847,13 +1322,29 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
             if let Some(ref dir) = flags.screenshot_dir {
                 cmd["screenshotDir"] = json!(dir);
             }
+            if interactive_only {
+                if !annotate {
+                    return Err(ParseError::InvalidValue {
+                        message: "--interactive-only only applies with --annotate".to_string(),
+                        usage: "screenshot --annotate --interactive-only [selector] [path]",
+                    });
+                }
+                cmd["interactiveOnly"] = json!(true);
+            }
             Ok(cmd)
         }
         "pdf" => {
-            let path = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "pdf".to_string(),
-                usage: "pdf <path>",
-            })?;
+            // With --artifacts-dir the path is optional; a name is generated.
+            let path = match rest.first() {
+                Some(path) => Some(*path),
+                None if flags.artifacts_dir.is_some() => None,
+                None => {
+                    return Err(ParseError::MissingArguments {
+                        context: "pdf".to_string(),
+                        usage: "pdf <path>",
+                    })
+                }
+            };
             Ok(json!({ "id": id, "action": "pdf", "path": path }))
         }
 
@@ -876,6 +1367,12 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                     "-u" | "--urls" => {
                         obj.insert("urls".to_string(), json!(true));
                     }
+                    "--stable-refs" => {
+                        obj.insert("stableRefs".to_string(), json!(true));
+                    }
+                    "--viewport-only" => {
+                        obj.insert("viewportOnly".to_string(), json!(true));
+                    }
                     "-d" | "--depth" => {
                         if let Some(d) = rest.get(i + 1) {
                             if let Ok(n) = d.parse::<i32>() {
@@ -886,7 +1383,12 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                     }
                     "-s" | "--selector" => {
                         if let Some(s) = rest.get(i + 1) {
-                            obj.insert("selector".to_string(), json!(s));
+                            insert_snapshot_scope(
+                                obj,
+                                s,
+                                flags.ref_prefix.as_deref(),
+                                "snapshot -s <selector|@ref>",
+                            )?;
                             i += 1;
                         }
                     }
@@ -899,48 +1401,27 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
 
         // === Eval ===
         "eval" => {
-            // Check for flags: -b/--base64 or --stdin
-            let (is_base64, is_stdin, script_parts): (bool, bool, &[&str]) =
-                if rest.first() == Some(&"-b") || rest.first() == Some(&"--base64") {
-                    (true, false, &rest[1..])
-                } else if rest.first() == Some(&"--stdin") {
-                    (false, true, &rest[1..])
-                } else {
-                    (false, false, rest.as_slice())
-                };
-
-            let script = if is_stdin {
-                // Read script from stdin
-                let stdin = io::stdin();
-                let lines: Vec<String> = stdin
-                    .lock()
-                    .lines()
-                    .map(|l| l.unwrap_or_default())
-                    .collect();
-                lines.join("\n")
+            // --raw-result only changes how the CLI prints the result, so it
+            // is accepted before or after the script.
+            let raw_result = rest.first() == Some(&"--raw-result")
+                || (rest.len() > 1 && rest.last() == Some(&"--raw-result"));
+            let rest: Vec<&str> = if rest.first() == Some(&"--raw-result") {
+                rest[1..].to_vec()
+            } else if raw_result {
+                rest[..rest.len() - 1].to_vec()
             } else {
-                let raw_script = script_parts.join(" ");
-                if is_base64 {
-                    let decoded =
-                        STANDARD
-                            .decode(&raw_script)
-                            .map_err(|_| ParseError::InvalidValue {
-                                message: "Invalid base64 encoding".to_string(),
-                                usage: "eval -b <base64-encoded-script>",
-                            })?;
-                    String::from_utf8(decoded).map_err(|_| ParseError::InvalidValue {
-                        message: "Base64 decoded to invalid UTF-8".to_string(),
-                        usage: "eval -b <base64-encoded-script>",
-                    })?
-                } else {
-                    raw_script
-                }
+                rest.to_vec()
             };
-            Ok(json!({ "id": id, "action": "evaluate", "script": script }))
+            let script = read_eval_script(&rest, &mut io::stdin().lock())?;
+            let mut cmd = json!({ "id": id, "action": "evaluate", "script": script });
+            if raw_result {
+                cmd["rawResult"] = json!(true);
+            }
+            Ok(cmd)
         }
 
         // === Close ===
-        "close" | "quit" | "exit" => Ok(json!({ "id": id, "action": "close" })),
+        "close" | "quit" | "exit" => parse_close(&rest, flags, &id),
 
         // === Inspect ===
         "inspect" => Ok(json!({ "id": id, "action": "inspect" })),
@@ -1202,101 +1683,32 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
         "connect" => {
             let endpoint = rest.first().ok_or_else(|| ParseError::MissingArguments {
                 context: "connect".to_string(),
-                usage: "connect <port|url>",
+                usage: CONNECT_USAGE,
             })?;
-            // Check if it's a URL (ws://, wss://, http://, https://)
-            if endpoint.starts_with("ws://")
-                || endpoint.starts_with("wss://")
-                || endpoint.starts_with("http://")
-                || endpoint.starts_with("https://")
-            {
-                Ok(json!({ "id": id, "action": "launch", "cdpUrl": endpoint }))
-            } else {
-                // It's a port number - validate and use cdpPort field
-                let port: u16 = match endpoint.parse::<u32>() {
-                    Ok(0) => {
-                        return Err(ParseError::InvalidValue {
-                            message: "Invalid port: port must be greater than 0".to_string(),
-                            usage: "connect <port|url>",
-                        });
-                    }
-                    Ok(p) if p > 65535 => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!(
-                                "Invalid port: {} is out of range (valid range: 1-65535)",
-                                p
-                            ),
-                            usage: "connect <port|url>",
-                        });
-                    }
-                    Ok(p) => p as u16,
-                    Err(_) => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!(
-                                "Invalid value: '{}' is not a valid port number or URL",
-                                endpoint
-                            ),
-                            usage: "connect <port|url>",
-                        });
-                    }
-                };
-                Ok(json!({ "id": id, "action": "launch", "cdpPort": port }))
-            }
+            let mut cmd =
+                cdp_endpoint_launch(endpoint).map_err(|message| ParseError::InvalidValue {
+                    message,
+                    usage: CONNECT_USAGE,
+                })?;
+            cmd["id"] = json!(id);
+            cmd["action"] = json!("launch");
+            Ok(cmd)
         }
 
         // === Runtime stream control ===
         "stream" => match rest.first().copied() {
-            Some("enable") => {
-                let mut cmd = json!({ "id": id, "action": "stream_enable" });
-                let mut i = 1;
-                while i < rest.len() {
-                    match rest[i] {
-                        "--port" => {
-                            let value =
-                                rest.get(i + 1)
-                                    .ok_or_else(|| ParseError::MissingArguments {
-                                        context: "stream enable --port".to_string(),
-                                        usage: "stream enable [--port <port>]",
-                                    })?;
-                            let port =
-                                value.parse::<u32>().map_err(|_| ParseError::InvalidValue {
-                                    message: format!(
-                                        "Invalid port: '{}' is not a valid integer",
-                                        value
-                                    ),
-                                    usage: "stream enable [--port <port>]",
-                                })?;
-                            if port > u16::MAX as u32 {
-                                return Err(ParseError::InvalidValue {
-                                    message: format!(
-                                        "Invalid port: {} is out of range (valid range: 0-65535)",
-                                        port
-                                    ),
-                                    usage: "stream enable [--port <port>]",
-                                });
-                            }
-                            cmd["port"] = json!(port);
-                            i += 2;
-                        }
-                        flag => {
-                            return Err(ParseError::InvalidValue {
-                                message: format!("Unknown flag for stream enable: {}", flag),
-                                usage: "stream enable [--port <port>]",
-                            });
-                        }
-                    }
-                }
-                Ok(cmd)
-            }
+            Some("enable") => parse_stream_start(&rest[1..], &id, "stream_enable"),
+            Some("start") => parse_stream_start(&rest[1..], &id, "stream_start"),
+            Some("stop") => Ok(json!({ "id": id, "action": "stream_stop" })),
             Some("disable") => Ok(json!({ "id": id, "action": "stream_disable" })),
             Some("status") => Ok(json!({ "id": id, "action": "stream_status" })),
             Some(sub) => Err(ParseError::UnknownSubcommand {
                 subcommand: sub.to_string(),
-                valid_options: &["enable", "disable", "status"],
+                valid_options: &["start", "stop", "status", "enable", "disable"],
             }),
             None => Err(ParseError::MissingArguments {
                 context: "stream".to_string(),
-                usage: "stream <enable|disable|status>",
+                usage: "stream <start|stop|status>",
             }),
         },
 
@@ -1316,7 +1728,8 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
         "set" => parse_set(&rest, &id),
 
         // === Network ===
-        "network" => parse_network(&rest, &id),
+        "network" => parse_network(&rest, flags, &id),
+        "har" => parse_har(&rest, flags, "har", &id),
 
         // === Storage ===
         "storage" => parse_storage(&rest, &id),
@@ -1375,11 +1788,11 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
 
                     let name = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
                         context: "cookies set".to_string(),
-                        usage: "cookies set <name> <value> [--url <url>] [--domain <domain>] [--path <path>] [--httpOnly] [--secure] [--sameSite <Strict|Lax|None>] [--expires <timestamp>]\n  or:  cookies set --curl <file> [--domain <domain>] [--url <url>]",
+                        usage: COOKIES_SET_USAGE,
                     })?;
                     let value = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
                         context: "cookies set".to_string(),
-                        usage: "cookies set <name> <value> [--url <url>] [--domain <domain>] [--path <path>] [--httpOnly] [--secure] [--sameSite <Strict|Lax|None>] [--expires <timestamp>]\n  or:  cookies set --curl <file> [--domain <domain>] [--url <url>]",
+                        usage: COOKIES_SET_USAGE,
                     })?;
 
                     let mut cookie = json!({ "name": name, "value": value });
@@ -1387,95 +1800,49 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                     // Parse optional flags
                     let mut i = 3;
                     while i < rest.len() {
-                        match rest[i] {
-                            "--url" => {
-                                if let Some(url) = rest.get(i + 1) {
-                                    cookie["url"] = json!(url);
-                                    i += 2;
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --url".to_string(),
-                                        usage: "--url <url>",
-                                    });
-                                }
-                            }
-                            "--domain" => {
-                                if let Some(domain) = rest.get(i + 1) {
-                                    cookie["domain"] = json!(domain);
-                                    i += 2;
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --domain".to_string(),
-                                        usage: "--domain <domain>",
-                                    });
-                                }
-                            }
-                            "--path" => {
-                                if let Some(path) = rest.get(i + 1) {
-                                    cookie["path"] = json!(path);
-                                    i += 2;
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --path".to_string(),
-                                        usage: "--path <path>",
-                                    });
-                                }
-                            }
-                            "--httpOnly" => {
-                                cookie["httpOnly"] = json!(true);
-                                i += 1;
-                            }
-                            "--secure" => {
-                                cookie["secure"] = json!(true);
-                                i += 1;
-                            }
-                            "--sameSite" => {
-                                if let Some(same_site) = rest.get(i + 1) {
-                                    // Validate sameSite value
-                                    if *same_site == "Strict"
-                                        || *same_site == "Lax"
-                                        || *same_site == "None"
-                                    {
-                                        cookie["sameSite"] = json!(same_site);
-                                        i += 2;
-                                    } else {
-                                        return Err(ParseError::MissingArguments {
-                                            context: "cookies set --sameSite".to_string(),
-                                            usage: "--sameSite <Strict|Lax|None>",
-                                        });
-                                    }
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --sameSite".to_string(),
-                                        usage: "--sameSite <Strict|Lax|None>",
-                                    });
-                                }
-                            }
-                            "--expires" => {
-                                if let Some(expires_str) = rest.get(i + 1) {
-                                    if let Ok(expires) = expires_str.parse::<i64>() {
-                                        cookie["expires"] = json!(expires);
-                                        i += 2;
-                                    } else {
-                                        return Err(ParseError::MissingArguments {
-                                            context: "cookies set --expires".to_string(),
-                                            usage: "--expires <timestamp>",
-                                        });
+                        let flag = rest[i];
+                        match flag {
+                            "--url" | "--domain" | "--path" | "--sameSite" | "--expires" => {
+                                let val = rest.get(i + 1).ok_or_else(|| {
+                                    ParseError::MissingArguments {
+                                        context: format!("cookies set {}", flag),
+                                        usage: COOKIES_SET_USAGE,
                                     }
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --expires".to_string(),
-                                        usage: "--expires <timestamp>",
-                                    });
-                                }
+                                })?;
+                                let key = &flag[2..];
+                                cookie[key] = match flag {
+                                    "--sameSite" => json!(parse_cookie_same_site(val)?),
+                                    "--expires" => parse_cookie_expires(val)?,
+                                    _ => json!(val),
+                                };
+                                i += 2;
                             }
-                            _ => {
-                                // Unknown flag, skip it (or could error)
+                            "--httpOnly" | "--secure" => {
+                                cookie[&flag[2..]] = json!(true);
                                 i += 1;
                             }
+                            other => {
+                                return Err(ParseError::InvalidValue {
+                                    message: format!("Unknown cookies set option: {}", other),
+                                    usage: COOKIES_SET_USAGE,
+                                });
+                            }
                         }
                     }
 
+                    // Without a URL the browser scopes the cookie by domain
+                    // and path together; a bare domain is rejected there with
+                    // a vaguer error.
+                    if cookie.get("domain").is_some()
+                        && cookie.get("path").is_none()
+                        && cookie.get("url").is_none()
+                    {
+                        return Err(ParseError::InvalidValue {
+                            message: "cookies set --domain needs --path (usually --path /), or use --url instead".to_string(),
+                            usage: COOKIES_SET_USAGE,
+                        });
+                    }
+
                     Ok(json!({ "id": id, "action": "cookies_set", "cookies": [cookie] }))
                 }
                 "clear" => Ok(json!({ "id": id, "action": "cookies_clear" })),
@@ -1597,7 +1964,7 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
         "trace" => {
             const VALID: &[&str] = &["start", "stop"];
             match rest.first().copied() {
-                Some("start") => Ok(json!({ "id": id, "action": "trace_start" })),
+                Some("start") => parse_trace_start(&rest[1..], &id),
                 Some("stop") => {
                     let mut cmd = json!({ "id": id, "action": "trace_stop" });
                     if let Some(path) = rest.get(1) {
@@ -1611,7 +1978,7 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                 }),
                 None => Err(ParseError::MissingArguments {
                     context: "trace".to_string(),
-                    usage: "trace <start|stop> [path]",
+                    usage: "trace <start [options]|stop [path]>",
                 }),
             }
         }
@@ -1620,21 +1987,7 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
         "profiler" => {
             const VALID: &[&str] = &["start", "stop"];
             match rest.first().copied() {
-                Some("start") => {
-                    let mut cmd = json!({ "id": id, "action": "profiler_start" });
-                    if let Some(idx) = rest.iter().position(|s| *s == "--categories") {
-                        if let Some(cats) = rest.get(idx + 1) {
-                            let categories: Vec<&str> = cats.split(',').collect();
-                            cmd["categories"] = json!(categories);
-                        } else {
-                            return Err(ParseError::MissingArguments {
-                                context: "profiler start --categories".to_string(),
-                                usage: "--categories <list>",
-                            });
-                        }
-                    }
-                    Ok(cmd)
-                }
+                Some("start") => parse_profiler_start(&rest[1..], &id),
                 Some("stop") => {
                     let mut cmd = json!({ "id": id, "action": "profiler_stop" });
                     if let Some(path) = rest.get(1) {
@@ -1657,45 +2010,9 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
         "record" => {
             const VALID: &[&str] = &["start", "stop", "restart"];
             match rest.first().copied() {
-                Some("start") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "record start".to_string(),
-                        usage: "record start <output.webm> [url]",
-                    })?;
-                    // Optional URL parameter
-                    let url = rest.get(2);
-                    let mut cmd = json!({ "id": id, "action": "recording_start", "path": path });
-                    if let Some(u) = url {
-                        // Add https:// prefix if needed (preserve special schemes)
-                        let url_str = if u.starts_with("http") || u.contains("://") {
-                            u.to_string()
-                        } else {
-                            format!("https://{}", u)
-                        };
-                        cmd["url"] = json!(url_str);
-                    }
-                    Ok(cmd)
-                }
+                Some("start") => parse_record_start("start", &rest[1..], flags, &id),
                 Some("stop") => Ok(json!({ "id": id, "action": "recording_stop" })),
-                Some("restart") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "record restart".to_string(),
-                        usage: "record restart <output.webm> [url]",
-                    })?;
-                    // Optional URL parameter
-                    let url = rest.get(2);
-                    let mut cmd = json!({ "id": id, "action": "recording_restart", "path": path });
-                    if let Some(u) = url {
-                        // Add https:// prefix if needed (preserve special schemes)
-                        let url_str = if u.starts_with("http") || u.contains("://") {
-                            u.to_string()
-                        } else {
-                            format!("https://{}", u)
-                        };
-                        cmd["url"] = json!(url_str);
-                    }
-                    Ok(cmd)
-                }
+                Some("restart") => parse_record_start("restart", &rest[1..], flags, &id),
                 Some(sub) => Err(ParseError::UnknownSubcommand {
                     subcommand: sub.to_string(),
                     valid_options: VALID,
@@ -1706,14 +2023,8 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                 }),
             }
         }
-        "console" => {
-            let clear = rest.contains(&"--clear");
-            Ok(json!({ "id": id, "action": "console", "clear": clear }))
-        }
-        "errors" => {
-            let clear = rest.contains(&"--clear");
-            Ok(json!({ "id": id, "action": "errors", "clear": clear }))
-        }
+        "console" => parse_log_listing("console", &rest, &id),
+        "errors" => parse_log_listing("errors", &rest, &id),
         "highlight" => {
             let sel = rest.first().ok_or_else(|| ParseError::MissingArguments {
                 context: "highlight".to_string(),
@@ -1745,47 +2056,100 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
 
         // === State ===
         "state" => {
-            const VALID: &[&str] = &["save", "load", "list", "clear", "show", "clean", "rename"];
+            const VALID: &[&str] = &[
+                "save",
+                "load",
+                "list",
+                "clear",
+                "show",
+                "clean",
+                "rename",
+                "checkpoint",
+                "restore",
+            ];
             match rest.first().copied() {
                 Some("save") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                    const USAGE: &str = "state save <name|path> [--encrypt|--no-encrypt]";
+                    let mut path: Option<&str> = None;
+                    let mut encrypt: Option<bool> = None;
+                    for arg in &rest[1..] {
+                        match *arg {
+                            "--encrypt" | "--no-encrypt" => {
+                                let value = *arg == "--encrypt";
+                                if encrypt.is_some_and(|e| e != value) {
+                                    return Err(ParseError::InvalidValue {
+                                        message: "Use only one of --encrypt and --no-encrypt"
+                                            .to_string(),
+                                        usage: USAGE,
+                                    });
+                                }
+                                encrypt = Some(value);
+                            }
+                            other if path.is_none() => path = Some(other),
+                            _ => {}
+                        }
+                    }
+                    let path = path.ok_or_else(|| ParseError::MissingArguments {
                         context: "state save".to_string(),
-                        usage: "state save <path>",
+                        usage: USAGE,
                     })?;
-                    Ok(json!({ "id": id, "action": "state_save", "path": path }))
+                    validate_state_arg(path).map_err(|message| ParseError::InvalidValue {
+                        message,
+                        usage: USAGE,
+                    })?;
+                    let mut cmd = json!({ "id": id, "action": "state_save", "path": path });
+                    if let Some(encrypt) = encrypt {
+                        cmd["encrypt"] = json!(encrypt);
+                    }
+                    Ok(cmd)
                 }
                 Some("load") => {
+                    const USAGE: &str = "state load <name|path>";
                     let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
                         context: "state load".to_string(),
-                        usage: "state load <path>",
+                        usage: USAGE,
+                    })?;
+                    validate_state_arg(path).map_err(|message| ParseError::InvalidValue {
+                        message,
+                        usage: USAGE,
                     })?;
                     Ok(json!({ "id": id, "action": "state_load", "path": path }))
                 }
                 Some("list") => Ok(json!({ "id": id, "action": "state_list" })),
                 Some("clear") => {
+                    const USAGE: &str = "state clear <name> | state clear --all";
                     let mut session_name: Option<&str> = None;
                     let mut all = false;
 
-                    let mut i = 1;
-                    while i < rest.len() {
-                        match rest[i] {
-                            "--all" | "-a" => {
-                                all = true;
+                    for arg in &rest[1..] {
+                        match *arg {
+                            "--all" | "-a" => all = true,
+                            other if !other.starts_with('-') && session_name.is_none() => {
+                                session_name = Some(other)
                             }
-                            arg if !arg.starts_with('-') => {
-                                session_name = Some(arg);
+                            other => {
+                                return Err(ParseError::InvalidValue {
+                                    message: format!("Unexpected argument '{}'", other),
+                                    usage: USAGE,
+                                });
                             }
-                            _ => {}
                         }
-                        i += 1;
                     }
-
-                    if let Some(name) = session_name {
-                        if !is_valid_session_name(name) {
-                            return Err(ParseError::InvalidSessionName {
-                                name: name.to_string(),
+                    // A bare `state clear` used to delete every saved state.
+                    match (session_name, all) {
+                        (None, false) => {
+                            return Err(ParseError::MissingArguments {
+                                context: "state clear".to_string(),
+                                usage: USAGE,
+                            });
+                        }
+                        (Some(_), true) => {
+                            return Err(ParseError::InvalidValue {
+                                message: "Use either a state name or --all, not both".to_string(),
+                                usage: USAGE,
                             });
                         }
+                        _ => {}
                     }
 
                     let mut cmd = json!({ "id": id, "action": "state_clear" });
@@ -1793,34 +2157,54 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                         cmd["all"] = json!(true);
                     }
                     if let Some(name) = session_name {
-                        cmd["sessionName"] = json!(name);
+                        cmd["name"] = json!(name);
                     }
                     Ok(cmd)
                 }
                 Some("show") => {
                     let filename = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
                         context: "state show".to_string(),
-                        usage: "state show <filename>",
+                        usage: "state show <name|path>",
                     })?;
                     Ok(json!({ "id": id, "action": "state_show", "path": filename }))
                 }
                 Some("clean") => {
-                    let mut days: Option<i64> = None;
+                    const USAGE: &str = "state clean --older-than <days>";
+                    let mut days: Option<&str> = None;
 
                     let mut i = 1;
                     while i < rest.len() {
-                        if rest[i] == "--older-than" {
-                            if let Some(d) = rest.get(i + 1) {
-                                days = d.parse().ok();
-                                i += 1;
+                        match rest[i] {
+                            "--older-than" => {
+                                days = Some(rest.get(i + 1).ok_or_else(|| {
+                                    ParseError::MissingArguments {
+                                        context: "state clean --older-than".to_string(),
+                                        usage: USAGE,
+                                    }
+                                })?);
+                                i += 2;
+                            }
+                            other => {
+                                return Err(ParseError::InvalidValue {
+                                    message: format!("Unexpected argument '{}'", other),
+                                    usage: USAGE,
+                                });
                             }
                         }
-                        i += 1;
                     }
 
                     let days = days.ok_or_else(|| ParseError::MissingArguments {
                         context: "state clean".to_string(),
-                        usage: "state clean --older-than <days>",
+                        usage: USAGE,
+                    })?;
+                    let days = days.parse::<u64>().ok().filter(|d| *d > 0).ok_or_else(|| {
+                        ParseError::InvalidValue {
+                            message: format!(
+                                "--older-than must be a positive whole number of days, got '{}'",
+                                days
+                            ),
+                            usage: USAGE,
+                        }
                     })?;
 
                     Ok(json!({ "id": id, "action": "state_clean", "days": days }))
@@ -1834,31 +2218,50 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                         context: "state rename".to_string(),
                         usage: "state rename <old-name> <new-name>",
                     })?;
-                    let old_name = old_name.trim_end_matches(".json");
-                    let new_name = new_name.trim_end_matches(".json");
-
-                    if !is_valid_session_name(old_name) {
-                        return Err(ParseError::InvalidSessionName {
-                            name: old_name.to_string(),
-                        });
-                    }
-                    if !is_valid_session_name(new_name) {
-                        return Err(ParseError::InvalidSessionName {
-                            name: new_name.to_string(),
-                        });
-                    }
-
                     Ok(
                         json!({ "id": id, "action": "state_rename", "oldName": old_name, "newName": new_name }),
                     )
                 }
+                Some(sub @ ("checkpoint" | "restore")) => {
+                    let usage = if sub == "checkpoint" {
+                        "state checkpoint [label]"
+                    } else {
+                        "state restore [label] | state restore --list"
+                    };
+                    let mut list = false;
+                    let mut label: Option<&str> = None;
+                    for arg in &rest[1..] {
+                        match *arg {
+                            "--list" if sub == "restore" => list = true,
+                            other if label.is_none() && !other.starts_with('-') => {
+                                label = Some(other)
+                            }
+                            other => {
+                                return Err(ParseError::InvalidValue {
+                                    message: format!("Unexpected argument '{}'", other),
+                                    usage,
+                                });
+                            }
+                        }
+                    }
+                    if list {
+                        return Ok(json!({ "id": id, "action": "state_checkpoints" }));
+                    }
+                    validate_checkpoint_label(label)
+                        .map_err(|message| ParseError::InvalidValue { message, usage })?;
+                    let mut cmd = json!({ "id": id, "action": format!("state_{}", sub) });
+                    if let Some(label) = label {
+                        cmd["label"] = json!(label);
+                    }
+                    Ok(cmd)
+                }
                 Some(sub) => Err(ParseError::UnknownSubcommand {
                     subcommand: sub.to_string(),
                     valid_options: VALID,
                 }),
                 None => Err(ParseError::MissingArguments {
                     context: "state".to_string(),
-                    usage: "state <save|load|list|clear|show|clean|rename> ...",
+                    usage: "state <save|load|list|clear|show|clean|rename|checkpoint|restore> ...",
                 }),
             }
         }
@@ -1900,26 +2303,104 @@ fn parse_command_inner(args: &[String], flags: &Flags) -> Result<Value, ParseErr
                     // List available iOS simulators
                     Ok(json!({ "id": id, "action": "device_list" }))
                 }
+                Some(sub @ ("use" | "boot")) => {
+                    let usage = if sub == "use" {
+                        "device use <name-or-udid>"
+                    } else {
+                        "device boot <name-or-udid>"
+                    };
+                    // Device names contain spaces, so the rest is one argument.
+                    let device = rest[1..].join(" ");
+                    if device.is_empty() {
+                        return Err(ParseError::MissingArguments {
+                            context: format!("device {}", sub),
+                            usage,
+                        });
+                    }
+                    Ok(json!({ "id": id, "action": format!("device_{}", sub), "device": device }))
+                }
                 Some(sub) => Err(ParseError::UnknownSubcommand {
                     subcommand: sub.to_string(),
-                    valid_options: &["list"],
+                    valid_options: &["list", "use", "boot"],
                 }),
             }
         }
 
-        "diff" => parse_diff(&rest, &id),
+        "diff" => parse_diff(&rest, flags, &id),
 
         // === Batch ===
         "batch" => {
-            let bail = rest.contains(&"--bail");
-            let commands: Vec<&str> = rest.iter().filter(|a| **a != "--bail").copied().collect();
-            let mut cmd = json!({ "id": id, "action": "batch", "bail": bail });
+            const BATCH_USAGE: &str = "batch [--bail] [--progress jsonl] [<command>...]";
+            let mut cmd = json!({ "id": id, "action": "batch", "bail": false });
+            let mut commands: Vec<&str> = Vec::new();
+            let mut i = 0;
+            while i < rest.len() {
+                match rest[i] {
+                    "--bail" => cmd["bail"] = json!(true),
+                    "--progress" => {
+                        i += 1;
+                        cmd["progress"] = json!(parse_progress_format(
+                            rest.get(i).copied(),
+                            "batch --progress",
+                            BATCH_USAGE
+                        )?);
+                    }
+                    command => commands.push(command),
+                }
+                i += 1;
+            }
             if !commands.is_empty() {
                 cmd["commands"] = json!(commands);
             }
             Ok(cmd)
         }
 
+        // === Act: batched steps plus a fresh interactive snapshot ===
+        "act" => {
+            const ACT_USAGE: &str =
+                "act [<json> | --file <path>] [--no-final-snapshot] [--progress jsonl]";
+            let mut cmd = json!({ "id": id, "action": "act", "finalSnapshot": true });
+            let mut i = 0;
+            while i < rest.len() {
+                match rest[i] {
+                    "--no-final-snapshot" => cmd["finalSnapshot"] = json!(false),
+                    "--progress" => {
+                        i += 1;
+                        cmd["progress"] = json!(parse_progress_format(
+                            rest.get(i).copied(),
+                            "act --progress",
+                            ACT_USAGE
+                        )?);
+                    }
+                    "--file" => {
+                        i += 1;
+                        let path = rest.get(i).ok_or_else(|| ParseError::MissingArguments {
+                            context: "act --file".to_string(),
+                            usage: ACT_USAGE,
+                        })?;
+                        cmd["file"] = json!(path);
+                    }
+                    // Explicit stdin, same as passing nothing.
+                    "-" => {}
+                    input if cmd.get("input").is_none() => cmd["input"] = json!(input),
+                    extra => {
+                        return Err(ParseError::InvalidValue {
+                            message: format!("Unexpected argument for act: {}", extra),
+                            usage: ACT_USAGE,
+                        })
+                    }
+                }
+                i += 1;
+            }
+            if cmd.get("input").is_some() && cmd.get("file").is_some() {
+                return Err(ParseError::InvalidValue {
+                    message: "Pass the steps inline or with --file, not both".to_string(),
+                    usage: ACT_USAGE,
+                });
+            }
+            Ok(cmd)
+        }
+
         // === React (requires `open --enable react-devtools`) ===
         "react" => parse_react(&rest, &id),
 
@@ -2125,19 +2606,7 @@ fn parse_read(rest: &[&str], id: &str, flags: &Flags) -> Result<Value, ParseErro
     if let Some(url) = url {
         cmd["url"] = json!(url);
     }
-    if let Some(ref headers_json) = flags.headers {
-        let headers = serde_json::from_str::<serde_json::Value>(headers_json).map_err(|_| {
-            ParseError::InvalidValue {
-                message: format!("Invalid JSON for --headers: {}", headers_json),
-                usage: READ_USAGE,
-            }
-        })?;
-        if !headers.is_object() {
-            return Err(ParseError::InvalidValue {
-                message: format!("Invalid JSON object for --headers: {}", headers_json),
-                usage: READ_USAGE,
-            });
-        }
+    if let Some(headers) = headers_payload(flags, Vec::new(), READ_USAGE)? {
         cmd["headers"] = headers;
     }
     if let Some(ref allowed_domains) = flags.allowed_domains {
@@ -2214,7 +2683,101 @@ fn parse_react(rest: &[&str], id: &str) -> Result<Value, ParseError> {
     }
 }
 
-fn parse_diff(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+const OPEN_HEADER_USAGE: &str =
+    "open <url> [--header '<Name>: <value>']... [--headers '{\"Key\": \"Value\"}']";
+
+/// Header name/value pairs in the order they were given.
+type HeaderPairs = Vec<(String, String)>;
+
+/// Pull repeatable `--header "Name: value"` pairs out of a command's args.
+/// Returns the headers and the remaining args.
+fn extract_header_args<'a>(
+    rest: &[&'a str],
+    usage: &'static str,
+) -> Result<(HeaderPairs, Vec<&'a str>), ParseError> {
+    let mut headers = Vec::new();
+    let mut remaining = Vec::new();
+    let mut i = 0;
+    while i < rest.len() {
+        if rest[i] != "--header" {
+            remaining.push(rest[i]);
+            i += 1;
+            continue;
+        }
+        let raw = rest
+            .get(i + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: "--header".to_string(),
+                usage,
+            })?;
+        let (name, value) = raw
+            .split_once(':')
+            .ok_or_else(|| ParseError::InvalidValue {
+                message: format!("Invalid --header {:?}: expected \"Name: value\"", raw),
+                usage,
+            })?;
+        let name = name.trim();
+        crate::flags::validate_header_name(name)
+            .map_err(|message| ParseError::InvalidValue { message, usage })?;
+        headers.push((name.to_string(), value.trim().to_string()));
+        i += 2;
+    }
+    Ok((headers, remaining))
+}
+
+/// Build the `headers` field for a request: the global `--headers` JSON with
+/// per-command `--header` values merged over it for this request only.
+fn headers_payload(
+    flags: &Flags,
+    extra: Vec<(String, String)>,
+    usage: &'static str,
+) -> Result<Option<Value>, ParseError> {
+    let base = match flags.headers {
+        Some(ref raw) => crate::flags::parse_headers_json(raw)
+            .map_err(|message| ParseError::InvalidValue { message, usage })?,
+        None => Vec::new(),
+    };
+    let merged = crate::flags::merge_headers(base, extra);
+    if merged.is_empty() {
+        return Ok(None);
+    }
+    let map = merged
+        .into_iter()
+        .map(|(name, value)| (name, Value::String(value)))
+        .collect::<serde_json::Map<_, _>>();
+    Ok(Some(Value::Object(map)))
+}
+
+/// Snapshot scoping accepts either a CSS selector or a ref from a previous
+/// snapshot (`@e12`, or `@ab12-e12` under `--ref-prefix ab12`). Refs are
+/// forwarded as `ref` so the daemon resolves them through its ref map instead
+/// of `document.querySelector`.
+fn insert_snapshot_scope(
+    obj: &mut serde_json::Map<String, Value>,
+    value: &str,
+    ref_prefix: Option<&str>,
+    usage: &'static str,
+) -> Result<(), ParseError> {
+    let plain = ref_prefix.and_then(|prefix| strip_ref_prefix(value, prefix));
+    let value = plain.as_deref().unwrap_or(value);
+    if let Some(ref_id) = value.strip_prefix('@') {
+        let valid = ref_id.len() > 1
+            && ref_id.starts_with('e')
+            && ref_id[1..].chars().all(|c| c.is_ascii_digit());
+        if !valid {
+            return Err(ParseError::InvalidValue {
+                message: format!("Invalid ref: {} (expected @e<number>, e.g. @e12)", value),
+                usage,
+            });
+        }
+        obj.insert("ref".to_string(), json!(ref_id));
+    } else {
+        obj.insert("selector".to_string(), json!(value));
+    }
+    Ok(())
+}
+
+fn parse_diff(rest: &[&str], flags: &Flags, id: &str) -> Result<Value, ParseError> {
     const VALID: &[&str] = &["snapshot", "screenshot", "url"];
 
     match rest.first().copied() {
@@ -2237,18 +2800,26 @@ fn parse_diff(rest: &[&str], id: &str) -> Result<Value, ParseError> {
                     }
                     "-s" | "--selector" => {
                         if let Some(s) = rest.get(i + 1) {
-                            obj.insert("selector".to_string(), json!(s));
+                            insert_snapshot_scope(
+                                obj,
+                                s,
+                                flags.ref_prefix.as_deref(),
+                                "diff snapshot --selector <selector|@ref>",
+                            )?;
                             i += 1;
                         } else {
                             return Err(ParseError::MissingArguments {
                                 context: "diff snapshot --selector".to_string(),
-                                usage: "diff snapshot --selector <sel>",
+                                usage: "diff snapshot --selector <selector|@ref>",
                             });
                         }
                     }
                     "-c" | "--compact" => {
                         obj.insert("compact".to_string(), json!(true));
                     }
+                    "--stable-refs" => {
+                        obj.insert("stableRefs".to_string(), json!(true));
+                    }
                     "-d" | "--depth" => {
                         if let Some(d) = rest.get(i + 1) {
                             match d.parse::<u32>() {
@@ -2276,13 +2847,13 @@ fn parse_diff(rest: &[&str], id: &str) -> Result<Value, ParseError> {
                     other if other.starts_with('-') => {
                         return Err(ParseError::InvalidValue {
                             message: format!("Unknown flag: {}", other),
-                            usage: "diff snapshot [--baseline <file>] [--selector <sel>] [--compact] [--depth <n>]",
+                            usage: "diff snapshot [--baseline <file>] [--selector <sel>] [--compact] [--depth <n>] [--stable-refs]",
                         });
                     }
                     other => {
                         return Err(ParseError::InvalidValue {
                             message: format!("Unexpected argument: {}", other),
-                            usage: "diff snapshot [--baseline <file>] [--selector <sel>] [--compact] [--depth <n>]",
+                            usage: "diff snapshot [--baseline <file>] [--selector <sel>] [--compact] [--depth <n>] [--stable-refs]",
                         });
                     }
                 }
@@ -2386,22 +2957,10 @@ fn parse_diff(rest: &[&str], id: &str) -> Result<Value, ParseError> {
             Ok(cmd)
         }
         Some("url") => {
-            let url1 = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "diff url".to_string(),
-                usage: "diff url <url1> <url2>",
-            })?;
-            let url2 = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "diff url".to_string(),
-                usage: "diff url <url1> <url2>",
-            })?;
-            let mut cmd = json!({
-                "id": id,
-                "action": "diff_url",
-                "url1": url1,
-                "url2": url2,
-            });
+            let mut cmd = json!({ "id": id, "action": "diff_url" });
             let obj = cmd.as_object_mut().unwrap();
-            let mut i = 3;
+            let mut urls: Vec<&str> = Vec::new();
+            let mut i = 1;
             while i < rest.len() {
                 match rest[i] {
                     "--screenshot" => {
@@ -2412,6 +2971,16 @@ fn parse_diff(rest: &[&str], id: &str) -> Result<Value, ParseError> {
                     }
                     "--wait-until" => {
                         if let Some(val) = rest.get(i + 1) {
+                            if !WAIT_LOAD_STATES.contains(val) {
+                                return Err(ParseError::InvalidValue {
+                                    message: format!(
+                                        "Invalid --wait-until value '{}'. Expected one of: {}",
+                                        val,
+                                        WAIT_LOAD_STATES.join(", ")
+                                    ),
+                                    usage: "diff url <url1> <url2> --wait-until <load|domcontentloaded|networkidle>",
+                                });
+                            }
                             obj.insert("waitUntil".to_string(), json!(val));
                             i += 1;
                         } else {
@@ -2465,6 +3034,7 @@ fn parse_diff(rest: &[&str], id: &str) -> Result<Value, ParseError> {
                             usage: "diff url <url1> <url2> [--screenshot] [--full/-f] [--wait-until <strategy>] [--selector <sel>] [--compact] [--depth <n>]",
                         });
                     }
+                    other if urls.len() < 2 => urls.push(other),
                     other => {
                         return Err(ParseError::InvalidValue {
                             message: format!("Unexpected argument: {}", other),
@@ -2474,6 +3044,15 @@ fn parse_diff(rest: &[&str], id: &str) -> Result<Value, ParseError> {
                 }
                 i += 1;
             }
+            let [url1, url2] = urls[..] else {
+                return Err(ParseError::MissingArguments {
+                    context: "diff url".to_string(),
+                    usage: "diff url <url1> <url2>",
+                });
+            };
+            // Same scheme handling as `open`: bare hosts get https://.
+            obj.insert("url1".to_string(), json!(normalize_navigation_url(url1)));
+            obj.insert("url2".to_string(), json!(normalize_navigation_url(url2)));
             Ok(cmd)
         }
         Some(sub) => Err(ParseError::UnknownSubcommand {
@@ -2500,13 +3079,7 @@ fn parse_get(rest: &[&str], id: &str) -> Result<Value, ParseError> {
             })?;
             Ok(json!({ "id": id, "action": "gettext", "selector": sel }))
         }
-        Some("html") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get html".to_string(),
-                usage: "get html <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "innerhtml", "selector": sel }))
-        }
+        Some("html") => parse_get_html(&rest[1..], id),
         Some("value") => {
             let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
                 context: "get value".to_string(),
@@ -2525,30 +3098,12 @@ fn parse_get(rest: &[&str], id: &str) -> Result<Value, ParseError> {
             })?;
             Ok(json!({ "id": id, "action": "getattribute", "selector": sel, "attribute": attr }))
         }
-        Some("url") => Ok(json!({ "id": id, "action": "url" })),
+        Some("url") => parse_get_url(&rest[1..], id),
         Some("cdp-url") => Ok(json!({ "id": id, "action": "cdp_url" })),
         Some("title") => Ok(json!({ "id": id, "action": "title" })),
-        Some("count") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get count".to_string(),
-                usage: "get count <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "count", "selector": sel }))
-        }
-        Some("box") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get box".to_string(),
-                usage: "get box <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "boundingbox", "selector": sel }))
-        }
-        Some("styles") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get styles".to_string(),
-                usage: "get styles <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "styles", "selector": sel }))
-        }
+        Some("count") => parse_get_count(&rest[1..], id),
+        Some("box") => parse_get_box(&rest[1..], id),
+        Some("styles") => parse_get_styles(&rest[1..], id),
         Some(sub) => Err(ParseError::UnknownSubcommand {
             subcommand: sub.to_string(),
             valid_options: VALID,
@@ -2560,17 +3115,826 @@ fn parse_get(rest: &[&str], id: &str) -> Result<Value, ParseError> {
     }
 }
 
-fn parse_is(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["visible", "enabled", "checked"];
+/// Comparison flags for `get count`, mapped to the op stored in the command.
+/// The comparison runs in the CLI (see `output::apply_count_comparison`).
+const COUNT_COMPARISON_FLAGS: &[(&str, &str)] = &[
+    ("--gt", "gt"),
+    ("--gte", "gte"),
+    ("--lt", "lt"),
+    ("--lte", "lte"),
+    ("--eq", "eq"),
+];
+
+/// Validate the value of `batch`/`act --progress`.
+fn parse_progress_format<'a>(
+    value: Option<&'a str>,
+    context: &str,
+    usage: &'static str,
+) -> Result<&'a str, ParseError> {
+    let value = value.ok_or_else(|| ParseError::MissingArguments {
+        context: context.to_string(),
+        usage,
+    })?;
+    crate::progress::ProgressFormat::parse(value)
+        .map_err(|message| ParseError::InvalidValue { message, usage })?;
+    Ok(value)
+}
 
-    match rest.first().copied() {
-        Some("visible") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "is visible".to_string(),
-                usage: "is visible <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "isvisible", "selector": sel }))
-        }
+const UPLOAD_USAGE: &str = "upload <selector> <files...> [--max-fetch-size <size>] [--keep-temp]";
+
+/// Default cap on a file fetched for `upload <url>`.
+pub const DEFAULT_MAX_FETCH_SIZE: u64 = 50 * 1024 * 1024;
+
+/// `upload <selector> <files...>`. Entries starting with `http://` or
+/// `https://` are fetched by the CLI before the command is sent, so
+/// `maxFetchSize` and `keepTemp` are only set when one is present.
+fn parse_upload(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    let mut positional: Vec<&str> = Vec::new();
+    let mut max_fetch_size = None;
+    let mut keep_temp = false;
+    let mut args = rest.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--max-fetch-size" => {
+                let raw = args.next().ok_or_else(|| ParseError::MissingArguments {
+                    context: "upload --max-fetch-size".to_string(),
+                    usage: UPLOAD_USAGE,
+                })?;
+                let size = parse_byte_size(raw).map_err(|message| ParseError::InvalidValue {
+                    message: format!("Invalid --max-fetch-size: {}", message),
+                    usage: UPLOAD_USAGE,
+                })?;
+                max_fetch_size = Some(size);
+            }
+            "--keep-temp" => keep_temp = true,
+            other => positional.push(other),
+        }
+    }
+    let Some((sel, files)) = positional.split_first() else {
+        return Err(ParseError::MissingArguments {
+            context: "upload".to_string(),
+            usage: UPLOAD_USAGE,
+        });
+    };
+    let mut cmd = json!({ "id": id, "action": "upload", "selector": sel, "files": files });
+    if files.iter().any(|f| is_remote_upload(f)) {
+        cmd["maxFetchSize"] = json!(max_fetch_size.unwrap_or(DEFAULT_MAX_FETCH_SIZE));
+        if keep_temp {
+            cmd["keepTemp"] = json!(true);
+        }
+    }
+    Ok(cmd)
+}
+
+/// Whether an `upload` entry is a URL to fetch rather than a local path.
+pub fn is_remote_upload(entry: &str) -> bool {
+    let lower = entry.get(..8).unwrap_or(entry).to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// Byte count with an optional 1024-based unit: `1048576`, `512K`, `50MB`,
+/// `1GiB`.
+fn parse_byte_size(raw: &str) -> Result<u64, String> {
+    let split = raw
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(split);
+    let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        _ => {
+            return Err(format!(
+                "'{}' has an unknown unit. Use a byte count or K, M, or G",
+                raw
+            ))
+        }
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n > 0.0 && n.is_finite() => Ok((n * multiplier as f64) as u64),
+        _ => Err(format!("'{}' is not a positive size", raw)),
+    }
+}
+
+const DRAG_USAGE: &str =
+    "drag <source> <target> [--source-offset <x,y>] [--target-offset <x,y>] [--html5]";
+
+/// `drag <source> <target>` with optional grab/drop offsets and the HTML5
+/// drag-and-drop event mode.
+fn parse_drag(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    let mut positional: Vec<&str> = Vec::new();
+    let mut cmd = json!({ "id": id, "action": "drag" });
+    let mut args = rest.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--source-offset" | "--target-offset" => {
+                let raw = args.next().ok_or_else(|| ParseError::MissingArguments {
+                    context: format!("drag {}", arg),
+                    usage: DRAG_USAGE,
+                })?;
+                let (x, y) = parse_offset(raw).map_err(|message| ParseError::InvalidValue {
+                    message: format!("Invalid {}: {}", arg, message),
+                    usage: DRAG_USAGE,
+                })?;
+                let key = if *arg == "--source-offset" {
+                    "sourceOffset"
+                } else {
+                    "targetOffset"
+                };
+                cmd[key] = json!({ "x": x, "y": y });
+            }
+            "--html5" => cmd["method"] = json!("html5"),
+            other => positional.push(other),
+        }
+    }
+    let (source, target) = match positional[..] {
+        [source, target] => (source, target),
+        [_, _, extra, ..] => {
+            return Err(ParseError::InvalidValue {
+                message: format!("Unexpected argument '{}'", extra),
+                usage: DRAG_USAGE,
+            })
+        }
+        _ => {
+            return Err(ParseError::MissingArguments {
+                context: "drag".to_string(),
+                usage: DRAG_USAGE,
+            })
+        }
+    };
+    cmd["source"] = json!(source);
+    cmd["target"] = json!(target);
+    Ok(cmd)
+}
+
+/// Parse an `x,y` pixel offset; both parts must be integers.
+fn parse_offset(raw: &str) -> Result<(i64, i64), String> {
+    let parts: Vec<&str> = raw.split(',').map(str::trim).collect();
+    match parts[..] {
+        [x, y] => match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => Ok((x, y)),
+            _ => Err(format!("'{}' must be two integers, e.g. 10,20", raw)),
+        },
+        _ => Err(format!("'{}' must be in the form x,y, e.g. 10,20", raw)),
+    }
+}
+
+/// Parse a duration such as `500ms`, `30s`, `5m`, or `2h` into
+/// milliseconds. A bare number is taken as milliseconds.
+pub fn parse_duration_ms(raw: &str) -> Result<u64, String> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration '{}'. Expected e.g. 30s, 5m, or 2h", raw))?;
+    let scale = match unit {
+        "" | "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => {
+            return Err(format!(
+                "Invalid duration unit '{}' in '{}'. Use ms, s, m, h, or d",
+                unit, raw
+            ))
+        }
+    };
+    number
+        .checked_mul(scale)
+        .ok_or_else(|| format!("Duration '{}' is too large", raw))
+}
+
+/// `console`/`errors [--clear] [--since <duration>] [--after <iso8601>]
+/// [--grep <text>]`, plus `errors --stack`. The filters are applied
+/// client-side by [`crate::output::apply_log_filters`].
+/// `trace start`: each toggle is always sent so the daemon never guesses a
+/// default. Screenshots and snapshots are on, sources off; the last of a
+/// flag and its `--no-` form wins.
+fn parse_trace_start(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str =
+        "trace start [--[no-]screenshots] [--[no-]snapshots] [--[no-]sources] [--title <t>]";
+    let mut cmd = json!({
+        "id": id,
+        "action": "trace_start",
+        "screenshots": true,
+        "snapshots": true,
+        "sources": false,
+    });
+    let mut args = rest.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--screenshots" => cmd["screenshots"] = json!(true),
+            "--no-screenshots" => cmd["screenshots"] = json!(false),
+            "--snapshots" => cmd["snapshots"] = json!(true),
+            "--no-snapshots" => cmd["snapshots"] = json!(false),
+            "--sources" => cmd["sources"] = json!(true),
+            "--no-sources" => cmd["sources"] = json!(false),
+            "--title" => {
+                let title = args.next().ok_or_else(|| ParseError::MissingArguments {
+                    context: "trace start --title".to_string(),
+                    usage: USAGE,
+                })?;
+                cmd["title"] = json!(title);
+            }
+            other => {
+                return Err(ParseError::InvalidValue {
+                    message: format!("Unknown trace start option '{}'", other),
+                    usage: USAGE,
+                })
+            }
+        }
+    }
+    Ok(cmd)
+}
+
+/// `--tail <n>` for the buffered listings: a positive entry count.
+fn parse_tail(raw: Option<&&str>, context: &str, usage: &'static str) -> Result<u64, ParseError> {
+    let raw = raw.ok_or_else(|| ParseError::MissingArguments {
+        context: format!("{} --tail", context),
+        usage,
+    })?;
+    raw.parse::<u64>()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| ParseError::InvalidValue {
+            message: format!("--tail must be a positive integer, got: {}", raw),
+            usage,
+        })
+}
+
+const PROFILER_START_USAGE: &str = "profiler start [--categories <list>]";
+
+/// `profiler start [--categories a,b]`. The list goes to the daemon as an
+/// array; spaces around commas and empty entries are dropped so a list
+/// pasted from a config file still works.
+fn parse_profiler_start(args: &[&str], id: &str) -> Result<Value, ParseError> {
+    let mut cmd = json!({ "id": id, "action": "profiler_start" });
+    let mut i = 0;
+    while i < args.len() {
+        let list = match args[i] {
+            "--categories" => {
+                i += 1;
+                match args.get(i) {
+                    Some(list) if !list.starts_with("--") => *list,
+                    _ => {
+                        return Err(ParseError::MissingArguments {
+                            context: "profiler start --categories".to_string(),
+                            usage: PROFILER_START_USAGE,
+                        })
+                    }
+                }
+            }
+            arg => match arg.strip_prefix("--categories=") {
+                Some(list) => list,
+                None => {
+                    return Err(ParseError::InvalidValue {
+                        message: format!("Unknown argument for profiler start: {}", arg),
+                        usage: PROFILER_START_USAGE,
+                    })
+                }
+            },
+        };
+        let categories: Vec<&str> = list
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .collect();
+        if categories.is_empty() {
+            return Err(ParseError::InvalidValue {
+                message: "--categories needs at least one trace category, e.g. devtools.timeline,v8.execute".to_string(),
+                usage: PROFILER_START_USAGE,
+            });
+        }
+        cmd["categories"] = json!(categories);
+        i += 1;
+    }
+    Ok(cmd)
+}
+
+const CLOSE_USAGE: &str = "close [--save-state [name|path]]";
+
+/// `close [--save-state [name|path]]`. Saving is folded into the close so
+/// the daemon captures storage before teardown starts. Without a path the
+/// state goes to the managed directory under the session name (the
+/// `--session-name` key when set). An explicit save replaces that session's
+/// auto-save on close.
+fn parse_close(args: &[&str], flags: &Flags, id: &str) -> Result<Value, ParseError> {
+    let mut cmd = json!({ "id": id, "action": "close" });
+    let mut i = 0;
+    while i < args.len() {
+        match args[i] {
+            "--save-state" => {
+                let path = match args.get(i + 1) {
+                    Some(p) if !p.starts_with('-') => {
+                        i += 1;
+                        p.to_string()
+                    }
+                    _ => flags
+                        .session_name
+                        .clone()
+                        .unwrap_or_else(|| flags.session.clone()),
+                };
+                cmd["saveStatePath"] = json!(path);
+            }
+            "--all" if args.contains(&"--save-state") => {
+                return Err(ParseError::InvalidValue {
+                    message:
+                        "--save-state saves a single session and cannot be combined with --all"
+                            .to_string(),
+                    usage: CLOSE_USAGE,
+                });
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(cmd)
+}
+
+/// An output path the daemon will write, made absolute since the daemon's
+/// working directory is not ours. A bare filename under `--artifacts-dir`
+/// is left for `parse_command` to place.
+fn output_path(path: &str, flags: &Flags) -> String {
+    let keep_for_artifacts =
+        flags.artifacts_dir.is_some() && crate::artifacts::is_bare_filename(path);
+    match std::env::current_dir() {
+        Ok(cwd) if !keep_for_artifacts => {
+            crate::flags::normalize_path_value(path, &cwd, dirs::home_dir().as_deref())
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// Values `har start --content` accepts. `omit` and `embed` are Playwright's
+/// names for `none` and `all`; `attach` writes bodies to files beside the HAR.
+const HAR_CONTENT_MODES: &[&str] = &["text", "all", "none", "omit", "embed", "attach"];
+
+/// `har start [path] [--content <mode>]` and `har stop [path]`, also reached
+/// as `network har`. A path given to start is where a bare `har stop`
+/// writes.
+fn parse_har(args: &[&str], flags: &Flags, name: &str, id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["start", "stop"];
+    const USAGE: &str =
+        "har <start [path.har] [--content <text|all|none|omit|embed|attach>]|stop [path.har]>";
+    let (sub, action) = match args.first().copied() {
+        Some("start") => ("start", "har_start"),
+        Some("stop") => ("stop", "har_stop"),
+        Some(sub) => {
+            return Err(ParseError::UnknownSubcommand {
+                subcommand: sub.to_string(),
+                valid_options: VALID,
+            })
+        }
+        None => {
+            return Err(ParseError::MissingArguments {
+                context: name.to_string(),
+                usage: USAGE,
+            })
+        }
+    };
+    let mut cmd = json!({ "id": id, "action": action });
+    let mut i = 1;
+    while i < args.len() {
+        match args[i] {
+            "--content" if sub == "start" => {
+                i += 1;
+                let mode = args.get(i).ok_or_else(|| ParseError::MissingArguments {
+                    context: format!("{} start --content", name),
+                    usage: USAGE,
+                })?;
+                if !HAR_CONTENT_MODES.contains(mode) {
+                    return Err(ParseError::InvalidValue {
+                        message: format!(
+                            "Invalid --content mode '{}'. Use one of: {}",
+                            mode,
+                            HAR_CONTENT_MODES.join(", ")
+                        ),
+                        usage: USAGE,
+                    });
+                }
+                cmd["content"] = json!(mode);
+            }
+            path if !path.starts_with('-') && cmd.get("path").is_none() => {
+                if !path.to_ascii_lowercase().ends_with(".har") {
+                    return Err(ParseError::InvalidValue {
+                        message: format!("HAR files must end in .har, got: {}", path),
+                        usage: USAGE,
+                    });
+                }
+                cmd["path"] = json!(output_path(path, flags));
+            }
+            other => {
+                return Err(ParseError::InvalidValue {
+                    message: format!("Unexpected argument for {} {}: {}", name, sub, other),
+                    usage: USAGE,
+                })
+            }
+        }
+        i += 1;
+    }
+    Ok(cmd)
+}
+
+/// `record start|restart <output.webm> [url]`. The recorder only writes
+/// WebM, and the daemon's working directory is not ours, so the path is
+/// checked and made absolute here. With `--artifacts-dir` the path may be
+/// left out or be a bare filename; `parse_command` places it.
+fn parse_record_start(
+    sub: &str,
+    args: &[&str],
+    flags: &Flags,
+    id: &str,
+) -> Result<Value, ParseError> {
+    let (action, usage) = if sub == "start" {
+        ("recording_start", "record start <output.webm> [url]")
+    } else {
+        ("recording_restart", "record restart <output.webm> [url]")
+    };
+    let path = match args.first() {
+        Some(path) => {
+            if !path.to_ascii_lowercase().ends_with(".webm") {
+                return Err(ParseError::InvalidValue {
+                    message: format!(
+                        "Recordings are saved as WebM; the path must end in .webm, got: {}",
+                        path
+                    ),
+                    usage,
+                });
+            }
+            Some(output_path(path, flags))
+        }
+        None if flags.artifacts_dir.is_some() => None,
+        None => {
+            return Err(ParseError::MissingArguments {
+                context: format!("record {} (output path, e.g. ./demo.webm)", sub),
+                usage,
+            })
+        }
+    };
+    let mut cmd = json!({ "id": id, "action": action, "path": path });
+    if let Some(u) = args.get(1) {
+        // Add https:// prefix if needed (preserve special schemes)
+        let url_str = if u.starts_with("http") || u.contains("://") {
+            u.to_string()
+        } else {
+            format!("https://{}", u)
+        };
+        cmd["url"] = json!(url_str);
+    }
+    if let Some(extra) = args.get(2) {
+        return Err(ParseError::InvalidValue {
+            message: format!("Unexpected argument for record {}: {}", sub, extra),
+            usage,
+        });
+    }
+    Ok(cmd)
+}
+
+const SCREENSHOT_USAGE: &str = "screenshot [selector|--selector <sel>] [path] [--full] [--annotate [--interactive-only]] [--clip x,y,w,h]";
+
+/// `screenshot --clip x,y,w,h`: a viewport rectangle in CSS pixels. All four
+/// values are non-negative integers, and the size must not be zero.
+fn parse_screenshot_clip(raw: Option<&str>) -> Result<Value, ParseError> {
+    let raw = raw.ok_or_else(|| ParseError::MissingArguments {
+        context: "screenshot --clip".to_string(),
+        usage: SCREENSHOT_USAGE,
+    })?;
+    let values: Vec<u32> = raw
+        .split(',')
+        .map(|part| part.trim().parse::<u32>())
+        .collect::<Result<_, _>>()
+        .ok()
+        .filter(|values: &Vec<u32>| values.len() == 4)
+        .ok_or_else(|| ParseError::InvalidValue {
+            message: format!(
+                "--clip expects x,y,width,height as non-negative integers, got: {}",
+                raw
+            ),
+            usage: SCREENSHOT_USAGE,
+        })?;
+    let [x, y, width, height] = [values[0], values[1], values[2], values[3]];
+    if width == 0 || height == 0 {
+        return Err(ParseError::InvalidValue {
+            message: format!(
+                "--clip width and height must be greater than 0, got: {}",
+                raw
+            ),
+            usage: SCREENSHOT_USAGE,
+        });
+    }
+    Ok(json!({ "x": x, "y": y, "width": width, "height": height }))
+}
+
+const EVAL_USAGE: &str =
+    "eval <script> | eval -b <base64> | eval --stdin | eval --file <path> [--raw-result]";
+
+/// The `eval` script: the remaining arguments joined with spaces, or one of
+/// the sources for scripts the shell cannot quote: `-b/--base64 <data>`,
+/// `--stdin` (all of it, newlines kept), or `--file <path>`.
+fn read_eval_script(rest: &[&str], stdin: &mut impl Read) -> Result<String, ParseError> {
+    let unexpected = |source: &str, extra: &str| ParseError::InvalidValue {
+        message: format!("unexpected argument '{}' after eval {}", extra, source),
+        usage: EVAL_USAGE,
+    };
+    let script = match rest {
+        [] => {
+            return Err(ParseError::MissingArguments {
+                context: "eval".to_string(),
+                usage: EVAL_USAGE,
+            })
+        }
+        ["-b" | "--base64"] | ["--file"] => {
+            return Err(ParseError::MissingArguments {
+                context: format!("eval {}", rest[0]),
+                usage: EVAL_USAGE,
+            })
+        }
+        ["-b" | "--base64", encoded @ ..] => {
+            // Tolerate the line wrapping `base64` adds by default.
+            let compact: String = encoded
+                .concat()
+                .chars()
+                .filter(|c| !c.is_ascii_whitespace())
+                .collect();
+            let decoded = STANDARD
+                .decode(&compact)
+                .map_err(|e| ParseError::InvalidValue {
+                    message: format!("Invalid base64 encoding: {}", e),
+                    usage: EVAL_USAGE,
+                })?;
+            String::from_utf8(decoded).map_err(|_| ParseError::InvalidValue {
+                message: "Base64 decoded to invalid UTF-8".to_string(),
+                usage: EVAL_USAGE,
+            })?
+        }
+        ["--stdin"] => {
+            let mut script = String::new();
+            stdin
+                .read_to_string(&mut script)
+                .map_err(|e| ParseError::InvalidValue {
+                    message: format!("Could not read script from stdin: {}", e),
+                    usage: EVAL_USAGE,
+                })?;
+            script
+        }
+        ["--stdin", extra, ..] => return Err(unexpected("--stdin", extra)),
+        ["--file", path] => {
+            std::fs::read_to_string(path).map_err(|e| ParseError::InvalidValue {
+                message: format!("Could not read script file '{}': {}", path, e),
+                usage: EVAL_USAGE,
+            })?
+        }
+        ["--file", _, extra, ..] => return Err(unexpected("--file", extra)),
+        parts => parts.join(" "),
+    };
+    if script.trim().is_empty() {
+        return Err(ParseError::InvalidValue {
+            message: "eval script is empty".to_string(),
+            usage: EVAL_USAGE,
+        });
+    }
+    Ok(script)
+}
+
+fn parse_log_listing(action: &str, rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    let usage = if action == "console" {
+        "console [--clear] [--tail <n>] [--since <duration>] [--after <iso8601>] [--grep <text>]"
+    } else {
+        "errors [--clear] [--tail <n>] [--since <duration>] [--after <iso8601>] [--grep <text>] [--stack]"
+    };
+    let mut cmd = json!({ "id": id, "action": action, "clear": false });
+    let mut args = rest.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--clear" => cmd["clear"] = json!(true),
+            "--tail" => cmd["tail"] = json!(parse_tail(args.next(), action, usage)?),
+            "--since" => {
+                let raw = args.next().ok_or_else(|| ParseError::MissingArguments {
+                    context: format!("{} --since", action),
+                    usage,
+                })?;
+                let ms = parse_duration_ms(raw)
+                    .map_err(|message| ParseError::InvalidValue { message, usage })?;
+                cmd["sinceMs"] = json!(ms);
+            }
+            "--after" => {
+                let raw = args.next().ok_or_else(|| ParseError::MissingArguments {
+                    context: format!("{} --after", action),
+                    usage,
+                })?;
+                let after = chrono::DateTime::parse_from_rfc3339(raw).map_err(|_| {
+                    ParseError::InvalidValue {
+                        message: format!(
+                            "Invalid --after timestamp '{}'. Expected ISO 8601 with a time zone, e.g. 2026-01-31T14:05:00Z",
+                            raw
+                        ),
+                        usage,
+                    }
+                })?;
+                cmd["afterMs"] = json!(after.timestamp_millis());
+            }
+            "--grep" => {
+                let pattern = args.next().ok_or_else(|| ParseError::MissingArguments {
+                    context: format!("{} --grep", action),
+                    usage,
+                })?;
+                cmd["grep"] = json!(pattern);
+            }
+            "--stack" if action == "errors" => cmd["stack"] = json!(true),
+            _ => {}
+        }
+    }
+    Ok(cmd)
+}
+
+fn parse_get_html(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "get html <selector> [--outer] [--strip-attrs] [--max-depth <n>]";
+    let mut cmd = json!({ "id": id, "action": "innerhtml" });
+    let mut args = rest.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--outer" => cmd["outer"] = json!(true),
+            "--strip-attrs" => cmd["stripAttrs"] = json!(true),
+            "--max-depth" => {
+                let raw = args.next().ok_or_else(|| ParseError::MissingArguments {
+                    context: "get html --max-depth".to_string(),
+                    usage: USAGE,
+                })?;
+                let depth: u32 = raw.parse().map_err(|_| ParseError::InvalidValue {
+                    message: format!("--max-depth must be a non-negative integer, got: {}", raw),
+                    usage: USAGE,
+                })?;
+                cmd["maxDepth"] = json!(depth);
+            }
+            _ if cmd.get("selector").is_none() => cmd["selector"] = json!(arg),
+            _ => {
+                return Err(ParseError::InvalidValue {
+                    message: format!("Unexpected argument for get html: {}", arg),
+                    usage: USAGE,
+                })
+            }
+        }
+    }
+    if cmd.get("selector").is_none() {
+        return Err(ParseError::MissingArguments {
+            context: "get html".to_string(),
+            usage: USAGE,
+        });
+    }
+    Ok(cmd)
+}
+
+/// `get url [--parts] [--query <name>]`. The daemon only returns the URL;
+/// the CLI splits it (see `output::apply_url_parts`).
+fn parse_get_url(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "get url [--parts] [--query <name>]";
+    let mut cmd = json!({ "id": id, "action": "url" });
+    let mut args = rest.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--parts" => cmd["parts"] = json!(true),
+            "--query" => {
+                let name = args.next().ok_or_else(|| ParseError::MissingArguments {
+                    context: "get url --query".to_string(),
+                    usage: USAGE,
+                })?;
+                cmd["queryParam"] = json!(name);
+            }
+            _ => {
+                return Err(ParseError::InvalidValue {
+                    message: format!("Unexpected argument for get url: {}", arg),
+                    usage: USAGE,
+                })
+            }
+        }
+    }
+    Ok(cmd)
+}
+
+fn parse_get_box(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "get box <selector> [--viewport-relative] [--visibility]";
+    let mut cmd = json!({ "id": id, "action": "boundingbox" });
+    for arg in rest {
+        match *arg {
+            "--viewport-relative" => cmd["viewportRelative"] = json!(true),
+            "--visibility" => cmd["visibility"] = json!(true),
+            _ if cmd.get("selector").is_none() => cmd["selector"] = json!(arg),
+            _ => {
+                return Err(ParseError::InvalidValue {
+                    message: format!("Unexpected argument for get box: {}", arg),
+                    usage: USAGE,
+                })
+            }
+        }
+    }
+    if cmd.get("selector").is_none() {
+        return Err(ParseError::MissingArguments {
+            context: "get box".to_string(),
+            usage: USAGE,
+        });
+    }
+    Ok(cmd)
+}
+
+fn parse_get_styles(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "get styles <selector> [--properties <name,...>]";
+    let mut cmd = json!({ "id": id, "action": "styles" });
+    let mut args = rest.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--properties" => {
+                let raw = args.next().ok_or_else(|| ParseError::MissingArguments {
+                    context: "get styles --properties".to_string(),
+                    usage: USAGE,
+                })?;
+                let properties: Vec<&str> = raw
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .collect();
+                if properties.is_empty() {
+                    return Err(ParseError::InvalidValue {
+                        message: "--properties needs at least one CSS property name".to_string(),
+                        usage: USAGE,
+                    });
+                }
+                cmd["properties"] = json!(properties);
+            }
+            _ if cmd.get("selector").is_none() => cmd["selector"] = json!(arg),
+            _ => {
+                return Err(ParseError::InvalidValue {
+                    message: format!("Unexpected argument for get styles: {}", arg),
+                    usage: USAGE,
+                })
+            }
+        }
+    }
+    if cmd.get("selector").is_none() {
+        return Err(ParseError::MissingArguments {
+            context: "get styles".to_string(),
+            usage: USAGE,
+        });
+    }
+    Ok(cmd)
+}
+
+fn parse_get_count(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const USAGE: &str = "get count <selector> [--visible-only] [--gt|--gte|--lt|--lte|--eq <n>]";
+    let mut cmd = json!({ "id": id, "action": "count" });
+    let mut i = 0;
+    while i < rest.len() {
+        let arg = rest[i];
+        if arg == "--visible-only" {
+            cmd["visibleOnly"] = json!(true);
+        } else if let Some((flag, op)) = COUNT_COMPARISON_FLAGS.iter().find(|(f, _)| *f == arg) {
+            if cmd.get("comparison").is_some() {
+                return Err(ParseError::InvalidValue {
+                    message: "Use only one of --gt, --gte, --lt, --lte, --eq".to_string(),
+                    usage: USAGE,
+                });
+            }
+            i += 1;
+            let value = rest
+                .get(i)
+                .and_then(|v| v.parse::<u64>().ok())
+                .ok_or_else(|| ParseError::InvalidValue {
+                    message: format!("{} requires a non-negative integer", flag),
+                    usage: USAGE,
+                })?;
+            cmd["comparison"] = json!({ "op": op, "value": value });
+        } else if cmd.get("selector").is_none() {
+            cmd["selector"] = json!(arg);
+        } else {
+            return Err(ParseError::InvalidValue {
+                message: format!("Unexpected argument for get count: {}", arg),
+                usage: USAGE,
+            });
+        }
+        i += 1;
+    }
+    if cmd.get("selector").is_none() {
+        return Err(ParseError::MissingArguments {
+            context: "get count".to_string(),
+            usage: USAGE,
+        });
+    }
+    Ok(cmd)
+}
+
+fn parse_is(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["visible", "enabled", "checked"];
+
+    match rest.first().copied() {
+        Some("visible") => {
+            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
+                context: "is visible".to_string(),
+                usage: "is visible <selector>",
+            })?;
+            Ok(json!({ "id": id, "action": "isvisible", "selector": sel }))
+        }
         Some("enabled") => {
             let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
                 context: "is enabled".to_string(),
@@ -2597,6 +3961,206 @@ fn parse_is(rest: &[&str], id: &str) -> Result<Value, ParseError> {
 }
 
 fn parse_find(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+    let locator = rest.first().ok_or_else(|| ParseError::MissingArguments {
+        context: "find".to_string(),
+        usage: "find <locator> <value> [action] [text]",
+    })?;
+
+    // Options may appear anywhere after the locator; pull them out so the
+    // positional parsing below only sees the value, action, and text.
+    let mut positional: Vec<&str> = vec![locator];
+    let mut options = FindOptions::default();
+    let mut i = 1;
+    while i < rest.len() {
+        let value = || {
+            rest.get(i + 1)
+                .copied()
+                .ok_or_else(|| ParseError::MissingArguments {
+                    context: format!("find {} {}", locator, rest[i]),
+                    usage: FIND_USAGE,
+                })
+        };
+        match rest[i] {
+            "--exact" => {
+                options.exact = true;
+                i += 1;
+                continue;
+            }
+            "--wait-for-popup" => {
+                options.popup.wait = true;
+                i += 1;
+                continue;
+            }
+            "--popup-timeout" => {
+                options.popup.timeout_ms = Some(parse_popup_timeout(rest.get(i + 1), FIND_USAGE)?)
+            }
+            "--name" => options.name = Some(value()?),
+            "--wait" => {
+                let raw = value()?;
+                let ms = raw.parse::<u64>().map_err(|_| ParseError::InvalidValue {
+                    message: format!(
+                        "--wait must be a non-negative number of milliseconds, got: {}",
+                        raw
+                    ),
+                    usage: FIND_USAGE,
+                })?;
+                options.wait_ms = ms;
+            }
+            "--state" => {
+                let raw = value()?;
+                if !FIND_STATES.contains(&raw) {
+                    return Err(ParseError::InvalidValue {
+                        message: format!(
+                            "--state must be one of {}, got: {}",
+                            FIND_STATES.join(", "),
+                            raw
+                        ),
+                        usage: FIND_USAGE,
+                    });
+                }
+                options.state = Some(raw);
+            }
+            token => {
+                positional.push(token);
+                i += 1;
+                continue;
+            }
+        }
+        i += 2;
+    }
+    let cmd = parse_find_positional(&positional, id, &options)?;
+    if options.popup.wait && cmd.get("subaction").and_then(|v| v.as_str()) != Some("click") {
+        return Err(ParseError::InvalidValue {
+            message: "--wait-for-popup only applies to the click action".to_string(),
+            usage: FIND_USAGE,
+        });
+    }
+    options.popup.apply(options.apply(cmd), FIND_USAGE)
+}
+
+const KEYBOARD_TYPE_USAGE: &str = "keyboard type <text> [--delay <ms>]";
+
+const SELECTOR_USAGE: &str = "<selector>: @ref, CSS, xpath=<path>, or @ref >> <CSS|xpath=> to match inside a ref; CSS may end in :has-text(\"x\"), :text(\"x\"), :text-is(\"x\"), or :visible, or be wrapped in :nth-match(<selector>, n)";
+
+const CLICK_USAGE: &str = "click <selector> [--new-tab] [--wait-for-popup] [--popup-timeout <ms>]";
+
+/// `click --wait-for-popup` and `find ... click --wait-for-popup`: wait for
+/// the page the click opens and switch to it.
+#[derive(Default)]
+struct PopupOptions {
+    wait: bool,
+    timeout_ms: Option<u64>,
+}
+
+impl PopupOptions {
+    /// Add `waitForPopup` and `popupTimeout` to a parsed click. A timeout
+    /// without `--wait-for-popup` would bound nothing, so it is rejected.
+    fn apply(&self, mut cmd: Value, usage: &'static str) -> Result<Value, ParseError> {
+        if !self.wait {
+            if self.timeout_ms.is_some() {
+                return Err(ParseError::InvalidValue {
+                    message: "--popup-timeout requires --wait-for-popup".to_string(),
+                    usage,
+                });
+            }
+            return Ok(cmd);
+        }
+        cmd["waitForPopup"] = json!(true);
+        if let Some(ms) = self.timeout_ms {
+            cmd["popupTimeout"] = json!(ms);
+        }
+        Ok(cmd)
+    }
+}
+
+fn parse_popup_timeout(raw: Option<&&str>, usage: &'static str) -> Result<u64, ParseError> {
+    let raw = raw.ok_or_else(|| ParseError::MissingArguments {
+        context: "--popup-timeout".to_string(),
+        usage,
+    })?;
+    raw.parse::<u64>()
+        .ok()
+        .filter(|ms| *ms > 0)
+        .ok_or_else(|| ParseError::InvalidValue {
+            message: format!(
+                "--popup-timeout must be a positive number of milliseconds, got: {}",
+                raw
+            ),
+            usage,
+        })
+}
+
+const WAIT_TEXT_USAGE: &str = "wait --text <text> [-s <scope>] [--exact] [--not] [--timeout <ms>]";
+
+const WAIT_USAGE: &str = "wait <selector|ms|--url|--load|--fn|--text|--download> [--timeout <ms>]";
+
+const WAIT_LOAD_USAGE: &str = "wait --load <load|domcontentloaded|networkidle>";
+
+/// Lifecycle states `wait --load` can wait for.
+const WAIT_LOAD_STATES: &[&str] = &["load", "domcontentloaded", "networkidle"];
+
+/// The exclusive `wait` modes named in `rest`, in order of appearance.
+/// Flag values are skipped so `wait --text -d` waits for the text "-d".
+fn wait_modes(rest: &[&str]) -> Vec<&'static str> {
+    let mut modes = Vec::new();
+    let mut i = 0;
+    while i < rest.len() {
+        let (mode, takes_value) = match rest[i] {
+            "--url" | "-u" => (Some("--url"), true),
+            "--load" | "-l" => (Some("--load"), true),
+            "--fn" | "-f" => (Some("--fn"), true),
+            "--text" | "-t" => (Some("--text"), true),
+            "--selector" | "-s" => (None, true),
+            "--download" | "-d" => (
+                Some("--download"),
+                rest.get(i + 1).is_some_and(|p| !p.starts_with("--")),
+            ),
+            _ => (None, false),
+        };
+        if let Some(mode) = mode.filter(|m| !modes.contains(m)) {
+            modes.push(mode);
+        }
+        i += if takes_value { 2 } else { 1 };
+    }
+    modes
+}
+
+const FIND_USAGE: &str =
+    "find <locator> <value> [action] [text] [--name <name>] [--exact] [--wait <ms>] [--state visible|attached] [--wait-for-popup] [--popup-timeout <ms>]";
+
+/// `find --state` values: wait until the element is in the DOM (the
+/// default) or also visible.
+const FIND_STATES: &[&str] = &["visible", "attached"];
+
+/// The `find` options that can appear anywhere after the locator.
+#[derive(Default)]
+struct FindOptions<'a> {
+    name: Option<&'a str>,
+    exact: bool,
+    wait_ms: u64,
+    state: Option<&'a str>,
+    popup: PopupOptions,
+}
+
+impl FindOptions<'_> {
+    /// Add `waitFor` and `state` to a parsed find command. Both are omitted
+    /// at their defaults so the daemon command is unchanged without them.
+    fn apply(&self, mut cmd: Value) -> Value {
+        if self.wait_ms > 0 {
+            cmd["waitFor"] = json!(self.wait_ms);
+        }
+        if let Some(state) = self.state {
+            cmd["state"] = json!(state);
+        }
+        cmd
+    }
+}
+
+fn parse_find_positional(
+    rest: &[&str],
+    id: &str,
+    options: &FindOptions,
+) -> Result<Value, ParseError> {
     const VALID: &[&str] = &[
         "role",
         "text",
@@ -2609,11 +4173,7 @@ fn parse_find(rest: &[&str], id: &str) -> Result<Value, ParseError> {
         "last",
         "nth",
     ];
-
-    let locator = rest.first().ok_or_else(|| ParseError::MissingArguments {
-        context: "find".to_string(),
-        usage: "find <locator> <value> [action] [text]",
-    })?;
+    let locator = &rest[0];
 
     match *locator {
         "role" | "text" | "label" | "placeholder" | "alt" | "title" | "testid" | "first"
@@ -2634,41 +4194,11 @@ fn parse_find(rest: &[&str], id: &str) -> Result<Value, ParseError> {
                 },
             })?;
             let subaction = rest.get(2).unwrap_or(&"click");
-            let mut name: Option<&str> = None;
-            let mut exact = false;
-            let mut fill_parts: Vec<&str> = Vec::new();
-
-            if rest.len() > 3 {
-                let mut i = 3;
-                while i < rest.len() {
-                    match rest[i] {
-                        "--exact" => {
-                            exact = true;
-                            i += 1;
-                        }
-                        "--name" => {
-                            let n =
-                                rest.get(i + 1)
-                                    .ok_or_else(|| ParseError::MissingArguments {
-                                        context: format!("find {}", locator),
-                                        usage:
-                                            "find role <role> [action] [--name <name>] [--exact]",
-                                    })?;
-                            name = Some(*n);
-                            i += 2;
-                        }
-                        token => {
-                            fill_parts.push(token);
-                            i += 1;
-                        }
-                    }
-                }
-            }
-
-            let fill_value = if fill_parts.is_empty() {
-                None
+            let (name, exact) = (options.name, options.exact);
+            let fill_value = if rest.len() > 3 {
+                Some(rest[3..].join(" "))
             } else {
-                Some(fill_parts.join(" "))
+                None
             };
 
             match *locator {
@@ -2824,6 +4354,361 @@ fn parse_mouse(rest: &[&str], id: &str) -> Result<Value, ParseError> {
     }
 }
 
+const SET_CREDENTIALS_USAGE: &str =
+    "set credentials <username> <password|--password-stdin> [--origin <origin>] | set credentials clear [--origin <origin>]";
+
+/// Normalize an `--origin` value the way origin-scoped headers are keyed:
+/// scheme, host, and port of an http(s) URL. A path is allowed and dropped.
+fn parse_origin(raw: &str) -> Result<String, String> {
+    let url = url::Url::parse(raw).map_err(|e| format!("invalid origin '{}': {}", raw, e))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(format!(
+            "invalid origin '{}': expected http(s)://host[:port]",
+            raw
+        ));
+    }
+    Ok(url.origin().ascii_serialization())
+}
+
+/// An origin whose certificate errors may be ignored: https only, since
+/// there is no certificate to check on plain http.
+pub fn parse_https_origin(raw: &str) -> Result<String, String> {
+    let origin = parse_origin(raw)?;
+    if !origin.starts_with("https://") {
+        return Err(format!(
+            "invalid origin '{}': expected https://host[:port]",
+            raw
+        ));
+    }
+    Ok(origin)
+}
+
+/// `set credentials <user> <pass> [--origin <o>] [--password-stdin]` and
+/// `set credentials clear [--origin <o>]`.
+fn parse_set_credentials(args: &[&str], id: &str) -> Result<Value, ParseError> {
+    let mut positional = Vec::new();
+    let mut origin = None;
+    let mut password_stdin = false;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i] {
+            "--origin" => {
+                let raw = args
+                    .get(i + 1)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: "set credentials --origin".to_string(),
+                        usage: SET_CREDENTIALS_USAGE,
+                    })?;
+                origin = Some(
+                    parse_origin(raw).map_err(|message| ParseError::InvalidValue {
+                        message,
+                        usage: SET_CREDENTIALS_USAGE,
+                    })?,
+                );
+                i += 1;
+            }
+            "--password-stdin" => password_stdin = true,
+            flag if flag.starts_with("--") => {
+                return Err(ParseError::InvalidValue {
+                    message: format!("unknown flag '{}' for set credentials", flag),
+                    usage: SET_CREDENTIALS_USAGE,
+                });
+            }
+            arg => positional.push(arg),
+        }
+        i += 1;
+    }
+
+    let mut cmd = match (positional.as_slice(), password_stdin) {
+        (["clear"], false) => json!({ "id": id, "action": "credentials", "clear": true }),
+        ([user], true) => {
+            json!({ "id": id, "action": "credentials", "username": user, "passwordStdin": true })
+        }
+        ([user, pass], false) => {
+            json!({ "id": id, "action": "credentials", "username": user, "password": pass })
+        }
+        ([_, _], true) => {
+            return Err(ParseError::InvalidValue {
+                message: "set credentials takes a password argument or --password-stdin, not both"
+                    .to_string(),
+                usage: SET_CREDENTIALS_USAGE,
+            });
+        }
+        ([], _) | ([_], false) => {
+            return Err(ParseError::MissingArguments {
+                context: "set credentials".to_string(),
+                usage: SET_CREDENTIALS_USAGE,
+            });
+        }
+        _ => {
+            return Err(ParseError::InvalidValue {
+                message: "too many arguments for set credentials".to_string(),
+                usage: SET_CREDENTIALS_USAGE,
+            });
+        }
+    };
+    if let Some(origin) = origin {
+        cmd["origin"] = json!(origin);
+    }
+    Ok(cmd)
+}
+
+const CONNECT_USAGE: &str = "connect <port|url>";
+
+/// The CDP fields of the launch that `connect <port|url>` and `--cdp` send:
+/// `cdpUrl` for a `ws(s)://` or `http(s)://` endpoint, `cdpPort` for a port.
+/// Shared so the two entry points accept and reject the same values.
+pub fn cdp_endpoint_launch(endpoint: &str) -> Result<Value, String> {
+    const SCHEMES: &[&str] = &["ws://", "wss://", "http://", "https://"];
+    if SCHEMES.iter().any(|s| endpoint.starts_with(s)) {
+        return Ok(json!({ "cdpUrl": endpoint }));
+    }
+    match endpoint.parse::<u32>() {
+        Ok(0) => Err("Invalid CDP port: port must be greater than 0".to_string()),
+        Ok(p) if p > 65535 => Err(format!(
+            "Invalid CDP port: {} is out of range (valid range: 1-65535)",
+            p
+        )),
+        Ok(p) => Ok(json!({ "cdpPort": p })),
+        Err(_) if endpoint.contains("://") => Err(format!(
+            "Invalid CDP URL: '{}' must use ws://, wss://, http://, or https://",
+            endpoint
+        )),
+        Err(_) if endpoint.contains([':', '/', '.']) => Err(format!(
+            "Invalid CDP URL: '{}' has no scheme. Add one, e.g. ws://{} or http://{}",
+            endpoint, endpoint, endpoint
+        )),
+        Err(_) => Err(format!(
+            "Invalid CDP value: '{}' is not a valid port number or URL",
+            endpoint
+        )),
+    }
+}
+
+/// The 1-65535 range used by `stream start --port`; `connect` and `--cdp`
+/// check theirs in [`cdp_endpoint_launch`].
+fn check_port_range(port: u32, usage: &'static str) -> Result<u16, ParseError> {
+    match port {
+        0 => Err(ParseError::InvalidValue {
+            message: "Invalid port: port must be greater than 0".to_string(),
+            usage,
+        }),
+        p if p > 65535 => Err(ParseError::InvalidValue {
+            message: format!("Invalid port: {} is out of range (valid range: 1-65535)", p),
+            usage,
+        }),
+        p => Ok(p as u16),
+    }
+}
+
+const STREAM_START_USAGE: &str = "stream start [--port <port>] [--quality <1-100>] [--fps <1-60>]";
+
+/// `stream start` (and its older name `stream enable`), with `action`
+/// telling the daemon which was used.
+fn parse_stream_start(args: &[&str], id: &str, action: &str) -> Result<Value, ParseError> {
+    let mut cmd = json!({ "id": id, "action": action });
+    let mut i = 0;
+    while i < args.len() {
+        let flag = args[i];
+        let (key, max) = match flag {
+            "--port" => ("port", 65535),
+            "--quality" => ("quality", 100),
+            "--fps" => ("fps", 60),
+            other => {
+                return Err(ParseError::InvalidValue {
+                    message: format!(
+                        "Unknown flag for stream {}: {}",
+                        stream_subcommand(action),
+                        other
+                    ),
+                    usage: STREAM_START_USAGE,
+                })
+            }
+        };
+        let value = args
+            .get(i + 1)
+            .ok_or_else(|| ParseError::MissingArguments {
+                context: format!("stream {} {}", stream_subcommand(action), flag),
+                usage: STREAM_START_USAGE,
+            })?;
+        let number = value.parse::<u32>().map_err(|_| ParseError::InvalidValue {
+            message: format!("Invalid {}: '{}' is not a valid integer", key, value),
+            usage: STREAM_START_USAGE,
+        })?;
+        if key == "port" {
+            cmd[key] = json!(check_port_range(number, STREAM_START_USAGE)?);
+        } else if number == 0 || number > max {
+            return Err(ParseError::InvalidValue {
+                message: format!("Invalid {}: {} (expected 1-{})", key, number, max),
+                usage: STREAM_START_USAGE,
+            });
+        } else {
+            cmd[key] = json!(number);
+        }
+        i += 2;
+    }
+    Ok(cmd)
+}
+
+fn stream_subcommand(action: &str) -> &str {
+    action.strip_prefix("stream_").unwrap_or(action)
+}
+
+const SET_TLS_USAGE: &str = "set tls ignore-errors <on|off> | set tls allow-origin <https-origin>";
+
+/// `set tls ignore-errors on|off` and `set tls allow-origin <origin>`.
+fn parse_set_tls(args: &[&str], id: &str) -> Result<Value, ParseError> {
+    const VALID: &[&str] = &["ignore-errors", "allow-origin"];
+    let invalid = |message: String| ParseError::InvalidValue {
+        message,
+        usage: SET_TLS_USAGE,
+    };
+    match args {
+        ["ignore-errors", value] => {
+            let ignore = match *value {
+                "on" | "true" => true,
+                "off" | "false" => false,
+                other => {
+                    return Err(invalid(format!(
+                        "set tls ignore-errors takes on or off, got: {}",
+                        other
+                    )))
+                }
+            };
+            Ok(json!({ "id": id, "action": "tls_config", "ignoreErrors": ignore }))
+        }
+        ["allow-origin", origin] => {
+            let origin = parse_https_origin(origin).map_err(invalid)?;
+            Ok(json!({ "id": id, "action": "tls_config", "allowOrigin": origin }))
+        }
+        [sub, _, _, ..] if VALID.contains(sub) => {
+            Err(invalid(format!("too many arguments for set tls {}", sub)))
+        }
+        [sub, ..] if !VALID.contains(sub) => Err(ParseError::UnknownSubcommand {
+            subcommand: sub.to_string(),
+            valid_options: VALID,
+        }),
+        _ => Err(ParseError::MissingArguments {
+            context: "set tls".to_string(),
+            usage: SET_TLS_USAGE,
+        }),
+    }
+}
+
+const SET_TIMEOUT_USAGE: &str = "set timeout <ms> [--navigation <ms>] | set timeout show";
+
+/// Bounds for `set timeout` values: short enough to fail fast, long enough
+/// for a slow checkout, and never so long a stuck wait looks like a hang.
+const MIN_SET_TIMEOUT_MS: u64 = 100;
+const MAX_SET_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+
+fn parse_timeout_ms(raw: &str, what: &str) -> Result<u64, ParseError> {
+    raw.parse::<u64>()
+        .ok()
+        .filter(|ms| (MIN_SET_TIMEOUT_MS..=MAX_SET_TIMEOUT_MS).contains(ms))
+        .ok_or_else(|| ParseError::InvalidValue {
+            message: format!(
+                "{} must be between {} and {} milliseconds, got: {}",
+                what, MIN_SET_TIMEOUT_MS, MAX_SET_TIMEOUT_MS, raw
+            ),
+            usage: SET_TIMEOUT_USAGE,
+        })
+}
+
+fn parse_set_timeout(args: &[&str], id: &str) -> Result<Value, ParseError> {
+    if args == ["show"] {
+        return Ok(json!({ "id": id, "action": "timeout" }));
+    }
+    let mut default_ms = None;
+    let mut navigation_ms = None;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i] {
+            "--navigation" => {
+                let raw = args
+                    .get(i + 1)
+                    .ok_or_else(|| ParseError::MissingArguments {
+                        context: "set timeout --navigation".to_string(),
+                        usage: SET_TIMEOUT_USAGE,
+                    })?;
+                navigation_ms = Some(parse_timeout_ms(raw, "--navigation")?);
+                i += 1;
+            }
+            raw if default_ms.is_none() && !raw.starts_with("--") => {
+                default_ms = Some(parse_timeout_ms(raw, "timeout")?);
+            }
+            other => {
+                return Err(ParseError::InvalidValue {
+                    message: format!("unexpected argument '{}' for set timeout", other),
+                    usage: SET_TIMEOUT_USAGE,
+                });
+            }
+        }
+        i += 1;
+    }
+    if default_ms.is_none() && navigation_ms.is_none() {
+        return Err(ParseError::MissingArguments {
+            context: "set timeout".to_string(),
+            usage: SET_TIMEOUT_USAGE,
+        });
+    }
+    let mut cmd = json!({ "id": id, "action": "timeout" });
+    if let Some(ms) = default_ms {
+        cmd["defaultTimeout"] = json!(ms);
+    }
+    if let Some(ms) = navigation_ms {
+        cmd["navigationTimeout"] = json!(ms);
+    }
+    Ok(cmd)
+}
+
+const SET_BUFFER_USAGE: &str = "set buffer <console|errors|requests> <max-entries>";
+
+/// `set buffer <console|errors|requests> <max-entries>`: cap how many
+/// entries the daemon keeps; the oldest are dropped first.
+fn parse_set_buffer(args: &[&str], id: &str) -> Result<Value, ParseError> {
+    const BUFFERS: &[&str] = &["console", "errors", "requests"];
+    let (buffer, raw) = match args {
+        [buffer, raw] => (*buffer, *raw),
+        [_, _, extra, ..] => {
+            return Err(ParseError::InvalidValue {
+                message: format!("unexpected argument '{}' for set buffer", extra),
+                usage: SET_BUFFER_USAGE,
+            })
+        }
+        _ => {
+            return Err(ParseError::MissingArguments {
+                context: "set buffer".to_string(),
+                usage: SET_BUFFER_USAGE,
+            })
+        }
+    };
+    if !BUFFERS.contains(&buffer) {
+        return Err(ParseError::InvalidValue {
+            message: format!(
+                "unknown buffer '{}'. Valid buffers: {}",
+                buffer,
+                BUFFERS.join(", ")
+            ),
+            usage: SET_BUFFER_USAGE,
+        });
+    }
+    let max_entries =
+        raw.parse::<u64>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| ParseError::InvalidValue {
+                message: format!("max-entries must be a positive integer, got: {}", raw),
+                usage: SET_BUFFER_USAGE,
+            })?;
+    Ok(json!({
+        "id": id,
+        "action": "buffer_config",
+        "buffer": buffer,
+        "maxEntries": max_entries,
+    }))
+}
+
 fn parse_set(rest: &[&str], id: &str) -> Result<Value, ParseError> {
     const VALID: &[&str] = &[
         "viewport",
@@ -2835,6 +4720,9 @@ fn parse_set(rest: &[&str], id: &str) -> Result<Value, ParseError> {
         "credentials",
         "auth",
         "media",
+        "timeout",
+        "tls",
+        "buffer",
     ];
 
     match rest.first().copied() {
@@ -2921,17 +4809,10 @@ fn parse_set(rest: &[&str], id: &str) -> Result<Value, ParseError> {
                 })?;
             Ok(json!({ "id": id, "action": "headers", "headers": headers }))
         }
-        Some("credentials") | Some("auth") => {
-            let user = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set credentials".to_string(),
-                usage: "set credentials <username> <password>",
-            })?;
-            let pass = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "set credentials".to_string(),
-                usage: "set credentials <username> <password>",
-            })?;
-            Ok(json!({ "id": id, "action": "credentials", "username": user, "password": pass }))
-        }
+        Some("credentials") | Some("auth") => parse_set_credentials(&rest[1..], id),
+        Some("timeout") => parse_set_timeout(&rest[1..], id),
+        Some("tls") => parse_set_tls(&rest[1..], id),
+        Some("buffer") => parse_set_buffer(&rest[1..], id),
         Some("media") => {
             let color = if rest.contains(&"dark") {
                 "dark"
@@ -2955,13 +4836,16 @@ fn parse_set(rest: &[&str], id: &str) -> Result<Value, ParseError> {
         }),
         None => Err(ParseError::MissingArguments {
             context: "set".to_string(),
-            usage: "set <viewport|device|geo|offline|headers|credentials|media> [args...]",
+            usage:
+                "set <viewport|device|geo|offline|headers|credentials|media|timeout|tls|buffer> [args...]",
         }),
     }
 }
 
+const NETWORK_REQUESTS_USAGE: &str = "network requests [--clear] [--tail <n>] [--filter <url>] [--type <csv>] [--method <m>] [--status <code>]";
+
 /// Parse network interception, request inspection, and HAR recording commands.
-fn parse_network(rest: &[&str], id: &str) -> Result<Value, ParseError> {
+fn parse_network(rest: &[&str], flags: &Flags, id: &str) -> Result<Value, ParseError> {
     const VALID: &[&str] = &["route", "unroute", "requests", "request", "har"];
 
     match rest.first().copied() {
@@ -3004,6 +4888,13 @@ fn parse_network(rest: &[&str], id: &str) -> Result<Value, ParseError> {
             let status_idx = rest.iter().position(|&s| s == "--status");
             let status = status_idx.and_then(|i| rest.get(i + 1).copied());
             let mut cmd = json!({ "id": id, "action": "requests", "clear": clear });
+            if let Some(i) = rest.iter().position(|&s| s == "--tail") {
+                cmd["tail"] = json!(parse_tail(
+                    rest.get(i + 1),
+                    "network requests",
+                    NETWORK_REQUESTS_USAGE
+                )?);
+            }
             if let Some(f) = filter {
                 cmd["filter"] = json!(f);
             }
@@ -3025,45 +4916,7 @@ fn parse_network(rest: &[&str], id: &str) -> Result<Value, ParseError> {
             })?;
             Ok(json!({ "id": id, "action": "request_detail", "requestId": request_id }))
         }
-        Some("har") => {
-            const HAR_VALID: &[&str] = &["start", "stop"];
-            match rest.get(1).copied() {
-                Some("start") => {
-                    let mut cmd = json!({ "id": id, "action": "har_start" });
-                    if let Some(content_idx) = rest.iter().position(|&s| s == "--content") {
-                        let mode = rest.get(content_idx + 1).ok_or_else(|| {
-                            ParseError::MissingArguments {
-                                context: "network har start --content".to_string(),
-                                usage: "network har start [--content <all|text|none>]",
-                            }
-                        })?;
-                        if !["all", "text", "none"].contains(mode) {
-                            return Err(ParseError::InvalidValue {
-                                message: format!("Invalid --content mode '{}'", mode),
-                                usage: "network har start [--content <all|text|none>]",
-                            });
-                        }
-                        cmd["content"] = json!(mode);
-                    }
-                    Ok(cmd)
-                }
-                Some("stop") => {
-                    let mut cmd = json!({ "id": id, "action": "har_stop" });
-                    if let Some(path) = rest.get(2) {
-                        cmd["path"] = json!(path);
-                    }
-                    Ok(cmd)
-                }
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: HAR_VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "network har".to_string(),
-                    usage: "network har <start|stop> [path]",
-                }),
-            }
-        }
+        Some("har") => parse_har(&rest[1..], flags, "network har", id),
         Some(sub) => Err(ParseError::UnknownSubcommand {
             subcommand: sub.to_string(),
             valid_options: VALID,
@@ -3126,6 +4979,170 @@ fn parse_storage(rest: &[&str], id: &str) -> Result<Value, ParseError> {
     }
 }
 
+/// Modifier names as sent to the browser, in the order chords are written.
+const KEY_MODIFIERS: &[&str] = &["Control", "Alt", "Shift", "Meta"];
+
+/// Named keys accepted as the final token of a key expression, besides
+/// single characters and `F1`-`F24`.
+const SPECIAL_KEYS: &[&str] = &[
+    "Enter",
+    "Tab",
+    "Escape",
+    "Backspace",
+    "Delete",
+    "Insert",
+    "Space",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "ArrowUp",
+    "ArrowDown",
+    "ArrowLeft",
+    "ArrowRight",
+    "CapsLock",
+    "NumLock",
+    "ScrollLock",
+    "PrintScreen",
+    "Pause",
+    "ContextMenu",
+    "Control",
+    "Alt",
+    "Shift",
+    "Meta",
+];
+
+/// Common spellings and their browser key names, matched case-insensitively.
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("ctrl", "Control"),
+    ("cmd", "Meta"),
+    ("command", "Meta"),
+    ("win", "Meta"),
+    ("super", "Meta"),
+    ("opt", "Alt"),
+    ("option", "Alt"),
+    ("esc", "Escape"),
+    ("del", "Delete"),
+    ("return", "Enter"),
+    ("ins", "Insert"),
+    ("pgup", "PageUp"),
+    ("pgdn", "PageDown"),
+    ("up", "ArrowUp"),
+    ("down", "ArrowDown"),
+    ("left", "ArrowLeft"),
+    ("right", "ArrowRight"),
+];
+
+/// Canonical name for a named key or alias, ignoring case.
+fn canonical_key_name(token: &str) -> Option<String> {
+    let lower = token.to_lowercase();
+    if let Some((_, name)) = KEY_ALIASES.iter().find(|(alias, _)| *alias == lower) {
+        return Some(name.to_string());
+    }
+    if let Some(name) = SPECIAL_KEYS.iter().find(|k| k.to_lowercase() == lower) {
+        return Some(name.to_string());
+    }
+    let n: u8 = lower.strip_prefix('f')?.parse().ok()?;
+    (1..=24).contains(&n).then(|| format!("F{}", n))
+}
+
+/// Closest named key to a mistyped token, if any is near enough.
+fn suggest_key_name(token: &str) -> Option<String> {
+    let lower = token.to_lowercase();
+    let candidates = SPECIAL_KEYS
+        .iter()
+        .map(|k| (k.to_lowercase(), *k))
+        .chain(KEY_ALIASES.iter().map(|(a, k)| (a.to_string(), *k)));
+    candidates
+        .map(|(spelling, name)| (suggest::edit_distance(&lower, &spelling), name))
+        .filter(|(distance, _)| *distance <= suggest::MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name.to_string())
+}
+
+/// Normalize a key or chord such as `ctrl+shift+p` or `esc` to the names
+/// the browser expects (`Control+Shift+p`, `Escape`). Modifiers are
+/// reordered canonically; a single-character key keeps its case. Unknown
+/// keys are rejected with the normalized expression to use instead.
+pub fn normalize_key_expression(raw: &str) -> Result<String, String> {
+    if raw.is_empty() {
+        return Err("Key must not be empty".to_string());
+    }
+    // `+` as the key itself: `+` alone or a chord ending in `++`.
+    let (chord, key) = match raw.strip_suffix("++") {
+        Some(chord) => (Some(chord), "+"),
+        None if raw == "+" => (None, "+"),
+        None => match raw.rsplit_once('+') {
+            Some((chord, key)) => (Some(chord), key),
+            None => (None, raw),
+        },
+    };
+
+    if key.is_empty() {
+        return Err(format!("Missing key after '+' in '{}'", raw));
+    }
+
+    let mut modifiers: Vec<&str> = Vec::new();
+    let mut bad_modifier: Option<&str> = None;
+    for token in chord.into_iter().flat_map(|c| c.split('+')) {
+        match canonical_key_name(token)
+            .as_deref()
+            .and_then(|name| KEY_MODIFIERS.iter().find(|m| **m == name))
+        {
+            Some(m) => {
+                if !modifiers.contains(m) {
+                    modifiers.push(m);
+                }
+            }
+            None => {
+                bad_modifier.get_or_insert(token);
+            }
+        }
+    }
+    modifiers.sort_by_key(|m| KEY_MODIFIERS.iter().position(|k| k == m));
+
+    let key_name = if key.chars().count() == 1 {
+        Some(key.to_string())
+    } else {
+        canonical_key_name(key)
+    };
+    let join = |key: &str| {
+        let mut parts: Vec<&str> = modifiers.clone();
+        parts.push(key);
+        parts.join("+")
+    };
+
+    if let Some(token) = bad_modifier {
+        return Err(format!(
+            "'{}' is not a modifier in '{}'. Use Control, Alt, Shift, or Meta (aliases: ctrl, opt, cmd, win)",
+            token, raw
+        ));
+    }
+    match key_name {
+        Some(name) => Ok(join(&name)),
+        None => Err(match suggest_key_name(key) {
+            Some(name) => format!("Unknown key '{}'. Did you mean '{}'?", key, join(&name)),
+            None => format!(
+                "Unknown key '{}'. Use a single character or a key name like Enter, Tab, Escape, ArrowUp, or F5",
+                key
+            ),
+        }),
+    }
+}
+
+/// The key argument of `press`/`keydown`/`keyup`, normalized.
+fn parse_key_arg(
+    arg: Option<&&str>,
+    context: &str,
+    usage: &'static str,
+) -> Result<String, ParseError> {
+    let raw = arg.ok_or_else(|| ParseError::MissingArguments {
+        context: context.to_string(),
+        usage,
+    })?;
+    normalize_key_expression(raw).map_err(|message| ParseError::InvalidValue { message, usage })
+}
+
 /// Split a string into arguments respecting shell quoting (double/single quotes, backslash escapes).
 pub fn shell_words_split(s: &str) -> Vec<String> {
     let mut args = Vec::new();
@@ -3175,17 +5192,22 @@ mod tests {
             enable: Vec::new(),
             cdp: None,
             profile: None,
+            user_data_dir: None,
             state: None,
             proxy: None,
             proxy_bypass: None,
             args: None,
+            window_size: None,
+            window_position: None,
             user_agent: None,
             provider: None,
             ignore_https_errors: false,
+            ignore_https_origins: Vec::new(),
             allow_file_access: false,
             hide_scrollbars: true,
             webgpu: false,
             no_xvfb: false,
+            no_history: false,
             device: None,
             auto_connect: false,
             session_name: None,
@@ -3201,6 +5223,7 @@ mod tests {
             cli_init_scripts: false,
             cli_enable: false,
             cli_profile: false,
+            cli_user_data_dir: false,
             cli_state: false,
             cli_args: false,
             cli_user_agent: false,
@@ -3213,15 +5236,32 @@ mod tests {
             cli_headed: false,
             cli_webgpu: false,
             cli_restore: false,
+            cli_allowed_domains: false,
+            cli_ignore_https_origins: false,
+            cli_action_policy: false,
+            cli_confirm_actions: false,
+            cli_device: false,
+            cli_provider: false,
+            cli_session_name: false,
             annotate: false,
+            full: false,
             color_scheme: None,
+            default_scheme: None,
             download_path: None,
+            artifacts_dir: None,
             content_boundaries: false,
             max_output: None,
+            cache_ms: None,
+            flag_warning_window_ms: 0,
+            auto_retry_actions: 0,
+            ref_prefix: None,
             allowed_domains: None,
             action_policy: None,
             confirm_actions: None,
             confirm_interactive: false,
+            on_confirmation: None,
+            provider_opts: Vec::new(),
+            sessions: None,
             engine: None,
             screenshot_dir: None,
             screenshot_quality: None,
@@ -3229,8 +5269,14 @@ mod tests {
             idle_timeout: None,
             default_timeout: None,
             no_auto_dialog: false,
+            no_exit_code: false,
+            no_install_prompt: false,
+            require_fresh_daemon: false,
             model: None,
             plugins: Vec::new(),
+            redact_patterns: None,
+            aliases: Default::default(),
+            session_profile: Vec::new(),
             verbose: false,
             quiet: false,
         }
@@ -3542,7 +5588,7 @@ mod tests {
     #[test]
     fn test_cookies_set_with_domain() {
         let cmd = parse_command(
-            &args("cookies set mycookie myvalue --domain example.com"),
+            &args("cookies set mycookie myvalue --domain example.com --path /"),
             &default_flags(),
         )
         .unwrap();
@@ -3550,6 +5596,28 @@ mod tests {
         assert_eq!(cmd["cookies"][0]["name"], "mycookie");
         assert_eq!(cmd["cookies"][0]["value"], "myvalue");
         assert_eq!(cmd["cookies"][0]["domain"], "example.com");
+        assert_eq!(cmd["cookies"][0]["path"], "/");
+    }
+
+    #[test]
+    fn test_cookies_set_domain_needs_path_or_url() {
+        let err = parse_command(
+            &args("cookies set mycookie myvalue --domain example.com"),
+            &default_flags(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.format().contains("--domain needs --path"));
+
+        let cmd = parse_command(
+            &args(
+                "cookies set mycookie myvalue --domain .example.com --url https://app.example.com",
+            ),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["cookies"][0]["domain"], ".example.com");
+        assert!(cmd["cookies"][0].get("path").is_none());
     }
 
     #[test]
@@ -3651,6 +5719,62 @@ mod tests {
             &default_flags(),
         );
         assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.format().contains("Expected Strict, Lax, or None"));
+    }
+
+    #[test]
+    fn test_cookies_set_samesite_is_case_insensitive() {
+        let cmd = parse_command(
+            &args("cookies set mycookie myvalue --sameSite lax"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["cookies"][0]["sameSite"], "Lax");
+    }
+
+    #[test]
+    fn test_cookies_set_expires_validation() {
+        let cmd = parse_command(
+            &args("cookies set mycookie myvalue --expires 1700000000.5"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["cookies"][0]["expires"], 1700000000.5);
+
+        for (value, expected) in [
+            ("tomorrow", "Expected a Unix timestamp in seconds"),
+            ("-5", "Expected a Unix timestamp in seconds"),
+            ("1700000000000", "looks like milliseconds"),
+        ] {
+            let err = parse_command(
+                &args(&format!("cookies set mycookie myvalue --expires {}", value)),
+                &default_flags(),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ParseError::InvalidValue { .. }), "{}", value);
+            assert!(err.format().contains(expected), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_cookies_set_rejects_unknown_and_valueless_options() {
+        let err = parse_command(
+            &args("cookies set mycookie myvalue --domian example.com"),
+            &default_flags(),
+        )
+        .unwrap_err();
+        assert!(err
+            .format()
+            .contains("Unknown cookies set option: --domian"));
+
+        let err = parse_command(
+            &args("cookies set mycookie myvalue --url"),
+            &default_flags(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ParseError::MissingArguments { .. }));
     }
 
     // === Storage Tests ===
@@ -3929,6 +6053,62 @@ mod tests {
         assert_eq!(cmd["headers"]["X-Custom"], "value");
     }
 
+    #[test]
+    fn test_navigate_with_per_command_header() {
+        let cmd = parse_command(
+            &[
+                "open".to_string(),
+                "example.com".to_string(),
+                "--header".to_string(),
+                "X-Trace: abc".to_string(),
+            ],
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["url"], "https://example.com");
+        assert_eq!(cmd["headers"]["X-Trace"], "abc");
+    }
+
+    #[test]
+    fn test_navigate_per_command_header_overrides_global_case_insensitively() {
+        let mut flags = default_flags();
+        flags.headers = Some(r#"{"Authorization": "Bearer old", "X-Keep": "1"}"#.to_string());
+        let cmd = parse_command(
+            &[
+                "open".to_string(),
+                "--header".to_string(),
+                "authorization: Bearer new".to_string(),
+                "example.com".to_string(),
+            ],
+            &flags,
+        )
+        .unwrap();
+        let headers = cmd["headers"].as_object().unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers["authorization"], "Bearer new");
+        assert_eq!(headers["X-Keep"], "1");
+        assert_eq!(cmd["url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_navigate_rejects_invalid_per_command_header() {
+        let result = parse_command(
+            &args("open example.com --header Connection:close"),
+            &default_flags(),
+        );
+        assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
+        let result = parse_command(&args("open example.com --header novalue"), &default_flags());
+        assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn test_navigate_rejects_non_string_header_values() {
+        let mut flags = default_flags();
+        flags.headers = Some(r#"{"X-Retries": 3}"#.to_string());
+        let result = parse_command(&args("open example.com"), &flags);
+        assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
+    }
+
     #[test]
     fn test_navigate_without_headers_flag() {
         let cmd = parse_command(&args("open example.com"), &default_flags()).unwrap();
@@ -3967,8 +6147,197 @@ mod tests {
         assert_eq!(cmd["url"], "chrome://extensions");
     }
 
+    #[test]
+    fn test_is_local_dev_host() {
+        for url in [
+            "localhost",
+            "localhost:3000",
+            "LOCALHOST:3000/path?q=1",
+            "app.localhost:8080",
+            "127.0.0.1",
+            "127.0.0.1:5173/",
+            "127.255.10.2#top",
+            "[::1]:8080/api",
+            "user:pw@localhost:3000",
+        ] {
+            assert!(is_local_dev_host(url), "{}", url);
+        }
+        for url in [
+            "example.com",
+            "internal-host",
+            "localhost.example.com",
+            "mylocalhost",
+            "128.0.0.1",
+            "127.0.0",
+            "127.0.0.256",
+            "[::2]:8080",
+            "192.168.1.10:3000",
+        ] {
+            assert!(!is_local_dev_host(url), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_open_default_scheme_modes() {
+        let open = |url: &str, mode: Option<&str>| {
+            let mut flags = default_flags();
+            flags.default_scheme = mode.map(String::from);
+            parse_command(&[String::from("open"), url.to_string()], &flags)
+        };
+        // https (default): loopback hosts still get http.
+        for mode in [None, Some("https")] {
+            assert_eq!(
+                open("example.com", mode).unwrap()["url"],
+                "https://example.com"
+            );
+            assert_eq!(
+                open("localhost:3000", mode).unwrap()["url"],
+                "http://localhost:3000"
+            );
+            assert_eq!(
+                open("127.0.0.1:8080", mode).unwrap()["url"],
+                "http://127.0.0.1:8080"
+            );
+        }
+        // http: everything without a scheme.
+        assert_eq!(
+            open("internal-host", Some("http")).unwrap()["url"],
+            "http://internal-host"
+        );
+        assert_eq!(
+            open("https://secure.example", Some("http")).unwrap()["url"],
+            "https://secure.example"
+        );
+        // none: explicit schemes only.
+        let err = open("internal-host", Some("none")).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err
+            .format()
+            .contains("Write http://internal-host or https://internal-host"));
+        assert!(open("localhost:3000", Some("none")).is_err());
+        assert_eq!(
+            open("http://internal-host", Some("none")).unwrap()["url"],
+            "http://internal-host"
+        );
+        assert_eq!(
+            open("about:blank", Some("none")).unwrap()["url"],
+            "about:blank"
+        );
+    }
+
+    #[test]
+    fn test_navigate_internal_schemes_pass_through() {
+        let mut flags = default_flags();
+        flags.allow_file_access = true;
+        for url in [
+            "about:blank",
+            "about:srcdoc",
+            "chrome://version",
+            "data:text/html,<h1>Hi</h1>",
+            "DATA:text/plain;base64,SGk=",
+            "file:///tmp/page.html",
+            "view-source:https://example.com",
+            "view-source:example.com",
+        ] {
+            let cmd = parse_command(&[String::from("goto"), url.to_string()], &flags).unwrap();
+            assert_eq!(cmd["url"], url, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_navigate_file_url_needs_file_access() {
+        for url in ["file:///tmp/page.html", "view-source:file:///tmp/page.html"] {
+            let err = parse_command(&[String::from("open"), url.to_string()], &default_flags())
+                .unwrap_err();
+            assert!(matches!(err, ParseError::InvalidValue { .. }), "{}", url);
+            assert!(err.format().contains("--allow-file-access"), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_navigate_chrome_url_rejected_on_provider() {
+        let mut flags = default_flags();
+        flags.provider = Some("browserbase".to_string());
+        let err = parse_command(&args("open chrome://version"), &flags).unwrap_err();
+        assert!(err.format().contains("--provider browserbase"));
+        assert!(parse_command(&args("open view-source:chrome://version"), &flags).is_err());
+
+        // Other internal pages still work there.
+        let cmd = parse_command(&args("open about:blank"), &flags).unwrap();
+        assert_eq!(cmd["url"], "about:blank");
+    }
+
     // === Set Headers Tests ===
 
+    #[test]
+    fn test_set_credentials_forms() {
+        let cmd = parse_command(&args("set credentials admin hunter2"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "credentials");
+        assert_eq!(cmd["username"], "admin");
+        assert_eq!(cmd["password"], "hunter2");
+        assert!(cmd.get("origin").is_none());
+
+        let cmd = parse_command(
+            &args("set credentials admin hunter2 --origin https://Intranet.example.com:8443/login"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["origin"], "https://intranet.example.com:8443");
+
+        let cmd = parse_command(
+            &args("set credentials --origin http://localhost:3000 admin --password-stdin"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["username"], "admin");
+        assert_eq!(cmd["passwordStdin"], true);
+        assert!(cmd.get("password").is_none());
+        assert_eq!(cmd["origin"], "http://localhost:3000");
+
+        let cmd = parse_command(&args("set credentials clear"), &default_flags()).unwrap();
+        assert_eq!(cmd["clear"], true);
+        assert!(cmd.get("username").is_none());
+        let cmd = parse_command(
+            &args("set credentials clear --origin https://example.com"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["clear"], true);
+        assert_eq!(cmd["origin"], "https://example.com");
+
+        // A user literally named "clear" still works with a password.
+        let cmd = parse_command(&args("set credentials clear pw"), &default_flags()).unwrap();
+        assert_eq!(cmd["username"], "clear");
+    }
+
+    #[test]
+    fn test_set_credentials_validation() {
+        for (input, missing) in [
+            ("set credentials", true),
+            ("set credentials admin", true),
+            ("set credentials admin pw --origin", true),
+            (
+                "set credentials admin pw --origin file:///etc/passwd",
+                false,
+            ),
+            ("set credentials admin pw --origin not-a-url", false),
+            ("set credentials admin pw --password-stdin", false),
+            ("set credentials admin pw extra", false),
+            ("set credentials admin pw --scope x", false),
+        ] {
+            let err = parse_command(&args(input), &default_flags()).unwrap_err();
+            if missing {
+                assert!(
+                    matches!(err, ParseError::MissingArguments { .. }),
+                    "{}",
+                    input
+                );
+            } else {
+                assert!(matches!(err, ParseError::InvalidValue { .. }), "{}", input);
+            }
+        }
+    }
+
     #[test]
     fn test_set_headers_parses_json() {
         let input: Vec<String> = vec![
@@ -4031,6 +6400,164 @@ mod tests {
         let cmd = parse_command(&args("click #button"), &default_flags()).unwrap();
         assert_eq!(cmd["action"], "click");
         assert_eq!(cmd["selector"], "#button");
+        assert!(cmd.get("describe").is_none());
+    }
+
+    #[test]
+    fn test_click_wait_for_popup() {
+        let cmd = parse_command(&args("click @e4 --wait-for-popup"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "@e4");
+        assert_eq!(cmd["waitForPopup"], true);
+        assert!(cmd.get("popupTimeout").is_none());
+        assert!(cmd.get("newTab").is_none());
+
+        let cmd = parse_command(
+            &args("click --popup-timeout 2500 --wait-for-popup #open"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "#open");
+        assert_eq!(cmd["popupTimeout"], 2500);
+
+        let cmd = parse_command(&args("click #button"), &default_flags()).unwrap();
+        assert!(cmd.get("waitForPopup").is_none());
+        let cmd = parse_command(&args("click --new-tab a.link"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "a.link");
+        assert_eq!(cmd["newTab"], true);
+    }
+
+    #[test]
+    fn test_click_wait_for_popup_validation() {
+        for input in [
+            "click @e4 --wait-for-popup --popup-timeout soon",
+            "click @e4 --wait-for-popup --popup-timeout 0",
+            "click @e4 --popup-timeout 1000",
+            "click @e4 --new-tab --wait-for-popup",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        assert!(matches!(
+            parse_command(
+                &args("click @e4 --wait-for-popup --popup-timeout"),
+                &default_flags()
+            ),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_json_mode_tags_mutating_actions_with_describe() {
+        let mut flags = default_flags();
+        flags.json = true;
+        for line in ["click @e2", "fill #q hello", "check #agree", "select #s a"] {
+            let cmd = parse_command(&args(line), &flags).unwrap();
+            assert_eq!(cmd["describe"], true, "{}", line);
+        }
+        for line in ["snapshot", "get text #x", "press Enter"] {
+            let cmd = parse_command(&args(line), &flags).unwrap();
+            assert!(cmd.get("describe").is_none(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_ref_prefix_is_accepted_in_selectors() {
+        let mut flags = default_flags();
+        flags.ref_prefix = Some("ab12".to_string());
+        let click = parse_command(&args("click @ab12-e3"), &flags).unwrap();
+        assert_eq!(click["selector"], "@e3");
+        let fill = parse_command(&args("fill ref=ab12-e4 hello"), &flags).unwrap();
+        assert_eq!(fill["selector"], "@e4");
+        assert_eq!(fill["value"], "hello");
+        let get = parse_command(&args("get text ab12-e5"), &flags).unwrap();
+        assert_eq!(get["selector"], "@e5");
+        let drag = parse_command(&args("drag @ab12-e1 @ab12-e2"), &flags).unwrap();
+        assert_eq!(drag["source"], "@e1");
+        assert_eq!(drag["target"], "@e2");
+        let snapshot = parse_command(&args("snapshot -i"), &flags).unwrap();
+        assert_eq!(snapshot["refPrefix"], "ab12");
+
+        // Plain refs, other prefixes, and CSS selectors pass through.
+        for sel in ["@e3", "@zz-e3", "#ab12-e3", "ab12-e3x"] {
+            let cmd = parse_command(&args(&format!("click {}", sel)), &flags).unwrap();
+            assert_eq!(cmd["selector"], sel);
+        }
+        let plain = parse_command(&args("click @ab12-e3"), &default_flags()).unwrap();
+        assert_eq!(plain["selector"], "@ab12-e3");
+        assert!(parse_command(&args("snapshot"), &default_flags())
+            .unwrap()
+            .get("refPrefix")
+            .is_none());
+    }
+
+    #[test]
+    fn test_ref_prefix_in_snapshot_scope() {
+        let mut flags = default_flags();
+        flags.ref_prefix = Some("ab12".to_string());
+        let cmd = parse_command(&args("snapshot -s @ab12-e5"), &flags).unwrap();
+        assert_eq!(cmd["ref"], "e5");
+        assert!(cmd.get("selector").is_none());
+        assert_eq!(cmd["refPrefix"], "ab12");
+        let cmd = parse_command(&args("snapshot -i --selector @e7"), &flags).unwrap();
+        assert_eq!(cmd["ref"], "e7");
+        let cmd = parse_command(&args("diff snapshot -s @ab12-e5"), &flags).unwrap();
+        assert_eq!(cmd["ref"], "e5");
+        assert_eq!(cmd["refPrefix"], "ab12");
+
+        // Without the flag a prefixed ref is still rejected.
+        let err = parse_command(&args("snapshot -s @ab12-e5"), &default_flags()).unwrap_err();
+        assert!(err.format().contains("Invalid ref"), "{}", err.format());
+        let err = parse_command(&args("snapshot -s @zz-e5"), &flags).unwrap_err();
+        assert!(err.format().contains("Invalid ref"), "{}", err.format());
+    }
+
+    #[test]
+    fn test_ref_scoped_selectors() {
+        let words = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let cmd = parse_command(&words(&["click", "@e5 >> css=button"]), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "@e5 >> button");
+        let cmd = parse_command(&args("fill @e5>>input hello"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "@e5 >> input");
+        assert_eq!(cmd["value"], "hello");
+        let cmd = parse_command(
+            &words(&["get", "text", "@e5  >>  li:has-text(\"a >> b\")"]),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "@e5 >> li:has-text(\"a >> b\")");
+
+        let mut flags = default_flags();
+        flags.ref_prefix = Some("ab12".to_string());
+        let cmd = parse_command(&words(&["click", "@ab12-e5 >> button"]), &flags).unwrap();
+        assert_eq!(cmd["selector"], "@e5 >> button");
+
+        for sel in ["@x5 >> button", "@e5 >>", "@e5 >> a >> b"] {
+            assert!(
+                matches!(
+                    parse_command(&words(&["click", sel]), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "{}",
+                sel
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_ref_prefix() {
+        assert!(validate_ref_prefix("ab12").is_ok());
+        assert!(validate_ref_prefix("ABCDEFG8").is_ok());
+        assert!(validate_ref_prefix("").is_err());
+        assert!(validate_ref_prefix("abcdefghi").is_err());
+        assert!(validate_ref_prefix("a-b").is_err());
+        assert!(validate_ref_prefix("ab_1").is_err());
+        assert!(validate_ref_prefix("é").is_err());
     }
 
     #[test]
@@ -4049,6 +6576,63 @@ mod tests {
         assert_eq!(cmd["text"], "some text");
     }
 
+    #[test]
+    fn test_keyboard_type_and_inserttext() {
+        let cmd = parse_command(&args("keyboard type Hello big world"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "keyboard");
+        assert_eq!(cmd["subaction"], "type");
+        assert_eq!(cmd["text"], "Hello big world");
+        assert!(cmd.get("delay").is_none());
+
+        let cmd = parse_command(&args("keyboard inserttext Hi there"), &default_flags()).unwrap();
+        assert_eq!(cmd["subaction"], "insertText");
+        assert_eq!(cmd["text"], "Hi there");
+
+        // Quotes the shell-style splitter keeps inside a word survive.
+        let words = shell_words_split(r#"keyboard type 'say "hi"' now"#);
+        let cmd = parse_command(&words, &default_flags()).unwrap();
+        assert_eq!(cmd["text"], r#"say "hi" now"#);
+    }
+
+    #[test]
+    fn test_keyboard_type_delay() {
+        let cmd = parse_command(
+            &args("keyboard type --delay 40 slow and steady"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["text"], "slow and steady");
+        assert_eq!(cmd["delay"], 40);
+
+        let cmd = parse_command(&args("keyboard type done --delay 5"), &default_flags()).unwrap();
+        assert_eq!(cmd["text"], "done");
+        assert_eq!(cmd["delay"], 5);
+
+        for input in ["keyboard type --delay", "keyboard type --delay 10"] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::MissingArguments { .. })
+                ),
+                "{}",
+                input
+            );
+        }
+        for input in [
+            "keyboard type hi --delay fast",
+            "keyboard inserttext hi --delay 10",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+
     #[test]
     fn test_find_fill_value_survives_for_all_locators() {
         // fill is advertised for these locators, so the value must reach dispatch.
@@ -4255,7 +6839,7 @@ mod tests {
     fn test_network_har_stop_with_path() {
         let cmd = parse_command(&args("network har stop ./capture.har"), &default_flags()).unwrap();
         assert_eq!(cmd["action"], "har_stop");
-        assert_eq!(cmd["path"], "./capture.har");
+        assert_eq!(cmd["path"], cwd_path("./capture.har"));
     }
 
     #[test]
@@ -4265,6 +6849,65 @@ mod tests {
         assert!(cmd.get("path").is_none());
     }
 
+    #[test]
+    fn test_har_command() {
+        let cmd = parse_command(
+            &args("har start capture.har --content attach"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "har_start");
+        assert_eq!(cmd["path"], cwd_path("capture.har"));
+        assert_eq!(cmd["content"], "attach");
+
+        let cmd = parse_command(&args("har start --content omit"), &default_flags()).unwrap();
+        assert_eq!(cmd["content"], "omit");
+        assert!(cmd.get("path").is_none());
+
+        let cmd = parse_command(&args("har stop /tmp/Out.HAR"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "har_stop");
+        assert_eq!(cmd["path"], "/tmp/Out.HAR");
+        let cmd = parse_command(&args("har stop"), &default_flags()).unwrap();
+        assert!(cmd.get("path").is_none());
+
+        // A bare name under --artifacts-dir is placed there, on start too.
+        let tmp = tempfile::tempdir().unwrap();
+        let mut flags = default_flags();
+        flags.artifacts_dir = Some(tmp.path().to_string_lossy().to_string());
+        let cmd = parse_command(&args("har start net.har"), &flags).unwrap();
+        assert_eq!(
+            cmd["path"],
+            json!(tmp.path().join("net.har").to_string_lossy())
+        );
+        let cmd = parse_command(&args("har start"), &flags).unwrap();
+        assert!(cmd.get("path").is_none());
+
+        for input in [
+            "har stop capture.json",
+            "har start capture",
+            "har start --content inline",
+            "har stop --content all",
+            "har stop a.har b.har",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "{}",
+                input
+            );
+        }
+        assert!(matches!(
+            parse_command(&args("har"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("har pause"), &default_flags()),
+            Err(ParseError::UnknownSubcommand { .. })
+        ));
+    }
+
     #[test]
     fn test_network_har_requires_subcommand() {
         let result = parse_command(&args("network har"), &default_flags());
@@ -4344,10 +6987,125 @@ mod tests {
     }
 
     #[test]
-    fn test_screenshot_full_page_shorthand() {
-        let cmd = parse_command(&args("screenshot -f"), &default_flags()).unwrap();
-        assert_eq!(cmd["action"], "screenshot");
-        assert_eq!(cmd["fullPage"], true);
+    fn test_screenshot_full_page_shorthand() {
+        let cmd = parse_command(&args("screenshot -f"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "screenshot");
+        assert_eq!(cmd["fullPage"], true);
+    }
+
+    #[test]
+    fn test_screenshot_full_and_annotate_defaults() {
+        let mut flags = default_flags();
+        flags.full = true;
+        flags.annotate = true;
+        let cmd = parse_command(&args("screenshot ./page.png"), &flags).unwrap();
+        assert_eq!(cmd["fullPage"], true);
+        assert_eq!(cmd["annotate"], true);
+        assert_eq!(cmd["path"], "./page.png");
+
+        let cmd = parse_command(&args("screenshot --full false ./page.png"), &flags).unwrap();
+        assert_eq!(cmd["fullPage"], false);
+        assert_eq!(cmd["path"], "./page.png");
+        let cmd = parse_command(&args("screenshot --full=false"), &flags).unwrap();
+        assert_eq!(cmd["fullPage"], false);
+
+        let cmd = parse_command(&args("screenshot --full true"), &default_flags()).unwrap();
+        assert_eq!(cmd["fullPage"], true);
+        assert!(cmd["path"].is_null());
+    }
+
+    #[test]
+    fn test_screenshot_interactive_only_needs_annotate() {
+        let mut flags = default_flags();
+        flags.annotate = true;
+        let cmd = parse_command(&args("screenshot --interactive-only ./page.png"), &flags).unwrap();
+        assert_eq!(cmd["interactiveOnly"], true);
+        assert_eq!(cmd["path"], "./page.png");
+        let cmd = parse_command(&args("screenshot ./page.png"), &flags).unwrap();
+        assert!(cmd.get("interactiveOnly").is_none());
+
+        let err =
+            parse_command(&args("screenshot --interactive-only"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.format().contains("--annotate"));
+    }
+
+    #[test]
+    fn test_screenshot_annotate_selector_and_clip() {
+        let cmd = parse_command(
+            &args("screenshot --annotate --selector #main ./main.png"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["annotate"], true);
+        assert_eq!(cmd["selector"], "#main");
+        assert_eq!(cmd["path"], "./main.png");
+        assert!(cmd["clip"].is_null());
+
+        let cmd = parse_command(&args("screenshot --selector @e4"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "@e4");
+        assert!(cmd["path"].is_null());
+        assert_eq!(cmd["annotate"], false);
+
+        let cmd = parse_command(
+            &args("screenshot --clip 0,40,800,600 --annotate shot.png"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(
+            cmd["clip"],
+            json!({ "x": 0, "y": 40, "width": 800, "height": 600 })
+        );
+        assert_eq!(cmd["annotate"], true);
+        assert_eq!(cmd["path"], "shot.png");
+        assert!(cmd["selector"].is_null());
+
+        // A configured full-page default gives way to an explicit clip.
+        let mut flags = default_flags();
+        flags.full = true;
+        let cmd = parse_command(&args("screenshot --clip 10,10,50,50"), &flags).unwrap();
+        assert_eq!(cmd["fullPage"], false);
+    }
+
+    #[test]
+    fn test_screenshot_clip_and_selector_errors() {
+        for input in [
+            "screenshot --clip 0,0,800",
+            "screenshot --clip 0,0,800,600,1",
+            "screenshot --clip -1,0,800,600",
+            "screenshot --clip 0,0,10.5,600",
+            "screenshot --clip a,b,c,d",
+            "screenshot --clip 0,0,0,600",
+            "screenshot --clip 0,0,800,600 @e1",
+            "screenshot --clip 0,0,800,600 --selector #main",
+            "screenshot --clip 0,0,800,600 --full",
+            "screenshot --selector #main @e1 out.png",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        for input in ["screenshot --clip", "screenshot --selector"] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::MissingArguments { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        let err = parse_command(&args("screenshot --clip 0,0,800"), &default_flags()).unwrap_err();
+        assert!(
+            err.format().contains("non-negative integers"),
+            "{}",
+            err.format()
+        );
     }
 
     #[test]
@@ -4449,6 +7207,58 @@ mod tests {
         assert_eq!(cmd["urls"], true);
     }
 
+    #[test]
+    fn test_snapshot_stable_refs() {
+        let cmd = parse_command(&args("snapshot -i --stable-refs"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "snapshot");
+        assert_eq!(cmd["stableRefs"], true);
+    }
+
+    #[test]
+    fn test_snapshot_viewport_only() {
+        let cmd = parse_command(&args("snapshot --viewport-only"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "snapshot");
+        assert_eq!(cmd["viewportOnly"], true);
+
+        let cmd = parse_command(
+            &args("snapshot -i --viewport-only -C -d 3"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["viewportOnly"], true);
+        assert_eq!(cmd["interactive"], true);
+        assert_eq!(cmd["cursor"], true);
+        assert_eq!(cmd["maxDepth"], 3);
+
+        let cmd = parse_command(&args("snapshot -i"), &default_flags()).unwrap();
+        assert!(cmd.get("viewportOnly").is_none());
+    }
+
+    #[test]
+    fn test_snapshot_selector_css() {
+        let cmd = parse_command(&args("snapshot -s #main -i"), &default_flags()).unwrap();
+        assert_eq!(cmd["selector"], "#main");
+        assert!(cmd.get("ref").is_none());
+        assert_eq!(cmd["interactive"], true);
+    }
+
+    #[test]
+    fn test_snapshot_selector_ref() {
+        let cmd = parse_command(&args("snapshot -s @e12 -i -c -d 2"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "snapshot");
+        assert_eq!(cmd["ref"], "e12");
+        assert!(cmd.get("selector").is_none());
+        assert_eq!(cmd["interactive"], true);
+        assert_eq!(cmd["compact"], true);
+        assert_eq!(cmd["maxDepth"], 2);
+    }
+
+    #[test]
+    fn test_snapshot_selector_invalid_ref() {
+        let result = parse_command(&args("snapshot -s @nav"), &default_flags());
+        assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
+    }
+
     // === Wait ===
 
     #[test]
@@ -4489,6 +7299,41 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_wait_load_states() {
+        for state in ["load", "domcontentloaded", "networkidle"] {
+            let cmd =
+                parse_command(&args(&format!("wait -l {}", state)), &default_flags()).unwrap();
+            assert_eq!(cmd["state"], state);
+        }
+        let err = parse_command(&args("wait --load idle"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.format().contains("networkidle"));
+    }
+
+    #[test]
+    fn test_wait_modes_are_exclusive() {
+        for input in [
+            "wait --url **/done --load networkidle",
+            "wait --fn window.ready --text Ready",
+            "wait --download ./file.pdf --url **/done",
+            "wait -t Ready -l load --timeout 5000",
+        ] {
+            let err = parse_command(&args(input), &default_flags()).unwrap_err();
+            assert!(
+                matches!(err, ParseError::InvalidValue { .. }),
+                "{}: {:?}",
+                input,
+                err
+            );
+            assert!(err.format().contains("cannot be combined"), "{}", input);
+        }
+
+        // Values that look like mode flags belong to the flag before them.
+        let cmd = parse_command(&args("wait --text -d"), &default_flags()).unwrap();
+        assert_eq!(cmd["text"], "-d");
+    }
+
     #[test]
     fn test_wait_fn() {
         let cmd = parse_command(&args("wait --fn window.ready"), &default_flags()).unwrap();
@@ -4516,6 +7361,53 @@ mod tests {
         assert_eq!(cmd["timeout"], 5000);
     }
 
+    #[test]
+    fn test_wait_text_scope_exact_not() {
+        let cmd = parse_command(
+            &args("wait --not -s #toast --text Saving --exact --timeout 3000"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "wait");
+        assert_eq!(cmd["text"], "Saving");
+        assert_eq!(cmd["scope"], "#toast");
+        assert_eq!(cmd["exact"], true);
+        assert_eq!(cmd["negate"], true);
+        assert_eq!(cmd["timeout"], 3000);
+
+        let cmd = parse_command(
+            &args("wait --text Done --selector .status"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["scope"], ".status");
+        assert!(cmd.get("exact").is_none());
+        assert!(cmd.get("negate").is_none());
+    }
+
+    #[test]
+    fn test_wait_text_flag_validation() {
+        for input in [
+            "wait --not",
+            "wait --exact",
+            "wait -s #toast",
+            "wait #x --not",
+        ] {
+            let err = parse_command(&args(input), &default_flags()).unwrap_err();
+            assert!(
+                matches!(err, ParseError::InvalidValue { .. }),
+                "{}: {:?}",
+                input,
+                err
+            );
+            assert!(err.format().contains("requires --text"), "{}", input);
+        }
+        let err = parse_command(&args("wait --text Done -s"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::MissingArguments { .. }));
+        let err = parse_command(&args("wait --text Done -s --not"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::MissingArguments { .. }));
+    }
+
     // === Clipboard Tests ===
 
     #[test]
@@ -4582,7 +7474,7 @@ mod tests {
     fn test_record_start() {
         let cmd = parse_command(&args("record start output.webm"), &default_flags()).unwrap();
         assert_eq!(cmd["action"], "recording_start");
-        assert_eq!(cmd["path"], "output.webm");
+        assert_eq!(cmd["path"], cwd_path("output.webm"));
         assert!(cmd.get("url").is_none());
     }
 
@@ -4594,7 +7486,7 @@ mod tests {
         )
         .unwrap();
         assert_eq!(cmd["action"], "recording_start");
-        assert_eq!(cmd["path"], "demo.webm");
+        assert_eq!(cmd["path"], cwd_path("demo.webm"));
         assert_eq!(cmd["url"], "https://example.com");
     }
 
@@ -4606,7 +7498,7 @@ mod tests {
         )
         .unwrap();
         assert_eq!(cmd["action"], "recording_start");
-        assert_eq!(cmd["path"], "demo.webm");
+        assert_eq!(cmd["path"], cwd_path("demo.webm"));
         assert_eq!(cmd["url"], "https://example.com");
     }
 
@@ -4618,7 +7510,7 @@ mod tests {
         )
         .unwrap();
         assert_eq!(cmd["action"], "recording_start");
-        assert_eq!(cmd["path"], "demo.webm");
+        assert_eq!(cmd["path"], cwd_path("demo.webm"));
         assert_eq!(cmd["url"], "chrome-extension://abcdef/popup.html");
     }
 
@@ -4632,6 +7524,55 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_artifacts_dir_places_bare_and_missing_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("artifacts");
+        let mut flags = default_flags();
+        flags.artifacts_dir = Some(dir.to_string_lossy().into_owned());
+        let under = |cmd: &Value, suffix: &str| {
+            let path = cmd["path"].as_str().unwrap().to_string();
+            assert!(
+                path.starts_with(dir.to_str().unwrap()) && path.ends_with(suffix),
+                "{}",
+                path
+            );
+        };
+
+        under(&parse_command(&args("pdf"), &flags).unwrap(), ".pdf");
+        under(
+            &parse_command(&args("record start"), &flags).unwrap(),
+            ".webm",
+        );
+        under(
+            &parse_command(&args("trace stop"), &flags).unwrap(),
+            ".json",
+        );
+        under(
+            &parse_command(&args("network har stop"), &flags).unwrap(),
+            ".har",
+        );
+        under(&parse_command(&args("screenshot"), &flags).unwrap(), ".png");
+        assert!(dir.is_dir());
+
+        let cmd = parse_command(&args("screenshot home.png"), &flags).unwrap();
+        assert_eq!(cmd["path"], json!(dir.join("home.png").to_string_lossy()));
+        let cmd = parse_command(&args("download #export report.csv"), &flags).unwrap();
+        assert_eq!(cmd["path"], json!(dir.join("report.csv").to_string_lossy()));
+
+        // Absolute paths and paths with a directory part bypass it.
+        let cmd = parse_command(&args("pdf /tmp/out.pdf"), &flags).unwrap();
+        assert_eq!(cmd["path"], "/tmp/out.pdf");
+        let cmd = parse_command(&args("screenshot ./shots/a.png"), &flags).unwrap();
+        assert_eq!(cmd["path"], "./shots/a.png");
+
+        // Without the flag the path stays required.
+        assert!(matches!(
+            parse_command(&args("pdf"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
     #[test]
     fn test_record_stop() {
         let cmd = parse_command(&args("record stop"), &default_flags()).unwrap();
@@ -4642,7 +7583,7 @@ mod tests {
     fn test_record_restart() {
         let cmd = parse_command(&args("record restart output.webm"), &default_flags()).unwrap();
         assert_eq!(cmd["action"], "recording_restart");
-        assert_eq!(cmd["path"], "output.webm");
+        assert_eq!(cmd["path"], cwd_path("output.webm"));
         assert!(cmd.get("url").is_none());
     }
 
@@ -4654,10 +7595,64 @@ mod tests {
         )
         .unwrap();
         assert_eq!(cmd["action"], "recording_restart");
-        assert_eq!(cmd["path"], "demo.webm");
+        assert_eq!(cmd["path"], cwd_path("demo.webm"));
         assert_eq!(cmd["url"], "https://example.com");
     }
 
+    #[test]
+    fn test_close_save_state() {
+        let cmd = parse_command(&args("close"), &default_flags()).unwrap();
+        assert!(cmd.get("saveStatePath").is_none());
+
+        let cmd = parse_command(&args("close --save-state ./auth.json"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "close");
+        assert_eq!(cmd["saveStatePath"], "./auth.json");
+
+        // Without a path the session name picks the managed state file.
+        let cmd = parse_command(&args("quit --save-state"), &default_flags()).unwrap();
+        assert_eq!(cmd["saveStatePath"], "test");
+
+        let mut flags = default_flags();
+        flags.session_name = Some("work".to_string());
+        let cmd = parse_command(&args("close --save-state"), &flags).unwrap();
+        assert_eq!(cmd["saveStatePath"], "work");
+        // An explicit path wins over the --session-name key.
+        let cmd = parse_command(&args("close --save-state snapshot"), &flags).unwrap();
+        assert_eq!(cmd["saveStatePath"], "snapshot");
+        let cmd = parse_command(&args("close --save-state --json"), &flags).unwrap();
+        assert_eq!(cmd["saveStatePath"], "work");
+
+        assert!(matches!(
+            parse_command(&args("close --all --save-state"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_record_path_validation() {
+        let cmd = parse_command(&args("record start /tmp/Demo.WEBM"), &default_flags()).unwrap();
+        assert_eq!(cmd["path"], "/tmp/Demo.WEBM");
+
+        for input in [
+            "record start demo.mp4",
+            "record restart ./take2",
+            "record start demo.webm example.com extra",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        let err = parse_command(&args("record start demo.mp4"), &default_flags()).unwrap_err();
+        assert!(err.format().contains(".webm"), "{}", err.format());
+        let err = parse_command(&args("record start"), &default_flags()).unwrap_err();
+        assert!(err.format().contains("./demo.webm"), "{}", err.format());
+    }
+
     #[test]
     fn test_record_restart_missing_path() {
         let result = parse_command(&args("record restart"), &default_flags());
@@ -4688,6 +7683,283 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_get_html_options() {
+        let cmd = parse_command(&args("get html #main"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "innerhtml");
+        assert_eq!(cmd["selector"], "#main");
+        assert!(cmd.get("outer").is_none());
+        assert!(cmd.get("stripAttrs").is_none());
+        assert!(cmd.get("maxDepth").is_none());
+
+        let cmd = parse_command(
+            &args("get html --outer #main --strip-attrs --max-depth 2"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "#main");
+        assert_eq!(cmd["outer"], true);
+        assert_eq!(cmd["stripAttrs"], true);
+        assert_eq!(cmd["maxDepth"], 2);
+    }
+
+    #[test]
+    fn test_get_html_invalid_options() {
+        for input in [
+            "get html #main --max-depth -1",
+            "get html #main --max-depth two",
+            "get html #main extra",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        for input in ["get html", "get html --outer", "get html #main --max-depth"] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::MissingArguments { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_url_options() {
+        let cmd = parse_command(&args("get url"), &default_flags()).unwrap();
+        assert_eq!(cmd, json!({ "id": cmd["id"], "action": "url" }));
+
+        let cmd = parse_command(&args("get url --parts --query page"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "url");
+        assert_eq!(cmd["parts"], true);
+        assert_eq!(cmd["queryParam"], "page");
+
+        assert!(matches!(
+            parse_command(&args("get url --query"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("get url --path"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
+    // === Upload Tests ===
+
+    #[test]
+    fn test_upload_local_files() {
+        let cmd = parse_command(&args("upload #f a.png b.png"), &default_flags()).unwrap();
+        assert_eq!(cmd["files"], json!(["a.png", "b.png"]));
+        assert!(cmd.get("maxFetchSize").is_none());
+        assert!(cmd.get("keepTemp").is_none());
+    }
+
+    #[test]
+    fn test_upload_urls_and_fetch_options() {
+        let cmd = parse_command(
+            &args("upload #f HTTPS://x.example/r.pdf ./a.png"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["maxFetchSize"], DEFAULT_MAX_FETCH_SIZE);
+        assert!(cmd.get("keepTemp").is_none());
+
+        let cmd = parse_command(
+            &args("upload #f --max-fetch-size 2MB https://x.example/r.pdf --keep-temp"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "#f");
+        assert_eq!(cmd["files"], json!(["https://x.example/r.pdf"]));
+        assert_eq!(cmd["maxFetchSize"], 2 * 1024 * 1024);
+        assert_eq!(cmd["keepTemp"], true);
+    }
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("1048576"), Ok(1_048_576));
+        assert_eq!(parse_byte_size("512k"), Ok(512 * 1024));
+        assert_eq!(parse_byte_size("1.5MiB"), Ok(1_572_864));
+        assert_eq!(parse_byte_size("1G"), Ok(1024 * 1024 * 1024));
+        for bad in ["0", "-1", "MB", "10TB", "abc"] {
+            assert!(parse_byte_size(bad).is_err(), "{}", bad);
+        }
+        assert!(matches!(
+            parse_command(&args("upload #f x --max-fetch-size"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("upload --keep-temp"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
+    // === Drag Tests ===
+
+    #[test]
+    fn test_drag_basic() {
+        let cmd = parse_command(&args("drag @e1 @e2"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "drag");
+        assert_eq!(cmd["source"], "@e1");
+        assert_eq!(cmd["target"], "@e2");
+        assert!(cmd.get("method").is_none());
+        assert!(cmd.get("sourceOffset").is_none());
+        assert!(cmd.get("targetOffset").is_none());
+    }
+
+    #[test]
+    fn test_drag_offsets_and_html5() {
+        let cmd = parse_command(
+            &args("drag #item --target-offset 10,-4 #list --source-offset 5,5 --html5"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["source"], "#item");
+        assert_eq!(cmd["target"], "#list");
+        assert_eq!(cmd["sourceOffset"], json!({ "x": 5, "y": 5 }));
+        assert_eq!(cmd["targetOffset"], json!({ "x": 10, "y": -4 }));
+        assert_eq!(cmd["method"], "html5");
+    }
+
+    #[test]
+    fn test_drag_rejects_bad_offsets() {
+        for input in [
+            "drag @e1 @e2 --target-offset 1.5,2",
+            "drag @e1 @e2 --target-offset 10",
+            "drag @e1 @e2 --source-offset a,b",
+            "drag @e1 @e2 --source-offset 1,2,3",
+            "drag @e1 @e2 @e3",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        assert!(matches!(
+            parse_command(&args("drag @e1 @e2 --target-offset"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("drag @e1 --html5"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
+    // === Keyboard Tests ===
+
+    #[test]
+    fn test_normalize_key_expression_table() {
+        let cases = [
+            ("Enter", "Enter"),
+            ("enter", "Enter"),
+            ("return", "Enter"),
+            ("RETURN", "Enter"),
+            ("esc", "Escape"),
+            ("Esc", "Escape"),
+            ("escape", "Escape"),
+            ("del", "Delete"),
+            ("tab", "Tab"),
+            ("backspace", "Backspace"),
+            ("space", "Space"),
+            ("up", "ArrowUp"),
+            ("arrowdown", "ArrowDown"),
+            ("pgdn", "PageDown"),
+            ("f5", "F5"),
+            ("F12", "F12"),
+            ("f24", "F24"),
+            ("a", "a"),
+            ("A", "A"),
+            ("1", "1"),
+            ("/", "/"),
+            ("+", "+"),
+            ("shift", "Shift"),
+            ("ctrl+a", "Control+a"),
+            ("CTRL+A", "Control+A"),
+            ("Control+a", "Control+a"),
+            ("CMD+V", "Meta+V"),
+            ("cmd+v", "Meta+v"),
+            ("win+r", "Meta+r"),
+            ("opt+left", "Alt+ArrowLeft"),
+            ("option+f4", "Alt+F4"),
+            ("ctrl+shift+p", "Control+Shift+p"),
+            ("shift+ctrl+p", "Control+Shift+p"),
+            ("Meta+Shift+Control+Alt+k", "Control+Alt+Shift+Meta+k"),
+            ("ctrl+ctrl+c", "Control+c"),
+            ("shift+return", "Shift+Enter"),
+            ("ctrl+esc", "Control+Escape"),
+            ("ctrl++", "Control++"),
+            ("shift+tab", "Shift+Tab"),
+            ("alt+shift", "Alt+Shift"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                normalize_key_expression(input).as_deref(),
+                Ok(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_key_expression_rejects_unknown_keys() {
+        let cases = [
+            ("Entr", Some("Did you mean 'Enter'?")),
+            ("ctrl+Entr", Some("Did you mean 'Control+Enter'?")),
+            (
+                "ctrl+shift+escap",
+                Some("Did you mean 'Control+Shift+Escape'?"),
+            ),
+            ("pagedwn", Some("Did you mean 'PageDown'?")),
+            ("f25", None),
+            ("hello", None),
+            ("ctrl+", Some("Missing key after '+'")),
+            ("", None),
+        ];
+        for (input, suggestion) in cases {
+            let err = normalize_key_expression(input).unwrap_err();
+            if let Some(suggestion) = suggestion {
+                assert!(err.contains(suggestion), "input: {}, got: {}", input, err);
+            }
+        }
+        let err = normalize_key_expression("foo+a").unwrap_err();
+        assert!(err.contains("'foo' is not a modifier"), "got: {}", err);
+        let err = normalize_key_expression("a+b").unwrap_err();
+        assert!(err.contains("'a' is not a modifier"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_press_keydown_keyup_normalize_keys() {
+        let cmd = parse_command(&args("press ctrl+a"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "press");
+        assert_eq!(cmd["key"], "Control+a");
+        let cmd = parse_command(&args("key esc"), &default_flags()).unwrap();
+        assert_eq!(cmd["key"], "Escape");
+        let cmd = parse_command(&args("keydown cmd"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "keydown");
+        assert_eq!(cmd["key"], "Meta");
+        let cmd = parse_command(&args("keyup SHIFT"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "keyup");
+        assert_eq!(cmd["key"], "Shift");
+        for input in ["press Entr", "keydown ctrl+nope", "keyup foo+a"] {
+            assert!(matches!(
+                parse_command(&args(input), &default_flags()),
+                Err(ParseError::InvalidValue { .. })
+            ));
+        }
+    }
+
     // === Profile (CDP Tracing) Tests ===
 
     #[test]
@@ -4711,6 +7983,47 @@ mod tests {
         assert_eq!(categories[1], "v8.execute");
     }
 
+    #[test]
+    fn test_profiler_start_categories_forms() {
+        let cmd = parse_command(
+            &args("profiler start --categories=devtools.timeline,,disabled-by-default-v8.cpu_profiler,"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(
+            cmd["categories"],
+            json!(["devtools.timeline", "disabled-by-default-v8.cpu_profiler"])
+        );
+        let cmd = parse_command(
+            &["profiler", "start", "--categories", " blink , v8 "].map(String::from),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["categories"], json!(["blink", "v8"]));
+
+        for input in [
+            "profiler start --categories ,",
+            "profiler start --categories=",
+            "profiler start --cats blink",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "{}",
+                input
+            );
+        }
+        assert!(matches!(
+            parse_command(
+                &args("profiler start --categories --json"),
+                &default_flags()
+            ),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
     #[test]
     fn test_profiler_start_categories_missing_value() {
         let result = parse_command(&args("profiler start --categories"), &default_flags());
@@ -4785,24 +8098,110 @@ mod tests {
     }
 
     #[test]
-    fn test_eval_base64_with_special_chars() {
-        // "document.querySelector('[src*=\"_next\"]')" in base64
-        let cmd = parse_command(
-            &args("eval -b ZG9jdW1lbnQucXVlcnlTZWxlY3RvcignW3NyYyo9Il9uZXh0Il0nKQ=="),
-            &default_flags(),
-        )
-        .unwrap();
+    fn test_eval_base64_with_special_chars() {
+        // "document.querySelector('[src*=\"_next\"]')" in base64
+        let cmd = parse_command(
+            &args("eval -b ZG9jdW1lbnQucXVlcnlTZWxlY3RvcignW3NyYyo9Il9uZXh0Il0nKQ=="),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "evaluate");
+        assert_eq!(cmd["script"], "document.querySelector('[src*=\"_next\"]')");
+    }
+
+    #[test]
+    fn test_eval_base64_invalid() {
+        let result = parse_command(&args("eval -b !!!invalid!!!"), &default_flags());
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.format().contains("Invalid base64"));
+    }
+
+    #[test]
+    fn test_eval_base64_tolerates_line_wrapping() {
+        let cmd_args: Vec<String> = ["eval", "-b", "ZG9jdW1lbnQu\ndGl0bGU="]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let cmd = parse_command(&cmd_args, &default_flags()).unwrap();
+        assert_eq!(cmd["script"], "document.title");
+
+        let err = parse_command(&args("eval -b"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::MissingArguments { .. }));
+        let err = parse_command(&args("eval -b //79"), &default_flags()).unwrap_err();
+        assert!(err.format().contains("invalid UTF-8"), "{}", err.format());
+    }
+
+    #[test]
+    fn test_eval_stdin_reads_everything() {
+        let script = "const rows = [...document.querySelectorAll('tr')];\n\
+                      rows.map(r => r.textContent)\n";
+        let read = read_eval_script(&["--stdin"], &mut script.as_bytes()).unwrap();
+        assert_eq!(read, script);
+
+        let err = read_eval_script(&["--stdin", "document.title"], &mut "".as_bytes());
+        assert!(matches!(err, Err(ParseError::InvalidValue { .. })));
+        let err = read_eval_script(&["--stdin"], &mut " \n".as_bytes()).unwrap_err();
+        assert!(err.format().contains("eval script is empty"));
+    }
+
+    #[test]
+    fn test_eval_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("script.js");
+        let script = "(() => {\n  const q = \"it's\";\n  return q;\n})()\n";
+        std::fs::write(&path, script).unwrap();
+        let cmd_args: Vec<String> = ["eval", "--file", path.to_str().unwrap(), "--raw-result"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let cmd = parse_command(&cmd_args, &default_flags()).unwrap();
         assert_eq!(cmd["action"], "evaluate");
-        assert_eq!(cmd["script"], "document.querySelector('[src*=\"_next\"]')");
+        assert_eq!(cmd["script"], script);
+        assert_eq!(cmd["rawResult"], true);
+
+        let missing = dir.path().join("missing.js");
+        let cmd_args: Vec<String> = ["eval", "--file", missing.to_str().unwrap()]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let err = parse_command(&cmd_args, &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(
+            err.format().contains("Could not read script file"),
+            "{}",
+            err.format()
+        );
+
+        assert!(matches!(
+            parse_command(&args("eval --file"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("eval --file a.js b.js"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("eval"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
     }
 
     #[test]
-    fn test_eval_base64_invalid() {
-        let result = parse_command(&args("eval -b !!!invalid!!!"), &default_flags());
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(matches!(err, ParseError::InvalidValue { .. }));
-        assert!(err.format().contains("Invalid base64"));
+    fn test_eval_raw_result() {
+        let cmd = parse_command(&args("eval document.title"), &default_flags()).unwrap();
+        assert!(cmd.get("rawResult").is_none());
+
+        for input in [
+            "eval --raw-result document.title",
+            "eval document.title --raw-result",
+            "eval --raw-result -b ZG9jdW1lbnQudGl0bGU=",
+        ] {
+            let cmd = parse_command(&args(input), &default_flags()).unwrap();
+            assert_eq!(cmd["rawResult"], true, "{}", input);
+            assert_eq!(cmd["script"], "document.title", "{}", input);
+        }
     }
 
     #[test]
@@ -4825,6 +8224,77 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_malformed_invocations_report_specific_errors() {
+        let err = |input: &str| parse_command(&args(input), &default_flags()).unwrap_err();
+        let missing = |input: &str, expected: &str| match err(input) {
+            ParseError::MissingArguments { context, .. } => assert_eq!(context, expected),
+            other => panic!("{}: expected MissingArguments, got {:?}", input, other),
+        };
+        missing("fill @e1", "fill");
+        missing("click", "click");
+        missing("press", "press");
+        missing("find role", "find role");
+        missing("set viewport 100", "set viewport");
+        missing("wait --load", "wait --load");
+        missing("cookies set session", "cookies set");
+        missing("storage local set k", "storage local set");
+
+        assert!(matches!(
+            err("screnshot"),
+            ParseError::UnknownCommand { command } if command == "screnshot"
+        ));
+        assert!(matches!(
+            err("get txt"),
+            ParseError::UnknownSubcommand { subcommand, .. } if subcommand == "txt"
+        ));
+        assert!(matches!(
+            err("set bogus"),
+            ParseError::UnknownSubcommand { subcommand, .. } if subcommand == "bogus"
+        ));
+        let scroll = err("scroll sideways");
+        assert!(matches!(scroll, ParseError::InvalidValue { .. }));
+        assert!(scroll
+            .format()
+            .contains("Expected up, down, left, or right"));
+        assert!(matches!(
+            err("act --progress xml"),
+            ParseError::InvalidValue { .. }
+        ));
+
+        // An explicit empty string still clears a field.
+        let cmd = parse_command(
+            &["fill".to_string(), "@e1".to_string(), String::new()],
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["value"], "");
+    }
+
+    #[test]
+    fn test_unknown_command_and_subcommand_suggestions() {
+        let err = parse_command(&args("screnshot"), &default_flags()).unwrap_err();
+        assert_eq!(err.suggestions(), vec!["screenshot"]);
+        assert_eq!(
+            err.format(),
+            "Unknown command 'screnshot'. Did you mean: screenshot?"
+        );
+
+        // Aliases are suggested by their canonical name.
+        let err = parse_command(&args("naviagte"), &default_flags()).unwrap_err();
+        assert_eq!(err.suggestions(), vec!["open"]);
+
+        let err = parse_command(&args("get txt"), &default_flags()).unwrap_err();
+        assert_eq!(err.suggestions(), vec!["text"]);
+        assert!(err
+            .format()
+            .starts_with("Unknown subcommand 'txt'. Did you mean: text?\nValid options: "));
+
+        let err = parse_command(&args("qwertyuiop"), &default_flags()).unwrap_err();
+        assert!(err.suggestions().is_empty());
+        assert_eq!(err.format(), "Unknown command 'qwertyuiop'");
+    }
+
     // === Error message tests ===
 
     #[test]
@@ -4855,6 +8325,148 @@ mod tests {
         assert!(err.format().contains("get text"));
     }
 
+    #[test]
+    fn test_get_box_flags() {
+        let cmd = parse_command(&args("get box #hero"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "boundingbox");
+        assert_eq!(cmd["selector"], "#hero");
+        assert!(cmd.get("viewportRelative").is_none());
+        let cmd = parse_command(
+            &args("get box --visibility @e3 --viewport-relative"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "@e3");
+        assert_eq!(cmd["viewportRelative"], true);
+        assert_eq!(cmd["visibility"], true);
+        assert!(matches!(
+            parse_command(&args("get box --visibility"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_get_styles() {
+        // Without --properties the daemon returns every computed property.
+        let cmd = parse_command(&args("get styles button.primary"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "styles");
+        assert_eq!(cmd["selector"], "button.primary");
+        assert!(cmd.get("properties").is_none());
+
+        let cmd = parse_command(
+            &args("get styles @e1 --properties color,font-size,,background-color"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "@e1");
+        assert_eq!(
+            cmd["properties"],
+            json!(["color", "font-size", "background-color"])
+        );
+
+        for input in ["get styles", "get styles --properties color"] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::MissingArguments { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        for input in ["get styles @e1 --properties ,", "get styles @e1 @e2"] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_selector_pseudo_classes_validated() {
+        let cmd = parse_command(
+            &args("get count li:has-text(\"Buy\"):visible"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "li:has-text(\"Buy\"):visible");
+
+        let cmd = parse_command(
+            &args("find first :nth-match(button:text(\"Save\"),2) click"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "nth");
+
+        let err = parse_command(
+            &args("click button:text-contains(\"Delete\")"),
+            &default_flags(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.format().contains("Did you mean ':has-text()'"));
+    }
+
+    #[test]
+    fn test_get_count_plain() {
+        let cmd = parse_command(&args("get count li.item"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "count");
+        assert_eq!(cmd["selector"], "li.item");
+        assert!(cmd.get("visibleOnly").is_none());
+        assert!(cmd.get("comparison").is_none());
+    }
+
+    #[test]
+    fn test_get_count_visible_only_and_comparison() {
+        let cmd = parse_command(
+            &args("get count li.item --visible-only --gte 3"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["selector"], "li.item");
+        assert_eq!(cmd["visibleOnly"], true);
+        assert_eq!(cmd["comparison"], json!({ "op": "gte", "value": 3 }));
+
+        for (flag, op) in [
+            ("--gt", "gt"),
+            ("--lt", "lt"),
+            ("--lte", "lte"),
+            ("--eq", "eq"),
+        ] {
+            let cmd = parse_command(
+                &args(&format!("get count {} 0 .row", flag)),
+                &default_flags(),
+            )
+            .unwrap();
+            assert_eq!(cmd["comparison"]["op"], op);
+            assert_eq!(cmd["selector"], ".row");
+        }
+    }
+
+    #[test]
+    fn test_get_count_rejects_bad_comparisons() {
+        let err = parse_command(&args("get count li --gt 1 --lt 5"), &default_flags()).unwrap_err();
+        assert!(err.format().contains("only one of"));
+        for bad in [
+            "get count li --gt",
+            "get count li --eq -1",
+            "get count li --eq x",
+        ] {
+            assert!(matches!(
+                parse_command(&args(bad), &default_flags()),
+                Err(ParseError::InvalidValue { .. })
+            ));
+        }
+        assert!(matches!(
+            parse_command(&args("get count --visible-only"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
     // === Protocol alignment tests ===
 
     #[test]
@@ -4873,6 +8485,164 @@ mod tests {
         assert_eq!(cmd["reducedMotion"], "no-preference");
     }
 
+    #[test]
+    fn test_set_timeout() {
+        let cmd = parse_command(&args("set timeout 2000"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "timeout");
+        assert_eq!(cmd["defaultTimeout"], 2000);
+        assert!(cmd.get("navigationTimeout").is_none());
+
+        let cmd = parse_command(
+            &args("set timeout --navigation 60000 5000"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["defaultTimeout"], 5000);
+        assert_eq!(cmd["navigationTimeout"], 60000);
+
+        let cmd =
+            parse_command(&args("set timeout --navigation 600000"), &default_flags()).unwrap();
+        assert!(cmd.get("defaultTimeout").is_none());
+        assert_eq!(cmd["navigationTimeout"], 600000);
+
+        // `show` sends no values, so the daemon only reports.
+        let cmd = parse_command(&args("set timeout show"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "timeout");
+        assert!(cmd.get("defaultTimeout").is_none());
+        assert!(cmd.get("navigationTimeout").is_none());
+    }
+
+    #[test]
+    fn test_set_tls() {
+        let cmd = parse_command(&args("set tls ignore-errors on"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "tls_config");
+        assert_eq!(cmd["ignoreErrors"], true);
+        assert!(cmd.get("allowOrigin").is_none());
+        let cmd = parse_command(&args("set tls ignore-errors off"), &default_flags()).unwrap();
+        assert_eq!(cmd["ignoreErrors"], false);
+
+        // Origins are normalized to scheme, host, and any non-default port.
+        let cmd = parse_command(
+            &args("set tls allow-origin https://Intranet.Example.com:8443/login"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "tls_config");
+        assert_eq!(cmd["allowOrigin"], "https://intranet.example.com:8443");
+        let cmd = parse_command(
+            &args("set tls allow-origin https://dev.local:443"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["allowOrigin"], "https://dev.local");
+    }
+
+    #[test]
+    fn test_set_tls_validation() {
+        for input in [
+            "set tls ignore-errors maybe",
+            "set tls allow-origin http://intranet.example.com",
+            "set tls allow-origin intranet.example.com",
+            "set tls allow-origin file:///etc/hosts",
+            "set tls allow-origin https://a.example https://b.example",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        assert!(matches!(
+            parse_command(&args("set tls pin https://a.example"), &default_flags()),
+            Err(ParseError::UnknownSubcommand { .. })
+        ));
+        for input in ["set tls", "set tls ignore-errors", "set tls allow-origin"] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::MissingArguments { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_timeout_validation() {
+        for input in [
+            "set timeout 99",
+            "set timeout 600001",
+            "set timeout soon",
+            "set timeout -5",
+            "set timeout 1000 --navigation 50",
+            "set timeout 1000 2000",
+            "set timeout 1000 --retries 3",
+            "set timeout show 1000",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        for input in ["set timeout", "set timeout --navigation"] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::MissingArguments { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+    }
+    #[test]
+    fn test_set_buffer() {
+        for buffer in ["console", "errors", "requests"] {
+            let cmd = parse_command(
+                &args(&format!("set buffer {} 200", buffer)),
+                &default_flags(),
+            )
+            .unwrap();
+            assert_eq!(cmd["action"], "buffer_config");
+            assert_eq!(cmd["buffer"], buffer);
+            assert_eq!(cmd["maxEntries"], 200);
+        }
+
+        for input in [
+            "set buffer network 200",
+            "set buffer console 0",
+            "set buffer console lots",
+            "set buffer console 200 300",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        for input in ["set buffer", "set buffer console"] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::MissingArguments { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+    }
+
     #[test]
     fn test_set_media_reduced_motion() {
         let cmd = parse_command(&args("set media light reduced-motion"), &default_flags()).unwrap();
@@ -4959,14 +8729,129 @@ mod tests {
         assert_eq!(cmd["value"], "hello");
     }
 
+    #[test]
+    fn test_find_wait_and_state_in_any_order() {
+        let expected = |cmd: &Value| {
+            assert_eq!(cmd["action"], "getbyrole");
+            assert_eq!(cmd["role"], "button");
+            assert_eq!(cmd["subaction"], "click");
+            assert_eq!(cmd["name"], "Submit");
+            assert_eq!(cmd["exact"], true);
+            assert_eq!(cmd["waitFor"], 5000);
+            assert_eq!(cmd["state"], "visible");
+            assert!(cmd.get("value").is_none());
+        };
+        for input in [
+            "find role button click --name Submit --exact --wait 5000 --state visible",
+            "find role button --wait 5000 click --state visible --exact --name Submit",
+            "find role --state visible --name Submit button --exact --wait 5000 click",
+        ] {
+            expected(&parse_command(&args(input), &default_flags()).unwrap());
+        }
+
+        let cmd = parse_command(
+            &args("find nth 1 li --wait 250 fill hello world --state attached"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "nth");
+        assert_eq!(cmd["index"], 1);
+        assert_eq!(cmd["value"], "hello world");
+        assert_eq!(cmd["waitFor"], 250);
+        assert_eq!(cmd["state"], "attached");
+
+        // The defaults leave the daemon command unchanged.
+        let cmd = parse_command(&args("find text Save --wait 0"), &default_flags()).unwrap();
+        assert!(cmd.get("waitFor").is_none());
+        assert!(cmd.get("state").is_none());
+    }
+
+    #[test]
+    fn test_find_click_wait_for_popup() {
+        for input in [
+            "find role link --name Share --wait-for-popup --popup-timeout 3000",
+            "find role link click --popup-timeout 3000 --name Share --wait-for-popup",
+        ] {
+            let cmd = parse_command(&args(input), &default_flags()).unwrap();
+            assert_eq!(cmd["action"], "getbyrole");
+            assert_eq!(cmd["subaction"], "click");
+            assert_eq!(cmd["name"], "Share");
+            assert_eq!(cmd["waitForPopup"], true);
+            assert_eq!(cmd["popupTimeout"], 3000);
+        }
+        let cmd = parse_command(
+            &args("find nth 2 a click --wait-for-popup"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "nth");
+        assert_eq!(cmd["waitForPopup"], true);
+
+        for input in [
+            "find text Email fill me@example.com --wait-for-popup",
+            "find text Save --popup-timeout 1000",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_wait_and_state_validation() {
+        for input in [
+            "find role button --wait -5",
+            "find role button --wait soon",
+            "find role button --wait 1.5",
+            "find text Save --state hidden",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        for input in [
+            "find role button click --wait",
+            "find role button --state",
+            "find role button --name",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::MissingArguments { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+    }
+
     // === Download Tests ===
 
+    fn cwd_path(relative: &str) -> String {
+        std::env::current_dir()
+            .unwrap()
+            .join(relative)
+            .to_string_lossy()
+            .into_owned()
+    }
+
     #[test]
     fn test_download() {
         let cmd = parse_command(&args("download #btn ./file.pdf"), &default_flags()).unwrap();
         assert_eq!(cmd["action"], "download");
         assert_eq!(cmd["selector"], "#btn");
-        assert_eq!(cmd["path"], "./file.pdf");
+        assert_eq!(cmd["path"], cwd_path("./file.pdf"));
+        assert!(cmd.get("timeout").is_none());
     }
 
     #[test]
@@ -4974,7 +8859,45 @@ mod tests {
         let cmd = parse_command(&args("download @e5 ./report.xlsx"), &default_flags()).unwrap();
         assert_eq!(cmd["action"], "download");
         assert_eq!(cmd["selector"], "@e5");
-        assert_eq!(cmd["path"], "./report.xlsx");
+        assert_eq!(cmd["path"], cwd_path("./report.xlsx"));
+    }
+
+    #[test]
+    fn test_download_path_resolution() {
+        let cmd = parse_command(
+            &args("download #btn /tmp/out/report.xlsx"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["path"], "/tmp/out/report.xlsx");
+        let cmd =
+            parse_command(&args("download #btn exports/report.csv"), &default_flags()).unwrap();
+        assert_eq!(cmd["path"], cwd_path("exports/report.csv"));
+    }
+
+    #[test]
+    fn test_download_timeout() {
+        for input in [
+            "download #export ./report.xlsx --timeout 120000",
+            "download --timeout 120000 #export ./report.xlsx",
+        ] {
+            let cmd = parse_command(&args(input), &default_flags()).unwrap();
+            assert_eq!(cmd["selector"], "#export", "{}", input);
+            assert_eq!(cmd["path"], cwd_path("./report.xlsx"), "{}", input);
+            assert_eq!(cmd["timeout"], 120000, "{}", input);
+        }
+        for input in [
+            "download #export ./r.xlsx --timeout",
+            "download #export ./r.xlsx --timeout 0",
+            "download #export ./r.xlsx --timeout soon",
+            "download #export ./r.xlsx extra",
+        ] {
+            assert!(
+                parse_command(&args(input), &default_flags()).is_err(),
+                "{}",
+                input
+            );
+        }
     }
 
     #[test]
@@ -4997,6 +8920,56 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_extension_commands() {
+        let cmd = parse_command(&args("extension"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "extension_list");
+        assert!(cmd.get("name").is_none());
+        let cmd = parse_command(&args("extension id mypopup"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "extension_list");
+        assert_eq!(cmd["name"], "mypopup");
+        assert!(matches!(
+            parse_command(&args("extension id"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("extension remove x"), &default_flags()),
+            Err(ParseError::UnknownSubcommand { .. })
+        ));
+    }
+
+    #[test]
+    fn test_downloads_list_is_default() {
+        let cmd = parse_command(&args("downloads"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "downloads_list");
+        let cmd = parse_command(&args("downloads list"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "downloads_list");
+    }
+
+    #[test]
+    fn test_downloads_clear() {
+        let cmd = parse_command(&args("downloads clear"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "downloads_clear");
+    }
+
+    #[test]
+    fn test_downloads_open_with_reveal() {
+        let cmd = parse_command(&args("downloads open 2 --reveal"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "downloads_open");
+        assert_eq!(cmd["index"], 2);
+        assert_eq!(cmd["reveal"], true);
+    }
+
+    #[test]
+    fn test_downloads_open_rejects_bad_index() {
+        for input in ["downloads open 0", "downloads open first"] {
+            let result = parse_command(&args(input), &default_flags());
+            assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
+        }
+        let result = parse_command(&args("downloads open"), &default_flags());
+        assert!(matches!(result, Err(ParseError::MissingArguments { .. })));
+    }
+
     // === Wait for Download Tests ===
 
     #[test]
@@ -5176,6 +9149,42 @@ mod tests {
         assert!(err.format().contains("not a valid port number or URL"));
     }
 
+    #[test]
+    fn test_connect_endpoint_errors() {
+        let err = parse_command(&args("connect localhost:9222"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.format().contains("has no scheme"), "{}", err.format());
+        assert!(err.format().contains("ws://localhost:9222"));
+        let err =
+            parse_command(&args("connect browser.example.com/cdp"), &default_flags()).unwrap_err();
+        assert!(err.format().contains("has no scheme"), "{}", err.format());
+        let err = parse_command(&args("connect ftp://host:9222"), &default_flags()).unwrap_err();
+        assert!(err.format().contains("must use ws://"), "{}", err.format());
+        let err = parse_command(&args("connect -1"), &default_flags()).unwrap_err();
+        assert!(err.format().contains("not a valid port number or URL"));
+    }
+
+    #[test]
+    fn test_connect_https_url_and_shared_helper() {
+        let cmd = parse_command(
+            &args("connect https://browser.example.com/json/version"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "launch");
+        assert_eq!(cmd["cdpUrl"], "https://browser.example.com/json/version");
+
+        // `--cdp` builds its launch from the same helper.
+        assert_eq!(cdp_endpoint_launch("9222"), Ok(json!({ "cdpPort": 9222 })));
+        assert_eq!(
+            cdp_endpoint_launch("wss://x.dev/cdp"),
+            Ok(json!({ "cdpUrl": "wss://x.dev/cdp" }))
+        );
+        assert!(cdp_endpoint_launch("70000")
+            .unwrap_err()
+            .contains("out of range"));
+    }
+
     #[test]
     fn test_connect_port_zero() {
         let result = parse_command(&args("connect 0"), &default_flags());
@@ -5209,6 +9218,30 @@ mod tests {
         assert_eq!(cmd["cdpPort"], 1);
     }
 
+    // === iOS device tests ===
+
+    #[test]
+    fn test_device_subcommands() {
+        let list = parse_command(&args("device"), &default_flags()).unwrap();
+        assert_eq!(list["action"], "device_list");
+        let cmd = parse_command(&args("device use iPhone 15 Pro"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "device_use");
+        assert_eq!(cmd["device"], "iPhone 15 Pro");
+        let udid = "A1B2C3D4-0000-1111-2222-333344445555";
+        let cmd = parse_command(&args(&format!("device boot {}", udid)), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "device_boot");
+        assert_eq!(cmd["device"], udid);
+    }
+
+    #[test]
+    fn test_device_subcommand_errors() {
+        let err = parse_command(&args("device boot"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::MissingArguments { .. }));
+        assert!(err.format().contains("device boot <name-or-udid>"));
+        let err = parse_command(&args("device boto x"), &default_flags()).unwrap_err();
+        assert_eq!(err.suggestions(), vec!["boot"]);
+    }
+
     // === Runtime stream control tests ===
 
     #[test]
@@ -5243,6 +9276,56 @@ mod tests {
         assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
     }
 
+    #[test]
+    fn test_stream_start_stop() {
+        let cmd = parse_command(&args("stream start"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "stream_start");
+        assert!(cmd.get("port").is_none());
+
+        let cmd = parse_command(
+            &args("stream start --port 9223 --quality 60 --fps 15"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "stream_start");
+        assert_eq!(cmd["port"], 9223);
+        assert_eq!(cmd["quality"], 60);
+        assert_eq!(cmd["fps"], 15);
+
+        let cmd = parse_command(&args("stream stop"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "stream_stop");
+    }
+
+    #[test]
+    fn test_stream_start_validation() {
+        for input in [
+            "stream start --port 0",
+            "stream start --port 70000",
+            "stream start --port abc",
+            "stream start --quality 0",
+            "stream start --quality 101",
+            "stream start --fps 0",
+            "stream start --fps 61",
+            "stream start --bitrate 5",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        assert!(matches!(
+            parse_command(&args("stream start --fps"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        // Same range message as connect <port>.
+        let err = parse_command(&args("stream start --port 70000"), &default_flags()).unwrap_err();
+        assert!(err.format().contains("valid range: 1-65535"));
+    }
+
     #[test]
     fn test_stream_missing_subcommand() {
         let result = parse_command(&args("stream"), &default_flags());
@@ -5255,6 +9338,51 @@ mod tests {
     fn test_trace_start() {
         let cmd = parse_command(&args("trace start"), &default_flags()).unwrap();
         assert_eq!(cmd["action"], "trace_start");
+        assert_eq!(cmd["screenshots"], true);
+        assert_eq!(cmd["snapshots"], true);
+        assert_eq!(cmd["sources"], false);
+        assert!(cmd.get("title").is_none());
+    }
+
+    #[test]
+    fn test_trace_start_toggles() {
+        let cmd = parse_command(
+            &args("trace start --no-screenshots --sources --title checkout"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["screenshots"], false);
+        assert_eq!(cmd["snapshots"], true);
+        assert_eq!(cmd["sources"], true);
+        assert_eq!(cmd["title"], "checkout");
+
+        let cmd = parse_command(
+            &args("trace start --no-snapshots --no-sources --snapshots"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["screenshots"], true);
+        assert_eq!(cmd["snapshots"], true);
+        assert_eq!(cmd["sources"], false);
+
+        let cmd = parse_command(
+            &args("trace start --sources --no-sources --no-screenshots --screenshots"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["screenshots"], true);
+        assert_eq!(cmd["sources"], false);
+    }
+
+    #[test]
+    fn test_trace_start_errors() {
+        assert!(matches!(
+            parse_command(&args("trace start --title"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        let err = parse_command(&args("trace start --screenshot"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err.format().contains("'--screenshot'"));
     }
 
     #[test]
@@ -5317,6 +9445,28 @@ mod tests {
         assert_eq!(cmd["maxDepth"], 2);
     }
 
+    #[test]
+    fn test_diff_snapshot_selector_ref() {
+        let cmd = parse_command(&args("diff snapshot -s @e5 -c"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "diff_snapshot");
+        assert_eq!(cmd["ref"], "e5");
+        assert!(cmd.get("selector").is_none());
+        assert_eq!(cmd["compact"], true);
+    }
+
+    #[test]
+    fn test_diff_snapshot_stable_refs() {
+        let cmd = parse_command(&args("diff snapshot --stable-refs"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "diff_snapshot");
+        assert_eq!(cmd["stableRefs"], true);
+    }
+
+    #[test]
+    fn test_diff_snapshot_selector_invalid_ref() {
+        let result = parse_command(&args("diff snapshot --selector @e"), &default_flags());
+        assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
+    }
+
     #[test]
     fn test_diff_screenshot_baseline() {
         let cmd = parse_command(
@@ -5410,6 +9560,86 @@ mod tests {
         assert_eq!(cmd["waitUntil"], "networkidle");
     }
 
+    #[test]
+    fn test_diff_url_prepends_https_like_open() {
+        let cmd = parse_command(
+            &args("diff url staging.example.com localhost:3000/page"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["url1"], "https://staging.example.com");
+        assert_eq!(cmd["url2"], "http://localhost:3000/page");
+
+        let cmd = parse_command(
+            &args("diff url http://a.test about:blank"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["url1"], "http://a.test");
+        assert_eq!(cmd["url2"], "about:blank");
+    }
+
+    #[test]
+    fn test_diff_url_flags_before_urls() {
+        let cmd = parse_command(
+            &args("diff url --screenshot -f --wait-until load a.com b.com -s #main -c -d 3"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["url1"], "https://a.com");
+        assert_eq!(cmd["url2"], "https://b.com");
+        assert_eq!(cmd["screenshot"], true);
+        assert_eq!(cmd["fullPage"], true);
+        assert_eq!(cmd["waitUntil"], "load");
+        assert_eq!(cmd["selector"], "#main");
+        assert_eq!(cmd["compact"], true);
+        assert_eq!(cmd["maxDepth"], 3);
+    }
+
+    #[test]
+    fn test_diff_url_wait_until_validated() {
+        for state in ["load", "domcontentloaded", "networkidle"] {
+            let cmd = parse_command(
+                &args(&format!("diff url a.com b.com --wait-until {}", state)),
+                &default_flags(),
+            )
+            .unwrap();
+            assert_eq!(cmd["waitUntil"], state);
+        }
+        let err = parse_command(
+            &args("diff url a.com b.com --wait-until idle"),
+            &default_flags(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+        assert!(err
+            .format()
+            .contains("Expected one of: load, domcontentloaded, networkidle"));
+    }
+
+    #[test]
+    fn test_diff_screenshot_threshold_bounds_inclusive() {
+        for (value, expected) in [("0", 0.0), ("1", 1.0), ("0.25", 0.25)] {
+            let cmd = parse_command(
+                &args(&format!("diff screenshot -b base.png -t {}", value)),
+                &default_flags(),
+            )
+            .unwrap();
+            assert_eq!(cmd["threshold"], expected, "{}", value);
+        }
+        for value in ["1.01", "NaN", "abc"] {
+            assert!(
+                parse_command(
+                    &args(&format!("diff screenshot -b base.png -t {}", value)),
+                    &default_flags(),
+                )
+                .is_err(),
+                "{}",
+                value
+            );
+        }
+    }
+
     #[test]
     fn test_diff_url_command_full_flag() {
         let cmd = parse_command(
@@ -5842,6 +10072,190 @@ mod tests {
         assert!(cmd.get("commands").is_none());
     }
 
+    #[test]
+    fn test_parse_duration_ms() {
+        assert_eq!(parse_duration_ms("250"), Ok(250));
+        assert_eq!(parse_duration_ms("250ms"), Ok(250));
+        assert_eq!(parse_duration_ms("30s"), Ok(30_000));
+        assert_eq!(parse_duration_ms("5m"), Ok(300_000));
+        assert_eq!(parse_duration_ms("2h"), Ok(7_200_000));
+        assert_eq!(parse_duration_ms("1d"), Ok(86_400_000));
+        assert!(parse_duration_ms("").is_err());
+        assert!(parse_duration_ms("s").is_err());
+        assert!(parse_duration_ms("5 minutes").is_err());
+        assert!(parse_duration_ms("1.5s").is_err());
+        assert!(parse_duration_ms("99999999999999999999h").is_err());
+    }
+
+    #[test]
+    fn test_console_and_errors_time_filters() {
+        let cmd = parse_command(&args("console --since 30s"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "console");
+        assert_eq!(cmd["sinceMs"], 30_000);
+        assert_eq!(cmd["clear"], false);
+
+        let cmd = parse_command(
+            &args("errors --after 2026-01-31T14:05:00Z --clear"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "errors");
+        assert_eq!(cmd["afterMs"], 1_769_868_300_000i64);
+        assert_eq!(cmd["clear"], true);
+
+        assert!(matches!(
+            parse_command(&args("console --since"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("console --since soon"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("errors --after yesterday"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_log_listings_tail() {
+        let cmd = parse_command(&args("console --tail 50"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "console");
+        assert_eq!(cmd["tail"], 50);
+
+        let cmd = parse_command(&args("errors --stack --tail 5"), &default_flags()).unwrap();
+        assert_eq!(cmd["tail"], 5);
+
+        let cmd = parse_command(
+            &args("network requests --type xhr --tail 20"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "requests");
+        assert_eq!(cmd["tail"], 20);
+        assert_eq!(cmd["type"], "xhr");
+
+        let cmd = parse_command(&args("console"), &default_flags()).unwrap();
+        assert!(cmd.get("tail").is_none());
+
+        for input in [
+            "console --tail 0",
+            "errors --tail -3",
+            "network requests --tail all",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+        for input in ["console --tail", "network requests --tail"] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()),
+                    Err(ParseError::MissingArguments { .. })
+                ),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_console_and_errors_grep_and_stack() {
+        let cmd = parse_command(&args("console --grep timeout"), &default_flags()).unwrap();
+        assert_eq!(cmd["grep"], "timeout");
+        assert!(cmd.get("stack").is_none());
+
+        let cmd =
+            parse_command(&args("errors --stack --grep TypeError"), &default_flags()).unwrap();
+        assert_eq!(cmd["grep"], "TypeError");
+        assert_eq!(cmd["stack"], true);
+
+        assert!(matches!(
+            parse_command(&args("errors --grep"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_batch_and_act_progress() {
+        let cmd_args = vec![
+            "batch".to_string(),
+            "--progress".to_string(),
+            "jsonl".to_string(),
+            "--bail".to_string(),
+            "click @e1".to_string(),
+        ];
+        let cmd = parse_command(&cmd_args, &default_flags()).unwrap();
+        assert_eq!(cmd["progress"], "jsonl");
+        assert_eq!(cmd["bail"], true);
+        assert_eq!(cmd["commands"], json!(["click @e1"]));
+
+        let cmd = parse_command(
+            &args("act --progress jsonl --file s.json"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["progress"], "jsonl");
+        assert_eq!(cmd["file"], "s.json");
+
+        assert!(matches!(
+            parse_command(&args("batch --progress"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("act --progress xml"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
+    // === Act Tests ===
+
+    #[test]
+    fn test_act_inline_and_flags() {
+        let cmd_args = vec![
+            "act".to_string(),
+            r#"[{"command": "click @e1"}]"#.to_string(),
+            "--no-final-snapshot".to_string(),
+        ];
+        let cmd = parse_command(&cmd_args, &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "act");
+        assert_eq!(cmd["input"], r#"[{"command": "click @e1"}]"#);
+        assert_eq!(cmd["finalSnapshot"], false);
+        assert!(cmd.get("file").is_none());
+    }
+
+    #[test]
+    fn test_act_file_and_stdin() {
+        let cmd = parse_command(&args("act --file steps.json"), &default_flags()).unwrap();
+        assert_eq!(cmd["file"], "steps.json");
+        assert_eq!(cmd["finalSnapshot"], true);
+
+        let cmd = parse_command(&args("act -"), &default_flags()).unwrap();
+        assert!(cmd.get("input").is_none());
+        assert!(cmd.get("file").is_none());
+    }
+
+    #[test]
+    fn test_act_rejects_bad_arguments() {
+        assert!(matches!(
+            parse_command(&args("act --file"), &default_flags()),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("act [] --file steps.json"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parse_command(&args("act [] []"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
     #[test]
     fn test_auth_login_credential_provider_flags() {
         let cmd = parse_command(
@@ -5882,4 +10296,158 @@ mod tests {
 
         assert!(matches!(err, ParseError::MissingArguments { .. }));
     }
+
+    #[test]
+    fn test_state_save_encrypt_flags() {
+        let cmd = parse_command(&args("state save github"), &default_flags()).unwrap();
+        assert_eq!(cmd["path"], "github");
+        assert!(cmd.get("encrypt").is_none());
+
+        let cmd = parse_command(&args("state save github --encrypt"), &default_flags()).unwrap();
+        assert_eq!(cmd["encrypt"], true);
+
+        let cmd = parse_command(
+            &args("state save --no-encrypt ./auth.json"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["path"], "./auth.json");
+        assert_eq!(cmd["encrypt"], false);
+
+        let err = parse_command(
+            &args("state save github --encrypt --no-encrypt"),
+            &default_flags(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+    }
+
+    #[test]
+    fn test_state_save_and_load_validate_names_before_sending() {
+        for path in ["./backup/auth", "/tmp/auth.state", "../shared/auth.json"] {
+            let cmd = parse_command(&args(&format!("state save {}", path)), &default_flags());
+            assert_eq!(cmd.unwrap()["path"], path);
+        }
+        for bad in [
+            "state save ../../etc",
+            "state save .hidden",
+            "state load ../x",
+        ] {
+            let err = parse_command(&args(bad), &default_flags()).unwrap_err();
+            assert!(matches!(err, ParseError::InvalidValue { .. }), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_state_list_and_show() {
+        let cmd = parse_command(&args("state list"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "state_list");
+
+        let cmd = parse_command(&args("state show work.json"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "state_show");
+        assert_eq!(cmd["path"], "work.json");
+        assert!(matches!(
+            parse_command(&args("state show"), &default_flags()).unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+    }
+
+    #[test]
+    fn test_state_clear_needs_name_or_all() {
+        let cmd = parse_command(&args("state clear --all"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "state_clear");
+        assert_eq!(cmd["all"], true);
+        assert!(cmd.get("name").is_none());
+
+        assert!(matches!(
+            parse_command(&args("state clear"), &default_flags()).unwrap_err(),
+            ParseError::MissingArguments { .. }
+        ));
+        for input in [
+            "state clear work --all",
+            "state clear work other",
+            "state clear --force",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()).unwrap_err(),
+                    ParseError::InvalidValue { .. }
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_state_clean_older_than() {
+        let cmd = parse_command(&args("state clean --older-than 7"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "state_clean");
+        assert_eq!(cmd["days"], 7);
+
+        for input in ["state clean", "state clean --older-than"] {
+            assert!(
+                matches!(
+                    parse_command(&args(input), &default_flags()).unwrap_err(),
+                    ParseError::MissingArguments { .. }
+                ),
+                "{}",
+                input
+            );
+        }
+        for days in ["0", "-3", "1.5", "week"] {
+            let err = parse_command(
+                &args(&format!("state clean --older-than {}", days)),
+                &default_flags(),
+            )
+            .unwrap_err();
+            assert!(
+                err.format().contains("positive whole number of days"),
+                "{}",
+                days
+            );
+        }
+    }
+
+    #[test]
+    fn test_state_clear_and_rename_names() {
+        let cmd = parse_command(&args("state clear work"), &default_flags()).unwrap();
+        assert_eq!(cmd["name"], "work");
+
+        let cmd = parse_command(&args("state rename old new.name"), &default_flags()).unwrap();
+        assert_eq!(cmd["oldName"], "old");
+        assert_eq!(cmd["newName"], "new.name");
+    }
+
+    #[test]
+    fn test_state_checkpoint_and_restore() {
+        let cmd = parse_command(&args("state checkpoint"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "state_checkpoint");
+        assert!(cmd.get("label").is_none());
+
+        let cmd = parse_command(&args("state checkpoint pre-pay"), &default_flags()).unwrap();
+        assert_eq!(cmd["label"], "pre-pay");
+
+        let cmd = parse_command(&args("state restore"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "state_restore");
+        assert!(cmd.get("label").is_none());
+
+        let cmd = parse_command(&args("state restore pre-pay"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "state_restore");
+        assert_eq!(cmd["label"], "pre-pay");
+
+        let cmd = parse_command(&args("state restore --list"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "state_checkpoints");
+
+        for bad in [
+            "state checkpoint a b",
+            "state checkpoint --list",
+            "state restore --bogus",
+            "state checkpoint ../x",
+            "state restore a/b",
+        ] {
+            let err = parse_command(&args(bad), &default_flags()).unwrap_err();
+            assert!(matches!(err, ParseError::InvalidValue { .. }), "{}", bad);
+        }
+    }
 }
@@ -1,4 +1,8 @@
+use crate::atomic_write::{write_atomic, write_private};
 use crate::validation::sanitize_session_component;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::env;
@@ -15,9 +19,11 @@ use std::time::{Duration, Instant};
 use std::os::unix::net::UnixStream;
 
 #[cfg(windows)]
-use windows_sys::Win32::Foundation::CloseHandle;
+use windows_sys::Win32::Foundation::{CloseHandle, FILETIME};
 #[cfg(windows)]
-use windows_sys::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+use windows_sys::Win32::System::Threading::{
+    GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+};
 
 pub(crate) const INTERNAL_DAEMON_SHUTDOWN_ACTION: &str = "__agent_browser_internal_shutdown";
 
@@ -37,6 +43,33 @@ pub struct Response {
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub warning: Option<String>,
+    /// Non-fatal notes for a command, from the daemon (deprecated syntax,
+    /// CSP violations, slow network) or the CLI (launch options a running
+    /// daemon ignored). Older daemons omit the field. Never affects the exit
+    /// code.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// What a mutating action did, added by the CLI in `--json` mode: the
+    /// action, selector, resolved ref, role, name, and duration. Never sent
+    /// by the daemon.
+    #[serde(rename = "_action", default, skip_serializing_if = "Option::is_none")]
+    pub action_echo: Option<Value>,
+    /// Which side failed: `protocol`, `page`, `browser`, or `internal`. Sent
+    /// by newer daemons; the CLI fills it in from the message otherwise.
+    #[serde(rename = "errorKind", default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+    /// The CLI error-code taxonomy entry for a failure (`page_error`,
+    /// `protocol_error`, ...), added by the CLI. Never sent by the daemon.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub error_type: Option<String>,
+    /// Set by the CLI when the response was replayed from the `--cache`
+    /// store instead of the daemon. Never sent by the daemon.
+    #[serde(
+        rename = "_cached",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub cached: bool,
 }
 
 #[allow(dead_code)]
@@ -153,6 +186,135 @@ fn get_config_path(session: &str) -> PathBuf {
     get_socket_dir().join(format!("{}.config", session))
 }
 
+fn get_cdp_target_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.cdp", session))
+}
+
+pub fn get_timeouts_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.timeouts", session))
+}
+
+fn get_flags_warning_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.warned", session))
+}
+
+/// Fingerprint of a set of ignored flag names, independent of their order.
+fn ignored_flags_hash(ignored: &[&str]) -> String {
+    let mut names = ignored.to_vec();
+    names.sort_unstable();
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    names.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Whether a `.warned` marker (`<hash> <unix ms>`) records the same ignored
+/// flags less than `window_ms` before `now_ms`.
+fn flags_warning_recent(marker: &str, hash: &str, now_ms: u64, window_ms: u64) -> bool {
+    let mut parts = marker.split_whitespace();
+    let (Some(seen_hash), Some(seen_at)) = (parts.next(), parts.next()) else {
+        return false;
+    };
+    let Ok(seen_at) = seen_at.parse::<u64>() else {
+        return false;
+    };
+    seen_hash == hash && now_ms >= seen_at && now_ms - seen_at < window_ms
+}
+
+/// Whether the "flags ignored: daemon already running" warning should be
+/// shown for `session`. Repeats of the same flag set within `window_ms` of
+/// the last shown warning are suppressed, so a chain of commands sharing one
+/// alias warns once. Showing the warning restarts the window.
+pub fn should_show_ignored_flags_warning(session: &str, ignored: &[&str], window_ms: u64) -> bool {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let hash = ignored_flags_hash(ignored);
+    let path = get_flags_warning_path(session);
+    if let Ok(marker) = fs::read_to_string(&path) {
+        if flags_warning_recent(&marker, &hash, now_ms, window_ms) {
+            return false;
+        }
+    }
+    let _ = write_atomic(&path, format!("{} {}\n", hash, now_ms));
+    true
+}
+
+/// The timeouts a session's daemon applies after `set timeout`, from the
+/// `.timeouts` file it writes. Empty until `set timeout` changes them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTimeouts {
+    pub default_timeout: Option<u64>,
+    pub navigation_timeout: Option<u64>,
+}
+
+pub fn read_session_timeouts(session: &str) -> SessionTimeouts {
+    fs::read_to_string(get_timeouts_path(session))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// The CDP connection fields (`cdpUrl` or `cdpPort`) of a launch command.
+pub fn cdp_target_from_launch(launch_cmd: &Value) -> Option<Value> {
+    if let Some(url) = launch_cmd.get("cdpUrl").and_then(|v| v.as_str()) {
+        return Some(json!({ "cdpUrl": url }));
+    }
+    launch_cmd
+        .get("cdpPort")
+        .and_then(|v| v.as_u64())
+        .filter(|p| (1..=u16::MAX as u64).contains(p))
+        .map(|port| json!({ "cdpPort": port }))
+}
+
+/// Remember the CDP target of a successful launch so a dropped remote
+/// connection can be re-established without the user repeating `--cdp`.
+/// The URL may carry provider API keys, so the file is owner-only.
+pub fn save_cdp_target(session: &str, launch_cmd: &Value) {
+    let Some(target) = cdp_target_from_launch(launch_cmd) else {
+        return;
+    };
+    let path = get_cdp_target_path(session);
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = write_private(&path, target.to_string());
+}
+
+/// The last CDP target saved for `session`, as launch-command fields.
+pub fn load_cdp_target(session: &str) -> Option<Value> {
+    let raw = fs::read_to_string(get_cdp_target_path(session)).ok()?;
+    let saved: Value = serde_json::from_str(&raw).ok()?;
+    cdp_target_from_launch(&saved)
+}
+
+/// True when a daemon error means the browser's CDP connection dropped, as
+/// opposed to the daemon itself being unreachable (see daemon_unreachable).
+pub fn is_browser_disconnected_error(error: &str) -> bool {
+    let lower = error.to_lowercase();
+    lower.contains("cdp response channel closed")
+        || lower.contains("failed to send cdp command")
+        || lower.contains("failed to send raw cdp message")
+        || lower.contains("browser disconnected")
+        || lower.contains("browser has disconnected")
+        || lower.contains("connection closed normally")
+        || lower.contains("trying to work with closed connection")
+}
+
+/// `AGENT_BROWSER_NO_AUTO_RECONNECT=1` turns off automatic CDP reconnection.
+pub fn auto_reconnect_disabled() -> bool {
+    env::var("AGENT_BROWSER_NO_AUTO_RECONNECT")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Whether a failed command should trigger one reconnect to the saved CDP
+/// target before the error is reported.
+pub fn should_auto_reconnect(error: &str, has_saved_target: bool, disabled: bool) -> bool {
+    !disabled && has_saved_target && is_browser_disconnected_error(error)
+}
+
 /// Clean up stale socket and PID files for a session
 pub fn cleanup_stale_files(session: &str) {
     let pid_path = get_pid_path(session);
@@ -163,6 +325,9 @@ pub fn cleanup_stale_files(session: &str) {
     let _ = fs::remove_file(&config_path);
     let stream_path = get_socket_dir().join(format!("{}.stream", session));
     let _ = fs::remove_file(&stream_path);
+    let _ = fs::remove_file(get_cdp_target_path(session));
+    let _ = fs::remove_file(get_timeouts_path(session));
+    let _ = fs::remove_file(get_flags_warning_path(session));
 
     #[cfg(unix)]
     {
@@ -202,6 +367,156 @@ pub fn is_pid_alive(pid: u32) -> bool {
     }
 }
 
+/// Opaque start time of a running process (clock ticks since boot on Linux,
+/// creation FILETIME on Windows), or None where it can't be read. Recorded
+/// in the `.pid` file so a recycled pid can be told apart from the daemon.
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // The command name may contain spaces, so count fields from the
+        // closing paren; starttime is field 22 of the stat line.
+        let after_comm = &stat[stat.rfind(')')? + 1..];
+        after_comm.split_whitespace().nth(19)?.parse().ok()
+    }
+    #[cfg(windows)]
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle == 0 {
+            return None;
+        }
+        let zero = || FILETIME {
+            dwLowDateTime: 0,
+            dwHighDateTime: 0,
+        };
+        let (mut created, mut exited, mut kernel, mut user) = (zero(), zero(), zero(), zero());
+        let ok = GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user);
+        CloseHandle(handle);
+        (ok != 0).then(|| ((created.dwHighDateTime as u64) << 32) | created.dwLowDateTime as u64)
+    }
+    #[cfg(not(any(target_os = "linux", windows)))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// Contents of a session's `.pid` file: `<pid>`, or `<pid> <start-time>`
+/// when the daemon could read its own start time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PidRecord {
+    pub pid: u32,
+    pub started: Option<u64>,
+}
+
+impl PidRecord {
+    /// The record for the calling process, written by the daemon at startup.
+    pub fn current() -> Self {
+        let pid = std::process::id();
+        PidRecord {
+            pid,
+            started: process_start_time(pid),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Option<Self> {
+        let mut fields = contents.split_whitespace();
+        let pid = fields.next()?.parse().ok()?;
+        let started = match fields.next() {
+            Some(value) => Some(value.parse().ok()?),
+            None => None,
+        };
+        Some(PidRecord { pid, started })
+    }
+}
+
+impl std::fmt::Display for PidRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.started {
+            Some(started) => write!(f, "{} {}", self.pid, started),
+            None => write!(f, "{}", self.pid),
+        }
+    }
+}
+
+fn read_pid_record(session: &str) -> Option<PidRecord> {
+    PidRecord::parse(&fs::read_to_string(get_pid_path(session)).ok()?)
+}
+
+/// How long after the `.pid` file is written a daemon may still be binding
+/// its socket or port; until then a refused connection is not a dead daemon.
+const DAEMON_STARTUP_GRACE: Duration = Duration::from_secs(15);
+
+/// Outcome of [`session_liveness`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liveness {
+    /// The process is the daemon and it accepts connections.
+    Alive,
+    /// The process is running but not accepting connections yet, within the
+    /// startup grace period.
+    Starting,
+    /// No process with the recorded pid exists.
+    ProcessGone,
+    /// The pid now belongs to a process with a different start time.
+    PidReused,
+    /// The process exists but never started accepting connections (a reused
+    /// pid whose start time wasn't recorded, or a daemon that failed to bind).
+    NotListening,
+}
+
+/// True when the recorded start time no longer matches the process now
+/// holding the pid. Unknown start times never count as reuse.
+fn pid_was_reused(record: PidRecord, start_time: impl Fn(u32) -> Option<u64>) -> bool {
+    matches!(
+        (record.started, start_time(record.pid)),
+        (Some(recorded), Some(actual)) if recorded != actual
+    )
+}
+
+/// Liveness decision with the probes injected, so pid reuse can be tested
+/// without real processes.
+fn check_liveness(
+    record: PidRecord,
+    pid_file_age: Option<Duration>,
+    pid_alive: impl Fn(u32) -> bool,
+    start_time: impl Fn(u32) -> Option<u64>,
+    accepts_connection: impl Fn() -> bool,
+) -> Liveness {
+    if !pid_alive(record.pid) {
+        return Liveness::ProcessGone;
+    }
+    if pid_was_reused(record, start_time) {
+        return Liveness::PidReused;
+    }
+    if accepts_connection() {
+        Liveness::Alive
+    } else if pid_file_age.is_some_and(|age| age < DAEMON_STARTUP_GRACE) {
+        Liveness::Starting
+    } else {
+        Liveness::NotListening
+    }
+}
+
+/// Whether the daemon recorded in a session's `.pid` file is really running:
+/// the pid must be alive, must still belong to the process that wrote the
+/// file, and the session's socket (or port on Windows) must accept a
+/// connection. This is the check behind `session list`, `session info`, and
+/// stale-file cleanup; [`ensure_daemon`] deliberately stays socket-only (see
+/// the comment there).
+pub fn session_liveness(session: &str, record: PidRecord) -> Liveness {
+    let pid_file_age = fs::metadata(get_pid_path(session))
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok());
+    check_liveness(
+        record,
+        pid_file_age,
+        is_pid_alive,
+        process_start_time,
+        || daemon_ready(session),
+    )
+}
+
 /// A currently-running daemon session discovered by [`walk_daemons`].
 #[derive(Debug, Clone)]
 pub struct ActiveSession {
@@ -216,6 +531,10 @@ pub struct ActiveSession {
 pub enum CleanReason {
     /// The `.pid` file referenced a process that no longer exists.
     ProcessGone,
+    /// The pid in the `.pid` file now belongs to a different process.
+    PidReused,
+    /// The process was alive but never accepted connections.
+    NotListening,
     /// The `.pid` file could not be parsed as a PID.
     UnreadablePidFile,
     /// A `.sock` file had no corresponding `.pid` file (unix only).
@@ -258,9 +577,11 @@ pub fn read_session_version(session: &str) -> Option<String> {
 
 /// Walk the socket directory and classify each `.pid` / `.sock` entry.
 ///
-/// - Live daemons go into `sessions` with their `.version` file contents.
-/// - Stale entries (process gone, unreadable pid, orphaned `.sock`) are
-///   cleaned via [`cleanup_stale_files`] and recorded in `cleaned`.
+/// - Live daemons (per [`session_liveness`]) go into `sessions` with their
+///   `.version` file contents.
+/// - Stale entries (process gone, reused pid, not listening, unreadable pid,
+///   orphaned `.sock`) are cleaned via [`cleanup_stale_files`] and recorded
+///   in `cleaned`.
 /// - `dashboard.pid` lands in `dashboard` with liveness info; if the
 ///   process is gone, the pid file is removed and a `DashboardGone` entry
 ///   is added to `cleaned`.
@@ -301,11 +622,11 @@ pub fn walk_daemons() -> DaemonInventory {
             _ => continue,
         };
 
-        let pid = match fs::read_to_string(entry.path())
+        let record = match fs::read_to_string(entry.path())
             .ok()
-            .and_then(|s| s.trim().parse::<u32>().ok())
+            .and_then(|s| PidRecord::parse(&s))
         {
-            Some(p) => p,
+            Some(r) => r,
             None => {
                 cleanup_stale_files(&session_name);
                 inventory.cleaned.push(CleanedSession {
@@ -316,11 +637,20 @@ pub fn walk_daemons() -> DaemonInventory {
             }
         };
 
-        if !is_pid_alive(pid) {
+        let reason = match session_liveness(&session_name, record) {
+            Liveness::Alive | Liveness::Starting => None,
+            Liveness::ProcessGone => Some(CleanReason::ProcessGone),
+            Liveness::PidReused => Some(CleanReason::PidReused),
+            Liveness::NotListening => Some(CleanReason::NotListening),
+        };
+        if let Some(reason) = reason {
             cleanup_stale_files(&session_name);
+            // The session is gone for good, unlike the pre-launch cleanup,
+            // so its notes go too.
+            crate::notes::remove(&session_name);
             inventory.cleaned.push(CleanedSession {
                 name: session_name,
-                reason: CleanReason::ProcessGone,
+                reason,
             });
             continue;
         }
@@ -328,7 +658,7 @@ pub fn walk_daemons() -> DaemonInventory {
         let version = read_session_version(&session_name);
         inventory.sessions.push(ActiveSession {
             name: session_name,
-            pid,
+            pid: record.pid,
             version,
         });
     }
@@ -396,6 +726,20 @@ pub fn resolve_port(session: &str) -> u16 {
         .unwrap_or_else(|| get_port_for_session(session))
 }
 
+/// Where a session's daemon listens, as `--json` fields: `socket` with the
+/// socket path on unix, `port` on Windows.
+pub fn session_endpoint(session: &str) -> serde_json::Map<String, Value> {
+    let mut endpoint = serde_json::Map::new();
+    #[cfg(unix)]
+    endpoint.insert(
+        "socket".to_string(),
+        Value::from(get_socket_path(session).to_string_lossy()),
+    );
+    #[cfg(windows)]
+    endpoint.insert("port".to_string(), Value::from(resolve_port(session)));
+    endpoint
+}
+
 pub fn daemon_ready(session: &str) -> bool {
     #[cfg(unix)]
     {
@@ -426,6 +770,7 @@ pub struct DaemonResult {
 /// Note: `confirm_interactive` is intentionally absent -- it is a CLI-side
 /// UX concern (prompting the user on stdin) and not a daemon configuration.
 /// The daemon only needs `confirm_actions` to gate action categories.
+#[derive(Clone)]
 pub struct DaemonOptions<'a> {
     pub headed: bool,
     pub debug: bool,
@@ -440,12 +785,17 @@ pub struct DaemonOptions<'a> {
     pub proxy_username: Option<&'a str>,
     pub proxy_password: Option<&'a str>,
     pub ignore_https_errors: bool,
+    /// Origins whose certificate errors are ignored, for AGENT_BROWSER_IGNORE_HTTPS_ORIGINS.
+    pub ignore_https_origins: &'a [String],
     pub allow_file_access: bool,
     pub hide_scrollbars: bool,
     pub webgpu: bool,
     pub profile: Option<&'a str>,
+    pub user_data_dir: Option<&'a str>,
     pub state: Option<&'a str>,
     pub provider: Option<&'a str>,
+    /// `--provider-opt` values as a JSON object, for AGENT_BROWSER_PROVIDER_OPTS.
+    pub provider_options: Option<&'a str>,
     pub device: Option<&'a str>,
     pub session_name: Option<&'a str>,
     pub restore_save: Option<&'a str>,
@@ -508,6 +858,12 @@ fn apply_daemon_env(cmd: &mut Command, session: &str, opts: &DaemonOptions) {
     if opts.ignore_https_errors {
         cmd.env("AGENT_BROWSER_IGNORE_HTTPS_ERRORS", "1");
     }
+    if !opts.ignore_https_origins.is_empty() {
+        cmd.env(
+            "AGENT_BROWSER_IGNORE_HTTPS_ORIGINS",
+            opts.ignore_https_origins.join(","),
+        );
+    }
     if opts.allow_file_access {
         cmd.env("AGENT_BROWSER_ALLOW_FILE_ACCESS", "1");
     }
@@ -521,12 +877,18 @@ fn apply_daemon_env(cmd: &mut Command, session: &str, opts: &DaemonOptions) {
     if let Some(prof) = opts.profile {
         cmd.env("AGENT_BROWSER_PROFILE", prof);
     }
+    if let Some(dir) = opts.user_data_dir {
+        cmd.env("AGENT_BROWSER_USER_DATA_DIR", dir);
+    }
     if let Some(st) = opts.state {
         cmd.env("AGENT_BROWSER_STATE", st);
     }
     if let Some(p) = opts.provider {
         cmd.env("AGENT_BROWSER_PROVIDER", p);
     }
+    if let Some(po) = opts.provider_options {
+        cmd.env("AGENT_BROWSER_PROVIDER_OPTS", po);
+    }
     if let Some(d) = opts.device {
         cmd.env("AGENT_BROWSER_IOS_DEVICE", d);
     }
@@ -613,14 +975,11 @@ fn daemon_config_matches(session: &str, opts: &DaemonOptions) -> bool {
 }
 
 fn write_daemon_config(session: &str, opts: &DaemonOptions) {
-    let _ = fs::write(get_config_path(session), daemon_config_fingerprint(opts));
+    let _ = write_atomic(get_config_path(session), daemon_config_fingerprint(opts));
 }
 
 fn daemon_pid_matches(session: &str, expected_pid: u32) -> bool {
-    fs::read_to_string(get_pid_path(session))
-        .ok()
-        .and_then(|pid| pid.trim().parse::<u32>().ok())
-        == Some(expected_pid)
+    read_pid_record(session).map(|r| r.pid) == Some(expected_pid)
 }
 
 fn ready_spawned_daemon_result(
@@ -689,6 +1048,14 @@ fn concurrent_daemon_config_error(session: &str) -> String {
     )
 }
 
+/// The version a session's daemon wrote at startup, if it is running.
+pub fn daemon_version(session: &str) -> Option<String> {
+    fs::read_to_string(get_version_path(session))
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
 /// Check if the running daemon's version matches this CLI binary.
 /// Returns false when the version file is missing — an unversioned daemon
 /// is most likely a stale leftover from before version tracking was added
@@ -712,9 +1079,10 @@ fn kill_stale_daemon(session: &str) {
         let _ = fs::remove_file(&socket_path);
     }
 
-    let pid_path = get_pid_path(session);
-    if let Ok(pid_str) = fs::read_to_string(&pid_path) {
-        if let Ok(pid) = pid_str.trim().parse::<u32>() {
+    if let Some(record) = read_pid_record(session) {
+        // Never signal a process that merely inherited the daemon's pid.
+        if !pid_was_reused(record, process_start_time) {
+            let pid = record.pid;
             #[cfg(unix)]
             {
                 unsafe {
@@ -906,9 +1274,35 @@ pub fn ensure_daemon(session: &str, opts: &DaemonOptions) -> Result<DaemonResult
         );
     }
 
+    wait_for_spawned_daemon(
+        session,
+        opts,
+        daemon_child.as_mut(),
+        restarted,
+        DAEMON_READY_TIMEOUT,
+    )
+}
+
+/// How long a freshly spawned daemon has to start accepting connections.
+const DAEMON_READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Daemon log lines quoted when a `--debug` daemon fails to start.
+const STARTUP_LOG_TAIL_LINES: usize = 20;
+
+/// Poll a freshly spawned daemon until it accepts connections. Stops as soon
+/// as the child exits, reporting its exit status and output, rather than
+/// waiting out `timeout`.
+fn wait_for_spawned_daemon(
+    session: &str,
+    opts: &DaemonOptions,
+    mut daemon_child: Option<&mut std::process::Child>,
+    restarted: bool,
+    timeout: Duration,
+) -> Result<DaemonResult, String> {
     let spawned_pid = daemon_child.as_ref().map(|child| child.id());
+    let start = Instant::now();
 
-    for _ in 0..50 {
+    loop {
         if daemon_ready(session) {
             if let Some(result) = ready_spawned_daemon_result(session, opts, spawned_pid, restarted)
             {
@@ -925,7 +1319,7 @@ pub fn ensure_daemon(session: &str, opts: &DaemonOptions) -> Result<DaemonResult
 
         // Detect early daemon exit and surface the real error from stderr
         if let Some(ref mut child) = daemon_child {
-            if let Ok(Some(_)) = child.try_wait() {
+            if let Ok(Some(status)) = child.try_wait() {
                 let mut stderr_output = String::new();
                 if let Some(mut stderr) = child.stderr.take() {
                     let _ = stderr.read_to_string(&mut stderr_output);
@@ -950,6 +1344,10 @@ pub fn ensure_daemon(session: &str, opts: &DaemonOptions) -> Result<DaemonResult
                     }
                 }
 
+                let mut message = format!(
+                    "Daemon process exited during startup ({})",
+                    describe_exit_status(status)
+                );
                 if !stderr_trimmed.is_empty() {
                     let msg = if stderr_trimmed.len() > 500 {
                         let mut end = 500;
@@ -960,16 +1358,27 @@ pub fn ensure_daemon(session: &str, opts: &DaemonOptions) -> Result<DaemonResult
                     } else {
                         stderr_trimmed
                     };
-                    return Err(format!("Daemon process exited during startup:\n{}", msg));
+                    message.push_str(":\n");
+                    message.push_str(msg);
                 }
-                return Err(
-                    "Daemon process exited during startup with no error output. \
-                     Re-run with --debug for more details."
-                        .to_string(),
-                );
+                match startup_log_tail(session, opts) {
+                    Some(tail) => {
+                        message.push_str(&format!("\n\nDaemon log ({}):\n{}", tail.0, tail.1));
+                    }
+                    None if stderr_trimmed.is_empty() => {
+                        message.push_str(
+                            " with no error output. Re-run with --debug for more details.",
+                        );
+                    }
+                    None => {}
+                }
+                return Err(message);
             }
         }
 
+        if start.elapsed() >= timeout {
+            break;
+        }
         thread::sleep(Duration::from_millis(100));
     }
 
@@ -981,7 +1390,54 @@ pub fn ensure_daemon(session: &str, opts: &DaemonOptions) -> Result<DaemonResult
     #[cfg(windows)]
     let endpoint_info = format!("port: 127.0.0.1:{}", resolve_port(session));
 
-    Err(format!("Daemon failed to start ({})", endpoint_info))
+    match spawned_pid {
+        // Still alive: retrying would only stack up another stuck daemon.
+        Some(pid) => {
+            let mut message = format!(
+                "Daemon running (pid {}) but not accepting connections after {}s ({}). Run `agent-browser --session {} close` or kill it, then retry.",
+                pid,
+                timeout.as_secs_f64(),
+                endpoint_info,
+                session
+            );
+            if let Some((path, tail)) = startup_log_tail(session, opts) {
+                message.push_str(&format!("\n\nDaemon log ({}):\n{}", path, tail));
+            }
+            Err(message)
+        }
+        None => Err(format!("Daemon failed to start ({})", endpoint_info)),
+    }
+}
+
+/// "exit code N", or the signal that killed the process.
+fn describe_exit_status(status: std::process::ExitStatus) -> String {
+    if let Some(code) = status.code() {
+        return format!("exit code {}", code);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return format!("killed by signal {}", signal);
+        }
+    }
+    "no exit code".to_string()
+}
+
+/// The last lines of the session's daemon log, which only a `--debug`
+/// daemon writes, with the log's path.
+fn startup_log_tail(session: &str, opts: &DaemonOptions) -> Option<(String, String)> {
+    if !opts.debug {
+        return None;
+    }
+    let path = get_socket_dir().join(format!("{}.log", session));
+    let text = fs::read_to_string(&path).ok()?;
+    let lines: Vec<&str> = text.lines().collect();
+    let tail = lines[lines.len().saturating_sub(STARTUP_LOG_TAIL_LINES)..].join("\n");
+    if tail.trim().is_empty() {
+        return None;
+    }
+    Some((path.display().to_string(), tail))
 }
 
 fn connect(session: &str) -> Result<Connection, String> {
@@ -1086,31 +1542,170 @@ fn has_os_error(error: &str, code: u32) -> bool {
 /// instead of 30s. Only commands that actually carry a `timeout` field get
 /// the extended budget, and that field is set client-side per invocation,
 /// avoiding the daemon's spawn-time env snapshot drifting from the client.
-fn read_timeout_for(cmd: &Value) -> Duration {
-    let op_ms = cmd.get("timeout").and_then(|v| v.as_u64()).unwrap_or(0);
-    Duration::from_millis(op_ms.saturating_add(10_000).max(30_000))
+/// A `find --wait` budget (`waitFor`) is added on top, since the subaction
+/// only starts once the wait is over.
+///
+/// A navigation timeout raised with `set timeout --navigation` raises the
+/// floor for every command, since clicks and form submits can wait on a
+/// navigation too.
+fn read_timeout_for(cmd: &Value, timeouts: &SessionTimeouts) -> Duration {
+    let field = |key: &str| cmd.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    let op_ms = field("timeout").saturating_add(field("waitFor"));
+    let floor_ms = timeouts
+        .navigation_timeout
+        .map_or(30_000, |ms| ms.saturating_add(10_000).max(30_000));
+    Duration::from_millis(op_ms.saturating_add(10_000).max(floor_ms))
 }
 
-fn send_command_once(cmd: &Value, session: &str) -> Result<Response, String> {
-    let mut stream = connect(session)?;
+/// Payloads at least this large are gzipped on the socket when the other
+/// side supports it; smaller ones stay plain JSON lines.
+pub const COMPRESSION_THRESHOLD: usize = 64 * 1024;
+
+/// Largest compressed frame either side will read, so a corrupt header
+/// cannot make it allocate without bound.
+const MAX_FRAME_BYTES: usize = 1 << 30;
+
+/// Request field asking the daemon to gzip a large response.
+pub const ACCEPT_ENCODING_FIELD: &str = "acceptEncoding";
+
+pub const GZIP_ENCODING: &str = "gzip";
+
+/// The line that precedes a compressed payload on the socket:
+/// `{"encoding":"gzip","length":N}`, then exactly N gzip bytes.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FrameHeader {
+    encoding: String,
+    length: usize,
+}
 
-    stream.set_read_timeout(Some(read_timeout_for(cmd))).ok();
-    stream.set_write_timeout(Some(Duration::from_secs(5))).ok();
+/// The compressed length a frame header line announces, or `None` when the
+/// line is a plain JSON message.
+pub fn parse_frame_header(line: &str) -> Result<Option<usize>, String> {
+    // Only header lines start this way, so plain messages (which can be
+    // megabytes) are never parsed twice.
+    if !line.trim_start().starts_with("{\"encoding\"") {
+        return Ok(None);
+    }
+    let header: FrameHeader = match serde_json::from_str(line.trim()) {
+        Ok(header) => header,
+        Err(_) => return Ok(None),
+    };
+    if header.encoding != GZIP_ENCODING {
+        return Err(format!("Unsupported encoding '{}'", header.encoding));
+    }
+    if header.length > MAX_FRAME_BYTES {
+        return Err(format!(
+            "Compressed frame of {} bytes exceeds the {} byte limit",
+            header.length, MAX_FRAME_BYTES
+        ));
+    }
+    Ok(Some(header.length))
+}
 
-    let mut json_str = serde_json::to_string(cmd).map_err(|e| e.to_string())?;
-    json_str.push('\n');
+/// A JSON message ready to write: a plain line, or a gzip frame when
+/// `compress` is set and the message reaches [`COMPRESSION_THRESHOLD`].
+pub fn encode_frame(json: &str, compress: bool) -> Vec<u8> {
+    if compress && json.len() >= COMPRESSION_THRESHOLD {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        if encoder.write_all(json.as_bytes()).is_ok() {
+            if let Ok(body) = encoder.finish() {
+                let mut frame = json!({ "encoding": GZIP_ENCODING, "length": body.len() })
+                    .to_string()
+                    .into_bytes();
+                frame.push(b'\n');
+                frame.extend_from_slice(&body);
+                return frame;
+            }
+        }
+    }
+    let mut frame = Vec::with_capacity(json.len() + 1);
+    frame.extend_from_slice(json.as_bytes());
+    frame.push(b'\n');
+    frame
+}
 
-    stream
-        .write_all(json_str.as_bytes())
-        .map_err(|e| format!("Failed to send: {}", e))?;
+/// The JSON text inside a gzip frame body.
+pub fn decode_frame_body(body: &[u8]) -> Result<String, String> {
+    let mut text = String::new();
+    GzDecoder::new(body)
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Invalid compressed frame: {}", e))?;
+    Ok(text)
+}
 
-    let mut reader = BufReader::new(stream);
-    let mut response_line = String::new();
+/// Read one message in either framing.
+fn read_frame<R: BufRead>(reader: &mut R) -> Result<String, String> {
+    let mut line = String::new();
     reader
-        .read_line(&mut response_line)
+        .read_line(&mut line)
         .map_err(|e| format!("Failed to read: {}", e))?;
+    match parse_frame_header(&line)? {
+        None => Ok(line),
+        Some(length) => {
+            let mut body = vec![0; length];
+            reader
+                .read_exact(&mut body)
+                .map_err(|e| format!("Failed to read: {}", e))?;
+            decode_frame_body(&body)
+        }
+    }
+}
+
+fn send_command_once(cmd: &Value, session: &str) -> Result<Response, String> {
+    DaemonConnection::open(session)?.send(cmd)
+}
+
+/// One socket to a session's daemon, reused for several commands. The daemon
+/// answers newline-delimited requests on a connection in order, so a caller
+/// can run a sequence of commands without reconnecting between them. Unlike
+/// [`send_command`], errors are not retried.
+///
+/// Every request asks for gzip responses, which older daemons ignore. Large
+/// requests are only gzipped when the daemon's version file matches this
+/// build, since an older daemon would reject the framing.
+pub struct DaemonConnection {
+    reader: BufReader<Connection>,
+    compress_requests: bool,
+    timeouts: SessionTimeouts,
+}
+
+impl DaemonConnection {
+    pub fn open(session: &str) -> Result<Self, String> {
+        let connection = connect(session)?;
+        let mut conn = Self::new(connection, daemon_version_matches(session));
+        conn.timeouts = read_session_timeouts(session);
+        Ok(conn)
+    }
+
+    fn new(connection: Connection, compress_requests: bool) -> Self {
+        Self {
+            reader: BufReader::new(connection),
+            compress_requests,
+            timeouts: SessionTimeouts::default(),
+        }
+    }
 
-    serde_json::from_str(&response_line).map_err(|e| format!("Invalid response: {}", e))
+    pub fn send(&mut self, cmd: &Value) -> Result<Response, String> {
+        let stream = self.reader.get_mut();
+        stream
+            .set_read_timeout(Some(read_timeout_for(cmd, &self.timeouts)))
+            .ok();
+        stream.set_write_timeout(Some(Duration::from_secs(5))).ok();
+
+        let mut request = cmd.clone();
+        if let Some(obj) = request.as_object_mut() {
+            obj.insert(ACCEPT_ENCODING_FIELD.to_string(), json!(GZIP_ENCODING));
+        }
+        let json_str = serde_json::to_string(&request).map_err(|e| e.to_string())?;
+
+        stream
+            .write_all(&encode_frame(&json_str, self.compress_requests))
+            .map_err(|e| format!("Failed to send: {}", e))?;
+
+        let response = read_frame(&mut self.reader)?;
+        serde_json::from_str(&response).map_err(|e| format!("Invalid response: {}", e))
+    }
 }
 
 #[cfg(test)]
@@ -1118,6 +1713,190 @@ mod tests {
     use super::*;
     use crate::test_utils::EnvGuard;
 
+    #[test]
+    fn test_response_deserializes_with_and_without_warnings() {
+        let resp: Response = serde_json::from_str(
+            r#"{"success":true,"data":{"url":"https://a.test"},"error":null}"#,
+        )
+        .unwrap();
+        assert!(resp.success);
+        assert!(resp.warnings.is_empty());
+
+        let resp: Response = serde_json::from_str(
+            r#"{"success":true,"data":null,"error":null,"warnings":["slow network","CSP violation"]}"#,
+        )
+        .unwrap();
+        assert_eq!(resp.warnings, vec!["slow network", "CSP violation"]);
+    }
+
+    #[test]
+    fn test_read_timeout_for_covers_operation_and_find_wait() {
+        let secs = |cmd: Value| read_timeout_for(&cmd, &SessionTimeouts::default()).as_secs();
+        assert_eq!(secs(serde_json::json!({ "action": "url" })), 30);
+        assert_eq!(secs(serde_json::json!({ "timeout": 60_000 })), 70);
+        assert_eq!(
+            secs(serde_json::json!({ "action": "getbyrole", "waitFor": 45_000 })),
+            55
+        );
+        assert_eq!(
+            secs(serde_json::json!({ "timeout": 25_000, "waitFor": 25_000 })),
+            60
+        );
+    }
+
+    #[test]
+    fn test_read_timeout_for_raises_floor_with_navigation_timeout() {
+        let timeouts = SessionTimeouts {
+            default_timeout: Some(5_000),
+            navigation_timeout: Some(120_000),
+        };
+        let secs = |cmd: Value| read_timeout_for(&cmd, &timeouts).as_secs();
+        assert_eq!(secs(serde_json::json!({ "action": "click" })), 130);
+        assert_eq!(secs(serde_json::json!({ "timeout": 300_000 })), 310);
+        // A tightened navigation timeout never drops below the usual floor.
+        let short = SessionTimeouts {
+            default_timeout: None,
+            navigation_timeout: Some(1_000),
+        };
+        assert_eq!(
+            read_timeout_for(&serde_json::json!({ "action": "open" }), &short).as_secs(),
+            30
+        );
+    }
+
+    #[test]
+    fn test_read_session_timeouts() {
+        let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = std::env::temp_dir().join(format!("ab-timeouts-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        guard.set("AGENT_BROWSER_SOCKET_DIR", dir.to_str().unwrap());
+
+        assert_eq!(read_session_timeouts("t"), SessionTimeouts::default());
+        fs::write(
+            get_timeouts_path("t"),
+            r#"{"defaultTimeout":2000,"navigationTimeout":60000}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            read_session_timeouts("t"),
+            SessionTimeouts {
+                default_timeout: Some(2000),
+                navigation_timeout: Some(60000),
+            }
+        );
+        fs::write(get_timeouts_path("t"), "garbage").unwrap();
+        assert_eq!(read_session_timeouts("t"), SessionTimeouts::default());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_frame_header() {
+        assert_eq!(parse_frame_header("{\"success\":true}\n"), Ok(None));
+        assert_eq!(parse_frame_header(""), Ok(None));
+        assert_eq!(
+            parse_frame_header("{\"encoding\":\"gzip\",\"length\":42}\n"),
+            Ok(Some(42))
+        );
+        assert!(parse_frame_header("{\"encoding\":\"br\",\"length\":42}").is_err());
+        assert!(parse_frame_header(&format!(
+            "{{\"encoding\":\"gzip\",\"length\":{}}}",
+            MAX_FRAME_BYTES + 1
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_encode_frame_compresses_only_large_payloads() {
+        let small = r#"{"action":"url"}"#;
+        assert_eq!(
+            encode_frame(small, true),
+            format!("{}\n", small).into_bytes()
+        );
+
+        let large = json!({ "data": "x".repeat(COMPRESSION_THRESHOLD) }).to_string();
+        assert_eq!(
+            encode_frame(&large, false),
+            format!("{}\n", large).into_bytes()
+        );
+
+        let frame = encode_frame(&large, true);
+        assert!(frame.len() < large.len() / 10);
+        let mut reader = BufReader::new(frame.as_slice());
+        assert_eq!(read_frame(&mut reader).unwrap(), large);
+    }
+
+    /// A mock daemon on the other end of a socket pair: reads one request in
+    /// either framing, then answers with `reply` framed per the request's
+    /// acceptEncoding.
+    #[cfg(unix)]
+    fn mock_daemon(
+        stream: UnixStream,
+        reply: Value,
+        honor_gzip: bool,
+    ) -> thread::JoinHandle<(Value, bool)> {
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let compressed = parse_frame_header(&line).unwrap();
+            let request: Value = match compressed {
+                None => serde_json::from_str(&line).unwrap(),
+                Some(length) => {
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    serde_json::from_str(&decode_frame_body(&body).unwrap()).unwrap()
+                }
+            };
+            let gzip = honor_gzip && request[ACCEPT_ENCODING_FIELD] == GZIP_ENCODING;
+            writer
+                .write_all(&encode_frame(&reply.to_string(), gzip))
+                .unwrap();
+            (request, compressed.is_some())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_daemon_connection_reads_plain_and_gzip_responses() {
+        let html = "<p>hello</p>".repeat(COMPRESSION_THRESHOLD);
+        let reply = json!({ "success": true, "data": { "html": html } });
+        for honor_gzip in [false, true] {
+            let (client, server) = UnixStream::pair().unwrap();
+            let daemon = mock_daemon(server, reply.clone(), honor_gzip);
+            let mut conn = DaemonConnection::new(Connection::Unix(client), false);
+            let resp = conn.send(&json!({ "action": "html" })).unwrap();
+            assert_eq!(resp.data.unwrap()["html"], html);
+
+            let (request, compressed) = daemon.join().unwrap();
+            assert_eq!(request[ACCEPT_ENCODING_FIELD], GZIP_ENCODING);
+            assert_eq!(request["action"], "html");
+            assert!(!compressed);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_daemon_connection_compresses_large_requests_when_supported() {
+        let script = format!("/* {} */ 1", "x".repeat(COMPRESSION_THRESHOLD));
+        let cmd = json!({ "action": "evaluate", "script": script });
+        for (supported, large) in [(true, true), (true, false), (false, true)] {
+            let cmd = if large {
+                cmd.clone()
+            } else {
+                json!({ "action": "evaluate", "script": "1" })
+            };
+            let (client, server) = UnixStream::pair().unwrap();
+            let daemon = mock_daemon(server, json!({ "success": true, "data": 1 }), true);
+            let mut conn = DaemonConnection::new(Connection::Unix(client), supported);
+            assert!(conn.send(&cmd).unwrap().success);
+
+            let (request, compressed) = daemon.join().unwrap();
+            assert_eq!(request["script"], cmd["script"]);
+            assert_eq!(compressed, supported && large);
+        }
+    }
+
     #[test]
     fn test_get_socket_dir_explicit_override() {
         let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "XDG_RUNTIME_DIR"]);
@@ -1229,6 +2008,190 @@ mod tests {
         assert_eq!(inventory.sessions[0].name, "current");
     }
 
+    #[test]
+    fn test_pid_record_parse_and_format() {
+        let legacy = PidRecord::parse("12345\n").unwrap();
+        assert_eq!(
+            legacy,
+            PidRecord {
+                pid: 12345,
+                started: None
+            }
+        );
+        assert_eq!(legacy.to_string(), "12345");
+
+        let record = PidRecord::parse("12345 987654321").unwrap();
+        assert_eq!(record.started, Some(987654321));
+        assert_eq!(PidRecord::parse(&record.to_string()), Some(record));
+
+        assert_eq!(PidRecord::parse(""), None);
+        assert_eq!(PidRecord::parse("abc"), None);
+        assert_eq!(PidRecord::parse("12345 soon"), None);
+    }
+
+    #[test]
+    fn test_liveness_detects_pid_reuse() {
+        let record = PidRecord {
+            pid: 4242,
+            started: Some(100),
+        };
+        let old = Some(Duration::from_secs(3600));
+        let alive = |_| true;
+
+        // The pid now belongs to another process (say notepad.exe) that
+        // happens to hold the daemon's port: still not the daemon.
+        assert_eq!(
+            check_liveness(record, old, alive, |_| Some(200), || true),
+            Liveness::PidReused
+        );
+        assert_eq!(
+            check_liveness(record, old, alive, |_| Some(100), || true),
+            Liveness::Alive
+        );
+        assert_eq!(
+            check_liveness(record, old, |_| false, |_| Some(100), || true),
+            Liveness::ProcessGone
+        );
+
+        // Without a recorded start time, the connection decides.
+        let legacy = PidRecord {
+            pid: 4242,
+            started: None,
+        };
+        assert_eq!(
+            check_liveness(legacy, old, alive, |_| Some(200), || false),
+            Liveness::NotListening
+        );
+        assert_eq!(
+            check_liveness(legacy, old, alive, |_| None, || true),
+            Liveness::Alive
+        );
+
+        // A daemon that just wrote its pid file may not be listening yet.
+        let fresh = Some(Duration::from_secs(1));
+        assert_eq!(
+            check_liveness(record, fresh, alive, |_| Some(100), || false),
+            Liveness::Starting
+        );
+        assert_eq!(
+            check_liveness(record, fresh, alive, |_| Some(200), || false),
+            Liveness::PidReused
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_walk_daemons_cleans_reused_pid() {
+        let _guard = EnvGuard::new(&[
+            "AGENT_BROWSER_SOCKET_DIR",
+            "XDG_RUNTIME_DIR",
+            "AGENT_BROWSER_NAMESPACE",
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+        _guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+        _guard.remove("XDG_RUNTIME_DIR");
+        _guard.remove("AGENT_BROWSER_NAMESPACE");
+
+        let current = PidRecord::current();
+        assert!(current.started.is_some());
+        let reused = PidRecord {
+            started: current.started.map(|t| t + 1),
+            ..current
+        };
+        fs::write(dir.path().join("reused.pid"), reused.to_string()).unwrap();
+        fs::write(dir.path().join("starting.pid"), current.to_string()).unwrap();
+        fs::write(dir.path().join("reused.notes.json"), "[]").unwrap();
+        fs::write(dir.path().join("starting.notes.json"), "[]").unwrap();
+
+        let inventory = walk_daemons();
+
+        let names: Vec<&str> = inventory.sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["starting"]);
+        assert_eq!(inventory.cleaned.len(), 1);
+        assert_eq!(inventory.cleaned[0].name, "reused");
+        assert_eq!(inventory.cleaned[0].reason, CleanReason::PidReused);
+        assert!(!dir.path().join("reused.pid").exists());
+        assert!(!dir.path().join("reused.notes.json").exists());
+        assert!(dir.path().join("starting.notes.json").exists());
+    }
+
+    #[test]
+    fn test_cdp_target_round_trip() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "AGENT_BROWSER_NAMESPACE"]);
+        let dir = tempfile::tempdir().unwrap();
+        _guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+        _guard.remove("AGENT_BROWSER_NAMESPACE");
+
+        assert_eq!(load_cdp_target("s1"), None);
+        save_cdp_target(
+            "s1",
+            &json!({ "action": "launch", "cdpUrl": "wss://cdp.example.com?apiKey=k", "headless": true }),
+        );
+        let raw = fs::read_to_string(dir.path().join("s1.cdp")).unwrap();
+        assert_eq!(raw, r#"{"cdpUrl":"wss://cdp.example.com?apiKey=k"}"#);
+        assert_eq!(
+            load_cdp_target("s1"),
+            Some(json!({ "cdpUrl": "wss://cdp.example.com?apiKey=k" }))
+        );
+
+        save_cdp_target("s2", &json!({ "action": "launch", "cdpPort": 9222 }));
+        assert_eq!(load_cdp_target("s2"), Some(json!({ "cdpPort": 9222 })));
+
+        // Launches without a CDP target leave the saved one alone.
+        save_cdp_target("s2", &json!({ "action": "launch", "headless": true }));
+        assert_eq!(load_cdp_target("s2"), Some(json!({ "cdpPort": 9222 })));
+
+        cleanup_stale_files("s2");
+        assert_eq!(load_cdp_target("s2"), None);
+    }
+
+    #[test]
+    fn test_load_cdp_target_rejects_garbage() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "AGENT_BROWSER_NAMESPACE"]);
+        let dir = tempfile::tempdir().unwrap();
+        _guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+        _guard.remove("AGENT_BROWSER_NAMESPACE");
+
+        fs::write(dir.path().join("a.cdp"), "not json").unwrap();
+        fs::write(dir.path().join("b.cdp"), r#"{"cdpPort":70000}"#).unwrap();
+        assert_eq!(load_cdp_target("a"), None);
+        assert_eq!(load_cdp_target("b"), None);
+    }
+
+    #[test]
+    fn test_should_auto_reconnect() {
+        let dropped = "CDP response channel closed";
+        assert!(should_auto_reconnect(dropped, true, false));
+        assert!(should_auto_reconnect(
+            "Failed to send CDP command: Trying to work with closed connection",
+            true,
+            false
+        ));
+        assert!(!should_auto_reconnect(dropped, false, false));
+        assert!(!should_auto_reconnect(dropped, true, true));
+        assert!(!should_auto_reconnect(
+            "Element not found: #btn",
+            true,
+            false
+        ));
+        assert!(!should_auto_reconnect(
+            "Failed to connect: No such file or directory (os error 2)",
+            true,
+            false
+        ));
+    }
+
+    #[test]
+    fn test_auto_reconnect_opt_out() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_NO_AUTO_RECONNECT"]);
+        _guard.remove("AGENT_BROWSER_NO_AUTO_RECONNECT");
+        assert!(!auto_reconnect_disabled());
+        _guard.set("AGENT_BROWSER_NO_AUTO_RECONNECT", "1");
+        assert!(auto_reconnect_disabled());
+        _guard.set("AGENT_BROWSER_NO_AUTO_RECONNECT", "0");
+        assert!(!auto_reconnect_disabled());
+    }
+
     fn test_daemon_options<'a>(
         idle_timeout: Option<&'a str>,
         no_auto_dialog: bool,
@@ -1248,12 +2211,15 @@ mod tests {
             proxy_username: None,
             proxy_password: None,
             ignore_https_errors: false,
+            ignore_https_origins: &[],
             allow_file_access: false,
             hide_scrollbars: true,
             webgpu: false,
             profile: None,
+            user_data_dir: None,
             state: None,
             provider: None,
+            provider_options: None,
             device: None,
             session_name: None,
             restore_save: None,
@@ -1274,6 +2240,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_apply_daemon_env_forwards_provider_options() {
+        let mut opts = test_daemon_options(None, false, None);
+        opts.provider = Some("browserbase");
+        opts.provider_options = Some(r#"{"projectId":"abc","region":"us-west-2"}"#);
+        let mut cmd = Command::new("agent-browser");
+
+        apply_daemon_env(&mut cmd, "default", &opts);
+
+        let env_value = |key: &str| {
+            cmd.get_envs()
+                .find(|(k, _)| *k == key)
+                .and_then(|(_, v)| v)
+                .map(|v| v.to_string_lossy().to_string())
+        };
+        assert_eq!(
+            env_value("AGENT_BROWSER_PROVIDER_OPTS").as_deref(),
+            Some(r#"{"projectId":"abc","region":"us-west-2"}"#)
+        );
+        assert_eq!(
+            env_value("AGENT_BROWSER_PROVIDER").as_deref(),
+            Some("browserbase")
+        );
+    }
+
+    #[test]
+    fn test_apply_daemon_env_forwards_ignore_https_origins() {
+        let origins = vec![
+            "https://intranet.example.com".to_string(),
+            "https://dev.local:8443".to_string(),
+        ];
+        let env_value = |cmd: &Command| {
+            cmd.get_envs()
+                .find(|(k, _)| *k == "AGENT_BROWSER_IGNORE_HTTPS_ORIGINS")
+                .and_then(|(_, v)| v)
+                .map(|v| v.to_string_lossy().to_string())
+        };
+
+        let mut cmd = Command::new("agent-browser");
+        apply_daemon_env(&mut cmd, "default", &test_daemon_options(None, false, None));
+        assert_eq!(env_value(&cmd), None);
+
+        let mut opts = test_daemon_options(None, false, None);
+        opts.ignore_https_origins = &origins;
+        let mut cmd = Command::new("agent-browser");
+        apply_daemon_env(&mut cmd, "default", &opts);
+        assert_eq!(
+            env_value(&cmd).as_deref(),
+            Some("https://intranet.example.com,https://dev.local:8443")
+        );
+    }
+
     #[test]
     fn test_daemon_config_fingerprint_tracks_daemon_owned_options() {
         let domains = vec!["example.com".to_string()];
@@ -1368,6 +2386,141 @@ mod tests {
         assert!(daemon_config_matches(session, &opts));
     }
 
+    #[cfg(unix)]
+    fn spawn_stub_daemon(script: &str) -> std::process::Child {
+        Command::new("sh")
+            .args(["-c", script])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_spawned_daemon_exit_reports_code_and_stderr() {
+        let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "AGENT_BROWSER_NAMESPACE"]);
+        let dir = tempfile::tempdir().unwrap();
+        guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+        guard.remove("AGENT_BROWSER_NAMESPACE");
+
+        let opts = test_daemon_options(None, false, None);
+        let mut child = spawn_stub_daemon("echo 'Error: Cannot find module ws' >&2; exit 3");
+        let start = Instant::now();
+        let err = match wait_for_spawned_daemon(
+            "stub-exit",
+            &opts,
+            Some(&mut child),
+            false,
+            Duration::from_secs(5),
+        ) {
+            Err(err) => err,
+            Ok(_) => panic!("an exiting daemon must not be reported as ready"),
+        };
+
+        assert!(
+            start.elapsed() < Duration::from_secs(3),
+            "should stop polling once the child exits"
+        );
+        assert!(err.contains("exit code 3"), "{}", err);
+        assert!(err.contains("Cannot find module ws"), "{}", err);
+
+        // With --debug the daemon log tail is quoted too.
+        let mut opts = test_daemon_options(None, false, None);
+        opts.debug = true;
+        fs::write(
+            get_socket_dir().join("stub-exit.log"),
+            "[daemon] Debug logging started\n[daemon] listen failed: EADDRINUSE\n",
+        )
+        .unwrap();
+        let mut child = spawn_stub_daemon("exit 1");
+        let err = match wait_for_spawned_daemon(
+            "stub-exit",
+            &opts,
+            Some(&mut child),
+            false,
+            Duration::from_secs(5),
+        ) {
+            Err(err) => err,
+            Ok(_) => panic!("an exiting daemon must not be reported as ready"),
+        };
+        assert!(err.contains("exit code 1"), "{}", err);
+        assert!(err.contains("Daemon log ("), "{}", err);
+        assert!(err.contains("listen failed: EADDRINUSE"), "{}", err);
+        assert!(!err.contains("no error output"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_spawned_daemon_hang_reports_pid() {
+        let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "AGENT_BROWSER_NAMESPACE"]);
+        let dir = tempfile::tempdir().unwrap();
+        guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+        guard.remove("AGENT_BROWSER_NAMESPACE");
+
+        let opts = test_daemon_options(None, false, None);
+        let mut child = spawn_stub_daemon("sleep 30");
+        let pid = child.id();
+        let result = wait_for_spawned_daemon(
+            "stub-hang",
+            &opts,
+            Some(&mut child),
+            false,
+            Duration::from_millis(300),
+        );
+        let _ = child.kill();
+        let _ = child.wait();
+
+        let err = match result {
+            Err(err) => err,
+            Ok(_) => panic!("a daemon without a socket must not be reported as ready"),
+        };
+        assert!(
+            err.contains(&format!(
+                "Daemon running (pid {}) but not accepting connections",
+                pid
+            )),
+            "{}",
+            err
+        );
+        assert!(err.contains("stub-hang.sock"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_spawned_daemon_ready_once_socket_accepts() {
+        let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "AGENT_BROWSER_NAMESPACE"]);
+        let dir = tempfile::tempdir().unwrap();
+        guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+        guard.remove("AGENT_BROWSER_NAMESPACE");
+
+        let session = "stub-ready";
+        let opts = test_daemon_options(None, false, None);
+        let mut child = spawn_stub_daemon("sleep 30");
+        // Stand in for the daemon: record the child's pid and accept on the socket.
+        fs::write(get_pid_path(session), child.id().to_string()).unwrap();
+        let _listener = std::os::unix::net::UnixListener::bind(get_socket_path(session)).unwrap();
+
+        let result = wait_for_spawned_daemon(
+            session,
+            &opts,
+            Some(&mut child),
+            true,
+            Duration::from_secs(5),
+        );
+        let _ = child.kill();
+        let _ = child.wait();
+
+        let result = match result {
+            Ok(result) => result,
+            Err(err) => panic!("ready daemon should be reported: {}", err),
+        };
+        assert!(!result.already_running);
+        assert!(result.restarted);
+        assert!(daemon_config_matches(session, &opts));
+    }
+
     #[test]
     fn test_spawn_owner_writes_config() {
         let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "AGENT_BROWSER_NAMESPACE"]);
@@ -1595,4 +2748,74 @@ mod tests {
 
         let _ = fs::remove_dir(&dir);
     }
+
+    #[test]
+    fn test_ignored_flags_hash_is_order_independent() {
+        assert_eq!(
+            ignored_flags_hash(&["--device", "--ignore-https-origin"]),
+            ignored_flags_hash(&["--ignore-https-origin", "--device"])
+        );
+        assert_ne!(
+            ignored_flags_hash(&["--device"]),
+            ignored_flags_hash(&["--device", "--ignore-https-origin"])
+        );
+    }
+
+    #[test]
+    fn test_flags_warning_recent_window() {
+        let hash = ignored_flags_hash(&["--device"]);
+        let marker = format!("{} 1000000\n", hash);
+        assert!(flags_warning_recent(&marker, &hash, 1_000_000, 600_000));
+        assert!(flags_warning_recent(&marker, &hash, 1_599_999, 600_000));
+        // The window has expired.
+        assert!(!flags_warning_recent(&marker, &hash, 1_600_000, 600_000));
+        // A different flag set warns again.
+        let other = ignored_flags_hash(&["--ignore-https-origin"]);
+        assert!(!flags_warning_recent(&marker, &other, 1_000_001, 600_000));
+        // A zero window never suppresses, and a clock that went backwards or
+        // a garbled marker does not either.
+        assert!(!flags_warning_recent(&marker, &hash, 1_000_001, 0));
+        assert!(!flags_warning_recent(&marker, &hash, 999_999, 600_000));
+        assert!(!flags_warning_recent("garbage", &hash, 1_000_001, 600_000));
+        assert!(!flags_warning_recent("", &hash, 1_000_001, 600_000));
+    }
+
+    #[test]
+    fn test_ignored_flags_warning_shown_once_per_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "XDG_RUNTIME_DIR"]);
+        _guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+
+        assert!(should_show_ignored_flags_warning(
+            "w",
+            &["--device"],
+            600_000
+        ));
+        assert!(!should_show_ignored_flags_warning(
+            "w",
+            &["--device"],
+            600_000
+        ));
+        assert!(should_show_ignored_flags_warning(
+            "w",
+            &["--device", "--ignore-https-origin"],
+            600_000
+        ));
+        // Other sessions keep their own marker.
+        assert!(should_show_ignored_flags_warning(
+            "other",
+            &["--device"],
+            600_000
+        ));
+        assert!(should_show_ignored_flags_warning("w", &["--device"], 0));
+
+        assert!(dir.path().join("w.warned").exists());
+        cleanup_stale_files("w");
+        assert!(!dir.path().join("w.warned").exists());
+        assert!(should_show_ignored_flags_warning(
+            "w",
+            &["--device"],
+            600_000
+        ));
+    }
 }
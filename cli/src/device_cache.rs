@@ -0,0 +1,180 @@
+//! Per-session cache of the last `device list` response.
+//!
+//! `device use` and `device boot` take a device name or UDID. The CLI checks
+//! it against the devices the session last listed so a typo fails before
+//! the daemon shells out to `xcrun`, with the closest names suggested. With
+//! no cached list the argument goes to the daemon unchecked.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::atomic_write::write_atomic;
+use crate::connection::get_socket_dir;
+use crate::suggest::{edit_distance, MAX_SUGGESTION_DISTANCE};
+
+const MAX_DEVICE_SUGGESTIONS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CachedDevice {
+    pub name: String,
+    pub udid: String,
+}
+
+pub fn cache_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.devices.json", session))
+}
+
+/// Store the `devices` array of a `device_list` response.
+pub fn save(path: &Path, data: &Value) -> std::io::Result<()> {
+    let Some(devices) = data.get("devices") else {
+        return Ok(());
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(path, serde_json::to_vec(devices)?)
+}
+
+pub fn load(path: &Path) -> Option<Vec<CachedDevice>> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Whether `query` selects `device` the way the daemon resolves it: the
+/// exact UDID, or a case-insensitive substring of the name.
+fn selects(device: &CachedDevice, query: &str) -> bool {
+    device.udid.eq_ignore_ascii_case(query)
+        || device.name.to_lowercase().contains(&query.to_lowercase())
+}
+
+/// Up to three device names closest to `query`, nearest first. Longer names
+/// tolerate proportionally more typos than command names do.
+pub fn closest_names(query: &str, devices: &[CachedDevice]) -> Vec<String> {
+    let query = query.to_lowercase();
+    let limit = MAX_SUGGESTION_DISTANCE.max(query.chars().count() / 3);
+    let mut ranked: Vec<(usize, &str)> = devices
+        .iter()
+        .map(|d| {
+            (
+                edit_distance(&query, &d.name.to_lowercase()),
+                d.name.as_str(),
+            )
+        })
+        .filter(|(distance, _)| *distance <= limit)
+        .collect();
+    ranked.sort_by_key(|(distance, _)| *distance);
+    let mut names: Vec<String> = Vec::new();
+    for (_, name) in ranked {
+        if !names.iter().any(|n| n == name) && names.len() < MAX_DEVICE_SUGGESTIONS {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Check `query` against a cached device list.
+pub fn check_device(query: &str, devices: &[CachedDevice]) -> Result<(), String> {
+    if devices.iter().any(|d| selects(d, query)) {
+        return Ok(());
+    }
+    match closest_names(query, devices).as_slice() {
+        [] => Err(format!(
+            "Unknown device '{}'. Run `agent-browser device list` to see available devices",
+            query
+        )),
+        names => Err(format!(
+            "Unknown device '{}'. Did you mean: {}?\nRun `agent-browser device list` to refresh the list",
+            query,
+            names.join(", ")
+        )),
+    }
+}
+
+/// Validate the device argument of `device use` and `device boot` against
+/// the session's cached device list, if there is one.
+pub fn check_command(cmd: &Value, session: &str) -> Result<(), String> {
+    if !matches!(
+        cmd.get("action").and_then(|v| v.as_str()),
+        Some("device_use" | "device_boot")
+    ) {
+        return Ok(());
+    }
+    let Some(query) = cmd.get("device").and_then(|v| v.as_str()) else {
+        return Ok(());
+    };
+    match load(&cache_path(session)) {
+        Some(devices) => check_device(query, &devices),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> Vec<CachedDevice> {
+        serde_json::from_value(json!([
+            { "name": "iPhone 15 Pro", "udid": "A1B2C3D4-0000-1111-2222-333344445555", "state": "Shutdown" },
+            { "name": "iPhone 15 Pro Max", "udid": "B1B2C3D4-0000-1111-2222-333344445555", "state": "Booted" },
+            { "name": "iPhone 16", "udid": "C1B2C3D4-0000-1111-2222-333344445555", "state": "Shutdown" },
+            { "name": "iPad Air (5th generation)", "udid": "D1B2C3D4-0000-1111-2222-333344445555", "state": "Shutdown" },
+            { "name": "iPhone 15 Pro", "udid": "E1B2C3D4-0000-1111-2222-333344445555", "state": "Shutdown" },
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_device_accepts_names_and_udids() {
+        let devices = fixture();
+        assert!(check_device("iPhone 15 Pro", &devices).is_ok());
+        assert!(check_device("iphone 16", &devices).is_ok());
+        assert!(check_device("iPad Air", &devices).is_ok());
+        assert!(check_device("c1b2c3d4-0000-1111-2222-333344445555", &devices).is_ok());
+    }
+
+    #[test]
+    fn test_check_device_suggests_closest_names() {
+        let devices = fixture();
+        let err = check_device("iPhon 15 Pro", &devices).unwrap_err();
+        assert!(
+            err.starts_with("Unknown device 'iPhon 15 Pro'. Did you mean: iPhone 15 Pro?"),
+            "{}",
+            err
+        );
+        assert!(err.contains("device list"));
+
+        let err = check_device("Galaxy S24", &devices).unwrap_err();
+        assert!(!err.contains("Did you mean"), "{}", err);
+    }
+
+    #[test]
+    fn test_closest_names_ranks_and_dedupes() {
+        let devices = fixture();
+        assert_eq!(
+            closest_names("iPhone 15 Pr Mx", &devices),
+            vec!["iPhone 15 Pro Max", "iPhone 15 Pro"]
+        );
+        assert_eq!(closest_names("iPhone 17", &devices), vec!["iPhone 16"]);
+        assert!(closest_names("zzz", &devices).is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("default.devices.json");
+        let data = json!({ "devices": [{ "name": "iPhone 16", "udid": "U1", "state": "Booted", "runtime": "iOS-18", "isReal": false }] });
+        save(&path, &data).unwrap();
+        assert_eq!(
+            load(&path).unwrap(),
+            vec![CachedDevice {
+                name: "iPhone 16".to_string(),
+                udid: "U1".to_string()
+            }]
+        );
+        assert!(load(&dir.path().join("missing.json")).is_none());
+    }
+}
@@ -0,0 +1,355 @@
+//! `docs`: render the full command reference from the command registry as
+//! Markdown (for the website) or a man page.
+
+use serde_json::json;
+use std::process::exit;
+
+use crate::color;
+use crate::command_registry::{trim_block, Category, CommandSpec, HELP_GLOBAL, HELP_INTRO};
+
+const DOCS_USAGE: &str = "docs [--format markdown|man]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Markdown,
+    Man,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Format::Markdown => "markdown",
+            Format::Man => "man",
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Format, String> {
+    let mut format = Format::Markdown;
+    let mut i = 1;
+    while i < args.len() {
+        let value = match args[i].as_str() {
+            "--format" => {
+                i += 1;
+                args.get(i)
+                    .map(String::as_str)
+                    .ok_or(format!("--format requires a value. Usage: {}", DOCS_USAGE))?
+            }
+            other => match other.strip_prefix("--format=") {
+                Some(value) => value,
+                None => {
+                    return Err(format!(
+                        "Unknown argument '{}'. Usage: {}",
+                        other, DOCS_USAGE
+                    ))
+                }
+            },
+        };
+        format = match value {
+            "markdown" | "md" => Format::Markdown,
+            "man" => Format::Man,
+            other => {
+                return Err(format!(
+                    "Invalid --format '{}': expected markdown or man. Usage: {}",
+                    other, DOCS_USAGE
+                ))
+            }
+        };
+        i += 1;
+    }
+    Ok(format)
+}
+
+/// A run of non-blank lines in a help text, classified for rendering.
+#[derive(Debug, PartialEq)]
+enum Block<'a> {
+    /// A `Title:` line heading the indented lines that follow it.
+    Title(&'a str),
+    /// Indented lines: option tables, command lists, examples.
+    Literal(Vec<&'a str>),
+    Prose(Vec<&'a str>),
+}
+
+fn classify<'a>(para: &[&'a str]) -> Vec<Block<'a>> {
+    let indented = |lines: &[&str]| lines.iter().all(|line| line.starts_with(' '));
+    let first = para[0];
+    if !first.starts_with(' ') && first.ends_with(':') && indented(&para[1..]) {
+        let mut out = vec![Block::Title(first.trim_end_matches(':'))];
+        if para.len() > 1 {
+            out.push(Block::Literal(para[1..].to_vec()));
+        }
+        out
+    } else if indented(para) {
+        vec![Block::Literal(para.to_vec())]
+    } else {
+        vec![Block::Prose(para.to_vec())]
+    }
+}
+
+fn blocks(text: &str) -> Vec<Block<'_>> {
+    let lines: Vec<&str> = text.lines().collect();
+    lines
+        .split(|line| line.trim().is_empty())
+        .filter(|para| !para.is_empty())
+        .flat_map(classify)
+        .collect()
+}
+
+/// Literal lines with their common indentation removed.
+fn dedent<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    let indent = lines
+        .iter()
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines.iter().map(|line| &line[indent..]).collect()
+}
+
+fn markdown_blocks(out: &mut String, text: &str) {
+    for block in blocks(text) {
+        match block {
+            Block::Title(title) => out.push_str(&format!("**{}**\n\n", title)),
+            Block::Literal(lines) => {
+                out.push_str("```text\n");
+                for line in dedent(&lines) {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+                out.push_str("```\n\n");
+            }
+            Block::Prose(lines) => {
+                out.push_str(&lines.join("\n"));
+                out.push_str("\n\n");
+            }
+        }
+    }
+}
+
+fn markdown_command(out: &mut String, spec: &CommandSpec) {
+    out.push_str(&format!("### {}\n\n{}\n\n", spec.name, spec.summary));
+    if !spec.aliases.is_empty() {
+        let aliases: Vec<String> = spec.aliases.iter().map(|a| format!("`{}`", a)).collect();
+        out.push_str(&format!("Aliases: {}\n\n", aliases.join(", ")));
+    }
+    out.push_str("```text\n");
+    for line in spec.usage {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str("```\n\n");
+    for (title, body) in spec.parts() {
+        if let Some(title) = title {
+            out.push_str(&format!("**{}**\n\n", title));
+        }
+        markdown_blocks(out, body);
+    }
+}
+
+/// The full reference as Markdown.
+pub fn render_markdown() -> String {
+    let mut out = String::from("# agent-browser command reference\n\n");
+    markdown_blocks(&mut out, trim_block(HELP_INTRO));
+    for category in Category::ALL {
+        out.push_str(&format!("## {}\n\n", category.title()));
+        for spec in crate::command_registry::in_category(*category) {
+            markdown_command(&mut out, spec);
+        }
+    }
+    out.push_str("## Global Options\n\n");
+    markdown_blocks(&mut out, trim_block(HELP_GLOBAL));
+    while out.ends_with("\n\n") {
+        out.pop();
+    }
+    out
+}
+
+/// Escape a line of text for roff: backslashes, and a leading `.` or `'`
+/// that would otherwise start a request.
+fn roff_line(line: &str) -> String {
+    let escaped = line.replace('\\', "\\e");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+fn man_blocks(out: &mut String, text: &str) {
+    for block in blocks(text) {
+        match block {
+            Block::Title(title) => out.push_str(&format!(".PP\n.B {}\n", roff_line(title))),
+            Block::Literal(lines) => {
+                out.push_str(".PP\n.RS 4\n.nf\n");
+                for line in dedent(&lines) {
+                    out.push_str(&roff_line(line));
+                    out.push('\n');
+                }
+                out.push_str(".fi\n.RE\n");
+            }
+            Block::Prose(lines) => {
+                out.push_str(".PP\n");
+                for line in lines {
+                    out.push_str(&roff_line(line));
+                    out.push('\n');
+                }
+            }
+        }
+    }
+}
+
+fn man_command(out: &mut String, spec: &CommandSpec) {
+    out.push_str(&format!(
+        ".SS {}\n{}\n",
+        roff_line(spec.name),
+        roff_line(spec.summary)
+    ));
+    if !spec.aliases.is_empty() {
+        out.push_str(&format!(
+            ".PP\nAliases: {}\n",
+            roff_line(&spec.aliases.join(", "))
+        ));
+    }
+    out.push_str(".PP\n.RS 4\n.nf\n");
+    for line in spec.usage {
+        out.push_str(&roff_line(line));
+        out.push('\n');
+    }
+    out.push_str(".fi\n.RE\n");
+    for (title, body) in spec.parts() {
+        if let Some(title) = title {
+            out.push_str(&format!(".PP\n.B {}\n", roff_line(title)));
+        }
+        man_blocks(out, body);
+    }
+}
+
+/// The full reference as a man(7) page.
+pub fn render_man() -> String {
+    let mut out = format!(
+        ".TH AGENT-BROWSER 1 \"\" \"agent-browser {}\" \"User Commands\"\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    out.push_str(".SH NAME\nagent-browser \\- fast browser automation CLI for AI agents\n");
+    out.push_str(
+        ".SH SYNOPSIS\n.B agent-browser\n\\fIcommand\\fR [\\fIargs\\fR] [\\fIoptions\\fR]\n",
+    );
+    out.push_str(".SH DESCRIPTION\n");
+    // Skip the intro's own title and usage lines; NAME and SYNOPSIS cover them.
+    let intro = trim_block(HELP_INTRO);
+    let intro = intro
+        .split_once("\n\n")
+        .and_then(|(_, rest)| rest.split_once("\n\n"))
+        .map_or(intro, |(_, rest)| rest);
+    man_blocks(&mut out, intro);
+    for category in Category::ALL {
+        out.push_str(&format!(
+            ".SH {}\n",
+            roff_line(&category.title().to_uppercase())
+        ));
+        for spec in crate::command_registry::in_category(*category) {
+            man_command(&mut out, spec);
+        }
+    }
+    out.push_str(".SH GLOBAL OPTIONS\n");
+    man_blocks(&mut out, trim_block(HELP_GLOBAL));
+    out
+}
+
+pub fn run_docs(args: &[String], json_mode: bool) {
+    let format = match parse_args(args) {
+        Ok(format) => format,
+        Err(msg) => {
+            if json_mode {
+                println!("{}", json!({ "success": false, "error": msg }));
+            } else {
+                eprintln!("{} {}", color::error_indicator(), msg);
+            }
+            exit(1);
+        }
+    };
+    let content = match format {
+        Format::Markdown => render_markdown(),
+        Format::Man => render_man(),
+    };
+    if json_mode {
+        println!(
+            "{}",
+            json!({ "success": true, "data": { "format": format.name(), "content": content } })
+        );
+    } else {
+        print!("{}", content);
+        if !content.ends_with('\n') {
+            println!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args_formats() {
+        assert_eq!(parse_args(&args("docs")).unwrap(), Format::Markdown);
+        assert_eq!(parse_args(&args("docs --format man")).unwrap(), Format::Man);
+        assert_eq!(
+            parse_args(&args("docs --format=markdown")).unwrap(),
+            Format::Markdown
+        );
+        assert!(parse_args(&args("docs --format html")).is_err());
+        assert!(parse_args(&args("docs --format")).is_err());
+        assert!(parse_args(&args("docs extra")).is_err());
+    }
+
+    #[test]
+    fn test_blocks_classifies_titles_literals_and_prose() {
+        let text = "Some prose\nacross lines.\n\nOptions:\n  --a   first\n\n  agent-browser x";
+        assert_eq!(
+            blocks(text),
+            vec![
+                Block::Prose(vec!["Some prose", "across lines."]),
+                Block::Title("Options"),
+                Block::Literal(vec!["  --a   first"]),
+                Block::Literal(vec!["  agent-browser x"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markdown_covers_every_command() {
+        let md = render_markdown();
+        for spec in crate::command_registry::COMMANDS {
+            assert!(
+                md.contains(&format!("### {}\n", spec.name)),
+                "missing {}",
+                spec.name
+            );
+        }
+        assert!(md.contains("## Global Options"));
+        assert_eq!(md.matches("```").count() % 2, 0, "unbalanced code fences");
+    }
+
+    #[test]
+    fn test_man_page_escapes_requests() {
+        let man = render_man();
+        assert!(man.starts_with(".TH AGENT-BROWSER 1"));
+        assert!(man.contains(".SS open\n"));
+        const REQUESTS: &[&str] = &[
+            ".TH ", ".SH ", ".SS ", ".B ", ".PP", ".RS", ".RE", ".nf", ".fi",
+        ];
+        for line in man.lines() {
+            if line.starts_with('.') {
+                assert!(
+                    REQUESTS.iter().any(|r| line.starts_with(r)),
+                    "unescaped line: {}",
+                    line
+                );
+            }
+            assert!(!line.starts_with('\''), "unescaped line: {}", line);
+        }
+    }
+}
@@ -13,6 +13,8 @@ pub(super) fn check(checks: &mut Vec<Check>) {
     for cleaned in &inventory.cleaned {
         let reason = match cleaned.reason {
             CleanReason::ProcessGone | CleanReason::DashboardGone => "process gone",
+            CleanReason::PidReused => "pid reused by another process",
+            CleanReason::NotListening => "not accepting connections",
             CleanReason::UnreadablePidFile => "unreadable pid file",
             CleanReason::OrphanedSocket => "orphaned socket",
         };
@@ -14,6 +14,7 @@ use serde_json::json;
 
 use super::helpers::new_id;
 use super::{Check, Status};
+use crate::atomic_write::write_private;
 use crate::connection::{cleanup_stale_files, send_command, walk_daemons};
 use crate::native::state::{get_sessions_dir, get_state_dir};
 
@@ -144,14 +145,7 @@ fn create_encryption_key_at(dir: &Path) -> bool {
         return false;
     }
     let hex: String = buf.iter().map(|b| format!("{:02x}", b)).collect();
-    if fs::write(&path, format!("{}\n", hex)).is_err() {
-        return false;
-    }
-    #[cfg(unix)]
-    {
-        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o600));
-    }
-    true
+    write_private(&path, format!("{}\n", hex)).is_ok()
 }
 
 #[cfg(test)]
@@ -64,12 +64,15 @@ pub(super) fn check(checks: &mut Vec<Check>, opts: &DoctorOptions) {
         proxy_username: None,
         proxy_password: None,
         ignore_https_errors: false,
+        ignore_https_origins: &[],
         allow_file_access: false,
         hide_scrollbars: true,
         webgpu: false,
         profile: None,
+        user_data_dir: None,
         state: None,
         provider: None,
+        provider_options: None,
         device: None,
         session_name: None,
         restore_save: None,
@@ -0,0 +1,460 @@
+//! Registry of the `AGENT_BROWSER_*` environment variables this binary reads,
+//! and the `env` command that reports them. flags.rs reads its variables
+//! through [`var`], which refuses (in debug builds) any name missing from
+//! [`ENV_VARS`], so a new variable cannot ship without a registry entry.
+
+use serde_json::{json, Value};
+use std::env;
+use std::process::exit;
+
+use crate::color;
+use crate::redact::{is_secret_name, redact_text};
+use crate::suggest::edit_distance;
+
+/// Every `AGENT_BROWSER_*` variable the CLI or daemon reads, with what it
+/// affects.
+pub const ENV_VARS: &[(&str, &str)] = &[
+    ("AGENT_BROWSER_CONFIG", "Path to a config file"),
+    (
+        "AGENT_BROWSER_SESSION",
+        "Session name (default: \"default\")",
+    ),
+    (
+        "AGENT_BROWSER_SESSION_NAME",
+        "Legacy auto-save/load state persistence name",
+    ),
+    ("AGENT_BROWSER_NAMESPACE", "Namespace for session isolation"),
+    (
+        "AGENT_BROWSER_RESTORE",
+        "Restore key for automatic state restore",
+    ),
+    (
+        "AGENT_BROWSER_RESTORE_SAVE",
+        "Restore save policy (auto, always, never)",
+    ),
+    (
+        "AGENT_BROWSER_RESTORE_CHECK_URL",
+        "URL check that validates a restored session",
+    ),
+    (
+        "AGENT_BROWSER_RESTORE_CHECK_TEXT",
+        "Text check that validates a restored session",
+    ),
+    (
+        "AGENT_BROWSER_RESTORE_CHECK_FN",
+        "JavaScript check that validates a restored session",
+    ),
+    (
+        "AGENT_BROWSER_EXECUTABLE_PATH",
+        "Custom browser executable path",
+    ),
+    (
+        "AGENT_BROWSER_EXTENSIONS",
+        "Comma-separated browser extension paths",
+    ),
+    (
+        "AGENT_BROWSER_INIT_SCRIPTS",
+        "Comma-separated paths to page init scripts",
+    ),
+    (
+        "AGENT_BROWSER_ENABLE",
+        "Comma-separated built-in init script features",
+    ),
+    (
+        "AGENT_BROWSER_PROFILE",
+        "Chrome profile name or persistent profile directory",
+    ),
+    (
+        "AGENT_BROWSER_USER_DATA_DIR",
+        "Existing Chrome user data directory to launch with",
+    ),
+    (
+        "AGENT_BROWSER_STATE",
+        "Storage state file to load at launch",
+    ),
+    (
+        "AGENT_BROWSER_STATE_EXPIRE_DAYS",
+        "Auto-delete saved states older than N days",
+    ),
+    (
+        "AGENT_BROWSER_ENCRYPTION_KEY",
+        "64-char hex key for session state encryption",
+    ),
+    (
+        "AGENT_BROWSER_AUTOSAVE_INTERVAL_MS",
+        "Min ms between periodic session autosaves",
+    ),
+    ("AGENT_BROWSER_PROXY", "Proxy server URL"),
+    ("AGENT_BROWSER_PROXY_BYPASS", "Hosts that bypass the proxy"),
+    (
+        "AGENT_BROWSER_PROXY_USERNAME",
+        "Proxy username (set by the CLI for the daemon)",
+    ),
+    (
+        "AGENT_BROWSER_PROXY_PASSWORD",
+        "Proxy password (set by the CLI for the daemon)",
+    ),
+    ("AGENT_BROWSER_ARGS", "Extra browser launch arguments"),
+    (
+        "AGENT_BROWSER_WINDOW_SIZE",
+        "Headed browser window size (WxH)",
+    ),
+    (
+        "AGENT_BROWSER_WINDOW_POSITION",
+        "Headed browser window position (X,Y)",
+    ),
+    ("AGENT_BROWSER_USER_AGENT", "Custom User-Agent string"),
+    ("AGENT_BROWSER_HEADED", "Show browser window (not headless)"),
+    (
+        "AGENT_BROWSER_NO_XVFB",
+        "Disable automatic Xvfb for headed mode",
+    ),
+    (
+        "AGENT_BROWSER_NO_HISTORY",
+        "Do not record commands in the session history",
+    ),
+    ("AGENT_BROWSER_WEBGPU", "Enable WebGPU"),
+    ("AGENT_BROWSER_JSON", "JSON output"),
+    ("AGENT_BROWSER_COLOR", "Force colored output"),
+    (
+        "AGENT_BROWSER_ANNOTATE",
+        "Annotated screenshots with numbered labels",
+    ),
+    ("AGENT_BROWSER_FULL", "Full-page screenshots by default"),
+    ("AGENT_BROWSER_DEBUG", "Debug output and daemon log"),
+    (
+        "AGENT_BROWSER_IGNORE_HTTPS_ERRORS",
+        "Ignore HTTPS certificate errors",
+    ),
+    (
+        "AGENT_BROWSER_IGNORE_HTTPS_ORIGINS",
+        "Comma-separated https origins whose certificate errors are ignored",
+    ),
+    (
+        "AGENT_BROWSER_ALLOW_FILE_ACCESS",
+        "Allow file:// URLs to access local files",
+    ),
+    (
+        "AGENT_BROWSER_HIDE_SCROLLBARS",
+        "Hide scrollbars in headless screenshots",
+    ),
+    (
+        "AGENT_BROWSER_COLOR_SCHEME",
+        "Color scheme (dark, light, no-preference)",
+    ),
+    (
+        "AGENT_BROWSER_DEFAULT_SCHEME",
+        "Scheme for URLs typed without one: https, http, none",
+    ),
+    ("AGENT_BROWSER_DOWNLOAD_PATH", "Default download directory"),
+    (
+        "AGENT_BROWSER_ARTIFACTS_DIR",
+        "Directory for screenshots, PDFs, traces, HARs, recordings and downloads given a bare filename or no path",
+    ),
+    (
+        "AGENT_BROWSER_DEFAULT_TIMEOUT",
+        "Default action timeout in ms",
+    ),
+    (
+        "AGENT_BROWSER_IDLE_TIMEOUT_MS",
+        "Auto-shutdown daemon after N ms of inactivity",
+    ),
+    ("AGENT_BROWSER_CDP", "CDP port or URL to connect to"),
+    (
+        "AGENT_BROWSER_AUTO_CONNECT",
+        "Auto-discover and connect to running Chrome",
+    ),
+    (
+        "AGENT_BROWSER_SCAN_PORTS",
+        "Extra ports for connect --list/--match",
+    ),
+    (
+        "AGENT_BROWSER_NO_AUTO_RECONNECT",
+        "Don't re-send the CDP launch after a drop",
+    ),
+    (
+        "AGENT_BROWSER_PROVIDER",
+        "Browser provider (ios, browserbase, plugin name, ...)",
+    ),
+    (
+        "AGENT_BROWSER_PROVIDER_OPTS",
+        "Provider options as a JSON object of strings",
+    ),
+    ("AGENT_BROWSER_IOS_DEVICE", "Default iOS device name"),
+    ("AGENT_BROWSER_ENGINE", "Browser engine: chrome, lightpanda"),
+    (
+        "AGENT_BROWSER_CONTENT_BOUNDARIES",
+        "Wrap page output in boundary markers",
+    ),
+    ("AGENT_BROWSER_MAX_OUTPUT", "Max characters for page output"),
+    (
+        "AGENT_BROWSER_CACHE_MS",
+        "TTL for caching read-only getter responses",
+    ),
+    (
+        "AGENT_BROWSER_AUTO_RETRY_ACTIONS",
+        "Retry click/fill/check/select on detached or covered elements up to N times",
+    ),
+    (
+        "AGENT_BROWSER_FLAG_WARNING_WINDOW_MS",
+        "Suppress repeats of the ignored-flags warning for this long (default 600000)",
+    ),
+    ("AGENT_BROWSER_REF_PREFIX", "Prefix for snapshot refs"),
+    (
+        "AGENT_BROWSER_ALLOWED_DOMAINS",
+        "Comma-separated allowed domain patterns",
+    ),
+    (
+        "AGENT_BROWSER_ACTION_POLICY",
+        "Path to action policy JSON file",
+    ),
+    (
+        "AGENT_BROWSER_POLICY",
+        "Legacy name for AGENT_BROWSER_ACTION_POLICY",
+    ),
+    (
+        "AGENT_BROWSER_CONFIRM_ACTIONS",
+        "Action categories requiring confirmation",
+    ),
+    (
+        "AGENT_BROWSER_CONFIRM_INTERACTIVE",
+        "Enable interactive confirmation prompts",
+    ),
+    (
+        "AGENT_BROWSER_ON_CONFIRMATION",
+        "Unattended confirmation policy (allow, deny, fail)",
+    ),
+    (
+        "AGENT_BROWSER_NO_AUTO_DIALOG",
+        "Disable automatic dialog dismissal",
+    ),
+    (
+        "AGENT_BROWSER_REQUIRE_FRESH_DAEMON",
+        "Fail if a running daemon ignores launch options",
+    ),
+    (
+        "AGENT_BROWSER_SCREENSHOT_DIR",
+        "Default screenshot output directory",
+    ),
+    ("AGENT_BROWSER_SCREENSHOT_QUALITY", "JPEG quality 0-100"),
+    (
+        "AGENT_BROWSER_SCREENSHOT_FORMAT",
+        "Screenshot format: png, jpeg",
+    ),
+    ("AGENT_BROWSER_PLUGINS", "JSON plugin registry override"),
+    (
+        "AGENT_BROWSER_SKILLS_DIR",
+        "Override the skills directory path",
+    ),
+    (
+        "AGENT_BROWSER_SOCKET_DIR",
+        "Directory for daemon sockets, pid files, and logs",
+    ),
+    ("AGENT_BROWSER_STREAM_PORT", "WebSocket streaming port"),
+    ("AGENT_BROWSER_DASHBOARD_PORT", "Dashboard server port"),
+    ("AGENT_BROWSER_DAEMON", "Internal: marks the daemon process"),
+    (
+        "AGENT_BROWSER_DASHBOARD",
+        "Internal: marks the dashboard server process",
+    ),
+];
+
+fn is_registered(name: &str) -> bool {
+    ENV_VARS.iter().any(|(n, _)| *n == name)
+}
+
+/// `env::var` for a registered variable.
+pub fn var(name: &str) -> Result<String, env::VarError> {
+    debug_assert!(
+        is_registered(name),
+        "{name} is not in the env_vars registry"
+    );
+    env::var(name)
+}
+
+/// What `env` shows for a value: secrets are masked, and anything else goes
+/// through the redactor because proxy and CDP URLs can embed credentials.
+fn display_value(name: &str, value: &str) -> String {
+    if is_secret_name(name) {
+        "********".to_string()
+    } else {
+        redact_text(value)
+    }
+}
+
+fn suggest(name: &str) -> Option<&'static str> {
+    ENV_VARS
+        .iter()
+        .map(|(n, _)| (edit_distance(name, n), *n))
+        .filter(|(d, _)| *d <= 3)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, n)| n)
+}
+
+fn env_report(vars: impl Iterator<Item = (String, String)>) -> Value {
+    let present: Vec<(String, String)> = vars
+        .filter(|(name, _)| name.starts_with("AGENT_BROWSER_"))
+        .collect();
+    let value_of = |name: &str| {
+        present
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(n, v)| display_value(n, v))
+    };
+    let variables: Vec<Value> = ENV_VARS
+        .iter()
+        .map(|(name, description)| {
+            let value = value_of(name);
+            json!({
+                "name": name,
+                "set": value.is_some(),
+                "value": value,
+                "description": description,
+            })
+        })
+        .collect();
+    let mut unrecognized: Vec<Value> = present
+        .iter()
+        .filter(|(name, _)| !is_registered(name))
+        .map(|(name, value)| {
+            json!({
+                "name": name,
+                "value": display_value(name, value),
+                "suggestion": suggest(name),
+            })
+        })
+        .collect();
+    unrecognized.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    json!({ "variables": variables, "unrecognized": unrecognized })
+}
+
+pub fn run_env(args: &[String], json_mode: bool) {
+    if let Some(extra) = args.get(1) {
+        let msg = format!("Unknown argument '{}'. Usage: env", extra);
+        if json_mode {
+            println!(
+                "{}",
+                serde_json::to_string(&json!({ "success": false, "error": msg }))
+                    .unwrap_or_default()
+            );
+        } else {
+            eprintln!("{} {}", color::error_indicator(), msg);
+        }
+        exit(1);
+    }
+
+    let report = env_report(env::vars());
+    if json_mode {
+        println!(
+            "{}",
+            serde_json::to_string(&json!({ "success": true, "data": report })).unwrap_or_default()
+        );
+        return;
+    }
+
+    let width = ENV_VARS.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+    for v in report["variables"].as_array().into_iter().flatten() {
+        let name = v["name"].as_str().unwrap_or("");
+        let description = v["description"].as_str().unwrap_or("");
+        match v["value"].as_str() {
+            Some(value) => println!(
+                "{}  {}  {}",
+                color::bold(&format!("{:width$}", name, width = width)),
+                value,
+                color::dim(description)
+            ),
+            None => println!(
+                "{}  {}",
+                color::dim(&format!("{:width$}", name, width = width)),
+                color::dim(&format!("(unset)  {}", description))
+            ),
+        }
+    }
+
+    let unrecognized = report["unrecognized"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    if !unrecognized.is_empty() {
+        println!();
+        for u in &unrecognized {
+            let name = u["name"].as_str().unwrap_or("");
+            let hint = match u["suggestion"].as_str() {
+                Some(s) => format!("not recognized; did you mean {}?", s),
+                None => "not recognized by this version".to_string(),
+            };
+            eprintln!("{} {} {}", color::warning_indicator(), name, hint);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_registry_names_are_unique_and_prefixed() {
+        for (i, (name, _)) in ENV_VARS.iter().enumerate() {
+            assert!(name.starts_with("AGENT_BROWSER_"), "{}", name);
+            assert!(
+                ENV_VARS[i + 1..].iter().all(|(other, _)| other != name),
+                "duplicate {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_flags_reads_only_registered_vars() {
+        // `var` asserts registration in debug builds, and parse_flags reads
+        // every variable it supports unconditionally.
+        let _ = crate::flags::parse_flags(&[]);
+    }
+
+    #[test]
+    fn test_env_report_masks_secrets_and_flags_typos() {
+        let report = env_report(
+            vars(&[
+                ("AGENT_BROWSER_HEADED", "1"),
+                ("AGENT_BROWSER_ENCRYPTION_KEY", &"ab".repeat(32)),
+                ("AGENT_BROWSER_PROXY", "http://user:pw@proxy:8080"),
+                ("AGENT_BROWSER_HEADLES", "1"),
+                ("AGENT_BROWSER_SOMETHING_ELSE_ENTIRELY", "x"),
+                ("HOME", "/home/me"),
+            ])
+            .into_iter(),
+        );
+        let find = |name: &str| {
+            report["variables"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|v| v["name"] == name)
+                .cloned()
+                .unwrap()
+        };
+        assert_eq!(find("AGENT_BROWSER_HEADED")["value"], "1");
+        assert_eq!(find("AGENT_BROWSER_HEADED")["set"], true);
+        assert_eq!(find("AGENT_BROWSER_ENCRYPTION_KEY")["value"], "********");
+        assert_eq!(find("AGENT_BROWSER_PROXY")["value"], "http://proxy:8080/");
+        assert_eq!(find("AGENT_BROWSER_JSON")["set"], false);
+        assert!(find("AGENT_BROWSER_JSON")["value"].is_null());
+        assert_eq!(
+            report["variables"].as_array().unwrap().len(),
+            ENV_VARS.len()
+        );
+
+        let unrecognized = report["unrecognized"].as_array().unwrap();
+        assert_eq!(unrecognized.len(), 2);
+        assert_eq!(unrecognized[0]["name"], "AGENT_BROWSER_HEADLES");
+        assert_eq!(unrecognized[0]["suggestion"], "AGENT_BROWSER_HEADED");
+        assert!(unrecognized[1]["suggestion"].is_null());
+    }
+}
@@ -0,0 +1,190 @@
+//! Classify a failed daemon response as a protocol, page, browser, or
+//! internal error.
+//!
+//! Newer daemons tag failures with `errorKind`. Older daemons send only the
+//! message, so the classifier falls back to matching well-known messages and
+//! otherwise assumes a page error, the common case an agent can act on by
+//! changing what it does on the page. A protocol error means the daemon did
+//! not understand the request at all, which usually points at a CLI/daemon
+//! version mismatch rather than anything on the page.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The daemon rejected the request itself: unknown action, malformed
+    /// JSON, a payload missing fields the CLI always sends.
+    Protocol,
+    /// The command ran but the page did not cooperate: element not found,
+    /// timeout, evaluation error.
+    Page,
+    /// The browser or its CDP connection is missing or gone.
+    Browser,
+    /// The daemon failed in a way that is a bug in agent-browser.
+    Internal,
+}
+
+/// Lowercase substrings that identify a kind when the daemon sends none.
+/// Checked in order; the first match wins.
+const PROTOCOL_PATTERNS: &[&str] = &["unknown action", "not yet implemented:", "invalid json"];
+const BROWSER_PATTERNS: &[&str] = &[
+    "browser not launched",
+    "chrome not found",
+    "failed to launch",
+    "auto-launch failed",
+    "browser has disconnected",
+    "target closed",
+    "cdp connection",
+    "websocket",
+];
+const INTERNAL_PATTERNS: &[&str] = &["panicked", "internal error"];
+
+impl ErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Protocol => "protocol",
+            ErrorKind::Page => "page",
+            ErrorKind::Browser => "browser",
+            ErrorKind::Internal => "internal",
+        }
+    }
+
+    /// The daemon's `errorKind` value, if it is one this CLI knows.
+    pub fn from_envelope(kind: &str) -> Option<Self> {
+        match kind {
+            "protocol" => Some(ErrorKind::Protocol),
+            "page" => Some(ErrorKind::Page),
+            "browser" => Some(ErrorKind::Browser),
+            "internal" => Some(ErrorKind::Internal),
+            _ => None,
+        }
+    }
+
+    /// The `type` reported in `--json` error output, alongside the parser's
+    /// `unknown_command`, `missing_arguments`, and `invalid_value`.
+    pub fn error_type(self) -> &'static str {
+        match self {
+            ErrorKind::Protocol => "protocol_error",
+            ErrorKind::Page => "page_error",
+            ErrorKind::Browser => "browser_error",
+            ErrorKind::Internal => "internal_error",
+        }
+    }
+}
+
+/// Classify a failure from its message and the daemon's `errorKind`, which
+/// wins when present and recognized.
+pub fn classify(error: &str, error_kind: Option<&str>) -> ErrorKind {
+    if let Some(kind) = error_kind.and_then(ErrorKind::from_envelope) {
+        return kind;
+    }
+    let lower = error.to_lowercase();
+    let matches = |patterns: &[&str]| patterns.iter().any(|p| lower.contains(p));
+    // "Missing 'x' parameter" is the daemon's check on a field the CLI
+    // always sends, so seeing it means the two disagree on the payload.
+    if matches(PROTOCOL_PATTERNS) || (lower.starts_with("missing '") && lower.contains("parameter"))
+    {
+        ErrorKind::Protocol
+    } else if matches(BROWSER_PATTERNS) {
+        ErrorKind::Browser
+    } else if matches(INTERNAL_PATTERNS) {
+        ErrorKind::Internal
+    } else {
+        ErrorKind::Page
+    }
+}
+
+/// Advice for a protocol error, given the version the session's daemon
+/// wrote at startup (`None` if it could not be read).
+pub fn protocol_hint(daemon_version: Option<&str>, cli_version: &str) -> String {
+    match daemon_version {
+        Some(v) if v != cli_version => format!(
+            "The daemon is version {} but this CLI is {}. Run `agent-browser close` to restart it on the installed version.",
+            v, cli_version
+        ),
+        Some(_) => "The daemon did not understand this request although it reports the same version as the CLI. Run `agent-browser doctor` to check for a stale daemon or a second install.".to_string(),
+        None => format!(
+            "The daemon did not understand this request; it may be older than this CLI ({}). Run `agent-browser close` to restart it.",
+            cli_version
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_kind_wins() {
+        for kind in [
+            ErrorKind::Protocol,
+            ErrorKind::Page,
+            ErrorKind::Browser,
+            ErrorKind::Internal,
+        ] {
+            assert_eq!(classify("Element not found: #x", Some(kind.as_str())), kind);
+        }
+        // An unrecognized kind from a newer daemon falls back to heuristics.
+        assert_eq!(
+            classify("Unknown action: frobnicate", Some("quota")),
+            ErrorKind::Protocol
+        );
+    }
+
+    #[test]
+    fn test_heuristic_fallbacks() {
+        let fixtures: &[(&str, ErrorKind)] = &[
+            ("Not yet implemented: device_use", ErrorKind::Protocol),
+            (
+                "Unknown action 'hover' for find. Valid actions: click, fill.",
+                ErrorKind::Protocol,
+            ),
+            ("Invalid JSON: expected value at line 1", ErrorKind::Protocol),
+            ("Missing 'selector' parameter", ErrorKind::Protocol),
+            ("Browser not launched", ErrorKind::Browser),
+            ("Auto-launch failed: Chrome not found", ErrorKind::Browser),
+            ("CDP connection closed", ErrorKind::Browser),
+            ("Target closed", ErrorKind::Browser),
+            ("thread 'main' panicked at src/x.rs", ErrorKind::Internal),
+            ("Internal error: state poisoned", ErrorKind::Internal),
+            (
+                "Element not found: #submit. Verify the selector, role, or name is correct and the element exists in the DOM.",
+                ErrorKind::Page,
+            ),
+            (
+                "Operation timed out. The page may still be loading or the element may not exist.",
+                ErrorKind::Page,
+            ),
+            ("ReferenceError: foo is not defined", ErrorKind::Page),
+            ("", ErrorKind::Page),
+        ];
+        for (message, kind) in fixtures {
+            assert_eq!(classify(message, None), *kind, "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_error_types() {
+        assert_eq!(ErrorKind::Protocol.error_type(), "protocol_error");
+        assert_eq!(ErrorKind::Page.error_type(), "page_error");
+        assert_eq!(ErrorKind::Browser.error_type(), "browser_error");
+        assert_eq!(ErrorKind::Internal.error_type(), "internal_error");
+        assert_eq!(ErrorKind::from_envelope("PAGE"), None);
+    }
+
+    #[test]
+    fn test_protocol_hint_cross_references_version() {
+        let hint = protocol_hint(Some("0.20.0"), "0.21.0");
+        assert!(
+            hint.contains("version 0.20.0 but this CLI is 0.21.0"),
+            "{}",
+            hint
+        );
+        assert!(hint.contains("agent-browser close"));
+
+        let hint = protocol_hint(Some("0.21.0"), "0.21.0");
+        assert!(hint.contains("same version"), "{}", hint);
+        assert!(hint.contains("doctor"));
+
+        let hint = protocol_hint(None, "0.21.0");
+        assert!(hint.contains("older than this CLI (0.21.0)"), "{}", hint);
+    }
+}
@@ -0,0 +1,177 @@
+//! `--sessions a,b,c`: run one command against several sessions at once.
+//!
+//! Each session runs on its own thread with its own daemon and connection.
+//! Output is printed only after every session has finished, grouped by
+//! session, so lines from different sessions never interleave.
+
+use serde_json::{json, Map, Value};
+
+use crate::color;
+use crate::connection::Response;
+
+pub type SessionResult = Result<Response, String>;
+
+/// Run `run` for every session concurrently and return the results in the
+/// order the sessions were given. A failure (or panic) in one session does
+/// not stop the others.
+pub fn fan_out<F>(sessions: &[String], run: F) -> Vec<(String, SessionResult)>
+where
+    F: Fn(&str) -> SessionResult + Sync,
+{
+    let run = &run;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = sessions
+            .iter()
+            .map(|session| (session, scope.spawn(move || run(session))))
+            .collect();
+        handles
+            .into_iter()
+            .map(|(session, handle)| {
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Session worker panicked".to_string()));
+                (session.clone(), result)
+            })
+            .collect()
+    })
+}
+
+pub fn succeeded(result: &SessionResult) -> bool {
+    matches!(result, Ok(resp) if resp.success)
+}
+
+/// `--json` output: one response object per session, keyed by session name.
+/// Connection errors take the same `{"success": false, "error": ...}` shape
+/// as a failed response.
+pub fn grouped_json(results: &[(String, SessionResult)]) -> Value {
+    let mut grouped = Map::new();
+    for (session, result) in results {
+        let value = match result {
+            Ok(resp) => serde_json::to_value(resp).unwrap_or(Value::Null),
+            Err(e) => json!({ "success": false, "data": null, "error": e }),
+        };
+        grouped.insert(session.clone(), value);
+    }
+    Value::Object(grouped)
+}
+
+/// Header printed above each session's output in text mode.
+pub fn session_header(session: &str) -> String {
+    color::bold(&format!("[{}]", session))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ok_response;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn sessions(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn ok(data: Value) -> SessionResult {
+        Ok(ok_response(data))
+    }
+
+    #[test]
+    fn test_fan_out_runs_sessions_concurrently() {
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let results = fan_out(&sessions(&["a", "b", "c"]), |session| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(100));
+            running.fetch_sub(1, Ordering::SeqCst);
+            ok(json!({ "session": session }))
+        });
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        assert_eq!(results.len(), 3);
+    }
+
+    #[test]
+    fn test_fan_out_keeps_input_order_and_isolates_failures() {
+        // Sessions finish in reverse order; results still follow the input.
+        let results = fan_out(&sessions(&["slow", "broken", "fast"]), |session| {
+            match session {
+                "slow" => std::thread::sleep(Duration::from_millis(150)),
+                "broken" => return Err("Failed to connect".to_string()),
+                _ => {}
+            }
+            ok(json!({ "session": session }))
+        });
+        let names: Vec<&str> = results.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(names, vec!["slow", "broken", "fast"]);
+        assert!(succeeded(&results[0].1));
+        assert!(!succeeded(&results[1].1));
+        assert!(succeeded(&results[2].1));
+        assert_eq!(
+            results[2].1.as_ref().unwrap().data,
+            Some(json!({ "session": "fast" }))
+        );
+    }
+
+    #[test]
+    fn test_grouped_json_keys_by_session() {
+        let results = vec![
+            ("a".to_string(), ok(json!({ "title": "A" }))),
+            ("b".to_string(), Err("Failed to connect".to_string())),
+        ];
+        let grouped = grouped_json(&results);
+        assert_eq!(grouped["a"]["success"], true);
+        assert_eq!(grouped["a"]["data"]["title"], "A");
+        assert_eq!(grouped["b"]["success"], false);
+        assert_eq!(grouped["b"]["error"], "Failed to connect");
+    }
+
+    /// Each session talks to its own daemon socket; replies never cross.
+    #[cfg(unix)]
+    #[test]
+    fn test_fan_out_over_mock_daemon_sockets() {
+        use crate::connection::send_command;
+        use crate::test_utils::EnvGuard;
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+
+        // "first" answers after "second" to show results are not reordered
+        // by completion time. "missing" has no socket at all.
+        let mut servers = Vec::new();
+        for (name, delay) in [("first", 150), ("second", 0)] {
+            let listener = UnixListener::bind(dir.path().join(format!("{}.sock", name))).unwrap();
+            servers.push(std::thread::spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let request: Value = serde_json::from_str(&line).unwrap();
+                std::thread::sleep(Duration::from_millis(delay));
+                let reply = json!({
+                    "success": true,
+                    "data": { "session": name, "action": request["action"] }
+                });
+                let mut stream = reader.into_inner();
+                writeln!(stream, "{}", reply).unwrap();
+            }));
+        }
+
+        let results = fan_out(&sessions(&["first", "missing", "second"]), |session| {
+            send_command(json!({ "id": "1", "action": "title" }), session)
+        });
+        for server in servers {
+            server.join().unwrap();
+        }
+
+        let grouped = grouped_json(&results);
+        assert_eq!(grouped["first"]["data"]["session"], "first");
+        assert_eq!(grouped["first"]["data"]["action"], "title");
+        assert_eq!(grouped["second"]["data"]["session"], "second");
+        assert_eq!(grouped["missing"]["success"], false);
+        let names: Vec<&str> = results.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(names, vec!["first", "missing", "second"]);
+    }
+}
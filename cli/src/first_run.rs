@@ -0,0 +1,182 @@
+//! Guidance when a command fails because no browser is installed yet.
+//!
+//! New users who skip `agent-browser install` otherwise see a launch error
+//! from the daemon with the fix buried in it. [`detect`] recognizes that
+//! class of error, and [`InstallRetry`] decides whether to offer running
+//! the install and retrying the command, at most once.
+
+/// Error code a daemon may attach (as `data.code`) to a launch failure
+/// caused by a missing browser binary.
+pub const BROWSER_NOT_INSTALLED: &str = "browser_not_installed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingBrowser {
+    /// Installable with `agent-browser install`.
+    Chrome,
+    /// Installed separately; `agent-browser install` cannot fetch it.
+    Lightpanda,
+}
+
+impl MissingBrowser {
+    /// What to tell the user, including the exact command to run.
+    pub fn hint(self) -> &'static str {
+        match self {
+            MissingBrowser::Chrome => {
+                "Chrome is not installed. Run `agent-browser install` to download it \
+                 (`agent-browser install --with-deps` on Linux also installs system libraries), \
+                 or pass --executable-path to use an existing browser."
+            }
+            MissingBrowser::Lightpanda => {
+                "Lightpanda is not installed. Install it from \
+                 https://lightpanda.io/docs/open-source/installation, \
+                 or pass --executable-path to point at the binary."
+            }
+        }
+    }
+}
+
+/// Which browser is missing, judging by a daemon error message and the
+/// structured code when the daemon provides one.
+pub fn detect(error: &str, code: Option<&str>) -> Option<MissingBrowser> {
+    let lower = error.to_lowercase();
+    if lower.contains("lightpanda not found") {
+        return Some(MissingBrowser::Lightpanda);
+    }
+    let chrome_missing = lower.starts_with("chrome not found")
+        || lower.contains("chrome not found. ")
+        // Playwright's wording, seen when reusing its browser cache.
+        || lower.contains("executable doesn't exist")
+        || lower.contains("executable does not exist");
+    if chrome_missing || code == Some(BROWSER_NOT_INSTALLED) {
+        return Some(MissingBrowser::Chrome);
+    }
+    None
+}
+
+/// What to do after a command's result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallDecision {
+    /// Not a missing-browser failure; handle the result as usual.
+    Proceed,
+    /// Print the hint, then handle the result as usual.
+    Report(MissingBrowser),
+    /// Print the hint and offer to install; on yes, install and retry.
+    OfferInstall(MissingBrowser),
+}
+
+/// Tracks the single retry allowed after an install.
+#[derive(Debug, Default)]
+pub struct InstallRetry {
+    retried: bool,
+}
+
+impl InstallRetry {
+    /// Decide what to do with a command's error. `interactive` is whether
+    /// stdin is a TTY; `prompt_disabled` is `--no-install-prompt`. Only
+    /// Chrome is offered, and only once.
+    pub fn decide(
+        &mut self,
+        error: Option<(&str, Option<&str>)>,
+        interactive: bool,
+        prompt_disabled: bool,
+    ) -> InstallDecision {
+        let Some(browser) = error.and_then(|(message, code)| detect(message, code)) else {
+            return InstallDecision::Proceed;
+        };
+        if browser == MissingBrowser::Chrome && interactive && !prompt_disabled && !self.retried {
+            self.retried = true;
+            InstallDecision::OfferInstall(browser)
+        } else {
+            InstallDecision::Report(browser)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROME_MISSING: &str = "Chrome not found. Checked:\n  \
+        - agent-browser cache: /home/u/.agent-browser/browsers\n  \
+        - System Chrome installations\n\
+        Run `agent-browser install` to download Chrome, or use --executable-path.";
+
+    #[test]
+    fn test_detect_missing_browser_errors() {
+        assert_eq!(detect(CHROME_MISSING, None), Some(MissingBrowser::Chrome));
+        assert_eq!(
+            detect(
+                "Chrome not found. Install Chrome or use --executable-path.",
+                None
+            ),
+            Some(MissingBrowser::Chrome)
+        );
+        assert_eq!(
+            detect(
+                "browserType.launch: Executable doesn't exist at /ms-playwright/chromium-1091/chrome",
+                None
+            ),
+            Some(MissingBrowser::Chrome)
+        );
+        assert_eq!(
+            detect(
+                "Lightpanda not found. Install it from https://lightpanda.io",
+                None
+            ),
+            Some(MissingBrowser::Lightpanda)
+        );
+        assert_eq!(
+            detect("Browser launch failed", Some(BROWSER_NOT_INSTALLED)),
+            Some(MissingBrowser::Chrome)
+        );
+    }
+
+    #[test]
+    fn test_detect_ignores_other_not_found_errors() {
+        for error in [
+            "Element not found: @e12",
+            "No Chrome user data directory found. Cannot resolve profile name.",
+            "Chrome profile \"Work\" not found",
+            "safaridriver not found. Safari WebDriver requires macOS with Safari.",
+        ] {
+            assert_eq!(detect(error, None), None, "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_install_retry_offers_once() {
+        let mut retry = InstallRetry::default();
+        let missing = Some((CHROME_MISSING, None));
+        assert_eq!(
+            retry.decide(missing, true, false),
+            InstallDecision::OfferInstall(MissingBrowser::Chrome)
+        );
+        // Still missing after the install: report, never loop.
+        assert_eq!(
+            retry.decide(missing, true, false),
+            InstallDecision::Report(MissingBrowser::Chrome)
+        );
+        assert_eq!(retry.decide(None, true, false), InstallDecision::Proceed);
+    }
+
+    #[test]
+    fn test_install_retry_only_prompts_when_allowed() {
+        let missing = Some((CHROME_MISSING, None));
+        assert_eq!(
+            InstallRetry::default().decide(missing, false, false),
+            InstallDecision::Report(MissingBrowser::Chrome)
+        );
+        assert_eq!(
+            InstallRetry::default().decide(missing, true, true),
+            InstallDecision::Report(MissingBrowser::Chrome)
+        );
+        assert_eq!(
+            InstallRetry::default().decide(Some(("Lightpanda not found.", None)), true, false),
+            InstallDecision::Report(MissingBrowser::Lightpanda)
+        );
+        assert_eq!(
+            InstallRetry::default().decide(Some(("Timeout waiting for @e1", None)), true, false),
+            InstallDecision::Proceed
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use crate::color;
+use crate::env_vars;
 use crate::plugins::PluginConfig;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -9,6 +11,10 @@ const CONFIG_DIR: &str = ".agent-browser";
 const CONFIG_FILENAME: &str = "config.json";
 const PROJECT_CONFIG_FILENAME: &str = "agent-browser.json";
 
+/// How long the same "ignored: daemon already running" warning stays
+/// suppressed when AGENT_BROWSER_FLAG_WARNING_WINDOW_MS is unset (10 minutes).
+const DEFAULT_FLAG_WARNING_WINDOW_MS: u64 = 10 * 60 * 1000;
+
 /// Parse idle timeout from user-friendly format.
 /// Supports: "10s" (seconds), "3m" (minutes), "1h" (hours), or raw milliseconds.
 fn parse_idle_timeout(s: &str) -> Result<String, String> {
@@ -51,7 +57,252 @@ fn parse_idle_timeout_value(value: Option<String>, source: &str) -> Option<Strin
     })
 }
 
-#[derive(Debug, Default, Deserialize)]
+/// Parse `--window-size` as `<width>x<height>` in pixels, both positive.
+pub fn parse_window_size(s: &str) -> Result<(u32, u32), String> {
+    let parts = s.trim().split_once(['x', 'X']);
+    match parts.map(|(w, h)| (w.trim().parse::<u32>(), h.trim().parse::<u32>())) {
+        Some((Ok(w), Ok(h))) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(format!(
+            "expected <width>x<height> in positive pixels, got '{}'",
+            s
+        )),
+    }
+}
+
+/// Parse `--window-position` as `<x>,<y>` in screen pixels. Zero is allowed
+/// so a window can be pinned to the top-left corner.
+pub fn parse_window_position(s: &str) -> Result<(u32, u32), String> {
+    match s
+        .trim()
+        .split_once(',')
+        .map(|(x, y)| (x.trim().parse::<u32>(), y.trim().parse::<u32>()))
+    {
+        Some((Ok(x), Ok(y))) => Ok((x, y)),
+        _ => Err(format!(
+            "expected <x>,<y> as non-negative pixels, got '{}'",
+            s
+        )),
+    }
+}
+
+/// Append `--window-size` / `--window-position` Chromium switches for the
+/// window flags to the user's `--args`. A switch the user already passed
+/// explicitly wins; the flag is dropped and a warning returned instead.
+pub fn merge_window_args(
+    mut args: Vec<String>,
+    size: Option<(u32, u32)>,
+    position: Option<(u32, u32)>,
+) -> (Vec<String>, Vec<String>) {
+    let mut warnings = Vec::new();
+    let explicit = |args: &[String], prefixes: &[&str]| {
+        args.iter()
+            .find(|a| prefixes.iter().any(|p| a.starts_with(p)))
+            .cloned()
+    };
+    if let Some((w, h)) = size {
+        match explicit(
+            &args,
+            &["--window-size", "--start-maximized", "--start-fullscreen"],
+        ) {
+            Some(arg) => warnings.push(format!(
+                "--window-size {}x{} ignored: --args already contains {}",
+                w, h, arg
+            )),
+            None => args.push(format!("--window-size={},{}", w, h)),
+        }
+    }
+    if let Some((x, y)) = position {
+        match explicit(&args, &["--window-position"]) {
+            Some(arg) => warnings.push(format!(
+                "--window-position {},{} ignored: --args already contains {}",
+                x, y, arg
+            )),
+            None => args.push(format!("--window-position={},{}", x, y)),
+        }
+    }
+    (args, warnings)
+}
+
+fn parse_window_value(
+    value: Option<String>,
+    flag: &str,
+    source: &str,
+    parse: fn(&str) -> Result<(u32, u32), String>,
+) -> Option<(u32, u32)> {
+    value.and_then(|raw| match parse(&raw) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            eprintln!(
+                "{} invalid {} from {}: {}",
+                color::warning_indicator(),
+                flag,
+                source,
+                e
+            );
+            None
+        }
+    })
+}
+
+/// Connection-specific headers (RFC 7230 section 6.1) that the browser manages
+/// itself and that must not be overridden per request.
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "trailers",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Check a header name against the RFC 7230 `token` grammar and reject
+/// hop-by-hop headers.
+pub fn validate_header_name(name: &str) -> Result<(), String> {
+    let is_tchar = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+    if name.is_empty() || !name.chars().all(is_tchar) {
+        return Err(format!("Invalid header name: {:?}", name));
+    }
+    if HOP_BY_HOP_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+        return Err(format!(
+            "Header {} is hop-by-hop and cannot be set per request",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Header object that keeps the order keys appear in, so duplicates can be
+/// collapsed with the later value winning.
+struct OrderedHeaders(Vec<(String, serde_json::Value)>);
+
+impl<'de> Deserialize<'de> for OrderedHeaders {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = OrderedHeaders;
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a JSON object of header names to string values")
+            }
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(OrderedHeaders(entries))
+            }
+        }
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+/// Parse and validate the `--headers` JSON: an object of string values with
+/// valid, non hop-by-hop names. Keys that differ only in case collapse to the
+/// last one given.
+pub fn parse_headers_json(raw: &str) -> Result<Vec<(String, String)>, String> {
+    let OrderedHeaders(entries) = serde_json::from_str(raw)
+        .map_err(|_| format!("Invalid JSON for --headers (expected an object): {}", raw))?;
+    let mut headers = Vec::new();
+    for (name, value) in entries {
+        validate_header_name(&name)?;
+        let value = value
+            .as_str()
+            .ok_or_else(|| format!("Header {} must have a string value, got {}", name, value))?
+            .to_string();
+        headers.push((name, value));
+    }
+    Ok(merge_headers(Vec::new(), headers))
+}
+
+/// Merge `overrides` over `base`. Names compare case-insensitively; a later
+/// entry replaces an earlier one in place.
+pub fn merge_headers(
+    base: Vec<(String, String)>,
+    overrides: Vec<(String, String)>,
+) -> Vec<(String, String)> {
+    let mut merged: Vec<(String, String)> = Vec::new();
+    for (name, value) in base.into_iter().chain(overrides) {
+        match merged
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(&name))
+        {
+            Some(entry) => *entry = (name, value),
+            None => merged.push((name, value)),
+        }
+    }
+    merged
+}
+
+/// Split one `--provider-opt key=value` on the first `=`, so values may
+/// themselves contain `=`.
+pub fn parse_provider_opt(raw: &str) -> Result<(String, String), String> {
+    let (key, value) = raw
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid --provider-opt {:?}: expected key=value", raw))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!(
+            "Invalid --provider-opt {:?}: key must not be empty",
+            raw
+        ));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Split a `--sessions` value on commas, dropping empty entries and
+/// duplicates while keeping the order given.
+pub fn parse_session_list(raw: &str) -> Vec<String> {
+    let mut sessions: Vec<String> = Vec::new();
+    for name in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        if !sessions.iter().any(|existing| existing == name) {
+            sessions.push(name.to_string());
+        }
+    }
+    sessions
+}
+
+/// Combine provider options from AGENT_BROWSER_PROVIDER_OPTS (a JSON object of
+/// strings) with repeatable `--provider-opt` values, which win per key.
+pub fn resolve_provider_options(
+    env_json: Option<&str>,
+    cli_opts: &[String],
+) -> Result<BTreeMap<String, String>, String> {
+    let mut options = BTreeMap::new();
+    if let Some(raw) = env_json.filter(|s| !s.trim().is_empty()) {
+        let parsed: BTreeMap<String, serde_json::Value> =
+            serde_json::from_str(raw).map_err(|_| {
+                format!(
+                    "Invalid AGENT_BROWSER_PROVIDER_OPTS (expected a JSON object): {}",
+                    raw
+                )
+            })?;
+        for (key, value) in parsed {
+            if key.trim().is_empty() {
+                return Err("AGENT_BROWSER_PROVIDER_OPTS keys must not be empty".to_string());
+            }
+            let value = value.as_str().ok_or_else(|| {
+                format!(
+                    "AGENT_BROWSER_PROVIDER_OPTS value for {} must be a string",
+                    key
+                )
+            })?;
+            options.insert(key, value.to_string());
+        }
+    }
+    for raw in cli_opts {
+        let (key, value) = parse_provider_opt(raw)?;
+        options.insert(key, value);
+    }
+    Ok(options)
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Config {
     pub headed: Option<bool>,
@@ -70,6 +321,7 @@ pub struct Config {
     pub init_scripts: Option<Vec<String>>,
     pub enable: Option<Vec<String>>,
     pub profile: Option<String>,
+    pub user_data_dir: Option<String>,
     pub state: Option<String>,
     pub proxy: Option<String>,
     pub proxy_bypass: Option<String>,
@@ -80,27 +332,38 @@ pub struct Config {
     pub hide_scrollbars: Option<bool>,
     pub webgpu: Option<bool>,
     pub ignore_https_errors: Option<bool>,
+    pub ignore_https_origins: Option<Vec<String>>,
     pub allow_file_access: Option<bool>,
     pub cdp: Option<String>,
     pub auto_connect: Option<bool>,
     pub headers: Option<String>,
     pub annotate: Option<bool>,
+    pub full: Option<bool>,
     pub color_scheme: Option<String>,
+    pub default_scheme: Option<String>,
     pub download_path: Option<String>,
+    pub artifacts_dir: Option<String>,
     pub content_boundaries: Option<bool>,
     pub max_output: Option<usize>,
     pub allowed_domains: Option<Vec<String>>,
     pub action_policy: Option<String>,
     pub confirm_actions: Option<String>,
     pub confirm_interactive: Option<bool>,
+    pub on_confirmation: Option<String>,
     pub engine: Option<String>,
     pub screenshot_dir: Option<String>,
     pub screenshot_quality: Option<u32>,
     pub screenshot_format: Option<String>,
     pub idle_timeout: Option<String>,
     pub no_auto_dialog: Option<bool>,
+    pub require_fresh_daemon: Option<bool>,
     pub model: Option<String>,
     pub plugins: Option<Vec<PluginConfig>>,
+    pub redact_patterns: Option<Vec<String>>,
+    pub aliases: Option<BTreeMap<String, Vec<String>>>,
+    /// Per-session defaults keyed by session name, merged over the rest of
+    /// the config when that session is selected.
+    pub sessions: Option<BTreeMap<String, Config>>,
 }
 
 impl Config {
@@ -140,6 +403,7 @@ impl Config {
                 (a, b) => b.or(a),
             },
             profile: other.profile.or(self.profile),
+            user_data_dir: other.user_data_dir.or(self.user_data_dir),
             state: other.state.or(self.state),
             proxy: other.proxy.or(self.proxy),
             proxy_bypass: other.proxy_bypass.or(self.proxy_bypass),
@@ -150,26 +414,46 @@ impl Config {
             hide_scrollbars: other.hide_scrollbars.or(self.hide_scrollbars),
             webgpu: other.webgpu.or(self.webgpu),
             ignore_https_errors: other.ignore_https_errors.or(self.ignore_https_errors),
+            ignore_https_origins: match (self.ignore_https_origins, other.ignore_https_origins) {
+                (Some(mut a), Some(b)) => {
+                    a.extend(b);
+                    Some(a)
+                }
+                (a, b) => b.or(a),
+            },
             allow_file_access: other.allow_file_access.or(self.allow_file_access),
             cdp: other.cdp.or(self.cdp),
             auto_connect: other.auto_connect.or(self.auto_connect),
             headers: other.headers.or(self.headers),
             annotate: other.annotate.or(self.annotate),
+            full: other.full.or(self.full),
             color_scheme: other.color_scheme.or(self.color_scheme),
+            default_scheme: other.default_scheme.or(self.default_scheme),
             download_path: other.download_path.or(self.download_path),
+            artifacts_dir: other.artifacts_dir.or(self.artifacts_dir),
             content_boundaries: other.content_boundaries.or(self.content_boundaries),
             max_output: other.max_output.or(self.max_output),
             allowed_domains: other.allowed_domains.or(self.allowed_domains),
             action_policy: other.action_policy.or(self.action_policy),
             confirm_actions: other.confirm_actions.or(self.confirm_actions),
             confirm_interactive: other.confirm_interactive.or(self.confirm_interactive),
+            on_confirmation: other.on_confirmation.or(self.on_confirmation),
             engine: other.engine.or(self.engine),
             screenshot_dir: other.screenshot_dir.or(self.screenshot_dir),
             screenshot_quality: other.screenshot_quality.or(self.screenshot_quality),
             screenshot_format: other.screenshot_format.or(self.screenshot_format),
             idle_timeout: other.idle_timeout.or(self.idle_timeout),
             no_auto_dialog: other.no_auto_dialog.or(self.no_auto_dialog),
+            require_fresh_daemon: other.require_fresh_daemon.or(self.require_fresh_daemon),
             model: other.model.or(self.model),
+            redact_patterns: other.redact_patterns.or(self.redact_patterns),
+            aliases: match (self.aliases, other.aliases) {
+                (Some(mut a), Some(b)) => {
+                    a.extend(b);
+                    Some(a)
+                }
+                (a, b) => b.or(a),
+            },
             plugins: match (self.plugins, other.plugins) {
                 (Some(mut a), Some(b)) => {
                     a.extend(b);
@@ -177,8 +461,52 @@ impl Config {
                 }
                 (a, b) => b.or(a),
             },
+            sessions: match (self.sessions, other.sessions) {
+                (Some(mut a), Some(b)) => {
+                    a.extend(b);
+                    Some(a)
+                }
+                (a, b) => b.or(a),
+            },
         }
     }
+
+    /// Merge the `sessions.<session>` profile over this config and return
+    /// the keys it set. A profile cannot pick another session or nest
+    /// profiles, so its `session` and `sessions` keys are ignored.
+    fn apply_session_profile(self, session: &str) -> (Config, Vec<String>) {
+        let mut base = self;
+        let Some(mut profile) = base
+            .sessions
+            .as_mut()
+            .and_then(|sessions| sessions.remove(session))
+        else {
+            return (base, Vec::new());
+        };
+        profile.session = None;
+        profile.sessions = None;
+        let keys = match serde_json::to_value(&profile) {
+            Ok(serde_json::Value::Object(map)) => map
+                .into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, _)| k)
+                .collect(),
+            _ => Vec::new(),
+        };
+        (base.merge(profile), keys)
+    }
+}
+
+/// The session a command runs in, for picking a config session profile:
+/// `--session`, then AGENT_BROWSER_SESSION, then the config's `session`.
+fn selected_session(args: &[String], config: &Config) -> String {
+    args.windows(2)
+        .rev()
+        .find(|pair| pair[0] == "--session")
+        .map(|pair| pair[1].clone())
+        .or_else(|| env_vars::var("AGENT_BROWSER_SESSION").ok())
+        .or_else(|| config.session.clone())
+        .unwrap_or_else(|| "default".to_string())
 }
 
 fn read_config_file(path: &Path) -> Option<Config> {
@@ -206,14 +534,26 @@ fn read_config_file(path: &Path) -> Option<Config> {
 /// Check if a boolean environment variable is set to a truthy value.
 /// Returns false when unset, empty, or set to "0", "false", or "no" (case-insensitive).
 fn env_var_is_truthy(name: &str) -> bool {
-    match env::var(name) {
+    match env_vars::var(name) {
         Ok(val) => !matches!(val.to_lowercase().as_str(), "0" | "false" | "no" | ""),
         Err(_) => false,
     }
 }
 
+/// AGENT_BROWSER_AUTO_RETRY_ACTIONS is a retry count, or a boolean word for
+/// the default count.
+fn parse_auto_retry_env(value: &str) -> u32 {
+    match value.trim().parse::<u32>() {
+        Ok(n) => n,
+        Err(_) if matches!(value.trim().to_lowercase().as_str(), "true" | "yes" | "on") => {
+            crate::action_retry::DEFAULT_RETRIES
+        }
+        Err(_) => 0,
+    }
+}
+
 fn env_var_bool(name: &str) -> Option<bool> {
-    env::var(name)
+    env_vars::var(name)
         .ok()
         .map(|val| !matches!(val.to_lowercase().as_str(), "0" | "false" | "no" | ""))
 }
@@ -232,6 +572,90 @@ fn parse_bool_arg(args: &[String], i: usize) -> (bool, bool) {
     }
 }
 
+/// Global boolean flags. Each accepts a bare form (true), `--flag=true|false`,
+/// or a following `true`/`false` token, so a config file value can be turned
+/// off for one invocation.
+const BOOL_FLAGS: &[&str] = &[
+    "--json",
+    "--headed",
+    "--webgpu",
+    "--debug",
+    "--ignore-https-errors",
+    "--allow-file-access",
+    "--hide-scrollbars",
+    "--auto-connect",
+    "--annotate",
+    "--content-boundaries",
+    "--confirm-interactive",
+    "--no-auto-dialog",
+    "--no-exit-code",
+    "--no-install-prompt",
+    "--require-fresh-daemon",
+    "-v",
+    "--verbose",
+    "-q",
+    "--quiet",
+];
+
+/// Match a boolean flag at `args[i]`. Returns the flag name, its value, and
+/// whether the following token was consumed as that value. An inline value
+/// other than true/false is not treated as the flag.
+fn parse_bool_flag(args: &[String], i: usize) -> Option<(&'static str, bool, bool)> {
+    let arg = args[i].as_str();
+    let (name, inline) = match arg.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (arg, None),
+    };
+    let name = *BOOL_FLAGS.iter().find(|flag| **flag == name)?;
+    match inline {
+        Some("true") => Some((name, true, false)),
+        Some("false") => Some((name, false, false)),
+        Some(_) => None,
+        None => {
+            let (value, consumed) = parse_bool_arg(args, i);
+            Some((name, value, consumed))
+        }
+    }
+}
+
+fn set_bool_flag(flags: &mut Flags, name: &str, value: bool) {
+    match name {
+        "--json" => flags.json = value,
+        "--headed" => {
+            flags.headed = value;
+            flags.cli_headed = true;
+        }
+        "--webgpu" => {
+            flags.webgpu = value;
+            flags.cli_webgpu = true;
+        }
+        "--debug" => flags.debug = value,
+        "--ignore-https-errors" => flags.ignore_https_errors = value,
+        "--allow-file-access" => {
+            flags.allow_file_access = value;
+            flags.cli_allow_file_access = true;
+        }
+        "--hide-scrollbars" => {
+            flags.hide_scrollbars = value;
+            flags.cli_hide_scrollbars = true;
+        }
+        "--auto-connect" => flags.auto_connect = value,
+        "--annotate" => {
+            flags.annotate = value;
+            flags.cli_annotate = true;
+        }
+        "--content-boundaries" => flags.content_boundaries = value,
+        "--confirm-interactive" => flags.confirm_interactive = value,
+        "--no-auto-dialog" => flags.no_auto_dialog = value,
+        "--no-exit-code" => flags.no_exit_code = value,
+        "--no-install-prompt" => flags.no_install_prompt = value,
+        "--require-fresh-daemon" => flags.require_fresh_daemon = value,
+        "-v" | "--verbose" => flags.verbose = value,
+        "-q" | "--quiet" => flags.quiet = value,
+        _ => {}
+    }
+}
+
 /// Extract --config <path> from args before full flag parsing.
 /// Returns `Some(Some(path))` if --config <path> found, `Some(None)` if --config
 /// was the last arg with no value, `None` if --config not present.
@@ -255,21 +679,32 @@ fn extract_config_path(args: &[String]) -> Option<Option<String>> {
         "--init-script",
         "--enable",
         "--profile",
+        "--user-data-dir",
         "--state",
         "--proxy",
         "--proxy-bypass",
         "--args",
+        "--window-size",
+        "--window-position",
         "--user-agent",
         "-p",
         "--provider",
         "--device",
         "--session-name",
         "--color-scheme",
+        "--default-scheme",
         "--download-path",
+        "--artifacts-dir",
         "--max-output",
+        "--cache",
+        "--ref-prefix",
         "--allowed-domains",
+        "--ignore-https-origin",
         "--action-policy",
         "--confirm-actions",
+        "--on-confirmation",
+        "--provider-opt",
+        "--sessions",
         "--engine",
         "--screenshot-dir",
         "--screenshot-quality",
@@ -294,7 +729,7 @@ pub fn load_config(args: &[String]) -> Result<Config, String> {
     let explicit = extract_config_path(args)
         .map(|p| ("--config", p))
         .or_else(|| {
-            env::var("AGENT_BROWSER_CONFIG")
+            env_vars::var("AGENT_BROWSER_CONFIG")
                 .ok()
                 .map(|p| ("AGENT_BROWSER_CONFIG", Some(p)))
         });
@@ -322,6 +757,7 @@ pub fn load_config(args: &[String]) -> Result<Config, String> {
     })
 }
 
+#[derive(Clone)]
 pub struct Flags {
     pub json: bool,
     pub headed: bool,
@@ -341,31 +777,68 @@ pub struct Flags {
     pub init_scripts: Vec<String>,
     pub enable: Vec<String>,
     pub profile: Option<String>,
+    pub user_data_dir: Option<String>,
     pub state: Option<String>,
     pub proxy: Option<String>,
     pub proxy_bypass: Option<String>,
     pub args: Option<String>,
+    /// `--window-size WxH`: the headed browser window's outer size.
+    pub window_size: Option<(u32, u32)>,
+    /// `--window-position X,Y`: where the headed browser window opens.
+    pub window_position: Option<(u32, u32)>,
     pub user_agent: Option<String>,
     pub provider: Option<String>,
+    /// Raw `--provider-opt key=value` entries, resolved with
+    /// [`resolve_provider_options`].
+    pub provider_opts: Vec<String>,
+    /// `--sessions a,b,c`: run the command against each named session.
+    pub sessions: Option<Vec<String>>,
     pub ignore_https_errors: bool,
+    /// `--ignore-https-origin`: https origins whose certificate errors are
+    /// ignored while TLS checks stay on for everything else.
+    pub ignore_https_origins: Vec<String>,
     pub allow_file_access: bool,
     pub hide_scrollbars: bool,
     pub webgpu: bool,
     /// Env-only (AGENT_BROWSER_NO_XVFB): disable automatic Xvfb for headed
     /// launches on displayless Linux hosts.
     pub no_xvfb: bool,
+    /// Env-only (AGENT_BROWSER_NO_HISTORY): do not record commands in the
+    /// session history.
+    pub no_history: bool,
     pub device: Option<String>,
     pub auto_connect: bool,
     pub session_name: Option<String>,
     pub annotate: bool,
+    /// Default for `screenshot --full` (AGENT_BROWSER_FULL or `full` config);
+    /// the command's own `--full [true|false]` wins.
+    pub full: bool,
     pub color_scheme: Option<String>,
+    pub default_scheme: Option<String>,
     pub download_path: Option<String>,
+    pub artifacts_dir: Option<String>,
     pub content_boundaries: bool,
     pub max_output: Option<usize>,
+    /// `--cache <ms>` or AGENT_BROWSER_CACHE_MS: replay read-only getter
+    /// responses younger than this (see [`crate::response_cache`]).
+    pub cache_ms: Option<u64>,
+    /// AGENT_BROWSER_FLAG_WARNING_WINDOW_MS: how long a repeat of the same
+    /// "ignored: daemon already running" warning stays suppressed (0 shows
+    /// every one).
+    pub flag_warning_window_ms: u64,
+    /// `--auto-retry-actions [n]` or AGENT_BROWSER_AUTO_RETRY_ACTIONS: retry a
+    /// click/fill/check/select whose element was detached or covered up to
+    /// this many times (0 is off; see [`crate::action_retry`]).
+    pub auto_retry_actions: u32,
+    /// Prefix for snapshot refs (`@ab12-e3`), from `--ref-prefix` or
+    /// AGENT_BROWSER_REF_PREFIX.
+    pub ref_prefix: Option<String>,
     pub allowed_domains: Option<Vec<String>>,
     pub action_policy: Option<String>,
     pub confirm_actions: Option<String>,
     pub confirm_interactive: bool,
+    /// Policy for confirmation_required responses: allow, deny or fail.
+    pub on_confirmation: Option<String>,
     pub engine: Option<String>,
     pub screenshot_dir: Option<String>,
     pub screenshot_quality: Option<u32>,
@@ -373,8 +846,25 @@ pub struct Flags {
     pub idle_timeout: Option<String>, // Canonical milliseconds string for AGENT_BROWSER_IDLE_TIMEOUT_MS
     pub default_timeout: Option<u64>, // AGENT_BROWSER_DEFAULT_TIMEOUT in ms
     pub no_auto_dialog: bool,
+    /// `--no-exit-code`: `is` checks exit 0 even when the result is false.
+    pub no_exit_code: bool,
+    /// `--no-install-prompt`: when no browser is installed, print how to
+    /// install one instead of offering to run `agent-browser install`.
+    pub no_install_prompt: bool,
+    /// `--require-fresh-daemon`: fail instead of warning when launch options
+    /// would be ignored by a daemon that is already running.
+    pub require_fresh_daemon: bool,
     pub model: Option<String>,
     pub plugins: Vec<PluginConfig>,
+    /// `redactPatterns` (config only): names whose values are hidden when a
+    /// command is echoed. `None` uses [`crate::redact::DEFAULT_REDACT_PATTERNS`].
+    pub redact_patterns: Option<Vec<String>>,
+    /// `aliases` (config only): command words expanded by
+    /// [`crate::aliases::expand`].
+    pub aliases: BTreeMap<String, Vec<String>>,
+    /// Config keys set by the selected session's `sessions.<name>` profile,
+    /// listed in `--debug` output.
+    pub session_profile: Vec<String>,
     pub verbose: bool,
     pub quiet: bool,
 
@@ -385,6 +875,7 @@ pub struct Flags {
     pub cli_init_scripts: bool,
     pub cli_enable: bool,
     pub cli_profile: bool,
+    pub cli_user_data_dir: bool,
     pub cli_state: bool,
     pub cli_args: bool,
     pub cli_user_agent: bool,
@@ -397,6 +888,13 @@ pub struct Flags {
     pub cli_headed: bool,
     pub cli_webgpu: bool,
     pub cli_restore: bool,
+    pub cli_allowed_domains: bool,
+    pub cli_ignore_https_origins: bool,
+    pub cli_action_policy: bool,
+    pub cli_confirm_actions: bool,
+    pub cli_device: bool,
+    pub cli_provider: bool,
+    pub cli_session_name: bool,
 }
 
 pub fn parse_flags(args: &[String]) -> Flags {
@@ -404,8 +902,10 @@ pub fn parse_flags(args: &[String]) -> Flags {
         eprintln!("{} {}", color::warning_indicator(), e);
         std::process::exit(1);
     });
+    let session = selected_session(args, &config);
+    let (config, session_profile) = config.apply_session_profile(&session);
 
-    let extensions_env = env::var("AGENT_BROWSER_EXTENSIONS")
+    let extensions_env = env_vars::var("AGENT_BROWSER_EXTENSIONS")
         .ok()
         .map(|s| {
             s.split(',')
@@ -421,7 +921,7 @@ pub fn parse_flags(args: &[String]) -> Flags {
         config.extensions.unwrap_or_default()
     };
 
-    let init_scripts_env = env::var("AGENT_BROWSER_INIT_SCRIPTS")
+    let init_scripts_env = env_vars::var("AGENT_BROWSER_INIT_SCRIPTS")
         .ok()
         .map(|s| {
             s.split(',')
@@ -437,7 +937,7 @@ pub fn parse_flags(args: &[String]) -> Flags {
         config.init_scripts.unwrap_or_default()
     };
 
-    let enable_env = env::var("AGENT_BROWSER_ENABLE")
+    let enable_env = env_vars::var("AGENT_BROWSER_ENABLE")
         .ok()
         .map(|s| {
             s.split(',')
@@ -453,7 +953,7 @@ pub fn parse_flags(args: &[String]) -> Flags {
         config.enable.unwrap_or_default()
     };
 
-    let plugins = env::var("AGENT_BROWSER_PLUGINS")
+    let plugins = env_vars::var("AGENT_BROWSER_PLUGINS")
         .ok()
         .and_then(
             |raw| match serde_json::from_str::<Vec<PluginConfig>>(&raw) {
@@ -474,38 +974,45 @@ pub fn parse_flags(args: &[String]) -> Flags {
         json: env_var_is_truthy("AGENT_BROWSER_JSON") || config.json.unwrap_or(false),
         headed: env_var_is_truthy("AGENT_BROWSER_HEADED") || config.headed.unwrap_or(false),
         debug: env_var_is_truthy("AGENT_BROWSER_DEBUG") || config.debug.unwrap_or(false),
-        session: env::var("AGENT_BROWSER_SESSION")
+        session: env_vars::var("AGENT_BROWSER_SESSION")
             .ok()
             .or(config.session)
             .unwrap_or_else(|| "default".to_string()),
-        restore: env::var("AGENT_BROWSER_RESTORE").ok().or(config.restore),
-        restore_save: env::var("AGENT_BROWSER_RESTORE_SAVE")
+        restore: env_vars::var("AGENT_BROWSER_RESTORE")
+            .ok()
+            .or(config.restore),
+        restore_save: env_vars::var("AGENT_BROWSER_RESTORE_SAVE")
             .ok()
             .or(config.restore_save),
-        restore_check_url: env::var("AGENT_BROWSER_RESTORE_CHECK_URL")
+        restore_check_url: env_vars::var("AGENT_BROWSER_RESTORE_CHECK_URL")
             .ok()
             .or(config.restore_check_url),
-        restore_check_text: env::var("AGENT_BROWSER_RESTORE_CHECK_TEXT")
+        restore_check_text: env_vars::var("AGENT_BROWSER_RESTORE_CHECK_TEXT")
             .ok()
             .or(config.restore_check_text),
-        restore_check_fn: env::var("AGENT_BROWSER_RESTORE_CHECK_FN")
+        restore_check_fn: env_vars::var("AGENT_BROWSER_RESTORE_CHECK_FN")
             .ok()
             .or(config.restore_check_fn),
-        namespace: env::var("AGENT_BROWSER_NAMESPACE")
+        namespace: env_vars::var("AGENT_BROWSER_NAMESPACE")
             .ok()
             .or(config.namespace),
         restore_uses_session: false,
         headers: config.headers,
-        executable_path: env::var("AGENT_BROWSER_EXECUTABLE_PATH")
+        executable_path: env_vars::var("AGENT_BROWSER_EXECUTABLE_PATH")
             .ok()
             .or(config.executable_path),
-        cdp: env::var("AGENT_BROWSER_CDP").ok().or(config.cdp),
+        cdp: env_vars::var("AGENT_BROWSER_CDP").ok().or(config.cdp),
         extensions,
         init_scripts,
         enable,
-        profile: env::var("AGENT_BROWSER_PROFILE").ok().or(config.profile),
-        state: env::var("AGENT_BROWSER_STATE").ok().or(config.state),
-        proxy: env::var("AGENT_BROWSER_PROXY")
+        profile: env_vars::var("AGENT_BROWSER_PROFILE")
+            .ok()
+            .or(config.profile),
+        user_data_dir: env_vars::var("AGENT_BROWSER_USER_DATA_DIR")
+            .ok()
+            .or(config.user_data_dir),
+        state: env_vars::var("AGENT_BROWSER_STATE").ok().or(config.state),
+        proxy: env_vars::var("AGENT_BROWSER_PROXY")
             .ok()
             .or(config.proxy)
             .or_else(|| env::var("HTTP_PROXY").ok())
@@ -514,18 +1021,44 @@ pub fn parse_flags(args: &[String]) -> Flags {
             .or_else(|| env::var("https_proxy").ok())
             .or_else(|| env::var("ALL_PROXY").ok())
             .or_else(|| env::var("all_proxy").ok()),
-        proxy_bypass: env::var("AGENT_BROWSER_PROXY_BYPASS")
+        proxy_bypass: env_vars::var("AGENT_BROWSER_PROXY_BYPASS")
             .ok()
             .or(config.proxy_bypass)
             .or_else(|| env::var("NO_PROXY").ok())
             .or_else(|| env::var("no_proxy").ok()),
-        args: env::var("AGENT_BROWSER_ARGS").ok().or(config.args),
-        user_agent: env::var("AGENT_BROWSER_USER_AGENT")
+        args: env_vars::var("AGENT_BROWSER_ARGS").ok().or(config.args),
+        window_size: parse_window_value(
+            env_vars::var("AGENT_BROWSER_WINDOW_SIZE").ok(),
+            "--window-size",
+            "AGENT_BROWSER_WINDOW_SIZE",
+            parse_window_size,
+        ),
+        window_position: parse_window_value(
+            env_vars::var("AGENT_BROWSER_WINDOW_POSITION").ok(),
+            "--window-position",
+            "AGENT_BROWSER_WINDOW_POSITION",
+            parse_window_position,
+        ),
+        user_agent: env_vars::var("AGENT_BROWSER_USER_AGENT")
             .ok()
             .or(config.user_agent),
-        provider: env::var("AGENT_BROWSER_PROVIDER").ok().or(config.provider),
+        provider: env_vars::var("AGENT_BROWSER_PROVIDER")
+            .ok()
+            .or(config.provider),
+        provider_opts: Vec::new(),
+        sessions: None,
         ignore_https_errors: env_var_is_truthy("AGENT_BROWSER_IGNORE_HTTPS_ERRORS")
             || config.ignore_https_errors.unwrap_or(false),
+        ignore_https_origins: env_vars::var("AGENT_BROWSER_IGNORE_HTTPS_ORIGINS")
+            .ok()
+            .map(|s| {
+                s.split(',')
+                    .map(|o| o.trim().to_string())
+                    .filter(|o| !o.is_empty())
+                    .collect()
+            })
+            .or(config.ignore_https_origins)
+            .unwrap_or_default(),
         allow_file_access: env_var_is_truthy("AGENT_BROWSER_ALLOW_FILE_ACCESS")
             || config.allow_file_access.unwrap_or(false),
         hide_scrollbars: env_var_bool("AGENT_BROWSER_HIDE_SCROLLBARS")
@@ -533,26 +1066,53 @@ pub fn parse_flags(args: &[String]) -> Flags {
             .unwrap_or(true),
         webgpu: env_var_is_truthy("AGENT_BROWSER_WEBGPU") || config.webgpu.unwrap_or(false),
         no_xvfb: env_var_is_truthy("AGENT_BROWSER_NO_XVFB"),
-        device: env::var("AGENT_BROWSER_IOS_DEVICE").ok().or(config.device),
+        no_history: env_var_is_truthy("AGENT_BROWSER_NO_HISTORY"),
+        device: env_vars::var("AGENT_BROWSER_IOS_DEVICE")
+            .ok()
+            .or(config.device),
         auto_connect: env_var_is_truthy("AGENT_BROWSER_AUTO_CONNECT")
             || config.auto_connect.unwrap_or(false),
-        session_name: env::var("AGENT_BROWSER_SESSION_NAME")
+        session_name: env_vars::var("AGENT_BROWSER_SESSION_NAME")
             .ok()
             .or(config.session_name),
-        annotate: env_var_is_truthy("AGENT_BROWSER_ANNOTATE") || config.annotate.unwrap_or(false),
-        color_scheme: env::var("AGENT_BROWSER_COLOR_SCHEME")
+        annotate: env_var_bool("AGENT_BROWSER_ANNOTATE")
+            .or(config.annotate)
+            .unwrap_or(false),
+        full: env_var_bool("AGENT_BROWSER_FULL")
+            .or(config.full)
+            .unwrap_or(false),
+        color_scheme: env_vars::var("AGENT_BROWSER_COLOR_SCHEME")
             .ok()
             .or(config.color_scheme),
-        download_path: env::var("AGENT_BROWSER_DOWNLOAD_PATH")
+        default_scheme: env_vars::var("AGENT_BROWSER_DEFAULT_SCHEME")
+            .ok()
+            .or(config.default_scheme),
+        download_path: env_vars::var("AGENT_BROWSER_DOWNLOAD_PATH")
             .ok()
             .or(config.download_path),
+        artifacts_dir: env_vars::var("AGENT_BROWSER_ARTIFACTS_DIR")
+            .ok()
+            .or(config.artifacts_dir),
         content_boundaries: env_var_is_truthy("AGENT_BROWSER_CONTENT_BOUNDARIES")
             || config.content_boundaries.unwrap_or(false),
-        max_output: env::var("AGENT_BROWSER_MAX_OUTPUT")
+        max_output: env_vars::var("AGENT_BROWSER_MAX_OUTPUT")
             .ok()
             .and_then(|s| s.parse().ok())
             .or(config.max_output),
-        allowed_domains: env::var("AGENT_BROWSER_ALLOWED_DOMAINS")
+        cache_ms: env_vars::var("AGENT_BROWSER_CACHE_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&ms| ms > 0),
+        flag_warning_window_ms: env_vars::var("AGENT_BROWSER_FLAG_WARNING_WINDOW_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_FLAG_WARNING_WINDOW_MS),
+        auto_retry_actions: env_vars::var("AGENT_BROWSER_AUTO_RETRY_ACTIONS")
+            .ok()
+            .map(|v| parse_auto_retry_env(&v))
+            .unwrap_or(0),
+        ref_prefix: env_vars::var("AGENT_BROWSER_REF_PREFIX").ok(),
+        allowed_domains: env_vars::var("AGENT_BROWSER_ALLOWED_DOMAINS")
             .ok()
             .map(|s| {
                 s.split(',')
@@ -561,38 +1121,48 @@ pub fn parse_flags(args: &[String]) -> Flags {
                     .collect()
             })
             .or(config.allowed_domains),
-        action_policy: env::var("AGENT_BROWSER_ACTION_POLICY")
+        action_policy: env_vars::var("AGENT_BROWSER_ACTION_POLICY")
             .ok()
             .or(config.action_policy),
-        confirm_actions: env::var("AGENT_BROWSER_CONFIRM_ACTIONS")
+        confirm_actions: env_vars::var("AGENT_BROWSER_CONFIRM_ACTIONS")
             .ok()
             .or(config.confirm_actions),
         confirm_interactive: env_var_is_truthy("AGENT_BROWSER_CONFIRM_INTERACTIVE")
             || config.confirm_interactive.unwrap_or(false),
-        engine: env::var("AGENT_BROWSER_ENGINE").ok().or(config.engine),
-        screenshot_dir: env::var("AGENT_BROWSER_SCREENSHOT_DIR")
+        on_confirmation: env_vars::var("AGENT_BROWSER_ON_CONFIRMATION")
+            .ok()
+            .or(config.on_confirmation),
+        engine: env_vars::var("AGENT_BROWSER_ENGINE").ok().or(config.engine),
+        screenshot_dir: env_vars::var("AGENT_BROWSER_SCREENSHOT_DIR")
             .ok()
             .or(config.screenshot_dir),
-        screenshot_quality: env::var("AGENT_BROWSER_SCREENSHOT_QUALITY")
+        screenshot_quality: env_vars::var("AGENT_BROWSER_SCREENSHOT_QUALITY")
             .ok()
             .and_then(|s| s.parse().ok())
             .or(config.screenshot_quality),
-        screenshot_format: env::var("AGENT_BROWSER_SCREENSHOT_FORMAT")
+        screenshot_format: env_vars::var("AGENT_BROWSER_SCREENSHOT_FORMAT")
             .ok()
             .or(config.screenshot_format)
             .filter(|s| s == "png" || s == "jpeg"),
         idle_timeout: parse_idle_timeout_value(
-            env::var("AGENT_BROWSER_IDLE_TIMEOUT_MS").ok(),
+            env_vars::var("AGENT_BROWSER_IDLE_TIMEOUT_MS").ok(),
             "AGENT_BROWSER_IDLE_TIMEOUT_MS",
         )
         .or(config.idle_timeout),
-        default_timeout: env::var("AGENT_BROWSER_DEFAULT_TIMEOUT")
+        default_timeout: env_vars::var("AGENT_BROWSER_DEFAULT_TIMEOUT")
             .ok()
             .and_then(|s| s.parse::<u64>().ok()),
         no_auto_dialog: env_var_is_truthy("AGENT_BROWSER_NO_AUTO_DIALOG")
             || config.no_auto_dialog.unwrap_or(false),
+        no_exit_code: false,
+        no_install_prompt: false,
+        require_fresh_daemon: env_var_is_truthy("AGENT_BROWSER_REQUIRE_FRESH_DAEMON")
+            || config.require_fresh_daemon.unwrap_or(false),
         model: env::var("AI_GATEWAY_MODEL").ok().or(config.model),
         plugins,
+        redact_patterns: config.redact_patterns,
+        aliases: config.aliases.unwrap_or_default(),
+        session_profile,
         verbose: false,
         quiet: false,
         cli_executable_path: false,
@@ -600,6 +1170,7 @@ pub fn parse_flags(args: &[String]) -> Flags {
         cli_init_scripts: false,
         cli_enable: false,
         cli_profile: false,
+        cli_user_data_dir: false,
         cli_state: false,
         cli_args: false,
         cli_user_agent: false,
@@ -612,6 +1183,13 @@ pub fn parse_flags(args: &[String]) -> Flags {
         cli_headed: false,
         cli_webgpu: false,
         cli_restore: false,
+        cli_allowed_domains: false,
+        cli_ignore_https_origins: false,
+        cli_action_policy: false,
+        cli_confirm_actions: false,
+        cli_device: false,
+        cli_provider: false,
+        cli_session_name: false,
     };
 
     let mut i = 0;
@@ -621,6 +1199,11 @@ pub fn parse_flags(args: &[String]) -> Flags {
         if !arg.starts_with('-') && looks_like_command(arg) {
             seen_command = true;
         }
+        if let Some((name, value, consumed)) = parse_bool_flag(args, i) {
+            set_bool_flag(&mut flags, name, value);
+            i += if consumed { 2 } else { 1 };
+            continue;
+        }
         match arg {
             s if s.starts_with("--restore=") => {
                 flags.cli_restore = true;
@@ -632,42 +1215,21 @@ pub fn parse_flags(args: &[String]) -> Flags {
                     flags.restore_uses_session = false;
                 }
             }
-            "--json" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.json = val;
-                if consumed {
-                    i += 1;
-                }
-            }
-            "--headed" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.headed = val;
-                flags.cli_headed = true;
-                if consumed {
-                    i += 1;
-                }
-            }
-            "--webgpu" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.webgpu = val;
-                flags.cli_webgpu = true;
-                if consumed {
-                    i += 1;
-                }
-            }
-            "--debug" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.debug = val;
-                if consumed {
-                    i += 1;
-                }
-            }
             "--session" => {
                 if let Some(s) = args.get(i + 1) {
                     flags.session = s.clone();
                     i += 1;
                 }
             }
+            "--auto-retry-actions" => {
+                flags.auto_retry_actions = match args.get(i + 1).and_then(|s| s.parse().ok()) {
+                    Some(n) => {
+                        i += 1;
+                        n
+                    }
+                    None => crate::action_retry::DEFAULT_RETRIES,
+                };
+            }
             "--restore" => {
                 flags.cli_restore = true;
                 let next = args.get(i + 1);
@@ -782,6 +1344,13 @@ pub fn parse_flags(args: &[String]) -> Flags {
                     i += 1;
                 }
             }
+            "--user-data-dir" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.user_data_dir = Some(s.clone());
+                    flags.cli_user_data_dir = true;
+                    i += 1;
+                }
+            }
             "--state" => {
                 if let Some(s) = args.get(i + 1) {
                     flags.state = Some(s.clone());
@@ -810,6 +1379,28 @@ pub fn parse_flags(args: &[String]) -> Flags {
                     i += 1;
                 }
             }
+            "--window-size" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.window_size = parse_window_value(
+                        Some(s.clone()),
+                        "--window-size",
+                        "the command line",
+                        parse_window_size,
+                    );
+                    i += 1;
+                }
+            }
+            "--window-position" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.window_position = parse_window_value(
+                        Some(s.clone()),
+                        "--window-position",
+                        "the command line",
+                        parse_window_position,
+                    );
+                    i += 1;
+                }
+            }
             "--user-agent" => {
                 if let Some(s) = args.get(i + 1) {
                     flags.user_agent = Some(s.clone());
@@ -820,62 +1411,51 @@ pub fn parse_flags(args: &[String]) -> Flags {
             "-p" | "--provider" => {
                 if let Some(p) = args.get(i + 1) {
                     flags.provider = Some(p.clone());
+                    flags.cli_provider = true;
                     i += 1;
                 }
             }
-            "--ignore-https-errors" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.ignore_https_errors = val;
-                if consumed {
-                    i += 1;
-                }
-            }
-            "--allow-file-access" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.allow_file_access = val;
-                flags.cli_allow_file_access = true;
-                if consumed {
+            "--provider-opt" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.provider_opts.push(s.clone());
                     i += 1;
                 }
             }
-            "--hide-scrollbars" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.hide_scrollbars = val;
-                flags.cli_hide_scrollbars = true;
-                if consumed {
+            "--sessions" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.sessions = Some(parse_session_list(s));
                     i += 1;
                 }
             }
             "--device" => {
                 if let Some(d) = args.get(i + 1) {
                     flags.device = Some(d.clone());
+                    flags.cli_device = true;
                     i += 1;
                 }
             }
-            "--auto-connect" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.auto_connect = val;
-                if consumed {
+            "--session-name" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.session_name = Some(s.clone());
+                    flags.cli_session_name = true;
                     i += 1;
                 }
             }
-            "--session-name" => {
+            "--color-scheme" => {
                 if let Some(s) = args.get(i + 1) {
-                    flags.session_name = Some(s.clone());
+                    flags.color_scheme = Some(s.clone());
                     i += 1;
                 }
             }
-            "--annotate" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.annotate = val;
-                flags.cli_annotate = true;
-                if consumed {
+            "--default-scheme" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.default_scheme = Some(s.clone());
                     i += 1;
                 }
             }
-            "--color-scheme" => {
+            "--artifacts-dir" => {
                 if let Some(s) = args.get(i + 1) {
-                    flags.color_scheme = Some(s.clone());
+                    flags.artifacts_dir = Some(s.clone());
                     i += 1;
                 }
             }
@@ -886,13 +1466,6 @@ pub fn parse_flags(args: &[String]) -> Flags {
                     i += 1;
                 }
             }
-            "--content-boundaries" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.content_boundaries = val;
-                if consumed {
-                    i += 1;
-                }
-            }
             "--max-output" => {
                 if let Some(s) = args.get(i + 1) {
                     if let Ok(n) = s.parse::<usize>() {
@@ -901,6 +1474,25 @@ pub fn parse_flags(args: &[String]) -> Flags {
                     i += 1;
                 }
             }
+            "--cache" => {
+                if let Some(s) = args.get(i + 1) {
+                    match s.parse::<u64>() {
+                        Ok(ms) => flags.cache_ms = (ms > 0).then_some(ms),
+                        Err(_) => eprintln!(
+                            "{} --cache expects a TTL in ms, got '{}'",
+                            color::warning_indicator(),
+                            s
+                        ),
+                    }
+                    i += 1;
+                }
+            }
+            "--ref-prefix" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.ref_prefix = Some(s.clone());
+                    i += 1;
+                }
+            }
             "--allowed-domains" => {
                 if let Some(s) = args.get(i + 1) {
                     flags.allowed_domains = Some(
@@ -909,25 +1501,34 @@ pub fn parse_flags(args: &[String]) -> Flags {
                             .filter(|d| !d.is_empty())
                             .collect(),
                     );
+                    flags.cli_allowed_domains = true;
+                    i += 1;
+                }
+            }
+            "--ignore-https-origin" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.ignore_https_origins.push(s.clone());
+                    flags.cli_ignore_https_origins = true;
                     i += 1;
                 }
             }
             "--action-policy" => {
                 if let Some(s) = args.get(i + 1) {
                     flags.action_policy = Some(s.clone());
+                    flags.cli_action_policy = true;
                     i += 1;
                 }
             }
             "--confirm-actions" => {
                 if let Some(s) = args.get(i + 1) {
                     flags.confirm_actions = Some(s.clone());
+                    flags.cli_confirm_actions = true;
                     i += 1;
                 }
             }
-            "--confirm-interactive" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.confirm_interactive = val;
-                if consumed {
+            "--on-confirmation" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.on_confirmation = Some(s.clone());
                     i += 1;
                 }
             }
@@ -973,25 +1574,12 @@ pub fn parse_flags(args: &[String]) -> Flags {
                     i += 1;
                 }
             }
-            "--no-auto-dialog" => {
-                let (val, consumed) = parse_bool_arg(args, i);
-                flags.no_auto_dialog = val;
-                if consumed {
-                    i += 1;
-                }
-            }
             "--model" => {
                 if let Some(s) = args.get(i + 1) {
                     flags.model = Some(s.clone());
                     i += 1;
                 }
             }
-            "-v" | "--verbose" => {
-                flags.verbose = true;
-            }
-            "-q" | "--quiet" => {
-                flags.quiet = true;
-            }
             "--config" => {
                 // Already handled by load_config(); skip the value
                 i += 1;
@@ -1000,9 +1588,204 @@ pub fn parse_flags(args: &[String]) -> Flags {
         }
         i += 1;
     }
+    normalize_path_flags(&mut flags);
     flags
 }
 
+/// Expand a leading `~` and make a relative path absolute against `cwd`. The
+/// daemon runs with its own working directory, so paths must be resolved on
+/// the CLI side to mean what the user typed.
+pub fn normalize_path_value(value: &str, cwd: &Path, home: Option<&Path>) -> String {
+    let expanded = match (value.strip_prefix('~'), home) {
+        (Some(""), Some(home)) => home.to_path_buf(),
+        (Some(rest), Some(home)) if rest.starts_with(['/', '\\']) => home.join(&rest[1..]),
+        _ => PathBuf::from(value),
+    };
+    let absolute = if expanded.is_absolute() || is_windows_absolute(value) {
+        expanded
+    } else {
+        cwd.join(expanded)
+    };
+    absolute.to_string_lossy().into_owned()
+}
+
+/// `C:\...` and `\\server\share` are absolute even when parsed on Unix.
+fn is_windows_absolute(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    (bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/'))
+        || value.starts_with("\\\\")
+}
+
+/// Normalize every path-valued launch option in place. A `--profile` that
+/// names a Chrome profile (no separators or `~`) is left alone.
+fn normalize_path_flags(flags: &mut Flags) {
+    let Ok(cwd) = env::current_dir() else {
+        return;
+    };
+    let home = dirs::home_dir();
+    let normalize = |value: &mut String| {
+        *value = normalize_path_value(value, &cwd, home.as_deref());
+    };
+    flags.download_path.iter_mut().for_each(normalize);
+    flags.artifacts_dir.iter_mut().for_each(normalize);
+    flags.state.iter_mut().for_each(normalize);
+    flags.user_data_dir.iter_mut().for_each(normalize);
+    flags.action_policy.iter_mut().for_each(normalize);
+    flags.extensions.iter_mut().for_each(normalize);
+    if let Some(profile) = flags.profile.as_mut() {
+        if !crate::native::cdp::chrome::is_chrome_profile_name(profile) {
+            normalize(profile);
+        }
+    }
+}
+
+/// A `%NAME%` reference such as `%USERPROFILE%`. Only cmd.exe expands these,
+/// so they arrive literally from PowerShell, config files, and agents.
+fn windows_env_reference(value: &str) -> Option<&str> {
+    let start = value.find('%')?;
+    let len = value[start + 1..].find('%')?;
+    let name = &value[start + 1..start + 1 + len];
+    (!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        .then(|| &value[start..start + len + 2])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathCheck {
+    /// A directory that is created if missing and must be writable.
+    WritableDir,
+    /// A directory if it exists; the browser creates it otherwise.
+    DirIfExists,
+    /// An existing directory.
+    Dir,
+    /// An existing file.
+    File,
+    /// An existing file or directory.
+    Exists,
+}
+
+fn check_path_flag(flag: &str, path: &str, check: PathCheck) -> Result<(), String> {
+    if let Some(reference) = windows_env_reference(path) {
+        return Err(format!(
+            "{}: '{}' contains {}, which is not expanded. Use ~ for your home directory (for example ~\\Downloads) or a full path.",
+            flag, path, reference
+        ));
+    }
+    let p = Path::new(path);
+    match check {
+        PathCheck::WritableDir => {
+            fs::create_dir_all(p)
+                .map_err(|e| format!("{}: cannot create directory {}: {}", flag, path, e))?;
+            let probe = p.join(format!(".agent-browser-write-check-{}", std::process::id()));
+            fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&probe)
+                .map_err(|e| format!("{}: directory {} is not writable: {}", flag, path, e))?;
+            let _ = fs::remove_file(&probe);
+        }
+        PathCheck::DirIfExists => {
+            if p.exists() && !p.is_dir() {
+                return Err(format!("{}: {} is not a directory", flag, path));
+            }
+        }
+        PathCheck::Dir => {
+            if !p.is_dir() {
+                return Err(format!("{}: directory not found: {}", flag, path));
+            }
+        }
+        PathCheck::File => {
+            if !p.is_file() {
+                return Err(format!("{}: file not found: {}", flag, path));
+            }
+        }
+        PathCheck::Exists => {
+            if !p.exists() {
+                return Err(format!("{}: path not found: {}", flag, path));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check the normalized path options before a daemon is launched with them,
+/// so a bad path fails here with the flag's name instead of deep inside the
+/// browser launch.
+pub fn validate_path_flags(flags: &Flags) -> Result<(), String> {
+    if let Some(ref dir) = flags.download_path {
+        check_path_flag("--download-path", dir, PathCheck::WritableDir)?;
+    }
+    if let Some(ref dir) = flags.artifacts_dir {
+        check_path_flag("--artifacts-dir", dir, PathCheck::WritableDir)?;
+    }
+    if let Some(ref profile) = flags.profile {
+        if !crate::native::cdp::chrome::is_chrome_profile_name(profile) {
+            check_path_flag("--profile", profile, PathCheck::DirIfExists)?;
+        }
+    }
+    if let Some(ref dir) = flags.user_data_dir {
+        check_path_flag("--user-data-dir", dir, PathCheck::Dir)?;
+    }
+    if let Some(ref state) = flags.state {
+        check_path_flag("--state", state, PathCheck::File)?;
+        let key = env_vars::var("AGENT_BROWSER_ENCRYPTION_KEY").ok();
+        crate::native::state::check_state_encryption_key(state, key.as_deref())
+            .map_err(|e| format!("--state: {}", e))?;
+    }
+    for extension in &flags.extensions {
+        check_path_flag("--extension", extension, PathCheck::Exists)?;
+    }
+    if let Some(ref policy) = flags.action_policy {
+        check_path_flag("--action-policy", policy, PathCheck::File)?;
+    }
+    Ok(())
+}
+
+const COLOR_SCHEMES: &[&str] = &["dark", "light", "no-preference"];
+/// `--default-scheme` values; see `commands::apply_default_scheme`.
+pub const DEFAULT_SCHEMES: &[&str] = &["https", "http", "none"];
+
+/// Check enum-like option values and the action policy file before anything
+/// is sent to the daemon, which would otherwise ignore or reject them late.
+pub fn validate_flag_values(flags: &Flags) -> Result<(), String> {
+    if let Some(ref scheme) = flags.color_scheme {
+        if !COLOR_SCHEMES.contains(&scheme.as_str()) {
+            return Err(format!(
+                "--color-scheme: invalid value '{}'. Valid values: {}",
+                scheme,
+                COLOR_SCHEMES.join(", ")
+            ));
+        }
+    }
+    if let Some(ref scheme) = flags.default_scheme {
+        if !DEFAULT_SCHEMES.contains(&scheme.as_str()) {
+            return Err(format!(
+                "--default-scheme: invalid value '{}'. Valid values: {}",
+                scheme,
+                DEFAULT_SCHEMES.join(", ")
+            ));
+        }
+    }
+    if let Some(ref prefix) = flags.ref_prefix {
+        crate::commands::validate_ref_prefix(prefix).map_err(|e| format!("--ref-prefix: {}", e))?;
+    }
+    for origin in &flags.ignore_https_origins {
+        crate::commands::parse_https_origin(origin)
+            .map_err(|e| format!("--ignore-https-origin: {}", e))?;
+    }
+    if let Some(ref categories) = flags.confirm_actions {
+        crate::native::policy::validate_confirm_actions(categories)
+            .map_err(|e| format!("--confirm-actions: {}", e))?;
+    }
+    if let Some(ref policy) = flags.action_policy {
+        crate::native::policy::validate_policy_file(policy)
+            .map_err(|e| format!("--action-policy: {}", e))?;
+    }
+    Ok(())
+}
+
 fn looks_like_command(value: &str) -> bool {
     crate::commands::is_top_level_command(value)
 }
@@ -1011,29 +1794,8 @@ pub fn clean_args(args: &[String]) -> Vec<String> {
     let mut result = Vec::new();
     let mut skip_next = false;
 
-    // Boolean flags that optionally take true/false
-    const GLOBAL_BOOL_FLAGS: &[&str] = &[
-        "--json",
-        "--headed",
-        "--webgpu",
-        "--debug",
-        "--ignore-https-errors",
-        "--allow-file-access",
-        "--hide-scrollbars",
-        "--auto-connect",
-        "--annotate",
-        "--content-boundaries",
-        "--confirm-interactive",
-        "--no-auto-dialog",
-        "-v",
-        "--verbose",
-        "-q",
-        "--quiet",
-        // doctor-specific flags; harmless on other commands (ignored)
-        "--offline",
-        "--quick",
-        "--fix",
-    ];
+    // Doctor-specific flags; harmless on other commands (ignored)
+    const DOCTOR_BOOL_FLAGS: &[&str] = &["--offline", "--quick", "--fix"];
     // Global flags that always take a value (need to skip the next arg too)
     const GLOBAL_FLAGS_WITH_VALUE: &[&str] = &[
         "--session",
@@ -1049,21 +1811,32 @@ pub fn clean_args(args: &[String]) -> Vec<String> {
         "--init-script",
         "--enable",
         "--profile",
+        "--user-data-dir",
         "--state",
         "--proxy",
         "--proxy-bypass",
         "--args",
+        "--window-size",
+        "--window-position",
         "--user-agent",
         "-p",
         "--provider",
         "--device",
         "--session-name",
         "--color-scheme",
+        "--default-scheme",
         "--download-path",
+        "--artifacts-dir",
         "--max-output",
+        "--cache",
+        "--ref-prefix",
         "--allowed-domains",
+        "--ignore-https-origin",
         "--action-policy",
         "--confirm-actions",
+        "--on-confirmation",
+        "--provider-opt",
+        "--sessions",
         "--config",
         "--engine",
         "--screenshot-dir",
@@ -1091,6 +1864,11 @@ pub fn clean_args(args: &[String]) -> Vec<String> {
             i += 1;
             continue;
         }
+        if arg == "--auto-retry-actions" {
+            let has_count = args.get(i + 1).is_some_and(|s| s.parse::<u32>().is_ok());
+            i += if has_count { 2 } else { 1 };
+            continue;
+        }
         if arg == "--restore" {
             if !seen_command {
                 if let Some(v) = args.get(i + 1) {
@@ -1102,12 +1880,11 @@ pub fn clean_args(args: &[String]) -> Vec<String> {
             i += 1;
             continue;
         }
-        if GLOBAL_BOOL_FLAGS.contains(&arg.as_str()) {
-            if let Some(v) = args.get(i + 1) {
-                if matches!(v.as_str(), "true" | "false") {
-                    i += 1;
-                }
-            }
+        if let Some((_, _, consumed)) = parse_bool_flag(args, i) {
+            i += if consumed { 2 } else { 1 };
+            continue;
+        }
+        if DOCTOR_BOOL_FLAGS.contains(&arg.as_str()) {
             i += 1;
             continue;
         }
@@ -1135,6 +1912,69 @@ mod tests {
         assert_eq!(flags.headers, Some(r#"{"Auth":"token"}"#.to_string()));
     }
 
+    #[test]
+    fn test_parse_headers_json_valid() {
+        let headers = parse_headers_json(r#"{"Authorization":"Bearer t","X-Trace":"1"}"#).unwrap();
+        assert_eq!(
+            headers,
+            vec![
+                ("Authorization".to_string(), "Bearer t".to_string()),
+                ("X-Trace".to_string(), "1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_headers_json_rejects_non_object() {
+        assert!(parse_headers_json(r#"["Authorization"]"#).is_err());
+        assert!(parse_headers_json("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_headers_json_rejects_non_string_values() {
+        let err = parse_headers_json(r#"{"X-Retries": 3}"#).unwrap_err();
+        assert!(err.contains("string value"));
+        assert!(parse_headers_json(r#"{"X-Flag": null}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_headers_json_rejects_invalid_names() {
+        assert!(parse_headers_json(r#"{"Bad Header": "x"}"#).is_err());
+        assert!(parse_headers_json(r#"{"X-Colon:": "x"}"#).is_err());
+        assert!(parse_headers_json(r#"{"": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_headers_json_rejects_hop_by_hop() {
+        let err = parse_headers_json(r#"{"Connection": "close"}"#).unwrap_err();
+        assert!(err.contains("hop-by-hop"));
+        assert!(parse_headers_json(r#"{"transfer-encoding": "chunked"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_headers_json_collapses_case_insensitive_duplicates() {
+        let headers = parse_headers_json(r#"{"x-token":"old","X-Token":"new"}"#).unwrap();
+        assert_eq!(headers, vec![("X-Token".to_string(), "new".to_string())]);
+    }
+
+    #[test]
+    fn test_merge_headers_override_wins() {
+        let merged = merge_headers(
+            vec![
+                ("Authorization".to_string(), "a".to_string()),
+                ("X-Keep".to_string(), "k".to_string()),
+            ],
+            vec![("authorization".to_string(), "b".to_string())],
+        );
+        assert_eq!(
+            merged,
+            vec![
+                ("authorization".to_string(), "b".to_string()),
+                ("X-Keep".to_string(), "k".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_idle_timeout_raw_ms() {
         assert_eq!(parse_idle_timeout("10").unwrap(), "10");
@@ -1187,6 +2027,65 @@ mod tests {
         assert!(flags.headers.is_none());
     }
 
+    #[test]
+    fn test_provider_opt_flag_is_repeatable() {
+        let flags = parse_flags(&args(
+            "-p browserbase --provider-opt projectId=abc --provider-opt region=us-west-2 open example.com",
+        ));
+        assert_eq!(
+            flags.provider_opts,
+            vec!["projectId=abc", "region=us-west-2"]
+        );
+        assert_eq!(
+            clean_args(&args("--provider-opt projectId=abc open example.com")),
+            vec!["open", "example.com"]
+        );
+    }
+
+    #[test]
+    fn test_sessions_flag_splits_and_dedupes() {
+        let flags = parse_flags(&args("--sessions a,b,,a,c snapshot -i"));
+        assert_eq!(
+            flags.sessions,
+            Some(vec!["a".into(), "b".into(), "c".into()])
+        );
+        assert_eq!(parse_session_list(" x , y ,"), vec!["x", "y"]);
+        assert_eq!(
+            clean_args(&args("--sessions a,b snapshot -i")),
+            vec!["snapshot", "-i"]
+        );
+        assert!(parse_flags(&args("snapshot")).sessions.is_none());
+    }
+
+    #[test]
+    fn test_parse_provider_opt_splits_on_first_equals() {
+        assert_eq!(
+            parse_provider_opt("context=a=b==").unwrap(),
+            ("context".to_string(), "a=b==".to_string())
+        );
+        assert_eq!(
+            parse_provider_opt("ttl=").unwrap(),
+            ("ttl".to_string(), String::new())
+        );
+        assert!(parse_provider_opt("=value").is_err());
+        assert!(parse_provider_opt("novalue").is_err());
+    }
+
+    #[test]
+    fn test_resolve_provider_options_cli_overrides_env() {
+        let options = resolve_provider_options(
+            Some(r#"{"region":"eu-central-1","udid":"ABC"}"#),
+            &["region=us-west-2".to_string()],
+        )
+        .unwrap();
+        assert_eq!(options.get("region").map(String::as_str), Some("us-west-2"));
+        assert_eq!(options.get("udid").map(String::as_str), Some("ABC"));
+
+        assert!(resolve_provider_options(Some("[]"), &[]).is_err());
+        assert!(resolve_provider_options(Some(r#"{"ttl":60}"#), &[]).is_err());
+        assert!(resolve_provider_options(None, &["=x".to_string()]).is_err());
+    }
+
     #[test]
     fn test_clean_args_removes_headers() {
         let input: Vec<String> = vec![
@@ -1345,6 +2244,103 @@ mod tests {
         assert_eq!(cleaned, vec!["open", "example.com"]);
     }
 
+    #[test]
+    fn test_cache_flag_parse_and_clean() {
+        let raw = args("--cache 1500 get title");
+        assert_eq!(clean_args(&raw), vec!["get", "title"]);
+        assert_eq!(parse_flags(&raw).cache_ms, Some(1500));
+        assert_eq!(parse_flags(&args("--cache 0 get title")).cache_ms, None);
+        assert_eq!(parse_flags(&args("--cache soon get title")).cache_ms, None);
+    }
+
+    #[test]
+    fn test_auto_retry_actions_flag() {
+        let raw = args("--auto-retry-actions 3 click @e1");
+        assert_eq!(clean_args(&raw), vec!["click", "@e1"]);
+        assert_eq!(parse_flags(&raw).auto_retry_actions, 3);
+
+        // Without a count the next word is the command, not a value.
+        let raw = args("--auto-retry-actions click @e1");
+        assert_eq!(clean_args(&raw), vec!["click", "@e1"]);
+        assert_eq!(
+            parse_flags(&raw).auto_retry_actions,
+            crate::action_retry::DEFAULT_RETRIES
+        );
+        assert_eq!(parse_flags(&args("click @e1")).auto_retry_actions, 0);
+
+        assert_eq!(parse_auto_retry_env("4"), 4);
+        assert_eq!(
+            parse_auto_retry_env("true"),
+            crate::action_retry::DEFAULT_RETRIES
+        );
+        assert_eq!(parse_auto_retry_env("0"), 0);
+        assert_eq!(parse_auto_retry_env("off"), 0);
+    }
+
+    #[test]
+    fn test_window_flags_parse_and_clean() {
+        let raw = args("--window-size 1280x800 --window-position 0,40 open example.com");
+        assert_eq!(clean_args(&raw), vec!["open", "example.com"]);
+        let flags = parse_flags(&raw);
+        assert_eq!(flags.window_size, Some((1280, 800)));
+        assert_eq!(flags.window_position, Some((0, 40)));
+
+        assert_eq!(parse_window_size("1920X1080"), Ok((1920, 1080)));
+        for bad in ["1280", "0x800", "1280x-1", "wide x tall", "1280,800"] {
+            assert!(parse_window_size(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(parse_window_position(" 10 , 20 "), Ok((10, 20)));
+        for bad in ["10", "-5,0", "10x20", "a,b"] {
+            assert!(parse_window_position(bad).is_err(), "{}", bad);
+        }
+        let flags = parse_flags(&args("--window-size huge open example.com"));
+        assert_eq!(flags.window_size, None);
+    }
+
+    #[test]
+    fn test_merge_window_args() {
+        let user = vec!["--disable-gpu".to_string()];
+        let (merged, warnings) = merge_window_args(user, Some((1280, 800)), Some((0, 40)));
+        assert_eq!(
+            merged,
+            vec![
+                "--disable-gpu",
+                "--window-size=1280,800",
+                "--window-position=0,40"
+            ]
+        );
+        assert!(warnings.is_empty());
+
+        let (merged, warnings) = merge_window_args(Vec::new(), None, None);
+        assert!(merged.is_empty() && warnings.is_empty());
+    }
+
+    #[test]
+    fn test_merge_window_args_prefers_explicit_args() {
+        let user = vec![
+            "--window-size=1920,1080".to_string(),
+            "--window-position=100,100".to_string(),
+        ];
+        let (merged, warnings) = merge_window_args(user.clone(), Some((1280, 800)), Some((0, 0)));
+        assert_eq!(merged, user);
+        assert_eq!(
+            warnings,
+            vec![
+                "--window-size 1280x800 ignored: --args already contains --window-size=1920,1080",
+                "--window-position 0,0 ignored: --args already contains --window-position=100,100",
+            ]
+        );
+
+        let (merged, warnings) = merge_window_args(
+            vec!["--start-maximized".to_string()],
+            Some((800, 600)),
+            Some((5, 5)),
+        );
+        assert_eq!(merged, vec!["--start-maximized", "--window-position=5,5"]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("--start-maximized"));
+    }
+
     #[test]
     fn test_parse_idle_timeout_flag_converts_to_ms() {
         let flags = parse_flags(&args("--idle-timeout 10s open example.com"));
@@ -1382,6 +2378,25 @@ mod tests {
         assert!(flags.cli_extensions);
     }
 
+    #[test]
+    fn test_ignore_https_origin_repeatable() {
+        let flags = parse_flags(&args(
+            "--ignore-https-origin https://intranet.example.com --ignore-https-origin https://dev.local:8443 open",
+        ));
+        assert!(flags.cli_ignore_https_origins);
+        assert!(flags.ignore_https_origins.ends_with(&[
+            "https://intranet.example.com".to_string(),
+            "https://dev.local:8443".to_string()
+        ]));
+        let cleaned = clean_args(&args(
+            "--ignore-https-origin https://intranet.example.com open intranet.example.com",
+        ));
+        assert_eq!(cleaned, vec!["open", "intranet.example.com"]);
+
+        let flags = parse_flags(&args("open"));
+        assert!(!flags.cli_ignore_https_origins);
+    }
+
     #[test]
     fn test_cli_profile_tracking() {
         let flags = parse_flags(&args("--profile /path/to/profile snapshot"));
@@ -1414,6 +2429,247 @@ mod tests {
         assert!(!flags.cli_download_path);
     }
 
+    #[test]
+    fn test_normalize_path_value() {
+        let cwd = Path::new("/work");
+        let home = Some(Path::new("/home/me"));
+        let norm = |v: &str| normalize_path_value(v, cwd, home);
+        assert_eq!(norm("~"), "/home/me");
+        assert_eq!(norm("~/Downloads"), "/home/me/Downloads");
+        assert_eq!(norm("downloads"), "/work/downloads");
+        assert_eq!(norm("./state.json"), "/work/./state.json");
+        assert_eq!(norm("/abs/dir"), "/abs/dir");
+        assert_eq!(norm(r"C:\Users\me"), r"C:\Users\me");
+        assert_eq!(norm(r"\\server\share"), r"\\server\share");
+        // `~user` is not expanded; it is an ordinary relative name.
+        assert_eq!(norm("~other/x"), "/work/~other/x");
+        assert_eq!(normalize_path_value("~/x", cwd, None), "/work/~/x");
+    }
+
+    #[test]
+    fn test_parse_flags_normalizes_paths() {
+        let guard = EnvGuard::new(&["HOME"]);
+        guard.set("HOME", "/home/me");
+        let cwd = env::current_dir().unwrap();
+        let flags = parse_flags(&args(
+            "--download-path ~/dl --state auth.json --extension ext --action-policy /p.json --profile ./prof --artifacts-dir out open",
+        ));
+        assert_eq!(flags.download_path, Some("/home/me/dl".to_string()));
+        assert_eq!(
+            flags.artifacts_dir,
+            Some(cwd.join("out").to_string_lossy().into_owned())
+        );
+        assert_eq!(
+            flags.state,
+            Some(cwd.join("auth.json").to_string_lossy().into_owned())
+        );
+        assert_eq!(
+            flags.extensions,
+            vec![cwd.join("ext").to_string_lossy().into_owned()]
+        );
+        assert_eq!(flags.action_policy, Some("/p.json".to_string()));
+        assert_eq!(
+            flags.profile,
+            Some(cwd.join("./prof").to_string_lossy().into_owned())
+        );
+
+        // A bare Chrome profile name is not a path.
+        let flags = parse_flags(&args("--profile Default open"));
+        assert_eq!(flags.profile, Some("Default".to_string()));
+    }
+
+    fn bool_flag_value(flags: &Flags, name: &str) -> bool {
+        match name {
+            "--json" => flags.json,
+            "--headed" => flags.headed,
+            "--webgpu" => flags.webgpu,
+            "--debug" => flags.debug,
+            "--ignore-https-errors" => flags.ignore_https_errors,
+            "--allow-file-access" => flags.allow_file_access,
+            "--hide-scrollbars" => flags.hide_scrollbars,
+            "--auto-connect" => flags.auto_connect,
+            "--annotate" => flags.annotate,
+            "--content-boundaries" => flags.content_boundaries,
+            "--confirm-interactive" => flags.confirm_interactive,
+            "--no-auto-dialog" => flags.no_auto_dialog,
+            "--no-exit-code" => flags.no_exit_code,
+            "--no-install-prompt" => flags.no_install_prompt,
+            "--require-fresh-daemon" => flags.require_fresh_daemon,
+            "-v" | "--verbose" => flags.verbose,
+            "-q" | "--quiet" => flags.quiet,
+            other => panic!("no accessor for {}", other),
+        }
+    }
+
+    #[test]
+    fn test_bool_flags_accept_every_form() {
+        // (args, expected value, expected command args after clean_args)
+        let forms: &[(&str, bool, &str)] = &[
+            ("{f} open", true, "open"),
+            ("{f}=true open", true, "open"),
+            ("{f}=false open", false, "open"),
+            ("{f} true open", true, "open"),
+            ("{f} false open", false, "open"),
+            ("open example.com {f} false", false, "open example.com"),
+            ("open example.com {f}", true, "open example.com"),
+            // A positional "false" before the flag stays with the command.
+            ("set offline false {f}", true, "set offline false"),
+            ("{f} false set offline false", false, "set offline false"),
+        ];
+        for flag in BOOL_FLAGS {
+            for (form, value, rest) in forms {
+                let input = args(&form.replace("{f}", flag));
+                let flags = parse_flags(&input);
+                assert_eq!(bool_flag_value(&flags, flag), *value, "{:?}", input);
+                assert_eq!(clean_args(&input), args(rest), "{:?}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bool_flag_false_overrides_earlier_true() {
+        let flags = parse_flags(&args(
+            "--headed --headed=false --json true --json=false open",
+        ));
+        assert!(!flags.headed);
+        assert!(flags.cli_headed);
+        assert!(!flags.json);
+    }
+
+    #[test]
+    fn test_bool_flag_with_unknown_inline_value_is_not_a_flag() {
+        let input = args("--headed=maybe open");
+        assert!(!parse_flags(&input).cli_headed);
+        assert_eq!(clean_args(&input), args("--headed=maybe open"));
+    }
+
+    #[test]
+    fn test_validate_flag_values() {
+        let cases: &[(&str, Option<&str>)] = &[
+            ("--color-scheme dark open", None),
+            ("--color-scheme light open", None),
+            ("--color-scheme no-preference open", None),
+            (
+                "--color-scheme drak open",
+                Some("--color-scheme: invalid value 'drak'"),
+            ),
+            (
+                "--color-scheme Dark open",
+                Some("--color-scheme: invalid value 'Dark'"),
+            ),
+            ("--default-scheme http open", None),
+            ("--default-scheme none open", None),
+            (
+                "--default-scheme ftp open",
+                Some("--default-scheme: invalid value 'ftp'. Valid values: https, http, none"),
+            ),
+            ("--confirm-actions eval,download open", None),
+            ("--confirm-actions navigate,,fill open", None),
+            ("--confirm-actions plugin:vault:credential.read open", None),
+            ("--confirm-actions EVALUATE open", None),
+            (
+                "--confirm-actions evil open",
+                Some("--confirm-actions: Unknown action category 'evil'"),
+            ),
+            (
+                "--confirm-actions click,nope,bad open",
+                Some("categories 'nope', 'bad'"),
+            ),
+            (
+                "--confirm-actions plugin:vault open",
+                Some("'plugin:vault'"),
+            ),
+            ("--ref-prefix ab12 snapshot", None),
+            (
+                "--ref-prefix ab-12 snapshot",
+                Some("--ref-prefix: invalid value 'ab-12'"),
+            ),
+            (
+                "--ref-prefix abcdefghi snapshot",
+                Some("expected 1 to 8 letters or digits"),
+            ),
+            ("--ignore-https-origin https://dev.local:8443 open", None),
+            (
+                "--ignore-https-origin http://dev.local open",
+                Some("--ignore-https-origin: invalid origin 'http://dev.local'"),
+            ),
+            (
+                "--ignore-https-origin dev.local open",
+                Some("--ignore-https-origin: invalid origin"),
+            ),
+        ];
+        for (input, expected) in cases {
+            let result = validate_flag_values(&parse_flags(&args(input)));
+            match expected {
+                None => assert!(result.is_ok(), "{}: {:?}", input, result),
+                Some(fragment) => {
+                    let err = result.unwrap_err();
+                    assert!(err.contains(fragment), "{}: {}", input, err);
+                }
+            }
+        }
+        let err =
+            validate_flag_values(&parse_flags(&args("--confirm-actions evil open"))).unwrap_err();
+        assert!(err.contains("Valid categories:") && err.contains("navigate"));
+    }
+
+    #[test]
+    fn test_validate_path_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut flags = parse_flags(&args("open"));
+        flags.download_path = Some(dir.path().join("new/dl").to_string_lossy().into_owned());
+        assert!(validate_path_flags(&flags).is_ok());
+        assert!(dir.path().join("new/dl").is_dir());
+        assert_eq!(fs::read_dir(dir.path().join("new/dl")).unwrap().count(), 0);
+
+        flags.download_path = Some(r"%USERPROFILE%\Downloads".to_string());
+        let err = validate_path_flags(&flags).unwrap_err();
+        assert!(err.starts_with("--download-path:") && err.contains("%USERPROFILE%"));
+
+        flags.download_path = None;
+        flags.state = Some(
+            dir.path()
+                .join("missing.json")
+                .to_string_lossy()
+                .into_owned(),
+        );
+        let err = validate_path_flags(&flags).unwrap_err();
+        assert!(err.starts_with("--state: file not found"));
+
+        flags.state = None;
+        let file = dir.path().join("file");
+        fs::write(&file, "x").unwrap();
+        flags.profile = Some(file.to_string_lossy().into_owned());
+        let err = validate_path_flags(&flags).unwrap_err();
+        assert!(err.starts_with("--profile:") && err.contains("not a directory"));
+
+        flags.profile = None;
+        flags.user_data_dir = Some(dir.path().join("missing").to_string_lossy().into_owned());
+        let err = validate_path_flags(&flags).unwrap_err();
+        assert!(err.starts_with("--user-data-dir: directory not found"));
+        flags.user_data_dir = Some(dir.path().to_string_lossy().into_owned());
+        assert!(validate_path_flags(&flags).is_ok());
+    }
+
+    #[test]
+    fn test_validate_path_flags_encrypted_state_needs_key() {
+        let guard = EnvGuard::new(&["AGENT_BROWSER_ENCRYPTION_KEY"]);
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("auth.state");
+        // Not JSON, so it can only be ciphertext.
+        fs::write(&state, [0x9au8; 48]).unwrap();
+        let mut flags = parse_flags(&args("open"));
+        flags.state = Some(state.to_string_lossy().into_owned());
+
+        guard.remove("AGENT_BROWSER_ENCRYPTION_KEY");
+        let err = validate_path_flags(&flags).unwrap_err();
+        assert!(err.starts_with("--state:"), "{}", err);
+        assert!(err.contains("AGENT_BROWSER_ENCRYPTION_KEY"), "{}", err);
+
+        guard.set("AGENT_BROWSER_ENCRYPTION_KEY", &"ab".repeat(32));
+        assert!(validate_path_flags(&flags).is_ok());
+    }
+
     #[test]
     fn test_cli_multiple_flags_tracking() {
         let flags = parse_flags(&args(
@@ -1542,6 +2798,19 @@ mod tests {
         assert_eq!(merged.debug, Some(true)); // added by project
     }
 
+    #[test]
+    fn test_config_merge_aliases_per_name() {
+        let user: Config = serde_json::from_str(
+            r#"{"aliases": {"si": ["snapshot", "-i"], "home": ["open", "a.com"]}}"#,
+        )
+        .unwrap();
+        let project: Config =
+            serde_json::from_str(r#"{"aliases": {"home": ["open", "b.com"]}}"#).unwrap();
+        let aliases = user.merge(project).aliases.unwrap();
+        assert_eq!(aliases["si"], vec!["snapshot", "-i"]);
+        assert_eq!(aliases["home"], vec!["open", "b.com"]);
+    }
+
     #[test]
     fn test_config_merge_none_does_not_override() {
         let user = Config {
@@ -1555,6 +2824,128 @@ mod tests {
         assert_eq!(merged.proxy.as_deref(), Some("http://proxy:8080"));
     }
 
+    #[test]
+    fn test_config_session_profile_merges_over_base() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "maxOutput": 1000,
+                "headed": true,
+                "sessions": {
+                    "mobile": { "device": "iPhone 15", "colorScheme": "dark", "session": "other" },
+                    "scraper": { "maxOutput": 20000 }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let (merged, mut keys) = config.apply_session_profile("mobile");
+        keys.sort();
+        assert_eq!(keys, vec!["colorScheme", "device"]);
+        assert_eq!(merged.device.as_deref(), Some("iPhone 15"));
+        assert_eq!(merged.color_scheme.as_deref(), Some("dark"));
+        assert_eq!(merged.max_output, Some(1000));
+        assert_eq!(merged.headed, Some(true));
+        assert_eq!(merged.session, None);
+
+        let config: Config =
+            serde_json::from_str(r#"{"maxOutput": 1000, "sessions": {"scraper": {}}}"#).unwrap();
+        let (merged, keys) = config.apply_session_profile("default");
+        assert!(keys.is_empty());
+        assert_eq!(merged.max_output, Some(1000));
+    }
+
+    #[test]
+    fn test_config_merge_session_profiles_per_name() {
+        let user: Config = serde_json::from_str(
+            r#"{"sessions": {"mobile": {"device": "iPhone 15"}, "scraper": {"maxOutput": 1}}}"#,
+        )
+        .unwrap();
+        let project: Config =
+            serde_json::from_str(r#"{"sessions": {"scraper": {"maxOutput": 2}}}"#).unwrap();
+        let sessions = user.merge(project).sessions.unwrap();
+        assert_eq!(sessions["mobile"].device.as_deref(), Some("iPhone 15"));
+        assert_eq!(sessions["scraper"].max_output, Some(2));
+    }
+
+    #[test]
+    fn test_session_profile_precedence() {
+        let guard = EnvGuard::new(&[
+            "AGENT_BROWSER_CONFIG",
+            "AGENT_BROWSER_SESSION",
+            "AGENT_BROWSER_MAX_OUTPUT",
+            "AGENT_BROWSER_IOS_DEVICE",
+            "AGENT_BROWSER_COLOR_SCHEME",
+        ]);
+        for name in [
+            "AGENT_BROWSER_CONFIG",
+            "AGENT_BROWSER_SESSION",
+            "AGENT_BROWSER_MAX_OUTPUT",
+            "AGENT_BROWSER_IOS_DEVICE",
+            "AGENT_BROWSER_COLOR_SCHEME",
+        ] {
+            guard.remove(name);
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent-browser.json");
+        fs::write(
+            &path,
+            r#"{
+                "maxOutput": 1000,
+                "colorScheme": "light",
+                "sessions": {
+                    "scraper": { "maxOutput": 20000, "device": "iPhone 15", "colorScheme": "dark" }
+                }
+            }"#,
+        )
+        .unwrap();
+        let with_config = |rest: &str| {
+            let mut a = vec!["--config".to_string(), path.to_string_lossy().to_string()];
+            a.extend(args(rest));
+            parse_flags(&a)
+        };
+
+        // Base config only.
+        let flags = with_config("--session other open example.com");
+        assert_eq!(flags.max_output, Some(1000));
+        assert_eq!(flags.color_scheme.as_deref(), Some("light"));
+        assert!(flags.device.is_none());
+        assert!(flags.session_profile.is_empty());
+
+        // Session profile over base config; not counted as a CLI launch option.
+        let flags = with_config("--session scraper open example.com");
+        assert_eq!(flags.max_output, Some(20000));
+        assert_eq!(flags.color_scheme.as_deref(), Some("dark"));
+        assert_eq!(flags.device.as_deref(), Some("iPhone 15"));
+        assert!(!flags.cli_device);
+        let mut keys = flags.session_profile.clone();
+        keys.sort();
+        assert_eq!(keys, vec!["colorScheme", "device", "maxOutput"]);
+
+        // The session can also come from the environment.
+        guard.set("AGENT_BROWSER_SESSION", "scraper");
+        let flags = with_config("open example.com");
+        assert_eq!(flags.session, "scraper");
+        assert_eq!(flags.max_output, Some(20000));
+        guard.remove("AGENT_BROWSER_SESSION");
+
+        // Env beats the session profile.
+        guard.set("AGENT_BROWSER_MAX_OUTPUT", "500");
+        guard.set("AGENT_BROWSER_IOS_DEVICE", "iPad Air");
+        let flags = with_config("--session scraper open example.com");
+        assert_eq!(flags.max_output, Some(500));
+        assert_eq!(flags.device.as_deref(), Some("iPad Air"));
+        assert_eq!(flags.color_scheme.as_deref(), Some("dark"));
+
+        // CLI flags beat env and the session profile.
+        let flags = with_config(
+            "--session scraper --max-output 42 --device iPhone-SE --color-scheme no-preference open example.com",
+        );
+        assert_eq!(flags.max_output, Some(42));
+        assert_eq!(flags.device.as_deref(), Some("iPhone-SE"));
+        assert!(flags.cli_device);
+        assert_eq!(flags.color_scheme.as_deref(), Some("no-preference"));
+    }
+
     #[test]
     fn test_load_config_from_file() {
         use std::io::Write;
@@ -1816,6 +3207,62 @@ mod tests {
         assert!(flags.cli_hide_scrollbars);
     }
 
+    /// Write `json` to a temp config file and return `--config <path>` args.
+    fn with_config(name: &str, json: &str, rest: &str) -> (PathBuf, Vec<String>) {
+        let path =
+            std::env::temp_dir().join(format!("ab-test-{}-{}.json", name, std::process::id()));
+        fs::write(&path, json).unwrap();
+        let mut argv = vec!["--config".to_string(), path.to_string_lossy().to_string()];
+        argv.extend(args(rest));
+        (path, argv)
+    }
+
+    #[test]
+    fn test_annotate_precedence_config_env_flag() {
+        let guard = EnvGuard::new(&["AGENT_BROWSER_ANNOTATE"]);
+        guard.remove("AGENT_BROWSER_ANNOTATE");
+        let (path, argv) = with_config("annotate", r#"{"annotate": true}"#, "screenshot");
+        assert!(parse_flags(&argv).annotate);
+
+        // The environment overrides the config file...
+        guard.set("AGENT_BROWSER_ANNOTATE", "false");
+        assert!(!parse_flags(&argv).annotate);
+        guard.set("AGENT_BROWSER_ANNOTATE", "1");
+        assert!(parse_flags(&args("screenshot")).annotate);
+
+        // ...and an explicit flag overrides both.
+        let mut negated = argv.clone();
+        negated.extend(args("--annotate false"));
+        let flags = parse_flags(&negated);
+        assert!(!flags.annotate);
+        assert!(flags.cli_annotate);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_full_precedence_config_env_flag() {
+        let guard = EnvGuard::new(&["AGENT_BROWSER_FULL"]);
+        guard.remove("AGENT_BROWSER_FULL");
+        assert!(!parse_flags(&args("screenshot")).full);
+
+        let (path, argv) = with_config("full", r#"{"full": true}"#, "screenshot");
+        assert!(parse_flags(&argv).full);
+        guard.set("AGENT_BROWSER_FULL", "0");
+        assert!(!parse_flags(&argv).full);
+        guard.set("AGENT_BROWSER_FULL", "true");
+        assert!(parse_flags(&args("screenshot")).full);
+
+        // `screenshot --full false` is parsed by the command itself and wins
+        // over this default; see commands::tests.
+        let cmd = crate::commands::parse_command(
+            &args("screenshot --full false"),
+            &parse_flags(&args("screenshot")),
+        )
+        .unwrap();
+        assert_eq!(cmd["fullPage"], false);
+        let _ = fs::remove_file(&path);
+    }
+
     #[test]
     fn test_auto_connect_false() {
         let flags = parse_flags(&args("--auto-connect false open"));
@@ -1887,6 +3334,17 @@ mod tests {
         assert!(flags.no_auto_dialog);
     }
 
+    #[test]
+    fn test_no_exit_code_flag() {
+        let flags = parse_flags(&args("is visible #modal --no-exit-code"));
+        assert!(flags.no_exit_code);
+        assert!(!parse_flags(&args("is visible #modal")).no_exit_code);
+        assert_eq!(
+            clean_args(&args("is visible #modal --no-exit-code")),
+            vec!["is", "visible", "#modal"]
+        );
+    }
+
     #[test]
     fn test_no_auto_dialog_default_false() {
         let flags = parse_flags(&args("open example.com"));
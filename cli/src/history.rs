@@ -0,0 +1,381 @@
+//! Per-session command history for `history` and `last`.
+//!
+//! Every successfully parsed command is appended, with its secrets already
+//! redacted, as one JSON line to `<session>.history` under the socket dir.
+//! Each entry is a single `write` on a file opened for append, so parallel
+//! invocations never interleave partial lines. Appends and the occasional
+//! compaction hold an advisory lock on `<session>.history.lock`, so an entry
+//! written while another invocation compacts is never lost with the file it
+//! replaces. Entries can still hold typed
+//! values, scripts, and URLs, so the file is owner-only (0600 on Unix) like
+//! other files written with `write_private`. Set `AGENT_BROWSER_NO_HISTORY` to
+//! turn recording off.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::atomic_write::write_private;
+use crate::color;
+use crate::connection::get_socket_dir;
+use crate::humanize::humanize_timestamp_ms;
+use crate::redact::COMMAND_REDACTED;
+
+/// Entries kept per session.
+pub const MAX_HISTORY: usize = 500;
+
+/// The file is compacted back to [`MAX_HISTORY`] entries once it grows past
+/// this many lines, so rotation is rare and appends stay append-only.
+const COMPACT_AT: usize = MAX_HISTORY * 2;
+
+const HISTORY_USAGE: &str = "history [--limit <n>]";
+const LAST_USAGE: &str = "last [offset]";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Epoch milliseconds.
+    pub ts: i64,
+    /// Command words after global flags were removed, secrets redacted.
+    pub args: Vec<String>,
+}
+
+pub fn history_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.history", session))
+}
+
+/// Append one entry, compacting the file when it has grown too long. Both
+/// happen under the history lock.
+pub fn record(path: &Path, args: &[String], ts: i64) -> std::io::Result<()> {
+    let entry = HistoryEntry {
+        ts,
+        args: args.to_vec(),
+    };
+    let mut line = serde_json::to_string(&entry)?;
+    line.push('\n');
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let lock = open_private_append(Path::new(&lock_path))?;
+    lock.lock()?;
+
+    let mut file = open_private_append(path)?;
+    file.write_all(line.as_bytes())?;
+    drop(file);
+
+    let contents = fs::read_to_string(path)?;
+    let lines: Vec<&str> = contents.lines().collect();
+    if lines.len() > COMPACT_AT {
+        let mut kept = lines[lines.len() - MAX_HISTORY..].join("\n");
+        kept.push('\n');
+        write_private(path, kept)?;
+    }
+    Ok(())
+}
+
+/// Open `path` for append, creating it owner-only. A file left readable by
+/// an older version is tightened too.
+fn open_private_append(path: &Path) -> std::io::Result<fs::File> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        let file = options.open(path)?;
+        if file.metadata()?.permissions().mode() & 0o077 != 0 {
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    options.open(path)
+}
+
+/// The last [`MAX_HISTORY`] entries, oldest first. Unreadable lines, such as
+/// one cut short by a full disk, are skipped.
+pub fn read_entries(path: &Path) -> Vec<HistoryEntry> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let entries: Vec<HistoryEntry> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = entries.len().saturating_sub(MAX_HISTORY);
+    entries.into_iter().skip(skip).collect()
+}
+
+/// The entry `offset` commands back: 1 is the most recent.
+pub fn lookup(entries: &[HistoryEntry], offset: usize) -> Option<&HistoryEntry> {
+    if offset == 0 {
+        return None;
+    }
+    entries.len().checked_sub(offset).map(|i| &entries[i])
+}
+
+fn fail(message: String, json_mode: bool) -> ! {
+    if json_mode {
+        println!("{}", json!({ "success": false, "error": message }));
+    } else {
+        eprintln!("{} {}", color::error_indicator(), message);
+    }
+    exit(1);
+}
+
+fn parse_count(raw: &str, what: &str, usage: &str) -> Result<usize, String> {
+    match raw.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!(
+            "Invalid {} '{}': expected a positive integer. Usage: {}",
+            what, raw, usage
+        )),
+    }
+}
+
+fn parse_history_limit(args: &[String]) -> Result<Option<usize>, String> {
+    let mut limit = None;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--limit" => {
+                let raw = args.get(i + 1).ok_or_else(|| {
+                    format!("Missing value for --limit. Usage: {}", HISTORY_USAGE)
+                })?;
+                limit = Some(parse_count(raw, "--limit", HISTORY_USAGE)?);
+                i += 2;
+            }
+            other => {
+                return Err(format!(
+                    "Unknown argument '{}'. Usage: {}",
+                    other, HISTORY_USAGE
+                ))
+            }
+        }
+    }
+    Ok(limit)
+}
+
+/// `agent-browser history [--limit N]`: most recent last, numbered by the
+/// offset `last` takes.
+pub fn run_history(args: &[String], session: &str, json_mode: bool) {
+    let limit = parse_history_limit(args).unwrap_or_else(|e| fail(e, json_mode));
+    let entries = read_entries(&history_path(session));
+    let start = limit.map_or(0, |n| entries.len().saturating_sub(n));
+    let shown = &entries[start..];
+
+    if json_mode {
+        let items: Vec<_> = shown
+            .iter()
+            .enumerate()
+            .map(|(i, e)| json!({ "offset": shown.len() - i, "ts": e.ts, "args": e.args }))
+            .collect();
+        println!(
+            "{}",
+            json!({ "success": true, "data": { "session": session, "entries": items } })
+        );
+        return;
+    }
+    if shown.is_empty() {
+        println!("No history for session {}", session);
+        return;
+    }
+    let width = shown.len().to_string().len();
+    for (i, entry) in shown.iter().enumerate() {
+        let when = humanize_timestamp_ms(entry.ts).unwrap_or_default();
+        println!(
+            "{:>width$}  {}  {}",
+            shown.len() - i,
+            color::dim(&format!("{:<10}", when)),
+            entry.args.join(" "),
+            width = width
+        );
+    }
+}
+
+/// Resolve `last [offset]` to the command words to run again.
+pub fn resolve_last(args: &[String], session: &str) -> Result<Vec<String>, String> {
+    let offset = match args.get(1) {
+        None => 1,
+        Some(raw) => parse_count(raw, "offset", LAST_USAGE)?,
+    };
+    if args.len() > 2 {
+        return Err(format!("Too many arguments. Usage: {}", LAST_USAGE));
+    }
+    let entries = read_entries(&history_path(session));
+    let entry = lookup(&entries, offset).ok_or_else(|| {
+        format!(
+            "No command {} back in the history of session {} ({} recorded)",
+            offset,
+            session,
+            entries.len()
+        )
+    })?;
+    if entry.args.iter().any(|a| a.contains(COMMAND_REDACTED)) {
+        return Err(format!(
+            "'{}' had secrets redacted from history; run it again explicitly",
+            entry.args.join(" ")
+        ));
+    }
+    Ok(entry.args.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    fn temp_history() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("default.history");
+        (dir, path)
+    }
+
+    #[test]
+    fn test_record_and_read_round_trip() {
+        let (_dir, path) = temp_history();
+        record(&path, &words("open example.com"), 1).unwrap();
+        record(&path, &words("snapshot -i"), 2).unwrap();
+        let entries = read_entries(&path);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].args, words("open example.com"));
+        assert_eq!(entries[1].ts, 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_history_file_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let (_dir, path) = temp_history();
+        record(&path, &words("fill #password hunter2"), 1).unwrap();
+        assert_eq!(mode(&path), 0o600);
+
+        // A file created readable by an older version is tightened.
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        record(&path, &words("snapshot"), 2).unwrap();
+        assert_eq!(mode(&path), 0o600);
+
+        for i in 0..=COMPACT_AT {
+            record(&path, &[format!("cmd{}", i)], i as i64).unwrap();
+        }
+        assert_eq!(mode(&path), 0o600);
+    }
+
+    #[test]
+    fn test_record_rotates_to_max_history() {
+        let (_dir, path) = temp_history();
+        for i in 0..=COMPACT_AT {
+            record(&path, &[format!("cmd{}", i)], i as i64).unwrap();
+        }
+        let lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert_eq!(lines, MAX_HISTORY);
+        let entries = read_entries(&path);
+        assert_eq!(entries.len(), MAX_HISTORY);
+        assert_eq!(
+            entries.last().unwrap().args,
+            vec![format!("cmd{}", COMPACT_AT)]
+        );
+        assert_eq!(
+            entries[0].args,
+            vec![format!("cmd{}", COMPACT_AT + 1 - MAX_HISTORY)]
+        );
+    }
+
+    /// Appends racing a compaction all land: with every write and rewrite
+    /// serialized by the lock, the line count is exactly what one writer
+    /// would leave.
+    #[test]
+    fn test_interleaved_appends_survive_compaction() {
+        const WRITERS: usize = 4;
+        const EACH: usize = 300;
+        let (_dir, path) = temp_history();
+        std::thread::scope(|scope| {
+            for writer in 0..WRITERS {
+                let path = &path;
+                scope.spawn(move || {
+                    for i in 0..EACH {
+                        let ts = (writer * EACH + i) as i64;
+                        record(path, &[format!("w{}-{}", writer, i)], ts).unwrap();
+                    }
+                });
+            }
+        });
+
+        let total = WRITERS * EACH;
+        assert!(total > COMPACT_AT && total - COMPACT_AT - 1 < COMPACT_AT - MAX_HISTORY);
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            contents.lines().count(),
+            MAX_HISTORY + total - COMPACT_AT - 1
+        );
+        assert_eq!(read_entries(&path).len(), MAX_HISTORY);
+    }
+
+    #[test]
+    fn test_read_caps_uncompacted_file_and_skips_bad_lines() {
+        let (_dir, path) = temp_history();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut contents = String::from("{\"ts\":0,\"args\":[\"trunc\n");
+        for i in 0..MAX_HISTORY + 3 {
+            contents.push_str(&format!("{{\"ts\":{},\"args\":[\"c{}\"]}}\n", i, i));
+        }
+        fs::write(&path, contents).unwrap();
+        let entries = read_entries(&path);
+        assert_eq!(entries.len(), MAX_HISTORY);
+        assert_eq!(entries[0].args, vec!["c3".to_string()]);
+    }
+
+    #[test]
+    fn test_record_keeps_redacted_words() {
+        use crate::redact::CommandRedactor;
+
+        let (_dir, path) = temp_history();
+        let args = words("set credentials admin hunter2-secret");
+        let cmd =
+            json!({ "action": "credentials", "username": "admin", "password": "hunter2-secret" });
+        let secrets = CommandRedactor::new(None).secrets(&cmd);
+        record(&path, &secrets.words(&args), 1).unwrap();
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("hunter2"));
+        assert_eq!(read_entries(&path)[0].args[3], COMMAND_REDACTED);
+    }
+
+    #[test]
+    fn test_lookup_offsets() {
+        let entries: Vec<HistoryEntry> = ["a", "b", "c"]
+            .iter()
+            .enumerate()
+            .map(|(i, a)| HistoryEntry {
+                ts: i as i64,
+                args: vec![a.to_string()],
+            })
+            .collect();
+        assert_eq!(lookup(&entries, 1).unwrap().args, vec!["c"]);
+        assert_eq!(lookup(&entries, 3).unwrap().args, vec!["a"]);
+        assert!(lookup(&entries, 4).is_none());
+        assert!(lookup(&entries, 0).is_none());
+        assert!(lookup(&[], 1).is_none());
+    }
+
+    #[test]
+    fn test_parse_history_limit() {
+        assert_eq!(parse_history_limit(&words("history")).unwrap(), None);
+        assert_eq!(
+            parse_history_limit(&words("history --limit 5")).unwrap(),
+            Some(5)
+        );
+        assert!(parse_history_limit(&words("history --limit 0")).is_err());
+        assert!(parse_history_limit(&words("history --limit")).is_err());
+        assert!(parse_history_limit(&words("history extra")).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Human-readable sizes, durations, counts and timestamps for text output.
+//!
+//! JSON output keeps raw numbers; these helpers are only for the text
+//! renderers in `output.rs`, so every command shows the same units.
+
+use chrono::{DateTime, Utc};
+
+/// Byte count in binary units with one decimal: `1023 B`, `1.0 KiB`,
+/// `3.4 MiB`.
+pub fn humanize_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    // Step up before rounding would print e.g. `1024.0 KiB`.
+    while size >= 1023.95 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Duration from milliseconds: `850 ms`, `59.9 s`, `2m 5s`, `1h 4m`.
+pub fn humanize_duration(ms: f64) -> String {
+    let ms = ms.max(0.0);
+    if ms.round() < 1000.0 {
+        return format!("{} ms", ms.round());
+    }
+    let tenths = (ms / 100.0).round() / 10.0;
+    if tenths < 60.0 {
+        return format!("{:.1} s", tenths);
+    }
+    let secs = (ms / 1000.0).round() as u64;
+    if secs < 3600 {
+        return format!("{}m {}s", secs / 60, secs % 60);
+    }
+    format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+}
+
+/// Integer with thousands separators: `12,345`.
+pub fn humanize_count(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+/// Compact count for one-line notes: `950`, `14.8k`, `15k`, `2.1M`.
+pub fn humanize_count_short(n: u64) -> String {
+    if n < 1000 {
+        return n.to_string();
+    }
+    // Step up before rounding would print e.g. `1000.0k`.
+    let (value, unit) = if n < 999_950 {
+        (n as f64 / 1e3, "k")
+    } else {
+        (n as f64 / 1e6, "M")
+    };
+    let digits = format!("{:.1}", value);
+    format!("{}{}", digits.strip_suffix(".0").unwrap_or(&digits), unit)
+}
+
+/// How long ago `time` was relative to `now` (`just now`, `5m ago`,
+/// `3d ago`). Times in the future or more than a week old fall back to
+/// the absolute UTC time.
+pub fn humanize_timestamp(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let secs = (now - time).num_seconds();
+    match secs {
+        s if !(0..7 * 86_400).contains(&s) => time.format("%Y-%m-%d %H:%M UTC").to_string(),
+        s if s < 10 => "just now".to_string(),
+        s if s < 60 => format!("{}s ago", s),
+        s if s < 3600 => format!("{}m ago", s / 60),
+        s if s < 86_400 => format!("{}h ago", s / 3600),
+        s => format!("{}d ago", s / 86_400),
+    }
+}
+
+/// [`humanize_timestamp`] for epoch milliseconds, relative to the clock.
+pub fn humanize_timestamp_ms(ms: i64) -> Option<String> {
+    DateTime::from_timestamp_millis(ms).map(|t| humanize_timestamp(t, Utc::now()))
+}
+
+/// [`humanize_timestamp`] for an RFC 3339 string, relative to the clock.
+pub fn humanize_rfc3339(raw: &str) -> Option<String> {
+    DateTime::parse_from_rfc3339(raw)
+        .ok()
+        .map(|t| humanize_timestamp(t.with_timezone(&Utc), Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_humanize_bytes_boundaries() {
+        assert_eq!(humanize_bytes(0), "0 B");
+        assert_eq!(humanize_bytes(1023), "1023 B");
+        assert_eq!(humanize_bytes(1024), "1.0 KiB");
+        assert_eq!(humanize_bytes(1025), "1.0 KiB");
+        assert_eq!(humanize_bytes(12_700), "12.4 KiB");
+        assert_eq!(humanize_bytes(1024 * 1024 - 1), "1.0 MiB");
+        assert_eq!(humanize_bytes(3 * 1024 * 1024), "3.0 MiB");
+        assert_eq!(humanize_bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+
+    #[test]
+    fn test_humanize_duration_boundaries() {
+        assert_eq!(humanize_duration(0.0), "0 ms");
+        assert_eq!(humanize_duration(12.4), "12 ms");
+        assert_eq!(humanize_duration(999.4), "999 ms");
+        assert_eq!(humanize_duration(999.6), "1.0 s");
+        assert_eq!(humanize_duration(1_000.0), "1.0 s");
+        assert_eq!(humanize_duration(59_900.0), "59.9 s");
+        assert_eq!(humanize_duration(59_960.0), "1m 0s");
+        assert_eq!(humanize_duration(125_000.0), "2m 5s");
+        assert_eq!(humanize_duration(3_840_000.0), "1h 4m");
+        assert_eq!(humanize_duration(-5.0), "0 ms");
+    }
+
+    #[test]
+    fn test_humanize_count() {
+        assert_eq!(humanize_count(0), "0");
+        assert_eq!(humanize_count(999), "999");
+        assert_eq!(humanize_count(1_000), "1,000");
+        assert_eq!(humanize_count(1_234_567), "1,234,567");
+    }
+
+    #[test]
+    fn test_humanize_count_short() {
+        assert_eq!(humanize_count_short(999), "999");
+        assert_eq!(humanize_count_short(1_000), "1k");
+        assert_eq!(humanize_count_short(14_800), "14.8k");
+        assert_eq!(humanize_count_short(15_000), "15k");
+        assert_eq!(humanize_count_short(999_960), "1M");
+        assert_eq!(humanize_count_short(2_140_000), "2.1M");
+    }
+
+    #[test]
+    fn test_humanize_timestamp() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        let ago = |d: Duration| humanize_timestamp(now - d, now);
+        assert_eq!(ago(Duration::seconds(3)), "just now");
+        assert_eq!(ago(Duration::seconds(59)), "59s ago");
+        assert_eq!(ago(Duration::seconds(60)), "1m ago");
+        assert_eq!(ago(Duration::minutes(59)), "59m ago");
+        assert_eq!(ago(Duration::hours(2)), "2h ago");
+        assert_eq!(ago(Duration::days(3)), "3d ago");
+        assert_eq!(ago(Duration::days(7)), "2024-05-03 12:00 UTC");
+        // Clock skew or a future time: absolute, never "-5s ago".
+        assert_eq!(
+            humanize_timestamp(now + Duration::seconds(5), now),
+            "2024-05-10 12:00 UTC"
+        );
+    }
+
+    #[test]
+    fn test_humanize_rfc3339_rejects_garbage() {
+        assert!(humanize_rfc3339("not a date").is_none());
+        assert!(humanize_rfc3339("2024-05-10T12:00:00Z").is_some());
+    }
+}
@@ -1,43 +1,76 @@
+mod act;
+mod action_retry;
+mod aliases;
+mod annotations;
+mod artifacts;
+mod atomic_write;
+mod bugreport;
 mod chat;
 mod color;
+mod command_registry;
 mod commands;
 mod connection;
+mod device_cache;
+mod docs;
 mod doctor;
+mod env_vars;
+mod error_kind;
+mod fanout;
+mod first_run;
 mod flags;
+mod history;
+mod humanize;
 mod install;
 mod mcp;
 mod native;
+mod notes;
 mod output;
 mod plugins;
+mod progress;
 mod read;
+mod redact;
+mod response_cache;
+mod selector;
+mod selftest;
 mod skills;
+mod suggest;
 #[cfg(test)]
 mod test_utils;
 mod upgrade;
+mod upload_fetch;
+mod url_parts;
 mod validation;
+mod verify_output;
 
 use serde_json::json;
 use sha2::{Digest, Sha256};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{exit, Command};
+use std::time::{Duration, Instant};
 
 #[cfg(windows)]
 use windows_sys::Win32::Foundation::CloseHandle;
 #[cfg(windows)]
 use windows_sys::Win32::System::Threading::OpenProcess;
 
-use commands::{gen_id, parse_command, ParseError};
+use atomic_write::write_atomic;
+use commands::{gen_id, parse_command};
 use connection::{
-    cleanup_stale_files, daemon_unreachable, ensure_daemon, get_socket_dir, is_pid_alive,
-    send_command, walk_daemons, DaemonOptions, Response,
+    auto_reconnect_disabled, cdp_target_from_launch, cleanup_stale_files, daemon_ready,
+    daemon_unreachable, ensure_daemon, get_socket_dir, is_pid_alive, load_cdp_target,
+    save_cdp_target, send_command, session_endpoint, should_auto_reconnect,
+    should_show_ignored_flags_warning, walk_daemons, ActiveSession, DaemonConnection,
+    DaemonOptions, DaemonResult, Response,
 };
 use flags::{clean_args, parse_flags, Flags};
 use install::run_install;
 use output::{
     print_command_help, print_help, print_response_with_opts, print_version, OutputOptions,
 };
+use progress::{ProgressEmitter, ProgressFormat, StepError};
+use redact::CommandRedactor;
 use upgrade::run_upgrade;
 
 fn serialize_json_value(value: &serde_json::Value) -> String {
@@ -97,10 +130,72 @@ fn attach_allowed_domains_to_launch_command(launch_cmd: &mut serde_json::Value,
     }
 }
 
+fn attach_provider_options_to_launch_command(
+    launch_cmd: &mut serde_json::Value,
+    options: &std::collections::BTreeMap<String, String>,
+) {
+    if !options.is_empty() {
+        launch_cmd["providerOptions"] = json!(options);
+    }
+}
+
+fn provider_launch_command(
+    provider: &str,
+    flags: &Flags,
+    provider_options: &std::collections::BTreeMap<String, String>,
+) -> serde_json::Value {
+    let mut launch_cmd = json!({
+        "id": gen_id(),
+        "action": "launch",
+        "provider": provider
+    });
+    launch_cmd["plugins"] = json!(flags.plugins.clone());
+    attach_provider_options_to_launch_command(&mut launch_cmd, provider_options);
+    attach_script_launch_options(&mut launch_cmd, flags);
+    attach_allowed_domains_to_launch_command(&mut launch_cmd, flags);
+    attach_restore_config_to_command(&mut launch_cmd, flags);
+
+    if let Some(ref cs) = flags.color_scheme {
+        launch_cmd["colorScheme"] = json!(cs);
+    }
+    launch_cmd
+}
+
+/// Options shared by every launch that attaches to an existing browser
+/// (`--auto-connect`, `--cdp`, and CDP auto-reconnect).
+fn attach_connect_launch_options(launch_cmd: &mut serde_json::Value, flags: &Flags) {
+    attach_script_launch_options(launch_cmd, flags);
+    attach_allowed_domains_to_launch_command(launch_cmd, flags);
+    attach_restore_config_to_command(launch_cmd, flags);
+
+    if flags.ignore_https_errors {
+        launch_cmd["ignoreHTTPSErrors"] = json!(true);
+    }
+
+    if let Some(ref cs) = flags.color_scheme {
+        launch_cmd["colorScheme"] = json!(cs);
+    }
+
+    if let Some(ref dp) = flags.download_path {
+        launch_cmd["downloadPath"] = json!(dp);
+    }
+}
+
 fn attach_plugins_to_command(cmd: &mut serde_json::Value, plugins: &[plugins::PluginConfig]) {
     cmd["plugins"] = json!(plugins);
 }
 
+/// `flags` with the session's `set timeout` default standing in for
+/// AGENT_BROWSER_DEFAULT_TIMEOUT, so wait commands carry the timeout the
+/// daemon was told to use and the socket read is sized to match it.
+fn flags_with_session_timeout(flags: &Flags) -> Flags {
+    let mut flags = flags.clone();
+    if let Some(ms) = connection::read_session_timeouts(&flags.session).default_timeout {
+        flags.default_timeout = Some(ms);
+    }
+    flags
+}
+
 fn restore_key_from_flags(flags: &Flags) -> Option<&str> {
     flags.restore.as_deref().or(flags.session_name.as_deref())
 }
@@ -122,6 +217,22 @@ fn incompatible_launch_mode_error(flags: &Flags) -> Option<&'static str> {
         return Some("Cannot use --auto-connect and -p/--provider together");
     }
 
+    // Both name the directory Chrome is launched with.
+    if flags.profile.is_some() && flags.user_data_dir.is_some() {
+        return Some("Cannot use --profile and --user-data-dir together");
+    }
+
+    // Every session would attach to the same browser and fight over it.
+    if flags.sessions.is_some() && flags.cdp.is_some() {
+        return Some("Cannot use --sessions with --cdp (each session needs its own browser)");
+    }
+
+    if flags.sessions.is_some() && flags.auto_connect {
+        return Some(
+            "Cannot use --sessions with --auto-connect (each session needs its own browser)",
+        );
+    }
+
     if flags.provider.is_some() && !flags.extensions.is_empty() {
         return Some(
             "Cannot use --extension with -p/--provider (extensions require local browser)",
@@ -155,14 +266,273 @@ fn incompatible_launch_mode_error(flags: &Flags) -> Option<&'static str> {
     None
 }
 
+/// Launch options that reach the daemon only through its startup environment:
+/// they are neither re-sent with each launch command nor part of the config
+/// fingerprint that restarts the daemon. The rest of the `cli_*` options are
+/// applied to a running daemon (or restart it), so they never appear here.
+type FlagPassed = fn(&Flags) -> bool;
+const STARTUP_ONLY_FLAGS: &[(&str, FlagPassed)] = &[
+    ("--device", |f| f.cli_device),
+    ("--ignore-https-origin", |f| f.cli_ignore_https_origins),
+];
+
+/// Options given on this command line that a daemon which was already
+/// running (and was not restarted) cannot pick up.
+fn ignored_launch_flags(flags: &Flags, daemon: &DaemonResult) -> Vec<&'static str> {
+    if !daemon.already_running || daemon.restarted {
+        return Vec::new();
+    }
+    STARTUP_ONLY_FLAGS
+        .iter()
+        .filter(|(_, passed)| passed(flags))
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+fn ignored_launch_flags_message(session: &str, ignored: &[&str]) -> String {
+    format!(
+        "{} ignored: the daemon for session '{}' is already running. Run `agent-browser --session {} close` first to apply {}.",
+        ignored.join(", "),
+        session,
+        session,
+        if ignored.len() == 1 { "it" } else { "them" }
+    )
+}
+
+/// Daemons older than `get box --viewport-relative` return the bare rect.
+/// Fetch the viewport size and scroll offsets with a follow-up eval so the
+/// derived fields still work against them.
+fn fill_box_viewport(cmd: &serde_json::Value, resp: &mut connection::Response, session: &str) {
+    let wants_geometry = ["viewportRelative", "visibility"]
+        .iter()
+        .any(|key| cmd.get(*key).and_then(|v| v.as_bool()) == Some(true));
+    if !wants_geometry || !resp.success {
+        return;
+    }
+    let Some(data) = resp.data.as_mut() else {
+        return;
+    };
+    if data.get("viewport").is_some() {
+        return;
+    }
+    let probe = json!({
+        "id": gen_id(),
+        "action": "evaluate",
+        "script": "({ width: innerWidth, height: innerHeight, scrollX, scrollY })",
+    });
+    if let Ok(probed) = send_command(probe, session) {
+        if let Some(viewport) = probed
+            .success
+            .then_some(probed.data)
+            .flatten()
+            .and_then(|d| d.get("result").cloned())
+        {
+            data["viewport"] = viewport;
+        }
+    }
+}
+
+/// The client-side post-processing every path (single command, `--sessions`,
+/// `batch`, `act`) applies to a daemon response before printing it. Returns
+/// the exit code a getter check (`get count --gt`, `get url --query`,
+/// `extension id`) asks for, if the command has one.
+fn postprocess_response(
+    cmd: &serde_json::Value,
+    resp: &mut connection::Response,
+    session: &str,
+    elapsed: Duration,
+) -> Option<i32> {
+    output::normalize_snapshot_ref_data(cmd, resp);
+    output::check_viewport_only_ack(cmd, resp);
+    fill_box_viewport(cmd, resp, session);
+    output::apply_box_geometry(cmd, resp);
+    output::apply_log_filters(cmd, resp);
+    output::apply_html_strip(cmd, resp);
+    output::apply_annotation_overlaps(cmd, resp);
+    output::apply_action_echo(cmd, resp, elapsed);
+    output::apply_error_kind(resp, connection::daemon_version(session).as_deref());
+    output::apply_count_comparison(cmd, resp)
+        .or_else(|| output::apply_extension_match(cmd, resp))
+        .or_else(|| output::apply_url_parts(cmd, resp))
+}
+
+/// `extension` inspects the extensions agent-browser loaded into a browser it
+/// launched, so it is rejected up front for attach modes.
+fn extension_command_error(flags: &Flags, cmd: &serde_json::Value) -> Option<&'static str> {
+    if cmd.get("action").and_then(|v| v.as_str()) != Some("extension_list") {
+        return None;
+    }
+    if flags.provider.is_some() {
+        return Some("Cannot use extension with -p/--provider (extensions require local browser)");
+    }
+    if flags.cdp.is_some() {
+        return Some("Cannot use extension with --cdp (extensions require local browser)");
+    }
+    if flags.auto_connect {
+        return Some("Cannot use extension with --auto-connect (extensions require local browser)");
+    }
+    None
+}
+
+/// Chrome refuses a user-data directory another browser holds, so a launch
+/// against a live profile fails. Warn up front rather than after the launch
+/// times out.
+fn user_data_dir_in_use_warning(flags: &Flags) -> Option<String> {
+    let dir = flags.user_data_dir.as_deref()?;
+    let lock = crate::native::cdp::chrome::user_data_dir_lock(Path::new(dir))?;
+    Some(format!(
+        "--user-data-dir {} appears to be in use ({} exists). Quit the browser using it first; Chrome will not start on a directory that is already open.",
+        dir,
+        lock.display()
+    ))
+}
+
+/// The launch command for a local browser when launch-time flags were given
+/// (see should_send_local_launch_config).
+fn local_launch_command(flags: &Flags) -> serde_json::Value {
+    let mut launch_cmd = json!({
+        "id": gen_id(),
+        "action": "launch",
+    });
+    // Only send headless when the user set it on this invocation. When
+    // absent, the daemon falls back to its spawn-time AGENT_BROWSER_HEADED
+    // env, so a follow-up command without --headed (common when env vars
+    // like AGENT_BROWSER_ARGS force a launch command on every call) does
+    // not flip a headed session back to headless and relaunch the browser
+    // onto about:blank.
+    if flags.headed || flags.cli_headed {
+        launch_cmd["headless"] = json!(!flags.headed);
+    }
+    launch_cmd["plugins"] = json!(flags.plugins.clone());
+    attach_restore_config_to_command(&mut launch_cmd, flags);
+
+    let cmd_obj = launch_cmd
+        .as_object_mut()
+        .expect("json! macro guarantees object type");
+
+    // Add executable path if specified
+    if let Some(ref exec_path) = flags.executable_path {
+        cmd_obj.insert("executablePath".to_string(), json!(exec_path));
+    }
+
+    // Add profile path if specified
+    if let Some(ref profile_path) = flags.profile {
+        cmd_obj.insert("profile".to_string(), json!(profile_path));
+    }
+
+    if let Some(ref dir) = flags.user_data_dir {
+        cmd_obj.insert("userDataDir".to_string(), json!(dir));
+    }
+
+    // Add state path if specified
+    if let Some(ref state_path) = flags.state {
+        cmd_obj.insert("storageState".to_string(), json!(state_path));
+        // Lets the daemon fail clearly when it was spawned without the key.
+        if native::state::detect_encrypted_state(std::path::Path::new(state_path)) {
+            cmd_obj.insert("encrypted".to_string(), json!(true));
+        }
+    }
+
+    if let Some(ref proxy_str) = flags.proxy {
+        let parsed = parse_proxy(proxy_str);
+        let mut proxy_obj = json!({ "server": parsed.server });
+        if let Some(ref username) = parsed.username {
+            proxy_obj["username"] = json!(username);
+        }
+        if let Some(ref password) = parsed.password {
+            proxy_obj["password"] = json!(password);
+        }
+        if let Some(ref bypass) = flags.proxy_bypass {
+            proxy_obj["bypass"] = json!(bypass);
+        }
+        cmd_obj.insert("proxy".to_string(), proxy_obj);
+    }
+
+    if let Some(ref ua) = flags.user_agent {
+        cmd_obj.insert("userAgent".to_string(), json!(ua));
+    }
+
+    // Parse args (comma or newline separated), then add the window flags
+    // as whole switches so their own commas survive.
+    let user_args: Vec<String> = flags
+        .args
+        .as_deref()
+        .unwrap_or_default()
+        .split(&[',', '\n'][..])
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    let (args_vec, warnings) =
+        flags::merge_window_args(user_args, flags.window_size, flags.window_position);
+    for warning in warnings {
+        eprintln!("{} {}", color::warning_indicator(), warning);
+    }
+    if flags.args.is_some() || !args_vec.is_empty() {
+        cmd_obj.insert("args".to_string(), json!(args_vec));
+    }
+
+    if !flags.extensions.is_empty() {
+        cmd_obj.insert("extensions".to_string(), json!(&flags.extensions));
+    }
+
+    if !flags.init_scripts.is_empty() {
+        cmd_obj.insert("initScripts".to_string(), json!(&flags.init_scripts));
+    }
+
+    if !flags.enable.is_empty() {
+        cmd_obj.insert("enable".to_string(), json!(&flags.enable));
+    }
+
+    if flags.ignore_https_errors {
+        launch_cmd["ignoreHTTPSErrors"] = json!(true);
+    }
+
+    if flags.allow_file_access {
+        launch_cmd["allowFileAccess"] = json!(true);
+    }
+
+    apply_hide_scrollbars_launch_option(
+        &mut launch_cmd,
+        flags.cli_hide_scrollbars,
+        flags.hide_scrollbars,
+    );
+
+    if flags.webgpu || flags.cli_webgpu {
+        launch_cmd["webgpu"] = json!(flags.webgpu);
+    }
+
+    // Env-only opt-out for automatic Xvfb; always stamped from the CLI's
+    // fresh environment so both setting and unsetting the var take effect
+    // on daemons spawned before the change.
+    launch_cmd["noXvfb"] = json!(flags.no_xvfb);
+
+    if let Some(ref cs) = flags.color_scheme {
+        launch_cmd["colorScheme"] = json!(cs);
+    }
+
+    if let Some(ref dp) = flags.download_path {
+        launch_cmd["downloadPath"] = json!(dp);
+    }
+
+    attach_allowed_domains_to_launch_command(&mut launch_cmd, flags);
+
+    if let Some(ref engine) = flags.engine {
+        launch_cmd["engine"] = json!(engine);
+    }
+    launch_cmd
+}
+
 fn should_send_local_launch_config(flags: &Flags) -> bool {
     (flags.headed
         || flags.cli_headed
         || flags.executable_path.is_some()
         || flags.profile.is_some()
+        || flags.user_data_dir.is_some()
         || flags.state.is_some()
         || flags.proxy.is_some()
         || flags.args.is_some()
+        || flags.window_size.is_some()
+        || flags.window_position.is_some()
         || flags.user_agent.is_some()
         || flags.allow_file_access
         || should_send_hide_scrollbars_launch_option(
@@ -274,61 +644,194 @@ fn confirmation_prompt_from_response(resp: &Response) -> Option<ConfirmationProm
     resp.data.as_ref().and_then(confirmation_prompt_from_data)
 }
 
-fn run_interactive_confirmations(
+/// How a `confirmation_required` response is handled without a human in the
+/// loop (`--on-confirmation`), or with one (`--confirm-interactive`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfirmationPolicy {
+    Prompt,
+    Allow,
+    Deny,
+    Fail,
+}
+
+impl ConfirmationPolicy {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "allow" => Some(Self::Allow),
+            "deny" => Some(Self::Deny),
+            "fail" => Some(Self::Fail),
+            _ => None,
+        }
+    }
+
+    fn from_flags(flags: &Flags) -> Self {
+        if flags.confirm_interactive {
+            return Self::Prompt;
+        }
+        flags
+            .on_confirmation
+            .as_deref()
+            .and_then(Self::parse)
+            .unwrap_or(Self::Fail)
+    }
+}
+
+fn confirmation_details(prompt: &ConfirmationPrompt, code: &str) -> serde_json::Value {
+    json!({
+        "code": code,
+        "confirmation_required": true,
+        "confirmation_id": prompt.confirmation_id,
+        "action": prompt.action,
+        "category": prompt.category,
+        "description": prompt.description,
+    })
+}
+
+/// Resolve pending confirmations according to `policy`. Confirm/deny commands
+/// go through `send`; `ask` is only consulted for the interactive policy.
+/// Denied or unresolved confirmations come back as failed responses carrying
+/// the confirmation details, so callers can treat them like any other error.
+fn apply_confirmation_policy(
     mut resp: Response,
-    flags: &Flags,
-    output_opts: &OutputOptions,
+    policy: ConfirmationPolicy,
+    mut send: impl FnMut(serde_json::Value) -> Result<Response, String>,
+    mut ask: impl FnMut(&ConfirmationPrompt) -> bool,
 ) -> Response {
     while let Some(prompt) = confirmation_prompt_from_response(&resp) {
-        eprintln!("[agent-browser] Action requires confirmation:");
-        if prompt.category.is_empty() {
-            eprintln!("  {}", prompt.description);
-        } else {
-            eprintln!("  {}: {}", prompt.category, prompt.description);
-        }
-        eprint!("  Allow? [y/N]: ");
-
-        let mut input = String::new();
-        let approved = if std::io::IsTerminal::is_terminal(&std::io::stdin()) {
-            std::io::stdin().read_line(&mut input).is_ok()
-                && matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
-        } else {
-            false
+        let approved = match policy {
+            ConfirmationPolicy::Fail => {
+                return Response {
+                    success: false,
+                    data: Some(confirmation_details(&prompt, "E_CONFIRMATION_REQUIRED")),
+                    error: Some(format!(
+                        "Confirmation required: {}. Run `agent-browser confirm {}` or `agent-browser deny {}`",
+                        prompt.description, prompt.confirmation_id, prompt.confirmation_id
+                    )),
+                    warning: resp.warning,
+                    ..Default::default()
+                };
+            }
+            ConfirmationPolicy::Allow => true,
+            ConfirmationPolicy::Deny => false,
+            ConfirmationPolicy::Prompt => ask(&prompt),
         };
 
-        let confirm_cmd = if approved {
-            json!({
-                "id": gen_id(),
-                "action": "confirm",
-                "confirmationId": prompt.confirmation_id
-            })
-        } else {
-            json!({
-                "id": gen_id(),
-                "action": "deny",
-                "confirmationId": prompt.confirmation_id
-            })
-        };
+        let confirm_cmd = json!({
+            "id": gen_id(),
+            "action": if approved { "confirm" } else { "deny" },
+            "confirmationId": prompt.confirmation_id
+        });
 
-        match send_command(confirm_cmd, &flags.session) {
+        match send(confirm_cmd) {
             Ok(next_resp) => {
                 if !approved {
-                    eprintln!("{} Action denied", color::error_indicator());
-                    exit(1);
+                    return Response {
+                        success: false,
+                        data: Some(confirmation_details(&prompt, "E_CONFIRMATION_DENIED")),
+                        error: Some(format!("Action denied: {}", prompt.description)),
+                        warning: next_resp.warning,
+                        ..Default::default()
+                    };
                 }
                 resp = next_resp;
             }
             Err(e) => {
-                eprintln!("{} {}", color::error_indicator(), e);
-                exit(1);
+                return Response {
+                    success: false,
+                    data: None,
+                    error: Some(e),
+                    warning: None,
+                    ..Default::default()
+                };
             }
         }
     }
-
-    print_response_with_opts(&resp, None, output_opts);
     resp
 }
 
+fn ask_confirmation_on_tty(prompt: &ConfirmationPrompt) -> bool {
+    eprintln!("[agent-browser] Action requires confirmation:");
+    if prompt.category.is_empty() {
+        eprintln!("  {}", prompt.description);
+    } else {
+        eprintln!("  {}: {}", prompt.category, prompt.description);
+    }
+    eprint!("  Allow? [y/N]: ");
+
+    let mut input = String::new();
+    std::io::IsTerminal::is_terminal(&std::io::stdin())
+        && std::io::stdin().read_line(&mut input).is_ok()
+        && matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn ask_install_on_tty() -> bool {
+    eprint!("  Run `agent-browser install` now and retry? [y/N]: ");
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input).is_ok()
+        && matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Send a command through `send`. When it fails because no browser is
+/// installed, say how to install one; on an interactive terminal, offer to
+/// run `agent-browser install` and retry the command once.
+fn send_with_install_offer(
+    flags: &Flags,
+    mut send: impl FnMut() -> Result<Response, String>,
+) -> Result<Response, String> {
+    use first_run::InstallDecision;
+
+    let interactive = !flags.json && std::io::IsTerminal::is_terminal(&std::io::stdin());
+    let mut retry = first_run::InstallRetry::default();
+    loop {
+        let result = send();
+        let error = match &result {
+            Ok(resp) if !resp.success => resp.error.as_deref().map(|message| {
+                let code = resp
+                    .data
+                    .as_ref()
+                    .and_then(|d| d.get("code"))
+                    .and_then(|c| c.as_str());
+                (message, code)
+            }),
+            Ok(_) => None,
+            Err(e) => Some((e.as_str(), None)),
+        };
+        match retry.decide(error, interactive, flags.no_install_prompt) {
+            InstallDecision::Proceed => return result,
+            InstallDecision::Report(browser) => {
+                if !flags.json {
+                    eprintln!("{} {}", color::warning_indicator(), browser.hint());
+                }
+                return result;
+            }
+            InstallDecision::OfferInstall(browser) => {
+                eprintln!("{} {}", color::warning_indicator(), browser.hint());
+                if !ask_install_on_tty() {
+                    return result;
+                }
+                run_install(false);
+            }
+        }
+    }
+}
+
+/// Apply the session's confirmation policy to a daemon response, talking to
+/// the daemon for confirm/deny as needed.
+fn resolve_confirmations(resp: Response, flags: &Flags) -> Response {
+    if confirmation_prompt_from_response(&resp).is_none() {
+        return resp;
+    }
+    apply_confirmation_policy(
+        resp,
+        ConfirmationPolicy::from_flags(flags),
+        |cmd| {
+            response_cache::invalidate_for(&flags.session, &cmd);
+            send_command(cmd, &flags.session)
+        },
+        ask_confirmation_on_tty,
+    )
+}
+
 struct ParsedProxy {
     server: String,
     username: Option<String>,
@@ -450,6 +953,141 @@ fn run_profiles(json_mode: bool) {
     }
 }
 
+/// Check a `--cdp` URL and exit with a classified error if it is unusable.
+fn run_cdp_preflight(cdp: &str, json_mode: bool) {
+    use crate::native::cdp::preflight::{preflight_cdp_url, PREFLIGHT_TIMEOUT};
+
+    let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+    match rt.block_on(preflight_cdp_url(cdp, PREFLIGHT_TIMEOUT)) {
+        Ok(Some(version)) => {
+            if !json_mode {
+                eprintln!(
+                    "{} CDP endpoint reachable: {}",
+                    color::success_indicator(),
+                    version.summary()
+                );
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            if json_mode {
+                print_json_error(e.to_string());
+            } else {
+                eprintln!("{} {}", color::error_indicator(), e);
+            }
+            exit(1);
+        }
+    }
+}
+
+/// Whether `connect` was given scan options instead of a port or URL.
+fn is_connect_scan(clean: &[String]) -> bool {
+    clean.first().map(|s| s.as_str()) == Some("connect")
+        && clean[1..]
+            .iter()
+            .any(|a| matches!(a.as_str(), "--list" | "--match" | "--port"))
+}
+
+/// Handle `connect --list`, `connect --match <title>` and `connect --port <p>`.
+/// Returns the chosen port so the caller can issue the normal `connect <port>`
+/// launch, or `None` once `--list` has printed the candidates.
+fn run_connect_scan(clean: &[String], json_mode: bool) -> Option<u16> {
+    use crate::native::cdp::scan::{scan, scan_ports, select_candidate, PROBE_TIMEOUT};
+
+    const USAGE: &str = "connect --list | connect [--match <title>] [--port <port>]";
+    let fail = |message: String, error_type: Option<&str>| -> ! {
+        match (json_mode, error_type) {
+            (true, Some(t)) => print_json_error_with_type(message, t),
+            (true, None) => print_json_error(message),
+            (false, _) => eprintln!("{} {}", color::error_indicator(), message),
+        }
+        exit(1);
+    };
+
+    let mut list = false;
+    let mut title: Option<&str> = None;
+    let mut port: Option<u16> = None;
+    let mut i = 1;
+    while i < clean.len() {
+        match clean[i].as_str() {
+            "--list" => list = true,
+            "--match" => {
+                i += 1;
+                match clean.get(i) {
+                    Some(t) if !t.is_empty() => title = Some(t),
+                    _ => fail(
+                        format!("Missing title for --match\nUsage: agent-browser {}", USAGE),
+                        Some("missing_arguments"),
+                    ),
+                }
+            }
+            "--port" => {
+                i += 1;
+                match clean.get(i).map(|p| p.parse::<u16>()) {
+                    Some(Ok(p)) if p > 0 => port = Some(p),
+                    _ => fail(
+                        format!(
+                            "Invalid value for --port: expected 1-65535\nUsage: agent-browser {}",
+                            USAGE
+                        ),
+                        Some("invalid_value"),
+                    ),
+                }
+            }
+            other => fail(
+                format!(
+                    "Unexpected argument '{}'\nUsage: agent-browser {}",
+                    other, USAGE
+                ),
+                Some("invalid_value"),
+            ),
+        }
+        i += 1;
+    }
+
+    let env_ports = env::var("AGENT_BROWSER_SCAN_PORTS").ok();
+    let mut ports =
+        scan_ports(env_ports.as_deref()).unwrap_or_else(|e| fail(e, Some("invalid_value")));
+    if let Some(p) = port {
+        if !ports.contains(&p) {
+            ports.push(p);
+        }
+    }
+    let candidates = scan(&ports, PROBE_TIMEOUT);
+
+    if list {
+        if json_mode {
+            print_json_value(json!({
+                "success": true,
+                "data": {
+                    "candidates": candidates.iter().map(|c| c.to_json()).collect::<Vec<_>>()
+                }
+            }));
+        } else if candidates.is_empty() {
+            println!("No browsers with remote debugging found");
+        } else {
+            for c in &candidates {
+                let title = match c.first_title() {
+                    "" => "(no open tabs)",
+                    t => t,
+                };
+                println!(
+                    "  {}  {}  {}",
+                    color::bold(&c.port.to_string()),
+                    c.browser,
+                    color::dim(title)
+                );
+            }
+        }
+        return None;
+    }
+
+    match select_candidate(&candidates, title, port) {
+        Ok(c) => Some(c.port),
+        Err(e) => fail(e, None),
+    }
+}
+
 fn canonical_path(path: PathBuf) -> PathBuf {
     path.canonicalize().unwrap_or(path)
 }
@@ -632,6 +1270,43 @@ fn run_session_info(session: &str, json_mode: bool) {
     }
 }
 
+/// One `session list --json` entry. `responding` is whether the daemon
+/// accepted a connection just now.
+fn session_list_entry(
+    active: &ActiveSession,
+    current: &str,
+    responding: bool,
+) -> serde_json::Value {
+    let mut entry = json!({
+        "name": active.name,
+        "pid": active.pid,
+        "current": active.name == current,
+        "responding": responding,
+    });
+    if let serde_json::Value::Object(map) = &mut entry {
+        map.extend(session_endpoint(&active.name));
+    }
+    entry
+}
+
+/// `session --json` data for the current session, running or not.
+fn current_session_data(
+    session: &str,
+    socket_dir: &str,
+    active: Option<&ActiveSession>,
+) -> serde_json::Value {
+    let mut data = json!({
+        "session": session,
+        "socketDir": socket_dir,
+        "running": active.is_some(),
+        "pid": active.map(|s| s.pid),
+    });
+    if let serde_json::Value::Object(map) = &mut data {
+        map.extend(session_endpoint(session));
+    }
+    data
+}
+
 fn run_session(args: &[String], session: &str, json_mode: bool) {
     let subcommand = args.get(1).map(|s| s.as_str());
 
@@ -639,39 +1314,45 @@ fn run_session(args: &[String], session: &str, json_mode: bool) {
         Some("id") => run_session_id(args, json_mode),
         Some("info") => run_session_info(session, json_mode),
         Some("list") => {
-            let sessions: Vec<String> = walk_daemons()
-                .sessions
-                .into_iter()
-                .map(|s| s.name)
-                .collect();
+            let sessions = walk_daemons().sessions;
 
             if json_mode {
-                println!(
-                    r#"{{"success":true,"data":{{"sessions":{}}}}}"#,
-                    serde_json::to_string(&sessions).unwrap_or_default()
-                );
+                let entries: Vec<serde_json::Value> = sessions
+                    .iter()
+                    .map(|s| session_list_entry(s, session, daemon_ready(&s.name)))
+                    .collect();
+                print_json_value(json!({
+                    "success": true,
+                    "data": { "sessions": entries },
+                }));
             } else if sessions.is_empty() {
                 println!("No active sessions");
             } else {
                 println!("Active sessions:");
                 for s in &sessions {
-                    let marker = if s == session {
-                        color::cyan("→")
+                    let marker = if s.name == session {
+                        color::current_marker().to_string()
                     } else {
                         " ".to_string()
                     };
-                    println!("{} {}", marker, s);
+                    println!("{} {}", marker, s.name);
                 }
             }
         }
         None | Some(_) => {
             // Just show current session
             if json_mode {
+                let active = walk_daemons()
+                    .sessions
+                    .into_iter()
+                    .find(|s| s.name == session);
                 print_json_value(json!({
                     "success": true,
-                    "data": {
-                        "session": session,
-                    },
+                    "data": current_session_data(
+                        session,
+                        &get_socket_dir().to_string_lossy(),
+                        active.as_ref(),
+                    ),
                 }));
             } else {
                 println!("{}", session);
@@ -757,7 +1438,7 @@ fn run_dashboard_start(port: u16, json_mode: bool) {
     {
         Ok(child) => {
             let pid = child.id();
-            let _ = fs::write(&pid_path, pid.to_string());
+            let _ = write_atomic(&pid_path, pid.to_string());
 
             if json_mode {
                 print_json_value(json!({
@@ -837,7 +1518,7 @@ fn run_dashboard_stop(json_mode: bool) {
     if json_mode {
         print_json_value(json!({ "success": true, "data": { "stopped": true } }));
     } else {
-        println!("{} Dashboard stopped", color::green("✓"));
+        println!("{} Dashboard stopped", color::success_indicator());
     }
 }
 
@@ -870,7 +1551,10 @@ fn run_close_all(flags: &Flags) {
     for (session, pid) in &sessions {
         let cmd = json!({ "id": gen_id(), "action": "close" });
         match send_command(cmd, session) {
-            Ok(resp) if resp.success => closed.push(session.clone()),
+            Ok(resp) if resp.success => {
+                notes::remove(session);
+                closed.push(session.clone());
+            }
             Ok(resp) => {
                 let err = resp.error.unwrap_or_else(|| "Unknown error".to_string());
                 failed.push((session.clone(), err));
@@ -892,6 +1576,7 @@ fn run_close_all(flags: &Flags) {
                     }
                 }
                 cleanup_stale_files(session);
+                notes::remove(session);
                 closed.push(session.clone());
             }
         }
@@ -908,7 +1593,7 @@ fn run_close_all(flags: &Flags) {
         }));
     } else {
         for s in &closed {
-            println!("{} Closed session: {}", color::green("✓"), s);
+            println!("{} Closed session: {}", color::success_indicator(), s);
         }
         for (s, e) in &failed {
             eprintln!("{} Failed to close {}: {}", color::error_indicator(), s, e);
@@ -924,12 +1609,10 @@ fn run_close_all(flags: &Flags) {
 }
 
 fn main() {
-    // Rust ignores SIGPIPE by default, causing println! to panic on broken pipes.
-    // Reset to SIG_DFL so the OS terminates the process cleanly instead.
-    #[cfg(unix)]
-    unsafe {
-        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
-    }
+    // Rust leaves SIGPIPE ignored, so a closed stdout (`snapshot | head -5`)
+    // surfaces as a write error. output.rs handles it and exits 0; the hook
+    // covers println! elsewhere, which would otherwise panic.
+    output::install_closed_stdout_hook();
 
     // Prevent MSYS/Git Bash path translation from mangling arguments
     #[cfg(windows)]
@@ -952,6 +1635,10 @@ fn main() {
         return;
     }
 
+    // Windows consoles need ANSI processing and UTF-8 output switched on;
+    // color.rs falls back to plain ASCII when that fails.
+    color::init_console();
+
     // Standalone dashboard server mode
     if env::var("AGENT_BROWSER_DASHBOARD").is_ok() {
         let port: u16 = env::var("AGENT_BROWSER_DASHBOARD_PORT")
@@ -965,13 +1652,33 @@ fn main() {
 
     let args: Vec<String> = env::args().skip(1).collect();
     let mut flags = parse_flags(&args);
+    if flags.debug && !flags.session_profile.is_empty() {
+        eprintln!(
+            "{}",
+            color::dim(&format!(
+                "[config] session '{}' profile applied: {}",
+                flags.session,
+                flags.session_profile.join(", ")
+            ))
+        );
+    }
     if flags.restore_uses_session {
         flags.restore = Some(flags.session.clone());
     }
     if let Some(ref namespace) = flags.namespace {
         env::set_var("AGENT_BROWSER_NAMESPACE", namespace);
     }
-    let clean = clean_args(&args);
+    let mut clean = match aliases::expand(&clean_args(&args), &flags.aliases) {
+        Ok(clean) => clean,
+        Err(e) => {
+            if flags.json {
+                print_json_error(&e);
+            } else {
+                eprintln!("{} {}", color::error_indicator(), e);
+            }
+            exit(1);
+        }
+    };
 
     let has_help = args.iter().any(|a| a == "--help" || a == "-h");
     let has_version = args.iter().any(|a| a == "--version" || a == "-V");
@@ -991,9 +1698,27 @@ fn main() {
         return;
     }
 
-    if clean.is_empty() {
-        print_help();
-        return;
+    // `last [offset]` runs a command from the session history again.
+    if clean.first().map(|s| s.as_str()) == Some("last") {
+        clean = match history::resolve_last(&clean, &flags.session) {
+            Ok(words) => words,
+            Err(e) => {
+                if flags.json {
+                    print_json_error(&e);
+                } else {
+                    eprintln!("{} {}", color::error_indicator(), e);
+                }
+                exit(1);
+            }
+        };
+        if !flags.json {
+            eprintln!("{}", color::dim(&clean.join(" ")));
+        }
+    }
+
+    if clean.is_empty() {
+        print_help();
+        return;
     }
 
     // Handle install separately
@@ -1029,6 +1754,12 @@ fn main() {
         exit(doctor::run_doctor(opts));
     }
 
+    // Handle selftest separately (spawns its own scratch session)
+    if clean.first().map(|s| s.as_str()) == Some("selftest") {
+        selftest::run_selftest(&clean, &flags);
+        return;
+    }
+
     // Handle dashboard subcommand
     if clean.first().map(|s| s.as_str()) == Some("dashboard") {
         match clean.get(1).map(|s| s.as_str()) {
@@ -1057,12 +1788,54 @@ fn main() {
         }
     }
 
+    // Handle alias command (doesn't need daemon)
+    if clean.first().map(|s| s.as_str()) == Some("alias") {
+        aliases::run_alias(&clean, &flags.aliases, flags.json);
+        return;
+    }
+
+    // Handle note command (doesn't need daemon)
+    if clean.first().map(|s| s.as_str()) == Some("note") {
+        notes::run_note(&clean, &flags.session, flags.json);
+        return;
+    }
+
+    // Handle history command (doesn't need daemon)
+    if clean.first().map(|s| s.as_str()) == Some("history") {
+        history::run_history(&clean, &flags.session, flags.json);
+        return;
+    }
+
+    // Handle verify-output command (doesn't need daemon)
+    if clean.first().map(|s| s.as_str()) == Some("verify-output") {
+        verify_output::run_verify_output(&clean, flags.json);
+        return;
+    }
+
     // Handle profiles command (doesn't need daemon)
     if clean.first().map(|s| s.as_str()) == Some("profiles") {
         run_profiles(flags.json);
         return;
     }
 
+    // Handle bugreport command (talks to the daemon only if one is running)
+    if clean.first().map(|s| s.as_str()) == Some("bugreport") {
+        bugreport::run_bugreport(&clean, &args, &flags.session, flags.json);
+        return;
+    }
+
+    // Handle env command (doesn't need daemon)
+    if clean.first().map(|s| s.as_str()) == Some("env") {
+        env_vars::run_env(&clean, flags.json);
+        return;
+    }
+
+    // Handle docs command (doesn't need daemon)
+    if clean.first().map(|s| s.as_str()) == Some("docs") {
+        docs::run_docs(&clean, flags.json);
+        return;
+    }
+
     // Handle skills command (doesn't need daemon)
     if clean.first().map(|s| s.as_str()) == Some("skills") {
         skills::run_skills(&clean, flags.json);
@@ -1099,6 +1872,7 @@ fn main() {
         clean.first().map(|s| s.as_str()),
         Some("close") | Some("quit") | Some("exit")
     ) && clean.iter().any(|a| a == "--all")
+        && !clean.iter().any(|a| a == "--save-state")
     {
         run_close_all(&flags);
         return;
@@ -1115,18 +1889,29 @@ fn main() {
         return;
     }
 
-    let mut cmd = match parse_command(&clean, &flags) {
+    // Handle connect scan modes: --list prints and exits, --match/--port
+    // resolve to a port and fall through to the normal `connect <port>`.
+    if is_connect_scan(&clean) {
+        match run_connect_scan(&clean, flags.json) {
+            Some(port) => clean = vec!["connect".to_string(), port.to_string()],
+            None => return,
+        }
+    }
+
+    let mut cmd = match parse_command(&clean, &flags_with_session_timeout(&flags)) {
         Ok(c) => c,
         Err(e) => {
             if flags.json {
-                let error_type = match &e {
-                    ParseError::UnknownCommand { .. } => "unknown_command",
-                    ParseError::UnknownSubcommand { .. } => "unknown_subcommand",
-                    ParseError::MissingArguments { .. } => "missing_arguments",
-                    ParseError::InvalidValue { .. } => "invalid_value",
-                    ParseError::InvalidSessionName { .. } => "invalid_session_name",
-                };
-                print_json_error_with_type(e.format(), error_type);
+                let mut error = json!({
+                    "success": false,
+                    "error": e.format(),
+                    "type": e.error_type(),
+                });
+                let suggestions = e.suggestions();
+                if !suggestions.is_empty() {
+                    error["suggestions"] = json!(suggestions);
+                }
+                print_json_value(error);
             } else {
                 eprintln!("{}", color::red(&e.format()));
             }
@@ -1134,9 +1919,26 @@ fn main() {
         }
     };
 
-    // Handle --password-stdin for auth save
-    if cmd.get("action").and_then(|v| v.as_str()) == Some("auth_save") {
-        if cmd.get("password").is_some() {
+    if !flags.no_history {
+        let secrets = CommandRedactor::new(flags.redact_patterns.as_deref()).secrets(&cmd);
+        let _ = history::record(
+            &history::history_path(&flags.session),
+            &secrets.words(&clean),
+            chrono::Utc::now().timestamp_millis(),
+        );
+    }
+
+    let action = cmd.get("action").and_then(|v| v.as_str());
+    if action == Some("credentials") && cmd.get("clear").is_none() && cmd.get("origin").is_none() {
+        eprintln!(
+            "{} Credentials without --origin are sent to every site this session visits. Pass --origin <origin> to scope them.",
+            color::warning_indicator()
+        );
+    }
+
+    // Handle --password-stdin for auth save and set credentials
+    if matches!(action, Some("auth_save" | "credentials")) {
+        if action == Some("auth_save") && cmd.get("password").is_some() {
             eprintln!(
                 "{} Passwords on the command line may be visible in process listings and shell history. Use --password-stdin instead.",
                 color::warning_indicator()
@@ -1165,6 +1967,12 @@ fn main() {
         }
     }
 
+    // `connect <port|url>` sends the same launch as `--cdp`, including the
+    // launch options that apply to a connected browser.
+    if cdp_target_from_launch(&cmd).is_some() {
+        attach_connect_launch_options(&mut cmd, &flags);
+    }
+
     // Send plugin config with commands so an already-running daemon can use
     // current config without a restart. The daemon strips this from stream
     // broadcasts before observers see the command payload.
@@ -1186,6 +1994,49 @@ fn main() {
         }
     }
 
+    if let Some(ref raw) = flags.headers {
+        if let Err(msg) = flags::parse_headers_json(raw) {
+            if flags.json {
+                print_json_error_with_type(msg, "invalid_value");
+            } else {
+                eprintln!("{} {}", color::error_indicator(), msg);
+            }
+            exit(1);
+        }
+    }
+
+    let provider_options = match flags::resolve_provider_options(
+        env::var("AGENT_BROWSER_PROVIDER_OPTS").ok().as_deref(),
+        &flags.provider_opts,
+    ) {
+        Ok(options) => options,
+        Err(msg) => {
+            if flags.json {
+                print_json_error_with_type(msg, "invalid_value");
+            } else {
+                eprintln!("{} {}", color::error_indicator(), msg);
+            }
+            exit(1);
+        }
+    };
+    let provider_options_json =
+        (!provider_options.is_empty()).then(|| json!(provider_options).to_string());
+
+    if let Some(ref policy) = flags.on_confirmation {
+        if ConfirmationPolicy::parse(policy).is_none() {
+            let msg = format!(
+                "Invalid --on-confirmation value '{}'. Use allow, deny, or fail.",
+                policy
+            );
+            if flags.json {
+                print_json_error_with_type(msg, "invalid_value");
+            } else {
+                eprintln!("{} {}", color::error_indicator(), msg);
+            }
+            exit(1);
+        }
+    }
+
     if let Some(ref policy) = flags.restore_save {
         if !is_valid_restore_save_policy(policy) {
             let msg = format!(
@@ -1212,12 +2063,14 @@ fn main() {
                 data: Some(data),
                 error: None,
                 warning: None,
+                ..Default::default()
             },
             Err(e) => connection::Response {
                 success: false,
                 data: None,
                 error: Some(e),
                 warning: None,
+                ..Default::default()
             },
         };
         let output_opts = OutputOptions::from_flags(&flags);
@@ -1228,7 +2081,9 @@ fn main() {
         return;
     }
 
-    if let Some(msg) = incompatible_launch_mode_error(&flags) {
+    if let Some(msg) =
+        incompatible_launch_mode_error(&flags).or_else(|| extension_command_error(&flags, &cmd))
+    {
         if flags.json {
             print_json_error(msg);
         } else {
@@ -1237,6 +2092,23 @@ fn main() {
         exit(1);
     }
 
+    // Path options only matter when this invocation launches the daemon.
+    let launching_daemon = !daemon_ready(&flags.session);
+    let flag_check = if launching_daemon {
+        flags::validate_path_flags(&flags)
+    } else {
+        Ok(())
+    }
+    .and_then(|()| flags::validate_flag_values(&flags));
+    if let Err(msg) = flag_check {
+        if flags.json {
+            print_json_error(&msg);
+        } else {
+            eprintln!("{} {}", color::error_indicator(), msg);
+        }
+        exit(1);
+    }
+
     // Parse proxy URL to separate server from credentials for the daemon.
     let (proxy_server, proxy_username, proxy_password) = if let Some(ref proxy_str) = flags.proxy {
         let parsed = parse_proxy(proxy_str);
@@ -1260,12 +2132,15 @@ fn main() {
         proxy_username: proxy_username.as_deref(),
         proxy_password: proxy_password.as_deref(),
         ignore_https_errors: flags.ignore_https_errors,
+        ignore_https_origins: &flags.ignore_https_origins,
         allow_file_access: flags.allow_file_access,
         hide_scrollbars: flags.hide_scrollbars,
         webgpu: flags.webgpu,
         profile: flags.profile.as_deref(),
+        user_data_dir: flags.user_data_dir.as_deref(),
         state: flags.state.as_deref(),
         provider: flags.provider.as_deref(),
+        provider_options: provider_options_json.as_deref(),
         device: flags.device.as_deref(),
         session_name: restore_key,
         restore_save: flags.restore_save.as_deref(),
@@ -1285,6 +2160,48 @@ fn main() {
         plugins: Some(plugin_registry_json.as_str()),
     };
 
+    // `device use|boot <name>`: catch typos against the last `device list`.
+    if let Err(e) = device_cache::check_command(&cmd, &flags.session) {
+        if flags.json {
+            print_json_error(e);
+        } else {
+            eprintln!("{} {}", color::error_indicator(), e);
+        }
+        exit(1);
+    }
+
+    // `upload <url>`: the daemon only takes local paths, so fetch first. The
+    // temp files are dropped as soon as the daemon has answered.
+    let fetched_uploads = match upload_fetch::fetch_remote_uploads(&mut cmd, flags.json) {
+        Ok(fetched) => fetched,
+        Err(e) => {
+            if flags.json {
+                print_json_error(e);
+            } else {
+                eprintln!("{} {}", color::error_indicator(), e);
+            }
+            exit(1);
+        }
+    };
+
+    if let Some(ref sessions) = flags.sessions {
+        let exit_code = run_sessions(sessions, &cmd, &flags, &daemon_opts, &provider_options);
+        drop(fetched_uploads);
+        if let Some(code) = exit_code {
+            exit(code);
+        }
+        return;
+    }
+
+    // Probe a --cdp URL before the daemon exists so an expired or unreachable
+    // endpoint fails fast with a specific reason. Once the daemon is up it
+    // already holds the connection, so later commands skip the probe.
+    if let Some(ref cdp_value) = flags.cdp {
+        if cdp_value.contains("://") && !daemon_ready(&flags.session) {
+            run_cdp_preflight(cdp_value, flags.json);
+        }
+    }
+
     let daemon_result = match ensure_daemon(&flags.session, &daemon_opts) {
         Ok(result) => result,
         Err(e) => {
@@ -1296,9 +2213,44 @@ fn main() {
             exit(1);
         }
     };
-    let _daemon_was_already_running = daemon_result.already_running;
     let daemon_restarted = daemon_result.restarted;
 
+    let ignored_flags = ignored_launch_flags(&flags, &daemon_result);
+    let mut launch_warnings = Vec::new();
+    if let Some(msg) = launching_daemon
+        .then(|| user_data_dir_in_use_warning(&flags))
+        .flatten()
+    {
+        if flags.json {
+            launch_warnings.push(msg);
+        } else {
+            eprintln!("{} {}", color::warning_indicator(), msg);
+        }
+    }
+    if !ignored_flags.is_empty() {
+        let msg = ignored_launch_flags_message(&flags.session, &ignored_flags);
+        if flags.require_fresh_daemon {
+            let msg = format!("--require-fresh-daemon: {}", msg);
+            if flags.json {
+                print_json_error(msg);
+            } else {
+                eprintln!("{} {}", color::error_indicator(), msg);
+            }
+            exit(1);
+        }
+        if flags.json {
+            launch_warnings.push(msg);
+        } else if flags.debug
+            || should_show_ignored_flags_warning(
+                &flags.session,
+                &ignored_flags,
+                flags.flag_warning_window_ms,
+            )
+        {
+            eprintln!("{} {}", color::warning_indicator(), msg);
+        }
+    }
+
     // Auto-connect to existing browser. This is sent even when the daemon is
     // already running so launch compatibility stays idempotent.
     if flags.auto_connect {
@@ -1307,21 +2259,7 @@ fn main() {
             "action": "launch",
             "autoConnect": true
         });
-        attach_script_launch_options(&mut launch_cmd, &flags);
-        attach_allowed_domains_to_launch_command(&mut launch_cmd, &flags);
-        attach_restore_config_to_command(&mut launch_cmd, &flags);
-
-        if flags.ignore_https_errors {
-            launch_cmd["ignoreHTTPSErrors"] = json!(true);
-        }
-
-        if let Some(ref cs) = flags.color_scheme {
-            launch_cmd["colorScheme"] = json!(cs);
-        }
-
-        if let Some(ref dp) = flags.download_path {
-            launch_cmd["downloadPath"] = json!(dp);
-        }
+        attach_connect_launch_options(&mut launch_cmd, &flags);
 
         let err = match send_command(launch_cmd, &flags.session) {
             Ok(resp) if resp.success => None,
@@ -1347,80 +2285,22 @@ fn main() {
     if let Some(ref cdp_value) = flags.cdp {
         // Validate CDP value eagerly (even when daemon is already running) so
         // the user gets an immediate error for bad input instead of a silent no-op.
-        let launch_cmd = if cdp_value.starts_with("ws://")
-            || cdp_value.starts_with("wss://")
-            || cdp_value.starts_with("http://")
-            || cdp_value.starts_with("https://")
-        {
-            // It's a URL - use cdpUrl field
-            json!({
-                "id": gen_id(),
-                "action": "launch",
-                "cdpUrl": cdp_value
-            })
-        } else {
-            // It's a port number - validate and use cdpPort field
-            let cdp_port: u16 = match cdp_value.parse::<u32>() {
-                Ok(0) => {
-                    let msg = "Invalid CDP port: port must be greater than 0".to_string();
-                    if flags.json {
-                        print_json_error(&msg);
-                    } else {
-                        eprintln!("{} {}", color::error_indicator(), msg);
-                    }
-                    exit(1);
-                }
-                Ok(p) if p > 65535 => {
-                    let msg = format!(
-                        "Invalid CDP port: {} is out of range (valid range: 1-65535)",
-                        p
-                    );
-                    if flags.json {
-                        print_json_error(&msg);
-                    } else {
-                        eprintln!("{} {}", color::error_indicator(), msg);
-                    }
-                    exit(1);
-                }
-                Ok(p) => p as u16,
-                Err(_) => {
-                    let msg = format!(
-                        "Invalid CDP value: '{}' is not a valid port number or URL",
-                        cdp_value
-                    );
-                    if flags.json {
-                        print_json_error(&msg);
-                    } else {
-                        eprintln!("{} {}", color::error_indicator(), msg);
-                    }
-                    exit(1);
+        let mut launch_cmd = match commands::cdp_endpoint_launch(cdp_value) {
+            Ok(fields) => fields,
+            Err(msg) => {
+                if flags.json {
+                    print_json_error(&msg);
+                } else {
+                    eprintln!("{} {}", color::error_indicator(), msg);
                 }
-            };
-            json!({
-                "id": gen_id(),
-                "action": "launch",
-                "cdpPort": cdp_port
-            })
+                exit(1);
+            }
         };
+        launch_cmd["id"] = json!(gen_id());
+        launch_cmd["action"] = json!("launch");
+        attach_connect_launch_options(&mut launch_cmd, &flags);
 
-        let mut launch_cmd = launch_cmd;
-        attach_script_launch_options(&mut launch_cmd, &flags);
-        attach_allowed_domains_to_launch_command(&mut launch_cmd, &flags);
-        attach_restore_config_to_command(&mut launch_cmd, &flags);
-
-        if flags.ignore_https_errors {
-            launch_cmd["ignoreHTTPSErrors"] = json!(true);
-        }
-
-        if let Some(ref cs) = flags.color_scheme {
-            launch_cmd["colorScheme"] = json!(cs);
-        }
-
-        if let Some(ref dp) = flags.download_path {
-            launch_cmd["downloadPath"] = json!(dp);
-        }
-
-        let err = match send_command(launch_cmd, &flags.session) {
+        let err = match send_command(launch_cmd.clone(), &flags.session) {
             Ok(resp) if resp.success => None,
             Ok(resp) => Some(
                 resp.error
@@ -1428,6 +2308,9 @@ fn main() {
             ),
             Err(e) => Some(e.to_string()),
         };
+        if err.is_none() {
+            save_cdp_target(&flags.session, &launch_cmd);
+        }
 
         if let Some(msg) = err {
             if flags.json {
@@ -1441,19 +2324,7 @@ fn main() {
 
     // Launch with cloud provider if -p flag is set.
     if let Some(ref provider) = flags.provider {
-        let mut launch_cmd = json!({
-            "id": gen_id(),
-            "action": "launch",
-            "provider": provider
-        });
-        launch_cmd["plugins"] = json!(flags.plugins.clone());
-        attach_script_launch_options(&mut launch_cmd, &flags);
-        attach_allowed_domains_to_launch_command(&mut launch_cmd, &flags);
-        attach_restore_config_to_command(&mut launch_cmd, &flags);
-
-        if let Some(ref cs) = flags.color_scheme {
-            launch_cmd["colorScheme"] = json!(cs);
-        }
+        let launch_cmd = provider_launch_command(provider, &flags, &provider_options);
 
         let err = match send_command(launch_cmd, &flags.session) {
             Ok(resp) if resp.success => None,
@@ -1476,120 +2347,9 @@ fn main() {
 
     // Launch headed browser or configure browser options (without CDP or provider)
     if should_send_local_launch_config(&flags) {
-        let mut launch_cmd = json!({
-            "id": gen_id(),
-            "action": "launch",
-        });
-        // Only send headless when the user set it on this invocation. When
-        // absent, the daemon falls back to its spawn-time AGENT_BROWSER_HEADED
-        // env, so a follow-up command without --headed (common when env vars
-        // like AGENT_BROWSER_ARGS force a launch command on every call) does
-        // not flip a headed session back to headless and relaunch the browser
-        // onto about:blank.
-        if flags.headed || flags.cli_headed {
-            launch_cmd["headless"] = json!(!flags.headed);
-        }
-        launch_cmd["plugins"] = json!(flags.plugins.clone());
-        attach_restore_config_to_command(&mut launch_cmd, &flags);
-
-        let cmd_obj = launch_cmd
-            .as_object_mut()
-            .expect("json! macro guarantees object type");
-
-        // Add executable path if specified
-        if let Some(ref exec_path) = flags.executable_path {
-            cmd_obj.insert("executablePath".to_string(), json!(exec_path));
-        }
-
-        // Add profile path if specified
-        if let Some(ref profile_path) = flags.profile {
-            cmd_obj.insert("profile".to_string(), json!(profile_path));
-        }
-
-        // Add state path if specified
-        if let Some(ref state_path) = flags.state {
-            cmd_obj.insert("storageState".to_string(), json!(state_path));
-        }
-
-        if let Some(ref proxy_str) = flags.proxy {
-            let parsed = parse_proxy(proxy_str);
-            let mut proxy_obj = json!({ "server": parsed.server });
-            if let Some(ref username) = parsed.username {
-                proxy_obj["username"] = json!(username);
-            }
-            if let Some(ref password) = parsed.password {
-                proxy_obj["password"] = json!(password);
-            }
-            if let Some(ref bypass) = flags.proxy_bypass {
-                proxy_obj["bypass"] = json!(bypass);
-            }
-            cmd_obj.insert("proxy".to_string(), proxy_obj);
-        }
-
-        if let Some(ref ua) = flags.user_agent {
-            cmd_obj.insert("userAgent".to_string(), json!(ua));
-        }
-
-        if let Some(ref a) = flags.args {
-            // Parse args (comma or newline separated)
-            let args_vec: Vec<String> = a
-                .split(&[',', '\n'][..])
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
-            cmd_obj.insert("args".to_string(), json!(args_vec));
-        }
-
-        if !flags.extensions.is_empty() {
-            cmd_obj.insert("extensions".to_string(), json!(&flags.extensions));
-        }
-
-        if !flags.init_scripts.is_empty() {
-            cmd_obj.insert("initScripts".to_string(), json!(&flags.init_scripts));
-        }
-
-        if !flags.enable.is_empty() {
-            cmd_obj.insert("enable".to_string(), json!(&flags.enable));
-        }
+        let launch_cmd = local_launch_command(&flags);
 
-        if flags.ignore_https_errors {
-            launch_cmd["ignoreHTTPSErrors"] = json!(true);
-        }
-
-        if flags.allow_file_access {
-            launch_cmd["allowFileAccess"] = json!(true);
-        }
-
-        apply_hide_scrollbars_launch_option(
-            &mut launch_cmd,
-            flags.cli_hide_scrollbars,
-            flags.hide_scrollbars,
-        );
-
-        if flags.webgpu || flags.cli_webgpu {
-            launch_cmd["webgpu"] = json!(flags.webgpu);
-        }
-
-        // Env-only opt-out for automatic Xvfb; always stamped from the CLI's
-        // fresh environment so both setting and unsetting the var take effect
-        // on daemons spawned before the change.
-        launch_cmd["noXvfb"] = json!(flags.no_xvfb);
-
-        if let Some(ref cs) = flags.color_scheme {
-            launch_cmd["colorScheme"] = json!(cs);
-        }
-
-        if let Some(ref dp) = flags.download_path {
-            launch_cmd["downloadPath"] = json!(dp);
-        }
-
-        attach_allowed_domains_to_launch_command(&mut launch_cmd, &flags);
-
-        if let Some(ref engine) = flags.engine {
-            launch_cmd["engine"] = json!(engine);
-        }
-
-        match send_command(launch_cmd, &flags.session) {
+        match send_with_install_offer(&flags, || send_command(launch_cmd.clone(), &flags.session)) {
             Ok(resp) if !resp.success => {
                 // Launch command failed (e.g., invalid state file, profile error)
                 let error_msg = resp
@@ -1618,55 +2378,373 @@ fn main() {
                 // Launch succeeded
             }
         }
-    }
+    }
+
+    // Anything but a cacheable getter may change what the getters return,
+    // and a new daemon has none of the pages they described.
+    let cache_path = response_cache::cache_path(&flags.session);
+    if daemon_restarted || response_cache::invalidates(&cmd) {
+        response_cache::invalidate(&cache_path);
+    }
+    let cache_key = flags
+        .cache_ms
+        .and_then(|ttl| Some((response_cache::cache_key(&flags.session, &cmd)?, ttl)));
+
+    // Handle batch command: from args or stdin
+    if cmd.get("action").and_then(|v| v.as_str()) == Some("batch") {
+        let bail = cmd.get("bail").and_then(|v| v.as_bool()).unwrap_or(false);
+        let arg_commands = cmd.get("commands").and_then(|v| v.as_array()).map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str())
+                .map(commands::shell_words_split)
+                .collect::<Vec<Vec<String>>>()
+        });
+        let mut progress = ProgressEmitter::stderr(progress_format(&cmd));
+        run_batch(&flags, &daemon_opts, bail, arg_commands, &mut progress);
+        return;
+    }
+
+    if cmd.get("action").and_then(|v| v.as_str()) == Some("act") {
+        let mut progress = ProgressEmitter::stderr(progress_format(&cmd));
+        run_act(&cmd, &flags, &daemon_opts, &mut progress);
+        return;
+    }
+
+    let output_opts = OutputOptions::from_flags(&flags).for_command(&cmd);
+    // Error messages can quote the command's own arguments back.
+    let secrets = CommandRedactor::new(flags.redact_patterns.as_deref()).secrets(&cmd);
+
+    let started = Instant::now();
+    let cached = cache_key.as_ref().and_then(|(key, ttl)| {
+        let now = chrono::Utc::now().timestamp_millis();
+        serde_json::from_value::<Response>(response_cache::lookup(&cache_path, key, now, *ttl)?)
+            .ok()
+    });
+    let result = match cached {
+        Some(mut resp) => {
+            resp.cached = true;
+            Ok(resp)
+        }
+        None => send_with_install_offer(&flags, || {
+            action_retry::send_with_retries(
+                &cmd,
+                flags.auto_retry_actions,
+                |c| send_command_with_respawn(c, &flags.session, &daemon_opts),
+                std::thread::sleep,
+            )
+        }),
+    };
+    let elapsed = started.elapsed();
+    drop(fetched_uploads);
+
+    match result {
+        Ok(mut resp) => {
+            if let Some(retried) = reconnect_cdp_and_retry(&cmd, &resp, &flags) {
+                resp = retried;
+            }
+            if let Some((key, ttl)) = cache_key.filter(|_| resp.success && !resp.cached) {
+                let action = cmd
+                    .get("action")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default();
+                if let Ok(value) = serde_json::to_value(&resp) {
+                    let now = chrono::Utc::now().timestamp_millis();
+                    let _ = response_cache::store(&cache_path, key, action, now, ttl, value);
+                }
+            }
+            if resp.success && cdp_target_from_launch(&cmd).is_some() {
+                save_cdp_target(&flags.session, &cmd);
+            }
+            if resp.success && cmd.get("action").and_then(|v| v.as_str()) == Some("device_list") {
+                if let Some(ref data) = resp.data {
+                    let _ = device_cache::save(&device_cache::cache_path(&flags.session), data);
+                }
+            }
+            if daemon_restarted {
+                mark_restarted_background(&mut resp);
+            }
+            resp.warnings.extend(launch_warnings.iter().cloned());
+            if confirmation_prompt_from_response(&resp).is_some() {
+                let policy = ConfirmationPolicy::from_flags(&flags);
+                resp = resolve_confirmations(resp, &flags);
+                if daemon_restarted {
+                    mark_restarted_background(&mut resp);
+                }
+                resp.warnings.extend(launch_warnings);
+                resp.error = resp.error.map(|e| secrets.text(&e));
+                // An unresolved confirmation is a hand-off to the orchestrator,
+                // which needs the confirmation id in machine-readable form.
+                if policy == ConfirmationPolicy::Fail && !resp.success {
+                    print_json_value(serde_json::to_value(&resp).unwrap_or_default());
+                } else {
+                    print_response_with_opts(&resp, None, &output_opts);
+                }
+                if !resp.success {
+                    exit(1);
+                }
+                return;
+            }
+            let success = resp.success;
+            resp.error = resp.error.map(|e| secrets.text(&e));
+            // Extract action for context-specific output handling
+            let action = cmd.get("action").and_then(|v| v.as_str());
+            if success && action == Some("close") {
+                notes::remove(&flags.session);
+            }
+            let check_exit_code = postprocess_response(&cmd, &mut resp, &flags.session, elapsed);
+            print_response_with_opts(&resp, action, &output_opts);
+            if !success {
+                exit(1);
+            }
+            if let Some(code) =
+                output::is_check_exit_code(action, &resp, flags.no_exit_code).or(check_exit_code)
+            {
+                exit(code);
+            }
+        }
+        Err(e) => {
+            let e = secrets.text(&e);
+            if flags.json {
+                print_json_error(e);
+            } else {
+                eprintln!("{} {}", color::error_indicator(), e);
+            }
+            exit(1);
+        }
+    }
+}
+
+/// `--sessions`: send `cmd` to every listed session concurrently. Each
+/// session gets its own daemon (spawned with the same launch-time options)
+/// and its own launch command when launch flags were given. Results are
+/// printed per session once all have finished. Returns the exit code: 1 when
+/// any session failed, else whatever a check (`is`, `get count --gt`) asked
+/// for.
+fn run_sessions(
+    sessions: &[String],
+    cmd: &serde_json::Value,
+    flags: &Flags,
+    daemon_opts: &DaemonOptions,
+    provider_options: &std::collections::BTreeMap<String, String>,
+) -> Option<i32> {
+    let fail = |msg: String, error_type: &str| -> ! {
+        if flags.json {
+            print_json_error_with_type(msg, error_type);
+        } else {
+            eprintln!("{} {}", color::error_indicator(), msg);
+        }
+        exit(1);
+    };
+    if sessions.is_empty() {
+        fail(
+            "--sessions requires at least one session name".to_string(),
+            "invalid_value",
+        );
+    }
+    if let Some(name) = sessions
+        .iter()
+        .find(|name| !validation::is_valid_session_name(name))
+    {
+        fail(validation::session_name_error(name), "invalid_session_name");
+    }
+    if let Some(action @ ("batch" | "act")) = cmd.get("action").and_then(|v| v.as_str()) {
+        fail(
+            format!(
+                "Cannot use --sessions with {}; run it once per session",
+                action
+            ),
+            "invalid_value",
+        );
+    }
+
+    let check_exit_code = std::sync::Mutex::new(None);
+    let results = fanout::fan_out(sessions, |session| {
+        let mut session_flags = flags.clone();
+        session_flags.session = session.to_string();
+        let mut session_cmd = cmd.clone();
+        session_cmd["id"] = json!(gen_id());
+        let mut session_opts = daemon_opts.clone();
+        if flags.restore_uses_session {
+            session_flags.restore = Some(session.to_string());
+            session_cmd["restoreKey"] = json!(session);
+            session_opts.session_name = Some(session);
+        }
+
+        ensure_daemon(session, &session_opts)?;
+        response_cache::invalidate_for(session, &session_cmd);
+        let launch_cmd = match flags.provider {
+            Some(ref provider) => Some(provider_launch_command(
+                provider,
+                &session_flags,
+                provider_options,
+            )),
+            None if should_send_local_launch_config(&session_flags) => {
+                Some(local_launch_command(&session_flags))
+            }
+            None => None,
+        };
+        if let Some(launch_cmd) = launch_cmd {
+            let resp = send_command(launch_cmd, session)?;
+            if !resp.success {
+                return Ok(resp);
+            }
+        }
+        let started = Instant::now();
+        let mut resp = send_command_with_respawn(session_cmd.clone(), session, &session_opts)?;
+        let check_code = postprocess_response(&session_cmd, &mut resp, session, started.elapsed());
+        let action = session_cmd.get("action").and_then(|v| v.as_str());
+        if let Some(code) = output::is_check_exit_code(action, &resp, flags.no_exit_code)
+            .or(check_code)
+            .filter(|&code| code != 0)
+        {
+            *check_exit_code.lock().unwrap() = Some(code);
+        }
+        Ok(resp)
+    });
+
+    if flags.json {
+        print_json_value(fanout::grouped_json(&results));
+    } else {
+        let action = cmd.get("action").and_then(|v| v.as_str());
+        let output_opts = OutputOptions::from_flags(flags).for_command(cmd);
+        for (i, (session, result)) in results.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            println!("{}", fanout::session_header(session));
+            match result {
+                Ok(resp) => print_response_with_opts(resp, action, &output_opts),
+                Err(e) => eprintln!("{} {}", color::error_indicator(), e),
+            }
+        }
+    }
+    if !results.iter().all(|(_, result)| fanout::succeeded(result)) {
+        return Some(1);
+    }
+    check_exit_code.into_inner().unwrap_or_default()
+}
+
+/// `act`: read and validate the steps, run them over one daemon connection,
+/// then print the per-step results followed by the final snapshot.
+fn run_act(
+    cmd: &serde_json::Value,
+    flags: &Flags,
+    daemon_opts: &DaemonOptions,
+    progress: &mut ProgressEmitter,
+) {
+    let fail = |msg: String| -> ! {
+        if flags.json {
+            print_json_error(msg);
+        } else {
+            eprintln!("{} {}", color::error_indicator(), msg);
+        }
+        exit(1);
+    };
 
-    // Handle batch command: from args or stdin
-    if cmd.get("action").and_then(|v| v.as_str()) == Some("batch") {
-        let bail = cmd.get("bail").and_then(|v| v.as_bool()).unwrap_or(false);
-        let arg_commands = cmd.get("commands").and_then(|v| v.as_array()).map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str())
-                .map(commands::shell_words_split)
-                .collect::<Vec<Vec<String>>>()
-        });
-        run_batch(&flags, &daemon_opts, bail, arg_commands);
-        return;
+    let input = if let Some(path) = cmd.get("file").and_then(|v| v.as_str()) {
+        fs::read_to_string(path).unwrap_or_else(|e| fail(format!("Failed to read {}: {}", path, e)))
+    } else if let Some(input) = cmd.get("input").and_then(|v| v.as_str()) {
+        input.to_string()
+    } else {
+        use std::io::Read as _;
+
+        let mut input = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+            fail(format!("Failed to read stdin: {}", e));
+        }
+        input
+    };
+
+    let mut steps = act::parse_steps(&input)
+        .and_then(|steps| act::validate_steps(steps, flags))
+        .unwrap_or_else(|e| fail(e));
+    for step in &mut steps {
+        attach_plugins_to_command(&mut step.command, &flags.plugins);
+        attach_restore_config_to_command(&mut step.command, flags);
     }
+    let final_snapshot = cmd
+        .get("finalSnapshot")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true)
+        .then(|| {
+            let mut snapshot = json!({ "id": gen_id(), "action": "snapshot", "interactive": true });
+            attach_plugins_to_command(&mut snapshot, &flags.plugins);
+            snapshot
+        });
 
-    let output_opts = OutputOptions::from_flags(&flags);
+    let mut conn = match DaemonConnection::open(&flags.session) {
+        Err(ref e) if daemon_unreachable(e) => ensure_daemon(&flags.session, daemon_opts)
+            .and_then(|_| DaemonConnection::open(&flags.session)),
+        other => other,
+    }
+    .unwrap_or_else(|e| fail(e));
+    let outcome = act::run_steps(
+        steps,
+        final_snapshot,
+        progress,
+        |step| {
+            send_step(step, flags, |c| conn.send(&c)).map(|resp| resolve_confirmations(resp, flags))
+        },
+        |step, resp, elapsed| {
+            let check_code = postprocess_response(step, resp, &flags.session, elapsed);
+            let action = step.get("action").and_then(|v| v.as_str());
+            output::is_check_exit_code(action, resp, flags.no_exit_code).or(check_code)
+        },
+    );
 
-    match send_command_with_respawn(cmd.clone(), &flags.session, &daemon_opts) {
-        Ok(mut resp) => {
-            if daemon_restarted {
-                mark_restarted_background(&mut resp);
-            }
-            if flags.confirm_interactive && confirmation_prompt_from_response(&resp).is_some() {
-                resp = run_interactive_confirmations(resp, &flags, &output_opts);
-                if daemon_restarted {
-                    mark_restarted_background(&mut resp);
-                }
-                if !resp.success {
-                    exit(1);
-                }
-                return;
-            }
-            let success = resp.success;
-            // Extract action for context-specific output handling
-            let action = cmd.get("action").and_then(|v| v.as_str());
-            print_response_with_opts(&resp, action, &output_opts);
-            if !success {
-                exit(1);
-            }
+    if flags.json {
+        print_json_value(outcome.to_json());
+    } else {
+        for line in outcome.step_lines() {
+            println!("{}", line);
         }
-        Err(e) => {
-            if flags.json {
-                print_json_error(e);
-            } else {
-                eprintln!("{} {}", color::error_indicator(), e);
+        match outcome.snapshot {
+            Some(Ok(ref resp)) => {
+                println!();
+                print_response_with_opts(resp, Some("snapshot"), &OutputOptions::from_flags(flags));
             }
-            exit(1);
+            Some(Err(ref e)) => eprintln!("{} Snapshot failed: {}", color::error_indicator(), e),
+            None => {}
         }
     }
+    if !outcome.success() {
+        exit(1);
+    }
+    if let Some(code) = outcome.check_exit_code() {
+        exit(code);
+    }
+}
+
+/// When `resp` failed because the remote CDP connection dropped, re-send the
+/// launch for the session's saved CDP target once and retry `cmd`. Returns
+/// `None` when no reconnect was attempted or the reconnect itself failed, so
+/// the caller reports the original error.
+fn reconnect_cdp_and_retry(
+    cmd: &serde_json::Value,
+    resp: &connection::Response,
+    flags: &Flags,
+) -> Option<connection::Response> {
+    if resp.success || cmd.get("action").and_then(|v| v.as_str()) == Some("launch") {
+        return None;
+    }
+    let error = resp.error.as_deref().unwrap_or_default();
+    let target = load_cdp_target(&flags.session);
+    if !should_auto_reconnect(error, target.is_some(), auto_reconnect_disabled()) {
+        return None;
+    }
+
+    let mut launch_cmd = target?;
+    launch_cmd["id"] = json!(gen_id());
+    launch_cmd["action"] = json!("launch");
+    attach_connect_launch_options(&mut launch_cmd, flags);
+    match send_command(launch_cmd, &flags.session) {
+        Ok(r) if r.success => {}
+        _ => return None,
+    }
+    if !flags.json {
+        eprintln!("{} reconnected to CDP endpoint", color::warning_indicator());
+    }
+    send_command(cmd.clone(), &flags.session).ok()
 }
 
 /// send_command plus the daemon-shutdown-race recovery: ensure_daemon no
@@ -1688,11 +2766,32 @@ fn send_command_with_respawn(
     }
 }
 
+/// Send one `batch` or `act` command, retried per `--auto-retry-actions`.
+/// Like the single-command path, anything but a cacheable getter drops the
+/// session's `--cache` store first.
+fn send_step(
+    cmd: &serde_json::Value,
+    flags: &Flags,
+    send: impl FnMut(serde_json::Value) -> Result<Response, String>,
+) -> Result<Response, String> {
+    response_cache::invalidate_for(&flags.session, cmd);
+    action_retry::send_with_retries(cmd, flags.auto_retry_actions, send, std::thread::sleep)
+}
+
+/// The `--progress` format `batch`/`act` was invoked with, if any. The parser
+/// has already rejected unknown formats.
+fn progress_format(cmd: &serde_json::Value) -> Option<ProgressFormat> {
+    cmd.get("progress")
+        .and_then(|v| v.as_str())
+        .and_then(|v| ProgressFormat::parse(v).ok())
+}
+
 fn run_batch(
     flags: &Flags,
     daemon_opts: &DaemonOptions,
     bail: bool,
     arg_commands: Option<Vec<Vec<String>>>,
+    progress: &mut ProgressEmitter,
 ) {
     let commands: Vec<Vec<String>> = if let Some(cmds) = arg_commands {
         cmds
@@ -1736,20 +2835,23 @@ fn run_batch(
         return;
     }
 
-    let output_opts = OutputOptions::from_flags(flags);
-
     let mut results: Vec<serde_json::Value> = Vec::new();
     let mut had_error = false;
+    let mut check_exit_code = None;
+    let redactor = CommandRedactor::new(flags.redact_patterns.as_deref());
 
     for (i, cmd_args) in commands.iter().enumerate() {
         if cmd_args.is_empty() {
             continue;
         }
 
-        let mut parsed = match parse_command(cmd_args, flags) {
+        // Re-read per command: an earlier `set timeout` in the batch changes it.
+        let mut parsed = match parse_command(cmd_args, &flags_with_session_timeout(flags)) {
             Ok(c) => c,
             Err(e) => {
                 had_error = true;
+                progress.step_start(i, &cmd_args[0]);
+                progress.step_end(Some(&StepError::from_parse_error(&e)));
                 if flags.json {
                     results.push(json!({
                         "command": cmd_args,
@@ -1778,18 +2880,46 @@ fn run_batch(
             .get("action")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
+        let secrets = redactor.secrets(&parsed);
         attach_plugins_to_command(&mut parsed, &flags.plugins);
         attach_restore_config_to_command(&mut parsed, flags);
-
-        match send_command_with_respawn(parsed, &flags.session, daemon_opts) {
+        let output_opts = OutputOptions::from_flags(flags).for_command(&parsed);
+
+        progress.step_start(i, action.as_deref().unwrap_or(&cmd_args[0]));
+        let started = Instant::now();
+        let result = send_step(&parsed, flags, |c| {
+            send_command_with_respawn(c, &flags.session, daemon_opts)
+        })
+        .map(|mut resp| {
+            resp.error = resp.error.map(|e| secrets.text(&e));
+            resp
+        })
+        .map_err(|e| secrets.text(&e));
+        let elapsed = started.elapsed();
+        progress.step_end(StepError::from_result(&result).as_ref());
+        match result {
             Ok(resp) => {
+                let mut resp = resolve_confirmations(resp, flags);
+                let check_code = postprocess_response(&parsed, &mut resp, &flags.session, elapsed);
+                let check_code =
+                    output::is_check_exit_code(action.as_deref(), &resp, flags.no_exit_code)
+                        .or(check_code)
+                        .filter(|&code| code != 0);
                 if flags.json {
-                    results.push(json!({
-                        "command": cmd_args,
+                    let mut entry = json!({
+                        "command": secrets.words(cmd_args),
                         "success": resp.success,
                         "result": resp.data,
                         "error": resp.error,
-                    }));
+                    });
+                    if let Some(echo) = resp.action_echo.take() {
+                        entry["_action"] = echo;
+                    }
+                    if let Some(kind) = resp.error_kind.take() {
+                        entry["errorKind"] = json!(kind);
+                        entry["type"] = json!(resp.error_type.take());
+                    }
+                    results.push(entry);
                 } else {
                     if i > 0 {
                         println!();
@@ -1804,13 +2934,21 @@ fn run_batch(
                         }
                         break;
                     }
+                } else if let Some(code) = check_code {
+                    check_exit_code.get_or_insert(code);
+                    if bail {
+                        if !flags.json {
+                            exit(code);
+                        }
+                        break;
+                    }
                 }
             }
             Err(e) => {
                 had_error = true;
                 if flags.json {
                     results.push(json!({
-                        "command": cmd_args,
+                        "command": secrets.words(cmd_args),
                         "success": false,
                         "error": e.to_string(),
                     }));
@@ -1834,14 +2972,168 @@ fn run_batch(
         );
     }
 
+    if let Some(code) = batch_exit_code(had_error, check_exit_code) {
+        exit(code);
+    }
+}
+
+/// How `batch` exits after running its commands: 1 when any failed, else
+/// the code of the first check that did not pass (a false `is`, a `get count`
+/// comparison, `get url --query`), as the command would exit on its own.
+fn batch_exit_code(had_error: bool, check_exit_code: Option<i32>) -> Option<i32> {
     if had_error {
-        exit(1);
+        Some(1)
+    } else {
+        check_exit_code
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::{ok_response, EnvGuard};
+
+    fn active_session(name: &str, pid: u32) -> ActiveSession {
+        ActiveSession {
+            name: name.to_string(),
+            pid,
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_batch_step_clears_response_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+        let flags = flags::parse_flags(&["--session".to_string(), "cached".to_string()]);
+        let path = response_cache::cache_path("cached");
+        let title = parse_command(&["get".to_string(), "title".to_string()], &flags).unwrap();
+        let key = response_cache::cache_key("cached", &title).unwrap();
+        response_cache::store(&path, key, "title", 0, 5000, json!({ "success": true })).unwrap();
+
+        send_step(&title, &flags, |_| {
+            Ok(ok_response(json!({ "title": "Home" })))
+        })
+        .unwrap();
+        assert!(path.exists());
+
+        let click = parse_command(&["click".to_string(), "@e1".to_string()], &flags).unwrap();
+        send_step(&click, &flags, |_| Ok(ok_response(json!({})))).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_postprocess_response_applies_url_parts() {
+        let flags = flags::parse_flags(&[]);
+        let url = json!({ "url": "https://example.com/a?tag=x" });
+        let words = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let cmd = parse_command(&words(&["get", "url", "--parts"]), &flags).unwrap();
+        let mut resp = ok_response(url.clone());
+        assert_eq!(
+            postprocess_response(&cmd, &mut resp, "pp", Duration::ZERO),
+            None
+        );
+        assert_eq!(resp.data.unwrap()["parts"]["path"], "/a");
+
+        let cmd = parse_command(&words(&["get", "url", "--query", "page"]), &flags).unwrap();
+        let mut resp = ok_response(url);
+        assert_eq!(
+            postprocess_response(&cmd, &mut resp, "pp", Duration::ZERO),
+            Some(output::NO_MATCH_EXIT_CODE)
+        );
+    }
+
+    #[test]
+    fn test_postprocess_response_applies_box_geometry() {
+        let flags = flags::parse_flags(&[]);
+        let words = [
+            "get",
+            "box",
+            "#footer",
+            "--viewport-relative",
+            "--visibility",
+        ];
+        let cmd = parse_command(&words.map(String::from), &flags).unwrap();
+        let mut resp = ok_response(json!({
+            "x": 0, "y": 660, "width": 100, "height": 400,
+            "viewport": { "width": 1000, "height": 800, "scrollX": 0, "scrollY": 0 }
+        }));
+        postprocess_response(&cmd, &mut resp, "pp", Duration::ZERO);
+        let data = resp.data.unwrap();
+        assert_eq!(data["pageY"], 660.0);
+        assert_eq!(data["belowFold"], 260.0);
+    }
+
+    #[test]
+    fn test_postprocess_response_applies_extension_match() {
+        let flags = flags::parse_flags(&[]);
+        let cmd = parse_command(&["extension", "id", "missing"].map(String::from), &flags).unwrap();
+        let mut resp = ok_response(json!({
+            "extensions": [{ "name": "My Popup", "id": "jpignaibiiemhngfjkcpokkamffknabf" }]
+        }));
+        assert_eq!(
+            postprocess_response(&cmd, &mut resp, "pp", Duration::ZERO),
+            Some(output::NO_MATCH_EXIT_CODE)
+        );
+        assert!(resp.data.unwrap()["match"].is_null());
+    }
+
+    #[test]
+    fn test_batch_exit_code() {
+        assert_eq!(batch_exit_code(false, None), None);
+        assert_eq!(batch_exit_code(false, Some(3)), Some(3));
+        assert_eq!(batch_exit_code(false, Some(2)), Some(2));
+        assert_eq!(batch_exit_code(true, Some(3)), Some(1));
+        assert_eq!(batch_exit_code(true, None), Some(1));
+    }
+
+    #[test]
+    fn test_session_list_entry_shape() {
+        let entry = session_list_entry(&active_session("work", 4242), "work", true);
+        assert_eq!(entry["name"], "work");
+        assert_eq!(entry["pid"], 4242);
+        assert_eq!(entry["current"], true);
+        assert_eq!(entry["responding"], true);
+        #[cfg(unix)]
+        assert!(entry["socket"].as_str().unwrap().ends_with("work.sock"));
+        #[cfg(windows)]
+        assert!(entry["port"].as_u64().is_some());
+
+        let other = session_list_entry(&active_session("other", 7), "work", false);
+        assert_eq!(other["current"], false);
+        assert_eq!(other["responding"], false);
+    }
+
+    #[test]
+    fn test_session_json_escapes_names() {
+        let name = "a\"b\\c\n{x}";
+        let entry = session_list_entry(&active_session(name, 1), name, true);
+        let text = serde_json::to_string(&json!({ "sessions": [entry] })).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed["sessions"][0]["name"], name);
+        assert_eq!(parsed["sessions"][0]["current"], true);
+
+        let data = current_session_data(name, "C:\\Users\\me\\run", None);
+        let parsed: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&data).unwrap()).unwrap();
+        assert_eq!(parsed["session"], name);
+        assert_eq!(parsed["socketDir"], "C:\\Users\\me\\run");
+    }
+
+    #[test]
+    fn test_current_session_data_running_state() {
+        let stopped = current_session_data("default", "/tmp/run", None);
+        assert_eq!(stopped["running"], false);
+        assert!(stopped["pid"].is_null());
+        assert_eq!(stopped["socketDir"], "/tmp/run");
+
+        let running =
+            current_session_data("default", "/tmp/run", Some(&active_session("default", 99)));
+        assert_eq!(running["running"], true);
+        assert_eq!(running["pid"], 99);
+    }
 
     #[test]
     fn test_parse_proxy_simple() {
@@ -1937,9 +3229,12 @@ mod tests {
         flags.cli_headed = false;
         flags.executable_path = None;
         flags.profile = None;
+        flags.user_data_dir = None;
         flags.state = None;
         flags.proxy = None;
         flags.args = None;
+        flags.window_size = None;
+        flags.window_position = None;
         flags.user_agent = None;
         flags.allow_file_access = false;
         flags.hide_scrollbars = true;
@@ -1973,6 +3268,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_attach_provider_options_to_launch_command() {
+        let options = flags::resolve_provider_options(
+            None,
+            &["projectId=abc".to_string(), "context=k=v".to_string()],
+        )
+        .unwrap();
+        let mut cmd = json!({ "action": "launch", "provider": "browserbase" });
+
+        attach_provider_options_to_launch_command(&mut cmd, &options);
+
+        assert_eq!(
+            cmd["providerOptions"],
+            json!({ "projectId": "abc", "context": "k=v" })
+        );
+
+        let mut cmd = json!({ "action": "launch" });
+        attach_provider_options_to_launch_command(&mut cmd, &Default::default());
+        assert!(cmd.get("providerOptions").is_none());
+    }
+
+    #[test]
+    fn test_window_flags_merge_into_launch_args() {
+        let mut flags = neutral_launch_config_flags();
+        flags.window_size = Some((1280, 800));
+        assert!(should_send_local_launch_config(&flags));
+        let cmd = local_launch_command(&flags);
+        assert_eq!(cmd["args"], json!(["--window-size=1280,800"]));
+
+        // An explicit --window-size in --args wins over the flag.
+        flags.args = Some("--no-first-run\n--window-size=1920".to_string());
+        flags.window_position = Some((0, 0));
+        let cmd = local_launch_command(&flags);
+        assert_eq!(
+            cmd["args"],
+            json!([
+                "--no-first-run",
+                "--window-size=1920",
+                "--window-position=0,0"
+            ])
+        );
+
+        flags = neutral_launch_config_flags();
+        assert!(local_launch_command(&flags).get("args").is_none());
+    }
+
     #[test]
     fn test_allowed_domains_requests_local_launch_configuration() {
         let mut flags = neutral_launch_config_flags();
@@ -2081,6 +3422,58 @@ mod tests {
         flags
     }
 
+    fn split_args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_ignored_launch_flags_only_for_running_daemon() {
+        let flags = parse_flags(&split_args(
+            "--device iPhone --allowed-domains example.com --action-policy /p.json -p ios open",
+        ));
+        let running = DaemonResult {
+            already_running: true,
+            restarted: false,
+        };
+        assert_eq!(ignored_launch_flags(&flags, &running), vec!["--device"]);
+
+        let spawned = DaemonResult {
+            already_running: false,
+            restarted: false,
+        };
+        assert!(ignored_launch_flags(&flags, &spawned).is_empty());
+        let restarted = DaemonResult {
+            already_running: true,
+            restarted: true,
+        };
+        assert!(ignored_launch_flags(&flags, &restarted).is_empty());
+
+        let flags = parse_flags(&split_args(
+            "--session-name app --confirm-actions eval open",
+        ));
+        assert!(flags.cli_session_name && flags.cli_confirm_actions);
+        assert!(ignored_launch_flags(&flags, &running).is_empty());
+    }
+
+    #[test]
+    fn test_ignored_launch_flags_message() {
+        assert_eq!(
+            ignored_launch_flags_message("work", &["--device"]),
+            "--device ignored: the daemon for session 'work' is already running. Run `agent-browser --session work close` first to apply it."
+        );
+    }
+
+    #[test]
+    fn test_response_warnings_serialized_only_when_present() {
+        let mut resp = ok_response(json!({}));
+        let value = serde_json::to_value(&resp).unwrap();
+        assert!(value.get("warnings").is_none());
+
+        resp.warnings.push("--device ignored".to_string());
+        let value = serde_json::to_value(&resp).unwrap();
+        assert_eq!(value["warnings"], json!(["--device ignored"]));
+    }
+
     #[test]
     fn test_incompatible_launch_mode_error_matches_existing_messages() {
         let cases = [
@@ -2111,6 +3504,83 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_incompatible_launch_mode_error_rejects_profile_with_user_data_dir() {
+        let mut flags = launch_mode_flags(false, false, false, false);
+        flags.profile = None;
+        flags.user_data_dir = Some("/home/me/.config/google-chrome".to_string());
+        assert_eq!(incompatible_launch_mode_error(&flags), None);
+
+        flags.profile = Some("/tmp/profile".to_string());
+        assert_eq!(
+            incompatible_launch_mode_error(&flags),
+            Some("Cannot use --profile and --user-data-dir together")
+        );
+    }
+
+    #[test]
+    fn test_extension_command_error_rejects_attach_modes() {
+        let cmd = json!({ "action": "extension_list", "name": "popup" });
+        let local = launch_mode_flags(false, false, false, false);
+        assert_eq!(extension_command_error(&local, &cmd), None);
+        assert_eq!(
+            extension_command_error(&launch_mode_flags(false, true, false, false), &cmd),
+            Some("Cannot use extension with --cdp (extensions require local browser)")
+        );
+        assert_eq!(
+            extension_command_error(&launch_mode_flags(false, false, true, false), &cmd),
+            Some("Cannot use extension with -p/--provider (extensions require local browser)")
+        );
+        assert_eq!(
+            extension_command_error(&launch_mode_flags(true, false, false, false), &cmd),
+            Some("Cannot use extension with --auto-connect (extensions require local browser)")
+        );
+        let other = json!({ "action": "snapshot" });
+        assert_eq!(
+            extension_command_error(&launch_mode_flags(false, true, false, false), &other),
+            None
+        );
+    }
+
+    #[test]
+    fn test_user_data_dir_in_use_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut flags = launch_mode_flags(false, false, false, false);
+        flags.user_data_dir = Some(dir.path().to_string_lossy().into_owned());
+        assert_eq!(user_data_dir_in_use_warning(&flags), None);
+
+        fs::write(dir.path().join("lockfile"), "").unwrap();
+        let msg = user_data_dir_in_use_warning(&flags).unwrap();
+        assert!(msg.contains("appears to be in use"), "got: {}", msg);
+        assert!(msg.contains("lockfile"), "got: {}", msg);
+    }
+
+    #[test]
+    fn test_incompatible_launch_mode_error_rejects_sessions_with_attach_modes() {
+        let with_sessions = |mut flags: Flags| {
+            flags.sessions = Some(vec!["a".to_string(), "b".to_string()]);
+            flags
+        };
+        assert_eq!(
+            incompatible_launch_mode_error(&with_sessions(launch_mode_flags(
+                false, true, false, false
+            ))),
+            Some("Cannot use --sessions with --cdp (each session needs its own browser)")
+        );
+        assert_eq!(
+            incompatible_launch_mode_error(&with_sessions(launch_mode_flags(
+                true, false, false, false
+            ))),
+            Some("Cannot use --sessions with --auto-connect (each session needs its own browser)")
+        );
+        assert_eq!(
+            incompatible_launch_mode_error(&with_sessions(launch_mode_flags(
+                false, false, true, false
+            ))),
+            None
+        );
+    }
+
     #[test]
     fn test_incompatible_launch_mode_error_rejects_webgpu_attach_modes() {
         let with_webgpu = |mut flags: Flags| {
@@ -2176,24 +3646,19 @@ mod tests {
 
     #[test]
     fn test_confirmation_prompt_from_response_finds_nested_confirm_result() {
-        let resp = Response {
-            success: true,
-            data: Some(json!({
-                "confirmed": true,
-                "action": "navigate",
-                "result": {
-                    "id": "original-command",
-                    "success": true,
-                    "data": {
-                        "confirmation_required": true,
-                        "confirmation_id": "original-command",
-                        "action": "plugin:stealth:launch.mutate"
-                    }
+        let resp = ok_response(json!({
+            "confirmed": true,
+            "action": "navigate",
+            "result": {
+                "id": "original-command",
+                "success": true,
+                "data": {
+                    "confirmation_required": true,
+                    "confirmation_id": "original-command",
+                    "action": "plugin:stealth:launch.mutate"
                 }
-            })),
-            error: None,
-            warning: None,
-        };
+            }
+        }));
 
         let prompt = confirmation_prompt_from_response(&resp).unwrap();
 
@@ -2201,4 +3666,100 @@ mod tests {
         assert_eq!(prompt.description, "plugin:stealth:launch.mutate");
         assert_eq!(prompt.confirmation_id, "original-command");
     }
+
+    fn confirmation_fixture() -> Response {
+        ok_response(json!({
+            "confirmation_required": true,
+            "confirmation_id": "c-1",
+            "action": "eval",
+            "category": "eval",
+            "description": "Evaluate JavaScript"
+        }))
+    }
+
+    #[test]
+    fn test_confirmation_policy_allow_sends_confirm() {
+        let mut sent = Vec::new();
+        let resp = apply_confirmation_policy(
+            confirmation_fixture(),
+            ConfirmationPolicy::Allow,
+            |cmd| {
+                sent.push(cmd);
+                Ok(ok_response(json!({ "confirmed": true, "result": 2 })))
+            },
+            |_| panic!("allow must not prompt"),
+        );
+
+        assert!(resp.success);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0]["action"], "confirm");
+        assert_eq!(sent[0]["confirmationId"], "c-1");
+    }
+
+    #[test]
+    fn test_confirmation_policy_deny_sends_deny_and_fails() {
+        let mut sent = Vec::new();
+        let resp = apply_confirmation_policy(
+            confirmation_fixture(),
+            ConfirmationPolicy::Deny,
+            |cmd| {
+                sent.push(cmd);
+                Ok(ok_response(json!({ "denied": true })))
+            },
+            |_| panic!("deny must not prompt"),
+        );
+
+        assert!(!resp.success);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0]["action"], "deny");
+        assert_eq!(resp.data.unwrap()["code"], "E_CONFIRMATION_DENIED");
+    }
+
+    #[test]
+    fn test_confirmation_policy_fail_returns_details_without_sending() {
+        let resp = apply_confirmation_policy(
+            confirmation_fixture(),
+            ConfirmationPolicy::Fail,
+            |_| panic!("fail must not contact the daemon"),
+            |_| panic!("fail must not prompt"),
+        );
+
+        assert!(!resp.success);
+        let data = resp.data.unwrap();
+        assert_eq!(data["code"], "E_CONFIRMATION_REQUIRED");
+        assert_eq!(data["confirmation_id"], "c-1");
+        assert_eq!(data["description"], "Evaluate JavaScript");
+    }
+
+    #[test]
+    fn test_confirmation_policy_prompt_uses_answer() {
+        let resp = apply_confirmation_policy(
+            confirmation_fixture(),
+            ConfirmationPolicy::Prompt,
+            |cmd| {
+                assert_eq!(cmd["action"], "confirm");
+                Ok(ok_response(json!({ "confirmed": true })))
+            },
+            |prompt| prompt.confirmation_id == "c-1",
+        );
+
+        assert!(resp.success);
+    }
+
+    #[test]
+    fn test_confirmation_policy_parse() {
+        assert_eq!(
+            ConfirmationPolicy::parse("allow"),
+            Some(ConfirmationPolicy::Allow)
+        );
+        assert_eq!(
+            ConfirmationPolicy::parse("deny"),
+            Some(ConfirmationPolicy::Deny)
+        );
+        assert_eq!(
+            ConfirmationPolicy::parse("fail"),
+            Some(ConfirmationPolicy::Fail)
+        );
+        assert_eq!(ConfirmationPolicy::parse("yes"), None);
+    }
 }
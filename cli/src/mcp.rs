@@ -41,6 +41,10 @@ const TOOL_SELECT: &str = "agent_browser_select";
 const TOOL_DRAG: &str = "agent_browser_drag";
 const TOOL_UPLOAD: &str = "agent_browser_upload";
 const TOOL_DOWNLOAD: &str = "agent_browser_download";
+const TOOL_DOWNLOADS_LIST: &str = "agent_browser_downloads_list";
+const TOOL_DOWNLOADS_OPEN: &str = "agent_browser_downloads_open";
+const TOOL_DOWNLOADS_CLEAR: &str = "agent_browser_downloads_clear";
+const TOOL_EXTENSION_LIST: &str = "agent_browser_extension_list";
 const TOOL_SCROLL: &str = "agent_browser_scroll";
 const TOOL_SCROLL_INTO_VIEW: &str = "agent_browser_scroll_into_view";
 const TOOL_WAIT_MS: &str = "agent_browser_wait_ms";
@@ -127,6 +131,8 @@ const TOOL_STATE_CLEAR: &str = "agent_browser_state_clear";
 const TOOL_STATE_SHOW: &str = "agent_browser_state_show";
 const TOOL_STATE_CLEAN: &str = "agent_browser_state_clean";
 const TOOL_STATE_RENAME: &str = "agent_browser_state_rename";
+const TOOL_STATE_CHECKPOINT: &str = "agent_browser_state_checkpoint";
+const TOOL_STATE_RESTORE: &str = "agent_browser_state_restore";
 const TOOL_TAP: &str = "agent_browser_tap";
 const TOOL_SWIPE: &str = "agent_browser_swipe";
 const TOOL_DEVICE: &str = "agent_browser_device";
@@ -134,6 +140,7 @@ const TOOL_DIFF_SNAPSHOT: &str = "agent_browser_diff_snapshot";
 const TOOL_DIFF_SCREENSHOT: &str = "agent_browser_diff_screenshot";
 const TOOL_DIFF_URL: &str = "agent_browser_diff_url";
 const TOOL_BATCH: &str = "agent_browser_batch";
+const TOOL_ACT: &str = "agent_browser_act";
 const TOOL_REACT_TREE: &str = "agent_browser_react_tree";
 const TOOL_REACT_INSPECT: &str = "agent_browser_react_inspect";
 const TOOL_REACT_RENDERS_START: &str = "agent_browser_react_renders_start";
@@ -146,6 +153,7 @@ const TOOL_REMOVE_INIT_SCRIPT: &str = "agent_browser_remove_init_script";
 const TOOL_CONFIRM: &str = "agent_browser_confirm";
 const TOOL_DENY: &str = "agent_browser_deny";
 const TOOL_CONNECT: &str = "agent_browser_connect";
+const TOOL_CONNECT_LIST: &str = "agent_browser_connect_list";
 const TOOL_STREAM_ENABLE: &str = "agent_browser_stream_enable";
 const TOOL_STREAM_DISABLE: &str = "agent_browser_stream_disable";
 const TOOL_STREAM_STATUS: &str = "agent_browser_stream_status";
@@ -162,6 +170,9 @@ const TOOL_PLUGIN_LIST: &str = "agent_browser_plugin_list";
 const TOOL_PLUGIN_SHOW: &str = "agent_browser_plugin_show";
 const TOOL_PLUGIN_RUN: &str = "agent_browser_plugin_run";
 const TOOL_DOCTOR: &str = "agent_browser_doctor";
+const TOOL_SELFTEST: &str = "agent_browser_selftest";
+const TOOL_BUGREPORT: &str = "agent_browser_bugreport";
+const TOOL_ENV: &str = "agent_browser_env";
 const TOOL_DASHBOARD_START: &str = "agent_browser_dashboard_start";
 const TOOL_DASHBOARD_STOP: &str = "agent_browser_dashboard_stop";
 const TOOL_INSTALL: &str = "agent_browser_install";
@@ -254,7 +265,7 @@ impl ToolProfile {
             Self::Core => "Everyday browser automation with navigation, snapshots, common interaction, waits, screenshots, basic reads, tab basics, JavaScript eval, close, and profile discovery.",
             Self::Network => "Network interception, request inspection, HAR capture, headers, credentials, and offline mode.",
             Self::State => "Cookies, storage, auth profiles, saved browser state, sessions, Chrome profiles, and bundled skills.",
-            Self::Debug => "Console/errors, highlighting, DevTools, tracing, profiling, accessibility audits, PDF, downloads/uploads, recording, clipboard, plugin registry and plugin command.run, doctor, dashboard, install, upgrade, and chat.",
+            Self::Debug => "Console/errors, highlighting, DevTools, tracing, profiling, accessibility audits, PDF, downloads/uploads, recording, clipboard, plugin registry and plugin command.run, doctor, selftest, dashboard, install, upgrade, and chat.",
             Self::Tabs => "Tab, window, frame, and JavaScript dialog management.",
             Self::React => "React tree inspection, render recording, Suspense inspection, Web Vitals, SPA pushstate, and init-script removal.",
             Self::Mobile => "Viewport/device/geolocation/media emulation plus touch, swipe, and lower-level mouse tools.",
@@ -387,6 +398,8 @@ const STATE_PROFILE_TOOLS: &[&str] = &[
     TOOL_STATE_SHOW,
     TOOL_STATE_CLEAN,
     TOOL_STATE_RENAME,
+    TOOL_STATE_CHECKPOINT,
+    TOOL_STATE_RESTORE,
     TOOL_SESSION,
     TOOL_SESSION_LIST,
     TOOL_SESSION_ID,
@@ -402,6 +415,10 @@ const DEBUG_PROFILE_TOOLS: &[&str] = &[
     TOOL_PDF,
     TOOL_UPLOAD,
     TOOL_DOWNLOAD,
+    TOOL_DOWNLOADS_LIST,
+    TOOL_DOWNLOADS_OPEN,
+    TOOL_DOWNLOADS_CLEAR,
+    TOOL_EXTENSION_LIST,
     TOOL_TRACE_START,
     TOOL_TRACE_STOP,
     TOOL_PROFILER_START,
@@ -422,9 +439,11 @@ const DEBUG_PROFILE_TOOLS: &[&str] = &[
     TOOL_DIFF_SCREENSHOT,
     TOOL_DIFF_URL,
     TOOL_BATCH,
+    TOOL_ACT,
     TOOL_CONFIRM,
     TOOL_DENY,
     TOOL_CONNECT,
+    TOOL_CONNECT_LIST,
     TOOL_STREAM_ENABLE,
     TOOL_STREAM_DISABLE,
     TOOL_STREAM_STATUS,
@@ -433,6 +452,9 @@ const DEBUG_PROFILE_TOOLS: &[&str] = &[
     TOOL_PLUGIN_SHOW,
     TOOL_PLUGIN_RUN,
     TOOL_DOCTOR,
+    TOOL_SELFTEST,
+    TOOL_BUGREPORT,
+    TOOL_ENV,
     TOOL_DASHBOARD_START,
     TOOL_DASHBOARD_STOP,
     TOOL_INSTALL,
@@ -752,7 +774,8 @@ fn tools() -> Vec<Value> {
             json!({
                 "url": { "type": "string", "description": "URL to open. Omit to launch about:blank." },
                 "headed": { "type": "boolean", "description": "Show the browser window. Explicit true/false overrides AGENT_BROWSER_HEADED and config; omit to use those defaults." },
-                "webgpu": { "type": "boolean", "description": "Enable WebGPU (SwiftShader software Vulkan on Linux; no GPU required). Explicit true/false overrides AGENT_BROWSER_WEBGPU and config; omit to use those defaults." }
+                "webgpu": { "type": "boolean", "description": "Enable WebGPU (SwiftShader software Vulkan on Linux; no GPU required). Explicit true/false overrides AGENT_BROWSER_WEBGPU and config; omit to use those defaults." },
+                "headers": { "type": "object", "additionalProperties": { "type": "string" }, "description": "HTTP headers scoped to the URL's origin for this navigation. Requires url." }
             }),
             &[],
         ),
@@ -779,8 +802,10 @@ fn tools() -> Vec<Value> {
                 "interactive": { "type": "boolean", "default": true, "description": "Only include interactive elements." },
                 "compact": { "type": "boolean", "default": false, "description": "Remove empty structural elements." },
                 "depth": { "type": "integer", "minimum": 0, "description": "Limit tree depth." },
-                "selector": { "type": "string", "description": "Scope the snapshot to a CSS selector." },
-                "includeUrls": { "type": "boolean", "default": false, "description": "Include href URLs on links." }
+                "selector": { "type": "string", "description": "Scope the snapshot to a CSS selector or an @ref from a previous snapshot." },
+                "includeUrls": { "type": "boolean", "default": false, "description": "Include href URLs on links." },
+                "stableRefs": { "type": "boolean", "default": false, "description": "Key refs by role, name and position so they survive re-snapshots." },
+                "viewportOnly": { "type": "boolean", "default": false, "description": "Only include elements intersecting the current viewport; scroll for more." }
             }),
             &[],
         ),
@@ -790,7 +815,9 @@ fn tools() -> Vec<Value> {
             "Click an element by @ref or CSS selector.",
             json!({
                 "selector": selector_schema(),
-                "newTab": { "type": "boolean", "default": false, "description": "Open link targets in a new tab." }
+                "newTab": { "type": "boolean", "default": false, "description": "Open link targets in a new tab." },
+                "waitForPopup": { "type": "boolean", "default": false, "description": "Wait for the popup the click opens and switch to it." },
+                "popupTimeout": { "type": "integer", "minimum": 1, "description": "Milliseconds to wait for the popup (default 5000)." }
             }),
             &["selector"],
         ),
@@ -858,7 +885,7 @@ fn tools() -> Vec<Value> {
         tool(TOOL_SCROLL_INTO_VIEW, "Scroll into view", "Scroll an element into view.", json!({ "selector": selector_schema() }), &["selector"]),
         tool(TOOL_WAIT_MS, "Wait milliseconds", "Wait for a fixed time.", json!({ "ms": { "type": "integer", "minimum": 0 } }), &["ms"]),
         wait_tool(TOOL_WAIT_FOR_SELECTOR, "Wait for selector", "Wait for an element to appear.", json!({ "selector": selector_schema() }), &["selector"]),
-        wait_tool(TOOL_WAIT_FOR_TEXT, "Wait for text", "Wait for text to appear.", json!({ "text": { "type": "string" } }), &["text"]),
+        wait_tool(TOOL_WAIT_FOR_TEXT, "Wait for text", "Wait for visible text to appear, or to disappear with not. The response reports the matched element and its ref when one exists.", json!({ "text": { "type": "string" }, "selector": { "type": "string", "description": "CSS selector limiting the search to one element's subtree." }, "exact": { "type": "boolean", "description": "Match an element's whole text instead of a substring." }, "not": { "type": "boolean", "description": "Wait until the text is no longer visible." } }), &["text"]),
         wait_tool(TOOL_WAIT_FOR_URL, "Wait for URL", "Wait for the current URL to match a pattern.", json!({ "url": { "type": "string", "description": "URL glob or pattern." } }), &["url"]),
         wait_tool(TOOL_WAIT_FOR_LOAD, "Wait for load state", "Wait for a page load state.", json!({ "state": { "type": "string", "enum": ["load", "domcontentloaded", "networkidle"] } }), &["state"]),
         wait_tool(TOOL_WAIT_FOR_FUNCTION, "Wait for function", "Wait for a JavaScript expression to become truthy.", json!({ "expression": { "type": "string" } }), &["expression"]),
@@ -878,9 +905,29 @@ fn tools() -> Vec<Value> {
             &[],
         ),
         tool(TOOL_GET_TEXT, "Get text", "Get visible text from an element.", json!({ "selector": selector_schema() }), &["selector"]),
-        tool(TOOL_GET_HTML, "Get HTML", "Get innerHTML from an element.", json!({ "selector": selector_schema() }), &["selector"]),
+        tool(
+            TOOL_GET_HTML,
+            "Get HTML",
+            "Get innerHTML from an element.",
+            json!({
+                "selector": selector_schema(),
+                "outer": { "type": "boolean", "description": "Return outerHTML, including the element itself." },
+                "stripAttrs": { "type": "boolean", "description": "Remove class, style, and data-* attributes." },
+                "maxDepth": { "type": "integer", "minimum": 0, "description": "Replace elements nested deeper than this with a comment." }
+            }),
+            &["selector"],
+        ),
         tool(TOOL_GET_VALUE, "Get value", "Get an input value.", json!({ "selector": selector_schema() }), &["selector"]),
-        tool(TOOL_GET_URL, "Get URL", "Get the current page URL.", json!({}), &[]),
+        tool(
+            TOOL_GET_URL,
+            "Get URL",
+            "Get the current page URL, optionally split into its components or reduced to one query parameter.",
+            json!({
+                "parts": { "type": "boolean", "description": "Add scheme, host, port, path, decoded query map, and fragment." },
+                "query": { "type": "string", "description": "Return only this query parameter's decoded value; an error when absent." }
+            }),
+            &[],
+        ),
         tool(TOOL_GET_TITLE, "Get title", "Get the current page title.", json!({}), &[]),
         tool(TOOL_GET_CDP_URL, "Get CDP URL", "Get the current browser CDP URL.", json!({}), &[]),
         tool(
@@ -922,14 +969,25 @@ fn parity_tools() -> Vec<Value> {
             TOOL_DRAG,
             "Drag and drop",
             "Drag one element to another.",
-            json!({ "source": selector_schema(), "target": selector_schema() }),
+            json!({
+                "source": selector_schema(),
+                "target": selector_schema(),
+                "sourceOffset": { "type": "string", "description": "Grab point as x,y pixels from the source's top-left" },
+                "targetOffset": { "type": "string", "description": "Drop point as x,y pixels from the target's top-left" },
+                "html5": { "type": "boolean", "description": "Dispatch HTML5 drag-and-drop events instead of mouse moves" }
+            }),
             &["source", "target"],
         ),
         tool(
             TOOL_UPLOAD,
             "Upload files",
-            "Upload files through a file input.",
-            json!({ "selector": selector_schema(), "files": string_array_schema("File paths to upload.") }),
+            "Upload files through a file input. http(s) URLs are downloaded first.",
+            json!({
+                "selector": selector_schema(),
+                "files": string_array_schema("File paths or http(s) URLs to upload."),
+                "maxFetchSize": { "type": "string", "description": "Largest URL download, as bytes or with a K, M, or G unit. Default 50MB." },
+                "keepTemp": { "type": "boolean", "description": "Keep downloaded files instead of deleting them after the upload." }
+            }),
             &["selector", "files"],
         ),
         tool(
@@ -939,6 +997,37 @@ fn parity_tools() -> Vec<Value> {
             json!({ "selector": selector_schema(), "path": { "type": "string" } }),
             &["selector", "path"],
         ),
+        tool(
+            TOOL_DOWNLOADS_LIST,
+            "List downloads",
+            "List files downloaded this session with index, filename, size, source URL, and timestamp.",
+            json!({}),
+            &[],
+        ),
+        tool(
+            TOOL_EXTENSION_LIST,
+            "List extensions",
+            "List the extensions the session was launched with (--extension) with name, version, extension id for chrome-extension:// URLs, and enabled state.",
+            json!({}),
+            &[],
+        ),
+        tool(
+            TOOL_DOWNLOADS_OPEN,
+            "Open download",
+            "Return the absolute path of a download by its 1-based index from the downloads list.",
+            json!({
+                "index": { "type": "integer", "minimum": 1 },
+                "reveal": { "type": "boolean", "description": "Also open the containing folder in the platform file manager." }
+            }),
+            &["index"],
+        ),
+        tool(
+            TOOL_DOWNLOADS_CLEAR,
+            "Clear downloads",
+            "Delete files downloaded this session. Requires confirmation through the session's confirmation policy.",
+            json!({}),
+            &[],
+        ),
         tool(
             TOOL_KEYDOWN,
             "Key down",
@@ -991,22 +1080,33 @@ fn parity_tools() -> Vec<Value> {
         tool(
             TOOL_GET_COUNT,
             "Get count",
-            "Count matching elements.",
-            json!({ "selector": selector_schema() }),
+            "Count matching elements, optionally only visible ones.",
+            json!({ "selector": selector_schema(), "visibleOnly": { "type": "boolean" } }),
             &["selector"],
         ),
         tool(
             TOOL_GET_BOX,
             "Get box",
-            "Get an element bounding box.",
-            json!({ "selector": selector_schema() }),
+            "Get an element bounding box relative to the viewport.",
+            json!({
+                "selector": selector_schema(),
+                "viewportRelative": { "type": "boolean", "description": "Add pageX/pageY and how far the element extends below the fold or above the viewport." },
+                "visibility": { "type": "boolean", "description": "Add intersectionRatio, the fraction of the element inside the viewport." }
+            }),
             &["selector"],
         ),
         tool(
             TOOL_GET_STYLES,
             "Get styles",
-            "Get computed styles for an element.",
-            json!({ "selector": selector_schema() }),
+            "Get computed styles for an element, optionally only the named CSS properties.",
+            json!({
+                "selector": selector_schema(),
+                "properties": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "CSS property names such as font-size; all computed properties when omitted."
+                }
+            }),
             &["selector"],
         ),
         tool(
@@ -1041,7 +1141,9 @@ fn parity_tools() -> Vec<Value> {
                 "text": { "type": "string", "description": "Optional value for the fill action." },
                 "index": { "type": "integer", "description": "Index for nth locator." },
                 "name": { "type": "string", "description": "Accessible name filter for role locator." },
-                "exact": { "type": "boolean", "description": "Exact, case-sensitive match. For the role locator it applies to the accessible name, whose default is a case-insensitive substring. The role value itself always matches case-insensitively, with or without exact.", "default": false }
+                "exact": { "type": "boolean", "description": "Exact, case-sensitive match. For the role locator it applies to the accessible name, whose default is a case-insensitive substring. The role value itself always matches case-insensitively, with or without exact.", "default": false },
+                "wait": { "type": "integer", "minimum": 0, "description": "Milliseconds to wait for the locator to resolve before acting. The response reports waitedMs." },
+                "state": { "type": "string", "enum": ["visible", "attached"], "description": "Whether the element must be visible or only attached to the DOM (default) to resolve." }
             }),
             &["locator", "value"],
         ),
@@ -1281,7 +1383,12 @@ fn parity_tools() -> Vec<Value> {
             TOOL_TRACE_START,
             "Trace start",
             "Start Chrome trace capture.",
-            json!({}),
+            json!({
+                "screenshots": { "type": "boolean", "description": "Record page screenshots (default true)." },
+                "snapshots": { "type": "boolean", "description": "Record layer and paint snapshots (default true)." },
+                "sources": { "type": "boolean", "description": "Record script sources (default false)." },
+                "title": { "type": "string", "description": "Title stored in the trace metadata." }
+            }),
             &[],
         ),
         tool(
@@ -1330,14 +1437,25 @@ fn parity_tools() -> Vec<Value> {
             TOOL_CONSOLE,
             "Console logs",
             "Read console logs.",
-            json!({ "clear": { "type": "boolean" } }),
+            json!({
+                "clear": { "type": "boolean" },
+                "since": { "type": "string", "description": "Only entries from this long ago, e.g. 30s or 5m" },
+                "after": { "type": "string", "description": "Only entries at or after this ISO 8601 time" },
+                "grep": { "type": "string", "description": "Only entries containing this text, ignoring case" }
+            }),
             &[],
         ),
         tool(
             TOOL_ERRORS,
             "Page errors",
             "Read page errors.",
-            json!({ "clear": { "type": "boolean" } }),
+            json!({
+                "clear": { "type": "boolean" },
+                "since": { "type": "string", "description": "Only entries from this long ago, e.g. 30s or 5m" },
+                "after": { "type": "string", "description": "Only entries at or after this ISO 8601 time" },
+                "grep": { "type": "string", "description": "Only entries containing this text, ignoring case" },
+                "stack": { "type": "boolean", "description": "Include stack traces" }
+            }),
             &[],
         ),
         tool(
@@ -1428,14 +1546,17 @@ fn parity_tools() -> Vec<Value> {
         tool(
             TOOL_STATE_SAVE,
             "State save",
-            "Save cookies and storage state.",
-            json!({ "path": { "type": "string" } }),
+            "Save cookies and storage state to a state name or a .json path.",
+            json!({
+                "path": { "type": "string" },
+                "encrypt": { "type": "boolean" }
+            }),
             &["path"],
         ),
         tool(
             TOOL_STATE_LOAD,
             "State load",
-            "Load cookies and storage state.",
+            "Load cookies and storage state from a state name or a .json path.",
             json!({ "path": { "type": "string" } }),
             &["path"],
         ),
@@ -1474,6 +1595,20 @@ fn parity_tools() -> Vec<Value> {
             json!({ "oldName": { "type": "string" }, "newName": { "type": "string" } }),
             &["oldName", "newName"],
         ),
+        tool(
+            TOOL_STATE_CHECKPOINT,
+            "State checkpoint",
+            "Save a checkpoint of the named session's state.",
+            json!({ "label": { "type": "string" } }),
+            &[],
+        ),
+        tool(
+            TOOL_STATE_RESTORE,
+            "State restore",
+            "Load the most recent checkpoint, optionally with a label, or list checkpoints.",
+            json!({ "label": { "type": "string" }, "list": { "type": "boolean" } }),
+            &[],
+        ),
         tool(
             TOOL_TAP,
             "Tap",
@@ -1491,15 +1626,15 @@ fn parity_tools() -> Vec<Value> {
         tool(
             TOOL_DEVICE,
             "Device",
-            "List available iOS simulators.",
-            json!({ "action": { "type": "string", "enum": ["list"], "default": "list" } }),
+            "List iOS simulators, pick one for the session (use), or boot one (boot).",
+            json!({ "action": { "type": "string", "enum": ["list", "use", "boot"], "default": "list" }, "device": { "type": "string", "description": "Device name or UDID, for use and boot" } }),
             &[],
         ),
         tool(
             TOOL_DIFF_SNAPSHOT,
             "Diff snapshot",
             "Diff current snapshot against last or baseline.",
-            json!({ "baseline": { "type": "string" }, "selector": { "type": "string" }, "compact": { "type": "boolean" }, "depth": { "type": "integer" } }),
+            json!({ "baseline": { "type": "string" }, "selector": { "type": "string" }, "compact": { "type": "boolean" }, "depth": { "type": "integer" }, "stableRefs": { "type": "boolean" } }),
             &[],
         ),
         tool(
@@ -1523,6 +1658,13 @@ fn parity_tools() -> Vec<Value> {
             json!({ "commands": { "type": "array", "items": { "type": "array", "items": { "type": "string" }, "minItems": 1 }, "minItems": 1 }, "bail": { "type": "boolean" } }),
             &["commands"],
         ),
+        tool(
+            TOOL_ACT,
+            "Act",
+            "Run steps in order, stop at the first failure, and return an interactive snapshot of the resulting page.",
+            json!({ "steps": { "type": "array", "items": { "type": "array", "items": { "type": "string" }, "minItems": 1 }, "minItems": 1 }, "noFinalSnapshot": { "type": "boolean" } }),
+            &["steps"],
+        ),
         tool(
             TOOL_REACT_TREE,
             "React tree",
@@ -1608,15 +1750,30 @@ fn parity_tools() -> Vec<Value> {
         tool(
             TOOL_CONNECT,
             "Connect CDP",
-            "Connect to a browser over CDP.",
-            json!({ "target": { "type": "string", "description": "CDP port or URL." } }),
-            &["target"],
+            "Connect to a browser over CDP. Pass a port or URL as target, or pick a scanned local browser with match and/or port.",
+            json!({
+                "target": { "type": "string", "description": "CDP port or URL." },
+                "match": { "type": "string", "description": "Case-insensitive substring of an open tab title." },
+                "port": { "type": "integer", "description": "Port of a scanned local browser." }
+            }),
+            &[],
+        ),
+        tool(
+            TOOL_CONNECT_LIST,
+            "Connect list",
+            "List local browsers with remote debugging (version, first tab title, port).",
+            json!({}),
+            &[],
         ),
         tool(
             TOOL_STREAM_ENABLE,
             "Stream enable",
-            "Enable runtime WebSocket streaming.",
-            json!({ "port": { "type": "integer" } }),
+            "Start runtime WebSocket streaming, or update quality/fps of the running stream. Returns the ws:// URL.",
+            json!({
+                "port": { "type": "integer" },
+                "quality": { "type": "integer", "description": "JPEG quality, 1-100." },
+                "fps": { "type": "integer", "description": "Maximum frames per second, 1-60." }
+            }),
             &[],
         ),
         tool(
@@ -1737,6 +1894,27 @@ fn parity_tools() -> Vec<Value> {
             json!({ "offline": { "type": "boolean" }, "quick": { "type": "boolean" }, "fix": { "type": "boolean" }, "webgpu": { "type": "boolean", "description": "Also run a live WebGPU render probe (launches a second Chrome)." }, "headed": { "type": "boolean", "description": "Run the WebGPU probe headed to validate the capture path (auto-Xvfb on displayless Linux). Explicit true/false overrides AGENT_BROWSER_HEADED/config." }, "debug": { "type": "boolean", "description": "Verbose diagnostics from the probes' scratch daemons." } }),
             &[],
         ),
+        tool(
+            TOOL_SELFTEST,
+            "Self-test",
+            "Run navigate, snapshot, click, fill, get text, screenshot, and close against a built-in page in a scratch session, and report per-step pass/fail with durations.",
+            json!({}),
+            &[],
+        ),
+        tool(
+            TOOL_BUGREPORT,
+            "Bug report",
+            "Write a redacted bug report bundle (versions, platform, env, config, daemon log tail) and return its path.",
+            json!({ "out": { "type": "string", "description": "Output file path." }, "lines": { "type": "integer", "description": "Daemon log lines to include (default 200)." } }),
+            &[],
+        ),
+        tool(
+            TOOL_ENV,
+            "Environment",
+            "List every AGENT_BROWSER_* variable this version reads with its current value (secrets masked), and flag unrecognized ones as probable typos.",
+            json!({}),
+            &[],
+        ),
         tool(
             TOOL_DASHBOARD_START,
             "Dashboard start",
@@ -1966,6 +2144,7 @@ fn is_read_only_tool(name: &str) -> bool {
             | TOOL_STORAGE_GET
             | TOOL_COOKIES_GET
             | TOOL_TAB_LIST
+            | TOOL_EXTENSION_LIST
             | TOOL_DIALOG_STATUS
             | TOOL_CLIPBOARD_READ
             | TOOL_AUTH_LIST
@@ -1988,6 +2167,7 @@ fn is_read_only_tool(name: &str) -> bool {
             | TOOL_SKILLS_PATH
             | TOOL_PLUGIN_LIST
             | TOOL_PLUGIN_SHOW
+            | TOOL_ENV
     )
 }
 
@@ -2005,6 +2185,9 @@ fn is_open_world_tool(name: &str) -> bool {
             | TOOL_PLUGIN_LIST
             | TOOL_PLUGIN_SHOW
             | TOOL_DOCTOR
+            | TOOL_SELFTEST
+            | TOOL_BUGREPORT
+            | TOOL_ENV
             | TOOL_DASHBOARD_START
             | TOOL_DASHBOARD_STOP
             | TOOL_INSTALL
@@ -2087,11 +2270,19 @@ fn call_tool(params: Option<&Value>, config: &McpConfig) -> Result<Value, Protoc
         TOOL_DRAG => call_drag(arguments),
         TOOL_UPLOAD => call_upload(arguments),
         TOOL_DOWNLOAD => call_download(arguments),
+        TOOL_DOWNLOADS_LIST => call_cli_tool(arguments, vec!["downloads".to_string()], None),
+        TOOL_DOWNLOADS_OPEN => call_downloads_open(arguments),
+        TOOL_EXTENSION_LIST => call_literal(arguments, &["extension", "list"]),
+        TOOL_DOWNLOADS_CLEAR => call_cli_tool(
+            arguments,
+            vec!["downloads".to_string(), "clear".to_string()],
+            None,
+        ),
         TOOL_SCROLL => call_scroll(arguments),
         TOOL_SCROLL_INTO_VIEW => call_simple_selector(arguments, "scrollintoview"),
         TOOL_WAIT_MS => call_wait_ms(arguments),
         TOOL_WAIT_FOR_SELECTOR => call_wait_flag(arguments, None, "selector"),
-        TOOL_WAIT_FOR_TEXT => call_wait_flag(arguments, Some("--text"), "text"),
+        TOOL_WAIT_FOR_TEXT => call_wait_for_text(arguments),
         TOOL_WAIT_FOR_URL => call_wait_flag(arguments, Some("--url"), "url"),
         TOOL_WAIT_FOR_LOAD => call_wait_flag(arguments, Some("--load"), "state"),
         TOOL_WAIT_FOR_FUNCTION => call_wait_flag(arguments, Some("--fn"), "expression"),
@@ -2099,13 +2290,13 @@ fn call_tool(params: Option<&Value>, config: &McpConfig) -> Result<Value, Protoc
         TOOL_SCREENSHOT => call_screenshot(arguments),
         TOOL_PDF => call_one_string(arguments, "pdf", "path"),
         TOOL_GET_TEXT => call_get_selector(arguments, "text"),
-        TOOL_GET_HTML => call_get_selector(arguments, "html"),
+        TOOL_GET_HTML => call_get_html(arguments),
         TOOL_GET_VALUE => call_get_selector(arguments, "value"),
         TOOL_GET_ATTR => call_get_attr(arguments),
-        TOOL_GET_COUNT => call_get_selector(arguments, "count"),
-        TOOL_GET_BOX => call_get_selector(arguments, "box"),
-        TOOL_GET_STYLES => call_get_selector(arguments, "styles"),
-        TOOL_GET_URL => call_cli_tool(arguments, vec!["get".to_string(), "url".to_string()], None),
+        TOOL_GET_COUNT => call_get_count(arguments),
+        TOOL_GET_BOX => call_get_box(arguments),
+        TOOL_GET_STYLES => call_get_styles(arguments),
+        TOOL_GET_URL => call_get_url(arguments),
         TOOL_GET_TITLE => call_cli_tool(
             arguments,
             vec!["get".to_string(), "title".to_string()],
@@ -2166,7 +2357,7 @@ fn call_tool(params: Option<&Value>, config: &McpConfig) -> Result<Value, Protoc
         TOOL_DIALOG_STATUS => call_literal(arguments, &["dialog", "status"]),
         TOOL_DIALOG_ACCEPT => call_optional_one(arguments, &["dialog", "accept"], "text"),
         TOOL_DIALOG_DISMISS => call_literal(arguments, &["dialog", "dismiss"]),
-        TOOL_TRACE_START => call_literal(arguments, &["trace", "start"]),
+        TOOL_TRACE_START => call_trace_start(arguments),
         TOOL_TRACE_STOP => call_optional_one(arguments, &["trace", "stop"], "path"),
         TOOL_PROFILER_START => call_profiler_start(arguments),
         TOOL_PROFILER_STOP => call_optional_one(arguments, &["profiler", "stop"], "path"),
@@ -2186,13 +2377,15 @@ fn call_tool(params: Option<&Value>, config: &McpConfig) -> Result<Value, Protoc
         TOOL_AUTH_LIST => call_literal(arguments, &["auth", "list"]),
         TOOL_AUTH_SHOW => call_one_string(arguments, "auth show", "name"),
         TOOL_AUTH_DELETE => call_one_string(arguments, "auth delete", "name"),
-        TOOL_STATE_SAVE => call_one_string(arguments, "state save", "path"),
+        TOOL_STATE_SAVE => call_state_save(arguments),
         TOOL_STATE_LOAD => call_one_string(arguments, "state load", "path"),
         TOOL_STATE_LIST => call_literal(arguments, &["state", "list"]),
         TOOL_STATE_CLEAR => call_state_clear(arguments),
         TOOL_STATE_SHOW => call_one_string(arguments, "state show", "path"),
         TOOL_STATE_CLEAN => call_state_clean(arguments),
         TOOL_STATE_RENAME => call_state_rename(arguments),
+        TOOL_STATE_CHECKPOINT => call_optional_one(arguments, &["state", "checkpoint"], "label"),
+        TOOL_STATE_RESTORE => call_state_restore(arguments),
         TOOL_TAP => call_simple_selector(arguments, "tap"),
         TOOL_SWIPE => call_swipe(arguments),
         TOOL_DEVICE => call_device(arguments),
@@ -2200,6 +2393,7 @@ fn call_tool(params: Option<&Value>, config: &McpConfig) -> Result<Value, Protoc
         TOOL_DIFF_SCREENSHOT => call_diff_screenshot(arguments),
         TOOL_DIFF_URL => call_diff_url(arguments),
         TOOL_BATCH => call_batch(arguments),
+        TOOL_ACT => call_act(arguments),
         TOOL_REACT_TREE => call_react_tree(arguments),
         TOOL_REACT_INSPECT => call_react_inspect(arguments),
         TOOL_REACT_RENDERS_START => call_react_renders_start(arguments),
@@ -2211,9 +2405,14 @@ fn call_tool(params: Option<&Value>, config: &McpConfig) -> Result<Value, Protoc
         TOOL_REMOVE_INIT_SCRIPT => call_one_string(arguments, "removeinitscript", "id"),
         TOOL_CONFIRM => call_one_string(arguments, "confirm", "id"),
         TOOL_DENY => call_one_string(arguments, "deny", "id"),
-        TOOL_CONNECT => call_one_string(arguments, "connect", "target"),
+        TOOL_CONNECT => call_cli_tool(arguments, connect_args(arguments)?, None),
+        TOOL_CONNECT_LIST => call_cli_tool(
+            arguments,
+            vec!["connect".to_string(), "--list".to_string()],
+            None,
+        ),
         TOOL_STREAM_ENABLE => call_stream_enable(arguments),
-        TOOL_STREAM_DISABLE => call_literal(arguments, &["stream", "disable"]),
+        TOOL_STREAM_DISABLE => call_literal(arguments, &["stream", "stop"]),
         TOOL_STREAM_STATUS => call_literal(arguments, &["stream", "status"]),
         TOOL_SESSION => call_literal(arguments, &["session"]),
         TOOL_SESSION_LIST => call_literal(arguments, &["session", "list"]),
@@ -2228,6 +2427,9 @@ fn call_tool(params: Option<&Value>, config: &McpConfig) -> Result<Value, Protoc
         TOOL_PLUGIN_SHOW => call_one_string(arguments, "plugin show", "name"),
         TOOL_PLUGIN_RUN => call_plugin_run(arguments),
         TOOL_DOCTOR => call_doctor(arguments),
+        TOOL_SELFTEST => call_literal(arguments, &["selftest"]),
+        TOOL_BUGREPORT => call_bugreport(arguments),
+        TOOL_ENV => call_literal(arguments, &["env"]),
         TOOL_DASHBOARD_START => call_dashboard_start(arguments),
         TOOL_DASHBOARD_STOP => call_literal(arguments, &["dashboard", "stop"]),
         TOOL_INSTALL => call_install(arguments),
@@ -2362,6 +2564,18 @@ fn open_args(arguments: &Value) -> Result<Vec<String>, ProtocolError> {
             args.push(url);
         }
     }
+    if let Some(headers) = optional_value(arguments, "headers")? {
+        let headers = headers
+            .as_object()
+            .ok_or_else(|| ProtocolError::invalid_params("headers must be an object"))?;
+        for (name, value) in headers {
+            let value = value.as_str().ok_or_else(|| {
+                ProtocolError::invalid_params(format!("headers.{} must be a string", name))
+            })?;
+            args.push("--header".to_string());
+            args.push(format!("{}: {}", name, value));
+        }
+    }
     Ok(args)
 }
 
@@ -2418,6 +2632,12 @@ fn call_snapshot(arguments: &Value) -> Result<Value, ProtocolError> {
         args.push("-s".to_string());
         args.push(selector);
     }
+    if optional_bool(arguments, "stableRefs")?.unwrap_or(false) {
+        args.push("--stable-refs".to_string());
+    }
+    if optional_bool(arguments, "viewportOnly")?.unwrap_or(false) {
+        args.push("--viewport-only".to_string());
+    }
 
     call_cli_tool(arguments, args, None)
 }
@@ -2433,6 +2653,13 @@ fn click_command_args(arguments: &Value) -> Result<Vec<String>, ProtocolError> {
     if optional_bool(arguments, "newTab")?.unwrap_or(false) {
         args.push("--new-tab".to_string());
     }
+    if optional_bool(arguments, "waitForPopup")?.unwrap_or(false) {
+        args.push("--wait-for-popup".to_string());
+    }
+    if let Some(ms) = optional_u64(arguments, "popupTimeout")? {
+        args.push("--popup-timeout".to_string());
+        args.push(ms.to_string());
+    }
     Ok(args)
 }
 
@@ -2468,7 +2695,20 @@ fn call_press(arguments: &Value) -> Result<Value, ProtocolError> {
 fn call_drag(arguments: &Value) -> Result<Value, ProtocolError> {
     let source = required_string(arguments, "source")?;
     let target = required_string(arguments, "target")?;
-    call_cli_tool(arguments, vec!["drag".to_string(), source, target], None)
+    let mut args = vec!["drag".to_string(), source, target];
+    for (key, flag) in [
+        ("sourceOffset", "--source-offset"),
+        ("targetOffset", "--target-offset"),
+    ] {
+        if let Some(value) = optional_string(arguments, key)? {
+            args.push(flag.to_string());
+            args.push(value);
+        }
+    }
+    if optional_bool(arguments, "html5")?.unwrap_or(false) {
+        args.push("--html5".to_string());
+    }
+    call_cli_tool(arguments, args, None)
 }
 
 fn call_upload(arguments: &Value) -> Result<Value, ProtocolError> {
@@ -2476,6 +2716,12 @@ fn call_upload(arguments: &Value) -> Result<Value, ProtocolError> {
     let files = required_string_array(arguments, "files")?;
     let mut args = vec!["upload".to_string(), selector];
     args.extend(files);
+    if let Some(size) = optional_string(arguments, "maxFetchSize")? {
+        args.extend(["--max-fetch-size".to_string(), size]);
+    }
+    if optional_bool(arguments, "keepTemp")?.unwrap_or(false) {
+        args.push("--keep-temp".to_string());
+    }
     call_cli_tool(arguments, args, None)
 }
 
@@ -2489,6 +2735,41 @@ fn call_download(arguments: &Value) -> Result<Value, ProtocolError> {
     )
 }
 
+fn connect_args(arguments: &Value) -> Result<Vec<String>, ProtocolError> {
+    let mut args = vec!["connect".to_string()];
+    if let Some(target) = optional_string(arguments, "target")? {
+        args.push(target);
+        return Ok(args);
+    }
+    if let Some(title) = optional_string(arguments, "match")? {
+        args.push("--match".to_string());
+        args.push(title);
+    }
+    if let Some(port) = optional_u64(arguments, "port")? {
+        args.push("--port".to_string());
+        args.push(port.to_string());
+    }
+    if args.len() == 1 {
+        return Err(ProtocolError::invalid_params(
+            "target, match, or port is required",
+        ));
+    }
+    Ok(args)
+}
+
+fn call_downloads_open(arguments: &Value) -> Result<Value, ProtocolError> {
+    let index = required_u64(arguments, "index")?;
+    let mut args = vec![
+        "downloads".to_string(),
+        "open".to_string(),
+        index.to_string(),
+    ];
+    if optional_bool(arguments, "reveal")?.unwrap_or(false) {
+        args.push("--reveal".to_string());
+    }
+    call_cli_tool(arguments, args, None)
+}
+
 fn call_select(arguments: &Value) -> Result<Value, ProtocolError> {
     let selector = required_string(arguments, "selector")?;
     let values = required_string_array(arguments, "values")?;
@@ -2533,6 +2814,26 @@ fn call_wait_flag(
     call_cli_tool(arguments, args, None)
 }
 
+fn call_wait_for_text(arguments: &Value) -> Result<Value, ProtocolError> {
+    let text = required_string(arguments, "text")?;
+    let mut args = vec!["wait".to_string(), "--text".to_string(), text];
+    if let Some(selector) = optional_string(arguments, "selector")? {
+        args.push("--selector".to_string());
+        args.push(selector);
+    }
+    if optional_bool(arguments, "exact")?.unwrap_or(false) {
+        args.push("--exact".to_string());
+    }
+    if optional_bool(arguments, "not")?.unwrap_or(false) {
+        args.push("--not".to_string());
+    }
+    if let Some(timeout) = optional_u64(arguments, "waitTimeoutMs")? {
+        args.push("--timeout".to_string());
+        args.push(timeout.to_string());
+    }
+    call_cli_tool(arguments, args, None)
+}
+
 fn call_wait_download(arguments: &Value) -> Result<Value, ProtocolError> {
     let mut args = vec!["wait".to_string(), "--download".to_string()];
     if let Some(path) = optional_string(arguments, "path")? {
@@ -2585,6 +2886,65 @@ fn call_get_selector(arguments: &Value, what: &str) -> Result<Value, ProtocolErr
     )
 }
 
+fn call_get_styles(arguments: &Value) -> Result<Value, ProtocolError> {
+    let selector = required_string(arguments, "selector")?;
+    let mut args = vec!["get".to_string(), "styles".to_string(), selector];
+    if let Some(properties) = optional_string_array(arguments, "properties")? {
+        args.push("--properties".to_string());
+        args.push(properties.join(","));
+    }
+    call_cli_tool(arguments, args, None)
+}
+
+fn call_get_html(arguments: &Value) -> Result<Value, ProtocolError> {
+    let selector = required_string(arguments, "selector")?;
+    let mut args = vec!["get".to_string(), "html".to_string(), selector];
+    if optional_bool(arguments, "outer")?.unwrap_or(false) {
+        args.push("--outer".to_string());
+    }
+    if optional_bool(arguments, "stripAttrs")?.unwrap_or(false) {
+        args.push("--strip-attrs".to_string());
+    }
+    if let Some(depth) = optional_u64(arguments, "maxDepth")? {
+        args.push("--max-depth".to_string());
+        args.push(depth.to_string());
+    }
+    call_cli_tool(arguments, args, None)
+}
+
+fn call_get_url(arguments: &Value) -> Result<Value, ProtocolError> {
+    let mut args = vec!["get".to_string(), "url".to_string()];
+    if optional_bool(arguments, "parts")?.unwrap_or(false) {
+        args.push("--parts".to_string());
+    }
+    if let Some(name) = optional_string(arguments, "query")? {
+        args.push("--query".to_string());
+        args.push(name);
+    }
+    call_cli_tool(arguments, args, None)
+}
+
+fn call_get_box(arguments: &Value) -> Result<Value, ProtocolError> {
+    let selector = required_string(arguments, "selector")?;
+    let mut args = vec!["get".to_string(), "box".to_string(), selector];
+    if optional_bool(arguments, "viewportRelative")?.unwrap_or(false) {
+        args.push("--viewport-relative".to_string());
+    }
+    if optional_bool(arguments, "visibility")?.unwrap_or(false) {
+        args.push("--visibility".to_string());
+    }
+    call_cli_tool(arguments, args, None)
+}
+
+fn call_get_count(arguments: &Value) -> Result<Value, ProtocolError> {
+    let selector = required_string(arguments, "selector")?;
+    let mut args = vec!["get".to_string(), "count".to_string(), selector];
+    if optional_bool(arguments, "visibleOnly")?.unwrap_or(false) {
+        args.push("--visible-only".to_string());
+    }
+    call_cli_tool(arguments, args, None)
+}
+
 fn call_get_attr(arguments: &Value) -> Result<Value, ProtocolError> {
     let selector = required_string(arguments, "selector")?;
     let name = required_string(arguments, "name")?;
@@ -2597,9 +2957,15 @@ fn call_get_attr(arguments: &Value) -> Result<Value, ProtocolError> {
 
 fn call_is(arguments: &Value, what: &str) -> Result<Value, ProtocolError> {
     let selector = required_string(arguments, "selector")?;
+    // A false result is an answer, not a tool error.
     call_cli_tool(
         arguments,
-        vec!["is".to_string(), what.to_string(), selector],
+        vec![
+            "--no-exit-code".to_string(),
+            "is".to_string(),
+            what.to_string(),
+            selector,
+        ],
         None,
     )
 }
@@ -2632,6 +2998,14 @@ fn call_find(arguments: &Value) -> Result<Value, ProtocolError> {
     if exact {
         args.push("--exact".to_string());
     }
+    if let Some(wait) = optional_u64(arguments, "wait")? {
+        args.push("--wait".to_string());
+        args.push(wait.to_string());
+    }
+    if let Some(state) = optional_string(arguments, "state")? {
+        args.push("--state".to_string());
+        args.push(state);
+    }
     call_cli_tool(arguments, args, None)
 }
 
@@ -2889,6 +3263,24 @@ fn call_tab_new(arguments: &Value) -> Result<Value, ProtocolError> {
     call_cli_tool(arguments, args, None)
 }
 
+fn call_trace_start(arguments: &Value) -> Result<Value, ProtocolError> {
+    let mut args = vec!["trace".to_string(), "start".to_string()];
+    for key in ["screenshots", "snapshots", "sources"] {
+        if let Some(on) = optional_bool(arguments, key)? {
+            args.push(if on {
+                format!("--{}", key)
+            } else {
+                format!("--no-{}", key)
+            });
+        }
+    }
+    if let Some(title) = optional_string(arguments, "title")? {
+        args.push("--title".to_string());
+        args.push(title);
+    }
+    call_cli_tool(arguments, args, None)
+}
+
 fn call_profiler_start(arguments: &Value) -> Result<Value, ProtocolError> {
     let mut args = vec!["profiler".to_string(), "start".to_string()];
     if let Some(categories) = optional_string(arguments, "categories")? {
@@ -2912,6 +3304,19 @@ fn call_clearable(arguments: &Value, command: &str) -> Result<Value, ProtocolErr
     if optional_bool(arguments, "clear")?.unwrap_or(false) {
         args.push("--clear".to_string());
     }
+    for (key, flag) in [
+        ("since", "--since"),
+        ("after", "--after"),
+        ("grep", "--grep"),
+    ] {
+        if let Some(value) = optional_string(arguments, key)? {
+            args.push(flag.to_string());
+            args.push(value);
+        }
+    }
+    if optional_bool(arguments, "stack")?.unwrap_or(false) {
+        args.push("--stack".to_string());
+    }
     call_cli_tool(arguments, args, None)
 }
 
@@ -2942,6 +3347,20 @@ fn call_auth_save(arguments: &Value) -> Result<Value, ProtocolError> {
     call_cli_tool(arguments, args, Some(password))
 }
 
+fn call_state_save(arguments: &Value) -> Result<Value, ProtocolError> {
+    let mut args = vec![
+        "state".to_string(),
+        "save".to_string(),
+        required_string(arguments, "path")?,
+    ];
+    match optional_bool(arguments, "encrypt")? {
+        Some(true) => args.push("--encrypt".to_string()),
+        Some(false) => args.push("--no-encrypt".to_string()),
+        None => {}
+    }
+    call_cli_tool(arguments, args, None)
+}
+
 fn call_state_clear(arguments: &Value) -> Result<Value, ProtocolError> {
     let mut args = vec!["state".to_string(), "clear".to_string()];
     if optional_bool(arguments, "all")?.unwrap_or(false) {
@@ -2982,6 +3401,21 @@ fn call_state_rename(arguments: &Value) -> Result<Value, ProtocolError> {
     )
 }
 
+fn call_state_restore(arguments: &Value) -> Result<Value, ProtocolError> {
+    if optional_bool(arguments, "list")?.unwrap_or(false) {
+        return call_cli_tool(
+            arguments,
+            vec![
+                "state".to_string(),
+                "restore".to_string(),
+                "--list".to_string(),
+            ],
+            None,
+        );
+    }
+    call_optional_one(arguments, &["state", "restore"], "label")
+}
+
 fn call_session_id(arguments: &Value) -> Result<Value, ProtocolError> {
     let mut args = vec![
         "session".to_string(),
@@ -3010,7 +3444,10 @@ fn call_swipe(arguments: &Value) -> Result<Value, ProtocolError> {
 
 fn call_device(arguments: &Value) -> Result<Value, ProtocolError> {
     let action = optional_string(arguments, "action")?.unwrap_or_else(|| "list".to_string());
-    let args = vec!["device".to_string(), action];
+    let mut args = vec!["device".to_string(), action.clone()];
+    if action != "list" {
+        args.push(required_string(arguments, "device")?);
+    }
     call_cli_tool(arguments, args, None)
 }
 
@@ -3031,6 +3468,9 @@ fn call_diff_snapshot(arguments: &Value) -> Result<Value, ProtocolError> {
         args.push("--depth".to_string());
         args.push(depth.to_string());
     }
+    if optional_bool(arguments, "stableRefs")?.unwrap_or(false) {
+        args.push("--stable-refs".to_string());
+    }
     call_cli_tool(arguments, args, None)
 }
 
@@ -3119,6 +3559,33 @@ fn call_batch(arguments: &Value) -> Result<Value, ProtocolError> {
     call_cli_tool(arguments, args, Some(stdin))
 }
 
+fn call_act(arguments: &Value) -> Result<Value, ProtocolError> {
+    let mut args = vec!["act".to_string()];
+    if optional_bool(arguments, "noFinalSnapshot")?.unwrap_or(false) {
+        args.push("--no-final-snapshot".to_string());
+    }
+    call_cli_tool(arguments, args, Some(act_steps_json(arguments)?))
+}
+
+/// Steps arrive as arrays of CLI words; `act` reads `{"command": [...]}`
+/// objects from stdin.
+fn act_steps_json(arguments: &Value) -> Result<String, ProtocolError> {
+    let steps = optional_value(arguments, "steps")?
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| ProtocolError::invalid_params("steps must be an array"))?;
+    let mut objects = Vec::with_capacity(steps.len());
+    for (i, step) in steps.iter().enumerate() {
+        let words = step
+            .as_array()
+            .filter(|items| items.iter().all(|item| item.is_string()))
+            .ok_or_else(|| {
+                ProtocolError::invalid_params(format!("steps[{}] must be an array of strings", i))
+            })?;
+        objects.push(json!({ "command": words }));
+    }
+    Ok(Value::Array(objects).to_string())
+}
+
 fn call_react_tree(arguments: &Value) -> Result<Value, ProtocolError> {
     let mut args = vec!["react".to_string(), "tree".to_string()];
     append_react_raw_json_arg(arguments, &mut args)?;
@@ -3202,10 +3669,12 @@ fn call_a11y(arguments: &Value) -> Result<Value, ProtocolError> {
 }
 
 fn call_stream_enable(arguments: &Value) -> Result<Value, ProtocolError> {
-    let mut args = vec!["stream".to_string(), "enable".to_string()];
-    if let Some(port) = optional_u64(arguments, "port")? {
-        args.push("--port".to_string());
-        args.push(port.to_string());
+    let mut args = vec!["stream".to_string(), "start".to_string()];
+    for key in ["port", "quality", "fps"] {
+        if let Some(value) = optional_u64(arguments, key)? {
+            args.push(format!("--{}", key));
+            args.push(value.to_string());
+        }
     }
     call_cli_tool(arguments, args, None)
 }
@@ -3300,6 +3769,19 @@ fn call_doctor(arguments: &Value) -> Result<Value, ProtocolError> {
     call_cli_tool(arguments, args, None)
 }
 
+fn call_bugreport(arguments: &Value) -> Result<Value, ProtocolError> {
+    let mut args = vec!["bugreport".to_string()];
+    if let Some(out) = optional_string(arguments, "out")? {
+        args.push("--out".to_string());
+        args.push(out);
+    }
+    if let Some(lines) = optional_u64(arguments, "lines")? {
+        args.push("--lines".to_string());
+        args.push(lines.to_string());
+    }
+    call_cli_tool(arguments, args, None)
+}
+
 fn call_dashboard_start(arguments: &Value) -> Result<Value, ProtocolError> {
     let mut args = vec!["dashboard".to_string(), "start".to_string()];
     if let Some(port) = optional_u64(arguments, "port")? {
@@ -3847,6 +4329,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn open_args_forwards_headers() {
+        assert_eq!(
+            open_args(&json!({
+                "url": "https://api.example.com",
+                "headers": { "Authorization": "Bearer t" }
+            }))
+            .unwrap(),
+            vec![
+                "open",
+                "https://api.example.com",
+                "--header",
+                "Authorization: Bearer t"
+            ]
+        );
+        assert!(open_args(&json!({ "url": "x.com", "headers": { "A": 1 } })).is_err());
+    }
+
+    #[test]
+    fn connect_args_accepts_target_or_scan_filters() {
+        assert_eq!(
+            connect_args(&json!({ "target": "9222" })).unwrap(),
+            vec!["connect", "9222"]
+        );
+        assert_eq!(
+            connect_args(&json!({ "match": "Inbox", "port": 9230 })).unwrap(),
+            vec!["connect", "--match", "Inbox", "--port", "9230"]
+        );
+        assert!(connect_args(&json!({})).is_err());
+    }
+
+    #[test]
+    fn act_steps_json_wraps_words_in_command_objects() {
+        let steps = act_steps_json(&json!({ "steps": [["click", "@e1"], ["press", "Enter"]] }));
+        assert_eq!(
+            serde_json::from_str::<Value>(&steps.unwrap()).unwrap(),
+            json!([{ "command": ["click", "@e1"] }, { "command": ["press", "Enter"] }])
+        );
+        assert!(act_steps_json(&json!({ "steps": [["click", 1]] })).is_err());
+        assert!(act_steps_json(&json!({})).is_err());
+    }
+
     #[test]
     fn doctor_tool_exposes_webgpu_option() {
         let tools = tools();
@@ -4079,6 +4603,27 @@ mod tests {
         assert_eq!(args, vec!["click", "@e1", "--new-tab"]);
     }
 
+    #[test]
+    fn click_command_args_include_popup_wait() {
+        let args = click_command_args(&json!({
+            "selector": "@e2",
+            "waitForPopup": true,
+            "popupTimeout": 2000,
+        }))
+        .unwrap();
+
+        assert_eq!(
+            args,
+            vec![
+                "click",
+                "@e2",
+                "--wait-for-popup",
+                "--popup-timeout",
+                "2000"
+            ]
+        );
+    }
+
     #[test]
     fn react_json_uses_command_local_raw_json_flag() {
         let mut args = vec!["react".to_string(), "tree".to_string()];
@@ -9,7 +9,10 @@ use std::sync::Arc;
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use tokio::sync::{broadcast, oneshot, RwLock};
 
-use crate::connection::{get_socket_dir, INTERNAL_DAEMON_SHUTDOWN_ACTION};
+use crate::atomic_write::write_atomic;
+use crate::connection::{
+    get_socket_dir, get_timeouts_path, SessionTimeouts, INTERNAL_DAEMON_SHUTDOWN_ACTION,
+};
 use crate::validation::{is_valid_session_name, session_name_error};
 
 use super::a11y;
@@ -25,10 +28,12 @@ use super::cdp::types::{
 };
 use super::cookies;
 use super::diff;
+use super::downloads::DownloadLog;
 use super::element::RefMap;
+use super::extensions;
 use super::inspect_server::InspectServer;
 use super::interaction;
-use super::network::{self, DomainFilter, EventTracker};
+use super::network::{self, DomainFilter, EventTracker, LogBuffer};
 use super::policy::{ActionPolicy, ConfirmActions, PolicyResult};
 use super::providers;
 use super::react;
@@ -37,8 +42,8 @@ use super::screenshot::{self, ScreenshotOptions};
 use super::snapshot::{self, SnapshotOptions};
 use super::state;
 use super::storage;
-use super::stream::{self, StreamServer};
-use super::tracing::{self as native_tracing, TracingState};
+use super::stream::{self, StreamServer, StreamSettings};
+use super::tracing::{self as native_tracing, TraceOptions, TracingState};
 use super::webdriver::appium::AppiumManager;
 use super::webdriver::backend::{BrowserBackend, WebDriverBackend, WEBDRIVER_UNSUPPORTED_ACTIONS};
 use super::webdriver::ios;
@@ -118,16 +123,21 @@ pub enum HarContentMode {
     Text,
     /// Every body; binary content is embedded base64-encoded.
     All,
+    /// Every body, written to its own file next to the HAR and referenced
+    /// from `content._file`, so the HAR itself stays small.
+    Attach,
 }
 
 impl HarContentMode {
     pub fn parse(s: &str) -> Result<Self, String> {
+        // `omit` and `embed` are Playwright's names for `none` and `all`.
         match s {
-            "none" => Ok(Self::None),
+            "none" | "omit" => Ok(Self::None),
             "text" => Ok(Self::Text),
-            "all" => Ok(Self::All),
+            "all" | "embed" => Ok(Self::All),
+            "attach" => Ok(Self::Attach),
             other => Err(format!(
-                "Invalid HAR content mode '{}'. Valid options: all, text, none",
+                "Invalid HAR content mode '{}'. Valid options: all, text, none, omit, embed, attach",
                 other
             )),
         }
@@ -175,6 +185,15 @@ pub struct TrackedRequest {
     pub response_headers: Option<Value>,
     #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
     pub mime_type: Option<String>,
+    /// Bytes received over the network, set by `Network.loadingFinished`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Milliseconds from request to `Network.loadingFinished`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// CDP monotonic timestamp (seconds) of `Network.requestWillBeSent`.
+    #[serde(skip)]
+    pub started: Option<f64>,
 }
 
 pub struct FetchPausedRequest {
@@ -335,6 +354,8 @@ fn launch_connection_is_external(
 pub struct DaemonState {
     pub browser: Option<BrowserManager>,
     pub appium: Option<AppiumManager>,
+    /// Simulator picked with `device use`; the next iOS launch uses it.
+    pub ios_device_udid: Option<String>,
     pub safari_driver: Option<safari::SafariDriverProcess>,
     pub webdriver_backend: Option<super::webdriver::backend::WebDriverBackend>,
     pub backend_type: BackendType,
@@ -370,14 +391,18 @@ pub struct DaemonState {
     pub har_recording: bool,
     pub har_entries: Vec<HarEntry>,
     pub har_content_mode: HarContentMode,
+    /// Path given to `har start`, used by a `har stop` without one.
+    pub har_path: Option<String>,
     /// Bytes of response bodies embedded so far this recording; enforces
     /// [`HAR_MAX_TOTAL_BODY_BYTES`].
     pub har_body_total_bytes: usize,
     pub confirm_actions: Option<ConfirmActions>,
     pub inspect_server: Option<InspectServer>,
     pub routes: Arc<RwLock<Vec<RouteEntry>>>,
-    pub tracked_requests: Vec<TrackedRequest>,
+    pub tracked_requests: LogBuffer<TrackedRequest>,
     pub request_tracking: bool,
+    /// Files downloaded this session, for `downloads list|clear|open`.
+    pub downloads: DownloadLog,
     pub active_frame_id: Option<String>,
     /// Cross-origin iframe frame_id → dedicated CDP session_id.
     /// Populated by Target.attachedToTarget events from Target.setAutoAttach.
@@ -389,13 +414,24 @@ pub struct DaemonState {
     /// Network tracking uses this subset so background-tab iframe traffic is
     /// not mixed into the active tab's request list or HAR capture.
     pub active_iframe_sessions: HashSet<String>,
-    /// Origin-scoped extra HTTP headers set via `--headers` on navigate.
+    /// Origin-scoped extra HTTP headers set via `--headers` on navigate or
+    /// `set credentials --origin`.
     /// Key is the origin (scheme + host + port), value is the headers map.
     /// Wrapped in Arc<RwLock<>> so the background Fetch handler can read it.
     pub origin_headers: Arc<RwLock<HashMap<String, HashMap<String, String>>>>,
+    /// Headers from `set headers`, sent to every origin.
+    pub extra_http_headers: HashMap<String, String>,
+    /// `Basic ...` Authorization from unscoped `set credentials`, sent to
+    /// every origin alongside `extra_http_headers`.
+    pub basic_auth_header: Option<String>,
     /// Proxy authentication credentials (username, password) for handling
     /// Fetch.authRequired events from authenticated proxies.
     pub proxy_credentials: Arc<RwLock<Option<(String, String)>>>,
+    /// Runtime `set tls ignore-errors` override; None keeps the launch setting.
+    pub ignore_https_errors: Option<bool>,
+    /// Origins from `--ignore-https-origin` and `set tls allow-origin` whose
+    /// certificate errors are accepted. Read by the background Fetch handler.
+    pub ignore_https_origins: Arc<RwLock<HashSet<String>>>,
     /// Background task that processes Fetch.requestPaused events in real-time,
     /// handling domain filtering, route interception, and origin-scoped headers
     /// without deadlocking navigation/evaluate.
@@ -423,8 +459,12 @@ pub struct DaemonState {
     network_auto_attach_installed: bool,
     /// Browser engine name (e.g. "chrome", "lightpanda") for observability.
     pub engine: String,
-    /// Default timeout for wait operations, from AGENT_BROWSER_DEFAULT_TIMEOUT env var.
+    /// Default timeout for wait operations, from AGENT_BROWSER_DEFAULT_TIMEOUT env var
+    /// or `set timeout`.
     pub default_timeout_ms: u64,
+    /// Navigation timeout from `set timeout --navigation`. Kept here rather
+    /// than only on the browser so a relaunched browser picks it up too.
+    pub navigation_timeout_ms: Option<u64>,
     /// Last viewport settings (width, height, deviceScaleFactor, mobile),
     /// re-applied to new contexts (e.g., recording).
     pub viewport: Option<(i32, i32, f64, bool)>,
@@ -441,6 +481,7 @@ impl DaemonState {
         Self {
             browser: None,
             appium: None,
+            ios_device_udid: None,
             safari_driver: None,
             webdriver_backend: None,
             backend_type: BackendType::Cdp,
@@ -478,17 +519,23 @@ impl DaemonState {
             har_recording: false,
             har_entries: Vec::new(),
             har_content_mode: HarContentMode::default(),
+            har_path: None,
             har_body_total_bytes: 0,
             confirm_actions: ConfirmActions::from_env(),
             inspect_server: None,
             routes: Arc::new(RwLock::new(Vec::new())),
-            tracked_requests: Vec::new(),
+            tracked_requests: LogBuffer::new(network::DEFAULT_BUFFER_LIMIT),
             request_tracking: false,
+            downloads: DownloadLog::new(),
             active_frame_id: None,
             iframe_sessions: HashMap::new(),
             active_iframe_sessions: HashSet::new(),
             origin_headers: Arc::new(RwLock::new(HashMap::new())),
+            extra_http_headers: HashMap::new(),
+            basic_auth_header: None,
             proxy_credentials: Arc::new(RwLock::new(None)),
+            ignore_https_errors: None,
+            ignore_https_origins: Arc::new(RwLock::new(ignore_https_origins_from_env())),
             fetch_handler_task: None,
             dialog_handler_task: None,
             mouse_state: MouseState::default(),
@@ -510,6 +557,7 @@ impl DaemonState {
                 .ok()
                 .and_then(|s| s.parse::<u64>().ok())
                 .unwrap_or(25_000),
+            navigation_timeout_ms: None,
             viewport: None,
             plugin_init_scripts: Vec::new(),
             active_provider_session: None,
@@ -572,10 +620,37 @@ impl DaemonState {
         let routes = self.routes.clone();
         let origin_headers = self.origin_headers.clone();
         let proxy_credentials = self.proxy_credentials.clone();
+        let ignore_https_origins = self.ignore_https_origins.clone();
 
         self.fetch_handler_task = Some(tokio::spawn(async move {
             loop {
                 match rx.recv().await {
+                    Ok(event) if event.method == "Security.certificateError" => {
+                        let Some(event_id) = event.params.get("eventId").and_then(|v| v.as_i64())
+                        else {
+                            continue;
+                        };
+                        let origin = event
+                            .params
+                            .get("requestURL")
+                            .and_then(|v| v.as_str())
+                            .and_then(|u| url::Url::parse(u).ok())
+                            .map(|u| u.origin().ascii_serialization());
+                        let allowed = match origin {
+                            Some(ref origin) => ignore_https_origins.read().await.contains(origin),
+                            None => false,
+                        };
+                        let _ = client
+                            .send_command(
+                                "Security.handleCertificateError",
+                                Some(json!({
+                                    "eventId": event_id,
+                                    "action": if allowed { "continue" } else { "cancel" },
+                                })),
+                                event.session_id.as_deref(),
+                            )
+                            .await;
+                    }
                     Ok(event) if event.method == "Fetch.authRequired" => {
                         let request_id = event
                             .params
@@ -1159,7 +1234,7 @@ impl DaemonState {
                 let wanted = match mode {
                     HarContentMode::None => false,
                     HarContentMode::Text => har_mime_is_text(&entry.mime_type),
-                    HarContentMode::All => true,
+                    HarContentMode::All | HarContentMode::Attach => true,
                 };
                 if wanted {
                     to_fetch.push((request_id.clone(), session_id.clone()));
@@ -1326,6 +1401,37 @@ impl DaemonState {
                             }
                             continue;
                         }
+                        "Browser.downloadWillBegin" => {
+                            let param = |key: &str| {
+                                event.params.get(key).and_then(|v| v.as_str()).unwrap_or("")
+                            };
+                            let download_dir = self
+                                .browser
+                                .as_ref()
+                                .and_then(|b| b.download_path.as_deref())
+                                .map(std::path::Path::new);
+                            self.downloads.begin(
+                                param("guid"),
+                                param("url"),
+                                param("suggestedFilename"),
+                                download_dir,
+                            );
+                            continue;
+                        }
+                        "Browser.downloadProgress" => {
+                            if let (Some(guid), Some(progress)) = (
+                                event.params.get("guid").and_then(|v| v.as_str()),
+                                event.params.get("state").and_then(|v| v.as_str()),
+                            ) {
+                                let total = event
+                                    .params
+                                    .get("totalBytes")
+                                    .and_then(|v| v.as_f64())
+                                    .map(|n| n as u64);
+                                self.downloads.progress(guid, progress, total);
+                            }
+                            continue;
+                        }
                         "Target.detachedFromTarget" => {
                             if let Some(sid) =
                                 event.params.get("sessionId").and_then(|v| v.as_str())
@@ -1388,7 +1494,7 @@ impl DaemonState {
                                     .unwrap_or(&details.text);
                                 self.event_tracker.add_error(
                                     text,
-                                    None,
+                                    details.url.as_deref(),
                                     details.line_number,
                                     details.column_number,
                                 );
@@ -1490,6 +1596,12 @@ impl DaemonState {
                                         status: None,
                                         response_headers: None,
                                         mime_type: None,
+                                        size: None,
+                                        duration: None,
+                                        started: event
+                                            .params
+                                            .get("timestamp")
+                                            .and_then(|v| v.as_f64()),
                                     });
                                 }
                             }
@@ -1566,7 +1678,9 @@ impl DaemonState {
                                 }
                             }
                         }
-                        "Network.loadingFinished" if self.har_recording => {
+                        "Network.loadingFinished"
+                            if self.har_recording || self.request_tracking =>
+                        {
                             let request_id = event
                                 .params
                                 .get("requestId")
@@ -1578,11 +1692,26 @@ impl DaemonState {
                                 .get("encodedDataLength")
                                 .and_then(|v| v.as_i64());
                             if let Some(entry) = self
-                                .har_entries
+                                .tracked_requests
                                 .iter_mut()
                                 .rev()
                                 .find(|e| e.request_id == request_id)
                             {
+                                entry.size =
+                                    encoded_data_length.and_then(|n| u64::try_from(n).ok());
+                                entry.duration = timestamp
+                                    .zip(entry.started)
+                                    .map(|(end, start)| ((end - start) * 1000.0).max(0.0));
+                            }
+                            let har_entry = if self.har_recording {
+                                self.har_entries
+                                    .iter_mut()
+                                    .rev()
+                                    .find(|e| e.request_id == request_id)
+                            } else {
+                                None
+                            };
+                            if let Some(entry) = har_entry {
                                 if let Some(ts) = timestamp {
                                     entry.loading_finished_timestamp = Some(ts);
                                 }
@@ -1965,7 +2094,7 @@ fn provider_plugin_launch_options_from_command(cmd: &Value) -> Value {
     if let Some(headless) = cmd.get("headless").and_then(|v| v.as_bool()) {
         options.insert("headed".to_string(), json!(!headless));
     }
-    for key in ["engine", "userAgent", "colorScheme"] {
+    for key in ["engine", "userAgent", "colorScheme", "providerOptions"] {
         if let Some(value) = cmd.get(key) {
             options.insert(key.to_string(), value.clone());
         }
@@ -1994,14 +2123,25 @@ fn skip_launch_action(action: &str) -> bool {
             | "auth_list"
             | "confirm"
             | "deny"
+            | "timeout"
+            | "tls_config"
+            | "buffer_config"
             | "state_list"
             | "state_show"
             | "state_clear"
             | "state_clean"
             | "state_rename"
+            | "state_checkpoints"
             | "device_list"
+            | "device_use"
+            | "device_boot"
+            | "downloads_list"
+            | "downloads_clear"
+            | "downloads_open"
             | "stream_enable"
             | "stream_disable"
+            | "stream_start"
+            | "stream_stop"
             | "stream_status"
             | "session_info"
     )
@@ -2082,7 +2222,7 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
     }
 
     if action == INTERNAL_DAEMON_SHUTDOWN_ACTION {
-        let mut resp = match handle_close(state).await {
+        let mut resp = match handle_close(cmd, state).await {
             Ok(data) => success_response(&id, data),
             Err(e) => error_response(&id, &super::browser::to_ai_friendly_error(&e)),
         };
@@ -2124,6 +2264,19 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
     // element::set_active_frame for why this is mirrored).
     super::element::set_active_frame(state.active_frame_id.as_deref());
 
+    // A CLI retry after a detached element (--auto-retry-actions): the ref's
+    // cached node is the one the page replaced, so look it up again by the
+    // role and name it was recorded with.
+    if cmd.get("refreshRef").and_then(|v| v.as_bool()) == Some(true) {
+        if let Some(ref_id) = cmd
+            .get("selector")
+            .and_then(|v| v.as_str())
+            .and_then(super::element::parse_ref)
+        {
+            state.ref_map.forget_node(&ref_id);
+        }
+    }
+
     let skip_launch = skip_launch_action(action);
     let restore_key_change_needs_launch = !skip_launch
         && command_changes_restore_key(cmd, state)
@@ -2241,6 +2394,18 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
             if mgr.page_count() == 0 {
                 let _ = mgr.ensure_page().await;
             }
+            if let Some(timeout_ms) = state.navigation_timeout_ms {
+                mgr.set_default_timeout_ms(timeout_ms);
+            }
+            // Re-apply `set tls` to a relaunched browser.
+            if let Some(ignore) = state.ignore_https_errors {
+                if mgr.ignore_https_errors != ignore {
+                    let _ = mgr.set_ignore_https_errors(ignore).await;
+                }
+            }
+            if !mgr.certificate_overrides() && !state.ignore_https_origins.read().await.is_empty() {
+                let _ = mgr.enable_certificate_overrides().await;
+            }
         }
     }
 
@@ -2305,7 +2470,20 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
         }
     }
 
-    let result = match action {
+    // Looked up before the action runs: a click can navigate and leave the
+    // ref map describing a page that is gone.
+    let described = if cmd.get("describe").and_then(|v| v.as_bool()) == Some(true) {
+        cmd.get("selector")
+            .and_then(|v| v.as_str())
+            .and_then(|s| super::element::describe_target(&state.ref_map, s))
+    } else {
+        None
+    };
+
+    // Set when the failure is the request itself rather than the page or
+    // browser, so the CLI can point at a version mismatch.
+    let mut error_kind = None;
+    let mut result = match action {
         "launch" => handle_launch(cmd, state).await,
         "navigate" => handle_navigate(cmd, state).await,
         "read" => handle_read(cmd, state).await,
@@ -2315,7 +2493,7 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
         "title" => handle_title(state).await,
         "content" => handle_content(state).await,
         "evaluate" => handle_evaluate(cmd, state).await,
-        "close" => handle_close(state).await,
+        "close" => handle_close(cmd, state).await,
         "snapshot" => handle_snapshot(cmd, state).await,
         "screenshot" => handle_screenshot(cmd, state).await,
         "click" => handle_click(cmd, state).await,
@@ -2345,17 +2523,23 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
         "storage_clear" => handle_storage_clear(cmd, state).await,
         "setcontent" => handle_setcontent(cmd, state).await,
         "headers" => handle_headers(cmd, state).await,
+        "timeout" => handle_timeout(cmd, state),
+        "tls_config" => handle_tls_config(cmd, state).await,
         "offline" => handle_offline(cmd, state).await,
         "console" => handle_console(cmd, state).await,
-        "errors" => handle_errors(state).await,
+        "errors" => handle_errors(cmd, state).await,
+        "buffer_config" => handle_buffer_config(cmd, state).await,
         "session_info" => handle_session_info(state).await,
         "state_save" => handle_state_save(cmd, state).await,
         "state_load" => handle_state_load(cmd, state).await,
+        "state_checkpoint" => handle_state_checkpoint(cmd, state).await,
+        "state_restore" => handle_state_restore(cmd, state).await,
+        "state_checkpoints" => state::checkpoint_list(state.session_name.as_deref()),
         "state_list" | "state_show" | "state_clear" | "state_clean" | "state_rename" => {
             state::dispatch_state_command(cmd)
                 .expect("dispatch_state_command must handle all state_* actions matched here")
         }
-        "trace_start" => handle_trace_start(state).await,
+        "trace_start" => handle_trace_start(cmd, state).await,
         "trace_stop" => handle_trace_stop(cmd, state).await,
         "profiler_start" => handle_profiler_start(cmd, state).await,
         "profiler_stop" => handle_profiler_stop(cmd, state).await,
@@ -2371,6 +2555,10 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
         "useragent" | "user_agent" => handle_user_agent(cmd, state).await,
         "set_media" => handle_set_media(cmd, state).await,
         "download" => handle_download(cmd, state).await,
+        "downloads_list" => Ok(state.downloads.list_json()),
+        "downloads_clear" => handle_downloads_clear(cmd, state),
+        "downloads_open" => handle_downloads_open(cmd, state),
+        "extension_list" => handle_extension_list(state),
         "diff_snapshot" => handle_diff_snapshot(cmd, state).await,
         "diff_url" => handle_diff_url(cmd, state).await,
         "credentials_set" => handle_credentials_set(cmd).await,
@@ -2417,22 +2605,16 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
         "device" => handle_device(cmd, state).await,
         "screencast_start" => handle_screencast_start(cmd, state).await,
         "screencast_stop" => handle_screencast_stop(state).await,
-        "stream_enable" => handle_stream_enable(cmd, state).await,
-        "stream_disable" => handle_stream_disable(state).await,
+        "stream_enable" | "stream_start" => handle_stream_enable(cmd, state).await,
+        "stream_disable" | "stream_stop" => handle_stream_disable(state).await,
         "stream_status" => handle_stream_status(state).await,
         "waitforurl" => handle_waitforurl(cmd, state).await,
         "waitforloadstate" => handle_waitforloadstate(cmd, state).await,
         "waitforfunction" => handle_waitforfunction(cmd, state).await,
         "frame" => handle_frame(cmd, state).await,
         "mainframe" => handle_mainframe(state).await,
-        "getbyrole" => handle_getbyrole(cmd, state).await,
-        "getbytext" => handle_getbytext(cmd, state).await,
-        "getbylabel" => handle_getbylabel(cmd, state).await,
-        "getbyplaceholder" => handle_getbyplaceholder(cmd, state).await,
-        "getbyalttext" => handle_getbyalttext(cmd, state).await,
-        "getbytitle" => handle_getbytitle(cmd, state).await,
-        "getbytestid" => handle_getbytestid(cmd, state).await,
-        "nth" => handle_nth(cmd, state).await,
+        "getbyrole" | "getbytext" | "getbylabel" | "getbyplaceholder" | "getbyalttext"
+        | "getbytitle" | "getbytestid" | "nth" => handle_find_locator(cmd, state, action).await,
         "find" => handle_find(cmd, state).await,
         "evalhandle" => handle_evalhandle(cmd, state).await,
         "drag" => handle_drag(cmd, state).await,
@@ -2462,6 +2644,8 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
         "deny" => handle_deny(cmd, state).await,
         "swipe" => handle_swipe(cmd, state).await,
         "device_list" => handle_device_list().await,
+        "device_use" => handle_device_use(cmd, state).await,
+        "device_boot" => handle_device_boot(cmd).await,
         "input_mouse" => handle_input_mouse(cmd, state).await,
         "input_keyboard" => handle_input_keyboard(cmd, state).await,
         "input_touch" => handle_input_touch(cmd, state).await,
@@ -2471,9 +2655,16 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
         "mousemove" => handle_mousemove(cmd, state).await,
         "mousedown" => handle_mousedown(cmd, state).await,
         "mouseup" => handle_mouseup(cmd, state).await,
-        _ => Err(format!("Not yet implemented: {}", action)),
+        _ => {
+            error_kind = Some("protocol");
+            Err(format!("Not yet implemented: {}", action))
+        }
     };
 
+    if let (Some(target), Ok(Value::Object(data))) = (described, result.as_mut()) {
+        data.insert("target".to_string(), target);
+    }
+
     if result.is_ok() && should_validate_restore_after_action(action) {
         validate_restore_if_pending(state).await;
     }
@@ -2487,7 +2678,13 @@ pub async fn execute_command(cmd: &Value, state: &mut DaemonState) -> Value {
 
     let mut resp = match result {
         Ok(data) => success_response(&id, data),
-        Err(e) => error_response(&id, &super::browser::to_ai_friendly_error(&e)),
+        Err(e) => {
+            let mut resp = error_response(&id, &super::browser::to_ai_friendly_error(&e));
+            if let Some(kind) = error_kind {
+                resp["errorKind"] = json!(kind);
+            }
+            resp
+        }
     };
     inject_lifecycle(
         &mut resp,
@@ -2704,7 +2901,7 @@ fn ensure_allowed_domains_supported_for_launch(
 
     if support.profile.is_some() {
         return Err(
-            "--allowed-domains is not supported with --profile because Chrome may restore existing pages before network containment is installed"
+            "--allowed-domains is not supported with --profile or --user-data-dir because Chrome may restore existing pages before network containment is installed"
                 .to_string(),
         );
     }
@@ -2992,6 +3189,9 @@ async fn auto_launch(
     plugins: Vec<crate::plugins::PluginConfig>,
 ) -> Result<(), String> {
     let mut options = launch_options_from_env();
+    let user_data_dir = env::var("AGENT_BROWSER_USER_DATA_DIR").ok();
+    options.profile = profile_with_user_data_dir(options.profile.take(), user_data_dir.as_deref())?;
+    options.use_real_keychain |= user_data_dir.is_some();
     state.plugin_init_scripts.clear();
 
     // Use the stream server's viewport dimensions for --window-size so the
@@ -3116,7 +3316,13 @@ async fn auto_launch(
         })?;
         // ios/safari are device providers handled via explicit launch command
         if !p.is_empty() && p != "ios" && p != "safari" {
-            let conn = providers::connect_provider_with_plugins(&p, &plugins).await?;
+            let conn = providers::connect_provider_with_plugins_and_options(
+                &p,
+                &plugins,
+                providers::provider_options_from_env()
+                    .map(|options| json!({ "providerOptions": options })),
+            )
+            .await?;
             if conn.direct_page && !allowed_domains.is_empty() {
                 if let Some(ref ps) = conn.session {
                     providers::close_provider_session_with_plugins(ps, &plugins).await;
@@ -3356,6 +3562,28 @@ async fn apply_launch_mutator_plugins(
     Ok(())
 }
 
+/// `userDataDir` points Chrome at an existing user-data directory as-is. It
+/// launches the same way as a `--profile` path, but with the real keychain so
+/// the directory's saved cookies and passwords can be decrypted.
+fn profile_with_user_data_dir(
+    profile: Option<String>,
+    user_data_dir: Option<&str>,
+) -> Result<Option<String>, String> {
+    let Some(dir) = user_data_dir else {
+        return Ok(profile);
+    };
+    if profile.is_some() {
+        return Err("Cannot use --profile and --user-data-dir together".to_string());
+    }
+    if super::cdp::chrome::is_chrome_profile_name(dir) {
+        return Err(format!(
+            "--user-data-dir must be a directory path, not a profile name ('{}'); use --profile to pick a Chrome profile by name",
+            dir
+        ));
+    }
+    Ok(Some(dir.to_string()))
+}
+
 fn launch_options_from_env() -> LaunchOptions {
     let headed = headed_from_env();
 
@@ -3527,7 +3755,13 @@ async fn validate_restored_state(state: &DaemonState) -> Result<(), String> {
     }
 
     if let Some(ref text) = state.restore_check_text {
-        wait_for_text(&mgr.client, &session_id, text, timeout_ms).await?;
+        let query = TextWait {
+            text,
+            scope: None,
+            exact: false,
+            negate: false,
+        };
+        wait_for_text(&mgr.client, &session_id, &query, timeout_ms).await?;
     }
 
     if let Some(ref expression) = state.restore_check_fn {
@@ -3755,6 +3989,16 @@ async fn handle_launch(cmd: &Value, state: &mut DaemonState) -> Result<Value, St
                 .collect()
         });
     let storage_state = cmd.get("storageState").and_then(|v| v.as_str());
+    if storage_state.is_some() && cmd.get("encrypted").and_then(|v| v.as_bool()) == Some(true) {
+        // The CLI checked its own environment; a daemon started earlier
+        // may not have the key.
+        if env::var("AGENT_BROWSER_ENCRYPTION_KEY").map_or(true, |k| k.is_empty()) {
+            return Err(
+                "The --state file is encrypted but this session's daemon was started without AGENT_BROWSER_ENCRYPTION_KEY. Close the session and retry with the key set."
+                    .to_string(),
+            );
+        }
+    }
     let storage_state_owned = storage_state.map(|s| s.to_string());
     let engine = cmd
         .get("engine")
@@ -3765,6 +4009,8 @@ async fn handle_launch(cmd: &Value, state: &mut DaemonState) -> Result<Value, St
         .get("profile")
         .and_then(|v| v.as_str())
         .map(String::from);
+    let user_data_dir = cmd.get("userDataDir").and_then(|v| v.as_str());
+    let profile = profile_with_user_data_dir(profile, user_data_dir)?;
 
     let requested_allowed_domains = allowed_domains_from_launch_command(cmd);
     let previous_domain_filter = state.domain_filter.read().await.clone();
@@ -3857,7 +4103,7 @@ async fn handle_launch(cmd: &Value, state: &mut DaemonState) -> Result<Value, St
             .map(String::from),
         hide_scrollbars: hide_scrollbars_from_launch_cmd(cmd),
         viewport_size: None,
-        use_real_keychain: false,
+        use_real_keychain: user_data_dir.is_some(),
         webgpu: webgpu_from_launch_cmd(cmd),
         no_xvfb: no_xvfb_from_launch_cmd(cmd),
         restrict_webrtc,
@@ -3969,7 +4215,7 @@ async fn handle_launch(cmd: &Value, state: &mut DaemonState) -> Result<Value, St
         apply_launch_init_scripts(state, &enable_features, &init_script_paths).await;
         try_auto_restore_state(state).await;
         load_storage_state_or_rollback(state, &storage_state_owned).await?;
-        return Ok(json!({ "launched": true, "relaunchedBrowser": had_browser_before_launch }));
+        return Ok(cdp_connected_response(state, had_browser_before_launch).await);
     }
 
     if let Some(port) = cdp_port {
@@ -3984,7 +4230,7 @@ async fn handle_launch(cmd: &Value, state: &mut DaemonState) -> Result<Value, St
         apply_launch_init_scripts(state, &enable_features, &init_script_paths).await;
         try_auto_restore_state(state).await;
         load_storage_state_or_rollback(state, &storage_state_owned).await?;
-        return Ok(json!({ "launched": true, "relaunchedBrowser": had_browser_before_launch }));
+        return Ok(cdp_connected_response(state, had_browser_before_launch).await);
     }
 
     if auto_connect {
@@ -4121,11 +4367,16 @@ async fn handle_launch(cmd: &Value, state: &mut DaemonState) -> Result<Value, St
 
 async fn launch_ios(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
     let device_name = cmd.get("deviceName").and_then(|v| v.as_str());
-    let device_udid = cmd.get("udid").and_then(|v| v.as_str());
+    // A `device use` choice outranks the --device name the daemon started with.
+    let device_udid = cmd
+        .get("udid")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .or_else(|| state.ios_device_udid.clone());
     let platform_version = cmd.get("platformVersion").and_then(|v| v.as_str());
 
     // Select device (or use default)
-    let device = ios::select_device(device_name, device_udid)?;
+    let device = ios::select_device(device_name, device_udid.as_deref())?;
 
     // Boot simulator if it's not real and not already booted
     if !device.is_real && device.state != "Booted" {
@@ -4278,19 +4529,9 @@ async fn handle_navigate(cmd: &Value, state: &mut DaemonState) -> Result<Value,
                 first
             };
 
-            // Enable Fetch interception the first time --headers is used.
-            // Fetch.enable is idempotent — safe even if domain filter or
-            // routes already enabled it. Wildcard ensures we see all requests.
             if first_origin_header {
-                let session_id = mgr.active_session_id()?.to_string();
                 let has_proxy_creds = state.proxy_credentials.read().await.is_some();
-                let mut params = json!({ "patterns": [{ "urlPattern": "*" }] });
-                if has_proxy_creds {
-                    params["handleAuthRequests"] = json!(true);
-                }
-                mgr.client
-                    .send_command("Fetch.enable", Some(params), Some(&session_id))
-                    .await?;
+                enable_origin_header_interception(mgr, has_proxy_creds).await?;
             }
         }
     }
@@ -4303,6 +4544,25 @@ async fn handle_navigate(cmd: &Value, state: &mut DaemonState) -> Result<Value,
     Ok(result)
 }
 
+/// Enable Fetch interception the first time any origin gets scoped headers,
+/// so the background handler can inject them. Fetch.enable is idempotent:
+/// safe even if domain filter or routes already enabled it. Wildcard ensures
+/// we see all requests.
+async fn enable_origin_header_interception(
+    mgr: &BrowserManager,
+    has_proxy_creds: bool,
+) -> Result<(), String> {
+    let session_id = mgr.active_session_id()?.to_string();
+    let mut params = json!({ "patterns": [{ "urlPattern": "*" }] });
+    if has_proxy_creds {
+        params["handleAuthRequests"] = json!(true);
+    }
+    mgr.client
+        .send_command("Fetch.enable", Some(params), Some(&session_id))
+        .await?;
+    Ok(())
+}
+
 async fn handle_url(state: &DaemonState) -> Result<Value, String> {
     if let Some(ref wb) = state.webdriver_backend {
         if state.browser.is_none() {
@@ -4385,22 +4645,27 @@ async fn handle_inspect(state: &mut DaemonState) -> Result<Value, String> {
 }
 
 fn open_url_in_browser(url: &str) {
+    if let Err(e) = open_with_platform_opener(url) {
+        let _ = writeln!(std::io::stderr(), "[inspect] Failed to open browser: {}", e);
+    }
+}
+
+/// Hand a URL or path to the OS default handler (`open`, `xdg-open`, `start`).
+fn open_with_platform_opener(target: &str) -> std::io::Result<()> {
     #[cfg(target_os = "macos")]
-    let result = std::process::Command::new("open").arg(url).spawn();
+    let result = std::process::Command::new("open").arg(target).spawn();
     #[cfg(target_os = "linux")]
-    let result = std::process::Command::new("xdg-open").arg(url).spawn();
+    let result = std::process::Command::new("xdg-open").arg(target).spawn();
     #[cfg(target_os = "windows")]
     let result = std::process::Command::new("cmd")
-        .args(["/c", "start", "", url])
+        .args(["/c", "start", "", target])
         .spawn();
     #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
     let result: Result<std::process::Child, std::io::Error> = Err(std::io::Error::new(
         std::io::ErrorKind::Unsupported,
         "unsupported platform",
     ));
-    if let Err(e) = result {
-        let _ = writeln!(std::io::stderr(), "[inspect] Failed to open browser: {}", e);
-    }
+    result.map(|_| ())
 }
 
 async fn handle_title(state: &DaemonState) -> Result<Value, String> {
@@ -4452,8 +4717,37 @@ async fn handle_evaluate(cmd: &Value, state: &DaemonState) -> Result<Value, Stri
     Ok(json!({ "result": result, "origin": url }))
 }
 
-async fn handle_close(state: &mut DaemonState) -> Result<Value, String> {
-    let save_result = auto_save_restore_state(state).await;
+/// Save storage state for `close --save-state` while the browser is still
+/// up. A failed save is an error, so the close is abandoned and the state
+/// is not lost.
+async fn save_state_before_close(state: &DaemonState, arg: &str) -> Result<Option<String>, String> {
+    let Some(ref mgr) = state.browser else {
+        return Ok(None);
+    };
+    let session_id = mgr.active_session_id()?.to_string();
+    let path = state::resolve_state_path(arg)?
+        .to_string_lossy()
+        .to_string();
+    state::save_state(
+        &mgr.client,
+        &session_id,
+        Some(&path),
+        None,
+        state.session_name.as_deref(),
+        &state.session_id,
+        mgr.visited_origins(),
+    )
+    .await
+    .map(Some)
+    .map_err(|e| format!("{}. The browser was left open.", e))
+}
+
+async fn handle_close(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
+    // An explicit --save-state replaces the session-name auto-save.
+    let (saved_state, save_result) = match cmd.get("saveStatePath").and_then(|v| v.as_str()) {
+        Some(arg) => (save_state_before_close(state, arg).await?, Ok(None)),
+        None => (None, auto_save_restore_state(state).await),
+    };
     close_current_browser(state).await?;
 
     // Stop background Fetch handler
@@ -4464,6 +4758,8 @@ async fn handle_close(state: &mut DaemonState) -> Result<Value, String> {
         let mut map = state.origin_headers.write().await;
         map.clear();
     }
+    state.extra_http_headers.clear();
+    state.basic_auth_header = None;
 
     // Close WebDriver sessions
     if let Some(ref mut wb) = state.webdriver_backend {
@@ -4485,40 +4781,75 @@ async fn handle_close(state: &mut DaemonState) -> Result<Value, String> {
     }
 
     state.ref_map.clear();
-    match save_result {
-        Ok(Some(path)) => Ok(json!({
+    let mut data = match save_result {
+        Ok(Some(path)) => json!({
             "closed": true,
             "restoreStatus": state.restore_status,
             "saveStatus": state.restore_save_status,
             "statePath": path
-        })),
-        Ok(None) => Ok(json!({
+        }),
+        Ok(None) => json!({
             "closed": true,
             "restoreStatus": state.restore_status,
             "saveStatus": state.restore_save_status
-        })),
-        Err(err) => Ok(json!({
+        }),
+        Err(err) => json!({
             "closed": true,
             "restoreStatus": state.restore_status,
             "saveStatus": state.restore_save_status,
             "saveError": err
-        })),
+        }),
+    };
+    if let Some(path) = saved_state {
+        data["savedState"] = json!(path);
     }
+    Ok(data)
 }
 
 // ---------------------------------------------------------------------------
 // Phase 2 handlers
 // ---------------------------------------------------------------------------
 
+/// Resolve the `ref` field of a snapshot command against the current ref map.
+/// This must run before the ref map is cleared for the new snapshot.
+async fn resolve_snapshot_scope_ref(
+    cmd: &Value,
+    state: &DaemonState,
+    mgr: &BrowserManager,
+    session_id: &str,
+) -> Result<Option<(String, String)>, String> {
+    let Some(raw) = cmd.get("ref").and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    let ref_id = super::element::parse_ref(raw).ok_or_else(|| format!("Invalid ref: {}", raw))?;
+    if state.ref_map.get(&ref_id).is_none() {
+        return Err(format!(
+            "Unknown ref: @{}. Refs are only valid until the next navigation; run `snapshot` again",
+            ref_id
+        ));
+    }
+    let (object_id, _) = super::element::resolve_element_object_id(
+        &mgr.client,
+        session_id,
+        &state.ref_map,
+        &ref_id,
+        &state.iframe_sessions,
+    )
+    .await?;
+    Ok(Some((ref_id, object_id)))
+}
+
 async fn handle_snapshot(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
     let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
     let session_id = mgr.active_session_id()?.to_string();
 
+    let scope_ref = resolve_snapshot_scope_ref(cmd, state, mgr, &session_id).await?;
     let options = SnapshotOptions {
         selector: cmd
             .get("selector")
             .and_then(|v| v.as_str())
             .map(String::from),
+        scope_ref,
         interactive: cmd
             .get("interactive")
             .and_then(|v| v.as_bool())
@@ -4532,6 +4863,18 @@ async fn handle_snapshot(cmd: &Value, state: &mut DaemonState) -> Result<Value,
             .and_then(|v| v.as_u64())
             .map(|d| d as usize),
         urls: cmd.get("urls").and_then(|v| v.as_bool()).unwrap_or(false),
+        stable_refs: cmd
+            .get("stableRefs")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        viewport_only: cmd
+            .get("viewportOnly")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        ref_prefix: cmd
+            .get("refPrefix")
+            .and_then(|v| v.as_str())
+            .map(String::from),
     };
 
     state.ref_map.clear();
@@ -4555,11 +4898,16 @@ async fn handle_snapshot(cmd: &Value, state: &mut DaemonState) -> Result<Value,
             let mut obj = serde_json::Map::new();
             obj.insert("role".into(), Value::String(entry.role));
             obj.insert("name".into(), Value::String(entry.name));
-            (ref_id, Value::Object(obj))
+            (options.ref_label(&ref_id), Value::Object(obj))
         })
         .collect();
 
-    Ok(json!({ "snapshot": tree, "origin": url, "refs": refs }))
+    let mut data = json!({ "snapshot": tree, "origin": url, "refs": refs });
+    if options.viewport_only {
+        data["viewportOnly"] = json!(true);
+        data["viewport"] = snapshot::viewport_metrics(&mgr.client, &session_id).await?;
+    }
+    Ok(data)
 }
 
 async fn handle_screenshot(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
@@ -4585,8 +4933,7 @@ async fn handle_screenshot(cmd: &Value, state: &mut DaemonState) -> Result<Value
                     &base64_data,
                 )
                 .map_err(|e| format!("Base64 decode error: {}", e))?;
-                std::fs::write(p, bytes)
-                    .map_err(|e| format!("Failed to write screenshot: {}", e))?;
+                write_atomic(p, bytes).map_err(|e| format!("Failed to write screenshot: {}", e))?;
                 return Ok(json!({ "path": p }));
             }
             let tmp = format!(
@@ -4599,8 +4946,7 @@ async fn handle_screenshot(cmd: &Value, state: &mut DaemonState) -> Result<Value
             let bytes =
                 base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &base64_data)
                     .map_err(|e| format!("Base64 decode error: {}", e))?;
-            std::fs::write(&tmp, bytes)
-                .map_err(|e| format!("Failed to write screenshot: {}", e))?;
+            write_atomic(&tmp, bytes).map_err(|e| format!("Failed to write screenshot: {}", e))?;
             return Ok(json!({ "path": tmp }));
         }
     }
@@ -4630,10 +4976,18 @@ async fn handle_screenshot(cmd: &Value, state: &mut DaemonState) -> Result<Value
             .and_then(|v| v.as_i64())
             .map(|q| q as i32),
         annotate,
+        interactive_only: cmd
+            .get("interactiveOnly")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
         output_dir: cmd
             .get("screenshotDir")
             .and_then(|v| v.as_str())
             .map(String::from),
+        clip: cmd.get("clip").and_then(|clip| {
+            let side = |key: &str| clip.get(key).and_then(|v| v.as_f64());
+            Some([side("x")?, side("y")?, side("width")?, side("height")?])
+        }),
     };
 
     if annotate {
@@ -4670,6 +5024,83 @@ async fn handle_screenshot(cmd: &Value, state: &mut DaemonState) -> Result<Value
     Ok(response)
 }
 
+/// How long `click --wait-for-popup` waits when no `--popup-timeout` is given.
+const DEFAULT_POPUP_TIMEOUT_MS: u64 = 5000;
+
+fn wants_popup(cmd: &Value) -> bool {
+    cmd.get("waitForPopup").and_then(|v| v.as_bool()) == Some(true)
+}
+
+/// The tabs open before a `--wait-for-popup` click, so the page the click
+/// opens can be told apart from them. `None` when the flag is not set.
+fn popup_baseline(cmd: &Value, state: &DaemonState) -> Option<HashSet<u32>> {
+    if !wants_popup(cmd) {
+        return None;
+    }
+    let pages = state.browser.as_ref()?.pages_list();
+    Some(pages.iter().map(|p| p.tab_id).collect())
+}
+
+/// Wait for a tab that is not in `baseline` and switch to it. Polls the CDP
+/// event queue rather than waiting for one event, since a popup surfaces as
+/// either a created or an auto-attached target depending on how it opened.
+async fn wait_for_popup_tab(
+    state: &mut DaemonState,
+    baseline: &HashSet<u32>,
+    timeout_ms: u64,
+) -> Result<Option<u32>, String> {
+    let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_millis(timeout_ms);
+    loop {
+        state.drain_cdp_events_background().await?;
+        let popup = state.browser.as_ref().and_then(|mgr| {
+            mgr.pages_list()
+                .into_iter()
+                .map(|p| p.tab_id)
+                .find(|id| !baseline.contains(id))
+        });
+        if popup.is_some() {
+            return Ok(popup);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Ok(None);
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    }
+}
+
+/// Add the popup a `--wait-for-popup` click opened to its response, after
+/// switching to it. A click that opens nothing still succeeds; the response
+/// says so and the active tab is left alone.
+async fn finish_popup_click(
+    cmd: &Value,
+    state: &mut DaemonState,
+    baseline: HashSet<u32>,
+    mut response: Value,
+) -> Result<Value, String> {
+    let timeout_ms = cmd
+        .get("popupTimeout")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_POPUP_TIMEOUT_MS);
+    let Some(tab_id) = wait_for_popup_tab(state, &baseline, timeout_ms).await? else {
+        response["popup"] = Value::Null;
+        response["note"] = json!(format!(
+            "No popup opened within {}ms; still on the original tab",
+            timeout_ms
+        ));
+        return Ok(response);
+    };
+    let mut popup = switch_to_tab(state, tab_id).await?;
+    if let Some(index) = state
+        .browser
+        .as_ref()
+        .and_then(|mgr| mgr.pages_list().iter().position(|p| p.tab_id == tab_id))
+    {
+        popup["index"] = json!(index);
+    }
+    response["popup"] = popup;
+    Ok(response)
+}
+
 async fn handle_click(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
     let selector = cmd
         .get("selector")
@@ -4678,6 +5109,9 @@ async fn handle_click(cmd: &Value, state: &mut DaemonState) -> Result<Value, Str
 
     if let Some(ref wb) = state.webdriver_backend {
         if state.browser.is_none() {
+            if wants_popup(cmd) {
+                return Err("--wait-for-popup requires a CDP browser session".to_string());
+            }
             wb.click(selector).await?;
             return Ok(json!({ "clicked": selector }));
         }
@@ -4758,6 +5192,7 @@ async fn handle_click(cmd: &Value, state: &mut DaemonState) -> Result<Value, Str
 
     let button = cmd.get("button").and_then(|v| v.as_str()).unwrap_or("left");
     let click_count = cmd.get("clickCount").and_then(|v| v.as_i64()).unwrap_or(1) as i32;
+    let popup_baseline = popup_baseline(cmd, state);
 
     let result = interaction::click(
         &mgr.client,
@@ -4774,7 +5209,11 @@ async fn handle_click(cmd: &Value, state: &mut DaemonState) -> Result<Value, Str
         state.pending_pointer_release = result.pending_release;
         return Ok(json!({ "clicked": selector, "dialogOpened": true }));
     }
-    Ok(json!({ "clicked": selector }))
+    let response = json!({ "clicked": selector });
+    match popup_baseline {
+        Some(baseline) => finish_popup_click(cmd, state, baseline, response).await,
+        None => Ok(response),
+    }
 }
 
 async fn handle_dblclick(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
@@ -5045,8 +5484,35 @@ async fn handle_wait(cmd: &Value, state: &mut DaemonState) -> Result<Value, Stri
     let timeout_ms = state.timeout_ms(cmd);
 
     if let Some(text) = cmd.get("text").and_then(|v| v.as_str()) {
-        wait_for_text(&mgr.client, &session_id, text, timeout_ms).await?;
-        return Ok(json!({ "waited": "text", "text": text }));
+        let query = TextWait {
+            text,
+            scope: cmd.get("scope").and_then(|v| v.as_str()),
+            exact: cmd.get("exact").and_then(|v| v.as_bool()).unwrap_or(false),
+            negate: cmd.get("negate").and_then(|v| v.as_bool()).unwrap_or(false),
+        };
+        if query.scope.is_some_and(|s| s.starts_with('@')) {
+            return Err(
+                "wait --text --selector takes a CSS selector; refs are not supported as a scope"
+                    .to_string(),
+            );
+        }
+        let matched = wait_for_text(&mgr.client, &session_id, &query, timeout_ms).await?;
+        let mut data = json!({ "waited": "text", "text": text });
+        if let Some(scope) = query.scope {
+            data["scope"] = json!(scope);
+        }
+        if query.negate {
+            data["negate"] = json!(true);
+        }
+        if let Some(mut matched) = matched {
+            if let Some(backend_node_id) = take_text_match_node(&mgr.client, &session_id).await {
+                if let Some(ref_id) = state.ref_map.ref_for_backend_node(backend_node_id) {
+                    matched["ref"] = json!(ref_id);
+                }
+            }
+            data["match"] = matched;
+        }
+        return Ok(data);
     }
 
     if let Some(selector) = cmd.get("selector").and_then(|v| v.as_str()) {
@@ -5294,33 +5760,26 @@ async fn wait_for_selector(
     state: &str,
     timeout_ms: u64,
 ) -> Result<(), String> {
+    let find = super::element::build_find_element_js_in("document", selector);
     let check_fn = match state {
-        "attached" => format!(
-            "!!document.querySelector({})",
-            serde_json::to_string(selector).unwrap_or_default()
-        ),
-        "detached" => format!(
-            "!document.querySelector({})",
-            serde_json::to_string(selector).unwrap_or_default()
-        ),
+        "attached" => format!("!!{find}"),
+        "detached" => format!("!{find}"),
         "hidden" => format!(
             r#"(() => {{
-                const el = document.querySelector({sel});
+                const el = {find};
                 if (!el) return true;
                 const s = window.getComputedStyle(el);
                 return s.display === 'none' || s.visibility === 'hidden' || parseFloat(s.opacity) === 0;
             }})()"#,
-            sel = serde_json::to_string(selector).unwrap_or_default()
         ),
         _ => format!(
             r#"(() => {{
-                const el = document.querySelector({sel});
+                const el = {find};
                 if (!el) return false;
                 const r = el.getBoundingClientRect();
                 const s = window.getComputedStyle(el);
                 return r.width > 0 && r.height > 0 && s.visibility !== 'hidden' && s.display !== 'none';
             }})()"#,
-            sel = serde_json::to_string(selector).unwrap_or_default()
         ),
     };
 
@@ -5344,17 +5803,133 @@ async fn wait_for_url(mgr: &BrowserManager, pattern: &str, timeout_ms: u64) -> R
     }
 }
 
+/// A `wait --text` query: the text, an optional CSS scope, whole-text vs
+/// substring matching, and whether to wait for the text to disappear.
+struct TextWait<'a> {
+    text: &'a str,
+    scope: Option<&'a str>,
+    exact: bool,
+    negate: bool,
+}
+
+/// Attribute marking the element a `wait --text` matched, so its DOM node
+/// can be looked up after the poll. Removed by [`take_text_match_node`].
+const TEXT_MATCH_ATTR: &str = "data-agent-browser-text-match";
+
+/// Expression for one `wait --text` poll. Evaluates to `{ ok, tag, text }`:
+/// `ok` is whether the wait is satisfied. Only visible text counts, so copies
+/// in hidden templates do not match. Without `exact`, the match is the
+/// deepest element whose whitespace-normalized text contains the query;
+/// with it, the deepest element whose whole text equals the query. A scope
+/// that does not exist yet matches nothing.
+fn text_wait_expression(query: &TextWait) -> String {
+    let quote = |s: &str| serde_json::to_string(s).unwrap_or_default();
+    format!(
+        r#"(() => {{
+            const norm = s => (s || '').replace(/\s+/g, ' ').trim();
+            const want = norm({text});
+            const exact = {exact};
+            const visible = el => el.checkVisibility ? el.checkVisibility() : el.getClientRects().length > 0;
+            const contains = el => visible(el) && norm(el.innerText).includes(want);
+            const root = {scope} === null ? document.body : document.querySelector({scope});
+            let found = null;
+            if (root && contains(root)) {{
+                if (exact) {{
+                    const equal = el => visible(el) && norm(el.innerText) === want;
+                    for (const el of [root, ...root.querySelectorAll('*')]) {{
+                        if (!equal(el)) continue;
+                        if (!found || found.contains(el)) found = el;
+                        else break;
+                    }}
+                }} else {{
+                    found = root;
+                    let child;
+                    while ((child = Array.from(found.children).find(contains))) found = child;
+                }}
+            }}
+            document.querySelectorAll('[{attr}]').forEach(el => el.removeAttribute('{attr}'));
+            if ({negate}) return {{ ok: !found }};
+            if (!found) return {{ ok: false }};
+            found.setAttribute('{attr}', '');
+            return {{ ok: true, tag: found.tagName.toLowerCase(), text: norm(found.innerText).slice(0, 200) }};
+        }})()"#,
+        text = quote(query.text),
+        exact = query.exact,
+        scope = query.scope.map(quote).unwrap_or_else(|| "null".to_string()),
+        negate = query.negate,
+        attr = TEXT_MATCH_ATTR,
+    )
+}
+
+/// Poll until a `wait --text` query is satisfied. Returns the matched
+/// element's `{ tag, text }`, or `None` for a negated wait.
 async fn wait_for_text(
     client: &super::cdp::client::CdpClient,
     session_id: &str,
-    text: &str,
+    query: &TextWait<'_>,
     timeout_ms: u64,
-) -> Result<(), String> {
-    let check_fn = format!(
-        "(document.body.innerText || '').includes({})",
-        serde_json::to_string(text).unwrap_or_default()
-    );
-    poll_until_true(client, session_id, &check_fn, timeout_ms).await
+) -> Result<Option<Value>, String> {
+    let expression = text_wait_expression(query);
+    let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_millis(timeout_ms);
+    loop {
+        let result: super::cdp::types::EvaluateResult = client
+            .send_command_typed(
+                "Runtime.evaluate",
+                &super::cdp::types::EvaluateParams {
+                    expression: expression.clone(),
+                    return_by_value: Some(true),
+                    await_promise: Some(false),
+                },
+                Some(session_id),
+            )
+            .await?;
+        if let Some(Value::Object(mut outcome)) = result.result.value {
+            if outcome.remove("ok").and_then(|v| v.as_bool()) == Some(true) {
+                return Ok((!query.negate).then_some(Value::Object(outcome)));
+            }
+        }
+        if tokio::time::Instant::now() >= deadline {
+            let what = if query.negate { "disappear" } else { "appear" };
+            return Err(format!(
+                "Wait timed out after {}ms for text {:?} to {}",
+                timeout_ms, query.text, what
+            ));
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    }
+}
+
+/// The backend node id of the element a `wait --text` marked, clearing the
+/// mark. `None` if it is gone or CDP cannot describe it.
+async fn take_text_match_node(
+    client: &super::cdp::client::CdpClient,
+    session_id: &str,
+) -> Option<i64> {
+    let result: super::cdp::types::EvaluateResult = client
+        .send_command_typed(
+            "Runtime.evaluate",
+            &super::cdp::types::EvaluateParams {
+                expression: format!(
+                    "(() => {{ const el = document.querySelector('[{attr}]'); el?.removeAttribute('{attr}'); return el; }})()",
+                    attr = TEXT_MATCH_ATTR
+                ),
+                return_by_value: Some(false),
+                await_promise: Some(false),
+            },
+            Some(session_id),
+        )
+        .await
+        .ok()?;
+    let object_id = result.result.object_id?;
+    let node = client
+        .send_command(
+            "DOM.describeNode",
+            Some(json!({ "objectId": object_id })),
+            Some(session_id),
+        )
+        .await
+        .ok()?;
+    node.get("node")?.get("backendNodeId")?.as_i64()
 }
 
 async fn wait_for_function(
@@ -5380,13 +5955,13 @@ async fn wait_for_selector_in_frame(
 ) -> Result<(), String> {
     let owner_object_id =
         super::element::frame_owner_object_id(client, session_id, frame_id).await?;
-    let sel = serde_json::to_string(selector).unwrap_or_default();
+    let find = super::element::build_find_element_js_in("doc", selector);
     let check = match state {
-        "attached" => format!("!!doc.querySelector({sel})"),
-        "detached" => format!("!doc.querySelector({sel})"),
+        "attached" => format!("!!{find}"),
+        "detached" => format!("!{find}"),
         "hidden" => format!(
             r#"(() => {{
-                const el = doc.querySelector({sel});
+                const el = {find};
                 if (!el) return true;
                 const s = doc.defaultView.getComputedStyle(el);
                 return s.display === 'none' || s.visibility === 'hidden' || parseFloat(s.opacity) === 0;
@@ -5394,7 +5969,7 @@ async fn wait_for_selector_in_frame(
         ),
         _ => format!(
             r#"(() => {{
-                const el = doc.querySelector({sel});
+                const el = {find};
                 if (!el) return false;
                 const r = el.getBoundingClientRect();
                 const s = doc.defaultView.getComputedStyle(el);
@@ -5571,10 +6146,121 @@ async fn handle_setcontent(cmd: &Value, state: &DaemonState) -> Result<Value, St
     Ok(json!({ "set": true }))
 }
 
-async fn handle_headers(cmd: &Value, state: &DaemonState) -> Result<Value, String> {
-    let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
-    let session_id = mgr.active_session_id()?.to_string();
+/// Bounds for `set timeout`, matching the CLI's validation.
+const MIN_DEFAULT_TIMEOUT_MS: u64 = 100;
+const MAX_DEFAULT_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+
+fn timeout_value(cmd: &Value, key: &str) -> Result<Option<u64>, String> {
+    let Some(value) = cmd.get(key).filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    match value.as_u64() {
+        Some(ms) if (MIN_DEFAULT_TIMEOUT_MS..=MAX_DEFAULT_TIMEOUT_MS).contains(&ms) => Ok(Some(ms)),
+        _ => Err(format!(
+            "'{}' must be between {} and {} milliseconds, got: {}",
+            key, MIN_DEFAULT_TIMEOUT_MS, MAX_DEFAULT_TIMEOUT_MS, value
+        )),
+    }
+}
+
+/// `set timeout`: change the default timeout for waits and, with
+/// `--navigation`, for navigation. Without either value (`set timeout
+/// show`) this only reports the effective values.
+fn handle_timeout(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
+    let default_ms = timeout_value(cmd, "defaultTimeout")?;
+    let navigation_ms = timeout_value(cmd, "navigationTimeout")?;
+    if let Some(ms) = default_ms {
+        state.default_timeout_ms = ms;
+    }
+    if let Some(ms) = navigation_ms {
+        state.navigation_timeout_ms = Some(ms);
+        if let Some(ref mut mgr) = state.browser {
+            mgr.set_default_timeout_ms(ms);
+        }
+    }
+    let navigation = state
+        .browser
+        .as_ref()
+        .map(|mgr| mgr.default_timeout_ms())
+        .or(state.navigation_timeout_ms)
+        .unwrap_or(super::browser::DEFAULT_NAVIGATION_TIMEOUT_MS);
+    if default_ms.is_some() || navigation_ms.is_some() {
+        // The CLI sizes its socket reads from this file, so a raised timeout
+        // does not outlast the client's read.
+        let timeouts = SessionTimeouts {
+            default_timeout: Some(state.default_timeout_ms),
+            navigation_timeout: Some(navigation),
+        };
+        let json = serde_json::to_string(&timeouts).map_err(|e| e.to_string())?;
+        write_atomic(get_timeouts_path(&state.session_id), json)
+            .map_err(|e| format!("Failed to record session timeouts: {}", e))?;
+    }
+    Ok(json!({
+        "defaultTimeout": state.default_timeout_ms,
+        "navigationTimeout": navigation,
+        "updated": default_ms.is_some() || navigation_ms.is_some(),
+    }))
+}
+
+/// Origins from AGENT_BROWSER_IGNORE_HTTPS_ORIGINS (`--ignore-https-origin`).
+fn ignore_https_origins_from_env() -> HashSet<String> {
+    env::var("AGENT_BROWSER_IGNORE_HTTPS_ORIGINS")
+        .map(|value| {
+            value
+                .split(',')
+                .filter_map(|origin| crate::commands::parse_https_origin(origin.trim()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `set tls ignore-errors on|off` and `set tls allow-origin <origin>`.
+/// Without a browser the setting is recorded and applied on launch.
+async fn handle_tls_config(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
+    let ignore_errors = cmd.get("ignoreErrors").and_then(|v| v.as_bool());
+    let allow_origin = cmd
+        .get("allowOrigin")
+        .and_then(|v| v.as_str())
+        .map(crate::commands::parse_https_origin)
+        .transpose()?;
+    if ignore_errors.is_none() && allow_origin.is_none() {
+        return Err("Missing 'ignoreErrors' or 'allowOrigin' parameter".to_string());
+    }
+
+    if let Some(ignore) = ignore_errors {
+        if let Some(ref mut mgr) = state.browser {
+            mgr.set_ignore_https_errors(ignore).await?;
+        }
+        state.ignore_https_errors = Some(ignore);
+    }
+    if let Some(origin) = allow_origin {
+        state.ignore_https_origins.write().await.insert(origin);
+        if let Some(ref mut mgr) = state.browser {
+            mgr.enable_certificate_overrides().await?;
+        }
+    }
 
+    let ignore_errors = state
+        .browser
+        .as_ref()
+        .map(|mgr| mgr.ignore_https_errors)
+        .or(state.ignore_https_errors)
+        .unwrap_or(false);
+    let mut allowed: Vec<String> = state
+        .ignore_https_origins
+        .read()
+        .await
+        .iter()
+        .cloned()
+        .collect();
+    allowed.sort();
+    Ok(json!({
+        "ignoreErrors": ignore_errors,
+        "allowedOrigins": allowed,
+    }))
+}
+
+async fn handle_headers(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
     let headers_value = cmd.get("headers").ok_or("Missing 'headers' parameter")?;
 
     let headers: HashMap<String, String> = headers_value
@@ -5586,10 +6272,24 @@ async fn handle_headers(cmd: &Value, state: &DaemonState) -> Result<Value, Strin
         })
         .unwrap_or_default();
 
-    network::set_extra_headers(&mgr.client, &session_id, &headers).await?;
+    state.extra_http_headers = headers;
+    apply_extra_http_headers(state).await?;
     Ok(json!({ "set": true }))
 }
 
+/// Send `set headers` plus any unscoped `set credentials` Authorization as
+/// the page's extra HTTP headers. CDP replaces the whole set on each call.
+async fn apply_extra_http_headers(state: &DaemonState) -> Result<(), String> {
+    let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
+    let session_id = mgr.active_session_id()?.to_string();
+    let mut headers = state.extra_http_headers.clone();
+    if let Some(ref auth) = state.basic_auth_header {
+        headers.retain(|name, _| !name.eq_ignore_ascii_case("authorization"));
+        headers.insert("Authorization".to_string(), auth.clone());
+    }
+    network::set_extra_headers(&mgr.client, &session_id, &headers).await
+}
+
 async fn handle_offline(cmd: &Value, state: &DaemonState) -> Result<Value, String> {
     let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
     let session_id = mgr.active_session_id()?.to_string();
@@ -5598,19 +6298,54 @@ async fn handle_offline(cmd: &Value, state: &DaemonState) -> Result<Value, Strin
     Ok(json!({ "offline": offline }))
 }
 
+/// `tail` from `console`/`errors`/`network requests --tail <n>`.
+fn tail_param(cmd: &Value) -> Option<usize> {
+    cmd.get("tail").and_then(|v| v.as_u64()).map(|n| n as usize)
+}
+
 async fn handle_console(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
     let clear = cmd.get("clear").and_then(|v| v.as_bool()).unwrap_or(false);
     if clear {
         state.event_tracker.clear_console();
         Ok(json!({ "cleared": true }))
     } else {
-        let result = state.event_tracker.get_console_json();
+        let result = state.event_tracker.get_console_json(tail_param(cmd));
         Ok(result)
     }
 }
 
-async fn handle_errors(state: &DaemonState) -> Result<Value, String> {
-    Ok(state.event_tracker.get_errors_json())
+async fn handle_errors(cmd: &Value, state: &DaemonState) -> Result<Value, String> {
+    Ok(state.event_tracker.get_errors_json(tail_param(cmd)))
+}
+
+/// `set buffer console|errors|requests <max-entries>`: cap a log buffer,
+/// evicting the oldest entries that no longer fit.
+async fn handle_buffer_config(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
+    let buffer = cmd
+        .get("buffer")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing 'buffer' parameter")?;
+    let max_entries = cmd
+        .get("maxEntries")
+        .and_then(|v| v.as_u64())
+        .filter(|n| *n > 0)
+        .ok_or("Missing or invalid 'maxEntries' parameter")? as usize;
+    let stats = match buffer {
+        "console" => {
+            state.event_tracker.console_entries.set_limit(max_entries);
+            state.event_tracker.console_entries.stats()
+        }
+        "errors" => {
+            state.event_tracker.error_entries.set_limit(max_entries);
+            state.event_tracker.error_entries.stats()
+        }
+        "requests" => {
+            state.tracked_requests.set_limit(max_entries);
+            state.tracked_requests.stats()
+        }
+        other => return Err(format!("Unknown buffer '{}'", other)),
+    };
+    Ok(json!({ "buffer": buffer, "stats": stats }))
 }
 
 async fn handle_session_info(state: &DaemonState) -> Result<Value, String> {
@@ -5646,12 +6381,19 @@ async fn handle_session_info(state: &DaemonState) -> Result<Value, String> {
 async fn handle_state_save(cmd: &Value, state: &DaemonState) -> Result<Value, String> {
     let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
     let session_id = mgr.active_session_id()?.to_string();
-    let path = cmd.get("path").and_then(|v| v.as_str());
+    let path = cmd
+        .get("path")
+        .and_then(|v| v.as_str())
+        .map(state::resolve_state_path)
+        .transpose()?
+        .map(|p| p.to_string_lossy().to_string());
+    let encrypt = cmd.get("encrypt").and_then(|v| v.as_bool());
 
     let saved_path = state::save_state(
         &mgr.client,
         &session_id,
-        path,
+        path.as_deref(),
+        encrypt,
         state.session_name.as_deref(),
         &state.session_id,
         mgr.visited_origins(),
@@ -5668,6 +6410,10 @@ async fn handle_state_load(cmd: &Value, state: &mut DaemonState) -> Result<Value
         .get("path")
         .and_then(|v| v.as_str())
         .ok_or("Missing 'path' parameter")?;
+    let path = state::resolve_existing_state_path(path)?
+        .to_string_lossy()
+        .to_string();
+    let path = path.as_str();
 
     ensure_state_replay_supported_by_active_domain_filter(state, "state load").await?;
     state::load_state(&mgr.client, &session_id, path).await?;
@@ -5675,6 +6421,40 @@ async fn handle_state_load(cmd: &Value, state: &mut DaemonState) -> Result<Value
     Ok(json!({ "loaded": true, "path": path }))
 }
 
+async fn handle_state_checkpoint(cmd: &Value, state: &DaemonState) -> Result<Value, String> {
+    let label = cmd.get("label").and_then(|v| v.as_str());
+    let path = state::new_checkpoint_path(state.session_name.as_deref(), label)?;
+    let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
+    let session_id = mgr.active_session_id()?.to_string();
+
+    let saved_path = state::save_state(
+        &mgr.client,
+        &session_id,
+        Some(&path.to_string_lossy()),
+        None,
+        state.session_name.as_deref(),
+        &state.session_id,
+        mgr.visited_origins(),
+    )
+    .await?;
+
+    Ok(json!({ "checkpoint": true, "saved": true, "path": saved_path, "label": label }))
+}
+
+async fn handle_state_restore(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
+    let label = cmd.get("label").and_then(|v| v.as_str());
+    let path = state::find_checkpoint(state.session_name.as_deref(), label)?
+        .to_string_lossy()
+        .to_string();
+    let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
+    let session_id = mgr.active_session_id()?.to_string();
+
+    ensure_state_replay_supported_by_active_domain_filter(state, "state restore").await?;
+    state::load_state(&mgr.client, &session_id, &path).await?;
+    mark_explicit_storage_state_loaded(state, &path);
+    Ok(json!({ "loaded": true, "path": path, "label": label }))
+}
+
 // ---------------------------------------------------------------------------
 // Phase 6 handlers
 // ---------------------------------------------------------------------------
@@ -5695,11 +6475,21 @@ async fn handle_diff_snapshot(cmd: &Value, state: &mut DaemonState) -> Result<Va
         .get("selector")
         .and_then(|v| v.as_str())
         .map(String::from);
+    let scope_ref = resolve_snapshot_scope_ref(cmd, state, mgr, &session_id).await?;
 
     let options = SnapshotOptions {
         compact,
         depth: max_depth,
         selector,
+        scope_ref,
+        stable_refs: cmd
+            .get("stableRefs")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        ref_prefix: cmd
+            .get("refPrefix")
+            .and_then(|v| v.as_str())
+            .map(String::from),
         ..SnapshotOptions::default()
     };
     let current = snapshot::take_snapshot(
@@ -5872,7 +6662,8 @@ async fn handle_keyboard(cmd: &Value, state: &DaemonState) -> Result<Value, Stri
                 .get("text")
                 .and_then(|v| v.as_str())
                 .ok_or("Missing 'text' parameter")?;
-            interaction::type_text_into_active_context(&mgr.client, &session_id, text, None)
+            let delay = cmd.get("delay").and_then(|v| v.as_u64());
+            interaction::type_text_into_active_context(&mgr.client, &session_id, text, delay)
                 .await?;
             return Ok(json!({ "typed": text }));
         }
@@ -5981,6 +6772,12 @@ async fn handle_tab_switch(cmd: &Value, state: &mut DaemonState) -> Result<Value
         let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
         mgr.resolve_tab_ref(&tab_ref)?
     };
+    switch_to_tab(state, tab_id).await
+}
+
+/// Make `tab_id` the active tab and reset the per-page state that belonged
+/// to the previous one.
+async fn switch_to_tab(state: &mut DaemonState, tab_id: u32) -> Result<Value, String> {
     let dialog_session = state
         .pending_dialog
         .as_ref()
@@ -6117,6 +6914,13 @@ async fn handle_set_media(cmd: &Value, state: &DaemonState) -> Result<Value, Str
     Ok(json!({ "set": true }))
 }
 
+fn download_timeout_error(timeout: tokio::time::Duration) -> String {
+    format!(
+        "Timeout waiting for download to complete after {}ms (raise it with --timeout <ms>)",
+        timeout.as_millis()
+    )
+}
+
 async fn handle_download(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
     let selector = cmd
         .get("selector")
@@ -6180,15 +6984,19 @@ async fn handle_download(cmd: &Value, state: &mut DaemonState) -> Result<Value,
     )
     .await?;
 
-    // Wait for download to complete
-    const DOWNLOAD_TIMEOUT: tokio::time::Duration = tokio::time::Duration::from_secs(30);
-    let deadline = tokio::time::Instant::now() + DOWNLOAD_TIMEOUT;
+    // Wait for download to complete (`download --timeout <ms>`, default 30s)
+    let download_timeout = tokio::time::Duration::from_millis(
+        cmd.get("timeout")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(30_000),
+    );
+    let deadline = tokio::time::Instant::now() + download_timeout;
     let mut downloaded_guid: Option<String> = None;
 
     loop {
         let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
         if remaining.is_zero() {
-            return Err("Timeout waiting for download to complete".to_string());
+            return Err(download_timeout_error(download_timeout));
         }
 
         match tokio::time::timeout(remaining, rx.recv()).await {
@@ -6229,7 +7037,7 @@ async fn handle_download(cmd: &Value, state: &mut DaemonState) -> Result<Value,
             }
             Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(_))) => continue,
             Ok(Err(_)) => return Err("Event stream closed".to_string()),
-            Err(_) => return Err("Timeout waiting for download to complete".to_string()),
+            Err(_) => return Err(download_timeout_error(download_timeout)),
         }
     }
 
@@ -6258,6 +7066,7 @@ async fn handle_download(cmd: &Value, state: &mut DaemonState) -> Result<Value,
                 ));
             }
         }
+        state.downloads.saved(&guid, &dest);
     } else {
         // GUID capture failed -- the file may have been saved under its original name
         // by Chrome. Only return success if dest already exists (avoid touching
@@ -6273,14 +7082,57 @@ async fn handle_download(cmd: &Value, state: &mut DaemonState) -> Result<Value,
     Ok(json!({ "path": dest_str }))
 }
 
+/// Delete this session's downloads. Always asks for confirmation first through
+/// the same `confirm`/`deny` flow used by action policies.
+fn handle_downloads_clear(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
+    const ACTION: &str = "downloads_clear";
+    if !state.confirmed_policy_actions.contains(ACTION) && !state.downloads.is_empty() {
+        let id = cmd.get("id").and_then(|v| v.as_str()).unwrap_or("");
+        state.pending_confirmation = Some(PendingConfirmation {
+            action: ACTION.to_string(),
+            cmd: cmd.clone(),
+            approved_actions: state.confirmed_policy_actions.iter().cloned().collect(),
+        });
+        return Ok(json!({
+            "confirmation_required": true,
+            "confirmation_id": id,
+            "action": ACTION,
+            "description": format!("Delete {} downloaded file(s)", state.downloads.len()),
+        }));
+    }
+    let cleared = state.downloads.clear()?;
+    Ok(json!({ "cleared": cleared }))
+}
+
+fn handle_downloads_open(cmd: &Value, state: &DaemonState) -> Result<Value, String> {
+    let index = cmd
+        .get("index")
+        .and_then(|v| v.as_u64())
+        .ok_or("Missing 'index' parameter")? as usize;
+    let path = state.downloads.path_at(index)?;
+    let reveal = cmd.get("reveal").and_then(|v| v.as_bool()).unwrap_or(false);
+    if reveal {
+        let folder = path.parent().unwrap_or(&path);
+        open_with_platform_opener(&folder.to_string_lossy())
+            .map_err(|e| format!("Failed to open {}: {}", folder.display(), e))?;
+    }
+    Ok(json!({ "path": path.to_string_lossy(), "revealed": reveal }))
+}
+
 // ---------------------------------------------------------------------------
 // Phase 4 handlers
 // ---------------------------------------------------------------------------
 
-async fn handle_trace_start(state: &mut DaemonState) -> Result<Value, String> {
+async fn handle_trace_start(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
     let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
     let session_id = mgr.active_session_id()?.to_string();
-    native_tracing::trace_start(&mgr.client, &session_id, &mut state.tracing_state).await
+    native_tracing::trace_start(
+        &mgr.client,
+        &session_id,
+        &mut state.tracing_state,
+        TraceOptions::from_command(cmd),
+    )
+    .await
 }
 
 async fn handle_trace_stop(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
@@ -6581,7 +7433,7 @@ async fn handle_pdf(cmd: &Value, state: &DaemonState) -> Result<Value, String> {
 
     let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, data)
         .map_err(|e| format!("Failed to decode PDF: {}", e))?;
-    std::fs::write(&save_path, &bytes).map_err(|e| format!("Failed to save PDF: {}", e))?;
+    write_atomic(&save_path, &bytes).map_err(|e| format!("Failed to save PDF: {}", e))?;
 
     Ok(json!({ "path": save_path }))
 }
@@ -6747,6 +7599,9 @@ async fn handle_boundingbox(cmd: &Value, state: &mut DaemonState) -> Result<Valu
         .get("selector")
         .and_then(|v| v.as_str())
         .ok_or("Missing 'selector' parameter")?;
+    let with_viewport = ["viewportRelative", "visibility"]
+        .iter()
+        .any(|key| cmd.get(*key).and_then(|v| v.as_bool()) == Some(true));
 
     let bbox = super::element::get_element_bounding_box(
         &mgr.client,
@@ -6754,6 +7609,7 @@ async fn handle_boundingbox(cmd: &Value, state: &mut DaemonState) -> Result<Valu
         &state.ref_map,
         selector,
         &state.iframe_sessions,
+        with_viewport,
     )
     .await?;
     Ok(bbox)
@@ -6786,12 +7642,17 @@ async fn handle_innerhtml(cmd: &Value, state: &mut DaemonState) -> Result<Value,
         .and_then(|v| v.as_str())
         .ok_or("Missing 'selector' parameter")?;
 
+    let outer = cmd.get("outer").and_then(|v| v.as_bool()).unwrap_or(false);
+    let max_depth = cmd.get("maxDepth").and_then(|v| v.as_u64());
+
     let html = super::element::get_element_inner_html(
         &mgr.client,
         &session_id,
         &state.ref_map,
         selector,
         &state.iframe_sessions,
+        outer,
+        max_depth,
     )
     .await?;
     Ok(json!({ "html": html }))
@@ -6848,8 +7709,14 @@ async fn handle_count(cmd: &Value, state: &mut DaemonState) -> Result<Value, Str
         .and_then(|v| v.as_str())
         .ok_or("Missing 'selector' parameter")?;
 
-    let count = super::element::get_element_count(&mgr.client, &session_id, selector).await?;
-    Ok(json!({ "count": count, "selector": selector }))
+    let visible_only = cmd
+        .get("visibleOnly")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let count =
+        super::element::get_element_count(&mgr.client, &session_id, selector, visible_only).await?;
+    Ok(json!({ "count": count, "selector": selector, "visibleOnly": visible_only }))
 }
 
 async fn handle_styles(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
@@ -7536,7 +8403,7 @@ fn stream_file_path(session_id: &str) -> PathBuf {
 
 fn write_stream_file(session_id: &str, port: u16) -> Result<(), String> {
     let path = stream_file_path(session_id);
-    fs::write(&path, port.to_string()).map_err(|e| {
+    write_atomic(&path, port.to_string()).map_err(|e| {
         format!(
             "Failed to write stream metadata '{}': {}",
             path.display(),
@@ -7563,7 +8430,7 @@ fn engine_file_path(session_id: &str) -> PathBuf {
 }
 
 fn write_engine_file(session_id: &str, engine: &str) {
-    let _ = fs::write(engine_file_path(session_id), engine);
+    let _ = write_atomic(engine_file_path(session_id), engine);
 }
 
 fn remove_engine_file(session_id: &str) {
@@ -7575,7 +8442,7 @@ fn provider_file_path(session_id: &str) -> PathBuf {
 }
 
 fn write_provider_file(session_id: &str, provider: &str) {
-    let _ = fs::write(provider_file_path(session_id), provider);
+    let _ = write_atomic(provider_file_path(session_id), provider);
 }
 
 fn remove_provider_file(session_id: &str) {
@@ -7590,7 +8457,7 @@ fn write_extensions_file(session_id: &str) {
     if let Ok(val) = env::var("AGENT_BROWSER_EXTENSIONS") {
         let trimmed = val.trim();
         if !trimmed.is_empty() {
-            let _ = fs::write(extensions_file_path(session_id), trimmed);
+            let _ = write_atomic(extensions_file_path(session_id), trimmed);
             return;
         }
     }
@@ -7612,10 +8479,38 @@ fn write_extensions_file_from_paths(session_id: &str, extensions: Option<&[Strin
     if joined.is_empty() {
         let _ = fs::remove_file(extensions_file_path(session_id));
     } else {
-        let _ = fs::write(extensions_file_path(session_id), joined);
+        let _ = write_atomic(extensions_file_path(session_id), joined);
     }
 }
 
+/// Extensions only exist in a browser agent-browser launched; an attached
+/// browser's extensions were not loaded from `--extension` paths.
+fn handle_extension_list(state: &DaemonState) -> Result<Value, String> {
+    if state.active_provider_session.is_some() {
+        return Err(
+            "Cannot use extension with -p/--provider (extensions require local browser)"
+                .to_string(),
+        );
+    }
+    if state
+        .browser
+        .as_ref()
+        .is_some_and(|mgr| mgr.is_cdp_connection())
+    {
+        return Err(
+            "Cannot use extension with --cdp or --auto-connect (extensions require local browser)"
+                .to_string(),
+        );
+    }
+    let paths: Vec<String> = fs::read_to_string(extensions_file_path(&state.session_id))
+        .unwrap_or_default()
+        .split(',')
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .collect();
+    Ok(extensions::list_json(&paths))
+}
+
 fn remove_extensions_file(session_id: &str) {
     let _ = fs::remove_file(extensions_file_path(session_id));
 }
@@ -7636,7 +8531,7 @@ async fn current_stream_status(state: &DaemonState) -> Value {
         None => false,
     };
 
-    json!({
+    let mut status = json!({
         "enabled": state.stream_server.is_some(),
         "port": state
             .stream_server
@@ -7645,20 +8540,60 @@ async fn current_stream_status(state: &DaemonState) -> Value {
             .unwrap_or(Value::Null),
         "connected": connected,
         "screencasting": connected && (state.screencasting || runtime_screencasting),
-    })
+    });
+    if let Some(ref server) = state.stream_server {
+        let settings = server.settings().await;
+        status["url"] = json!(format!("ws://127.0.0.1:{}", server.port()));
+        status["clients"] = json!(server.client_count().await);
+        status["fps"] = json!(server.current_fps().await);
+        status["quality"] = json!(settings.quality);
+        status["maxFps"] = json!(settings.max_fps);
+    }
+    status
+}
+
+/// `--quality` and `--fps` from `stream start`, falling back to `current`.
+fn stream_settings(cmd: &Value, current: StreamSettings) -> Result<StreamSettings, String> {
+    let quality = match cmd.get("quality").and_then(|v| v.as_u64()) {
+        Some(q) if (1..=100).contains(&q) => q as u8,
+        Some(q) => return Err(format!("Invalid stream quality {}: expected 1-100", q)),
+        None => current.quality,
+    };
+    let max_fps = match cmd.get("fps").and_then(|v| v.as_u64()) {
+        Some(fps) if (1..=60).contains(&fps) => Some(fps as u32),
+        Some(fps) => return Err(format!("Invalid stream fps {}: expected 1-60", fps)),
+        None => current.max_fps,
+    };
+    Ok(StreamSettings { quality, max_fps })
 }
 
 async fn handle_stream_enable(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
-    if state.stream_server.is_some() {
-        return Err("Streaming is already enabled for this session".to_string());
-    }
-
     let requested_port = match cmd.get("port").and_then(|value| value.as_u64()) {
         Some(raw) => u16::try_from(raw)
             .map_err(|_| format!("Invalid stream port '{}': expected 0-65535", raw))?,
         None => 0,
     };
 
+    if let Some(server) = state.stream_server.clone() {
+        // `stream start` on a running stream (the daemon streams by default)
+        // only updates its settings, as long as it is not asked to move.
+        let is_start = cmd.get("action").and_then(|v| v.as_str()) == Some("stream_start");
+        if !is_start {
+            return Err("Streaming is already enabled for this session".to_string());
+        }
+        if requested_port != 0 && requested_port != server.port() {
+            return Err(format!(
+                "Streaming is already running on port {}. Run `stream stop` first to move it to port {}",
+                server.port(),
+                requested_port
+            ));
+        }
+        let settings = stream_settings(cmd, server.settings().await)?;
+        server.set_settings(settings).await;
+        return Ok(current_stream_status(state).await);
+    }
+    let settings = stream_settings(cmd, StreamSettings::default())?;
+
     let (server, client_slot) =
         StreamServer::start_without_client(requested_port, state.session_id.clone(), false).await?;
     let port = server.port();
@@ -7667,6 +8602,7 @@ async fn handle_stream_enable(cmd: &Value, state: &mut DaemonState) -> Result<Va
         return Err(err);
     }
 
+    server.set_settings(settings).await;
     state.stream_client = Some(client_slot);
     state.stream_server = Some(Arc::new(server));
     state.request_tracking = true;
@@ -8060,8 +8996,22 @@ async fn execute_subaction(
     let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
     let session_id = mgr.active_session_id()?.to_string();
 
+    if cmd.get("state").and_then(|v| v.as_str()) == Some("visible")
+        && !super::element::is_element_visible(
+            &mgr.client,
+            &session_id,
+            &state.ref_map,
+            selector,
+            &state.iframe_sessions,
+        )
+        .await?
+    {
+        return Err(FIND_NOT_VISIBLE.to_string());
+    }
+
     match subaction {
         "click" => {
+            let popup_baseline = popup_baseline(cmd, state);
             let result = interaction::click(
                 &mgr.client,
                 &session_id,
@@ -8076,7 +9026,11 @@ async fn execute_subaction(
                 state.pending_pointer_release = result.pending_release;
                 return Ok(json!({ "clicked": selector, "dialogOpened": true }));
             }
-            Ok(json!({ "clicked": selector }))
+            let response = json!({ "clicked": selector });
+            match popup_baseline {
+                Some(baseline) => finish_popup_click(cmd, state, baseline, response).await,
+                None => Ok(response),
+            }
         }
         "fill" => {
             let value = cmd
@@ -8331,6 +9285,65 @@ async fn eval_body_in_active_frame(
     }
 }
 
+/// How often `find --wait` re-runs the locator while it has not resolved.
+const FIND_WAIT_POLL_INTERVAL_MS: u64 = 100;
+
+/// Run a `find` locator and its subaction. With `waitFor` (from
+/// `find --wait <ms>`), a locator that has not resolved yet is retried until
+/// it does or the wait runs out, and the response reports `waitedMs`. Only
+/// "not found" failures are retried, so a subaction that fails on a located
+/// element is reported at once rather than repeated.
+async fn handle_find_locator(
+    cmd: &Value,
+    state: &mut DaemonState,
+    action: &str,
+) -> Result<Value, String> {
+    let wait_ms = cmd.get("waitFor").and_then(|v| v.as_u64()).unwrap_or(0);
+    let started = std::time::Instant::now();
+    loop {
+        let result = match action {
+            "getbyrole" => handle_getbyrole(cmd, state).await,
+            "getbytext" => handle_getbytext(cmd, state).await,
+            "getbylabel" => handle_getbylabel(cmd, state).await,
+            "getbyplaceholder" => handle_getbyplaceholder(cmd, state).await,
+            "getbyalttext" => handle_getbyalttext(cmd, state).await,
+            "getbytitle" => handle_getbytitle(cmd, state).await,
+            "getbytestid" => handle_getbytestid(cmd, state).await,
+            _ => handle_nth(cmd, state).await,
+        };
+        let waited = started.elapsed().as_millis() as u64;
+        match result {
+            Err(e) if waited < wait_ms && is_find_miss(&e) => {
+                let pause = FIND_WAIT_POLL_INTERVAL_MS.min(wait_ms - waited);
+                tokio::time::sleep(tokio::time::Duration::from_millis(pause)).await;
+            }
+            Err(e) if wait_ms > 0 && is_find_miss(&e) => {
+                return Err(format!("{} (waited {}ms)", e, waited));
+            }
+            Ok(mut data) if wait_ms > 0 => {
+                if let Some(obj) = data.as_object_mut() {
+                    obj.insert("waitedMs".to_string(), json!(waited));
+                }
+                return Ok(data);
+            }
+            other => return other,
+        }
+    }
+}
+
+/// Whether a `find` failure means the locator has not resolved (yet), as
+/// opposed to the subaction failing on an element that was found.
+fn is_find_miss(error: &str) -> bool {
+    error.starts_with("No element found")
+        || error.starts_with("No element at index")
+        || error == FIND_NOT_VISIBLE
+        || error.contains("but none match name")
+        || error.contains("has no live DOM element")
+}
+
+/// The error for `find --state visible` when the located element is hidden.
+const FIND_NOT_VISIBLE: &str = "Element found but not visible";
+
 async fn handle_getbyrole(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
     let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
     let session_id = mgr.active_session_id()?.to_string();
@@ -8693,38 +9706,71 @@ async fn handle_nth(cmd: &Value, state: &mut DaemonState) -> Result<Value, Strin
         .and_then(|v| v.as_i64())
         .ok_or("Missing 'index' parameter")?;
 
-    let js = format!(
-        r#"(() => {{
-            const els = document.querySelectorAll({sel});
-            const idx = {idx} < 0 ? els.length + {idx} : {idx};
+    let mark = format!(
+        r#"const idx = {idx} < 0 ? els.length + {idx} : {idx};
             if (idx < 0 || idx >= els.length) return false;
             els[idx].setAttribute('data-agent-browser-located', 'true');
-            return true;
-        }})()"#,
-        sel = serde_json::to_string(selector).unwrap_or_default(),
+            return true;"#,
         idx = index,
     );
 
-    let result: super::cdp::types::EvaluateResult = mgr
-        .client
-        .send_command_typed(
-            "Runtime.evaluate",
-            &super::cdp::types::EvaluateParams {
-                expression: js,
-                return_by_value: Some(true),
-                await_promise: Some(false),
-            },
-            Some(&session_id),
-        )
-        .await?;
+    // `@e5 >> li` counts matches inside the ref's element only.
+    let found = match crate::selector::parse_ref_scope(selector) {
+        Some(scope) => {
+            let (ref_part, sub) = scope?;
+            let (scope_id, scope_session) = super::element::resolve_element_object_id(
+                &mgr.client,
+                &session_id,
+                &state.ref_map,
+                &ref_part,
+                &state.iframe_sessions,
+            )
+            .await?;
+            let function = format!(
+                "function() {{ const els = {}; {} }}",
+                super::element::build_query_all_in_element_js(&sub),
+                mark
+            );
+            let result = mgr
+                .client
+                .send_command(
+                    "Runtime.callFunctionOn",
+                    Some(json!({
+                        "objectId": scope_id,
+                        "functionDeclaration": function,
+                        "returnByValue": true,
+                    })),
+                    Some(&scope_session),
+                )
+                .await?;
+            result
+                .get("result")
+                .and_then(|r| r.get("value"))
+                .and_then(|v| v.as_bool())
+        }
+        None => {
+            let js = format!(
+                "(() => {{ const els = {}; {} }})()",
+                super::element::build_query_all_js("document", selector),
+                mark
+            );
+            let result: super::cdp::types::EvaluateResult = mgr
+                .client
+                .send_command_typed(
+                    "Runtime.evaluate",
+                    &super::cdp::types::EvaluateParams {
+                        expression: js,
+                        return_by_value: Some(true),
+                        await_promise: Some(false),
+                    },
+                    Some(&session_id),
+                )
+                .await?;
+            result.result.value.as_ref().and_then(|v| v.as_bool())
+        }
+    };
 
-    if !result
-        .result
-        .value
-        .as_ref()
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false)
-    {
+    if !found.unwrap_or(false) {
         return Err(format!(
             "No element at index {} for selector '{}'",
             index, selector
@@ -8755,15 +9801,15 @@ async fn handle_find(cmd: &Value, state: &DaemonState) -> Result<Value, String>
 
     let js = format!(
         r#"(() => {{
-            const els = document.querySelectorAll({});
-            return Array.from(els).map((el, i) => ({{
+            const els = {};
+            return els.map((el, i) => ({{
                 index: i,
                 tagName: el.tagName.toLowerCase(),
                 text: el.textContent?.trim().substring(0, 100) || '',
                 visible: el.offsetWidth > 0 && el.offsetHeight > 0,
             }}));
         }})()"#,
-        serde_json::to_string(selector).unwrap_or_default()
+        super::element::build_query_all_js("document", selector)
     );
 
     let result = mgr.evaluate(&js, None).await?;
@@ -8811,22 +9857,30 @@ async fn handle_drag(cmd: &Value, state: &mut DaemonState) -> Result<Value, Stri
         .and_then(|v| v.as_str())
         .ok_or("Missing 'target' parameter")?;
 
-    let (sx, sy, source_session_id) = super::element::resolve_element_center(
-        &mgr.client,
-        &session_id,
-        &state.ref_map,
-        source,
-        &state.iframe_sessions,
-    )
-    .await?;
-    let (tx, ty, target_session_id) = super::element::resolve_element_center(
-        &mgr.client,
-        &session_id,
-        &state.ref_map,
-        target,
-        &state.iframe_sessions,
-    )
-    .await?;
+    let source_offset = drag_offset(cmd, "sourceOffset")?;
+    let target_offset = drag_offset(cmd, "targetOffset")?;
+
+    let (sx, sy, source_session_id) =
+        drag_point(mgr, &session_id, state, source, source_offset).await?;
+    let (tx, ty, target_session_id) =
+        drag_point(mgr, &session_id, state, target, target_offset).await?;
+
+    if cmd.get("method").and_then(|v| v.as_str()) == Some("html5") {
+        interaction::html5_drag(
+            &mgr.client,
+            &session_id,
+            &state.ref_map,
+            source,
+            target,
+            (sx, sy),
+            (tx, ty),
+            &state.iframe_sessions,
+        )
+        .await?;
+        return Ok(
+            json!({ "dragged": true, "source": source, "target": target, "method": "html5" }),
+        );
+    }
 
     // Mouse down at source
     mgr.client
@@ -8872,6 +9926,55 @@ async fn handle_drag(cmd: &Value, state: &mut DaemonState) -> Result<Value, Stri
     Ok(json!({ "dragged": true, "source": source, "target": target }))
 }
 
+/// A drag `sourceOffset`/`targetOffset` (`{ "x": .., "y": .. }`), if set.
+fn drag_offset(cmd: &Value, key: &str) -> Result<Option<(f64, f64)>, String> {
+    let Some(offset) = cmd.get(key) else {
+        return Ok(None);
+    };
+    let x = offset.get("x").and_then(|v| v.as_i64());
+    let y = offset.get("y").and_then(|v| v.as_i64());
+    match (x, y) {
+        (Some(x), Some(y)) => Ok(Some((x as f64, y as f64))),
+        _ => Err(format!("'{}' must have integer x and y", key)),
+    }
+}
+
+/// Where a drag grabs or drops on an element: its center, or `offset`
+/// pixels from its top-left corner.
+async fn drag_point(
+    mgr: &BrowserManager,
+    session_id: &str,
+    state: &DaemonState,
+    selector: &str,
+    offset: Option<(f64, f64)>,
+) -> Result<(f64, f64, String), String> {
+    // Resolving the center also scrolls the element into view, so the
+    // bounding box below is in the same viewport coordinates.
+    let (x, y, frame_session_id) = super::element::resolve_element_center(
+        &mgr.client,
+        session_id,
+        &state.ref_map,
+        selector,
+        &state.iframe_sessions,
+    )
+    .await?;
+    let Some((dx, dy)) = offset else {
+        return Ok((x, y, frame_session_id));
+    };
+    let bbox = super::element::get_element_bounding_box(
+        &mgr.client,
+        session_id,
+        &state.ref_map,
+        selector,
+        &state.iframe_sessions,
+        false,
+    )
+    .await?;
+    let left = bbox.get("x").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let top = bbox.get("y").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    Ok((left + dx, top + dy, frame_session_id))
+}
+
 async fn handle_expose(cmd: &Value, state: &DaemonState) -> Result<Value, String> {
     let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
     let session_id = mgr.active_session_id()?.to_string();
@@ -9175,7 +10278,9 @@ async fn handle_diff_screenshot(cmd: &Value, state: &DaemonState) -> Result<Valu
         format: "png".to_string(),
         quality: None,
         annotate: false,
+        interactive_only: false,
         output_dir: None,
+        clip: None,
     };
 
     let result = screenshot::take_screenshot(
@@ -9198,7 +10303,7 @@ async fn handle_diff_screenshot(cmd: &Value, state: &DaemonState) -> Result<Valu
 
     let output_path = cmd.get("output").and_then(|v| v.as_str());
     if let (Some(out_path), Some(ref diff_data)) = (output_path, &result.diff_image) {
-        std::fs::write(out_path, diff_data)
+        write_atomic(out_path, diff_data)
             .map_err(|e| format!("Failed to write diff image: {}", e))?;
     }
 
@@ -9290,17 +10395,39 @@ async fn handle_har_start(cmd: &Value, state: &mut DaemonState) -> Result<Value,
     state.har_entries.clear();
     state.har_content_mode = content_mode;
     state.har_body_total_bytes = 0;
+    state.har_path = cmd.get("path").and_then(|v| v.as_str()).map(String::from);
     Ok(json!({ "started": true }))
 }
 
 /// Stop HAR recording and write the captured requests to disk.
 async fn handle_har_stop(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
-    let path = har_output_path(cmd.get("path").and_then(|v| v.as_str()));
+    let start_path = state.har_path.take();
+    let path = har_output_path(
+        cmd.get("path")
+            .and_then(|v| v.as_str())
+            .or(start_path.as_deref()),
+    );
 
     state.har_recording = false;
     state.har_body_total_bytes = 0;
 
-    let entries: Vec<Value> = state.har_entries.drain(..).map(har_entry_to_json).collect();
+    let attach_dir = (state.har_content_mode == HarContentMode::Attach)
+        .then(|| har_attach_dir(std::path::Path::new(&path)));
+    let mut entries: Vec<Value> = Vec::new();
+    for (index, mut entry) in state.har_entries.drain(..).enumerate() {
+        let attached = match (&attach_dir, entry.response_body.take()) {
+            (Some(dir), Some(body)) => Some(write_har_body(dir, index, &entry, &body)?),
+            (_, body) => {
+                entry.response_body = body;
+                None
+            }
+        };
+        let mut json = har_entry_to_json(entry);
+        if let Some(file) = attached {
+            json["response"]["content"]["_file"] = json!(file);
+        }
+        entries.push(json);
+    }
     let request_count = entries.len();
     let browser = har_browser_metadata(state).await;
 
@@ -9319,7 +10446,7 @@ async fn handle_har_stop(cmd: &Value, state: &mut DaemonState) -> Result<Value,
 
     let har_str = serde_json::to_string_pretty(&har)
         .map_err(|e| format!("Failed to serialize HAR: {}", e))?;
-    std::fs::write(&path, har_str).map_err(|e| format!("Failed to write HAR: {}", e))?;
+    write_atomic(&path, har_str).map_err(|e| format!("Failed to write HAR: {}", e))?;
 
     Ok(json!({ "path": path, "requestCount": request_count }))
 }
@@ -9623,6 +10750,58 @@ fn har_wall_time_to_rfc3339(wall_time: f64) -> String {
         .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string())
 }
 
+/// Directory for `--content attach` bodies: `capture.har` keeps them in
+/// `capture-bodies/` beside it.
+fn har_attach_dir(har_path: &std::path::Path) -> PathBuf {
+    let stem = har_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "har".to_string());
+    har_path.with_file_name(format!("{}-bodies", stem))
+}
+
+/// Write one response body for `--content attach` and return the `_file`
+/// value for its entry: a path relative to the HAR file.
+fn write_har_body(
+    dir: &std::path::Path,
+    index: usize,
+    entry: &HarEntry,
+    body: &str,
+) -> Result<String, String> {
+    let bytes = if entry.response_body_base64 {
+        base64::Engine::decode(&base64::engine::general_purpose::STANDARD, body)
+            .map_err(|e| format!("Failed to decode response body for HAR: {}", e))?
+    } else {
+        body.as_bytes().to_vec()
+    };
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let name = format!("{}.{}", index + 1, har_body_extension(&entry.mime_type));
+    write_atomic(dir.join(&name), bytes)
+        .map_err(|e| format!("Failed to write HAR body {}: {}", name, e))?;
+    let dir_name = dir.file_name().unwrap_or_default().to_string_lossy();
+    Ok(format!("{}/{}", dir_name, name))
+}
+
+fn har_body_extension(mime_type: &str) -> &'static str {
+    let essence = mime_type.split(';').next().unwrap_or("").trim();
+    match essence.to_ascii_lowercase().as_str() {
+        "text/html" => "html",
+        "text/css" => "css",
+        "text/javascript" | "application/javascript" => "js",
+        "text/plain" => "txt",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "font/woff2" => "woff2",
+        m if m == "application/json" || m.ends_with("+json") => "json",
+        m if m.ends_with("/xml") || m.ends_with("+xml") => "xml",
+        _ => "bin",
+    }
+}
+
 fn har_output_path(explicit_path: Option<&str>) -> String {
     match explicit_path {
         Some(path) => path.to_string(),
@@ -9651,6 +10830,26 @@ fn unix_timestamp_millis() -> u128 {
         .as_millis()
 }
 
+/// Launch response for a `--cdp` / `connect` attach, with the browser's
+/// product string and how many page targets were picked up.
+async fn cdp_connected_response(state: &DaemonState, relaunched: bool) -> Value {
+    let mut resp = json!({ "launched": true, "relaunchedBrowser": relaunched });
+    let Some(mgr) = state.browser.as_ref() else {
+        return resp;
+    };
+    resp["targets"] = json!(mgr.page_count());
+    if let Ok(version) = mgr
+        .client
+        .send_command_no_params("Browser.getVersion", None)
+        .await
+    {
+        if let Some(product) = version.get("product").and_then(|v| v.as_str()) {
+            resp["browserVersion"] = json!(product);
+        }
+    }
+    resp
+}
+
 async fn har_browser_metadata(state: &DaemonState) -> Option<Value> {
     let mgr = state.browser.as_ref()?;
     if !mgr.is_connection_alive().await {
@@ -10126,7 +11325,7 @@ async fn handle_requests(cmd: &Value, state: &mut DaemonState) -> Result<Value,
         .map(|t| t.split(',').map(|s| s.trim().to_lowercase()).collect())
         .unwrap_or_default();
 
-    let requests: Vec<&TrackedRequest> = state
+    let matching: Vec<&TrackedRequest> = state
         .tracked_requests
         .iter()
         .filter(|r| {
@@ -10151,8 +11350,9 @@ async fn handle_requests(cmd: &Value, state: &mut DaemonState) -> Result<Value,
             true
         })
         .collect();
+    let requests = network::tail_of(matching, tail_param(cmd));
 
-    Ok(json!({ "requests": requests }))
+    Ok(json!({ "requests": requests, "buffer": state.tracked_requests.stats() }))
 }
 
 async fn handle_request_detail(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
@@ -10200,9 +11400,42 @@ async fn handle_request_detail(cmd: &Value, state: &mut DaemonState) -> Result<V
     Ok(result)
 }
 
-async fn handle_http_credentials(cmd: &Value, state: &DaemonState) -> Result<Value, String> {
+/// Drop `set credentials` Authorization headers from the origin-scoped map,
+/// for one origin or all of them. Origins left without headers are removed.
+/// Returns how many origins had credentials.
+fn remove_scoped_basic_auth(
+    map: &mut HashMap<String, HashMap<String, String>>,
+    origin: Option<&str>,
+) -> usize {
+    let mut removed = 0;
+    map.retain(|key, headers| {
+        if origin.is_some_and(|o| o != key) {
+            return true;
+        }
+        let before = headers.len();
+        headers.retain(|name, value| {
+            !(name.eq_ignore_ascii_case("authorization") && value.starts_with("Basic "))
+        });
+        if headers.len() < before {
+            removed += 1;
+        }
+        !headers.is_empty()
+    });
+    removed
+}
+
+async fn handle_http_credentials(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
+    let origin = cmd.get("origin").and_then(|v| v.as_str());
+    if cmd.get("clear").and_then(|v| v.as_bool()).unwrap_or(false) {
+        let scoped = remove_scoped_basic_auth(&mut *state.origin_headers.write().await, origin);
+        let global = origin.is_none() && state.basic_auth_header.take().is_some();
+        if global {
+            apply_extra_http_headers(state).await?;
+        }
+        return Ok(json!({ "cleared": scoped + usize::from(global), "origin": origin }));
+    }
+
     let mgr = state.browser.as_ref().ok_or("Browser not launched")?;
-    let session_id = mgr.active_session_id()?.to_string();
     let username = cmd
         .get("username")
         .and_then(|v| v.as_str())
@@ -10217,11 +11450,30 @@ async fn handle_http_credentials(cmd: &Value, state: &DaemonState) -> Result<Val
         format!("{}:{}", username, password),
     );
 
-    let mut headers = HashMap::new();
-    headers.insert("Authorization".to_string(), format!("Basic {}", encoded));
-    network::set_extra_headers(&mgr.client, &session_id, &headers).await?;
+    let header = format!("Basic {}", encoded);
 
-    Ok(json!({ "set": true }))
+    match origin {
+        Some(origin) => {
+            let first_origin_header = {
+                let mut map = state.origin_headers.write().await;
+                let first = map.is_empty();
+                map.entry(origin.to_string())
+                    .or_default()
+                    .insert("Authorization".to_string(), header);
+                first
+            };
+            if first_origin_header {
+                let has_proxy_creds = state.proxy_credentials.read().await.is_some();
+                enable_origin_header_interception(mgr, has_proxy_creds).await?;
+            }
+            Ok(json!({ "set": true, "origin": origin }))
+        }
+        None => {
+            state.basic_auth_header = Some(header);
+            apply_extra_http_headers(state).await?;
+            Ok(json!({ "set": true }))
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -10765,6 +12017,89 @@ async fn handle_device_list() -> Result<Value, String> {
     }
 }
 
+/// Resolve the `device` argument of `device use` / `device boot`.
+fn requested_device(cmd: &Value, action: &str) -> Result<ios::IosDevice, String> {
+    if !cfg!(target_os = "macos") {
+        return Err(format!("{} is only available on macOS with Xcode", action));
+    }
+    let query = cmd
+        .get("device")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing 'device' parameter")?;
+    let devices = ios::list_all_devices()?;
+    ios::find_device(&devices, query)
+        .cloned()
+        .ok_or_else(|| format!("Device '{}' not found", query))
+}
+
+/// `device use`: make a device the session's iOS target. A running iOS
+/// session is closed and relaunched on the new device.
+async fn handle_device_use(cmd: &Value, state: &mut DaemonState) -> Result<Value, String> {
+    let device = requested_device(cmd, "device_use")?;
+    state.ios_device_udid = Some(device.udid.clone());
+
+    let relaunch = state.appium.is_some();
+    if relaunch {
+        if let Some(ref mut wb) = state.webdriver_backend {
+            let _ = wb.close().await;
+        }
+        state.webdriver_backend = None;
+        if let Some(ref mut appium) = state.appium {
+            let _ = appium.close().await;
+        }
+        state.appium = None;
+        state.ref_map.clear();
+        launch_ios(&json!({ "udid": device.udid }), state).await?;
+    }
+
+    Ok(json!({
+        "device": device.name,
+        "udid": device.udid,
+        "relaunched": relaunch,
+    }))
+}
+
+/// Longest `device boot` waits for a simulator to report `Booted`.
+const DEVICE_BOOT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
+/// `device boot`: boot a simulator and report each state it passed through.
+async fn handle_device_boot(cmd: &Value) -> Result<Value, String> {
+    let device = requested_device(cmd, "device_boot")?;
+    if device.is_real {
+        return Err(format!(
+            "'{}' is a physical device; only simulators can be booted",
+            device.name
+        ));
+    }
+
+    let mut transitions = vec![device.state.clone()];
+    if device.state != "Booted" {
+        ios::boot_simulator(&device.udid)?;
+        let deadline = std::time::Instant::now() + DEVICE_BOOT_TIMEOUT;
+        loop {
+            let current = ios::simulator_state(&device.udid)?;
+            ios::push_state(&mut transitions, &current);
+            if current == "Booted" {
+                break;
+            }
+            if std::time::Instant::now() >= deadline {
+                return Err(format!(
+                    "Timed out waiting for {} to boot (state: {})",
+                    device.name, current
+                ));
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+    }
+
+    Ok(json!({
+        "device": device.name,
+        "udid": device.udid,
+        "state": "Booted",
+        "transitions": transitions,
+    }))
+}
+
 // ---------------------------------------------------------------------------
 // Input event handlers
 // ---------------------------------------------------------------------------
@@ -11194,6 +12529,30 @@ mod tests {
         assert!(validate_find_subaction("click", &json!({ "subaction": "type" })).is_ok());
     }
 
+    /// `find --wait` retries only while the locator has not resolved; a
+    /// subaction that failed on a found element must not be repeated.
+    #[test]
+    fn is_find_miss_only_matches_unresolved_locators() {
+        for miss in [
+            "No element found: getByRole('button', { name: 'Submit' })",
+            "No element found by text 'Save'",
+            "No element at index 3 for selector 'li'",
+            "2 elements have role \"button\", but none match name \"Send\". Names seen: \"A\", \"B\"",
+            "Found role \"button\" in the accessibility tree, but it has no live DOM element to act on.",
+            FIND_NOT_VISIBLE,
+        ] {
+            assert!(is_find_miss(miss), "{}", miss);
+        }
+        for other in [
+            "Element is disabled",
+            "Missing 'value' for fill subaction",
+            "Unknown action 'type' for find. Valid actions: click.",
+            "Browser not launched",
+        ] {
+            assert!(!is_find_miss(other), "{}", other);
+        }
+    }
+
     /// Every entry in `FIND_ACTIONS` must actually dispatch in
     /// `execute_subaction`'s match, not just pass the guard; this is the
     /// real lock the guard alone can't provide, since the guard and the
@@ -12042,6 +13401,26 @@ mod tests {
         assert!(state.pending_confirmation.is_none());
     }
 
+    #[tokio::test]
+    async fn test_downloads_clear_requires_confirmation() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("report.pdf");
+        fs::write(&file, b"pdf").unwrap();
+        let mut state = DaemonState::new();
+        state.downloads.saved("guid-1", &file);
+
+        let cmd = json!({ "action": "downloads_clear", "id": "dl-clear" });
+        let resp = execute_command(&cmd, &mut state).await;
+        assert_eq!(resp["data"]["confirmation_required"], true);
+        assert_eq!(resp["data"]["confirmation_id"], "dl-clear");
+        assert!(file.exists());
+
+        let confirm = json!({ "action": "confirm", "id": "c1", "confirmationId": "dl-clear" });
+        let resp = execute_command(&confirm, &mut state).await;
+        assert_eq!(resp["data"]["result"]["data"]["cleared"], 1);
+        assert!(!file.exists());
+    }
+
     #[tokio::test]
     async fn test_policy_denies_read_before_fetch() {
         let dir = tempfile::tempdir().unwrap();
@@ -12548,6 +13927,65 @@ printf '%s' '{"protocol":"agent-browser.plugin.v1","success":true,"data":{}}'
         let _ = fs::remove_dir_all(&socket_dir);
     }
 
+    #[tokio::test]
+    async fn test_stream_start_reports_url_and_updates_settings() {
+        let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "AGENT_BROWSER_SESSION"]);
+        let socket_dir = unique_socket_dir("stream-start");
+        fs::create_dir_all(&socket_dir).expect("socket dir should be created");
+        guard.set(
+            "AGENT_BROWSER_SOCKET_DIR",
+            socket_dir.to_str().expect("socket dir should be utf-8"),
+        );
+        guard.set("AGENT_BROWSER_SESSION", "stream-start-session");
+
+        let mut state = DaemonState::new();
+
+        let started = handle_stream_enable(
+            &json!({ "action": "stream_start", "quality": 50 }),
+            &mut state,
+        )
+        .await
+        .expect("stream start should succeed");
+        let port = started["port"]
+            .as_u64()
+            .expect("stream should report a port");
+        assert_eq!(started["url"], format!("ws://127.0.0.1:{}", port));
+        assert_eq!(started["clients"], 0);
+        assert_eq!(started["fps"], 0);
+        assert_eq!(started["quality"], 50);
+        assert_eq!(started["maxFps"], Value::Null);
+
+        // Starting again keeps the server and only changes its settings.
+        let restarted =
+            handle_stream_enable(&json!({ "action": "stream_start", "fps": 10 }), &mut state)
+                .await
+                .expect("stream start on a running stream should update settings");
+        assert_eq!(restarted["port"], port);
+        assert_eq!(restarted["quality"], 50);
+        assert_eq!(restarted["maxFps"], 10);
+
+        let other_port = if port == 65535 { port - 1 } else { port + 1 };
+        let err = handle_stream_enable(
+            &json!({ "action": "stream_start", "port": other_port }),
+            &mut state,
+        )
+        .await
+        .expect_err("moving a running stream should fail");
+        assert!(err.contains("stream stop"), "{}", err);
+        let err = handle_stream_enable(
+            &json!({ "action": "stream_start", "quality": 0 }),
+            &mut state,
+        )
+        .await
+        .expect_err("out-of-range quality should fail");
+        assert!(err.contains("1-100"), "{}", err);
+
+        handle_stream_disable(&mut state)
+            .await
+            .expect("stream stop should succeed");
+        let _ = fs::remove_dir_all(&socket_dir);
+    }
+
     #[tokio::test]
     async fn test_stream_disable_preserves_existing_screencast_state() {
         let guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "AGENT_BROWSER_SESSION"]);
@@ -13101,6 +14539,26 @@ printf '%s' '{"protocol":"agent-browser.plugin.v1","success":true,"data":{}}'
         }
     }
 
+    #[test]
+    fn test_profile_with_user_data_dir() {
+        assert_eq!(
+            profile_with_user_data_dir(Some("/p".to_string()), None).unwrap(),
+            Some("/p".to_string())
+        );
+        assert_eq!(
+            profile_with_user_data_dir(None, Some("/home/me/.config/google-chrome")).unwrap(),
+            Some("/home/me/.config/google-chrome".to_string())
+        );
+        let err = profile_with_user_data_dir(Some("/p".to_string()), Some("/u")).unwrap_err();
+        assert!(
+            err.contains("--profile and --user-data-dir"),
+            "got: {}",
+            err
+        );
+        let err = profile_with_user_data_dir(None, Some("Default")).unwrap_err();
+        assert!(err.contains("not a profile name"), "got: {}", err);
+    }
+
     #[tokio::test]
     async fn test_allowed_domains_reject_chrome_profiles() {
         let guard = EnvGuard::new(&["AGENT_BROWSER_ALLOWED_DOMAINS"]);
@@ -13787,6 +15245,9 @@ printf '%s' '{"protocol":"agent-browser.plugin.v1","success":true,"browser":{"cd
         assert_eq!(HarContentMode::parse("all"), Ok(HarContentMode::All));
         assert_eq!(HarContentMode::parse("none"), Ok(HarContentMode::None));
         assert!(HarContentMode::parse("everything").is_err());
+        assert_eq!(HarContentMode::parse("omit"), Ok(HarContentMode::None));
+        assert_eq!(HarContentMode::parse("embed"), Ok(HarContentMode::All));
+        assert_eq!(HarContentMode::parse("attach"), Ok(HarContentMode::Attach));
         assert_eq!(HarContentMode::default(), HarContentMode::Text);
     }
 
@@ -13928,6 +15389,71 @@ printf '%s' '{"protocol":"agent-browser.plugin.v1","success":true,"browser":{"cd
         let _ = fs::remove_file(path);
     }
 
+    #[tokio::test]
+    async fn test_handle_har_stop_attaches_bodies_to_start_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let har_path = tmp.path().join("capture.har");
+        let mut state = DaemonState::new();
+        state.har_recording = true;
+        state.har_content_mode = HarContentMode::Attach;
+        state.har_path = Some(har_path.to_string_lossy().to_string());
+        for (id, mime, body, base64) in [
+            (
+                "req-1",
+                "application/json; charset=utf-8",
+                "{\"ok\":true}",
+                false,
+            ),
+            ("req-2", "image/png", "aGVsbG8=", true),
+            ("req-3", "text/css", "", false),
+        ] {
+            state.har_entries.push(HarEntry {
+                request_id: id.to_string(),
+                wall_time: 1773576000.0,
+                method: "GET".to_string(),
+                url: "https://example.com/".to_string(),
+                request_headers: vec![],
+                post_data: None,
+                request_body_size: 0,
+                resource_type: "Fetch".to_string(),
+                status: Some(200),
+                status_text: "OK".to_string(),
+                http_version: "HTTP/2.0".to_string(),
+                response_headers: vec![],
+                mime_type: mime.to_string(),
+                redirect_url: String::new(),
+                response_body_size: body.len() as i64,
+                cdp_timing: None,
+                loading_finished_timestamp: None,
+                response_body: (id != "req-3").then(|| body.to_string()),
+                response_body_base64: base64,
+            });
+        }
+
+        let result = handle_har_stop(&json!({ "action": "har_stop" }), &mut state)
+            .await
+            .unwrap();
+        assert_eq!(result["path"], json!(har_path.to_string_lossy()));
+        assert!(state.har_path.is_none());
+
+        let har: Value = serde_json::from_str(&fs::read_to_string(&har_path).unwrap()).unwrap();
+        let entries = har["log"]["entries"].as_array().unwrap();
+        let content = &entries[0]["response"]["content"];
+        assert_eq!(content["_file"], "capture-bodies/1.json");
+        assert!(content.get("text").is_none());
+        assert_eq!(
+            entries[1]["response"]["content"]["_file"],
+            "capture-bodies/2.png"
+        );
+        assert!(entries[2]["response"]["content"].get("_file").is_none());
+        let bodies = tmp.path().join("capture-bodies");
+        assert_eq!(
+            fs::read_to_string(bodies.join("1.json")).unwrap(),
+            "{\"ok\":true}"
+        );
+        assert_eq!(fs::read(bodies.join("2.png")).unwrap(), b"hello");
+    }
+
     #[tokio::test]
     async fn test_execute_har_stop_skips_browser_auto_launch() {
         let path = std::env::temp_dir().join(format!(
@@ -14014,6 +15540,9 @@ printf '%s' '{"protocol":"agent-browser.plugin.v1","success":true,"browser":{"cd
             "Unexpected error: {}",
             error_msg
         );
+        if error_msg.contains("Not yet implemented") {
+            assert_eq!(result["errorKind"], "protocol");
+        }
     }
 
     #[tokio::test]
@@ -14247,6 +15776,43 @@ printf '%s' '{"protocol":"agent-browser.plugin.v1","success":true,"browser":{"cd
         assert_eq!(patterns[0]["urlPattern"], "*");
     }
 
+    #[test]
+    fn test_remove_scoped_basic_auth() {
+        let headers = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        let mut map = HashMap::new();
+        map.insert(
+            "https://a.example".to_string(),
+            headers(&[("Authorization", "Basic YTpi")]),
+        );
+        map.insert(
+            "https://b.example".to_string(),
+            headers(&[("authorization", "Basic Yzpk"), ("X-Trace", "1")]),
+        );
+        map.insert(
+            "https://c.example".to_string(),
+            headers(&[("Authorization", "Bearer token")]),
+        );
+
+        assert_eq!(
+            remove_scoped_basic_auth(&mut map, Some("https://b.example")),
+            1
+        );
+        assert_eq!(map["https://b.example"], headers(&[("X-Trace", "1")]));
+        assert!(map.contains_key("https://a.example"));
+
+        // Clearing everything keeps headers set with `open --headers`.
+        assert_eq!(remove_scoped_basic_auth(&mut map, None), 1);
+        assert!(!map.contains_key("https://a.example"));
+        assert!(map.contains_key("https://b.example"));
+        assert!(map.contains_key("https://c.example"));
+        assert_eq!(remove_scoped_basic_auth(&mut map, None), 0);
+    }
+
     #[tokio::test]
     async fn test_build_fetch_patterns_adds_wildcard_for_origin_headers() {
         let state = DaemonState::new();
@@ -6,6 +6,8 @@ use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
 
+use crate::atomic_write::write_private;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthProfile {
@@ -130,13 +132,8 @@ fn ensure_encryption_key() -> Result<Vec<u8>, String> {
     }
 
     let key_file = get_key_file_path();
-    fs::write(&key_file, format!("{}\n", key_hex))
+    write_private(&key_file, format!("{}\n", key_hex))
         .map_err(|e| format!("Failed to write encryption key: {}", e))?;
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let _ = fs::set_permissions(&key_file, fs::Permissions::from_mode(0o600));
-    }
 
     let _ = writeln!(
         std::io::stderr(),
@@ -244,13 +241,7 @@ fn save_profile(profile: &AuthProfile) -> Result<(), String> {
 
     let encrypted_json = encrypt_profile(profile)?;
     let path = get_profile_path(&profile.name);
-    fs::write(&path, &encrypted_json).map_err(|e| format!("Failed to write profile: {}", e))?;
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o600));
-    }
-    Ok(())
+    write_private(&path, &encrypted_json).map_err(|e| format!("Failed to write profile: {}", e))
 }
 
 fn load_profile(name: &str) -> Result<AuthProfile, String> {
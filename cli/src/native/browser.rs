@@ -91,6 +91,10 @@ fn validate_lightpanda_options(options: &LaunchOptions) -> Result<(), String> {
     Ok(())
 }
 
+/// How long navigation waits for its load event before failing, until
+/// `set timeout --navigation` changes it.
+pub const DEFAULT_NAVIGATION_TIMEOUT_MS: u64 = 25_000;
+
 /// Liveness-probe timeout. A live renderer answers in ms; a discarded tab has
 /// no renderer and never answers, so a short timeout is the only discard signal
 /// CDP exposes (#1528). A false positive is cheap: recovery reactivates the tab,
@@ -352,6 +356,13 @@ pub struct BrowserManager {
     pub download_path: Option<String>,
     /// Whether to ignore HTTPS certificate errors, re-applied to new contexts (e.g., recording)
     pub ignore_https_errors: bool,
+    /// Chrome was started with --ignore-certificate-errors, which cannot be
+    /// turned off again at runtime.
+    https_errors_ignored_at_launch: bool,
+    /// Whether page sessions report certificate errors as
+    /// Security.certificateError events for the daemon to decide, used by
+    /// per-origin `--ignore-https-origin` / `set tls allow-origin`.
+    certificate_overrides: bool,
     /// Origins visited during this session, used by save_state to collect cross-origin localStorage.
     visited_origins: HashSet<String>,
     next_tab_id: u32,
@@ -425,9 +436,11 @@ impl BrowserManager {
                 ws_url,
                 pages: Vec::new(),
                 active_page_index: 0,
-                default_timeout_ms: 25_000,
+                default_timeout_ms: DEFAULT_NAVIGATION_TIMEOUT_MS,
                 download_path: download_path.clone(),
                 ignore_https_errors,
+                https_errors_ignored_at_launch: ignore_https_errors,
+                certificate_overrides: false,
                 visited_origins: HashSet::new(),
                 next_tab_id: 1,
                 direct_page: false,
@@ -476,7 +489,11 @@ impl BrowserManager {
                 .client
                 .send_command(
                     "Browser.setDownloadBehavior",
-                    Some(json!({ "behavior": "allow", "downloadPath": path })),
+                    Some(json!({
+                        "behavior": "allow",
+                        "downloadPath": path,
+                        "eventsEnabled": true,
+                    })),
                     None,
                 )
                 .await;
@@ -515,9 +532,11 @@ impl BrowserManager {
             ws_url,
             pages: Vec::new(),
             active_page_index: 0,
-            default_timeout_ms: 25_000,
+            default_timeout_ms: DEFAULT_NAVIGATION_TIMEOUT_MS,
             download_path: None,
             ignore_https_errors: false,
+            https_errors_ignored_at_launch: false,
+            certificate_overrides: false,
             visited_origins: HashSet::new(),
             next_tab_id: 1,
             direct_page,
@@ -683,6 +702,9 @@ impl BrowserManager {
         self.client
             .send_command_no_params("Network.enable", Some(session_id))
             .await?;
+        if self.certificate_overrides {
+            self.override_certificate_errors(session_id).await?;
+        }
         // Enable auto-attach for cross-origin iframe support.
         // flatten: true gives each iframe its own session_id.
         // waitForDebuggerOnStart keeps child targets paused until the daemon
@@ -703,6 +725,71 @@ impl BrowserManager {
         Ok(())
     }
 
+    async fn override_certificate_errors(&self, session_id: &str) -> Result<(), String> {
+        self.client
+            .send_command_no_params("Security.enable", Some(session_id))
+            .await?;
+        self.client
+            .send_command(
+                "Security.setOverrideCertificateErrors",
+                Some(json!({ "override": true })),
+                Some(session_id),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub fn certificate_overrides(&self) -> bool {
+        self.certificate_overrides
+    }
+
+    /// Route certificate errors on every open page, and on pages opened
+    /// later, through Security.certificateError so the daemon can let
+    /// allowed origins through.
+    pub async fn enable_certificate_overrides(&mut self) -> Result<(), String> {
+        if self.certificate_overrides {
+            return Ok(());
+        }
+        for page in &self.pages {
+            self.override_certificate_errors(&page.session_id).await?;
+        }
+        self.certificate_overrides = true;
+        Ok(())
+    }
+
+    /// Toggle ignoring certificate errors for the whole browser. Turning it
+    /// off fails when Chrome was launched with --ignore-https-errors, since
+    /// that command-line switch wins over the CDP setting.
+    pub async fn set_ignore_https_errors(&mut self, ignore: bool) -> Result<(), String> {
+        if !ignore && self.https_errors_ignored_at_launch {
+            return Err(
+                "The browser was launched with --ignore-https-errors, which cannot be turned off at runtime. Close the session and relaunch without it.".to_string(),
+            );
+        }
+        // Browser-level where available so new tabs inherit it; page-level as
+        // a fallback for direct page connections.
+        let _ = self
+            .client
+            .send_command(
+                "Security.setIgnoreCertificateErrors",
+                Some(json!({ "ignore": ignore })),
+                None,
+            )
+            .await;
+        for page in &self.pages {
+            let _ = self
+                .client
+                .send_command(
+                    "Security.setIgnoreCertificateErrors",
+                    Some(json!({ "ignore": ignore })),
+                    Some(&page.session_id),
+                )
+                .await;
+        }
+        self.ignore_https_errors = ignore;
+        Ok(())
+    }
+
     /// Whether the tab's renderer answers a renderer-bound command within
     /// `timeout_ms`. A discarded tab keeps its CDP session but has no
     /// renderer to reply (#1528). A CDP error still counts as responding.
@@ -1029,6 +1116,10 @@ impl BrowserManager {
         self.default_timeout_ms
     }
 
+    pub fn set_default_timeout_ms(&mut self, timeout_ms: u64) {
+        self.default_timeout_ms = timeout_ms;
+    }
+
     /// Checks if the CDP connection is alive by sending a simple command.
     /// Returns false if the command times out or fails.
     pub async fn is_connection_alive(&self) -> bool {
@@ -1884,9 +1975,11 @@ async fn initialize_lightpanda_manager(
             ws_url: ws_url.clone(),
             pages: Vec::new(),
             active_page_index: 0,
-            default_timeout_ms: 25_000,
+            default_timeout_ms: DEFAULT_NAVIGATION_TIMEOUT_MS,
             download_path: None,
             ignore_https_errors: false,
+            https_errors_ignored_at_launch: false,
+            certificate_overrides: false,
             visited_origins: HashSet::new(),
             next_tab_id: 1,
             direct_page: false,
@@ -1087,6 +1087,19 @@ pub fn find_chrome_user_data_dir() -> Option<PathBuf> {
         .find(|dir| dir.join("Local State").is_file())
 }
 
+/// Returns the lock file Chrome leaves in a user-data directory while a
+/// browser is using it (`SingletonLock` on macOS/Linux, `lockfile` on
+/// Windows). A second Chrome pointed at the same directory hands off to the
+/// running one and exits, so a launch against it fails.
+pub fn user_data_dir_lock(dir: &Path) -> Option<PathBuf> {
+    ["SingletonLock", "lockfile"]
+        .iter()
+        .map(|name| dir.join(name))
+        // SingletonLock is a symlink to `<host>-<pid>`, which never resolves,
+        // so check the link itself rather than its target.
+        .find(|path| std::fs::symlink_metadata(path).is_ok())
+}
+
 /// A Chrome profile entry parsed from `Local State`.
 #[derive(Debug, Clone)]
 pub struct ChromeProfile {
@@ -2089,6 +2102,19 @@ mod tests {
         assert!(!is_chrome_profile_name("relative/path"));
     }
 
+    #[test]
+    fn test_user_data_dir_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(user_data_dir_lock(dir.path()), None);
+
+        let lock = dir.path().join("SingletonLock");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("myhost-12345", &lock).unwrap();
+        #[cfg(not(unix))]
+        std::fs::write(&lock, "").unwrap();
+        assert_eq!(user_data_dir_lock(dir.path()), Some(lock));
+    }
+
     /// Helper to create a fake Chrome user-data dir with a `Local State` file.
     fn create_fake_local_state(base: &Path, profiles: &[(&str, &str)]) {
         let mut info_cache = serde_json::Map::new();
@@ -2,4 +2,6 @@ pub mod chrome;
 pub mod client;
 pub mod discovery;
 pub mod lightpanda;
+pub mod preflight;
+pub mod scan;
 pub mod types;
@@ -0,0 +1,322 @@
+//! Pre-flight check for `--cdp` URLs.
+//!
+//! Runs in the CLI before the daemon is started so that a dead or
+//! unauthorized endpoint fails fast with a specific reason instead of a
+//! generic "CDP connection failed" after the daemon's own retries.
+
+use std::fmt;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::redact::redact_url;
+
+/// Budget for the whole pre-flight (DNS plus the `/json/version` request).
+pub const PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Version details reported by an endpoint's `/json/version`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CdpEndpointVersion {
+    #[serde(rename = "Browser")]
+    pub browser: Option<String>,
+    #[serde(rename = "Protocol-Version")]
+    pub protocol_version: Option<String>,
+}
+
+impl CdpEndpointVersion {
+    pub fn summary(&self) -> String {
+        let browser = self.browser.as_deref().unwrap_or("unknown browser");
+        match self.protocol_version.as_deref() {
+            Some(protocol) => format!("{} (protocol {})", browser, protocol),
+            None => browser.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreflightError {
+    InvalidUrl(String),
+    Dns { host: String, error: String },
+    Unauthorized { url: String, status: u16 },
+    Timeout { url: String },
+    Connect { url: String, error: String },
+}
+
+impl fmt::Display for PreflightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreflightError::InvalidUrl(msg) => write!(f, "Invalid CDP URL: {}", msg),
+            PreflightError::Dns { host, error } => write!(
+                f,
+                "Cannot resolve CDP host '{}': {}. Check the hostname in --cdp.",
+                host, error
+            ),
+            PreflightError::Unauthorized { url, status } => write!(
+                f,
+                "CDP endpoint {} rejected the request (HTTP {}). Check that the API key or token in the URL is valid and that the remote session has not expired.",
+                url, status
+            ),
+            PreflightError::Timeout { url } => write!(
+                f,
+                "CDP endpoint {} did not respond within {}s",
+                url,
+                PREFLIGHT_TIMEOUT.as_secs()
+            ),
+            PreflightError::Connect { url, error } => {
+                write!(f, "Cannot connect to CDP endpoint {}: {}", url, error)
+            }
+        }
+    }
+}
+
+/// The `/json/version` URL for an http(s) CDP value, keeping any query
+/// string (providers often carry the API key there).
+pub fn version_url(cdp: &url::Url) -> url::Url {
+    let mut probe = cdp.clone();
+    let path = format!("{}/json/version", cdp.path().trim_end_matches('/'));
+    probe.set_path(&path);
+    probe
+}
+
+/// Check a `--cdp` URL before handing it to the daemon.
+///
+/// For http(s) URLs this fetches `/json/version` and returns the reported
+/// version. Endpoints that answer but do not serve `/json/version` (some
+/// providers only speak WebSocket) return `Ok(None)` and are left to the
+/// daemon's own discovery. For ws(s) URLs only the syntax and DNS are checked.
+pub async fn preflight_cdp_url(
+    cdp: &str,
+    timeout: Duration,
+) -> Result<Option<CdpEndpointVersion>, PreflightError> {
+    let parsed = url::Url::parse(cdp).map_err(|e| PreflightError::InvalidUrl(e.to_string()))?;
+    if !matches!(parsed.scheme(), "ws" | "wss" | "http" | "https") {
+        return Err(PreflightError::InvalidUrl(format!(
+            "unsupported scheme '{}' (use ws://, wss://, http://, or https://)",
+            parsed.scheme()
+        )));
+    }
+    let host = parsed
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .ok_or_else(|| PreflightError::InvalidUrl(format!("no host in {}", cdp)))?
+        .to_string();
+    let port = parsed.port_or_known_default().unwrap_or(80);
+
+    let lookup_host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let lookup = tokio::net::lookup_host((lookup_host, port));
+    match tokio::time::timeout(timeout, lookup).await {
+        Err(_) => {
+            return Err(PreflightError::Timeout {
+                url: redact_url(parsed.as_str()),
+            })
+        }
+        Ok(Err(e)) => {
+            return Err(PreflightError::Dns {
+                host,
+                error: e.to_string(),
+            })
+        }
+        Ok(Ok(addrs)) => {
+            if addrs.count() == 0 {
+                return Err(PreflightError::Dns {
+                    host,
+                    error: "no addresses found".to_string(),
+                });
+            }
+        }
+    }
+
+    if matches!(parsed.scheme(), "ws" | "wss") {
+        return Ok(None);
+    }
+
+    let probe = version_url(&parsed);
+    let shown = redact_url(probe.as_str());
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| PreflightError::Connect {
+            url: shown.clone(),
+            error: e.to_string(),
+        })?;
+    let resp = client.get(probe).send().await.map_err(|e| {
+        if e.is_timeout() {
+            PreflightError::Timeout { url: shown.clone() }
+        } else {
+            PreflightError::Connect {
+                url: shown.clone(),
+                error: root_cause(&e),
+            }
+        }
+    })?;
+
+    let status = resp.status().as_u16();
+    if status == 401 || status == 403 {
+        return Err(PreflightError::Unauthorized { url: shown, status });
+    }
+    if !resp.status().is_success() {
+        return Ok(None);
+    }
+    match resp.text().await {
+        Ok(body) => Ok(serde_json::from_str(&body).ok()),
+        Err(e) if e.is_timeout() => Err(PreflightError::Timeout { url: shown }),
+        Err(_) => Ok(None),
+    }
+}
+
+fn root_cause(err: &(dyn std::error::Error + 'static)) -> String {
+    let mut cause = err;
+    while let Some(source) = cause.source() {
+        cause = source;
+    }
+    cause.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve a single canned HTTP response and return the request line.
+    async fn serve_once(response: String) -> (u16, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (mut s, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 2048];
+            let n = s.read(&mut buf).await.unwrap();
+            s.write_all(response.as_bytes()).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            request.lines().next().unwrap_or_default().to_string()
+        });
+        (port, handle)
+    }
+
+    fn http_response(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\nContent-Type: application/json\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+    }
+
+    #[tokio::test]
+    async fn reports_version_from_json_version() {
+        let (port, server) = serve_once(http_response(
+            "200 OK",
+            r#"{"Browser":"Chrome/126.0.0.0","Protocol-Version":"1.3"}"#,
+        ))
+        .await;
+        let version = preflight_cdp_url(
+            &format!("http://127.0.0.1:{}/session?apiKey=k", port),
+            PREFLIGHT_TIMEOUT,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(version.summary(), "Chrome/126.0.0.0 (protocol 1.3)");
+        assert_eq!(
+            server.await.unwrap(),
+            "GET /session/json/version?apiKey=k HTTP/1.1"
+        );
+    }
+
+    #[tokio::test]
+    async fn classifies_unauthorized() {
+        let (port, server) = serve_once(http_response("401 Unauthorized", "")).await;
+        let err = preflight_cdp_url(
+            &format!("http://127.0.0.1:{}?apiKey=secret", port),
+            PREFLIGHT_TIMEOUT,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            PreflightError::Unauthorized { status: 401, .. }
+        ));
+        let msg = err.to_string();
+        assert!(msg.contains("API key"));
+        assert!(!msg.contains("secret"));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn tolerates_endpoints_without_json_version() {
+        let (port, server) = serve_once(http_response("404 Not Found", "")).await;
+        let result = preflight_cdp_url(&format!("http://127.0.0.1:{}", port), PREFLIGHT_TIMEOUT)
+            .await
+            .unwrap();
+        assert!(result.is_none());
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn classifies_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (_s, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        });
+        let err = preflight_cdp_url(
+            &format!("http://127.0.0.1:{}", port),
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, PreflightError::Timeout { .. }));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn classifies_connection_refused() {
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let err = preflight_cdp_url(&format!("http://127.0.0.1:{}", port), PREFLIGHT_TIMEOUT)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PreflightError::Connect { .. }));
+    }
+
+    #[tokio::test]
+    async fn classifies_dns_failure() {
+        let err = preflight_cdp_url("wss://no-such-host.invalid/cdp", PREFLIGHT_TIMEOUT)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PreflightError::Dns { .. }));
+    }
+
+    #[tokio::test]
+    async fn validates_ws_url_syntax() {
+        assert!(matches!(
+            preflight_cdp_url("ws://", PREFLIGHT_TIMEOUT).await,
+            Err(PreflightError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            preflight_cdp_url("ftp://example.com", PREFLIGHT_TIMEOUT).await,
+            Err(PreflightError::InvalidUrl(_))
+        ));
+        assert_eq!(
+            preflight_cdp_url("ws://127.0.0.1:9222/devtools/browser/x", PREFLIGHT_TIMEOUT)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn version_url_appends_path_and_keeps_query() {
+        let url = url::Url::parse("https://connect.example.com/?apiKey=k").unwrap();
+        assert_eq!(
+            version_url(&url).as_str(),
+            "https://connect.example.com/json/version?apiKey=k"
+        );
+    }
+}
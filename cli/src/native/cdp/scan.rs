@@ -0,0 +1,359 @@
+//! Scan local remote-debugging ports for running browsers.
+//!
+//! Backs `connect --list`, `connect --match <title>` and `connect --port <p>`.
+//! Probing runs in the CLI before any daemon exists, so it uses blocking
+//! sockets on plain threads rather than the daemon's tokio runtime.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+/// Ports probed by default: Chrome's conventional 9222 up through 9250.
+pub const DEFAULT_SCAN_PORTS: std::ops::RangeInclusive<u16> = 9222..=9250;
+
+/// Most ports one scan probes, defaults included. Each port gets its own
+/// thread, so `AGENT_BROWSER_SCAN_PORTS=1-65535` is refused rather than run.
+pub const MAX_SCAN_PORTS: usize = 256;
+
+/// Per-port budget for connecting and reading a discovery response.
+pub const PROBE_TIMEOUT: Duration = Duration::from_millis(400);
+
+/// A browser answering CDP discovery requests on a local port.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CdpCandidate {
+    pub port: u16,
+    /// `Browser` field of `/json/version`, e.g. `Chrome/126.0.6478.126`.
+    pub browser: String,
+    /// Titles of the open page targets, in the order `/json/list` reports them.
+    pub titles: Vec<String>,
+}
+
+impl CdpCandidate {
+    pub fn first_title(&self) -> &str {
+        self.titles.first().map(|s| s.as_str()).unwrap_or("")
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "port": self.port,
+            "browser": self.browser,
+            "title": self.first_title(),
+            "tabs": self.titles.len(),
+        })
+    }
+}
+
+/// Parse a port list such as `9333,9400-9410`. Entries are single ports or
+/// inclusive ranges; whitespace around entries is ignored.
+pub fn parse_scan_ports(spec: &str) -> Result<Vec<u16>, String> {
+    let mut ports = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        match entry.split_once('-') {
+            Some((start, end)) => {
+                let start = parse_port(start.trim(), entry)?;
+                let end = parse_port(end.trim(), entry)?;
+                if start > end {
+                    return Err(format!(
+                        "Invalid port range '{}': start is greater than end",
+                        entry
+                    ));
+                }
+                ports.extend(start..=end);
+            }
+            None => ports.push(parse_port(entry, entry)?),
+        }
+    }
+    Ok(ports)
+}
+
+fn parse_port(value: &str, entry: &str) -> Result<u16, String> {
+    match value.parse::<u16>() {
+        Ok(0) | Err(_) => Err(format!(
+            "Invalid port '{}' in '{}' (expected 1-65535 or a range like 9300-9310)",
+            value, entry
+        )),
+        Ok(p) => Ok(p),
+    }
+}
+
+/// The default port range plus any ports from `AGENT_BROWSER_SCAN_PORTS`,
+/// sorted and deduplicated. More than [`MAX_SCAN_PORTS`] in total is an error.
+pub fn scan_ports(extra: Option<&str>) -> Result<Vec<u16>, String> {
+    let mut ports: Vec<u16> = DEFAULT_SCAN_PORTS.collect();
+    if let Some(spec) = extra {
+        ports.extend(
+            parse_scan_ports(spec).map_err(|e| format!("AGENT_BROWSER_SCAN_PORTS: {}", e))?,
+        );
+    }
+    ports.sort_unstable();
+    ports.dedup();
+    if ports.len() > MAX_SCAN_PORTS {
+        return Err(format!(
+            "AGENT_BROWSER_SCAN_PORTS: {} ports to scan with the defaults, at most {} are allowed. Narrow the ranges.",
+            ports.len(),
+            MAX_SCAN_PORTS
+        ));
+    }
+    Ok(ports)
+}
+
+/// Probe every port concurrently on `127.0.0.1`, one thread per port.
+/// Results are ordered by port.
+pub fn scan(ports: &[u16], timeout: Duration) -> Vec<CdpCandidate> {
+    let handles: Vec<_> = ports
+        .iter()
+        .map(|&port| std::thread::spawn(move || probe_port(port, timeout)))
+        .collect();
+    let mut found: Vec<CdpCandidate> = handles
+        .into_iter()
+        .filter_map(|h| h.join().ok().flatten())
+        .collect();
+    found.sort_by_key(|c| c.port);
+    found
+}
+
+/// Query `/json/version` and `/json/list` on one port. Returns `None` when
+/// nothing CDP-shaped answers within `timeout`.
+pub fn probe_port(port: u16, timeout: Duration) -> Option<CdpCandidate> {
+    let version: Value = serde_json::from_str(&http_get(port, "/json/version", timeout)?).ok()?;
+    let browser = version.get("Browser")?.as_str()?.to_string();
+    let titles = http_get(port, "/json/list", timeout)
+        .and_then(|body| serde_json::from_str::<Value>(&body).ok())
+        .and_then(|list| list.as_array().cloned())
+        .map(|targets| {
+            targets
+                .iter()
+                .filter(|t| t.get("type").and_then(|v| v.as_str()) == Some("page"))
+                .filter_map(|t| t.get("title").and_then(|v| v.as_str()))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(CdpCandidate {
+        port,
+        browser,
+        titles,
+    })
+}
+
+/// Minimal blocking HTTP/1.1 GET against `127.0.0.1:<port>`. Returns the body
+/// of a 200 response.
+fn http_get(port: u16, path: &str, timeout: Duration) -> Option<String> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let mut stream = TcpStream::connect_timeout(&addr, timeout).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    stream.set_write_timeout(Some(timeout)).ok()?;
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nConnection: close\r\n\r\n",
+        path, port
+    );
+    stream.write_all(request.as_bytes()).ok()?;
+
+    // Chrome's DevTools server may keep the socket open, so stop once
+    // Content-Length bytes have arrived instead of waiting for EOF.
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        match stream.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            Err(_) => break,
+        }
+        if let Some((head_len, content_length)) = response_head(&buf) {
+            if content_length.is_some_and(|len| buf.len() >= head_len + len) {
+                break;
+            }
+        }
+    }
+
+    let (head_len, _) = response_head(&buf)?;
+    let head = String::from_utf8_lossy(&buf[..head_len]);
+    let status = head.split_whitespace().nth(1)?;
+    if status != "200" {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&buf[head_len..]).into_owned())
+}
+
+/// Length of the header block (including the blank line) and the declared
+/// Content-Length, once the full header block has been received.
+fn response_head(buf: &[u8]) -> Option<(usize, Option<usize>)> {
+    let end = buf.windows(4).position(|w| w == b"\r\n\r\n")? + 4;
+    let head = String::from_utf8_lossy(&buf[..end]);
+    let content_length = head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            value.trim().parse().ok()
+        } else {
+            None
+        }
+    });
+    Some((end, content_length))
+}
+
+/// Pick the candidate to connect to. `port` must match exactly; `title` is a
+/// case-insensitive substring of any open tab's title. With neither filter
+/// there must be exactly one candidate.
+pub fn select_candidate<'a>(
+    candidates: &'a [CdpCandidate],
+    title: Option<&str>,
+    port: Option<u16>,
+) -> Result<&'a CdpCandidate, String> {
+    if candidates.is_empty() {
+        return Err(
+            "No browsers with remote debugging found. Launch Chrome with --remote-debugging-port or set AGENT_BROWSER_SCAN_PORTS."
+                .to_string(),
+        );
+    }
+    let needle = title.map(|t| t.to_lowercase());
+    let matches: Vec<&CdpCandidate> = candidates
+        .iter()
+        .filter(|c| port.is_none_or(|p| c.port == p))
+        .filter(|c| {
+            needle.as_ref().is_none_or(|n| {
+                c.titles
+                    .iter()
+                    .any(|t| t.to_lowercase().contains(n.as_str()))
+            })
+        })
+        .collect();
+
+    match matches.as_slice() {
+        [only] => Ok(only),
+        [] => {
+            let mut criteria = Vec::new();
+            if let Some(p) = port {
+                criteria.push(format!("port {}", p));
+            }
+            if let Some(t) = title {
+                criteria.push(format!("a tab titled '{}'", t));
+            }
+            Err(format!(
+                "No browser found with {} (candidates on ports {})",
+                criteria.join(" and "),
+                port_list(candidates.iter())
+            ))
+        }
+        many => Err(format!(
+            "Multiple browsers match (ports {}). Narrow it down with --match <title> or --port <port>.",
+            port_list(many.iter().copied())
+        )),
+    }
+}
+
+fn port_list<'a>(candidates: impl Iterator<Item = &'a CdpCandidate>) -> String {
+    candidates
+        .map(|c| c.port.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(port: u16, titles: &[&str]) -> CdpCandidate {
+        CdpCandidate {
+            port,
+            browser: "Chrome/126.0.0.0".to_string(),
+            titles: titles.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_scan_ports_single_and_ranges() {
+        assert_eq!(
+            parse_scan_ports("9300, 9400-9402,,9500").unwrap(),
+            vec![9300, 9400, 9401, 9402, 9500]
+        );
+        assert!(parse_scan_ports("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_scan_ports_rejects_bad_entries() {
+        assert!(parse_scan_ports("abc").is_err());
+        assert!(parse_scan_ports("0").is_err());
+        assert!(parse_scan_ports("70000").is_err());
+        assert!(parse_scan_ports("9410-9400").is_err());
+        assert!(parse_scan_ports("9400-").is_err());
+    }
+
+    #[test]
+    fn test_scan_ports_merges_env_with_defaults() {
+        let ports = scan_ports(Some("9222,9300")).unwrap();
+        assert_eq!(ports.first(), Some(&9222));
+        assert_eq!(ports.last(), Some(&9300));
+        assert_eq!(ports.len(), DEFAULT_SCAN_PORTS.count() + 1);
+        assert!(scan_ports(Some("nope"))
+            .unwrap_err()
+            .contains("AGENT_BROWSER_SCAN_PORTS"));
+    }
+
+    #[test]
+    fn test_scan_ports_caps_total() {
+        let err = scan_ports(Some("1-65535")).unwrap_err();
+        assert!(err.contains("65535 ports"), "{}", err);
+        assert!(err.contains("at most 256"), "{}", err);
+        // Overlap with the defaults counts once.
+        assert_eq!(scan_ports(Some("9222-9450")).unwrap().len(), 229);
+        assert!(scan_ports(Some("9222-9478")).is_err());
+    }
+
+    #[test]
+    fn test_select_single_candidate_without_filters() {
+        let found = vec![candidate(9222, &["Inbox"])];
+        assert_eq!(select_candidate(&found, None, None).unwrap().port, 9222);
+    }
+
+    #[test]
+    fn test_select_ambiguous_without_filters() {
+        let found = vec![candidate(9222, &["Inbox"]), candidate(9230, &["Docs"])];
+        let err = select_candidate(&found, None, None).unwrap_err();
+        assert!(err.contains("9222, 9230"));
+    }
+
+    #[test]
+    fn test_select_by_title_matches_any_tab_case_insensitively() {
+        let found = vec![
+            candidate(9222, &["Inbox - Mail"]),
+            candidate(9230, &["New Tab", "Project Docs"]),
+        ];
+        assert_eq!(
+            select_candidate(&found, Some("project docs"), None)
+                .unwrap()
+                .port,
+            9230
+        );
+        assert!(select_candidate(&found, Some("calendar"), None).is_err());
+    }
+
+    #[test]
+    fn test_select_by_port_and_title() {
+        let found = vec![candidate(9222, &["Docs"]), candidate(9230, &["Docs"])];
+        assert!(select_candidate(&found, Some("docs"), None).is_err());
+        assert_eq!(
+            select_candidate(&found, Some("docs"), Some(9230))
+                .unwrap()
+                .port,
+            9230
+        );
+        let err = select_candidate(&found, None, Some(9999)).unwrap_err();
+        assert!(err.contains("port 9999"));
+    }
+
+    #[test]
+    fn test_select_with_no_candidates() {
+        assert!(select_candidate(&[], None, Some(9222))
+            .unwrap_err()
+            .contains("No browsers"));
+    }
+
+    #[test]
+    fn test_response_head_reads_content_length() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}";
+        assert_eq!(response_head(raw), Some((raw.len() - 2, Some(2))));
+        assert_eq!(response_head(b"HTTP/1.1 200 OK\r\n"), None);
+    }
+}
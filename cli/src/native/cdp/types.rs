@@ -268,6 +268,7 @@ pub struct RemoteObject {
 pub struct ExceptionDetails {
     pub text: String,
     pub exception: Option<RemoteObject>,
+    pub url: Option<String>,
     pub line_number: Option<i64>,
     pub column_number: Option<i64>,
 }
@@ -7,7 +7,7 @@ use std::process;
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::signal;
 use tokio::sync::{mpsc, Notify, RwLock};
 
@@ -18,7 +18,11 @@ use super::actions::{
 use super::cdp::client::CdpClient;
 use super::state;
 use super::stream::StreamServer;
-use crate::connection::INTERNAL_DAEMON_SHUTDOWN_ACTION;
+use crate::atomic_write::write_atomic;
+use crate::connection::{
+    decode_frame_body, encode_frame, parse_frame_header, PidRecord, ACCEPT_ENCODING_FIELD,
+    GZIP_ENCODING, INTERNAL_DAEMON_SHUTDOWN_ACTION,
+};
 
 pub async fn run_daemon(session: &str) {
     let socket_dir = get_daemon_socket_dir();
@@ -64,10 +68,10 @@ pub async fn run_daemon(session: &str) {
     }
 
     let pid_path = socket_dir.join(format!("{}.pid", session));
-    let _ = fs::write(&pid_path, process::id().to_string());
+    let _ = write_atomic(&pid_path, PidRecord::current().to_string());
 
     let version_path = socket_dir.join(format!("{}.version", session));
-    let _ = fs::write(&version_path, env!("CARGO_PKG_VERSION"));
+    let _ = write_atomic(&version_path, env!("CARGO_PKG_VERSION"));
 
     // On Unix the daemon listens on a Unix domain socket; on Windows it uses
     // TCP, so there is no .sock file — only a .port file written by the server.
@@ -88,6 +92,8 @@ pub async fn run_daemon(session: &str) {
     let _ = fs::remove_file(socket_dir.join(format!("{}.engine", session)));
     let _ = fs::remove_file(socket_dir.join(format!("{}.provider", session)));
     let _ = fs::remove_file(socket_dir.join(format!("{}.extensions", session)));
+    let _ = fs::remove_file(socket_dir.join(format!("{}.timeouts", session)));
+    let _ = fs::remove_file(socket_dir.join(format!("{}.warned", session)));
 
     if let Ok(days_str) = env::var("AGENT_BROWSER_STATE_EXPIRE_DAYS") {
         if let Ok(days) = days_str.parse::<u64>() {
@@ -106,7 +112,7 @@ pub async fn run_daemon(session: &str) {
     match StreamServer::start_without_client(preferred_port, session.to_string(), true).await {
         Ok((stream_server, client_slot)) => {
             stream_client = Some(client_slot.clone());
-            if let Err(e) = fs::write(&stream_path, stream_server.port().to_string()) {
+            if let Err(e) = write_atomic(&stream_path, stream_server.port().to_string()) {
                 let _ = writeln!(std::io::stderr(), "Failed to write .stream file: {}", e);
             }
             stream_server_instance = Some(Arc::new(stream_server));
@@ -149,6 +155,8 @@ pub async fn run_daemon(session: &str) {
     let _ = fs::remove_file(socket_dir.join(format!("{}.engine", session)));
     let _ = fs::remove_file(socket_dir.join(format!("{}.provider", session)));
     let _ = fs::remove_file(socket_dir.join(format!("{}.extensions", session)));
+    let _ = fs::remove_file(socket_dir.join(format!("{}.timeouts", session)));
+    let _ = fs::remove_file(socket_dir.join(format!("{}.warned", session)));
 
     if let Err(e) = result {
         let _ = writeln!(std::io::stderr(), "Daemon error: {}", e);
@@ -304,7 +312,7 @@ async fn run_socket_server(
 
     let socket_dir = socket_path.parent().unwrap_or(std::path::Path::new("."));
     let port_path = socket_dir.join(format!("{}.port", session));
-    let _ = fs::write(&port_path, actual_port.to_string());
+    let _ = write_atomic(&port_path, actual_port.to_string());
 
     let stream_file: Option<PathBuf> = if stream_server.is_some() {
         Some(socket_dir.join(format!("{}.stream", session)))
@@ -423,19 +431,46 @@ async fn handle_connection<S>(
                     break;
                 }
 
-                let cmd: Value = match serde_json::from_str(trimmed) {
+                // A large request arrives as a gzip frame: a header line,
+                // then the compressed JSON.
+                let decompressed;
+                let request = match parse_frame_header(trimmed) {
+                    Ok(None) => trimmed,
+                    Ok(Some(length)) => {
+                        let mut body = vec![0; length];
+                        if buf_reader.read_exact(&mut body).await.is_err() {
+                            break;
+                        }
+                        match decode_frame_body(&body) {
+                            Ok(text) => {
+                                decompressed = text;
+                                decompressed.as_str()
+                            }
+                            Err(e) => {
+                                write_protocol_error(&mut writer, e).await;
+                                continue;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        // The body length is unknown, so the stream cannot
+                        // be resynchronized.
+                        write_protocol_error(&mut writer, e).await;
+                        break;
+                    }
+                };
+
+                let mut cmd: Value = match serde_json::from_str(request) {
                     Ok(v) => v,
                     Err(e) => {
-                        let err = serde_json::json!({
-                            "success": false,
-                            "error": format!("Invalid JSON: {}", e),
-                        });
-                        let mut resp = serde_json::to_string(&err).unwrap_or_default();
-                        resp.push('\n');
-                        let _ = writer.write_all(resp.as_bytes()).await;
+                        write_protocol_error(&mut writer, format!("Invalid JSON: {}", e)).await;
                         continue;
                     }
                 };
+                let accepts_gzip = cmd
+                    .as_object_mut()
+                    .and_then(|obj| obj.remove(ACCEPT_ENCODING_FIELD))
+                    .is_some_and(|v| v == GZIP_ENCODING);
 
                 if let Some(ref tx) = idle_reset_tx {
                     let _ = tx.try_send(());
@@ -452,9 +487,12 @@ async fn handle_connection<S>(
                     execute_command(&cmd, &mut s).await
                 };
 
-                let mut resp = serde_json::to_string(&response).unwrap_or_default();
-                resp.push('\n');
-                if writer.write_all(resp.as_bytes()).await.is_err() {
+                let resp = serde_json::to_string(&response).unwrap_or_default();
+                if writer
+                    .write_all(&encode_frame(&resp, accepts_gzip))
+                    .await
+                    .is_err()
+                {
                     break;
                 }
 
@@ -475,6 +513,20 @@ async fn handle_connection<S>(
     }
 }
 
+async fn write_protocol_error<W>(writer: &mut W, error: String)
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    let err = serde_json::json!({
+        "success": false,
+        "error": error,
+        "errorKind": "protocol",
+    });
+    let mut resp = serde_json::to_string(&err).unwrap_or_default();
+    resp.push('\n');
+    let _ = writer.write_all(resp.as_bytes()).await;
+}
+
 fn looks_like_http(line: &str) -> bool {
     let prefixes = [
         "GET ", "POST ", "PUT ", "DELETE ", "PATCH ", "HEAD ", "OPTIONS ", "CONNECT ", "TRACE ",
@@ -0,0 +1,245 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+/// A file the browser downloaded during this daemon session.
+#[derive(Debug, Clone)]
+pub struct DownloadRecord {
+    pub guid: String,
+    pub url: String,
+    pub suggested_filename: String,
+    /// Where the file landed on disk, when known. Launch-level downloads go to
+    /// `--download-path` under the suggested name; `download <sel> <path>`
+    /// records the path it renamed the file to.
+    pub path: Option<PathBuf>,
+    pub total_bytes: Option<u64>,
+    /// Unix time in milliseconds when the download started.
+    pub timestamp: u64,
+    pub completed: bool,
+}
+
+/// Downloads seen this session, fed from `Browser.download*` CDP events and
+/// from the `download` command.
+#[derive(Debug, Default)]
+pub struct DownloadLog {
+    records: Vec<DownloadRecord>,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl DownloadLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entry(&mut self, guid: &str) -> &mut DownloadRecord {
+        if let Some(pos) = self.records.iter().position(|r| r.guid == guid) {
+            return &mut self.records[pos];
+        }
+        self.records.push(DownloadRecord {
+            guid: guid.to_string(),
+            url: String::new(),
+            suggested_filename: String::new(),
+            path: None,
+            total_bytes: None,
+            timestamp: now_millis(),
+            completed: false,
+        });
+        self.records.last_mut().expect("record was just pushed")
+    }
+
+    /// Handle `Browser.downloadWillBegin`. `download_dir` is the directory the
+    /// browser was told to save into, if any.
+    pub fn begin(&mut self, guid: &str, url: &str, filename: &str, download_dir: Option<&Path>) {
+        let record = self.entry(guid);
+        if record.url.is_empty() {
+            record.url = url.to_string();
+        }
+        if record.suggested_filename.is_empty() {
+            record.suggested_filename = filename.to_string();
+        }
+        if record.path.is_none() && !filename.is_empty() {
+            record.path = download_dir.map(|dir| dir.join(filename));
+        }
+    }
+
+    /// Handle `Browser.downloadProgress`. Canceled downloads are dropped.
+    pub fn progress(&mut self, guid: &str, state: &str, total_bytes: Option<u64>) {
+        match state {
+            "completed" => {
+                let record = self.entry(guid);
+                record.completed = true;
+                if total_bytes.is_some_and(|n| n > 0) {
+                    record.total_bytes = total_bytes;
+                }
+            }
+            "canceled" => self.records.retain(|r| r.guid != guid),
+            _ => {}
+        }
+    }
+
+    /// Record the final location of a download saved by the `download` command.
+    pub fn saved(&mut self, guid: &str, path: &Path) {
+        let record = self.entry(guid);
+        record.path = Some(path.to_path_buf());
+        record.completed = true;
+    }
+
+    /// Completed downloads whose file is still on disk, oldest first.
+    pub fn files(&self) -> Vec<(&DownloadRecord, &Path)> {
+        self.records
+            .iter()
+            .filter(|r| r.completed)
+            .filter_map(|r| r.path.as_deref().filter(|p| p.is_file()).map(|p| (r, p)))
+            .collect()
+    }
+
+    /// JSON listing for `downloads_list`. Indexes are 1-based, matching
+    /// `downloads open <index>`.
+    pub fn list_json(&self) -> Value {
+        let downloads: Vec<Value> = self
+            .files()
+            .into_iter()
+            .enumerate()
+            .map(|(i, (record, path))| {
+                let size = std::fs::metadata(path)
+                    .map(|m| m.len())
+                    .ok()
+                    .or(record.total_bytes);
+                let filename = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| record.suggested_filename.clone());
+                json!({
+                    "index": i + 1,
+                    "filename": filename,
+                    "path": path.to_string_lossy(),
+                    "size": size,
+                    "url": record.url,
+                    "timestamp": record.timestamp,
+                })
+            })
+            .collect();
+        json!({ "downloads": downloads })
+    }
+
+    /// Absolute path of the download at 1-based `index`.
+    pub fn path_at(&self, index: usize) -> Result<PathBuf, String> {
+        let files = self.files();
+        if index == 0 || index > files.len() {
+            return Err(format!(
+                "No download at index {} ({} file(s) downloaded this session)",
+                index,
+                files.len()
+            ));
+        }
+        let path = files[index - 1].1;
+        Ok(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()))
+    }
+
+    /// Delete every downloaded file and forget all records. Returns the number
+    /// of files removed.
+    pub fn clear(&mut self) -> Result<usize, String> {
+        let paths: Vec<PathBuf> = self
+            .files()
+            .into_iter()
+            .map(|(_, p)| p.to_path_buf())
+            .collect();
+        let mut removed = 0;
+        for path in &paths {
+            std::fs::remove_file(path)
+                .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+            removed += 1;
+        }
+        self.records.clear();
+        Ok(removed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completed_download_is_listed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        std::fs::write(dir.join("report.pdf"), b"12345").unwrap();
+
+        let mut log = DownloadLog::new();
+        log.begin(
+            "g1",
+            "https://example.com/report.pdf",
+            "report.pdf",
+            Some(&dir),
+        );
+        assert!(log.is_empty());
+        log.progress("g1", "completed", Some(5));
+
+        let list = log.list_json();
+        let entry = &list["downloads"][0];
+        assert_eq!(entry["index"], 1);
+        assert_eq!(entry["filename"], "report.pdf");
+        assert_eq!(entry["size"], 5);
+        assert_eq!(entry["url"], "https://example.com/report.pdf");
+    }
+
+    #[test]
+    fn test_canceled_and_missing_files_are_skipped() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let mut log = DownloadLog::new();
+        log.begin("g1", "https://example.com/a", "a.bin", Some(&dir));
+        log.progress("g1", "canceled", None);
+        log.begin("g2", "https://example.com/b", "b.bin", Some(&dir));
+        log.progress("g2", "completed", Some(3));
+        assert!(log.is_empty());
+        assert!(log.path_at(1).is_err());
+    }
+
+    #[test]
+    fn test_saved_path_overrides_guid_name() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let dest = dir.join("renamed.txt");
+        std::fs::write(&dest, b"x").unwrap();
+
+        let mut log = DownloadLog::new();
+        log.saved("g1", &dest);
+        log.begin("g1", "https://example.com/x", "x.txt", Some(&dir));
+        log.progress("g1", "completed", Some(1));
+
+        assert_eq!(log.path_at(1).unwrap(), dest.canonicalize().unwrap());
+        assert_eq!(
+            log.list_json()["downloads"][0]["url"],
+            "https://example.com/x"
+        );
+    }
+
+    #[test]
+    fn test_clear_removes_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let file = dir.join("a.txt");
+        std::fs::write(&file, b"a").unwrap();
+
+        let mut log = DownloadLog::new();
+        log.saved("g1", &file);
+        assert_eq!(log.clear().unwrap(), 1);
+        assert!(!file.exists());
+        assert!(log.is_empty());
+    }
+}
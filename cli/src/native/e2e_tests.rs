@@ -2346,6 +2346,81 @@ async fn e2e_drag_action_sends_buttons_during_move() {
     assert_success(&resp);
 }
 
+#[tokio::test]
+#[ignore]
+async fn e2e_drag_html5_drops_at_target_offset() {
+    let mut state = DaemonState::new();
+
+    let resp = execute_command(
+        &json!({ "id": "1", "action": "launch", "headless": true }),
+        &mut state,
+    )
+    .await;
+    assert_success(&resp);
+
+    let resp = execute_command(
+        &json!({
+            "id": "2",
+            "action": "navigate",
+            "url": native_test_fixture_url("html5_drag_probe")
+        }),
+        &mut state,
+    )
+    .await;
+    assert_success(&resp);
+
+    let resp = execute_command(
+        &json!({
+            "id": "3",
+            "action": "drag",
+            "source": "#source",
+            "target": "#dest",
+            "targetOffset": { "x": 5, "y": 7 },
+            "method": "html5"
+        }),
+        &mut state,
+    )
+    .await;
+    assert_success(&resp);
+    assert_eq!(get_data(&resp)["method"], "html5");
+
+    let resp = execute_command(
+        &json!({
+            "id": "4",
+            "action": "evaluate",
+            "script": "(() => { const r = document.getElementById('dest').getBoundingClientRect(); return { events: window.__html5DragProbe.events, x: r.x, y: r.y }; })()"
+        }),
+        &mut state,
+    )
+    .await;
+    assert_success(&resp);
+
+    let probe = &get_data(&resp)["result"];
+    let events = probe["events"]
+        .as_array()
+        .expect("html5 drag probe should expose events");
+    let drop = events
+        .iter()
+        .find(|event| event["type"] == "drop" && event["target"] == "dest")
+        .expect("Expected a drop event on the target");
+    assert_eq!(drop["dropped"], "probe");
+    assert_eq!(
+        drop["x"].as_f64(),
+        Some((probe["x"].as_f64().unwrap() + 5.0).floor())
+    );
+    assert_eq!(
+        drop["y"].as_f64(),
+        Some((probe["y"].as_f64().unwrap() + 7.0).floor())
+    );
+    assert!(
+        !events.iter().any(|event| event["type"] == "mousedown"),
+        "HTML5 mode should not dispatch mouse events"
+    );
+
+    let resp = execute_command(&json!({ "id": "99", "action": "close" }), &mut state).await;
+    assert_success(&resp);
+}
+
 // ---------------------------------------------------------------------------
 // State save/load, state management
 // ---------------------------------------------------------------------------
@@ -4,6 +4,7 @@ use serde_json::Value;
 
 use super::cdp::client::CdpClient;
 use super::cdp::types::*;
+use crate::selector::{parse_ref_scope, parse_selector, ExtendedSelector, PseudoFilter};
 
 #[derive(Debug, Clone)]
 pub struct RefEntry {
@@ -103,6 +104,22 @@ impl RefMap {
         entries
     }
 
+    /// The lowest-numbered ref pointing at a DOM node, if any.
+    pub fn ref_for_backend_node(&self, backend_node_id: i64) -> Option<String> {
+        self.entries_sorted()
+            .into_iter()
+            .find(|(_, entry)| entry.backend_node_id == Some(backend_node_id))
+            .map(|(ref_id, _)| ref_id)
+    }
+
+    /// Drop the cached DOM node of `ref_id` so the next resolution re-queries
+    /// the accessibility tree by its role and name.
+    pub fn forget_node(&mut self, ref_id: &str) {
+        if let Some(entry) = self.map.get_mut(ref_id) {
+            entry.backend_node_id = None;
+        }
+    }
+
     pub fn remove(&mut self, ref_id: &str) {
         self.map.remove(ref_id);
     }
@@ -121,6 +138,28 @@ impl RefMap {
     }
 }
 
+/// The ref, role, and name behind `selector` for commands sent with
+/// `describe`. A ref resolves through the ref map; any other selector only
+/// resolves when a cursor-interactive ref was recorded for exactly it.
+pub fn describe_target(ref_map: &RefMap, selector: &str) -> Option<Value> {
+    let (ref_id, entry) = match parse_ref(selector) {
+        Some(ref_id) => {
+            let entry = ref_map.get(&ref_id)?;
+            (ref_id, entry)
+        }
+        None => ref_map
+            .map
+            .iter()
+            .find(|(_, entry)| entry.selector.as_deref() == Some(selector))
+            .map(|(ref_id, entry)| (ref_id.clone(), entry))?,
+    };
+    Some(serde_json::json!({
+        "ref": ref_id,
+        "role": entry.role,
+        "name": entry.name,
+    }))
+}
+
 pub fn parse_ref(input: &str) -> Option<String> {
     let trimmed = input.trim();
 
@@ -303,6 +342,20 @@ pub async fn resolve_element_center(
     selector_or_ref: &str,
     iframe_sessions: &HashMap<String, String>,
 ) -> Result<(f64, f64, String), String> {
+    if parse_ref_scope(selector_or_ref).is_some() {
+        let (object_id, effective_session_id) = resolve_element_object_id(
+            client,
+            session_id,
+            ref_map,
+            selector_or_ref,
+            iframe_sessions,
+        )
+        .await?;
+        let (x, y) =
+            center_of_object(client, &effective_session_id, &object_id, selector_or_ref).await?;
+        return Ok((x, y, effective_session_id));
+    }
+
     if let Some(ref_id) = parse_ref(selector_or_ref) {
         let entry = ref_map
             .get(&ref_id)
@@ -395,6 +448,60 @@ pub async fn resolve_element_center(
     Ok((x, y, session_id.to_string()))
 }
 
+/// Scroll an element into view and return its center in top-level viewport
+/// coordinates, erroring if another element would receive a click there.
+async fn center_of_object(
+    client: &CdpClient,
+    session_id: &str,
+    object_id: &str,
+    target: &str,
+) -> Result<(f64, f64), String> {
+    let function = format!(
+        r#"function() {{
+            if (this.scrollIntoViewIfNeeded) this.scrollIntoViewIfNeeded(true);
+            else this.scrollIntoView({{ block: 'center', inline: 'center' }});
+            const rect = this.getBoundingClientRect();
+            let x = rect.x + rect.width / 2;
+            let y = rect.y + rect.height / 2;
+            const doc = this.ownerDocument || document;
+            let win = doc.defaultView;
+            while (win && win.frameElement) {{
+                const frameRect = win.frameElement.getBoundingClientRect();
+                x += frameRect.x + win.frameElement.clientLeft;
+                y += frameRect.y + win.frameElement.clientTop;
+                win = win.parent;
+            }}
+            const blockerAt = {BLOCKER_AT_JS};
+            const topDoc = win ? win.document : doc;
+            return {{ x: x, y: y, blocker: blockerAt(topDoc, this, x, y) }};
+        }}"#,
+    );
+    let result = client
+        .send_command(
+            "Runtime.callFunctionOn",
+            Some(serde_json::json!({
+                "objectId": object_id,
+                "functionDeclaration": function,
+                "returnByValue": true,
+            })),
+            Some(session_id),
+        )
+        .await?;
+    let value = result.get("result").and_then(|r| r.get("value"));
+    if let Some(blocker) = value
+        .and_then(|v| v.get("blocker"))
+        .and_then(|v| v.as_str())
+    {
+        return Err(intercepted_error(target, blocker));
+    }
+    let x = value.and_then(|v| v.get("x")).and_then(|v| v.as_f64());
+    let y = value.and_then(|v| v.get("y")).and_then(|v| v.as_f64());
+    match (x, y) {
+        (Some(x), Some(y)) => Ok((x, y)),
+        _ => Err(format!("Element not found: {}", target)),
+    }
+}
+
 /// Hit-test a ref-resolved node at its computed click point and error if an
 /// unrelated element (overlay, banner, sticky header) would receive the input
 /// instead. Best effort: resolution failures skip the check rather than block
@@ -483,6 +590,42 @@ pub async fn resolve_element_object_id(
     selector_or_ref: &str,
     iframe_sessions: &HashMap<String, String>,
 ) -> Result<(String, String), String> {
+    // `@e5 >> button`: resolve the ref, then search inside it on the ref's
+    // own session (its frame, if it has one).
+    if let Some(scope) = parse_ref_scope(selector_or_ref) {
+        let (ref_part, sub) = scope?;
+        let (scope_id, scope_session) = Box::pin(resolve_element_object_id(
+            client,
+            session_id,
+            ref_map,
+            &ref_part,
+            iframe_sessions,
+        ))
+        .await?;
+        let function = format!(
+            "function() {{ return {}; }}",
+            build_find_in_element_js(&sub)
+        );
+        let result = client
+            .send_command(
+                "Runtime.callFunctionOn",
+                Some(serde_json::json!({
+                    "objectId": scope_id,
+                    "functionDeclaration": function,
+                    "returnByValue": false,
+                })),
+                Some(&scope_session),
+            )
+            .await?;
+        let object_id = result
+            .get("result")
+            .and_then(|r| r.get("objectId"))
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .ok_or_else(|| format!("Element not found: {} inside {}", sub, ref_part))?;
+        return Ok((object_id, scope_session));
+    }
+
     if let Some(ref_id) = parse_ref(selector_or_ref) {
         let entry = ref_map
             .get(&ref_id)
@@ -692,8 +835,10 @@ fn build_find_element_js(selector: &str) -> String {
 
 /// Same as build_find_element_js but rooted at an arbitrary Document
 /// expression (e.g. an iframe's contentDocument).
-fn build_find_element_js_in(root: &str, selector: &str) -> String {
-    if let Some(xpath) = selector.strip_prefix("xpath=") {
+pub(super) fn build_find_element_js_in(root: &str, selector: &str) -> String {
+    if let Ok(Some(extended)) = parse_selector(selector) {
+        format!("({}[0] || null)", build_extended_query_js(root, &extended))
+    } else if let Some(xpath) = selector.strip_prefix("xpath=") {
         format!(
             "{root}.evaluate({xpath}, {root}, null, XPathResult.FIRST_ORDERED_NODE_TYPE, null).singleNodeValue",
             xpath = serde_json::to_string(xpath).unwrap_or_default(),
@@ -706,21 +851,152 @@ fn build_find_element_js_in(root: &str, selector: &str) -> String {
     }
 }
 
+/// Like [`build_find_element_js_in`], searching inside the element bound to
+/// `this`. XPath is evaluated with the element as context node, so it should
+/// be relative (`.//button`).
+fn build_find_in_element_js(selector: &str) -> String {
+    match selector.strip_prefix("xpath=") {
+        Some(xpath) => format!(
+            "this.ownerDocument.evaluate({}, this, null, XPathResult.FIRST_ORDERED_NODE_TYPE, null).singleNodeValue",
+            serde_json::to_string(xpath).unwrap_or_default(),
+        ),
+        None => build_find_element_js_in("this", selector),
+    }
+}
+
 /// Build a JS expression that counts matching DOM elements by CSS selector or XPath.
-fn build_count_elements_js(selector: &str) -> String {
-    if let Some(xpath) = selector.strip_prefix("xpath=") {
+/// Same rule as `is visible`: a non-empty box that is not hidden by
+/// `display`, `visibility`, or zero opacity.
+const IS_VISIBLE_JS: &str = r#"(el) => {
+    const rect = el.getBoundingClientRect();
+    const style = window.getComputedStyle(el);
+    return rect.width > 0 && rect.height > 0 &&
+           style.visibility !== 'hidden' &&
+           style.display !== 'none' &&
+           parseFloat(style.opacity) > 0;
+}"#;
+
+/// Build a JS expression that evaluates to an array of every element under
+/// `root` matching a CSS selector, XPath, or Playwright pseudo-class.
+pub(super) fn build_query_all_js(root: &str, selector: &str) -> String {
+    if let Ok(Some(extended)) = parse_selector(selector) {
+        build_extended_query_js(root, &extended)
+    } else if let Some(xpath) = selector.strip_prefix("xpath=") {
         format!(
-            "document.evaluate({}, document, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null).snapshotLength",
-            serde_json::to_string(xpath).unwrap_or_default()
+            "(() => {{ const s = {root}.evaluate({xpath}, {root}, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null); const out = []; for (let i = 0; i < s.snapshotLength; i++) out.push(s.snapshotItem(i)); return out; }})()",
+            xpath = serde_json::to_string(xpath).unwrap_or_default(),
         )
     } else {
         format!(
-            "document.querySelectorAll({}).length",
-            serde_json::to_string(selector).unwrap_or_default()
+            "Array.from({root}.querySelectorAll({selector}))",
+            selector = serde_json::to_string(selector).unwrap_or_default(),
         )
     }
 }
 
+/// Like [`build_query_all_js`], for every match inside the element bound to
+/// `this`.
+pub(super) fn build_query_all_in_element_js(selector: &str) -> String {
+    match selector.strip_prefix("xpath=") {
+        Some(xpath) => format!(
+            "(() => {{ const s = this.ownerDocument.evaluate({xpath}, this, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null); const out = []; for (let i = 0; i < s.snapshotLength; i++) out.push(s.snapshotItem(i)); return out; }})()",
+            xpath = serde_json::to_string(xpath).unwrap_or_default(),
+        ),
+        None => build_query_all_js("this", selector),
+    }
+}
+
+/// Text matching for the Playwright pseudo-classes: whitespace is collapsed,
+/// `:has-text`/`:text` ignore case, and `:text`/`:text-is` keep only the
+/// innermost element whose text matches (not every ancestor too).
+const TEXT_MATCH_JS: &str = r#"(el, kind, want) => {
+    const norm = (s) => (s || '').replace(/\s+/g, ' ').trim();
+    const test = kind === 'is'
+        ? (n) => norm(n.innerText ?? n.textContent) === want
+        : (n) => norm(n.innerText ?? n.textContent).toLowerCase().includes(want.toLowerCase());
+    if (!test(el)) return false;
+    return kind === 'has' || !Array.from(el.children).some(test);
+}"#;
+
+/// The elements an [`ExtendedSelector`] matches under `root`, as a JS array.
+fn build_extended_query_js(root: &str, selector: &ExtendedSelector) -> String {
+    match selector {
+        ExtendedSelector::Filtered { css, filters } => {
+            let conditions: Vec<String> = filters
+                .iter()
+                .map(|filter| match filter {
+                    PseudoFilter::HasText(text) => text_condition("has", text),
+                    PseudoFilter::Text(text) => text_condition("text", text),
+                    PseudoFilter::TextIs(text) => text_condition("is", text),
+                    PseudoFilter::Visible => format!("({})(el)", IS_VISIBLE_JS),
+                })
+                .collect();
+            let all = format!(
+                "Array.from({root}.querySelectorAll({css}))",
+                css = serde_json::to_string(css).unwrap_or_default(),
+            );
+            if conditions.is_empty() {
+                all
+            } else {
+                format!(
+                    "{all}.filter((el) => {{ const textMatch = {TEXT_MATCH_JS}; return {}; }})",
+                    conditions.join(" && ")
+                )
+            }
+        }
+        ExtendedSelector::NthMatch { inner, n } => {
+            format!(
+                "{}.slice({}, {})",
+                build_extended_query_js(root, inner),
+                n - 1,
+                n
+            )
+        }
+    }
+}
+
+fn text_condition(kind: &str, text: &str) -> String {
+    format!(
+        "textMatch(el, '{}', {})",
+        kind,
+        serde_json::to_string(text).unwrap_or_default()
+    )
+}
+
+fn build_count_elements_js(selector: &str, visible_only: bool) -> String {
+    if let Ok(Some(extended)) = parse_selector(selector) {
+        let all = build_extended_query_js("document", &extended);
+        if visible_only {
+            format!("{}.filter({}).length", all, IS_VISIBLE_JS)
+        } else {
+            format!("{}.length", all)
+        }
+    } else if let Some(xpath) = selector.strip_prefix("xpath=") {
+        let snapshot = format!(
+            "document.evaluate({}, document, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null)",
+            serde_json::to_string(xpath).unwrap_or_default()
+        );
+        if visible_only {
+            format!(
+                "(() => {{ const s = {}; let n = 0; for (let i = 0; i < s.snapshotLength; i++) {{ const el = s.snapshotItem(i); if (el instanceof Element && ({})(el)) n++; }} return n; }})()",
+                snapshot, IS_VISIBLE_JS
+            )
+        } else {
+            format!("{}.snapshotLength", snapshot)
+        }
+    } else {
+        let all = format!(
+            "document.querySelectorAll({})",
+            serde_json::to_string(selector).unwrap_or_default()
+        );
+        if visible_only {
+            format!("Array.from({}).filter({}).length", all, IS_VISIBLE_JS)
+        } else {
+            format!("{}.length", all)
+        }
+    }
+}
+
 /// JS function source for `blockerAt(doc, el, x, y)`: returns a short
 /// description of the element that would actually receive a click at (x, y)
 /// when that element is unrelated to `el`, or null when the click would land
@@ -1101,12 +1377,17 @@ pub async fn get_element_inner_text(
         .unwrap_or_default())
 }
 
+/// The element's innerHTML, or outerHTML when `outer` is set. With
+/// `max_depth`, descendant elements nested deeper than that many levels
+/// below the element are replaced by a `<!-- max-depth -->` comment.
 pub async fn get_element_inner_html(
     client: &CdpClient,
     session_id: &str,
     ref_map: &RefMap,
     selector_or_ref: &str,
     iframe_sessions: &HashMap<String, String>,
+    outer: bool,
+    max_depth: Option<u64>,
 ) -> Result<String, String> {
     let (object_id, effective_session_id) = resolve_element_object_id(
         client,
@@ -1121,9 +1402,35 @@ pub async fn get_element_inner_html(
         .send_command_typed(
             "Runtime.callFunctionOn",
             &CallFunctionOnParams {
-                function_declaration: "function() { return this.innerHTML || ''; }".to_string(),
+                function_declaration: r#"function(outer, maxDepth) {
+                    let el = this;
+                    if (maxDepth !== null) {
+                        el = this.cloneNode(true);
+                        const prune = (node, depth) => {
+                            for (const child of Array.from(node.children)) {
+                                if (depth >= maxDepth) {
+                                    child.replaceWith(document.createComment(' max-depth '));
+                                } else {
+                                    prune(child, depth + 1);
+                                }
+                            }
+                        };
+                        prune(el, 0);
+                    }
+                    return (outer ? el.outerHTML : el.innerHTML) || '';
+                }"#
+                .to_string(),
                 object_id: Some(object_id),
-                arguments: None,
+                arguments: Some(vec![
+                    CallArgument {
+                        value: Some(serde_json::json!(outer)),
+                        object_id: None,
+                    },
+                    CallArgument {
+                        value: Some(serde_json::json!(max_depth)),
+                        object_id: None,
+                    },
+                ]),
                 return_by_value: Some(true),
                 await_promise: Some(false),
             },
@@ -1222,6 +1529,7 @@ pub async fn get_element_bounding_box(
     ref_map: &RefMap,
     selector_or_ref: &str,
     iframe_sessions: &HashMap<String, String>,
+    with_viewport: bool,
 ) -> Result<Value, String> {
     let (object_id, effective_session_id) = resolve_element_object_id(
         client,
@@ -1232,13 +1540,23 @@ pub async fn get_element_bounding_box(
     )
     .await?;
 
+    // The rect is relative to the element's frame viewport; the viewport
+    // size and scroll offsets let callers derive page coordinates and how
+    // much of the element is on screen.
     let result: EvaluateResult = client
         .send_command_typed(
             "Runtime.callFunctionOn",
             &CallFunctionOnParams {
                 function_declaration: r#"function() {
                     const r = this.getBoundingClientRect();
-                    return { x: r.x, y: r.y, width: r.width, height: r.height };
+                    const w = this.ownerDocument.defaultView;
+                    return {
+                        x: r.x, y: r.y, width: r.width, height: r.height,
+                        viewport: {
+                            width: w.innerWidth, height: w.innerHeight,
+                            scrollX: w.scrollX, scrollY: w.scrollY
+                        }
+                    };
                 }"#
                 .to_string(),
                 object_id: Some(object_id),
@@ -1250,18 +1568,25 @@ pub async fn get_element_bounding_box(
         )
         .await?;
 
-    result
+    let mut bbox = result
         .result
         .value
-        .ok_or_else(|| format!("Could not get bounding box for: {}", selector_or_ref))
+        .ok_or_else(|| format!("Could not get bounding box for: {}", selector_or_ref))?;
+    if !with_viewport {
+        if let Some(obj) = bbox.as_object_mut() {
+            obj.remove("viewport");
+        }
+    }
+    Ok(bbox)
 }
 
 pub async fn get_element_count(
     client: &CdpClient,
     session_id: &str,
     selector: &str,
+    visible_only: bool,
 ) -> Result<i64, String> {
-    let js = build_count_elements_js(selector);
+    let js = build_count_elements_js(selector, visible_only);
 
     let result: EvaluateResult = client
         .send_command_typed(
@@ -1342,6 +1667,26 @@ pub async fn get_element_styles(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_describe_target() {
+        let mut map = RefMap::new();
+        map.add("e1".to_string(), Some(10), "button", "Submit", None);
+        map.add_selector(
+            "e2".to_string(),
+            "#menu > div".to_string(),
+            "clickable",
+            "Open menu",
+            None,
+        );
+        assert_eq!(
+            describe_target(&map, "@e1"),
+            Some(serde_json::json!({ "ref": "e1", "role": "button", "name": "Submit" }))
+        );
+        assert_eq!(describe_target(&map, "#menu > div").unwrap()["ref"], "e2");
+        assert!(describe_target(&map, "@e9").is_none());
+        assert!(describe_target(&map, "#other").is_none());
+    }
+
     #[test]
     fn test_parse_ref_at_prefix() {
         assert_eq!(parse_ref("@e1"), Some("e1".to_string()));
@@ -1376,6 +1721,21 @@ mod tests {
         assert!(map.get("e2").is_none());
     }
 
+    #[test]
+    fn test_ref_map_forget_node_keeps_role_and_name() {
+        let mut map = RefMap::new();
+        map.add("e1".to_string(), Some(42), "button", "Submit", Some(1));
+        map.forget_node("e1");
+        map.forget_node("e9");
+        let entry = map.get("e1").unwrap();
+        assert_eq!(entry.backend_node_id, None);
+        assert_eq!(
+            (entry.role.as_str(), entry.name.as_str()),
+            ("button", "Submit")
+        );
+        assert_eq!(entry.nth, Some(1));
+    }
+
     #[test]
     fn test_build_selector_js_css() {
         let js = build_selector_js("#submit-btn");
@@ -1405,18 +1765,67 @@ mod tests {
 
     #[test]
     fn test_build_count_elements_js_css() {
-        let js = build_count_elements_js(".item");
+        let js = build_count_elements_js(".item", false);
         assert!(js.contains("document.querySelectorAll(\".item\").length"));
         assert!(!js.contains("document.evaluate"));
     }
 
     #[test]
     fn test_build_count_elements_js_xpath() {
-        let js = build_count_elements_js("xpath=//li");
+        let js = build_count_elements_js("xpath=//li", false);
         assert!(js.contains("document.evaluate(\"//li\", document, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null).snapshotLength"));
         assert!(!js.contains("querySelectorAll"));
     }
 
+    #[test]
+    fn test_build_count_elements_js_visible_only() {
+        let css = build_count_elements_js(".item", true);
+        assert!(css.starts_with("Array.from(document.querySelectorAll(\".item\")).filter("));
+        assert!(css.contains("style.display !== 'none'"));
+        let xpath = build_count_elements_js("xpath=//li", true);
+        assert!(xpath.contains("snapshotItem(i)"));
+        assert!(xpath.contains("getBoundingClientRect"));
+    }
+
+    #[test]
+    fn test_build_query_js_playwright_pseudos() {
+        let js = build_find_element_js("button:has-text(\"Delete\")");
+        assert!(js.starts_with("(Array.from(document.querySelectorAll(\"button\")).filter("));
+        assert!(js.contains("textMatch(el, 'has', \"Delete\")"));
+        assert!(js.ends_with("[0] || null)"));
+
+        let count = build_count_elements_js(":nth-match(li:visible, 2)", false);
+        assert!(count.contains("querySelectorAll(\"li\")"));
+        assert!(count.ends_with(".slice(1, 2).length"));
+
+        // Plain CSS keeps the direct DOM query.
+        assert_eq!(
+            build_query_all_js("doc", "li.item"),
+            "Array.from(doc.querySelectorAll(\"li.item\"))"
+        );
+    }
+
+    #[test]
+    fn test_build_find_in_element_js() {
+        assert_eq!(
+            build_find_in_element_js("button.primary"),
+            "this.querySelector(\"button.primary\")"
+        );
+        assert!(build_find_in_element_js("li:has-text(\"x\")")
+            .starts_with("(Array.from(this.querySelectorAll(\"li\"))"));
+        assert_eq!(
+            build_query_all_in_element_js("li"),
+            "Array.from(this.querySelectorAll(\"li\"))"
+        );
+        assert!(
+            build_query_all_in_element_js("xpath=.//li").contains("this.ownerDocument.evaluate(")
+        );
+        assert_eq!(
+            build_find_in_element_js("xpath=.//a"),
+            "this.ownerDocument.evaluate(\".//a\", this, null, XPathResult.FIRST_ORDERED_NODE_TYPE, null).singleNodeValue"
+        );
+    }
+
     #[test]
     fn test_box_model_center() {
         let model = BoxModel {
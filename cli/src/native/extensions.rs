@@ -0,0 +1,189 @@
+//! Metadata for the unpacked extensions a session was launched with
+//! (`--extension`). Chrome derives an unpacked extension's id from its
+//! manifest `key` when present and from its absolute path otherwise, so the id
+//! used in `chrome-extension://<id>/` URLs can be computed without asking the
+//! browser.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// Chrome's id alphabet: each hex digit of the hash maps to `a`..=`p`.
+fn id_from_hash(hash: &[u8]) -> String {
+    hash.iter()
+        .take(16)
+        .flat_map(|b| [b >> 4, b & 0x0f])
+        .map(|nibble| (b'a' + nibble) as char)
+        .collect()
+}
+
+/// The id Chrome assigns to an extension whose manifest carries `key`
+/// (base64 DER public key).
+fn id_from_key(key: &str) -> Option<String> {
+    let der = base64::engine::general_purpose::STANDARD
+        .decode(key.split_whitespace().collect::<String>())
+        .ok()?;
+    Some(id_from_hash(&Sha256::digest(der)))
+}
+
+/// The id Chrome assigns to an unpacked extension without a `key`: a hash
+/// of the absolute path as the platform stores it (UTF-16 on Windows, with
+/// the drive letter uppercased).
+fn id_from_path(path: &Path) -> String {
+    #[cfg(windows)]
+    {
+        let raw = path.to_string_lossy();
+        let raw = raw.strip_prefix(r"\\?\").unwrap_or(&raw);
+        let mut chars: Vec<char> = raw.chars().collect();
+        if chars.len() > 1 && chars[1] == ':' {
+            chars[0] = chars[0].to_ascii_uppercase();
+        }
+        let bytes: Vec<u8> = chars
+            .iter()
+            .collect::<String>()
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        id_from_hash(&Sha256::digest(bytes))
+    }
+    #[cfg(not(windows))]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        id_from_hash(&Sha256::digest(path.as_os_str().as_bytes()))
+    }
+}
+
+/// Resolve `__MSG_name__` manifest strings against the default locale.
+fn localized(dir: &Path, manifest: &Value, value: &str) -> String {
+    let Some(key) = value
+        .strip_prefix("__MSG_")
+        .and_then(|rest| rest.strip_suffix("__"))
+    else {
+        return value.to_string();
+    };
+    let locale = manifest
+        .get("default_locale")
+        .and_then(|v| v.as_str())
+        .unwrap_or("en");
+    let messages = dir.join("_locales").join(locale).join("messages.json");
+    fs::read_to_string(messages)
+        .ok()
+        .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+        .and_then(|messages| {
+            // Message names are case-insensitive.
+            messages.as_object()?.iter().find_map(|(name, entry)| {
+                name.eq_ignore_ascii_case(key)
+                    .then(|| entry.get("message")?.as_str().map(String::from))
+                    .flatten()
+            })
+        })
+        .unwrap_or_else(|| value.to_string())
+}
+
+/// Describe one `--extension` path: name, version, id, and whether Chrome
+/// could load it. An unreadable manifest means Chrome refused the extension,
+/// so it is reported as disabled with the reason.
+pub fn describe_extension(path: &str) -> Value {
+    let dir = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    let manifest = fs::read_to_string(dir.join("manifest.json"))
+        .map_err(|e| format!("cannot read manifest.json: {}", e))
+        .and_then(|s| {
+            serde_json::from_str::<Value>(&s).map_err(|e| format!("invalid manifest.json: {}", e))
+        });
+    let manifest = match manifest {
+        Ok(manifest) => manifest,
+        Err(error) => {
+            return json!({
+                "path": path,
+                "name": dir.file_name().map(|n| n.to_string_lossy().into_owned()),
+                "version": null,
+                "id": id_from_path(&dir),
+                "enabled": false,
+                "error": error,
+            });
+        }
+    };
+    let name = manifest
+        .get("name")
+        .and_then(|v| v.as_str())
+        .map(|name| localized(&dir, &manifest, name));
+    let id = manifest
+        .get("key")
+        .and_then(|v| v.as_str())
+        .and_then(id_from_key)
+        .unwrap_or_else(|| id_from_path(&dir));
+    json!({
+        "path": path,
+        "name": name,
+        "version": manifest.get("version").and_then(|v| v.as_str()),
+        "id": id,
+        "enabled": true,
+    })
+}
+
+/// `extension_list` data for the given `--extension` paths.
+pub fn list_json(paths: &[String]) -> Value {
+    let extensions: Vec<Value> = paths.iter().map(|p| describe_extension(p)).collect();
+    json!({ "extensions": extensions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_from_hash_uses_chrome_alphabet() {
+        // Chrome's crx_file::id_util::GenerateId("test").
+        assert_eq!(
+            id_from_hash(&Sha256::digest(b"test")),
+            "jpignaibiiemhngfjkcpokkamffknabf"
+        );
+    }
+
+    #[test]
+    fn test_describe_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let ext = dir.path().join("popup");
+        fs::create_dir_all(ext.join("_locales/en")).unwrap();
+        fs::write(
+            ext.join("manifest.json"),
+            r#"{"name":"__MSG_appName__","version":"1.2.0","default_locale":"en","manifest_version":3}"#,
+        )
+        .unwrap();
+        fs::write(
+            ext.join("_locales/en/messages.json"),
+            r#"{"APPNAME":{"message":"My Popup"}}"#,
+        )
+        .unwrap();
+
+        let info = describe_extension(ext.to_str().unwrap());
+        assert_eq!(info["name"], "My Popup");
+        assert_eq!(info["version"], "1.2.0");
+        assert_eq!(info["enabled"], true);
+        let id = info["id"].as_str().unwrap();
+        assert_eq!(id.len(), 32);
+        assert!(id.chars().all(|c| ('a'..='p').contains(&c)));
+        assert_eq!(id, id_from_path(&fs::canonicalize(&ext).unwrap()));
+
+        // A manifest key pins the id regardless of where the extension lives.
+        fs::write(
+            ext.join("manifest.json"),
+            r#"{"name":"Keyed","version":"1","key":"dGVzdA=="}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            describe_extension(ext.to_str().unwrap())["id"],
+            "jpignaibiiemhngfjkcpokkamffknabf"
+        );
+
+        let missing = describe_extension(dir.path().join("gone").to_str().unwrap());
+        assert_eq!(missing["enabled"], false);
+        assert!(missing["error"]
+            .as_str()
+            .unwrap()
+            .contains("cannot read manifest.json"));
+    }
+}
@@ -486,6 +486,72 @@ pub async fn select_option(
     Ok(())
 }
 
+/// Drag `source` onto `target` by dispatching the HTML5 drag-and-drop
+/// events (`dragstart`, `dragenter`, `dragover`, `drop`, `dragend`) with a
+/// shared `DataTransfer`, for libraries that ignore synthetic mouse drags.
+/// Points are viewport coordinates used as the events' clientX/clientY.
+#[allow(clippy::too_many_arguments)]
+pub async fn html5_drag(
+    client: &CdpClient,
+    session_id: &str,
+    ref_map: &RefMap,
+    source: &str,
+    target: &str,
+    source_point: (f64, f64),
+    target_point: (f64, f64),
+    iframe_sessions: &HashMap<String, String>,
+) -> Result<(), String> {
+    let (source_id, source_session) =
+        resolve_element_object_id(client, session_id, ref_map, source, iframe_sessions).await?;
+    let (target_id, target_session) =
+        resolve_element_object_id(client, session_id, ref_map, target, iframe_sessions).await?;
+    if source_session != target_session {
+        return Err("HTML5 drag requires the source and target to be in the same frame".into());
+    }
+
+    let js = r#"function(target, sx, sy, tx, ty) {
+            const dataTransfer = new DataTransfer();
+            const fire = (el, type, x, y) => el.dispatchEvent(new DragEvent(type, {
+                bubbles: true, cancelable: true, composed: true,
+                clientX: x, clientY: y, dataTransfer
+            }));
+            fire(this, 'dragstart', sx, sy);
+            fire(target, 'dragenter', tx, ty);
+            fire(target, 'dragover', tx, ty);
+            fire(target, 'drop', tx, ty);
+            fire(this, 'dragend', tx, ty);
+        }"#
+    .to_string();
+    let value = |v: f64| CallArgument {
+        value: Some(serde_json::json!(v)),
+        object_id: None,
+    };
+
+    client
+        .send_command_typed::<_, Value>(
+            "Runtime.callFunctionOn",
+            &CallFunctionOnParams {
+                function_declaration: js,
+                object_id: Some(source_id),
+                arguments: Some(vec![
+                    CallArgument {
+                        value: None,
+                        object_id: Some(target_id),
+                    },
+                    value(source_point.0),
+                    value(source_point.1),
+                    value(target_point.0),
+                    value(target_point.1),
+                ]),
+                return_by_value: Some(true),
+                await_promise: Some(false),
+            },
+            Some(&source_session),
+        )
+        .await?;
+    Ok(())
+}
+
 pub async fn check(
     client: &CdpClient,
     session_id: &str,
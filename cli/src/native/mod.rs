@@ -15,8 +15,12 @@ pub mod daemon;
 #[allow(dead_code)]
 pub mod diff;
 #[allow(dead_code)]
+pub mod downloads;
+#[allow(dead_code)]
 pub mod element;
 #[allow(dead_code)]
+pub mod extensions;
+#[allow(dead_code)]
 pub mod inspect_server;
 #[allow(dead_code)]
 pub mod interaction;
@@ -1,5 +1,6 @@
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Deref;
 
 use super::cdp::client::CdpClient;
 
@@ -567,6 +568,8 @@ pub struct ConsoleEntry {
     pub level: String,
     pub text: String,
     pub args: Vec<Value>,
+    /// Milliseconds since the Unix epoch when the entry was recorded.
+    pub timestamp: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -575,31 +578,107 @@ pub struct ErrorEntry {
     pub url: Option<String>,
     pub line: Option<i64>,
     pub column: Option<i64>,
+    /// Milliseconds since the Unix epoch when the entry was recorded.
+    pub timestamp: u64,
+}
+
+/// Entries each log buffer keeps until `set buffer` changes it.
+pub const DEFAULT_BUFFER_LIMIT: usize = 1000;
+
+/// A capped log. Once `limit` entries are held, each new one evicts the
+/// oldest; `dropped` counts evictions since the last clear so listings can
+/// say how much history is gone.
+#[derive(Debug)]
+pub struct LogBuffer<T> {
+    entries: VecDeque<T>,
+    limit: usize,
+    dropped: u64,
+}
+
+impl<T> LogBuffer<T> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            limit: limit.max(1),
+            dropped: 0,
+        }
+    }
+
+    pub fn push(&mut self, entry: T) {
+        while self.entries.len() >= self.limit {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Change the cap, evicting the oldest entries that no longer fit.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit.max(1);
+        while self.entries.len() > self.limit {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.dropped = 0;
+    }
+
+    pub fn iter_mut(&mut self) -> std::collections::vec_deque::IterMut<'_, T> {
+        self.entries.iter_mut()
+    }
+
+    /// `limit`, `buffered` and `dropped` for a listing response.
+    pub fn stats(&self) -> Value {
+        json!({
+            "limit": self.limit,
+            "buffered": self.entries.len(),
+            "dropped": self.dropped,
+        })
+    }
+}
+
+/// Read-only access; writes go through `push` so the cap holds.
+impl<T> Deref for LogBuffer<T> {
+    type Target = VecDeque<T>;
+
+    fn deref(&self) -> &VecDeque<T> {
+        &self.entries
+    }
+}
+
+/// The last `n` of `items` (all of them when `n` is `None`).
+pub fn tail_of<T>(items: Vec<T>, n: Option<usize>) -> Vec<T> {
+    match n {
+        Some(n) if n < items.len() => {
+            let skip = items.len() - n;
+            items.into_iter().skip(skip).collect()
+        }
+        _ => items,
+    }
 }
 
 pub struct EventTracker {
-    pub console_entries: Vec<ConsoleEntry>,
-    pub error_entries: Vec<ErrorEntry>,
-    pub max_entries: usize,
+    pub console_entries: LogBuffer<ConsoleEntry>,
+    pub error_entries: LogBuffer<ErrorEntry>,
 }
 
 impl EventTracker {
     pub fn new() -> Self {
         Self {
-            console_entries: Vec::new(),
-            error_entries: Vec::new(),
-            max_entries: 1000,
+            console_entries: LogBuffer::new(DEFAULT_BUFFER_LIMIT),
+            error_entries: LogBuffer::new(DEFAULT_BUFFER_LIMIT),
         }
     }
 
     pub fn add_console(&mut self, level: &str, text: &str, args: Vec<Value>) {
-        if self.console_entries.len() >= self.max_entries {
-            self.console_entries.remove(0);
-        }
         self.console_entries.push(ConsoleEntry {
             level: level.to_string(),
             text: text.to_string(),
             args,
+            timestamp: super::stream::timestamp_ms(),
         });
     }
 
@@ -610,14 +689,12 @@ impl EventTracker {
         line: Option<i64>,
         col: Option<i64>,
     ) {
-        if self.error_entries.len() >= self.max_entries {
-            self.error_entries.remove(0);
-        }
         self.error_entries.push(ErrorEntry {
             text: text.to_string(),
             url: url.map(String::from),
             line,
             column: col,
+            timestamp: super::stream::timestamp_ms(),
         });
     }
 
@@ -625,12 +702,17 @@ impl EventTracker {
         self.console_entries.clear();
     }
 
-    pub fn get_console_json(&self) -> Value {
-        let messages: Vec<Value> = self
-            .console_entries
-            .iter()
+    /// The buffered console messages, or only the last `tail` of them.
+    pub fn get_console_json(&self, tail: Option<usize>) -> Value {
+        let entries: Vec<&ConsoleEntry> = self.console_entries.iter().collect();
+        let messages: Vec<Value> = tail_of(entries, tail)
+            .into_iter()
             .map(|e| {
-                let mut msg = json!({ "type": e.level, "text": e.text });
+                let mut msg = json!({
+                    "type": e.level,
+                    "text": e.text,
+                    "timestamp": e.timestamp,
+                });
                 if !e.args.is_empty() {
                     msg.as_object_mut()
                         .unwrap()
@@ -639,23 +721,25 @@ impl EventTracker {
                 msg
             })
             .collect();
-        json!({ "messages": messages })
+        json!({ "messages": messages, "buffer": self.console_entries.stats() })
     }
 
-    pub fn get_errors_json(&self) -> Value {
-        let entries: Vec<Value> = self
-            .error_entries
-            .iter()
+    /// The buffered page errors, or only the last `tail` of them.
+    pub fn get_errors_json(&self, tail: Option<usize>) -> Value {
+        let errors: Vec<&ErrorEntry> = self.error_entries.iter().collect();
+        let entries: Vec<Value> = tail_of(errors, tail)
+            .into_iter()
             .map(|e| {
                 json!({
                     "text": e.text,
                     "url": e.url,
                     "line": e.line,
                     "column": e.column,
+                    "timestamp": e.timestamp,
                 })
             })
             .collect();
-        json!({ "errors": entries })
+        json!({ "errors": entries, "buffer": self.error_entries.stats() })
     }
 }
 
@@ -728,6 +812,59 @@ mod tests {
 
         assert_eq!(tracker.console_entries.len(), 1);
         assert_eq!(tracker.error_entries.len(), 1);
+        let console = tracker.get_console_json(None);
+        assert!(console["messages"][0]["timestamp"].as_u64().unwrap() > 0);
+        let errors = tracker.get_errors_json(None);
+        assert!(errors["errors"][0]["timestamp"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_log_buffer_evicts_and_counts_dropped() {
+        let mut buffer = LogBuffer::new(3);
+        for n in 0..5 {
+            buffer.push(n);
+        }
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(
+            buffer.stats(),
+            json!({ "limit": 3, "buffered": 3, "dropped": 2 })
+        );
+
+        buffer.set_limit(1);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![4]);
+        assert_eq!(buffer.stats()["dropped"], 4);
+
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.stats()["dropped"], 0);
+    }
+
+    #[test]
+    fn test_console_json_tail() {
+        let mut tracker = EventTracker::new();
+        tracker.console_entries.set_limit(3);
+        for text in ["a", "b", "c", "d"] {
+            tracker.add_console("log", text, vec![]);
+        }
+        let result = tracker.get_console_json(Some(2));
+        let texts: Vec<&str> = result["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(texts, vec!["c", "d"]);
+        assert_eq!(
+            result["buffer"],
+            json!({ "limit": 3, "buffered": 3, "dropped": 1 })
+        );
+        assert_eq!(
+            tracker.get_console_json(Some(10))["messages"]
+                .as_array()
+                .unwrap()
+                .len(),
+            3
+        );
     }
 
     #[test]
@@ -739,7 +876,7 @@ mod tests {
         ];
         tracker.add_console("log", "hello 42", raw_args);
 
-        let result = tracker.get_console_json();
+        let result = tracker.get_console_json(None);
         let messages = result.get("messages").unwrap().as_array().unwrap();
         assert_eq!(messages.len(), 1);
         assert_eq!(messages[0].get("text").unwrap(), "hello 42");
@@ -754,7 +891,7 @@ mod tests {
         let mut tracker = EventTracker::new();
         tracker.add_console("log", "text only", vec![]);
 
-        let result = tracker.get_console_json();
+        let result = tracker.get_console_json(None);
         let messages = result.get("messages").unwrap().as_array().unwrap();
         assert!(messages[0].get("args").is_none());
     }
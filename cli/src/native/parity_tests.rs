@@ -106,6 +106,10 @@ const DOCUMENTED_ACTIONS: &[&str] = &[
     "user_agent",
     "set_media",
     "download",
+    "downloads_list",
+    "downloads_clear",
+    "downloads_open",
+    "extension_list",
     "diff_snapshot",
     "diff_url",
     "credentials_set",
@@ -184,6 +188,8 @@ const DOCUMENTED_ACTIONS: &[&str] = &[
     "deny",
     "swipe",
     "device_list",
+    "device_use",
+    "device_boot",
     "input_mouse",
     "input_keyboard",
     "input_touch",
@@ -338,6 +344,9 @@ fn minimal_command(action: &str, id: &str) -> Value {
         "download" => {
             obj.insert("path".to_string(), json!("/tmp/parity-download"));
         }
+        "downloads_open" => {
+            obj.insert("index".to_string(), json!(1));
+        }
         "multiselect" => {
             obj.insert("selector".to_string(), json!("select"));
             obj.insert("values".to_string(), json!([]));
@@ -574,12 +583,27 @@ async fn test_tracked_request_struct() {
         status: Some(200),
         response_headers: None,
         mime_type: Some("text/html".to_string()),
+        size: None,
+        duration: None,
+        started: None,
     };
     let serialized = serde_json::to_value(&tr).unwrap();
     assert_eq!(serialized["url"], "https://example.com/api");
     assert_eq!(serialized["method"], "GET");
     assert_eq!(serialized["resourceType"], "Document");
     assert_eq!(serialized["timestamp"], 12345);
+    assert!(serialized.get("size").is_none());
+
+    let finished = TrackedRequest {
+        size: Some(2048),
+        duration: Some(35.5),
+        started: Some(1.25),
+        ..tr
+    };
+    let serialized = serde_json::to_value(&finished).unwrap();
+    assert_eq!(serialized["size"], 2048);
+    assert_eq!(serialized["duration"], 35.5);
+    assert!(serialized.get("started").is_none());
 }
 
 #[tokio::test]
@@ -599,6 +623,9 @@ async fn test_request_tracking_state() {
         status: None,
         response_headers: None,
         mime_type: None,
+        size: None,
+        duration: None,
+        started: None,
     });
     state.tracked_requests.push(super::actions::TrackedRequest {
         url: "https://other.com".to_string(),
@@ -611,6 +638,9 @@ async fn test_request_tracking_state() {
         status: None,
         response_headers: None,
         mime_type: None,
+        size: None,
+        duration: None,
+        started: None,
     });
     assert_eq!(state.tracked_requests.len(), 2);
 
@@ -30,6 +30,275 @@ pub struct ActionPolicy {
     confirm: Option<Vec<String>>,
 }
 
+/// Daemon action names that a policy or `--confirm-actions` can gate. Keep in
+/// sync with the dispatcher in actions.rs; `confirm` and `deny` are never gated.
+pub const ACTION_CATEGORIES: &[&str] = &[
+    "launch",
+    "navigate",
+    "read",
+    "url",
+    "cdp_url",
+    "inspect",
+    "title",
+    "content",
+    "evaluate",
+    "close",
+    "snapshot",
+    "screenshot",
+    "click",
+    "dblclick",
+    "fill",
+    "type",
+    "press",
+    "hover",
+    "scroll",
+    "select",
+    "check",
+    "uncheck",
+    "wait",
+    "gettext",
+    "getattribute",
+    "isvisible",
+    "isenabled",
+    "ischecked",
+    "back",
+    "forward",
+    "reload",
+    "cookies_get",
+    "cookies_set",
+    "cookies_clear",
+    "storage_get",
+    "storage_set",
+    "storage_clear",
+    "setcontent",
+    "headers",
+    "offline",
+    "console",
+    "errors",
+    "session_info",
+    "state_save",
+    "state_load",
+    "state_checkpoint",
+    "state_restore",
+    "state_checkpoints",
+    "state_list",
+    "state_show",
+    "state_clear",
+    "state_clean",
+    "state_rename",
+    "trace_start",
+    "trace_stop",
+    "profiler_start",
+    "profiler_stop",
+    "recording_start",
+    "recording_stop",
+    "recording_restart",
+    "pdf",
+    "tab_list",
+    "tab_new",
+    "tab_switch",
+    "tab_close",
+    "viewport",
+    "useragent",
+    "user_agent",
+    "set_media",
+    "download",
+    "downloads_list",
+    "downloads_clear",
+    "downloads_open",
+    "extension_list",
+    "diff_snapshot",
+    "diff_url",
+    "credentials_set",
+    "credentials_get",
+    "credentials_delete",
+    "credentials_list",
+    "mouse",
+    "keyboard",
+    "focus",
+    "clear",
+    "selectall",
+    "scrollintoview",
+    "dispatch",
+    "highlight",
+    "tap",
+    "boundingbox",
+    "innertext",
+    "innerhtml",
+    "inputvalue",
+    "setvalue",
+    "count",
+    "styles",
+    "bringtofront",
+    "timezone",
+    "locale",
+    "geolocation",
+    "permissions",
+    "dialog",
+    "upload",
+    "addscript",
+    "addinitscript",
+    "removeinitscript",
+    "addstyle",
+    "react_tree",
+    "react_inspect",
+    "react_renders_start",
+    "react_renders_stop",
+    "react_suspense",
+    "vitals",
+    "a11y",
+    "pushstate",
+    "clipboard",
+    "wheel",
+    "device",
+    "screencast_start",
+    "screencast_stop",
+    "stream_enable",
+    "stream_disable",
+    "stream_start",
+    "stream_stop",
+    "stream_status",
+    "waitforurl",
+    "waitforloadstate",
+    "waitforfunction",
+    "frame",
+    "mainframe",
+    "getbyrole",
+    "getbytext",
+    "getbylabel",
+    "getbyplaceholder",
+    "getbyalttext",
+    "getbytitle",
+    "getbytestid",
+    "nth",
+    "find",
+    "evalhandle",
+    "drag",
+    "expose",
+    "pause",
+    "multiselect",
+    "responsebody",
+    "waitfordownload",
+    "window_new",
+    "diff_screenshot",
+    "video_start",
+    "video_stop",
+    "har_start",
+    "har_stop",
+    "route",
+    "unroute",
+    "requests",
+    "request_detail",
+    "credentials",
+    "emulatemedia",
+    "timeout",
+    "tls_config",
+    "buffer_config",
+    "auth_save",
+    "auth_login",
+    "auth_list",
+    "auth_delete",
+    "auth_show",
+    "swipe",
+    "device_list",
+    "device_use",
+    "device_boot",
+    "input_mouse",
+    "input_keyboard",
+    "input_touch",
+    "keydown",
+    "keyup",
+    "inserttext",
+    "mousemove",
+    "mousedown",
+    "mouseup",
+];
+
+/// Shorthand category names accepted for the daemon action they stand for.
+const CATEGORY_ALIASES: &[(&str, &str)] = &[("eval", "evaluate")];
+
+/// Keys an action policy file may contain.
+const POLICY_KEYS: &[&str] = &["default", "allow", "deny", "confirm"];
+
+fn resolve_category(name: &str) -> &str {
+    CATEGORY_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, action)| action)
+}
+
+/// Whether `name` is an action category or a plugin capability action of the
+/// form `plugin:<name>:<capability>`.
+fn is_known_category(name: &str) -> bool {
+    if let Some(rest) = name.strip_prefix("plugin:") {
+        return rest
+            .split_once(':')
+            .is_some_and(|(plugin, capability)| !plugin.is_empty() && !capability.is_empty());
+    }
+    ACTION_CATEGORIES.contains(&resolve_category(name))
+}
+
+/// Check a comma-separated `--confirm-actions` value.
+pub fn validate_confirm_actions(value: &str) -> Result<(), String> {
+    let unknown: Vec<&str> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && !is_known_category(&s.to_lowercase()))
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Unknown action categor{} '{}'. Valid categories: {}, or plugin:<name>:<capability>",
+        if unknown.len() == 1 { "y" } else { "ies" },
+        unknown.join("', '"),
+        ACTION_CATEGORIES.join(", ")
+    ))
+}
+
+/// Check that a policy file exists, parses, and only uses known keys with
+/// values of the right shape.
+pub fn validate_policy_file(path: &str) -> Result<(), String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read policy file: {}", e))?;
+    let value: serde_json::Value =
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid policy JSON: {}", e))?;
+    let Some(object) = value.as_object() else {
+        return Err("Invalid policy: expected a JSON object".to_string());
+    };
+    for (key, value) in object {
+        match key.as_str() {
+            "default" => match value.as_str() {
+                Some(d) if d.eq_ignore_ascii_case("allow") || d.eq_ignore_ascii_case("deny") => {}
+                _ => {
+                    return Err(
+                        "Invalid policy: \"default\" must be \"allow\" or \"deny\"".to_string()
+                    )
+                }
+            },
+            "allow" | "deny" | "confirm" => {
+                let is_string_list = value
+                    .as_array()
+                    .is_some_and(|items| items.iter().all(|item| item.is_string()));
+                if !is_string_list {
+                    return Err(format!(
+                        "Invalid policy: \"{}\" must be an array of action names",
+                        key
+                    ));
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "Invalid policy: unknown key \"{}\" (expected one of: {})",
+                    key,
+                    POLICY_KEYS.join(", ")
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Confirmation categories parsed from AGENT_BROWSER_CONFIRM_ACTIONS.
 #[derive(Debug, Clone)]
 pub struct ConfirmActions {
@@ -46,6 +315,7 @@ impl ConfirmActions {
             .split(',')
             .map(|s| s.trim().to_lowercase())
             .filter(|s| !s.is_empty())
+            .map(|s| resolve_category(&s).to_string())
             .collect();
         if categories.is_empty() {
             None
@@ -204,6 +474,52 @@ mod tests {
         assert!(matches!(policy.check("navigate"), PolicyResult::Deny(_)));
     }
 
+    #[test]
+    fn test_validate_policy_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let cases: &[(&str, Option<&str>)] = &[
+            (r#"{"default": "allow", "deny": ["evaluate"]}"#, None),
+            (
+                r#"{"default": "DENY", "allow": [], "confirm": ["upload"]}"#,
+                None,
+            ),
+            ("{}", None),
+            ("{not json", Some("Invalid policy JSON")),
+            (r#"["click"]"#, Some("expected a JSON object")),
+            (r#"{"defualt": "deny"}"#, Some("unknown key \"defualt\"")),
+            (r#"{"default": "block"}"#, Some("\"default\" must be")),
+            (r#"{"deny": "evaluate"}"#, Some("\"deny\" must be an array")),
+            (
+                r#"{"allow": ["click", 1]}"#,
+                Some("\"allow\" must be an array"),
+            ),
+        ];
+        for (i, (json, expected)) in cases.iter().enumerate() {
+            let path = dir.path().join(format!("policy-{}.json", i));
+            fs::write(&path, json).unwrap();
+            let result = validate_policy_file(path.to_str().unwrap());
+            match expected {
+                None => assert!(result.is_ok(), "{}: {:?}", json, result),
+                Some(fragment) => {
+                    let err = result.unwrap_err();
+                    assert!(err.contains(fragment), "{}: {}", json, err);
+                }
+            }
+        }
+        let missing = dir.path().join("missing.json");
+        assert!(validate_policy_file(missing.to_str().unwrap())
+            .unwrap_err()
+            .starts_with("Failed to read policy file"));
+    }
+
+    #[test]
+    fn test_confirm_actions_eval_alias() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_CONFIRM_ACTIONS"]);
+        _guard.set("AGENT_BROWSER_CONFIRM_ACTIONS", "eval");
+        let ca = ConfirmActions::from_env().unwrap();
+        assert!(ca.requires_confirmation("evaluate"));
+    }
+
     #[test]
     fn test_confirm_actions_from_env() {
         let _guard = EnvGuard::new(&["AGENT_BROWSER_CONFIRM_ACTIONS"]);
@@ -41,16 +41,21 @@ pub async fn connect_provider_with_plugins(
 
 /// Connects to a built-in provider or plugin provider with launch options
 /// supplied by the command that requested the provider. Built-in providers keep
-/// their existing environment-based behavior; plugin providers receive these
+/// their existing environment-based behavior (Browserbase also reads
+/// `providerOptions` into its session request); plugin providers receive these
 /// options in the stdio protocol request.
 pub async fn connect_provider_with_plugins_and_options(
     provider_name: &str,
     plugins: &[crate::plugins::PluginConfig],
     launch_options: Option<Value>,
 ) -> Result<ProviderConnection, String> {
+    let provider_options = launch_options
+        .as_ref()
+        .and_then(|o| o.get("providerOptions"))
+        .cloned();
     match provider_name.to_lowercase().as_str() {
         "browserbase" => {
-            let (url, session) = connect_browserbase().await?;
+            let (url, session) = connect_browserbase(provider_options.as_ref()).await?;
             Ok(ProviderConnection {
                 ws_url: url,
                 session,
@@ -248,7 +253,41 @@ fn env_var_is_truthy(name: &str) -> bool {
     }
 }
 
-async fn connect_browserbase() -> Result<(String, Option<ProviderSession>), String> {
+/// `--provider-opt` values forwarded to the daemon at startup via
+/// AGENT_BROWSER_PROVIDER_OPTS, used when a command auto-launches the provider.
+pub fn provider_options_from_env() -> Option<Value> {
+    env::var("AGENT_BROWSER_PROVIDER_OPTS")
+        .ok()
+        .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+        .filter(|v| v.as_object().is_some_and(|o| !o.is_empty()))
+}
+
+/// Browserbase session-create body from provider options. Option values are
+/// strings on the command line; `timeout` and `keepAlive` are converted to the
+/// number and boolean the API expects.
+fn browserbase_session_body(provider_options: Option<&Value>) -> Value {
+    let mut body = serde_json::Map::new();
+    if let Some(options) = provider_options.and_then(|v| v.as_object()) {
+        for (key, value) in options {
+            let Some(raw) = value.as_str() else {
+                continue;
+            };
+            let value = match key.as_str() {
+                "timeout" => raw.parse::<u64>().map(Value::from).ok(),
+                "keepAlive" => raw.parse::<bool>().map(Value::from).ok(),
+                _ => Some(Value::from(raw)),
+            };
+            if let Some(value) = value {
+                body.insert(key.clone(), value);
+            }
+        }
+    }
+    Value::Object(body)
+}
+
+async fn connect_browserbase(
+    provider_options: Option<&Value>,
+) -> Result<(String, Option<ProviderSession>), String> {
     let api_key = env::var("BROWSERBASE_API_KEY")
         .map_err(|_| "BROWSERBASE_API_KEY environment variable is not set")?;
 
@@ -257,7 +296,7 @@ async fn connect_browserbase() -> Result<(String, Option<ProviderSession>), Stri
         .post("https://api.browserbase.com/v1/sessions")
         .header("content-type", "application/json")
         .header("x-bb-api-key", &api_key)
-        .body("{}")
+        .body(browserbase_session_body(provider_options).to_string())
         .send()
         .await
         .map_err(|e| format!("Browserbase request failed: {}", e))?;
@@ -895,6 +934,21 @@ mod tests {
         assert!(result.unwrap_err().contains("Unknown provider"));
     }
 
+    #[test]
+    fn test_browserbase_session_body_from_provider_options() {
+        let body = browserbase_session_body(Some(&json!({
+            "projectId": "proj-1",
+            "region": "us-west-2",
+            "timeout": "600",
+            "keepAlive": "true"
+        })));
+        assert_eq!(body["projectId"], "proj-1");
+        assert_eq!(body["region"], "us-west-2");
+        assert_eq!(body["timeout"], 600);
+        assert_eq!(body["keepAlive"], true);
+        assert_eq!(browserbase_session_body(None), json!({}));
+    }
+
     #[test]
     fn test_agentcore_env_defaults() {
         // Test that default values are used when env vars not set
@@ -1085,7 +1139,8 @@ printf '%s' '{"protocol":"agent-browser.plugin.v1","success":true,"browser":{"cd
                 "colorScheme": "dark",
                 "engine": "lightpanda",
                 "headed": true,
-                "userAgent": "cli-agent"
+                "userAgent": "cli-agent",
+                "providerOptions": { "region": "us-west-2" }
             })),
         ))
         .unwrap();
@@ -1099,5 +1154,9 @@ printf '%s' '{"protocol":"agent-browser.plugin.v1","success":true,"browser":{"cd
             request["request"]["launchOptions"]["userAgent"],
             "cli-agent"
         );
+        assert_eq!(
+            request["request"]["launchOptions"]["providerOptions"]["region"],
+            "us-west-2"
+        );
     }
 }
@@ -7,6 +7,8 @@ use std::collections::HashMap;
 use super::cdp::client::CdpClient;
 use super::cdp::types::*;
 use super::element::RefMap;
+use super::snapshot::is_content_role;
+use crate::atomic_write::write_atomic;
 
 const ANNOTATION_OVERLAY_ID: &str = "__agent_browser_annotations__";
 
@@ -59,7 +61,13 @@ pub struct ScreenshotOptions {
     pub format: String,
     pub quality: Option<i32>,
     pub annotate: bool,
+    /// Leave content refs (headings, cells, list items) out of the overlay
+    /// and legend so only controls are numbered.
+    pub interactive_only: bool,
     pub output_dir: Option<String>,
+    /// `--clip`: capture only this viewport rectangle (x, y, width, height
+    /// in CSS pixels).
+    pub clip: Option<[f64; 4]>,
 }
 
 impl Default for ScreenshotOptions {
@@ -71,7 +79,9 @@ impl Default for ScreenshotOptions {
             format: "png".to_string(),
             quality: None,
             annotate: false,
+            interactive_only: false,
             output_dir: None,
+            clip: None,
         }
     }
 }
@@ -105,12 +115,13 @@ pub async fn take_screenshot(
     iframe_sessions: &HashMap<String, String>,
 ) -> Result<ScreenshotResult, String> {
     let target_rect = if options.annotate {
-        match options.selector.as_deref() {
-            Some(selector) => {
+        match (options.clip, options.selector.as_deref()) {
+            (Some(clip), _) => Some(clip_rect(clip)),
+            (None, Some(selector)) => {
                 get_rect_for_selector(client, session_id, ref_map, selector, iframe_sessions)
                     .await?
             }
-            None => None,
+            (None, None) => None,
         }
     } else {
         None
@@ -122,7 +133,11 @@ pub async fn take_screenshot(
         Vec::new()
     };
 
-    let overlay_items = filter_annotations(raw_annotations, target_rect.as_ref());
+    let overlay_items = filter_annotations(
+        raw_annotations,
+        target_rect.as_ref(),
+        options.interactive_only,
+    );
     let overlay_injected = if options.annotate && !overlay_items.is_empty() {
         inject_annotation_overlay(client, session_id, &overlay_items).await?;
         true
@@ -207,6 +222,15 @@ async fn capture_screenshot_base64(
                 scale: 1.0,
             });
         }
+    } else if let Some(clip) = options.clip {
+        let rect = clip_rect(clip);
+        params.clip = Some(Viewport {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+            scale: 1.0,
+        });
     } else if let Some(ref selector) = options.selector {
         if let Some(rect) =
             get_rect_for_selector(client, session_id, ref_map, selector, iframe_sessions).await?
@@ -321,6 +345,15 @@ async fn collect_annotations(
     Ok(annotations)
 }
 
+fn clip_rect([x, y, width, height]: [f64; 4]) -> Rect {
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
 async fn get_rect_for_selector(
     client: &CdpClient,
     session_id: &str,
@@ -377,9 +410,11 @@ fn parse_rect(value: &Value) -> Option<Rect> {
 fn filter_annotations(
     annotations: Vec<RawAnnotation>,
     target_rect: Option<&Rect>,
+    interactive_only: bool,
 ) -> Vec<RawAnnotation> {
     let mut items = annotations
         .into_iter()
+        .filter(|annotation| !(interactive_only && is_content_role(&annotation.role)))
         .filter(|annotation| match target_rect {
             Some(target) => overlaps(&annotation.rect, target),
             None => true,
@@ -577,7 +612,7 @@ fn save_screenshot(
     let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, base64_data)
         .map_err(|e| format!("Failed to decode screenshot: {}", e))?;
 
-    std::fs::write(&save_path, &bytes)
+    write_atomic(&save_path, &bytes)
         .map_err(|e| format!("Failed to save screenshot to {}: {}", save_path, e))?;
 
     Ok(save_path)
@@ -637,11 +672,41 @@ mod tests {
             height: 100.0,
         };
 
-        let filtered = filter_annotations(annotations, Some(&target));
+        let filtered = filter_annotations(annotations, Some(&target), false);
         assert_eq!(filtered.len(), 1);
         assert_eq!(filtered[0].ref_id, "e1");
     }
 
+    #[test]
+    fn interactive_only_drops_content_refs() {
+        let annotation = |number: u64, role: &str| RawAnnotation {
+            ref_id: format!("e{}", number),
+            number,
+            role: role.to_string(),
+            name: Some("x".to_string()),
+            rect: Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 10.0,
+                height: 10.0,
+            },
+        };
+        let annotations = vec![
+            annotation(1, "heading"),
+            annotation(2, "button"),
+            annotation(3, "cell"),
+            annotation(4, "generic"),
+        ];
+
+        let all = filter_annotations(annotations.clone(), None, false);
+        assert_eq!(all.len(), 4);
+        let numbers: Vec<u64> = filter_annotations(annotations, None, true)
+            .iter()
+            .map(|a| a.number)
+            .collect();
+        assert_eq!(numbers, vec![2, 4]);
+    }
+
     #[test]
     fn projects_selector_annotations_relative_to_target() {
         let annotations = vec![RawAnnotation {
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use serde_json::Value;
 
@@ -29,6 +29,12 @@ const INTERACTIVE_ROLES: &[&str] = &[
     "Iframe",
 ];
 
+/// Roles that only get a ref because they have a name (headings, cells, list
+/// items), as opposed to controls and cursor-interactive elements.
+pub(super) fn is_content_role(role: &str) -> bool {
+    CONTENT_ROLES.contains(&role)
+}
+
 const CONTENT_ROLES: &[&str] = &[
     "heading",
     "cell",
@@ -77,10 +83,32 @@ const INVISIBLE_CHARS: &[char] = &[
 #[derive(Default)]
 pub struct SnapshotOptions {
     pub selector: Option<String>,
+    /// Ref (`e12`) the snapshot is scoped to, with its remote object id
+    /// already resolved by the caller from the previous snapshot's ref map.
+    pub scope_ref: Option<(String, String)>,
     pub interactive: bool,
     pub compact: bool,
     pub depth: Option<usize>,
     pub urls: bool,
+    /// Derive ref numbers from role+name+nth instead of document order so
+    /// unchanged elements keep the same ref across snapshots.
+    pub stable_refs: bool,
+    /// Drop elements that do not intersect the current viewport. Only the
+    /// top-level document is culled; child iframes are kept whole.
+    pub viewport_only: bool,
+    /// Rendered before each ref (`ab12-e3`) so refs cannot be confused with
+    /// page text. The ref map keeps the plain ids.
+    pub ref_prefix: Option<String>,
+}
+
+impl SnapshotOptions {
+    /// How `ref_id` appears in snapshot output.
+    pub fn ref_label(&self, ref_id: &str) -> String {
+        match self.ref_prefix {
+            Some(ref prefix) => format!("{}-{}", prefix, ref_id),
+            None => ref_id.to_string(),
+        }
+    }
 }
 
 struct TreeNode {
@@ -228,46 +256,61 @@ pub async fn take_snapshot(
         .send_command_no_params("Accessibility.enable", Some(session_id))
         .await?;
 
-    // If a CSS selector is provided, resolve the set of backendNodeIds that
-    // belong to the DOM subtree rooted at the matched element.  We use this
-    // set to pick the right AX subtree root(s) later.
-    let selector_backend_ids: Option<std::collections::HashSet<i64>> =
-        if let Some(ref selector) = options.selector {
-            let js = format!(
-                "document.querySelector({})",
-                serde_json::to_string(selector).unwrap_or_default()
-            );
-            let result: EvaluateResult = client
-                .send_command_typed(
-                    "Runtime.evaluate",
-                    &EvaluateParams {
-                        expression: js,
-                        return_by_value: Some(false),
-                        await_promise: Some(false),
-                    },
-                    Some(session_id),
-                )
-                .await?;
-
-            // A throwing evaluation (e.g. an invalid CSS selector like a
-            // snapshot ref "@e1") still yields an objectId — for the
-            // exception object. Passing that to DOM.describeNode produces the
-            // cryptic "Object id doesn't reference a Node"; fail clearly
-            // instead, and only accept results that are actually DOM nodes.
-            if let Some(exception) = result.exception_details {
-                let detail = exception
-                    .exception
-                    .and_then(|e| e.description)
-                    .unwrap_or(exception.text);
-                return Err(format!("Invalid selector '{}': {}", selector, detail));
-            }
-            if result.result.subtype.as_deref() != Some("node") {
-                return Err(format!("Selector '{}' did not match any element", selector));
-            }
-            let object_id = result
+    // If a CSS selector or ref is provided, resolve the set of backendNodeIds
+    // that belong to the DOM subtree rooted at the matched element.  We use
+    // this set to pick the right AX subtree root(s) later.
+    let scope_label = match (&options.scope_ref, &options.selector) {
+        (Some((ref_id, _)), _) => Some(format!("@{}", ref_id)),
+        (None, Some(selector)) => Some(selector.clone()),
+        (None, None) => None,
+    };
+    let scope_object_id: Option<String> = if let Some((_, ref object_id)) = options.scope_ref {
+        Some(object_id.clone())
+    } else if let Some(ref selector) = options.selector {
+        let js = format!(
+            "document.querySelector({})",
+            serde_json::to_string(selector).unwrap_or_default()
+        );
+        let result: EvaluateResult = client
+            .send_command_typed(
+                "Runtime.evaluate",
+                &EvaluateParams {
+                    expression: js,
+                    return_by_value: Some(false),
+                    await_promise: Some(false),
+                },
+                Some(session_id),
+            )
+            .await?;
+
+        // A throwing evaluation (e.g. an invalid CSS selector like a
+        // snapshot ref "@e1") still yields an objectId — for the
+        // exception object. Passing that to DOM.describeNode produces the
+        // cryptic "Object id doesn't reference a Node"; fail clearly
+        // instead, and only accept results that are actually DOM nodes.
+        if let Some(exception) = result.exception_details {
+            let detail = exception
+                .exception
+                .and_then(|e| e.description)
+                .unwrap_or(exception.text);
+            return Err(format!("Invalid selector '{}': {}", selector, detail));
+        }
+        if result.result.subtype.as_deref() != Some("node") {
+            return Err(format!("Selector '{}' did not match any element", selector));
+        }
+        Some(
+            result
                 .result
                 .object_id
-                .ok_or_else(|| format!("Selector '{}' did not match any element", selector))?;
+                .ok_or_else(|| format!("Selector '{}' did not match any element", selector))?,
+        )
+    } else {
+        None
+    };
+
+    let selector_backend_ids: Option<std::collections::HashSet<i64>> =
+        if let Some(object_id) = scope_object_id {
+            let selector = scope_label.as_deref().unwrap_or("");
 
             // Request the full DOM subtree (depth: -1) so we can collect all
             // backendNodeIds that live under the matched element.
@@ -320,6 +363,13 @@ pub async fn take_snapshot(
 
     let (mut tree_nodes, root_indices) = build_tree(&ax_tree.nodes);
 
+    let in_viewport: Option<Vec<bool>> = if options.viewport_only && frame_id.is_none() {
+        let visible = visible_backend_node_ids(client, effective_session_id).await?;
+        Some(cull_to_viewport(&mut tree_nodes, &root_indices, &visible))
+    } else {
+        None
+    };
+
     // When a selector is given, find AX nodes whose backendDOMNodeId falls
     // within the target DOM subtree and pick the top-level ones as roots.
     let effective_roots = if let Some(ref id_set) = selector_backend_ids {
@@ -345,7 +395,7 @@ pub async fn take_snapshot(
         if roots.is_empty() {
             return Err(format!(
                 "No accessibility node found for selector '{}'",
-                options.selector.as_deref().unwrap_or("")
+                scope_label.as_deref().unwrap_or("")
             ));
         }
         roots
@@ -367,6 +417,9 @@ pub async fn take_snapshot(
     promote_hidden_inputs(&mut tree_nodes, &cursor_elements);
 
     for (idx, node) in tree_nodes.iter().enumerate() {
+        if in_viewport.as_ref().is_some_and(|keep| !keep[idx]) {
+            continue;
+        }
         let role = node.role.as_str();
         let mut should_ref = if INTERACTIVE_ROLES.contains(&role) {
             true
@@ -401,8 +454,13 @@ pub async fn take_snapshot(
             None
         };
 
-        let ref_id = format!("e{}", next_ref);
-        next_ref += 1;
+        let ref_id = if options.stable_refs {
+            stable_ref_id(ref_map, &node.role, &node.name, *nth, frame_id)
+        } else {
+            let id = format!("e{}", next_ref);
+            next_ref += 1;
+            id
+        };
 
         ref_map.add_with_frame(
             ref_id.clone(),
@@ -541,7 +599,7 @@ pub async fn take_snapshot(
 
         // Insert each child snapshot after its Iframe line in the output
         for (ref_id, child_text) in iframe_snapshots {
-            let marker = format!("[ref={}]", ref_id);
+            let marker = format!("[ref={}]", options.ref_label(&ref_id));
             if let Some(pos) = output.find(&marker) {
                 // Find the end of the Iframe line
                 let line_end = output[pos..]
@@ -587,6 +645,153 @@ pub async fn take_snapshot(
     Ok(trimmed)
 }
 
+/// Scroll position, viewport size and page size of the top-level document,
+/// reported alongside a `--viewport-only` snapshot.
+pub async fn viewport_metrics(client: &CdpClient, session_id: &str) -> Result<Value, String> {
+    let result: EvaluateResult = client
+        .send_command_typed(
+            "Runtime.evaluate",
+            &EvaluateParams {
+                expression: r#"(() => {
+                    const d = document.documentElement;
+                    return {
+                        scrollX: Math.round(window.scrollX),
+                        scrollY: Math.round(window.scrollY),
+                        width: window.innerWidth,
+                        height: window.innerHeight,
+                        pageWidth: Math.max(d.scrollWidth, window.innerWidth),
+                        pageHeight: Math.max(d.scrollHeight, window.innerHeight)
+                    };
+                })()"#
+                    .to_string(),
+                return_by_value: Some(true),
+                await_promise: Some(false),
+            },
+            Some(session_id),
+        )
+        .await?;
+    result
+        .result
+        .value
+        .ok_or_else(|| "Could not read viewport metrics".to_string())
+}
+
+/// Backend node ids of DOM nodes whose layout box intersects the viewport.
+async fn visible_backend_node_ids(
+    client: &CdpClient,
+    session_id: &str,
+) -> Result<HashSet<i64>, String> {
+    let metrics = viewport_metrics(client, session_id).await?;
+    let metric = |key: &str| metrics.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let (left, top) = (metric("scrollX"), metric("scrollY"));
+    let (right, bottom) = (left + metric("width"), top + metric("height"));
+
+    // Layout bounds are in document coordinates, so they compare directly
+    // against the scrolled viewport rectangle.
+    let capture: Value = client
+        .send_command(
+            "DOMSnapshot.captureSnapshot",
+            Some(serde_json::json!({ "computedStyles": [] })),
+            Some(session_id),
+        )
+        .await?;
+    let document = capture
+        .get("documents")
+        .and_then(|d| d.get(0))
+        .ok_or("DOMSnapshot returned no documents")?;
+    let backend_ids = document
+        .pointer("/nodes/backendNodeId")
+        .and_then(|v| v.as_array())
+        .ok_or("DOMSnapshot returned no nodes")?;
+    let layout_nodes = document
+        .pointer("/layout/nodeIndex")
+        .and_then(|v| v.as_array())
+        .ok_or("DOMSnapshot returned no layout")?;
+    let bounds = document
+        .pointer("/layout/bounds")
+        .and_then(|v| v.as_array())
+        .ok_or("DOMSnapshot returned no layout bounds")?;
+
+    let mut visible = HashSet::new();
+    for (node_index, rect) in layout_nodes.iter().zip(bounds) {
+        let rect: Vec<f64> = rect
+            .as_array()
+            .map(|r| r.iter().filter_map(|v| v.as_f64()).collect())
+            .unwrap_or_default();
+        let [x, y, w, h] = rect[..] else {
+            continue;
+        };
+        if x + w < left || x > right || y + h < top || y > bottom {
+            continue;
+        }
+        if let Some(id) = node_index
+            .as_u64()
+            .and_then(|i| backend_ids.get(i as usize))
+            .and_then(|v| v.as_i64())
+        {
+            visible.insert(id);
+        }
+    }
+    Ok(visible)
+}
+
+/// Keep nodes that are visible or have a visible descendant, unlinking the
+/// rest from their parents. Returns which nodes were kept.
+fn cull_to_viewport(nodes: &mut [TreeNode], roots: &[usize], visible: &HashSet<i64>) -> Vec<bool> {
+    fn mark(nodes: &[TreeNode], idx: usize, visible: &HashSet<i64>, keep: &mut [bool]) -> bool {
+        let mut kept = nodes[idx]
+            .backend_node_id
+            .is_some_and(|id| visible.contains(&id));
+        for &child in &nodes[idx].children {
+            kept |= mark(nodes, child, visible, keep);
+        }
+        keep[idx] = kept;
+        kept
+    }
+
+    let mut keep = vec![false; nodes.len()];
+    for &root in roots {
+        mark(nodes, root, visible, &mut keep);
+    }
+    for node in nodes.iter_mut() {
+        node.children.retain(|&child| keep[child]);
+    }
+    keep
+}
+
+/// Stable refs live in a numeric range the sequential counter never reaches in
+/// practice, so both kinds can coexist in one ref map.
+const STABLE_REF_BASE: u64 = 100_000;
+const STABLE_REF_SPAN: u64 = 900_000;
+
+/// Hash role, name, duplicate index and frame into a ref number. Collisions
+/// probe forward to the next free number; the probe order is deterministic,
+/// so identical pages still produce identical refs.
+fn stable_ref_id(
+    ref_map: &RefMap,
+    role: &str,
+    name: &str,
+    nth: usize,
+    frame_id: Option<&str>,
+) -> String {
+    // FNV-1a: deterministic across processes, unlike std's RandomState.
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in [role, name, &nth.to_string(), frame_id.unwrap_or("")] {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    let mut n = hash % STABLE_REF_SPAN;
+    loop {
+        let ref_id = format!("e{}", STABLE_REF_BASE + n);
+        if ref_map.get(&ref_id).is_none() {
+            return ref_id;
+        }
+        n = (n + 1) % STABLE_REF_SPAN;
+    }
+}
+
 /// Resolve the child frame ID for an iframe element given its backendNodeId.
 async fn resolve_iframe_frame_id(
     client: &CdpClient,
@@ -1167,7 +1372,7 @@ fn render_tree(
     }
 
     if let Some(ref ref_id) = node.ref_id {
-        attrs.push(format!("ref={}", ref_id));
+        attrs.push(format!("ref={}", options.ref_label(ref_id)));
     }
 
     if let Some(ref url) = node.url {
@@ -1528,6 +1733,46 @@ mod tests {
         node
     }
 
+    #[test]
+    fn test_cull_to_viewport_keeps_visible_nodes_and_ancestors() {
+        // 0 main -> [1 heading (visible), 2 list -> [3 link (off-screen)]]
+        // 4 footer -> [5 link (off-screen)]
+        let mut nodes = vec![
+            make_node("main", "", Some(10)),
+            make_node("heading", "Title", Some(11)),
+            make_node("list", "", Some(12)),
+            make_node("link", "Far", Some(13)),
+            make_node("contentinfo", "", Some(14)),
+            make_node("link", "Footer", Some(15)),
+        ];
+        nodes[0].children = vec![1, 2];
+        nodes[2].children = vec![3];
+        nodes[4].children = vec![5];
+        let root = make_node("RootWebArea", "", None);
+        nodes.push(root);
+        nodes[6].children = vec![0, 4];
+
+        let visible: HashSet<i64> = [11].into_iter().collect();
+        let keep = cull_to_viewport(&mut nodes, &[6], &visible);
+        assert_eq!(keep, vec![true, true, false, false, false, false, true]);
+        assert_eq!(nodes[6].children, vec![0]);
+        assert_eq!(nodes[0].children, vec![1]);
+
+        // A visible descendant keeps its off-screen ancestors.
+        let mut nodes = vec![
+            make_node("list", "", Some(1)),
+            make_node("listitem", "", Some(2)),
+            make_node("link", "Sticky", Some(3)),
+        ];
+        nodes[0].children = vec![1];
+        nodes[1].children = vec![2];
+        let visible: HashSet<i64> = [3].into_iter().collect();
+        assert_eq!(
+            cull_to_viewport(&mut nodes, &[0], &visible),
+            vec![true, true, true]
+        );
+    }
+
     fn make_cursor_info(
         hidden_kind: Option<HiddenInputKind>,
         hidden_checked: Option<&str>,
@@ -1598,4 +1843,45 @@ mod tests {
 
         assert_eq!(nodes[0].role, "LabelText"); // unchanged
     }
+
+    #[test]
+    fn test_stable_ref_id_is_deterministic() {
+        let ref_map = RefMap::new();
+        let a = stable_ref_id(&ref_map, "button", "Submit", 0, None);
+        let b = stable_ref_id(&ref_map, "button", "Submit", 0, None);
+        assert_eq!(a, b);
+        assert_ne!(a, stable_ref_id(&ref_map, "button", "Submit", 1, None));
+        assert_ne!(a, stable_ref_id(&ref_map, "link", "Submit", 0, None));
+        assert!(crate::native::element::parse_ref(&a).is_some());
+    }
+
+    #[test]
+    fn test_stable_ref_id_probes_on_collision() {
+        let mut ref_map = RefMap::new();
+        let first = stable_ref_id(&ref_map, "button", "Submit", 0, None);
+        ref_map.add(first.clone(), None, "button", "Submit", None);
+        let second = stable_ref_id(&ref_map, "button", "Submit", 0, None);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_render_tree_with_ref_prefix() {
+        let mut node = make_node("button", "Go to @e3", Some(1));
+        node.ref_id = Some("e3".to_string());
+        node.has_ref = true;
+        let nodes = vec![node];
+
+        let mut output = String::new();
+        render_tree(&nodes, 0, 0, &mut output, &SnapshotOptions::default());
+        assert_eq!(output, "- button \"Go to @e3\" [ref=e3]\n");
+
+        let options = SnapshotOptions {
+            ref_prefix: Some("ab12".to_string()),
+            ..SnapshotOptions::default()
+        };
+        let mut output = String::new();
+        render_tree(&nodes, 0, 0, &mut output, &options);
+        assert_eq!(output, "- button \"Go to @e3\" [ref=ab12-e3]\n");
+        assert_eq!(options.ref_label("e7"), "ab12-e7");
+    }
 }
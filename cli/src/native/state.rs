@@ -5,7 +5,8 @@ use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
 use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 use super::cdp::client::CdpClient;
 use super::cdp::types::{
@@ -13,6 +14,7 @@ use super::cdp::types::{
     CreateTargetResult, EvaluateParams,
 };
 use super::cookies::{self, Cookie};
+use crate::atomic_write::write_atomic;
 use crate::validation::{is_valid_session_name, sanitize_session_component, session_name_error};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -249,10 +251,12 @@ pub async fn save_state(
     client: &CdpClient,
     session_id: &str,
     path: Option<&str>,
+    encrypt: Option<bool>,
     session_name: Option<&str>,
     session_id_str: &str,
     visited_origins: &HashSet<String>,
 ) -> Result<String, String> {
+    let key = state_encryption_key(encrypt, std::env::var("AGENT_BROWSER_ENCRYPTION_KEY").ok())?;
     let cookies = cookies::get_all_cookies(client, session_id).await?;
 
     let origin_js = r#"(() => {
@@ -310,7 +314,12 @@ pub async fn save_state(
         .map_err(|e| format!("Failed to serialize state: {}", e))?;
 
     let mut save_path = match path {
-        Some(p) => p.to_string(),
+        Some(p) => {
+            if let Some(dir) = Path::new(p).parent().filter(|d| !d.as_os_str().is_empty()) {
+                let _ = fs::create_dir_all(dir);
+            }
+            p.to_string()
+        }
         None => {
             let dir = get_sessions_dir();
             let _ = fs::create_dir_all(&dir);
@@ -324,19 +333,31 @@ pub async fn save_state(
         }
     };
 
-    if let Ok(key) = std::env::var("AGENT_BROWSER_ENCRYPTION_KEY") {
+    save_path = encrypted_state_path(&save_path, key.is_some());
+    if let Some(key) = key {
         let encrypted = encrypt_data(json_str.as_bytes(), &key)?;
-        save_path.push_str(".enc");
-        fs::write(&save_path, &encrypted)
+        write_atomic(&save_path, &encrypted)
             .map_err(|e| format!("Failed to write state to {}: {}", save_path, e))?;
     } else {
-        fs::write(&save_path, &json_str)
+        write_atomic(&save_path, &json_str)
             .map_err(|e| format!("Failed to write state to {}: {}", save_path, e))?;
     }
 
     Ok(save_path)
 }
 
+/// The file a save actually writes: `.enc` is appended when encrypting and
+/// dropped when not, so saving over an existing `<name>.json.enc` neither
+/// doubles the extension nor writes plain JSON under an encrypted name.
+fn encrypted_state_path(path: &str, encrypted: bool) -> String {
+    let base = path.strip_suffix(".enc").unwrap_or(path);
+    if encrypted {
+        format!("{}.enc", base)
+    } else {
+        base.to_string()
+    }
+}
+
 pub async fn save_auto_state_transactional(
     client: &CdpClient,
     session_id: &str,
@@ -379,6 +400,7 @@ pub async fn save_auto_state_transactional(
         client,
         session_id,
         Some(&candidate_arg),
+        None,
         Some(session_name),
         session_id_str,
         visited_origins,
@@ -423,7 +445,7 @@ pub async fn save_auto_state_transactional(
 }
 
 fn read_state_json(path: &str) -> Result<String, String> {
-    if is_encrypted_state(std::path::Path::new(path)) {
+    if detect_encrypted_state(std::path::Path::new(path)) {
         let key = std::env::var("AGENT_BROWSER_ENCRYPTION_KEY").map_err(|_| {
             "Encrypted state file requires AGENT_BROWSER_ENCRYPTION_KEY".to_string()
         })?;
@@ -554,7 +576,178 @@ fn is_encrypted_state(path: &std::path::Path) -> bool {
     path.ends_with(".json.enc") || path.ends_with(".json.enc.previous")
 }
 
+/// Smallest payload `encrypt_data` writes: a 12-byte nonce and 16-byte tag.
+const MIN_ENCRYPTED_STATE_LEN: u64 = 28;
+
+/// Whether a state file holds encrypted data: a `.json.enc` name, or
+/// content that is not JSON (an encrypted file saved or renamed without the
+/// extension). `is_encrypted_state` only looks at the name.
+pub fn detect_encrypted_state(path: &Path) -> bool {
+    if is_encrypted_state(path) {
+        return true;
+    }
+    let Ok(mut file) = fs::File::open(path) else {
+        return false;
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut head = [0u8; 64];
+    let read = file.read(&mut head).unwrap_or(0);
+    looks_encrypted(&head[..read], len)
+}
+
+/// Plain state is JSON, so after any BOM and whitespace it starts with `{`.
+fn looks_encrypted(head: &[u8], len: u64) -> bool {
+    if len < MIN_ENCRYPTED_STATE_LEN {
+        return false;
+    }
+    let head = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+    head.iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_some_and(|b| *b != b'{')
+}
+
+/// Before launching with `--state <path>`: whether the file is encrypted,
+/// failing when it is and no key is set, since the daemon could only
+/// report that after starting the browser.
+pub fn check_state_encryption_key(path: &str, env_key: Option<&str>) -> Result<bool, String> {
+    if !detect_encrypted_state(Path::new(path)) {
+        return Ok(false);
+    }
+    if env_key.is_none_or(str::is_empty) {
+        return Err(format!(
+            "{} is encrypted. Set AGENT_BROWSER_ENCRYPTION_KEY to the key it was saved with. See {}",
+            path, STATE_ENCRYPTION_DOCS
+        ));
+    }
+    Ok(true)
+}
+
+const STATE_ENCRYPTION_DOCS: &str = "https://agent-browser.dev/sessions#state-encryption";
+
+/// Key to encrypt a saved state with. By default the env key is used when
+/// set; `--encrypt` requires one and `--no-encrypt` skips it.
+fn state_encryption_key(
+    encrypt: Option<bool>,
+    env_key: Option<String>,
+) -> Result<Option<String>, String> {
+    let env_key = env_key.filter(|k| !k.is_empty());
+    match (encrypt, env_key) {
+        (Some(false), _) => Ok(None),
+        (Some(true), None) => Err(format!(
+            "--encrypt requires AGENT_BROWSER_ENCRYPTION_KEY to be set. See {}",
+            STATE_ENCRYPTION_DOCS
+        )),
+        (_, key) => Ok(key),
+    }
+}
+
+/// Extensions that mark a state argument as a file rather than a name.
+const STATE_FILE_EXTENSIONS: &[&str] = &[".json.enc", ".json"];
+
+fn strip_state_extension(arg: &str) -> Option<&str> {
+    STATE_FILE_EXTENSIONS
+        .iter()
+        .find_map(|ext| arg.strip_suffix(ext))
+}
+
+/// A name for a state in the managed directory: letters, digits, `-`, `_`,
+/// and `.`, with no leading dot and no `..`, so it can never leave the
+/// directory.
+pub fn is_valid_state_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains("..")
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn state_name_error(name: &str) -> String {
+    format!(
+        "Invalid state name '{}'. Names may use letters, digits, '-', '_', and '.', but no path separators or '..'. To use a file elsewhere, pass a path ending in .json.",
+        name
+    )
+}
+
+/// Resolve a state name, or a file name as printed by `state list`, to its
+/// file in the managed state directory. A bare name prefers `<name>.json`
+/// and falls back to an existing `<name>.json.enc`.
+pub fn managed_state_path(arg: &str) -> Result<PathBuf, String> {
+    let name = strip_state_extension(arg).unwrap_or(arg);
+    if !is_valid_state_name(name) {
+        return Err(state_name_error(arg));
+    }
+    let dir = get_sessions_dir();
+    if name != arg {
+        return Ok(dir.join(arg));
+    }
+    let json = dir.join(format!("{}.json", name));
+    let encrypted = dir.join(format!("{}.json.enc", name));
+    Ok(if !json.exists() && encrypted.exists() {
+        encrypted
+    } else {
+        json
+    })
+}
+
+/// Whether a `state save`/`load` argument is a path rather than a name: it
+/// contains a path separator or ends in `.json` or `.json.enc`.
+fn is_state_path_arg(arg: &str) -> bool {
+    arg.contains(['/', '\\']) || strip_state_extension(arg).is_some()
+}
+
+/// Check the argument of `state save` or `state load` without touching the
+/// filesystem, so the CLI can reject it before a browser is launched. A bare
+/// name must be a valid state name; a path without a state extension may
+/// not climb with `..`, since that is a name trying to leave the directory.
+pub fn validate_state_arg(arg: &str) -> Result<(), String> {
+    let climbs = arg.split(['/', '\\']).any(|part| part == "..");
+    let valid = if is_state_path_arg(arg) {
+        !climbs || strip_state_extension(arg).is_some()
+    } else {
+        is_valid_state_name(arg)
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(state_name_error(arg))
+    }
+}
+
+/// Resolve the argument of `state save` or `state load`. A path (see
+/// [`validate_state_arg`]) is used as given; a bare name is a state in the
+/// managed directory.
+pub fn resolve_state_path(arg: &str) -> Result<PathBuf, String> {
+    validate_state_arg(arg)?;
+    if is_state_path_arg(arg) {
+        Ok(PathBuf::from(arg))
+    } else {
+        managed_state_path(arg)
+    }
+}
+
+/// Like [`resolve_state_path`], for commands that read an existing state: a
+/// bare file name that doesn't exist in the working directory is looked up
+/// in the managed directory, so names copied from `state list` work.
+pub fn resolve_existing_state_path(arg: &str) -> Result<PathBuf, String> {
+    let path = resolve_state_path(arg)?;
+    let bare = !arg.contains(['/', '\\']);
+    if bare && !path.exists() {
+        if let Ok(managed) = managed_state_path(arg) {
+            if managed.exists() {
+                return Ok(managed);
+            }
+        }
+    }
+    Ok(path)
+}
+
 pub fn state_list() -> Result<Value, String> {
+    list_state_files(|_| true)
+}
+
+/// `state_list` output for the state files whose names pass `keep`.
+fn list_state_files(keep: impl Fn(&str) -> bool) -> Result<Value, String> {
     let dir = get_sessions_dir();
     if !dir.exists() {
         return Ok(json!({ "files": [], "directory": dir.to_string_lossy() }));
@@ -566,7 +759,10 @@ pub fn state_list() -> Result<Value, String> {
 
     for entry in entries.flatten() {
         let path = entry.path();
-        if is_state_file(&path) {
+        let keep_file = path
+            .file_name()
+            .is_some_and(|name| keep(&name.to_string_lossy()));
+        if is_state_file(&path) && keep_file {
             let metadata = fs::metadata(&path).ok();
             let filename = path
                 .file_name()
@@ -596,11 +792,28 @@ pub fn state_list() -> Result<Value, String> {
 }
 
 pub fn state_show(path: &str) -> Result<Value, String> {
-    let encrypted = is_encrypted_state(std::path::Path::new(path));
-    let json_str = if encrypted {
-        let key = std::env::var("AGENT_BROWSER_ENCRYPTION_KEY").map_err(|_| {
-            "Encrypted state file requires AGENT_BROWSER_ENCRYPTION_KEY".to_string()
-        })?;
+    let encrypted = detect_encrypted_state(std::path::Path::new(path));
+    let key = std::env::var("AGENT_BROWSER_ENCRYPTION_KEY")
+        .ok()
+        .filter(|k| !k.is_empty());
+    if encrypted && key.is_none() {
+        // Size and mtime are all that can be read; say why instead of
+        // failing to parse ciphertext.
+        let metadata =
+            fs::metadata(path).map_err(|e| format!("Failed to read state file: {}", e))?;
+        return Ok(json!({
+            "filename": Path::new(path).file_name().unwrap_or_default().to_string_lossy(),
+            "path": path,
+            "size": metadata.len(),
+            "encrypted": true,
+            "locked": true,
+            "note": format!(
+                "{} is encrypted. Set AGENT_BROWSER_ENCRYPTION_KEY to the key it was saved with to show its cookies and origins.",
+                path
+            ),
+        }));
+    }
+    let json_str = if let (true, Some(key)) = (encrypted, key) {
         let data = fs::read(path).map_err(|e| format!("Failed to read state file: {}", e))?;
         let decrypted = decrypt_data(&data, &key)?;
         String::from_utf8(decrypted)
@@ -634,29 +847,41 @@ pub fn state_show(path: &str) -> Result<Value, String> {
     }))
 }
 
-pub fn state_clear(path: Option<&str>) -> Result<Value, String> {
-    if let Some(p) = path {
-        fs::remove_file(p).map_err(|e| format!("Failed to delete state: {}", e))?;
-        return Ok(json!({ "deleted": p }));
-    }
+/// Delete saved states. With a name, removes that state and the states
+/// auto-saved for the session of the same name (`<name>-<id>.json`);
+/// without one, removes every state in the managed directory.
+pub fn state_clear(name: Option<&str>) -> Result<Value, String> {
+    let named = name.map(managed_state_path).transpose()?;
+    let auto_prefix = name.map(|n| format!("{}-", strip_state_extension(n).unwrap_or(n)));
 
     let dir = get_sessions_dir();
     if !dir.exists() {
-        return Ok(json!({ "deleted": 0 }));
+        return Ok(json!({ "cleared": 0 }));
     }
 
     let mut count = 0;
     if let Ok(entries) = fs::read_dir(&dir) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if is_state_file(&path) {
-                let _ = fs::remove_file(&path);
+            if !is_state_file(&path) {
+                continue;
+            }
+            let matches = match (&named, &auto_prefix) {
+                (Some(named), Some(prefix)) => {
+                    let fname = path.file_name().unwrap_or_default().to_string_lossy();
+                    path == *named
+                        || (fname.starts_with(prefix.as_str())
+                            && strip_state_extension(&fname).is_some())
+                }
+                _ => true,
+            };
+            if matches && fs::remove_file(&path).is_ok() {
                 count += 1;
             }
         }
     }
 
-    Ok(json!({ "deleted": count }))
+    Ok(json!({ "cleared": count }))
 }
 
 pub fn state_clean(max_age_days: u64) -> Result<Value, String> {
@@ -695,21 +920,36 @@ pub fn state_clean(max_age_days: u64) -> Result<Value, String> {
     Ok(json!({ "cleaned": deleted, "keptCount": kept, "days": max_age_days }))
 }
 
-pub fn state_rename(old_path: &str, new_name: &str) -> Result<Value, String> {
-    let old = PathBuf::from(old_path);
+/// Rename a state in the managed directory. Both sides are state names (or
+/// `state list` file names); the file keeps its `.json` or `.json.enc`
+/// extension.
+pub fn state_rename(old_name: &str, new_name: &str) -> Result<Value, String> {
+    let old = managed_state_path(old_name)?;
+    let new_stem = strip_state_extension(new_name).unwrap_or(new_name);
+    if !is_valid_state_name(new_stem) {
+        return Err(state_name_error(new_name));
+    }
     if !old.exists() {
-        return Err(format!("State file not found: {}", old_path));
+        return Err(format!("State not found: {}", old.display()));
     }
 
-    let fallback = PathBuf::from(".");
-    let dir = old.parent().unwrap_or(&fallback);
-    let new_path = dir.join(format!("{}.json", new_name));
+    let extension = if is_encrypted_state(&old) {
+        ".json.enc"
+    } else {
+        ".json"
+    };
+    let new_path = old.with_file_name(format!("{}{}", new_stem, extension));
+    if new_path.exists() {
+        return Err(format!("State already exists: {}", new_path.display()));
+    }
 
     fs::rename(&old, &new_path).map_err(|e| format!("Failed to rename state: {}", e))?;
 
     Ok(json!({
         "renamed": true,
-        "from": old_path,
+        "oldName": old_name,
+        "newName": new_stem,
+        "from": old.to_string_lossy(),
         "to": new_path.to_string_lossy(),
     }))
 }
@@ -787,6 +1027,127 @@ pub fn find_auto_state_file(session_name: &str) -> Option<String> {
     best_path.map(|(p, _)| p)
 }
 
+/// Checkpoints are `<session>.checkpoint-<YYYYMMDD-HHMMSS>[-<label>].json`
+/// in the managed directory. The dot after the session name keeps them out of
+/// the `<session>-*` auto-save lookup and out of `state clear <session>`.
+const CHECKPOINT_MARKER: &str = ".checkpoint-";
+const CHECKPOINT_STAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+const CHECKPOINT_STAMP_LEN: usize = 15;
+
+fn checkpoint_file_name(session_name: &str, stamp: &str, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!(
+            "{}{}{}-{}.json",
+            session_name, CHECKPOINT_MARKER, stamp, label
+        ),
+        None => format!("{}{}{}.json", session_name, CHECKPOINT_MARKER, stamp),
+    }
+}
+
+/// Split a checkpoint file name of `session_name` into its timestamp and
+/// optional label.
+fn parse_checkpoint_name<'a>(
+    file_name: &'a str,
+    session_name: &str,
+) -> Option<(&'a str, Option<&'a str>)> {
+    let rest = strip_state_extension(file_name)?
+        .strip_prefix(session_name)?
+        .strip_prefix(CHECKPOINT_MARKER)?;
+    let stamp = rest.get(..CHECKPOINT_STAMP_LEN)?;
+    let well_formed = stamp
+        .char_indices()
+        .all(|(i, c)| if i == 8 { c == '-' } else { c.is_ascii_digit() });
+    if !well_formed {
+        return None;
+    }
+    match &rest[CHECKPOINT_STAMP_LEN..] {
+        "" => Some((stamp, None)),
+        label => label
+            .strip_prefix('-')
+            .filter(|l| !l.is_empty())
+            .map(|l| (stamp, Some(l))),
+    }
+}
+
+/// The newest checkpoint of `session_name` among `file_names`, optionally
+/// limited to one label. Timestamps sort lexically; ties go to the later name.
+fn latest_checkpoint<'a>(
+    file_names: impl IntoIterator<Item = &'a str>,
+    session_name: &str,
+    label: Option<&str>,
+) -> Option<&'a str> {
+    file_names
+        .into_iter()
+        .filter_map(|name| parse_checkpoint_name(name, session_name).map(|c| (c, name)))
+        .filter(|((_, l), _)| label.is_none() || *l == label)
+        .max_by(|((a, _), a_name), ((b, _), b_name)| a.cmp(b).then(a_name.cmp(b_name)))
+        .map(|(_, name)| name)
+}
+
+fn checkpoint_session(session_name: Option<&str>) -> Result<&str, String> {
+    let name = session_name.filter(|n| !n.is_empty()).ok_or(
+        "Checkpoints belong to a named session. Pass --restore <key> (or --session-name <name>) first.",
+    )?;
+    if !is_valid_session_name(name) {
+        return Err(session_name_error(name));
+    }
+    Ok(name)
+}
+
+pub fn validate_checkpoint_label(label: Option<&str>) -> Result<(), String> {
+    match label {
+        Some(label) if !is_valid_state_name(label) => Err(state_name_error(label)),
+        _ => Ok(()),
+    }
+}
+
+/// Path for a new checkpoint of the session, stamped with the current time.
+pub fn new_checkpoint_path(
+    session_name: Option<&str>,
+    label: Option<&str>,
+) -> Result<PathBuf, String> {
+    let session_name = checkpoint_session(session_name)?;
+    validate_checkpoint_label(label)?;
+    let stamp = chrono::Utc::now()
+        .format(CHECKPOINT_STAMP_FORMAT)
+        .to_string();
+    Ok(get_sessions_dir().join(checkpoint_file_name(session_name, &stamp, label)))
+}
+
+/// The most recent checkpoint of the session, optionally with a given label.
+pub fn find_checkpoint(session_name: Option<&str>, label: Option<&str>) -> Result<PathBuf, String> {
+    let session_name = checkpoint_session(session_name)?;
+    validate_checkpoint_label(label)?;
+    let dir = get_sessions_dir();
+    let names: Vec<String> = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    latest_checkpoint(names.iter().map(String::as_str), session_name, label)
+        .map(|name| dir.join(name))
+        .ok_or_else(|| match label {
+            Some(label) => format!(
+                "No checkpoint labelled '{}' for session '{}'",
+                label, session_name
+            ),
+            None => format!("No checkpoints for session '{}'", session_name),
+        })
+}
+
+/// The session's checkpoints in `state_list` form, newest first.
+pub fn checkpoint_list(session_name: Option<&str>) -> Result<Value, String> {
+    let session_name = checkpoint_session(session_name)?;
+    let mut listing = list_state_files(|name| parse_checkpoint_name(name, session_name).is_some())?;
+    if let Some(files) = listing.get_mut("files").and_then(|f| f.as_array_mut()) {
+        files.sort_by(|a, b| b["filename"].as_str().cmp(&a["filename"].as_str()));
+    }
+    Ok(listing)
+}
+
 /// Dispatch a state management command from its JSON payload.
 /// Returns `Some(result)` for recognised state_* actions, `None` otherwise.
 pub fn dispatch_state_command(cmd: &Value) -> Option<Result<Value, String>> {
@@ -797,25 +1158,30 @@ pub fn dispatch_state_command(cmd: &Value) -> Option<Result<Value, String>> {
             cmd.get("path")
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| "Missing 'path' parameter".to_string())
-                .and_then(state_show),
+                .and_then(resolve_existing_state_path)
+                .and_then(|path| state_show(&path.to_string_lossy())),
         ),
         "state_clear" => {
-            let path = cmd.get("path").and_then(|v| v.as_str());
-            Some(state_clear(path))
+            let name = cmd.get("name").and_then(|v| v.as_str());
+            let all = cmd.get("all").and_then(|v| v.as_bool()).unwrap_or(false);
+            if name.is_none() && !all {
+                return Some(Err("state_clear needs a 'name' or 'all: true'".to_string()));
+            }
+            Some(state_clear(name))
         }
         "state_clean" => {
             let days = cmd.get("days").and_then(|v| v.as_u64()).unwrap_or(30);
             Some(state_clean(days))
         }
         "state_rename" => Some(
-            cmd.get("path")
+            cmd.get("oldName")
                 .and_then(|v| v.as_str())
-                .ok_or_else(|| "Missing 'path' parameter".to_string())
-                .and_then(|path| {
-                    cmd.get("name")
+                .ok_or_else(|| "Missing 'oldName' parameter".to_string())
+                .and_then(|old_name| {
+                    cmd.get("newName")
                         .and_then(|v| v.as_str())
-                        .ok_or_else(|| "Missing 'name' parameter".to_string())
-                        .and_then(|name| state_rename(path, name))
+                        .ok_or_else(|| "Missing 'newName' parameter".to_string())
+                        .and_then(|new_name| state_rename(old_name, new_name))
                 }),
         ),
         _ => None,
@@ -850,6 +1216,84 @@ pub fn get_sessions_dir() -> PathBuf {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_detect_encrypted_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = "k".repeat(64);
+        let ciphertext = encrypt_data(br#"{"cookies":[],"origins":[]}"#, &key).unwrap();
+
+        let plain = dir.path().join("plain.json");
+        fs::write(&plain, "\n  {\"cookies\": [], \"origins\": []}").unwrap();
+        assert!(!detect_encrypted_state(&plain));
+
+        let bom = dir.path().join("bom.json");
+        fs::write(&bom, b"\xEF\xBB\xBF{\"cookies\": [], \"origins\": []}").unwrap();
+        assert!(!detect_encrypted_state(&bom));
+
+        // Named as encrypted, whatever the content.
+        let named = dir.path().join("named.json.enc");
+        fs::write(&named, "{}").unwrap();
+        assert!(detect_encrypted_state(&named));
+
+        // Ciphertext under a plain name is found by its content.
+        let renamed = dir.path().join("auth.state");
+        fs::write(&renamed, &ciphertext).unwrap();
+        assert!(detect_encrypted_state(&renamed));
+
+        let tiny = dir.path().join("tiny.state");
+        fs::write(&tiny, b"\x01\x02").unwrap();
+        assert!(!detect_encrypted_state(&tiny));
+        assert!(!detect_encrypted_state(&dir.path().join("missing.json")));
+    }
+
+    #[test]
+    fn test_check_state_encryption_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("plain.json");
+        fs::write(&plain, r#"{"cookies":[],"origins":[]}"#).unwrap();
+        let encrypted = dir.path().join("auth.state");
+        fs::write(
+            &encrypted,
+            encrypt_data(br#"{"cookies":[],"origins":[]}"#, "secret").unwrap(),
+        )
+        .unwrap();
+        let plain = plain.to_str().unwrap();
+        let encrypted = encrypted.to_str().unwrap();
+
+        assert_eq!(check_state_encryption_key(plain, None), Ok(false));
+        assert_eq!(
+            check_state_encryption_key(encrypted, Some("secret")),
+            Ok(true)
+        );
+        for key in [None, Some("")] {
+            let err = check_state_encryption_key(encrypted, key).unwrap_err();
+            assert!(err.contains("is encrypted"), "{}", err);
+            assert!(err.contains("AGENT_BROWSER_ENCRYPTION_KEY"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_read_state_json_decrypts_by_content() {
+        let guard = crate::test_utils::EnvGuard::new(&["AGENT_BROWSER_ENCRYPTION_KEY"]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auth.state");
+        let json = r#"{"cookies":[],"origins":[]}"#;
+        fs::write(&path, encrypt_data(json.as_bytes(), "secret").unwrap()).unwrap();
+        let path = path.to_str().unwrap();
+
+        guard.remove("AGENT_BROWSER_ENCRYPTION_KEY");
+        let shown = state_show(path).unwrap();
+        assert_eq!(shown["locked"], true);
+        assert!(shown["note"].as_str().unwrap().contains("is encrypted"));
+        assert!(shown.get("summary").is_none());
+
+        guard.set("AGENT_BROWSER_ENCRYPTION_KEY", "secret");
+        assert_eq!(read_state_json(path).unwrap(), json);
+        let shown = state_show(path).unwrap();
+        assert_eq!(shown["encrypted"], true);
+        assert_eq!(shown["summary"], "0 cookies, 0 origins");
+    }
+
     #[test]
     fn test_storage_state_serialization() {
         let state = StorageState {
@@ -902,9 +1346,16 @@ mod tests {
     }
 
     #[test]
-    fn test_state_clear_nonexistent_file() {
+    fn test_state_clear_rejects_paths() {
         let result = state_clear(Some("/tmp/nonexistent-agent-browser-state-file.json"));
-        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Invalid state name"));
+    }
+
+    #[test]
+    fn test_dispatch_state_clear_needs_name_or_all() {
+        let cmd = json!({ "action": "state_clear", "id": "c1" });
+        let err = dispatch_state_command(&cmd).unwrap().unwrap_err();
+        assert!(err.contains("'name' or 'all: true'"));
     }
 
     #[test]
@@ -935,7 +1386,7 @@ mod tests {
 
         let result = state_clear(None).unwrap();
 
-        assert_eq!(result["deleted"], 3);
+        assert_eq!(result["cleared"], 3);
         assert!(!sessions.join("auth-test.json").exists());
         assert!(!sessions.join("auth-test.json.previous").exists());
         assert!(!sessions.join("auth-test.json.enc.previous").exists());
@@ -943,8 +1394,12 @@ mod tests {
 
     #[test]
     fn test_state_rename_nonexistent() {
-        let result = state_rename("/tmp/nonexistent-agent-browser-state-file.json", "new-name");
-        assert!(result.is_err());
+        let guard = crate::test_utils::EnvGuard::new(&["HOME", "AGENT_BROWSER_NAMESPACE"]);
+        let dir = tempfile::tempdir().unwrap();
+        guard.set("HOME", dir.path().to_str().unwrap());
+        guard.remove("AGENT_BROWSER_NAMESPACE");
+
+        let result = state_rename("nonexistent-state", "new-name");
         assert!(result.unwrap_err().contains("not found"));
     }
 
@@ -1051,11 +1506,287 @@ mod tests {
         let cmd = serde_json::json!({ "action": "state_rename" });
         let result = dispatch_state_command(&cmd).unwrap();
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Missing 'path' parameter");
+        assert_eq!(result.unwrap_err(), "Missing 'oldName' parameter");
 
-        let cmd = serde_json::json!({ "action": "state_rename", "path": "/tmp/test.json" });
+        let cmd = serde_json::json!({ "action": "state_rename", "oldName": "test" });
         let result = dispatch_state_command(&cmd).unwrap();
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Missing 'name' parameter");
+        assert_eq!(result.unwrap_err(), "Missing 'newName' parameter");
+    }
+
+    fn isolated_sessions_dir(guard: &crate::test_utils::EnvGuard, home: &Path) -> PathBuf {
+        guard.set("HOME", home.to_str().unwrap());
+        guard.remove("AGENT_BROWSER_NAMESPACE");
+        let sessions = get_sessions_dir();
+        fs::create_dir_all(&sessions).unwrap();
+        sessions
+    }
+
+    #[test]
+    fn test_state_names_allow_dots_but_not_traversal() {
+        for name in ["github", "work-2", "my_app", "app.example.com", "v1.2"] {
+            assert!(is_valid_state_name(name), "{} should be valid", name);
+        }
+        for name in [
+            "",
+            ".",
+            "..",
+            ".hidden",
+            "../../etc",
+            "a/b",
+            "a\\b",
+            "a..b",
+            "x y",
+        ] {
+            assert!(!is_valid_state_name(name), "{} should be invalid", name);
+        }
+    }
+
+    #[test]
+    fn test_resolve_state_path_names_and_explicit_paths() {
+        let guard = crate::test_utils::EnvGuard::new(&["HOME", "AGENT_BROWSER_NAMESPACE"]);
+        let home = tempfile::tempdir().unwrap();
+        let sessions = isolated_sessions_dir(&guard, home.path());
+
+        assert_eq!(
+            resolve_state_path("github").unwrap(),
+            sessions.join("github.json")
+        );
+        assert_eq!(
+            resolve_state_path("app.example.com").unwrap(),
+            sessions.join("app.example.com.json")
+        );
+        // Anything ending in .json or containing a separator is a path and
+        // is used as given.
+        assert_eq!(
+            resolve_state_path("./backup/auth").unwrap(),
+            PathBuf::from("./backup/auth")
+        );
+        assert_eq!(
+            resolve_state_path("/tmp/auth.state").unwrap(),
+            PathBuf::from("/tmp/auth.state")
+        );
+        assert_eq!(
+            resolve_state_path("./auth-state.json").unwrap(),
+            PathBuf::from("./auth-state.json")
+        );
+        assert_eq!(
+            resolve_state_path("../shared/auth.json").unwrap(),
+            PathBuf::from("../shared/auth.json")
+        );
+        assert_eq!(
+            resolve_state_path("auth.json.enc").unwrap(),
+            PathBuf::from("auth.json.enc")
+        );
+
+        // An existing encrypted state is found by its bare name.
+        fs::write(sessions.join("secure.json.enc"), "x").unwrap();
+        assert_eq!(
+            resolve_state_path("secure").unwrap(),
+            sessions.join("secure.json.enc")
+        );
+    }
+
+    #[test]
+    fn test_resolve_state_path_rejects_traversal() {
+        let guard = crate::test_utils::EnvGuard::new(&["HOME", "AGENT_BROWSER_NAMESPACE"]);
+        let home = tempfile::tempdir().unwrap();
+        isolated_sessions_dir(&guard, home.path());
+
+        for arg in [
+            "../../etc",
+            "../secrets",
+            "..",
+            "a/../b",
+            "..\\x",
+            ".hidden",
+        ] {
+            let err = resolve_state_path(arg).unwrap_err();
+            assert!(err.contains("Invalid state name"), "{}: {}", arg, err);
+        }
+        for arg in ["../../etc.json", "a/b.json"] {
+            assert!(
+                managed_state_path(arg).is_err(),
+                "{} should be rejected",
+                arg
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_existing_state_path_finds_listed_files() {
+        let guard = crate::test_utils::EnvGuard::new(&["HOME", "AGENT_BROWSER_NAMESPACE"]);
+        let home = tempfile::tempdir().unwrap();
+        let sessions = isolated_sessions_dir(&guard, home.path());
+        let listed = "work-ab12cd.json";
+        fs::write(sessions.join(listed), "{}").unwrap();
+
+        assert_eq!(
+            resolve_existing_state_path(listed).unwrap(),
+            sessions.join(listed)
+        );
+        // Paths with a directory component are never redirected.
+        assert_eq!(
+            resolve_existing_state_path("./work-ab12cd.json").unwrap(),
+            PathBuf::from("./work-ab12cd.json")
+        );
+    }
+
+    #[test]
+    fn test_state_rename_and_clear_by_name() {
+        let guard = crate::test_utils::EnvGuard::new(&["HOME", "AGENT_BROWSER_NAMESPACE"]);
+        let home = tempfile::tempdir().unwrap();
+        let sessions = isolated_sessions_dir(&guard, home.path());
+        fs::write(sessions.join("old.name.json"), "{}").unwrap();
+        fs::write(sessions.join("vault.json.enc"), "x").unwrap();
+        fs::write(sessions.join("work-1a2b.json"), "{}").unwrap();
+        fs::write(sessions.join("keep.json"), "{}").unwrap();
+
+        let renamed = state_rename("old.name", "new.name").unwrap();
+        assert_eq!(renamed["newName"], "new.name");
+        assert!(sessions.join("new.name.json").exists());
+        assert!(!sessions.join("old.name.json").exists());
+
+        // Encrypted states keep their extension.
+        state_rename("vault.json.enc", "vault2").unwrap();
+        assert!(sessions.join("vault2.json.enc").exists());
+
+        assert!(state_rename("keep", "../../etc").is_err());
+        assert!(state_rename("keep", "new.name").is_err());
+
+        // Clearing a name also removes states auto-saved for that session.
+        fs::write(sessions.join("work.json"), "{}").unwrap();
+        assert_eq!(state_clear(Some("work")).unwrap()["cleared"], 2);
+        assert!(sessions.join("keep.json").exists());
+        assert!(state_clear(Some("../../etc")).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_state_path() {
+        assert_eq!(encrypted_state_path("a.json", true), "a.json.enc");
+        assert_eq!(encrypted_state_path("a.json.enc", true), "a.json.enc");
+        assert_eq!(encrypted_state_path("a.json.enc", false), "a.json");
+        assert_eq!(encrypted_state_path("a.json", false), "a.json");
+    }
+
+    #[test]
+    fn test_state_encryption_key_overrides() {
+        let key = || Some("ab".repeat(32));
+        assert_eq!(state_encryption_key(None, key()).unwrap(), key());
+        assert_eq!(state_encryption_key(None, None).unwrap(), None);
+        assert_eq!(state_encryption_key(Some(false), key()).unwrap(), None);
+        assert_eq!(state_encryption_key(Some(true), key()).unwrap(), key());
+        let err = state_encryption_key(Some(true), None).unwrap_err();
+        assert!(err.contains("AGENT_BROWSER_ENCRYPTION_KEY"));
+        assert!(err.contains(STATE_ENCRYPTION_DOCS));
+        assert!(state_encryption_key(Some(true), Some(String::new())).is_err());
+    }
+
+    #[test]
+    fn test_parse_checkpoint_name() {
+        assert_eq!(
+            parse_checkpoint_name("shop.checkpoint-20261017-093000.json", "shop"),
+            Some(("20261017-093000", None))
+        );
+        assert_eq!(
+            parse_checkpoint_name("shop.checkpoint-20261017-093000-pre.pay.json.enc", "shop"),
+            Some(("20261017-093000", Some("pre.pay")))
+        );
+        for name in [
+            "shop-abc123.json",
+            "shop.json",
+            "shopping.checkpoint-20261017-093000.json",
+            "shop.checkpoint-2026101-0930000.json",
+            "shop.checkpoint-20261017-093000-.json",
+            "shop.checkpoint-20261017-093000x.json",
+            "shop.checkpoint-20261017-093000.txt",
+        ] {
+            assert_eq!(parse_checkpoint_name(name, "shop"), None, "{}", name);
+        }
+        assert_eq!(
+            checkpoint_file_name("shop", "20261017-093000", Some("cart")),
+            "shop.checkpoint-20261017-093000-cart.json"
+        );
+    }
+
+    #[test]
+    fn test_latest_checkpoint_selection() {
+        let files = [
+            "shop-d41d8c.json",
+            "shop.checkpoint-20261016-235959-cart.json",
+            "shop.checkpoint-20261017-080000.json",
+            "shop.checkpoint-20261017-093000-login.json.enc",
+            "shop.checkpoint-20261015-120000-cart.json",
+            "other.checkpoint-20261231-000000.json",
+            "notes.txt",
+        ];
+        assert_eq!(
+            latest_checkpoint(files, "shop", None),
+            Some("shop.checkpoint-20261017-093000-login.json.enc")
+        );
+        assert_eq!(
+            latest_checkpoint(files, "shop", Some("cart")),
+            Some("shop.checkpoint-20261016-235959-cart.json")
+        );
+        assert_eq!(latest_checkpoint(files, "shop", Some("missing")), None);
+        assert_eq!(
+            latest_checkpoint(files, "other", None),
+            Some("other.checkpoint-20261231-000000.json")
+        );
+        assert_eq!(latest_checkpoint(files, "nobody", None), None);
+    }
+
+    #[test]
+    fn test_checkpoint_paths_and_listing() {
+        let guard = crate::test_utils::EnvGuard::new(&["HOME", "AGENT_BROWSER_NAMESPACE"]);
+        let home = tempfile::tempdir().unwrap();
+        let sessions = isolated_sessions_dir(&guard, home.path());
+
+        let err = new_checkpoint_path(None, None).unwrap_err();
+        assert!(err.contains("--restore"));
+        assert!(new_checkpoint_path(Some("shop"), Some("../x")).is_err());
+        assert!(find_checkpoint(Some("shop"), Some("a/b")).is_err());
+
+        let path = new_checkpoint_path(Some("shop"), Some("cart")).unwrap();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert_eq!(path.parent(), Some(sessions.as_path()));
+        assert_eq!(
+            parse_checkpoint_name(&name, "shop").map(|(_, label)| label),
+            Some(Some("cart"))
+        );
+
+        assert!(find_checkpoint(Some("shop"), None)
+            .unwrap_err()
+            .contains("No checkpoints"));
+        for file in [
+            "shop.checkpoint-20261017-080000.json",
+            "shop.checkpoint-20261017-093000-cart.json",
+            "shop-abc.json",
+        ] {
+            fs::write(sessions.join(file), "{}").unwrap();
+        }
+        assert_eq!(
+            find_checkpoint(Some("shop"), None).unwrap(),
+            sessions.join("shop.checkpoint-20261017-093000-cart.json")
+        );
+
+        let listing = checkpoint_list(Some("shop")).unwrap();
+        let names: Vec<&str> = listing["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["filename"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "shop.checkpoint-20261017-093000-cart.json",
+                "shop.checkpoint-20261017-080000.json"
+            ]
+        );
+
+        // Clearing the session's auto-saves leaves its checkpoints alone.
+        state_clear(Some("shop")).unwrap();
+        assert!(find_checkpoint(Some("shop"), Some("cart")).is_ok());
     }
 }
@@ -1,12 +1,13 @@
 use serde_json::{json, Value};
 use std::sync::Arc;
+use std::time::Instant;
 
 use tokio::sync::{broadcast, watch, Mutex, RwLock};
 
 use crate::native::cdp::client::CdpClient;
 use crate::native::network;
 
-use super::timestamp_ms;
+use super::{timestamp_ms, FrameRate, StreamSettings};
 
 /// Background task that subscribes to CDP events and broadcasts screencast frames in real-time.
 /// Also handles auto-start/stop of screencast based on WebSocket client count.
@@ -24,6 +25,8 @@ pub(super) async fn cdp_event_loop(
     last_tabs: Arc<RwLock<Vec<Value>>>,
     last_engine: Arc<RwLock<String>>,
     recording: Arc<Mutex<bool>>,
+    stream_settings: Arc<Mutex<StreamSettings>>,
+    frame_rate: Arc<Mutex<FrameRate>>,
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
     loop {
@@ -59,6 +62,7 @@ pub(super) async fn cdp_event_loop(
 
                 let vw = *viewport_width.lock().await;
                 let vh = *viewport_height.lock().await;
+                let settings = *stream_settings.lock().await;
 
                 let eng = last_engine.read().await.clone();
                 let supports_screencast = eng == "chrome";
@@ -69,7 +73,7 @@ pub(super) async fn cdp_event_loop(
                             "Page.startScreencast",
                             Some(json!({
                                 "format": "jpeg",
-                                "quality": 80,
+                                "quality": settings.quality,
                                 "maxWidth": vw,
                                 "maxHeight": vh,
                                 "everyNthFrame": 1,
@@ -148,7 +152,11 @@ pub(super) async fn cdp_event_loop(
                                             ).await;
                                         }
 
-                                        if let Some(data) = evt.params.get("data").and_then(|v| v.as_str()) {
+                                        let admitted = frame_rate
+                                            .lock()
+                                            .await
+                                            .admit(Instant::now(), settings.max_fps);
+                                        if let Some(data) = evt.params.get("data").and_then(|v| v.as_str()).filter(|_| admitted) {
                                             let meta = evt.params.get("metadata");
                                             let msg = json!({
                                                 "type": "frame",
@@ -244,7 +252,8 @@ pub(super) async fn cdp_event_loop(
                             let new_vw = *viewport_width.lock().await;
                             let new_vh = *viewport_height.lock().await;
                             let viewport_changed = new_vw != vw || new_vh != vh;
-                            if client_changed || session_changed || viewport_changed {
+                            let settings_changed = *stream_settings.lock().await != settings;
+                            if client_changed || session_changed || viewport_changed || settings_changed {
                                 if supports_screencast {
                                     let _ = client_arc
                                         .send_command_no_params("Page.stopScreencast", session_id.as_deref())
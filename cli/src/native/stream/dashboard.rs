@@ -4,7 +4,7 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpListener;
 use tokio_tungstenite::tungstenite::Message;
 
-use crate::connection::get_socket_dir;
+use crate::connection::{get_socket_dir, PidRecord};
 
 use super::chat::{chat_status_json, handle_chat_request, handle_models_request};
 use super::discovery::discover_sessions;
@@ -737,10 +737,9 @@ async fn kill_session(body: &str) -> Result<String, String> {
 
     let pid_str = std::fs::read_to_string(&pid_path)
         .map_err(|_| format!("No PID file for session '{}'", session))?;
-    let pid: u32 = pid_str
-        .trim()
-        .parse()
-        .map_err(|_| format!("Invalid PID in file: {}", pid_str.trim()))?;
+    let pid = PidRecord::parse(&pid_str)
+        .map(|record| record.pid)
+        .ok_or_else(|| format!("Invalid PID in file: {}", pid_str.trim()))?;
 
     #[cfg(unix)]
     {
@@ -1,13 +1,16 @@
 use serde_json::{json, Value};
 use std::path::Path;
 
-use crate::connection::get_socket_dir;
+use crate::connection::{get_socket_dir, PidRecord};
 
 pub(super) fn discover_sessions() -> String {
-    let dir = get_socket_dir();
+    discover_sessions_in(&get_socket_dir())
+}
+
+fn discover_sessions_in(dir: &Path) -> String {
     let mut sessions = Vec::new();
 
-    if let Ok(entries) = std::fs::read_dir(&dir) {
+    if let Ok(entries) = std::fs::read_dir(dir) {
         for entry in entries.flatten() {
             let name = entry.file_name();
             let name_str = name.to_string_lossy();
@@ -27,7 +30,7 @@ pub(super) fn discover_sessions() -> String {
                                 .ok()
                                 .filter(|s| !s.trim().is_empty());
 
-                            let extensions = read_extensions_metadata(&dir, session);
+                            let extensions = read_extensions_metadata(dir, session);
 
                             let mut entry = json!({
                                 "session": session,
@@ -102,9 +105,9 @@ fn is_process_alive(pid_path: &Path) -> bool {
         Ok(s) => s,
         Err(_) => return false,
     };
-    let pid: u32 = match pid_str.trim().parse() {
-        Ok(p) => p,
-        Err(_) => return false,
+    let pid = match PidRecord::parse(&pid_str) {
+        Some(record) => record.pid,
+        None => return false,
     };
     #[cfg(unix)]
     {
@@ -116,3 +119,24 @@ fn is_process_alive(pid_path: &Path) -> bool {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_reads_pid_file_with_start_time() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("work.stream"), "9333").unwrap();
+        let record = PidRecord {
+            pid: std::process::id(),
+            started: Some(123456),
+        };
+        std::fs::write(dir.path().join("work.pid"), record.to_string()).unwrap();
+
+        let sessions: Vec<Value> = serde_json::from_str(&discover_sessions_in(dir.path())).unwrap();
+        assert_eq!(sessions.len(), 1, "{:?}", sessions);
+        assert_eq!(sessions[0]["session"], "work");
+        assert_eq!(sessions[0]["port"], 9333);
+    }
+}
@@ -9,7 +9,9 @@ pub use cdp_loop::{ack_screencast_frame, start_screencast, stop_screencast};
 pub use dashboard::run_dashboard_server;
 
 use serde_json::{json, Value};
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tokio::net::TcpListener;
 use tokio::sync::{broadcast, watch, Mutex, Notify, RwLock};
@@ -42,6 +44,60 @@ impl Default for FrameMetadata {
     }
 }
 
+/// Screencast settings from `stream start --quality/--fps`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamSettings {
+    /// JPEG quality passed to Page.startScreencast, 1-100.
+    pub quality: u8,
+    /// Cap on frames broadcast per second; None forwards every frame.
+    pub max_fps: Option<u32>,
+}
+
+impl Default for StreamSettings {
+    fn default() -> Self {
+        Self {
+            quality: 80,
+            max_fps: None,
+        }
+    }
+}
+
+/// Frames broadcast over the last second, and the throttle for `--fps`.
+#[derive(Debug, Default)]
+pub(crate) struct FrameRate {
+    sent: VecDeque<Instant>,
+}
+
+impl FrameRate {
+    /// Record a frame sent at `now` unless it arrives sooner than `max_fps`
+    /// allows after the previous one. Returns whether to send it.
+    pub(crate) fn admit(&mut self, now: Instant, max_fps: Option<u32>) -> bool {
+        if let (Some(fps), Some(last)) = (max_fps.filter(|fps| *fps > 0), self.sent.back()) {
+            if now.saturating_duration_since(*last) < Duration::from_secs(1) / fps {
+                return false;
+            }
+        }
+        self.sent.push_back(now);
+        self.prune(now);
+        true
+    }
+
+    pub(crate) fn fps(&mut self, now: Instant) -> usize {
+        self.prune(now);
+        self.sent.len()
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .sent
+            .front()
+            .is_some_and(|t| now.saturating_duration_since(*t) >= Duration::from_secs(1))
+        {
+            self.sent.pop_front();
+        }
+    }
+}
+
 pub struct StreamServer {
     port: u16,
     session_name: String,
@@ -58,6 +114,8 @@ pub struct StreamServer {
     last_engine: Arc<RwLock<String>>,
     last_frame: Arc<RwLock<Option<String>>>,
     recording: Arc<Mutex<bool>>,
+    settings: Arc<Mutex<StreamSettings>>,
+    frame_rate: Arc<Mutex<FrameRate>>,
     shutdown_tx: watch::Sender<bool>,
     accept_task: Mutex<Option<tokio::task::JoinHandle<()>>>,
     cdp_task: Mutex<Option<tokio::task::JoinHandle<()>>>,
@@ -127,6 +185,32 @@ impl StreamServer {
         (w, h)
     }
 
+    /// Change the screencast quality and frame cap. An active screencast is
+    /// restarted so the new quality takes effect.
+    pub async fn set_settings(&self, settings: StreamSettings) {
+        let mut guard = self.settings.lock().await;
+        if *guard == settings {
+            return;
+        }
+        *guard = settings;
+        drop(guard);
+        self.client_notify.notify_one();
+    }
+
+    pub async fn settings(&self) -> StreamSettings {
+        *self.settings.lock().await
+    }
+
+    /// Number of WebSocket clients currently connected.
+    pub async fn client_count(&self) -> usize {
+        *self.client_count.lock().await
+    }
+
+    /// Frames broadcast to clients over the last second.
+    pub async fn current_fps(&self) -> usize {
+        self.frame_rate.lock().await.fps(Instant::now())
+    }
+
     /// Override the cached screencast state for explicit CLI start/stop commands.
     pub async fn set_screencasting(&self, active: bool) {
         let mut guard = self.screencasting.lock().await;
@@ -187,6 +271,8 @@ impl StreamServer {
         let last_engine = Arc::new(RwLock::new("chrome".to_string()));
         let last_frame = Arc::new(RwLock::new(None::<String>));
         let recording = Arc::new(Mutex::new(false));
+        let settings = Arc::new(Mutex::new(StreamSettings::default()));
+        let frame_rate = Arc::new(Mutex::new(FrameRate::default()));
         let (shutdown_tx, shutdown_rx) = watch::channel(false);
 
         let frame_tx_clone = frame_tx.clone();
@@ -237,6 +323,8 @@ impl StreamServer {
         let last_tabs_bg = last_tabs.clone();
         let last_engine_bg = last_engine.clone();
         let recording_bg = recording.clone();
+        let settings_bg = settings.clone();
+        let frame_rate_bg = frame_rate.clone();
         let cdp_task = tokio::spawn(async move {
             cdp_loop::cdp_event_loop(
                 frame_tx_bg,
@@ -251,6 +339,8 @@ impl StreamServer {
                 last_tabs_bg,
                 last_engine_bg,
                 recording_bg,
+                settings_bg,
+                frame_rate_bg,
                 shutdown_rx,
             )
             .await;
@@ -272,6 +362,8 @@ impl StreamServer {
                 last_engine,
                 last_frame,
                 recording,
+                settings,
+                frame_rate,
                 shutdown_tx,
                 accept_task: Mutex::new(Some(accept_task)),
                 cdp_task: Mutex::new(Some(cdp_task)),
@@ -455,6 +547,30 @@ pub fn is_allowed_origin(origin: Option<&str>) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_frame_rate_counts_last_second() {
+        let start = Instant::now();
+        let mut rate = FrameRate::default();
+        for i in 0..5 {
+            assert!(rate.admit(start + Duration::from_millis(i * 100), None));
+        }
+        assert_eq!(rate.fps(start + Duration::from_millis(500)), 5);
+        assert_eq!(rate.fps(start + Duration::from_millis(1250)), 2);
+        assert_eq!(rate.fps(start + Duration::from_secs(3)), 0);
+    }
+
+    #[test]
+    fn test_frame_rate_throttles_to_max_fps() {
+        let start = Instant::now();
+        let mut rate = FrameRate::default();
+        let admitted = (0..60)
+            .filter(|i| rate.admit(start + Duration::from_millis(i * 16), Some(10)))
+            .count();
+        // 60 frames over ~950ms at 16ms spacing, capped at one per 100ms.
+        assert_eq!(admitted, 9);
+        assert_eq!(rate.fps(start + Duration::from_millis(950)), 9);
+    }
+
     #[test]
     fn test_allowed_origin_none() {
         assert!(is_allowed_origin(None));
@@ -2,6 +2,7 @@ use serde_json::{json, Value};
 use std::path::PathBuf;
 
 use super::cdp::client::CdpClient;
+use crate::atomic_write::write_atomic;
 
 const MAX_PROFILE_EVENTS: usize = 5_000_000;
 
@@ -23,10 +24,74 @@ const DEFAULT_PROFILER_CATEGORIES: &[&str] = &[
     "toplevel",
 ];
 
+/// Categories `trace start` adds on top of Chrome's defaults for each toggle.
+const SCREENSHOT_CATEGORIES: &[&str] = &["disabled-by-default-devtools.screenshot"];
+const SNAPSHOT_CATEGORIES: &[&str] = &[
+    "disabled-by-default-devtools.timeline.layers",
+    "disabled-by-default-devtools.timeline.picture",
+];
+const SOURCE_CATEGORIES: &[&str] = &[
+    "disabled-by-default-devtools.v8-source-rundown",
+    "disabled-by-default-devtools.v8-source-rundown-sources",
+];
+
+/// `trace start` toggles. Screenshots and snapshots make traces far larger,
+/// which matters when saving over a remote CDP connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceOptions {
+    pub screenshots: bool,
+    pub snapshots: bool,
+    pub sources: bool,
+    pub title: Option<String>,
+}
+
+impl Default for TraceOptions {
+    fn default() -> Self {
+        Self {
+            screenshots: true,
+            snapshots: true,
+            sources: false,
+            title: None,
+        }
+    }
+}
+
+impl TraceOptions {
+    pub fn from_command(cmd: &Value) -> Self {
+        let defaults = Self::default();
+        let flag =
+            |key: &str, default: bool| cmd.get(key).and_then(|v| v.as_bool()).unwrap_or(default);
+        Self {
+            screenshots: flag("screenshots", defaults.screenshots),
+            snapshots: flag("snapshots", defaults.snapshots),
+            sources: flag("sources", defaults.sources),
+            title: cmd.get("title").and_then(|v| v.as_str()).map(String::from),
+        }
+    }
+
+    /// `*` keeps every enabled-by-default category; the toggles add the
+    /// disabled-by-default ones.
+    pub fn categories(&self) -> Vec<&'static str> {
+        let mut cats = vec!["*"];
+        for (on, extra) in [
+            (self.screenshots, SCREENSHOT_CATEGORIES),
+            (self.snapshots, SNAPSHOT_CATEGORIES),
+            (self.sources, SOURCE_CATEGORIES),
+        ] {
+            if on {
+                cats.extend_from_slice(extra);
+            }
+        }
+        cats
+    }
+}
+
 pub struct TracingState {
     pub active: bool,
     pub events: Vec<Value>,
     pub events_dropped: bool,
+    /// `trace start --title`, written into the saved trace's metadata.
+    pub title: Option<String>,
 }
 
 impl TracingState {
@@ -35,6 +100,7 @@ impl TracingState {
             active: false,
             events: Vec::new(),
             events_dropped: false,
+            title: None,
         }
     }
 }
@@ -43,6 +109,7 @@ pub async fn trace_start(
     client: &CdpClient,
     session_id: &str,
     tracing_state: &mut TracingState,
+    options: TraceOptions,
 ) -> Result<Value, String> {
     if tracing_state.active {
         return Err("Tracing already active".to_string());
@@ -54,6 +121,7 @@ pub async fn trace_start(
             Some(json!({
                 "traceConfig": {
                     "recordMode": "recordContinuously",
+                    "includedCategories": options.categories(),
                 },
                 "transferMode": "ReturnAsStream",
             })),
@@ -64,8 +132,14 @@ pub async fn trace_start(
     tracing_state.active = true;
     tracing_state.events.clear();
     tracing_state.events_dropped = false;
-
-    Ok(json!({ "started": true }))
+    tracing_state.title = options.title;
+
+    Ok(json!({
+        "started": true,
+        "screenshots": options.screenshots,
+        "snapshots": options.snapshots,
+        "sources": options.sources,
+    }))
 }
 
 pub async fn trace_stop(
@@ -171,13 +245,17 @@ pub async fn trace_stop(
         }
     };
 
-    let trace_json = json!({ "traceEvents": trace_events });
+    let mut trace_json = json!({ "traceEvents": trace_events });
+    if let Some(title) = tracing_state.title.take() {
+        trace_json["metadata"] = json!({ "title": title });
+    }
     let json_str = serde_json::to_string(&trace_json)
         .map_err(|e| format!("Failed to serialize trace: {}", e))?;
-    std::fs::write(&save_path, json_str)
+    let size = json_str.len();
+    write_atomic(&save_path, json_str)
         .map_err(|e| format!("Failed to write trace to {}: {}", save_path, e))?;
 
-    Ok(json!({ "path": save_path, "eventCount": trace_events.len() }))
+    Ok(json!({ "path": save_path, "eventCount": trace_events.len(), "size": size }))
 }
 
 pub async fn profiler_start(
@@ -297,7 +375,7 @@ pub async fn profiler_stop(
 
     let json_str = serde_json::to_string(&profile)
         .map_err(|e| format!("Failed to serialize profile: {}", e))?;
-    std::fs::write(&save_path, json_str)
+    write_atomic(&save_path, json_str)
         .map_err(|e| format!("Failed to write profile to {}: {}", save_path, e))?;
 
     let event_count = events.len();
@@ -371,3 +449,48 @@ fn get_profiles_dir() -> PathBuf {
         std::env::temp_dir().join("agent-browser").join("profiles")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_options_from_command() {
+        assert_eq!(
+            TraceOptions::from_command(&json!({})),
+            TraceOptions::default()
+        );
+        let opts = TraceOptions::from_command(&json!({
+            "screenshots": false,
+            "sources": true,
+            "title": "checkout",
+        }));
+        assert!(!opts.screenshots && opts.snapshots && opts.sources);
+        assert_eq!(opts.title.as_deref(), Some("checkout"));
+    }
+
+    #[test]
+    fn test_trace_options_categories() {
+        let cats = TraceOptions::default().categories();
+        assert_eq!(cats[0], "*");
+        assert!(cats.contains(&"disabled-by-default-devtools.screenshot"));
+        assert!(cats.contains(&"disabled-by-default-devtools.timeline.layers"));
+        assert!(!cats.iter().any(|c| c.contains("source-rundown")));
+
+        let bare = TraceOptions {
+            screenshots: false,
+            snapshots: false,
+            sources: false,
+            title: None,
+        };
+        assert_eq!(bare.categories(), vec!["*"]);
+
+        let sources = TraceOptions {
+            sources: true,
+            ..bare
+        };
+        assert!(sources
+            .categories()
+            .contains(&"disabled-by-default-devtools.v8-source-rundown-sources"));
+    }
+}
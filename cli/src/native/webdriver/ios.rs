@@ -187,6 +187,36 @@ pub fn select_device(device_name: Option<&str>, udid: Option<&str>) -> Result<Io
     Ok((*iphone_devices.last().unwrap()).clone())
 }
 
+/// The device `query` names: an exact UDID, else the first device whose
+/// name contains it, ignoring case.
+pub fn find_device<'a>(devices: &'a [IosDevice], query: &str) -> Option<&'a IosDevice> {
+    let lower = query.to_lowercase();
+    devices
+        .iter()
+        .find(|d| d.udid.eq_ignore_ascii_case(query))
+        .or_else(|| {
+            devices
+                .iter()
+                .find(|d| d.name.to_lowercase().contains(&lower))
+        })
+}
+
+/// Current simulator state (`Shutdown`, `Booting`, `Booted`, ...).
+pub fn simulator_state(udid: &str) -> Result<String, String> {
+    list_simulators()?
+        .into_iter()
+        .find(|d| d.udid == udid)
+        .map(|d| d.state)
+        .ok_or_else(|| format!("Simulator {} disappeared while booting", udid))
+}
+
+/// Append `state` unless it repeats the last one recorded.
+pub fn push_state(transitions: &mut Vec<String>, state: &str) {
+    if transitions.last().map(String::as_str) != Some(state) {
+        transitions.push(state.to_string());
+    }
+}
+
 pub fn to_device_json(devices: &[IosDevice]) -> Value {
     let list: Vec<Value> = devices
         .iter()
@@ -232,4 +262,31 @@ mod tests {
         let json = to_device_json(&devices);
         assert!(json.get("devices").unwrap().as_array().unwrap().len() == 1);
     }
+
+    #[test]
+    fn test_find_device_by_udid_then_name() {
+        let device = |name: &str, udid: &str| IosDevice {
+            name: name.to_string(),
+            udid: udid.to_string(),
+            state: "Shutdown".to_string(),
+            runtime: "iOS-18".to_string(),
+            is_real: false,
+        };
+        let devices = vec![
+            device("iPhone 15 Pro Max", "AAA-1"),
+            device("iPhone 15 Pro", "BBB-2"),
+        ];
+        assert_eq!(find_device(&devices, "bbb-2").unwrap().udid, "BBB-2");
+        assert_eq!(find_device(&devices, "15 pro").unwrap().udid, "AAA-1");
+        assert!(find_device(&devices, "iPad").is_none());
+    }
+
+    #[test]
+    fn test_push_state_skips_repeats() {
+        let mut transitions = vec!["Shutdown".to_string()];
+        for state in ["Booting", "Booting", "Booted"] {
+            push_state(&mut transitions, state);
+        }
+        assert_eq!(transitions, vec!["Shutdown", "Booting", "Booted"]);
+    }
 }
@@ -0,0 +1,246 @@
+//! Per-session scratchpad for `note add/list/clear`.
+//!
+//! Notes are kept client-side as a JSON array in `<session>.notes.json` next
+//! to the session's pid file, so they survive across invocations without a
+//! round trip to the daemon. The file is capped at [`MAX_NOTES_BYTES`] and is
+//! removed when the session is closed or its daemon is found dead.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::atomic_write::write_atomic;
+use crate::color;
+use crate::connection::get_socket_dir;
+use crate::humanize::humanize_timestamp_ms;
+
+/// Largest notes file a session may hold, as serialized.
+pub const MAX_NOTES_BYTES: usize = 64 * 1024;
+
+const NOTE_USAGE: &str = "note <add <text>|list|clear>";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    /// Epoch milliseconds.
+    pub ts: i64,
+    pub text: String,
+}
+
+pub fn notes_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.notes.json", session))
+}
+
+/// All notes, oldest first. A missing or unreadable file reads as empty.
+pub fn read_notes(path: &Path) -> Vec<Note> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Append a note, refusing it if the file would grow past
+/// [`MAX_NOTES_BYTES`]. Returns the number of notes now stored.
+pub fn add(path: &Path, text: &str, ts: i64) -> Result<usize, String> {
+    let mut notes = read_notes(path);
+    notes.push(Note {
+        ts,
+        text: text.to_string(),
+    });
+    let contents = serde_json::to_string(&notes).map_err(|e| e.to_string())?;
+    if contents.len() > MAX_NOTES_BYTES {
+        return Err(format!(
+            "Notes are limited to {} KB per session; run `agent-browser note clear` to make room",
+            MAX_NOTES_BYTES / 1024
+        ));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    write_atomic(path, contents).map_err(|e| format!("Failed to save note: {}", e))?;
+    Ok(notes.len())
+}
+
+/// Delete the notes file. Returns how many notes it held.
+pub fn clear(path: &Path) -> io::Result<usize> {
+    let count = read_notes(path).len();
+    match fs::remove_file(path) {
+        Ok(()) => Ok(count),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Drop a session's notes when the session ends (`close`, or a dead daemon
+/// cleaned up by the session walk).
+pub fn remove(session: &str) {
+    let _ = fs::remove_file(notes_path(session));
+}
+
+fn fail(message: String, json_mode: bool) -> ! {
+    if json_mode {
+        println!("{}", json!({ "success": false, "error": message }));
+    } else {
+        eprintln!("{} {}", color::error_indicator(), message);
+    }
+    exit(1);
+}
+
+/// `agent-browser note add <text>`, `note list`, `note clear`.
+pub fn run_note(args: &[String], session: &str, json_mode: bool) {
+    let path = notes_path(session);
+    match args.get(1).map(|s| s.as_str()) {
+        Some("add") => {
+            let text = args[2..].join(" ");
+            if text.trim().is_empty() {
+                fail(
+                    format!("Missing note text. Usage: {}", NOTE_USAGE),
+                    json_mode,
+                );
+            }
+            let ts = chrono::Utc::now().timestamp_millis();
+            let count = add(&path, &text, ts).unwrap_or_else(|e| fail(e, json_mode));
+            if json_mode {
+                println!(
+                    "{}",
+                    json!({ "success": true, "data": { "session": session, "ts": ts, "text": text, "count": count } })
+                );
+            } else {
+                println!("{} Note {} saved", color::success_indicator(), count);
+            }
+        }
+        Some("list") if args.len() == 2 => {
+            let notes = read_notes(&path);
+            if json_mode {
+                println!(
+                    "{}",
+                    json!({ "success": true, "data": { "session": session, "notes": notes } })
+                );
+                return;
+            }
+            if notes.is_empty() {
+                println!("No notes for session {}", session);
+                return;
+            }
+            let width = notes.len().to_string().len();
+            for (i, note) in notes.iter().enumerate() {
+                let when = humanize_timestamp_ms(note.ts).unwrap_or_default();
+                println!(
+                    "{:>width$}  {}  {}",
+                    i + 1,
+                    color::dim(&format!("{:<10}", when)),
+                    note.text,
+                    width = width
+                );
+            }
+        }
+        Some("clear") if args.len() == 2 => {
+            let cleared = clear(&path)
+                .unwrap_or_else(|e| fail(format!("Failed to clear notes: {}", e), json_mode));
+            if json_mode {
+                println!(
+                    "{}",
+                    json!({ "success": true, "data": { "session": session, "cleared": cleared } })
+                );
+            } else {
+                println!("{} Cleared {} notes", color::success_indicator(), cleared);
+            }
+        }
+        Some("list") | Some("clear") => fail(
+            format!("Too many arguments. Usage: {}", NOTE_USAGE),
+            json_mode,
+        ),
+        Some(other) => fail(
+            format!("Unknown note subcommand '{}'. Usage: {}", other, NOTE_USAGE),
+            json_mode,
+        ),
+        None => fail(format!("Usage: {}", NOTE_USAGE), json_mode),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_notes() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("default.notes.json");
+        (dir, path)
+    }
+
+    #[test]
+    fn test_add_and_list_keep_order() {
+        let (_dir, path) = temp_notes();
+        assert!(read_notes(&path).is_empty());
+        assert_eq!(add(&path, "logged in as user X", 2).unwrap(), 1);
+        assert_eq!(add(&path, "cart id = 123", 1).unwrap(), 2);
+        let notes = read_notes(&path);
+        assert_eq!(
+            notes,
+            vec![
+                Note {
+                    ts: 2,
+                    text: "logged in as user X".to_string()
+                },
+                Note {
+                    ts: 1,
+                    text: "cart id = 123".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_enforces_size_cap() {
+        let (_dir, path) = temp_notes();
+        let chunk = "x".repeat(1000);
+        let mut added = 0;
+        let err = loop {
+            match add(&path, &chunk, added) {
+                Ok(_) => added += 1,
+                Err(e) => break e,
+            }
+        };
+        assert!(err.contains("64 KB"), "{}", err);
+        assert!(added > 0);
+        // The rejected note left the stored ones untouched.
+        assert_eq!(read_notes(&path).len(), added as usize);
+        assert!(fs::metadata(&path).unwrap().len() as usize <= MAX_NOTES_BYTES);
+        // A small note still fits after clearing.
+        assert_eq!(clear(&path).unwrap(), added as usize);
+        assert_eq!(add(&path, "fresh", 0).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_clear_and_unreadable_file() {
+        let (_dir, path) = temp_notes();
+        assert_eq!(clear(&path).unwrap(), 0);
+        add(&path, "a", 1).unwrap();
+        add(&path, "b", 2).unwrap();
+        assert_eq!(clear(&path).unwrap(), 2);
+        assert!(!path.exists());
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "[{\"ts\":1,\"te").unwrap();
+        assert!(read_notes(&path).is_empty());
+        assert_eq!(add(&path, "recovered", 3).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_remove_on_session_end() {
+        let _guard = crate::test_utils::EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = tempfile::tempdir().unwrap();
+        _guard.set("AGENT_BROWSER_SOCKET_DIR", dir.path().to_str().unwrap());
+
+        add(&notes_path("work"), "keep me", 1).unwrap();
+        add(&notes_path("other"), "unrelated", 1).unwrap();
+        assert_eq!(notes_path("work"), dir.path().join("work.notes.json"));
+
+        remove("work");
+        assert!(read_notes(&notes_path("work")).is_empty());
+        assert_eq!(read_notes(&notes_path("other")).len(), 1);
+    }
+}
@@ -4,6 +4,7 @@
 //! Core agent-browser keeps ownership of browser automation, policy checks, and
 //! redaction-sensitive flows; credential plugins only resolve secrets on demand.
 
+use crate::atomic_write::write_atomic;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::fs;
@@ -655,7 +656,7 @@ fn write_config_json(path: &Path, value: &serde_json::Value) -> Result<(), Strin
     }
     let raw = serde_json::to_string_pretty(value)
         .map_err(|e| format!("Failed to encode config JSON: {}", e))?;
-    fs::write(path, format!("{}\n", raw))
+    write_atomic(path, format!("{}\n", raw))
         .map_err(|e| format!("Failed to write config {}: {}", path.display(), e))
 }
 
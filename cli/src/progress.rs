@@ -0,0 +1,190 @@
+//! `--progress jsonl`: incremental step events for `batch` and `act`.
+//!
+//! Orchestrators running long step lists get one JSON line on stderr when
+//! each step starts and ends, while stdout still carries only the final
+//! aggregate result. All events go through [`ProgressEmitter`], so another
+//! format or destination only has to extend it.
+
+use std::io::{self, Write};
+use std::time::Instant;
+
+use serde_json::{json, Value};
+
+use crate::commands::ParseError;
+use crate::connection::Response;
+
+pub const PROGRESS_FORMATS: &[&str] = &["jsonl"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressFormat {
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl ProgressFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "jsonl" => Ok(ProgressFormat::Jsonl),
+            other => Err(format!(
+                "Invalid --progress format '{}'. Valid formats: {}",
+                other,
+                PROGRESS_FORMATS.join(", ")
+            )),
+        }
+    }
+}
+
+/// Why a step failed, as reported in `step_end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepError {
+    /// `command_failed` when the daemon rejected the command,
+    /// `connection_failed` when it could not be reached, or the parse error
+    /// type (`unknown_command`, `invalid_value`, ...) for a malformed step.
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl StepError {
+    pub fn from_result(result: &Result<Response, String>) -> Option<Self> {
+        match result {
+            Ok(resp) if resp.success => None,
+            Ok(resp) => Some(StepError {
+                code: "command_failed",
+                message: resp
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| "Command failed".to_string()),
+            }),
+            Err(e) => Some(StepError {
+                code: "connection_failed",
+                message: e.clone(),
+            }),
+        }
+    }
+
+    pub fn from_parse_error(error: &ParseError) -> Self {
+        StepError {
+            code: error.error_type(),
+            message: error.format(),
+        }
+    }
+}
+
+/// Writes step events in the requested format, or nothing when progress
+/// was not requested.
+pub struct ProgressEmitter<W: Write = io::Stderr> {
+    format: Option<ProgressFormat>,
+    out: W,
+    current: Option<(usize, String, Instant)>,
+}
+
+impl ProgressEmitter {
+    pub fn stderr(format: Option<ProgressFormat>) -> Self {
+        Self::new(format, io::stderr())
+    }
+}
+
+impl<W: Write> ProgressEmitter<W> {
+    pub fn new(format: Option<ProgressFormat>, out: W) -> Self {
+        Self {
+            format,
+            out,
+            current: None,
+        }
+    }
+
+    /// Start step `index` (0-based position in the input).
+    pub fn step_start(&mut self, index: usize, action: &str) {
+        self.current = Some((index, action.to_string(), Instant::now()));
+        self.emit(json!({ "event": "step_start", "index": index, "action": action }));
+    }
+
+    /// Finish the step most recently started.
+    pub fn step_end(&mut self, error: Option<&StepError>) {
+        let Some((index, action, started)) = self.current.take() else {
+            return;
+        };
+        let mut event = json!({
+            "event": "step_end",
+            "index": index,
+            "action": action,
+            "success": error.is_none(),
+            "durationMs": started.elapsed().as_millis() as u64,
+        });
+        if let Some(error) = error {
+            event["error"] = json!({ "code": error.code, "message": error.message });
+        }
+        self.emit(event);
+    }
+
+    fn emit(&mut self, event: Value) {
+        match self.format {
+            Some(ProgressFormat::Jsonl) => {
+                // Flush per line so a reader sees each event as it happens.
+                let _ = writeln!(self.out, "{}", event);
+                let _ = self.out.flush();
+            }
+            None => {}
+        }
+    }
+
+    #[cfg(test)]
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(buf: &[u8]) -> Vec<Value> {
+        String::from_utf8_lossy(buf)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(ProgressFormat::parse("jsonl"), Ok(ProgressFormat::Jsonl));
+        let err = ProgressFormat::parse("xml").unwrap_err();
+        assert!(err.contains("Valid formats: jsonl"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_step_events() {
+        let mut progress = ProgressEmitter::new(Some(ProgressFormat::Jsonl), Vec::new());
+        progress.step_start(0, "click");
+        progress.step_end(None);
+        progress.step_start(1, "fill");
+        progress.step_end(Some(&StepError {
+            code: "command_failed",
+            message: "Element not found".to_string(),
+        }));
+        // An end without a start is ignored.
+        progress.step_end(None);
+
+        let events = events(&progress.into_inner());
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events[0],
+            json!({ "event": "step_start", "index": 0, "action": "click" })
+        );
+        assert_eq!(events[1]["event"], "step_end");
+        assert_eq!(events[1]["success"], true);
+        assert!(events[1]["durationMs"].is_u64());
+        assert!(events[1].get("error").is_none());
+        assert_eq!(events[3]["index"], 1);
+        assert_eq!(events[3]["success"], false);
+        assert_eq!(events[3]["error"]["code"], "command_failed");
+    }
+
+    #[test]
+    fn test_disabled_emitter_writes_nothing() {
+        let mut progress = ProgressEmitter::new(None, Vec::new());
+        progress.step_start(0, "click");
+        progress.step_end(None);
+        assert!(progress.into_inner().is_empty());
+    }
+}
@@ -0,0 +1,495 @@
+//! Redaction rules for diagnostics that leave the machine (bug reports,
+//! error messages that echo URLs) and for commands echoed back to the user.
+//! Keep every rule here so callers don't drift apart on what counts as a
+//! secret.
+
+use serde_json::Value;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Name fragments that mark an env var, config key, header, or query
+/// parameter as secret. Matched against the lowercased name with `_` and
+/// `-` removed, so `BROWSERBASE_API_KEY`, `apiKey`, and `x-api-key` all hit.
+const SECRET_NAME_PARTS: &[&str] = &[
+    "apikey",
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "cookie",
+    "authorization",
+    "credential",
+    "encryptionkey",
+    "accesskey",
+    "privatekey",
+    "sessionkey",
+    "signature",
+];
+
+/// Whether a name (env var, JSON key, header, query parameter) holds a secret.
+pub fn is_secret_name(name: &str) -> bool {
+    let normalized: String = name
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    normalized == "auth"
+        || normalized == "key"
+        || normalized.ends_with("auth")
+        || SECRET_NAME_PARTS
+            .iter()
+            .any(|part| normalized.contains(part))
+}
+
+/// Strip credentials from a URL: userinfo is dropped and every query value is
+/// replaced with `REDACTED` (unbracketed so it survives URL encoding), since
+/// providers put API keys and session tokens there under arbitrary names.
+/// Strings that don't parse as URLs are returned unchanged.
+pub fn redact_url(raw: &str) -> String {
+    let Ok(mut url) = url::Url::parse(raw) else {
+        return raw.to_string();
+    };
+    if url.cannot_be_a_base() {
+        return raw.to_string();
+    }
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    if url.query().is_some() {
+        let keys: Vec<String> = url.query_pairs().map(|(k, _)| k.into_owned()).collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(keys.iter().map(|k| (k.as_str(), "REDACTED")));
+    }
+    url.to_string()
+}
+
+/// Redact free text such as a log line: URLs are passed through
+/// [`redact_url`], and `Name: value` / `name=value` pairs with a secret name
+/// have their value replaced.
+pub fn redact_text(text: &str) -> String {
+    let trimmed = text.trim_start();
+    if let Some((name, _)) = trimmed.split_once(": ") {
+        if !name.contains(' ') && is_secret_name(name) {
+            let indent = &text[..text.len() - trimmed.len()];
+            return format!("{}{}: {}", indent, name, REDACTED);
+        }
+    }
+
+    text.split(' ')
+        .map(redact_word)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn redact_word(word: &str) -> String {
+    if let Some(sep) = word.find("://") {
+        let start = word[..sep]
+            .rfind(|c: char| !c.is_ascii_alphabetic())
+            .map_or(0, |i| i + 1);
+        let end = word
+            .trim_end_matches(['"', '\'', ')', ']', ',', ';'])
+            .len()
+            .max(start);
+        return format!(
+            "{}{}{}",
+            &word[..start],
+            redact_url(&word[start..end]),
+            &word[end..]
+        );
+    }
+    match word.split_once('=') {
+        Some((name, value)) if !value.is_empty() && is_secret_name(name) => {
+            format!("{}={}", name, REDACTED)
+        }
+        _ => word.to_string(),
+    }
+}
+
+/// Redact a JSON value in place: values under secret-named keys are replaced
+/// and every other string goes through [`redact_text`].
+pub fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if is_secret_name(key) && !v.is_null() {
+                    *v = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::String(s) => *s = redact_text(s),
+        _ => {}
+    }
+}
+
+/// Replacement for secrets in echoed commands (batch/act step lines, error
+/// messages).
+pub const COMMAND_REDACTED: &str = "***";
+
+/// Default `redactPatterns`: a cookie name, storage key, or header name
+/// containing one of these (case-insensitive, ignoring `_` and `-`) has its
+/// value hidden when the command is echoed.
+pub const DEFAULT_REDACT_PATTERNS: &[&str] = &[
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "session",
+    "sid",
+    "auth",
+    "jwt",
+    "apikey",
+    "credential",
+    "bearer",
+];
+
+/// Secrets shorter than this are only hidden when they make up a whole
+/// word, so a one-letter cookie value does not mangle unrelated text.
+const MIN_SUBSTRING_SECRET_LEN: usize = 4;
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Hides the secrets a command carries, as marked by
+/// [`sensitive_fields`](crate::commands::sensitive_fields), wherever the
+/// command is echoed back to the user.
+pub struct CommandRedactor {
+    patterns: Vec<String>,
+}
+
+impl CommandRedactor {
+    /// `patterns` replaces [`DEFAULT_REDACT_PATTERNS`] when given.
+    pub fn new(patterns: Option<&[String]>) -> Self {
+        let patterns: Vec<String> = match patterns {
+            Some(patterns) => patterns.iter().map(|p| normalize_name(p)).collect(),
+            None => DEFAULT_REDACT_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        };
+        Self {
+            patterns: patterns.into_iter().filter(|p| !p.is_empty()).collect(),
+        }
+    }
+
+    /// Whether a cookie name, storage key, or header name matches a pattern.
+    pub fn is_secret_key(&self, name: &str) -> bool {
+        let name = normalize_name(name);
+        self.patterns.iter().any(|p| name.contains(p.as_str()))
+    }
+
+    /// The secret values in `cmd`, for hiding them in argv and messages.
+    pub fn secrets(&self, cmd: &Value) -> Secrets {
+        let mut values = Vec::new();
+        self.visit(cmd, &mut |v| {
+            if !v.is_empty() {
+                values.push(v.to_string());
+            }
+        });
+        // Longest first so a secret containing another is replaced whole.
+        values.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        values.dedup();
+        Secrets { values }
+    }
+
+    fn visit(&self, cmd: &Value, f: &mut dyn FnMut(&str)) {
+        use crate::commands::{sensitive_fields, SensitiveField};
+
+        let action = cmd.get("action").and_then(|v| v.as_str()).unwrap_or("");
+        for field in sensitive_fields(action) {
+            match *field {
+                SensitiveField::Always(key) => {
+                    if let Some(v) = cmd.get(key).and_then(|v| v.as_str()) {
+                        f(v);
+                    }
+                }
+                SensitiveField::NamedValue { list, name, value } => {
+                    let targets: Vec<&Value> = match list {
+                        Some(list) => match cmd.get(list) {
+                            Some(Value::Array(items)) => items.iter().collect(),
+                            _ => Vec::new(),
+                        },
+                        None => vec![cmd],
+                    };
+                    for target in targets {
+                        let named = target
+                            .get(name)
+                            .and_then(|n| n.as_str())
+                            .is_some_and(|n| self.is_secret_key(n));
+                        if let Some(v) = target.get(value).and_then(|v| v.as_str()) {
+                            if named {
+                                f(v);
+                            }
+                        }
+                    }
+                }
+                SensitiveField::KeyedValues(key) => {
+                    if let Some(Value::Object(map)) = cmd.get(key) {
+                        for (name, v) in map {
+                            if let Some(v) = v.as_str().filter(|_| self.is_secret_key(name)) {
+                                f(v);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Secret values taken from one command by [`CommandRedactor::secrets`].
+#[derive(Debug, Default)]
+pub struct Secrets {
+    values: Vec<String>,
+}
+
+impl Secrets {
+    /// Hide the secrets in free text such as an error message.
+    pub fn text(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for secret in &self.values {
+            if out == *secret {
+                return COMMAND_REDACTED.to_string();
+            }
+            if secret.len() >= MIN_SUBSTRING_SECRET_LEN {
+                out = out.replace(secret.as_str(), COMMAND_REDACTED);
+            }
+        }
+        out
+    }
+
+    /// Hide the secrets in the argv a command was parsed from.
+    pub fn words(&self, words: &[String]) -> Vec<String> {
+        words.iter().map(|w| self.text(w)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_secret_names() {
+        for name in [
+            "BROWSERBASE_API_KEY",
+            "AGENT_BROWSER_ENCRYPTION_KEY",
+            "AWS_SECRET_ACCESS_KEY",
+            "AWS_SESSION_TOKEN",
+            "apiKey",
+            "x-api-key",
+            "Authorization",
+            "Proxy-Authorization",
+            "Cookie",
+            "Set-Cookie",
+            "password",
+            "proxyPassword",
+        ] {
+            assert!(is_secret_name(name), "{} should be secret", name);
+        }
+        for name in [
+            "AGENT_BROWSER_SESSION",
+            "AGENT_BROWSER_HEADED",
+            "AWS_REGION",
+            "keyboard",
+            "url",
+            "monkey",
+        ] {
+            assert!(!is_secret_name(name), "{} should not be secret", name);
+        }
+    }
+
+    #[test]
+    fn test_redact_url_strips_userinfo_and_query_values() {
+        assert_eq!(
+            redact_url("wss://user:pw@connect.example.com/cdp?apiKey=abc&session=42"),
+            "wss://connect.example.com/cdp?apiKey=REDACTED&session=REDACTED"
+        );
+        assert_eq!(
+            redact_url("https://example.com/path"),
+            "https://example.com/path"
+        );
+        assert_eq!(redact_url("not a url"), "not a url");
+    }
+
+    #[test]
+    fn test_redact_text_handles_urls_headers_and_pairs() {
+        assert_eq!(
+            redact_text("[daemon] connecting to \"wss://cdp.example.com/?token=t0k\" now"),
+            "[daemon] connecting to \"wss://cdp.example.com/?token=REDACTED\" now"
+        );
+        assert_eq!(
+            redact_text("  Cookie: sid=abc; theme=dark"),
+            "  Cookie: [REDACTED]"
+        );
+        assert_eq!(
+            redact_text("login password=hunter2 user=bob"),
+            "login password=[REDACTED] user=bob"
+        );
+        assert_eq!(redact_text("Navigated: ok"), "Navigated: ok");
+    }
+
+    #[test]
+    fn test_redact_value_walks_nested_json() {
+        let mut value = json!({
+            "cdp": "wss://host/?apiKey=k",
+            "proxyPassword": "pw",
+            "headers": { "Authorization": "Bearer x", "Accept": "text/html" },
+            "cookies": [{ "name": "sid", "value": "v" }],
+            "token": null,
+            "count": 3
+        });
+        redact_value(&mut value);
+        assert_eq!(value["cdp"], "wss://host/?apiKey=REDACTED");
+        assert_eq!(value["proxyPassword"], REDACTED);
+        assert_eq!(value["headers"]["Authorization"], REDACTED);
+        assert_eq!(value["headers"]["Accept"], "text/html");
+        assert_eq!(value["cookies"], REDACTED);
+        assert_eq!(value["token"], Value::Null);
+        assert_eq!(value["count"], 3);
+    }
+
+    fn hidden(words: &[&str], patterns: Option<&[String]>) -> Vec<String> {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        let cmd = crate::commands::parse_command(&words, &crate::flags::parse_flags(&[])).unwrap();
+        CommandRedactor::new(patterns).secrets(&cmd).words(&words)
+    }
+
+    #[test]
+    fn test_secrets_follow_marked_fields() {
+        assert_eq!(
+            hidden(&["set", "credentials", "admin", "hunter2"], None),
+            vec!["set", "credentials", "admin", "***"]
+        );
+        assert_eq!(
+            hidden(
+                &[
+                    "set",
+                    "credentials",
+                    "admin",
+                    "hunter2",
+                    "--origin",
+                    "https://example.com"
+                ],
+                None
+            ),
+            vec![
+                "set",
+                "credentials",
+                "admin",
+                "***",
+                "--origin",
+                "https://example.com"
+            ]
+        );
+        // Nothing secret on the command line for the stdin and clear forms.
+        assert_eq!(
+            hidden(&["set", "credentials", "admin", "--password-stdin"], None),
+            vec!["set", "credentials", "admin", "--password-stdin"]
+        );
+        assert_eq!(
+            hidden(&["set", "credentials", "clear"], None),
+            vec!["set", "credentials", "clear"]
+        );
+        assert_eq!(
+            hidden(
+                &[
+                    "cookies",
+                    "set",
+                    "session",
+                    "abc123",
+                    "--domain",
+                    "example.com",
+                    "--path",
+                    "/"
+                ],
+                None
+            ),
+            vec![
+                "cookies",
+                "set",
+                "session",
+                "***",
+                "--domain",
+                "example.com",
+                "--path",
+                "/"
+            ]
+        );
+        assert_eq!(
+            hidden(&["cookies", "set", "theme", "dark"], None),
+            vec!["cookies", "set", "theme", "dark"]
+        );
+        assert_eq!(
+            hidden(
+                &["storage", "local", "set", "authToken", "eyJhbGciOi.x.y"],
+                None
+            ),
+            vec!["storage", "local", "set", "authToken", "***"]
+        );
+        assert_eq!(
+            hidden(
+                &[
+                    "set",
+                    "headers",
+                    r#"{"Authorization":"Bearer t0k3n","Accept":"text/html"}"#
+                ],
+                None
+            ),
+            vec![
+                "set",
+                "headers",
+                r#"{"Authorization":"***","Accept":"text/html"}"#
+            ]
+        );
+        assert_eq!(hidden(&["click", "@e1"], None), vec!["click", "@e1"]);
+    }
+
+    #[test]
+    fn test_secrets_hide_messages() {
+        let redactor = CommandRedactor::new(None);
+        let cmd = json!({ "action": "storage_set", "key": "authToken", "value": "eyJhbGciOi.x.y" });
+        assert_eq!(
+            redactor
+                .secrets(&cmd)
+                .text("Failed to set authToken=eyJhbGciOi.x.y: quota exceeded"),
+            "Failed to set authToken=***: quota exceeded"
+        );
+
+        // Short secrets only match whole words.
+        let cmd = json!({ "action": "cookies_set", "cookies": [{ "name": "sid", "value": "a" }] });
+        let secrets = redactor.secrets(&cmd);
+        assert_eq!(secrets.text("a"), COMMAND_REDACTED);
+        assert_eq!(secrets.text("cannot set a cookie"), "cannot set a cookie");
+    }
+
+    #[test]
+    fn test_configured_patterns_replace_defaults() {
+        let patterns = ["X-Tenant".to_string()];
+        let redactor = CommandRedactor::new(Some(&patterns));
+        assert!(redactor.is_secret_key("x_tenant_id"));
+        assert!(!redactor.is_secret_key("session"));
+        assert_eq!(
+            hidden(
+                &["storage", "session", "set", "xTenant", "acme"],
+                Some(&patterns)
+            ),
+            vec!["storage", "session", "set", "xTenant", "***"]
+        );
+        assert_eq!(
+            hidden(&["cookies", "set", "session", "abc123"], Some(&patterns)),
+            vec!["cookies", "set", "session", "abc123"]
+        );
+        // Passwords are always secret, whatever the patterns say.
+        assert_eq!(
+            hidden(&["set", "credentials", "admin", "hunter2"], Some(&patterns)),
+            vec!["set", "credentials", "admin", "***"]
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Opt-in client-side cache for read-only getters (`--cache <ms>` or
+//! AGENT_BROWSER_CACHE_MS).
+//!
+//! Agents often repeat `get title`, `get url`, or `snapshot -i` within a
+//! second while reasoning. With a TTL set, successful responses to the
+//! [`CACHEABLE_ACTIONS`] are kept in `<session>.cache.json` next to the
+//! session's pid file and replayed, marked `_cached`, instead of paying a
+//! round trip. Any other action sent from the CLI deletes the file first,
+//! whether or not caching is on for that invocation and including `batch`
+//! and `act` steps and confirmation replies, so a click or navigation is
+//! never followed by a stale answer. A missing, unreadable,
+//! or future-dated entry is a miss.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::atomic_write::write_atomic;
+use crate::connection::get_socket_dir;
+
+/// Actions whose responses may be cached. Everything else invalidates.
+pub const CACHEABLE_ACTIONS: &[&str] = &["title", "url", "gettext", "count", "snapshot"];
+
+/// Most entries a cache file keeps; the oldest go first.
+const MAX_ENTRIES: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub action: String,
+    /// Epoch milliseconds when the response was stored.
+    pub ts: i64,
+    pub response: Value,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CacheFile {
+    pub entries: BTreeMap<String, Entry>,
+}
+
+pub fn cache_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.cache.json", session))
+}
+
+fn action(cmd: &Value) -> Option<&str> {
+    cmd.get("action").and_then(|v| v.as_str())
+}
+
+/// The cache key for `cmd`: the session plus the command serialized without
+/// its per-invocation `id`. `None` for actions that are not cacheable.
+pub fn cache_key(session: &str, cmd: &Value) -> Option<String> {
+    if !CACHEABLE_ACTIONS.contains(&action(cmd)?) {
+        return None;
+    }
+    let mut cmd = cmd.clone();
+    cmd.as_object_mut()?.remove("id");
+    Some(format!("{}\n{}", session, cmd))
+}
+
+/// Whether sending `cmd` must drop the session's cached responses.
+pub fn invalidates(cmd: &Value) -> bool {
+    !action(cmd).is_some_and(|a| CACHEABLE_ACTIONS.contains(&a))
+}
+
+impl CacheFile {
+    /// The stored response for `key` if it is younger than `ttl_ms`.
+    pub fn get(&self, key: &str, now: i64, ttl_ms: u64) -> Option<&Value> {
+        let entry = self.entries.get(key)?;
+        let age = now.checked_sub(entry.ts)?;
+        (age >= 0 && (age as u64) < ttl_ms).then_some(&entry.response)
+    }
+
+    /// Store a response, dropping expired entries. A new snapshot replaces
+    /// every other cached snapshot, because the daemon's refs now belong to
+    /// this one.
+    pub fn insert(&mut self, key: String, action: &str, now: i64, ttl_ms: u64, response: Value) {
+        self.entries.retain(|_, entry| {
+            let age = now.saturating_sub(entry.ts);
+            age >= 0 && (age as u64) < ttl_ms && !(action == "snapshot" && entry.action == action)
+        });
+        self.entries.insert(
+            key,
+            Entry {
+                action: action.to_string(),
+                ts: now,
+                response,
+            },
+        );
+        while self.entries.len() > MAX_ENTRIES {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.ts)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => self.entries.remove(&key),
+                None => break,
+            };
+        }
+    }
+}
+
+/// Read a cache file. Anything unreadable reads as empty.
+pub fn load(path: &Path) -> CacheFile {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn lookup(path: &Path, key: &str, now: i64, ttl_ms: u64) -> Option<Value> {
+    load(path).get(key, now, ttl_ms).cloned()
+}
+
+pub fn store(
+    path: &Path,
+    key: String,
+    action: &str,
+    now: i64,
+    ttl_ms: u64,
+    response: Value,
+) -> std::io::Result<()> {
+    let mut cache = load(path);
+    cache.insert(key, action, now, ttl_ms, response);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(path, serde_json::to_vec(&cache)?)
+}
+
+pub fn invalidate(path: &Path) {
+    let _ = fs::remove_file(path);
+}
+
+/// Drop `session`'s cached responses if sending `cmd` may change them.
+pub fn invalidate_for(session: &str, cmd: &Value) {
+    if invalidates(cmd) {
+        invalidate(&cache_path(session));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cache_key_ignores_id_and_keeps_options() {
+        let a = json!({ "id": "1", "action": "snapshot", "interactive": true });
+        let b = json!({ "id": "2", "action": "snapshot", "interactive": true });
+        let c = json!({ "id": "3", "action": "snapshot" });
+        assert_eq!(cache_key("default", &a), cache_key("default", &b));
+        assert_ne!(cache_key("default", &a), cache_key("default", &c));
+        assert_ne!(cache_key("default", &a), cache_key("work", &a));
+        assert_eq!(
+            cache_key("s", &json!({ "id": "1", "action": "title" })).unwrap(),
+            "s\n{\"action\":\"title\"}"
+        );
+        assert!(cache_key("s", &json!({ "id": "1", "action": "click" })).is_none());
+        assert!(cache_key("s", &json!({ "id": "1" })).is_none());
+    }
+
+    #[test]
+    fn test_invalidates_everything_but_getters() {
+        for action in CACHEABLE_ACTIONS {
+            assert!(!invalidates(&json!({ "action": action })), "{}", action);
+        }
+        for action in [
+            "click", "navigate", "fill", "evaluate", "batch", "act", "close",
+        ] {
+            assert!(invalidates(&json!({ "action": action })), "{}", action);
+        }
+        assert!(invalidates(&json!({})));
+    }
+
+    #[test]
+    fn test_get_respects_ttl_and_clock_skew() {
+        let mut cache = CacheFile::default();
+        cache.insert("k".into(), "title", 1_000, 500, json!({ "success": true }));
+        assert!(cache.get("k", 1_000, 500).is_some());
+        assert!(cache.get("k", 1_499, 500).is_some());
+        assert!(cache.get("k", 1_500, 500).is_none());
+        // Written by a process with a clock ahead of ours.
+        assert!(cache.get("k", 999, 500).is_none());
+        assert!(cache.get("other", 1_000, 500).is_none());
+    }
+
+    #[test]
+    fn test_insert_prunes_expired_snapshots_and_caps_size() {
+        let mut cache = CacheFile::default();
+        cache.insert("old".into(), "title", 0, 100, json!(1));
+        cache.insert("url".into(), "url", 150, 100, json!(2));
+        assert!(!cache.entries.contains_key("old"));
+
+        cache.insert("snap-i".into(), "snapshot", 160, 100, json!(3));
+        cache.insert("snap".into(), "snapshot", 170, 100, json!(4));
+        assert!(!cache.entries.contains_key("snap-i"));
+        assert!(cache.entries.contains_key("snap"));
+        assert!(cache.entries.contains_key("url"));
+
+        let mut cache = CacheFile::default();
+        for i in 0..(MAX_ENTRIES as i64 + 5) {
+            cache.insert(format!("k{}", i), "count", i, 10_000, json!(i));
+        }
+        assert_eq!(cache.entries.len(), MAX_ENTRIES);
+        assert!(!cache.entries.contains_key("k0"));
+        assert!(cache.entries.contains_key(&format!("k{}", MAX_ENTRIES + 4)));
+    }
+
+    #[test]
+    fn test_file_round_trip_and_fail_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("default.cache.json");
+        assert!(lookup(&path, "k", 0, 1_000).is_none());
+
+        store(
+            &path,
+            "k".into(),
+            "title",
+            10,
+            1_000,
+            json!({ "data": "t" }),
+        )
+        .unwrap();
+        assert_eq!(lookup(&path, "k", 20, 1_000), Some(json!({ "data": "t" })));
+
+        // A torn or foreign file is a miss, and the next store replaces it.
+        fs::write(&path, "{\"entries\":{\"k\":{\"ts\"").unwrap();
+        assert!(lookup(&path, "k", 20, 1_000).is_none());
+        store(&path, "k".into(), "title", 30, 1_000, json!(2)).unwrap();
+        assert_eq!(lookup(&path, "k", 40, 1_000), Some(json!(2)));
+
+        invalidate(&path);
+        assert!(!path.exists());
+        invalidate(&path);
+    }
+}
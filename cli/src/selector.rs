@@ -0,0 +1,609 @@
+//! Playwright's text pseudo-classes (`:has-text()`, `:text()`, `:text-is()`,
+//! `:nth-match()`, `:visible`) on top of CSS selectors. The CLI validates
+//! selectors with [`validate_selector`] before sending them, and the daemon
+//! turns a parsed [`ExtendedSelector`] into a DOM query plus JS filters,
+//! since `querySelector` rejects these names.
+//!
+//! A selector can also be anchored at a snapshot ref with `>>`: `@e5 >>
+//! button` matches `button` inside the element behind `@e5` (see
+//! [`parse_ref_scope`]).
+
+use crate::suggest::edit_distance;
+
+/// The Playwright pseudo-classes agent-browser understands.
+pub const PLAYWRIGHT_PSEUDOS: &[&str] = &["has-text", "text", "text-is", "nth-match", "visible"];
+
+/// Names people reach for from other tools, mapped to the supported one.
+const PSEUDO_ALIASES: &[(&str, &str)] = &[
+    ("contains", "has-text"),
+    ("contains-text", "has-text"),
+    ("text-contains", "has-text"),
+    ("has-text-is", "text-is"),
+    ("text-equals", "text-is"),
+    ("exact-text", "text-is"),
+    ("is-visible", "visible"),
+    ("nth", "nth-match"),
+];
+
+/// A filter a Playwright pseudo-class applies to the elements the CSS part
+/// matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PseudoFilter {
+    /// `:has-text("x")`: the element's text contains `x`, case-insensitively.
+    HasText(String),
+    /// `:text("x")`: the smallest element whose text contains `x`.
+    Text(String),
+    /// `:text-is("x")`: the smallest element whose whole text is `x`.
+    TextIs(String),
+    /// `:visible`: the element has a box and is not hidden by CSS.
+    Visible,
+}
+
+/// A selector that uses at least one Playwright pseudo-class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtendedSelector {
+    /// Elements matching `css` that pass every filter, in document order.
+    Filtered {
+        css: String,
+        filters: Vec<PseudoFilter>,
+    },
+    /// `:nth-match(inner, n)`: the n-th (1-based) match of `inner`.
+    NthMatch {
+        inner: Box<ExtendedSelector>,
+        n: usize,
+    },
+}
+
+/// A `:name` or `:name(args)` found while scanning a selector.
+struct Pseudo<'a> {
+    start: usize,
+    end: usize,
+    name: &'a str,
+    arg: Option<&'a str>,
+    nested: bool,
+}
+
+/// What a scan of a selector found outside quotes and attribute brackets.
+struct Scan<'a> {
+    pseudos: Vec<Pseudo<'a>>,
+    /// Byte offsets of top-level combinators.
+    combinators: Vec<usize>,
+    /// Byte offsets of top-level commas (a selector list).
+    commas: Vec<usize>,
+}
+
+/// Check a selector before it is sent to the daemon. Refs and `xpath=`
+/// selectors pass through untouched.
+pub fn validate_selector(selector: &str) -> Result<(), String> {
+    if let Some(scope) = parse_ref_scope(selector) {
+        return scope.map(|_| ());
+    }
+    parse_selector(selector).map(|_| ())
+}
+
+/// Split a ref-anchored selector, `@e5 >> button`, into the ref as written
+/// and the selector to match inside it, with an optional `css=` prefix
+/// dropped. Spaces around `>>` are optional, and a `>>` inside quotes
+/// belongs to the sub-selector. `None` means `selector` is not anchored at
+/// a ref.
+pub fn parse_ref_scope(selector: &str) -> Option<Result<(String, String), String>> {
+    let selector = selector.trim();
+    if !(selector.starts_with('@') || selector.starts_with("ref=")) {
+        return None;
+    }
+    let at = find_chain(selector)?;
+    let head = selector[..at].trim();
+    let sub = selector[at + 2..].trim();
+    Some(check_ref_scope(selector, head, sub).map(|sub| (head.to_string(), sub)))
+}
+
+fn check_ref_scope(selector: &str, head: &str, sub: &str) -> Result<String, String> {
+    let id = head
+        .strip_prefix('@')
+        .or_else(|| head.strip_prefix("ref="))
+        .unwrap_or(head);
+    // A `--ref-prefix` ref carries its prefix, as in `@ab12-e5`.
+    let (prefix, plain) = id.rsplit_once('-').unwrap_or(("", id));
+    let valid_ref = prefix.chars().all(|c| c.is_ascii_alphanumeric())
+        && plain
+            .strip_prefix('e')
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    if !valid_ref {
+        return Err(format!(
+            "Invalid ref `{}` before `>>` in `{}`. Use a ref from the latest snapshot, e.g. `@e5 >> button`",
+            head, selector
+        ));
+    }
+    let sub = sub.strip_prefix("css=").unwrap_or(sub).trim();
+    if sub.is_empty() {
+        return Err(format!(
+            "Missing selector after `>>` in `{}`, e.g. `{} >> button`",
+            selector, head
+        ));
+    }
+    if find_chain(sub).is_some() {
+        return Err(format!(
+            "Only one `>>` is supported, after the ref, in `{}`",
+            selector
+        ));
+    }
+    if sub.starts_with('@') || sub.starts_with("ref=") {
+        return Err(format!(
+            "The selector after `>>` must be CSS or xpath=, not a ref, in `{}`",
+            selector
+        ));
+    }
+    parse_selector(sub)?;
+    Ok(sub.to_string())
+}
+
+/// Byte offset of the first `>>` outside quotes and attribute brackets.
+fn find_chain(selector: &str) -> Option<usize> {
+    let bytes = selector.as_bytes();
+    let mut quote: Option<u8> = None;
+    let mut in_brackets = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match (quote, c) {
+            (_, b'\\') => {
+                i += 2;
+                continue;
+            }
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(c),
+            (None, b'[') => in_brackets = true,
+            (None, b']') => in_brackets = false,
+            (None, b'>') if !in_brackets && bytes.get(i + 1) == Some(&b'>') => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Parse the Playwright pseudo-classes out of `selector`. `Ok(None)` means a
+/// plain CSS selector (or a ref or XPath) that needs no special handling.
+pub fn parse_selector(selector: &str) -> Result<Option<ExtendedSelector>, String> {
+    let selector = selector.trim();
+    if selector.starts_with('@')
+        || selector.starts_with("ref=")
+        || selector.starts_with("xpath=")
+        || selector.starts_with("//")
+    {
+        return Ok(None);
+    }
+    let scan = scan(selector)?;
+
+    // `:nth-match()` wraps a whole selector, which may use the other
+    // pseudo-classes itself.
+    if let Some(nth) = scan.pseudos.first().filter(|p| p.name == "nth-match") {
+        if nth.start == 0 && nth.end == selector.len() {
+            return parse_nth_match(nth.arg.unwrap_or(""), selector).map(Some);
+        }
+    }
+
+    for pseudo in &scan.pseudos {
+        let playwright = PLAYWRIGHT_PSEUDOS.contains(&pseudo.name);
+        if playwright && pseudo.nested {
+            return Err(format!(
+                "':{}' is only supported on the element being matched, not inside another pseudo-class, in selector `{}`",
+                pseudo.name, selector
+            ));
+        }
+        if !playwright {
+            if let Some(suggestion) = suggest_pseudo(pseudo.name) {
+                return Err(format!(
+                    "Unknown pseudo-class ':{}' in selector `{}`. Did you mean ':{}'?",
+                    pseudo.name,
+                    selector,
+                    display_pseudo(suggestion)
+                ));
+            }
+        }
+    }
+
+    let playwright: Vec<&Pseudo> = scan
+        .pseudos
+        .iter()
+        .filter(|p| !p.nested && PLAYWRIGHT_PSEUDOS.contains(&p.name))
+        .collect();
+    let Some(first) = playwright.first() else {
+        return Ok(None);
+    };
+
+    if playwright.iter().any(|p| p.name == "nth-match") {
+        return Err(format!(
+            ":nth-match() must wrap the whole selector, e.g. :nth-match(li:has-text(\"Buy\"), 2), in `{}`",
+            selector
+        ));
+    }
+
+    if !scan.commas.is_empty() {
+        return Err(format!(
+            "Playwright pseudo-classes are not supported in a selector list (a, b): `{}`",
+            selector
+        ));
+    }
+    if scan.combinators.iter().any(|&at| at > first.start) {
+        return Err(format!(
+            "':{}' must be on the last element of the selector, e.g. `.list button:has-text(\"Delete\")`, in `{}`",
+            first.name, selector
+        ));
+    }
+
+    let mut filters = Vec::new();
+    let mut css = String::new();
+    let mut last = 0;
+    for pseudo in &playwright {
+        css.push_str(&selector[last..pseudo.start]);
+        last = pseudo.end;
+        filters.push(pseudo_filter(pseudo, selector)?);
+    }
+    css.push_str(&selector[last..]);
+    let mut css = css.trim_start().to_string();
+    if css.is_empty() || css.ends_with(|c: char| c.is_whitespace() || "> +~".contains(c)) {
+        css.push('*');
+    }
+    Ok(Some(ExtendedSelector::Filtered { css, filters }))
+}
+
+/// Best guess for a mistyped Playwright pseudo-class, if the name is close
+/// enough to one to be a typo rather than some other CSS pseudo-class.
+fn suggest_pseudo(name: &str) -> Option<&'static str> {
+    if let Some((_, target)) = PSEUDO_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return Some(target);
+    }
+    if name.starts_with('-') {
+        return None;
+    }
+    PLAYWRIGHT_PSEUDOS
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance > 0 && *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn display_pseudo(name: &str) -> String {
+    if name == "visible" {
+        name.to_string()
+    } else {
+        format!("{}()", name)
+    }
+}
+
+fn pseudo_filter(pseudo: &Pseudo, selector: &str) -> Result<PseudoFilter, String> {
+    if pseudo.name == "visible" {
+        return match pseudo.arg {
+            None => Ok(PseudoFilter::Visible),
+            Some(_) => Err(format!(":visible takes no arguments in `{}`", selector)),
+        };
+    }
+    let text = pseudo
+        .arg
+        .map(unquote)
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| {
+            format!(
+                ":{}() needs the text to match, e.g. :{}(\"Delete\"), in `{}`",
+                pseudo.name, pseudo.name, selector
+            )
+        })?;
+    Ok(match pseudo.name {
+        "has-text" => PseudoFilter::HasText(text),
+        "text" => PseudoFilter::Text(text),
+        _ => PseudoFilter::TextIs(text),
+    })
+}
+
+fn parse_nth_match(arg: &str, selector: &str) -> Result<ExtendedSelector, String> {
+    let usage = || {
+        format!(
+            ":nth-match() takes a selector and a 1-based index, e.g. :nth-match(button, 2), in `{}`",
+            selector
+        )
+    };
+    let comma = *scan(arg)?.commas.last().ok_or_else(usage)?;
+    let inner = arg[..comma].trim();
+    let n = arg[comma + 1..]
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|&n| n >= 1)
+        .ok_or_else(usage)?;
+    if inner.is_empty() {
+        return Err(usage());
+    }
+    let inner = match parse_selector(inner)? {
+        Some(extended) => extended,
+        None => ExtendedSelector::Filtered {
+            css: inner.to_string(),
+            filters: Vec::new(),
+        },
+    };
+    Ok(ExtendedSelector::NthMatch {
+        inner: Box::new(inner),
+        n,
+    })
+}
+
+/// Strip one pair of matching quotes and their backslash escapes, and
+/// collapse whitespace, the way the text is compared in the page.
+fn unquote(arg: &str) -> String {
+    let arg = arg.trim();
+    let inner = match arg.chars().next() {
+        Some(q @ ('"' | '\'')) if arg.len() >= 2 && arg.ends_with(q) => &arg[1..arg.len() - 1],
+        _ => arg,
+    };
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(next);
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Walk `selector` once, honoring quotes, backslash escapes, attribute
+/// brackets, and parentheses, and record its pseudo-classes and top-level
+/// combinators.
+fn scan(selector: &str) -> Result<Scan<'_>, String> {
+    let bytes = selector.as_bytes();
+    let mut scan = Scan {
+        pseudos: Vec::new(),
+        combinators: Vec::new(),
+        commas: Vec::new(),
+    };
+    // Start offsets of the pseudo-classes whose parentheses are open.
+    let mut open: Vec<Option<usize>> = Vec::new();
+    let mut quote: Option<u8> = None;
+    let mut in_brackets = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c == b'\\' {
+            i += 2;
+            continue;
+        }
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+        match c {
+            b'"' | b'\'' => quote = Some(c),
+            b'[' => in_brackets = true,
+            b']' => in_brackets = false,
+            _ if in_brackets => {}
+            b'(' => open.push(None),
+            b')' => {
+                let Some(started) = open.pop() else {
+                    return Err(format!("Unbalanced ')' in selector `{}`", selector));
+                };
+                if let Some(index) = started {
+                    let pseudo = &mut scan.pseudos[index];
+                    let arg_start = pseudo.start + 1 + pseudo.name.len() + 1;
+                    pseudo.arg = Some(&selector[arg_start..i]);
+                    pseudo.end = i + 1;
+                }
+            }
+            b':' if bytes.get(i + 1) == Some(&b':') => {
+                // A pseudo-element such as ::before.
+                i += 2;
+                continue;
+            }
+            b':' => {
+                let name_end = selector[i + 1..]
+                    .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '-' || ch == '_'))
+                    .map_or(selector.len(), |n| i + 1 + n);
+                let name = &selector[i + 1..name_end];
+                if !name.is_empty() {
+                    scan.pseudos.push(Pseudo {
+                        start: i,
+                        end: name_end,
+                        name,
+                        arg: None,
+                        nested: !open.is_empty(),
+                    });
+                    if bytes.get(name_end) == Some(&b'(') {
+                        open.push(Some(scan.pseudos.len() - 1));
+                        i = name_end + 1;
+                        continue;
+                    }
+                }
+                i = name_end;
+                continue;
+            }
+            b',' if open.is_empty() => scan.commas.push(i),
+            b' ' | b'\t' | b'\n' | b'>' | b'+' | b'~' if open.is_empty() => {
+                scan.combinators.push(i);
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if quote.is_some() {
+        return Err(format!(
+            "Unterminated quote in selector `{}`. Quote the whole selector so the shell keeps it in one argument, e.g. 'button:has-text(\"Save draft\")'",
+            selector
+        ));
+    }
+    if !open.is_empty() {
+        return Err(format!("Unbalanced '(' in selector `{}`", selector));
+    }
+    Ok(scan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filtered(css: &str, filters: Vec<PseudoFilter>) -> Option<ExtendedSelector> {
+        Some(ExtendedSelector::Filtered {
+            css: css.to_string(),
+            filters,
+        })
+    }
+
+    #[test]
+    fn test_parse_selector_table() {
+        use PseudoFilter::*;
+        let cases: &[(&str, Option<ExtendedSelector>)] = &[
+            ("button.primary", None),
+            ("@e3", None),
+            ("xpath=//button[contains(., ':text')]", None),
+            ("a[href=\"https://x.dev:8080/\"]", None),
+            ("li:not(.done):first-child", None),
+            ("p::before", None),
+            (
+                "button:has-text(\"Delete\")",
+                filtered("button", vec![HasText("Delete".into())]),
+            ),
+            (
+                ".list  button:has-text('Save   draft'):visible",
+                filtered(".list  button", vec![HasText("Save draft".into()), Visible]),
+            ),
+            (
+                ":text(Sign in)",
+                filtered("*", vec![Text("Sign in".into())]),
+            ),
+            (
+                "a:text-is(\"Say \\\"hi\\\" (now)\")",
+                filtered("a", vec![TextIs("Say \"hi\" (now)".into())]),
+            ),
+            (
+                "nav > :visible:not(.x)",
+                filtered("nav > :not(.x)", vec![Visible]),
+            ),
+            (
+                "div :has-text(\"x\")",
+                filtered("div *", vec![HasText("x".into())]),
+            ),
+            (
+                ":nth-match(li:has-text(\"a, b\"), 2)",
+                Some(ExtendedSelector::NthMatch {
+                    inner: Box::new(ExtendedSelector::Filtered {
+                        css: "li".into(),
+                        filters: vec![HasText("a, b".into())],
+                    }),
+                    n: 2,
+                }),
+            ),
+            (
+                ":nth-match(ul > li, 1)",
+                Some(ExtendedSelector::NthMatch {
+                    inner: Box::new(ExtendedSelector::Filtered {
+                        css: "ul > li".into(),
+                        filters: Vec::new(),
+                    }),
+                    n: 1,
+                }),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(&parse_selector(input).unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_validate_selector_errors_table() {
+        let cases: &[(&str, &str)] = &[
+            (
+                "button:text-contains(\"Delete\")",
+                "Did you mean ':has-text()'",
+            ),
+            ("button:has-txt(\"Delete\")", "Did you mean ':has-text()'"),
+            ("a:contains('x')", "Did you mean ':has-text()'"),
+            ("li:visble", "Did you mean ':visible'"),
+            ("li:nth(2)", "Did you mean ':nth-match()'"),
+            ("button:has-text(\"Delete", "Unterminated quote"),
+            ("button:has-text(\"x\"", "Unbalanced '('"),
+            ("button)", "Unbalanced ')'"),
+            ("button:has-text()", "needs the text"),
+            ("button:has-text", "needs the text"),
+            ("li:visible(1)", "takes no arguments"),
+            ("div:has-text(\"x\") > span", "last element"),
+            ("a:has-text(\"x\"), b", "selector list"),
+            ("div:not(:has-text(\"x\"))", "not inside another"),
+            ("ul :nth-match(li, 2)", "wrap the whole selector"),
+            (":nth-match(li, 0)", "1-based index"),
+            (":nth-match(li)", "1-based index"),
+        ];
+        for (input, message) in cases {
+            let err = validate_selector(input).unwrap_err();
+            assert!(err.contains(message), "{}: {}", input, err);
+        }
+    }
+
+    #[test]
+    fn test_parse_ref_scope() {
+        let split = |s: &str| parse_ref_scope(s).map(|r| r.unwrap());
+        let pair = |a: &str, b: &str| Some((a.to_string(), b.to_string()));
+        assert_eq!(split("@e5 >> button"), pair("@e5", "button"));
+        assert_eq!(
+            split("  @e5>>css=button.primary "),
+            pair("@e5", "button.primary")
+        );
+        assert_eq!(
+            split("ref=e12 >>  li:nth-child(2)"),
+            pair("ref=e12", "li:nth-child(2)")
+        );
+        assert_eq!(split("@ab12-e3 >> input"), pair("@ab12-e3", "input"));
+        assert_eq!(
+            split("@e5 >> xpath=.//button"),
+            pair("@e5", "xpath=.//button")
+        );
+        assert_eq!(
+            split(r#"@e5 >> button:has-text("a >> b")"#),
+            pair("@e5", r#"button:has-text("a >> b")"#)
+        );
+        assert_eq!(
+            split(r#"@e5 >> [data-x='>>']"#),
+            pair("@e5", r#"[data-x='>>']"#)
+        );
+        assert_eq!(parse_ref_scope("@e5"), None);
+        assert_eq!(parse_ref_scope("div >> span"), None);
+        assert_eq!(parse_ref_scope(r#"@e5:has-text(">>")"#), None);
+    }
+
+    #[test]
+    fn test_ref_scope_errors_table() {
+        let cases: &[(&str, &str)] = &[
+            ("@x5 >> button", "Invalid ref `@x5`"),
+            ("@e >> button", "Invalid ref `@e`"),
+            ("@e5 button >> a", "Invalid ref `@e5 button`"),
+            ("@e5 >>", "Missing selector after `>>`"),
+            ("@e5 >> css=", "Missing selector after `>>`"),
+            ("@e5 >> .card >> button", "Only one `>>`"),
+            ("@e5 >> @e6", "not a ref"),
+            ("@e5 >> button:has-txt(\"x\")", "Did you mean ':has-text()'"),
+            ("@e5 >> button:has-text(\"x)", "Unterminated quote"),
+        ];
+        for (input, message) in cases {
+            let err = validate_selector(input).unwrap_err();
+            assert!(err.contains(message), "{}: {}", input, err);
+        }
+    }
+
+    #[test]
+    fn test_quotes_survive_shell_split() {
+        let words = crate::commands::shell_words_split(
+            r#"click 'button:has-text("Save draft")' --new-tab"#,
+        );
+        assert_eq!(words[1], r#"button:has-text("Save draft")"#);
+        assert!(validate_selector(&words[1]).is_ok());
+
+        let words = crate::commands::shell_words_split(r#"click "li:text-is(\"Done\")""#);
+        assert_eq!(words[1], r#"li:text-is("Done")"#);
+        assert!(validate_selector(&words[1]).is_ok());
+    }
+}
@@ -0,0 +1,661 @@
+//! `agent-browser selftest`: drive a scratch daemon session through the core
+//! command loop against a built-in fixture page.
+//!
+//! The steps mirror what an agent does on a real page: open it, take an
+//! interactive snapshot, click and fill by ref, read text back, screenshot,
+//! and close. The fixture's `#out` paragraph reflects both the click and the
+//! input value, so one `get text` proves the two interactions landed. Step
+//! commands and assertions are plain functions over response JSON so they
+//! can be tested without a browser.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde_json::{json, Value};
+
+use crate::color;
+use crate::connection::{cleanup_stale_files, ensure_daemon, send_command, DaemonOptions};
+use crate::flags::Flags;
+
+const USAGE: &str = "selftest";
+
+pub const FIXTURE_TITLE: &str = "agent-browser selftest";
+const FIXTURE_HTML: &str = concat!(
+    "<!doctype html><title>agent-browser selftest</title>",
+    "<script>var clicked=false;function render(){",
+    "document.getElementById('out').textContent=",
+    "(clicked?'clicked':'idle')+':'+document.getElementById('name').value}</script>",
+    "<button onclick=\"clicked=true;render()\">Go</button>",
+    "<input id=\"name\" aria-label=\"Name\" oninput=\"render()\">",
+    "<p id=\"out\">idle:</p>",
+);
+const FILL_VALUE: &str = "selftest";
+const EXPECTED_TEXT: &str = "clicked:selftest";
+
+/// The fixture as a `data:` URL. The markup avoids `#` and `%` so it needs
+/// no escaping.
+pub fn fixture_url() -> String {
+    format!("data:text/html,{}", FIXTURE_HTML)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Navigate,
+    Snapshot,
+    Click,
+    Fill,
+    GetText,
+    Screenshot,
+    Close,
+}
+
+impl Step {
+    pub const ALL: [Step; 7] = [
+        Step::Navigate,
+        Step::Snapshot,
+        Step::Click,
+        Step::Fill,
+        Step::GetText,
+        Step::Screenshot,
+        Step::Close,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Step::Navigate => "navigate",
+            Step::Snapshot => "snapshot",
+            Step::Click => "click",
+            Step::Fill => "fill",
+            Step::GetText => "get text",
+            Step::Screenshot => "screenshot",
+            Step::Close => "close",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail,
+    /// Not run because an earlier step failed.
+    Skip,
+}
+
+impl Status {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Pass => "pass",
+            Status::Fail => "fail",
+            Status::Skip => "skip",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub step: Step,
+    pub status: Status,
+    pub duration: Duration,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub steps: Vec<StepResult>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|s| s.status == Status::Pass)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|s| s.duration).sum()
+    }
+}
+
+/// State carried between steps: the refs found by the snapshot and where the
+/// screenshot goes.
+#[derive(Debug, Clone)]
+pub struct Context {
+    pub screenshot_path: PathBuf,
+    button_ref: Option<String>,
+    input_ref: Option<String>,
+}
+
+impl Context {
+    pub fn new(screenshot_path: PathBuf) -> Self {
+        Self {
+            screenshot_path,
+            button_ref: None,
+            input_ref: None,
+        }
+    }
+}
+
+/// The daemon command for `step`. Click and fill fail here if the snapshot
+/// did not find their refs.
+pub fn command(step: Step, ctx: &Context) -> Result<Value, String> {
+    let id = format!("selftest-{}", step.as_str().replace(' ', "-"));
+    let ref_for = |r: &Option<String>, what: &str| {
+        r.as_ref()
+            .map(|r| format!("@{}", r))
+            .ok_or_else(|| format!("No {} ref from the snapshot", what))
+    };
+    Ok(match step {
+        Step::Navigate => json!({ "id": id, "action": "navigate", "url": fixture_url() }),
+        Step::Snapshot => json!({ "id": id, "action": "snapshot", "interactive": true }),
+        Step::Click => json!({
+            "id": id,
+            "action": "click",
+            "selector": ref_for(&ctx.button_ref, "button")?,
+        }),
+        Step::Fill => json!({
+            "id": id,
+            "action": "fill",
+            "selector": ref_for(&ctx.input_ref, "textbox")?,
+            "value": FILL_VALUE,
+        }),
+        Step::GetText => json!({ "id": id, "action": "gettext", "selector": "#out" }),
+        Step::Screenshot => json!({
+            "id": id,
+            "action": "screenshot",
+            "path": ctx.screenshot_path.to_string_lossy(),
+        }),
+        Step::Close => json!({ "id": id, "action": "close" }),
+    })
+}
+
+/// Find the ref whose entry has `role`, and `name` if given.
+fn find_ref(
+    refs: &serde_json::Map<String, Value>,
+    role: &str,
+    name: Option<&str>,
+) -> Option<String> {
+    refs.iter()
+        .find(|(_, entry)| {
+            entry.get("role").and_then(|v| v.as_str()) == Some(role)
+                && name.is_none_or(|n| entry.get("name").and_then(|v| v.as_str()) == Some(n))
+        })
+        .map(|(r, _)| r.clone())
+}
+
+/// Assert on the `data` of a successful response, recording refs in `ctx`.
+pub fn check(step: Step, data: &Value, ctx: &mut Context) -> Result<(), String> {
+    match step {
+        Step::Navigate => {
+            let title = data.get("title").and_then(|v| v.as_str()).unwrap_or("");
+            if title != FIXTURE_TITLE {
+                return Err(format!(
+                    "Expected title '{}', got '{}'",
+                    FIXTURE_TITLE, title
+                ));
+            }
+        }
+        Step::Snapshot => {
+            let refs = data
+                .get("refs")
+                .and_then(|v| v.as_object())
+                .ok_or("Snapshot returned no refs")?;
+            ctx.button_ref = find_ref(refs, "button", Some("Go"));
+            ctx.input_ref = find_ref(refs, "textbox", None);
+            if ctx.button_ref.is_none() {
+                return Err("Snapshot has no ref for the 'Go' button".to_string());
+            }
+            if ctx.input_ref.is_none() {
+                return Err("Snapshot has no ref for the textbox".to_string());
+            }
+        }
+        Step::GetText => {
+            let text = data.get("text").and_then(|v| v.as_str()).unwrap_or("");
+            if text.trim() != EXPECTED_TEXT {
+                return Err(format!("Expected text '{}', got '{}'", EXPECTED_TEXT, text));
+            }
+        }
+        Step::Screenshot => {
+            let path = data
+                .get("path")
+                .and_then(|v| v.as_str())
+                .ok_or("Screenshot returned no path")?;
+            match fs::metadata(path) {
+                Ok(meta) if meta.len() > 0 => {}
+                Ok(_) => return Err(format!("Screenshot {} is empty", path)),
+                Err(e) => return Err(format!("Screenshot {} not written: {}", path, e)),
+            }
+        }
+        Step::Click | Step::Fill | Step::Close => {}
+    }
+    Ok(())
+}
+
+fn run_step<F>(step: Step, ctx: &mut Context, send: &mut F) -> Result<(), String>
+where
+    F: FnMut(Value) -> Result<Value, String>,
+{
+    let resp = send(command(step, ctx)?)?;
+    if resp.get("success").and_then(|v| v.as_bool()) != Some(true) {
+        return Err(resp
+            .get("error")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown error")
+            .to_string());
+    }
+    check(step, resp.get("data").unwrap_or(&Value::Null), ctx)
+}
+
+/// Run every step through `send`. After a failure the remaining steps are
+/// skipped, except `close`, which always runs so the session is torn down.
+pub fn run_steps<F>(ctx: &mut Context, mut send: F) -> Report
+where
+    F: FnMut(Value) -> Result<Value, String>,
+{
+    let mut steps = Vec::new();
+    let mut failed = false;
+    for step in Step::ALL {
+        if failed && step != Step::Close {
+            steps.push(StepResult {
+                step,
+                status: Status::Skip,
+                duration: Duration::ZERO,
+                error: None,
+            });
+            continue;
+        }
+        let started = Instant::now();
+        let outcome = run_step(step, ctx, &mut send);
+        failed |= outcome.is_err();
+        steps.push(StepResult {
+            step,
+            status: if outcome.is_ok() {
+                Status::Pass
+            } else {
+                Status::Fail
+            },
+            duration: started.elapsed(),
+            error: outcome.err(),
+        });
+    }
+    Report { steps }
+}
+
+pub fn report_json(report: &Report) -> Value {
+    let steps: Vec<Value> = report
+        .steps
+        .iter()
+        .map(|s| {
+            let mut entry = json!({
+                "step": s.step.as_str(),
+                "status": s.status.as_str(),
+                "durationMs": s.duration.as_millis() as u64,
+            });
+            if let Some(ref e) = s.error {
+                entry["error"] = json!(e);
+            }
+            entry
+        })
+        .collect();
+    json!({
+        "success": report.passed(),
+        "data": {
+            "passed": report.passed(),
+            "durationMs": report.duration().as_millis() as u64,
+            "steps": steps,
+        },
+    })
+}
+
+fn print_text(report: &Report) {
+    println!("{}", color::bold("agent-browser selftest"));
+    for s in &report.steps {
+        let ms = format!("{}ms", s.duration.as_millis());
+        match s.status {
+            Status::Pass => println!(
+                "  {} {:<10} {}",
+                color::success_indicator(),
+                s.step.as_str(),
+                color::dim(&ms)
+            ),
+            Status::Fail => println!(
+                "  {} {:<10} {} {}",
+                color::error_indicator(),
+                s.step.as_str(),
+                color::dim(&ms),
+                s.error.as_deref().unwrap_or("")
+            ),
+            Status::Skip => println!("  - {:<10} {}", s.step.as_str(), color::dim("skipped")),
+        }
+    }
+    let passed = report
+        .steps
+        .iter()
+        .filter(|s| s.status == Status::Pass)
+        .count();
+    let line = format!(
+        "{}/{} steps passed in {}ms",
+        passed,
+        report.steps.len(),
+        report.duration().as_millis()
+    );
+    if report.passed() {
+        println!("{}", color::green(&line));
+    } else {
+        println!("{}", color::red(&line));
+    }
+}
+
+fn scratch_session() -> String {
+    format!(
+        "selftest-{}-{}",
+        std::process::id(),
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0)
+    )
+}
+
+/// Closes the scratch session and removes its sidecar files, even if a step
+/// panics. `close` is sent again in case the close step never ran.
+struct SessionGuard<'a> {
+    session: String,
+    screenshot: &'a Path,
+}
+
+impl Drop for SessionGuard<'_> {
+    fn drop(&mut self) {
+        let _ = send_command(
+            json!({ "id": "selftest-cleanup", "action": "close" }),
+            &self.session,
+        );
+        cleanup_stale_files(&self.session);
+        let _ = fs::remove_file(self.screenshot);
+    }
+}
+
+/// `agent-browser selftest`. Only the browser flags that decide how Chrome
+/// starts (`--headed`, `--executable-path`, `--engine`, `--debug`) carry
+/// over; the scratch session ignores the rest of the user's config. Exits 0
+/// only if every step passes.
+pub fn run_selftest(args: &[String], flags: &Flags) {
+    let fail = |message: String| -> ! {
+        if flags.json {
+            println!("{}", json!({ "success": false, "error": message }));
+        } else {
+            eprintln!("{} {}", color::error_indicator(), message);
+        }
+        exit(1);
+    };
+
+    if args.len() > 1 {
+        fail(format!("Too many arguments. Usage: {}", USAGE));
+    }
+
+    let session = scratch_session();
+    let screenshot = env::temp_dir().join(format!("{}.png", session));
+    let daemon_opts = DaemonOptions {
+        headed: flags.headed,
+        debug: flags.debug,
+        executable_path: flags.executable_path.as_deref(),
+        extensions: &[],
+        init_scripts: &[],
+        enable: &[],
+        args: None,
+        user_agent: None,
+        proxy: None,
+        proxy_bypass: None,
+        proxy_username: None,
+        proxy_password: None,
+        ignore_https_errors: false,
+        ignore_https_origins: &[],
+        allow_file_access: false,
+        hide_scrollbars: true,
+        webgpu: false,
+        profile: None,
+        user_data_dir: None,
+        state: None,
+        provider: None,
+        provider_options: None,
+        device: None,
+        session_name: None,
+        restore_save: None,
+        restore_check_url: None,
+        restore_check_text: None,
+        restore_check_fn: None,
+        download_path: None,
+        allowed_domains: None,
+        action_policy: None,
+        confirm_actions: None,
+        engine: flags.engine.as_deref(),
+        auto_connect: false,
+        idle_timeout: None,
+        default_timeout: None,
+        cdp: None,
+        no_auto_dialog: false,
+        plugins: None,
+    };
+    if let Err(e) = ensure_daemon(&session, &daemon_opts) {
+        fail(format!("Could not start daemon: {}", e));
+    }
+
+    let report = {
+        let _guard = SessionGuard {
+            session: session.clone(),
+            screenshot: &screenshot,
+        };
+        let mut ctx = Context::new(screenshot.clone());
+        run_steps(&mut ctx, |cmd| {
+            send_command(cmd, &session)
+                .and_then(|resp| serde_json::to_value(resp).map_err(|e| e.to_string()))
+        })
+    };
+
+    if flags.json {
+        println!("{}", report_json(&report));
+    } else {
+        print_text(&report);
+    }
+    if !report.passed() {
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok(data: Value) -> Result<Value, String> {
+        Ok(json!({ "success": true, "data": data }))
+    }
+
+    fn snapshot_data() -> Value {
+        json!({
+            "snapshot": "- button \"Go\" [ref=e1]\n- textbox \"Name\" [ref=e2]",
+            "refs": {
+                "e1": { "role": "button", "name": "Go" },
+                "e2": { "role": "textbox", "name": "Name" },
+            },
+        })
+    }
+
+    /// A daemon that answers every step the way a working browser would.
+    fn healthy(cmd: &Value, screenshot: &Path) -> Result<Value, String> {
+        match cmd["action"].as_str().unwrap() {
+            "navigate" => ok(json!({ "url": fixture_url(), "title": FIXTURE_TITLE })),
+            "snapshot" => ok(snapshot_data()),
+            "click" => ok(json!({ "clicked": cmd["selector"] })),
+            "fill" => ok(json!({ "filled": cmd["selector"] })),
+            "gettext" => ok(json!({ "text": EXPECTED_TEXT })),
+            "screenshot" => {
+                fs::write(screenshot, b"png").unwrap();
+                ok(json!({ "path": screenshot.to_string_lossy() }))
+            }
+            "close" => ok(json!({ "closed": true })),
+            other => panic!("unexpected action {}", other),
+        }
+    }
+
+    fn statuses(report: &Report) -> Vec<&'static str> {
+        report.steps.iter().map(|s| s.status.as_str()).collect()
+    }
+
+    #[test]
+    fn test_fixture_url_needs_no_escaping() {
+        let url = fixture_url();
+        assert!(url.starts_with("data:text/html,<!doctype html>"));
+        assert!(!url.contains('#') && !url.contains('%'));
+        assert!(url.contains(&format!("<title>{}</title>", FIXTURE_TITLE)));
+    }
+
+    #[test]
+    fn test_all_steps_pass_and_use_snapshot_refs() {
+        let dir = tempfile::tempdir().unwrap();
+        let shot = dir.path().join("shot.png");
+        let mut ctx = Context::new(shot.clone());
+        let mut sent = Vec::new();
+        let report = run_steps(&mut ctx, |cmd| {
+            sent.push(cmd.clone());
+            healthy(&cmd, &shot)
+        });
+
+        assert!(report.passed());
+        assert_eq!(statuses(&report), vec!["pass"; 7]);
+        let actions: Vec<&str> = sent.iter().map(|c| c["action"].as_str().unwrap()).collect();
+        assert_eq!(
+            actions,
+            vec![
+                "navigate",
+                "snapshot",
+                "click",
+                "fill",
+                "gettext",
+                "screenshot",
+                "close"
+            ]
+        );
+        assert_eq!(sent[1]["interactive"], true);
+        assert_eq!(sent[2]["selector"], "@e1");
+        assert_eq!(sent[3]["selector"], "@e2");
+        assert_eq!(sent[3]["value"], FILL_VALUE);
+        assert_eq!(sent[5]["path"], shot.to_string_lossy().as_ref());
+    }
+
+    #[test]
+    fn test_failure_skips_remaining_steps_but_still_closes() {
+        let dir = tempfile::tempdir().unwrap();
+        let shot = dir.path().join("shot.png");
+        let mut ctx = Context::new(shot.clone());
+        let report = run_steps(&mut ctx, |cmd| match cmd["action"].as_str().unwrap() {
+            "snapshot" => {
+                ok(json!({ "snapshot": "", "refs": { "e1": { "role": "button", "name": "Go" } } }))
+            }
+            _ => healthy(&cmd, &shot),
+        });
+
+        assert!(!report.passed());
+        assert_eq!(
+            statuses(&report),
+            vec!["pass", "fail", "skip", "skip", "skip", "skip", "pass"]
+        );
+        assert_eq!(
+            report.steps[1].error.as_deref(),
+            Some("Snapshot has no ref for the textbox")
+        );
+    }
+
+    #[test]
+    fn test_daemon_errors_and_transport_errors_fail_the_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let shot = dir.path().join("shot.png");
+
+        let mut ctx = Context::new(shot.clone());
+        let report = run_steps(&mut ctx, |cmd| match cmd["action"].as_str().unwrap() {
+            "click" => Ok(json!({ "success": false, "error": "Element is covered" })),
+            _ => healthy(&cmd, &shot),
+        });
+        assert_eq!(report.steps[2].status, Status::Fail);
+        assert_eq!(report.steps[2].error.as_deref(), Some("Element is covered"));
+
+        let mut ctx = Context::new(shot.clone());
+        let report = run_steps(&mut ctx, |_| Err("Connection refused".to_string()));
+        assert_eq!(statuses(&report)[0], "fail");
+        assert_eq!(report.steps[6].status, Status::Fail);
+    }
+
+    #[test]
+    fn test_check_assertions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ctx = Context::new(dir.path().join("missing.png"));
+
+        let err = check(Step::Navigate, &json!({ "title": "Other" }), &mut ctx).unwrap_err();
+        assert_eq!(err, "Expected title 'agent-browser selftest', got 'Other'");
+
+        let err = check(Step::GetText, &json!({ "text": "idle:selftest" }), &mut ctx).unwrap_err();
+        assert!(err.contains("got 'idle:selftest'"), "{}", err);
+        assert!(check(
+            Step::GetText,
+            &json!({ "text": "clicked:selftest\n" }),
+            &mut ctx
+        )
+        .is_ok());
+
+        let missing = ctx.screenshot_path.to_string_lossy().to_string();
+        let err = check(Step::Screenshot, &json!({ "path": missing }), &mut ctx).unwrap_err();
+        assert!(err.contains("not written"), "{}", err);
+
+        assert_eq!(
+            command(Step::Click, &ctx).unwrap_err(),
+            "No button ref from the snapshot"
+        );
+    }
+
+    #[test]
+    fn test_report_json_shape() {
+        let report = Report {
+            steps: vec![
+                StepResult {
+                    step: Step::Navigate,
+                    status: Status::Pass,
+                    duration: Duration::from_millis(12),
+                    error: None,
+                },
+                StepResult {
+                    step: Step::GetText,
+                    status: Status::Fail,
+                    duration: Duration::from_millis(3),
+                    error: Some("boom".to_string()),
+                },
+            ],
+        };
+        let out = report_json(&report);
+        assert_eq!(out["success"], false);
+        assert_eq!(out["data"]["durationMs"], 15);
+        assert_eq!(out["data"]["steps"][0]["step"], "navigate");
+        assert!(out["data"]["steps"][0].get("error").is_none());
+        assert_eq!(out["data"]["steps"][1]["step"], "get text");
+        assert_eq!(out["data"]["steps"][1]["error"], "boom");
+    }
+
+    /// Runs the steps against an in-process daemon and real Chrome.
+    ///   cargo test selftest_live -- --ignored
+    #[test]
+    #[ignore]
+    fn selftest_live_against_chrome() {
+        use crate::native::actions::{execute_command, DaemonState};
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mut state = DaemonState::new();
+        let launch = json!({ "id": "launch", "action": "launch", "headless": true });
+        let resp = rt.block_on(execute_command(&launch, &mut state));
+        assert_eq!(resp["success"], true, "{}", resp);
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut ctx = Context::new(dir.path().join("selftest.png"));
+        let report = run_steps(&mut ctx, |cmd| {
+            Ok(rt.block_on(execute_command(&cmd, &mut state)))
+        });
+        assert!(report.passed(), "{}", report_json(&report));
+    }
+}
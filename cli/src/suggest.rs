@@ -0,0 +1,94 @@
+//! "Did you mean" suggestions for mistyped command, subcommand, and
+//! environment variable names.
+
+/// Candidates further than this from the input are not suggested.
+pub const MAX_SUGGESTION_DISTANCE: usize = 2;
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Levenshtein distance between two strings, by character.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(cur)
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+/// Up to three `candidates` within [`MAX_SUGGESTION_DISTANCE`] of `input`
+/// (case-insensitive), nearest first with ties in candidate order. A
+/// candidate listed in `aliases` is reported as its canonical name, so
+/// `naviagte` suggests `open` rather than `navigate`.
+pub fn suggestions(
+    input: &str,
+    candidates: &[&'static str],
+    aliases: &[(&str, &'static str)],
+) -> Vec<&'static str> {
+    let input = input.to_lowercase();
+    let mut ranked: Vec<(usize, &'static str)> = candidates
+        .iter()
+        .map(|candidate| {
+            let canonical = aliases
+                .iter()
+                .find(|(alias, _)| alias == candidate)
+                .map_or(*candidate, |(_, canonical)| *canonical);
+            (edit_distance(&input, candidate), canonical)
+        })
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .collect();
+    ranked.sort_by_key(|(distance, _)| *distance);
+    let mut out: Vec<&'static str> = Vec::new();
+    for (_, name) in ranked {
+        if !out.contains(&name) && out.len() < MAX_SUGGESTIONS {
+            out.push(name);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("HEADED", "HEADED"), 0);
+        assert_eq!(edit_distance("HEADLES", "HEADED"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("screnshot", "screenshot"), 1);
+    }
+
+    #[test]
+    fn test_suggestions_rank_and_limit() {
+        let candidates = ["screenshot", "snapshot", "scroll", "click", "close"];
+        assert_eq!(
+            suggestions("screnshot", &candidates, &[]),
+            vec!["screenshot"]
+        );
+        assert_eq!(suggestions("clse", &candidates, &[]), vec!["close"]);
+        assert_eq!(suggestions("SCROL", &candidates, &[]), vec!["scroll"]);
+        assert!(suggestions("zzzzzz", &candidates, &[]).is_empty());
+
+        let many = ["aab", "aac", "aad", "aae", "aaf"];
+        assert_eq!(suggestions("aaa", &many, &[]), vec!["aab", "aac", "aad"]);
+    }
+
+    #[test]
+    fn test_suggestions_map_aliases_to_canonical_names() {
+        let candidates = ["open", "goto", "navigate", "press", "key"];
+        let aliases = [("goto", "open"), ("navigate", "open"), ("key", "press")];
+        assert_eq!(suggestions("naviagte", &candidates, &aliases), vec!["open"]);
+        assert_eq!(suggestions("goto2", &candidates, &aliases), vec!["open"]);
+        assert_eq!(suggestions("ke", &candidates, &aliases), vec!["press"]);
+    }
+}
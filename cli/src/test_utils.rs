@@ -1,9 +1,22 @@
 use std::sync::{Mutex, MutexGuard};
 
+use serde_json::Value;
+
+use crate::connection::Response;
+
 /// Global mutex shared across all test modules to prevent parallel tests from
 /// interfering with each other when mutating environment variables.
 pub static ENV_MUTEX: Mutex<()> = Mutex::new(());
 
+/// A successful daemon response carrying `data`.
+pub fn ok_response(data: Value) -> Response {
+    Response {
+        success: true,
+        data: Some(data),
+        ..Default::default()
+    }
+}
+
 /// RAII guard that locks [`ENV_MUTEX`] and restores environment variables on drop.
 pub struct EnvGuard<'a> {
     _lock: MutexGuard<'a, ()>,
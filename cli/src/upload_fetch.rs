@@ -0,0 +1,355 @@
+//! `upload <selector> <url>`: fetch remote files for the daemon.
+//!
+//! The daemon only uploads local paths, so the CLI downloads each
+//! `http(s)://` entry into a private temp directory first and rewrites the
+//! command to point at the downloaded files. The directory is removed once
+//! the daemon has answered, unless `--keep-temp` was given.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use reqwest::header::USER_AGENT;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use crate::color;
+use crate::commands::{is_remote_upload, DEFAULT_MAX_FETCH_SIZE};
+use crate::humanize::humanize_bytes;
+
+const USER_AGENT_VALUE: &str = concat!("agent-browser/", env!("CARGO_PKG_VERSION"), " upload");
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Temp directory holding fetched uploads, removed on drop unless kept.
+pub struct FetchedUploads {
+    dir: PathBuf,
+    keep: bool,
+}
+
+impl Drop for FetchedUploads {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+/// File name for a fetched URL: the last path segment, or `download`.
+/// Pages see this name on the file input, so it is kept when it is safe.
+fn file_name_for(url: &reqwest::Url) -> String {
+    let name = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .map(|s| urlencoding::decode_binary(s.as_bytes()).into_owned())
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+        .unwrap_or_default();
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    if name.is_empty() || name == "." || name == ".." {
+        "download".to_string()
+    } else {
+        name
+    }
+}
+
+/// Stream `url` into `dest`, failing once more than `max_bytes` arrive.
+/// Errors name the URL and, for HTTP failures, the status.
+pub async fn fetch_to_file(
+    client: &Client,
+    url: &str,
+    dest: &Path,
+    max_bytes: u64,
+    progress: &mut dyn FnMut(String),
+) -> Result<u64, String> {
+    let too_large = || {
+        format!(
+            "Failed to fetch {}: larger than --max-fetch-size ({})",
+            url,
+            humanize_bytes(max_bytes)
+        )
+    };
+    let response = client
+        .get(url)
+        .header(USER_AGENT, USER_AGENT_VALUE)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Failed to fetch {}: HTTP {}", url, status));
+    }
+    let expected = response.content_length();
+    if expected.is_some_and(|len| len > max_bytes) {
+        return Err(too_large());
+    }
+    progress(match expected {
+        Some(len) => format!("Fetching {} ({})", url, humanize_bytes(len)),
+        None => format!("Fetching {}", url),
+    });
+
+    let mut file = fs::File::create(dest)
+        .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let mut written: u64 = 0;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+        written += chunk.len() as u64;
+        if written > max_bytes {
+            return Err(too_large());
+        }
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    }
+    Ok(written)
+}
+
+/// A new, unpredictably named directory under the system temp dir, 0700 on
+/// unix so other local users cannot read the fetched files, even when they
+/// are kept with `--keep-temp`.
+fn create_temp_dir() -> Result<PathBuf, String> {
+    let mut suffix = [0u8; 6];
+    getrandom::getrandom(&mut suffix).map_err(|e| e.to_string())?;
+    let suffix: String = suffix.iter().map(|b| format!("{:02x}", b)).collect();
+    let dir = std::env::temp_dir().join(format!("agent-browser-upload-{}", suffix));
+    let mut dir_builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        dir_builder.mode(0o700);
+    }
+    dir_builder
+        .create(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+async fn fetch_all(
+    files: &mut [Value],
+    dir: &Path,
+    max_bytes: u64,
+    progress: &mut dyn FnMut(String),
+) -> Result<(), String> {
+    let client = Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    for (i, entry) in files.iter_mut().enumerate() {
+        let Some(url) = entry.as_str().filter(|f| is_remote_upload(f)) else {
+            continue;
+        };
+        let parsed =
+            reqwest::Url::parse(url).map_err(|e| format!("Invalid upload URL {}: {}", url, e))?;
+        // One subdirectory per entry keeps each original file name.
+        let slot = dir.join(i.to_string());
+        fs::create_dir_all(&slot)
+            .map_err(|e| format!("Failed to create {}: {}", slot.display(), e))?;
+        let dest = slot.join(file_name_for(&parsed));
+        let size = fetch_to_file(&client, url, &dest, max_bytes, progress).await?;
+        progress(format!("Fetched {} ({})", url, humanize_bytes(size)));
+        *entry = json!(dest.to_string_lossy());
+    }
+    Ok(())
+}
+
+/// Download the remote entries of an `upload` command and point `files` at
+/// the local copies. Returns `None` when there was nothing to fetch.
+/// Progress notes go to stderr unless `quiet`.
+pub fn fetch_remote_uploads(
+    cmd: &mut Value,
+    quiet: bool,
+) -> Result<Option<FetchedUploads>, String> {
+    let max_bytes = cmd
+        .get("maxFetchSize")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_MAX_FETCH_SIZE);
+    let keep = cmd
+        .get("keepTemp")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if let Some(obj) = cmd.as_object_mut() {
+        obj.remove("maxFetchSize");
+        obj.remove("keepTemp");
+    }
+    let Some(files) = cmd.get_mut("files").and_then(|v| v.as_array_mut()) else {
+        return Ok(None);
+    };
+    if !files
+        .iter()
+        .any(|f| f.as_str().is_some_and(is_remote_upload))
+    {
+        return Ok(None);
+    }
+
+    let fetched = FetchedUploads {
+        dir: create_temp_dir()?,
+        keep,
+    };
+    let mut progress = |note: String| {
+        if !quiet {
+            eprintln!("{}", color::dim(&note));
+        }
+    };
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| format!("Failed to create tokio runtime: {}", e))?;
+    rt.block_on(fetch_all(files, &fetched.dir, max_bytes, &mut progress))?;
+    if keep && !quiet {
+        eprintln!(
+            "{}",
+            color::dim(&format!("Kept fetched files in {}", fetched.dir.display()))
+        );
+    }
+    Ok(Some(fetched))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve each canned response to one connection, in order.
+    async fn serve(responses: Vec<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0_u8; 2048];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn ok_response(body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/csv\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    #[tokio::test]
+    async fn test_fetch_to_file_success() {
+        let base = serve(vec![ok_response("a,b\n1,2\n")]).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("report.csv");
+        let mut notes = Vec::new();
+        let size = fetch_to_file(
+            &Client::new(),
+            &format!("{}/report.csv", base),
+            &dest,
+            1024,
+            &mut |n| notes.push(n),
+        )
+        .await
+        .unwrap();
+        assert_eq!(size, 8);
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "a,b\n1,2\n");
+        assert!(notes[0].starts_with("Fetching http://"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_to_file_reports_status_and_url() {
+        let base = serve(vec![
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        ])
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("{}/missing.pdf", base);
+        let err = fetch_to_file(
+            &Client::new(),
+            &url,
+            &dir.path().join("missing.pdf"),
+            1024,
+            &mut |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains(&url), "{}", err);
+        assert!(err.contains("HTTP 404"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_to_file_enforces_size_limit() {
+        let body = "x".repeat(100);
+        // Declared length over the limit fails before reading the body.
+        let declared = serve(vec![ok_response(&body)]).await;
+        // Without a length the limit is enforced while streaming.
+        let streamed = serve(vec![format!(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n{}",
+            body
+        )])
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        for base in [declared, streamed] {
+            let err = fetch_to_file(
+                &Client::new(),
+                &format!("{}/big.bin", base),
+                &dir.path().join("big.bin"),
+                10,
+                &mut |_| {},
+            )
+            .await
+            .unwrap_err();
+            assert!(err.contains("--max-fetch-size (10 B)"), "{}", err);
+            assert!(err.contains("/big.bin"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_fetch_remote_uploads_rewrites_and_cleans_up() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let base = rt.block_on(serve(vec![ok_response("hello")]));
+        let url = format!("{}/fixtures/My%20File.txt", base);
+        let mut cmd = json!({
+            "action": "upload",
+            "selector": "#file",
+            "files": ["./local.txt", url],
+            "maxFetchSize": 1024,
+        });
+        let fetched = fetch_remote_uploads(&mut cmd, true).unwrap().unwrap();
+        assert!(cmd.get("maxFetchSize").is_none());
+        assert_eq!(cmd["files"][0], "./local.txt");
+        let local = PathBuf::from(cmd["files"][1].as_str().unwrap());
+        assert_eq!(local.file_name().unwrap(), "My File.txt");
+        assert_eq!(fs::read_to_string(&local).unwrap(), "hello");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&fetched.dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        let dir = fetched.dir.clone();
+        drop(fetched);
+        assert!(!dir.exists());
+        drop(rt);
+    }
+
+    #[test]
+    fn test_fetch_remote_uploads_without_urls_is_a_no_op() {
+        let mut cmd = json!({ "action": "upload", "selector": "#f", "files": ["a.txt"] });
+        assert!(fetch_remote_uploads(&mut cmd, true).unwrap().is_none());
+        assert_eq!(cmd["files"][0], "a.txt");
+    }
+
+    #[test]
+    fn test_file_name_for_url() {
+        let name = |u: &str| file_name_for(&reqwest::Url::parse(u).unwrap());
+        assert_eq!(name("https://x.example/a/report.pdf?sig=1"), "report.pdf");
+        assert_eq!(name("https://x.example/"), "download");
+        assert_eq!(name("https://x.example"), "download");
+        assert_eq!(name("https://x.example/a%2Fb.txt"), "a_b.txt");
+    }
+}
@@ -0,0 +1,175 @@
+//! Split a page URL into its components for `get url --parts` and
+//! `get url --query <name>`.
+
+use serde_json::{json, Map, Value};
+use url::Url;
+
+/// The components of `raw` as a JSON object: `scheme`, `host`, `port`,
+/// `path`, `query`, and `fragment`. The host is the ASCII (punycode) form,
+/// the port falls back to the scheme's default, and `query` maps each
+/// decoded key to its decoded value, or to an array of values when the key
+/// repeats. Missing components are `null`.
+pub fn url_parts(raw: &str) -> Result<Value, String> {
+    let url = Url::parse(raw).map_err(|e| format!("Could not parse URL '{}': {}", raw, e))?;
+    let query = url.query().map(|_| query_map(&url));
+    Ok(json!({
+        "scheme": url.scheme(),
+        "host": url.host_str(),
+        "port": url.port_or_known_default(),
+        "path": url.path(),
+        "query": query,
+        "fragment": url.fragment(),
+    }))
+}
+
+fn query_map(url: &Url) -> Value {
+    let mut map = Map::new();
+    for (key, value) in url.query_pairs() {
+        let value = Value::String(value.into_owned());
+        match map.get_mut(key.as_ref()) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => *existing = json!([existing.take(), value]),
+            None => {
+                map.insert(key.into_owned(), value);
+            }
+        }
+    }
+    Value::Object(map)
+}
+
+/// The decoded values of query parameter `name` in `raw`, in order. Empty
+/// when the parameter is absent or the URL does not parse.
+pub fn query_values(raw: &str, name: &str) -> Vec<String> {
+    let Ok(url) = Url::parse(raw) else {
+        return Vec::new();
+    };
+    url.query_pairs()
+        .filter(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+        .collect()
+}
+
+/// Labeled lines for text output, one query parameter per line (repeated
+/// keys print once per value).
+pub fn format_parts_text(parts: &Value) -> String {
+    let field = |key: &str| match parts.get(key) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => "-".to_string(),
+    };
+    let mut lines = vec![
+        format!("scheme:   {}", field("scheme")),
+        format!("host:     {}", field("host")),
+        format!("port:     {}", field("port")),
+        format!("path:     {}", field("path")),
+    ];
+    match parts.get("query").and_then(|v| v.as_object()) {
+        Some(query) if !query.is_empty() => {
+            lines.push("query:".to_string());
+            for (key, value) in query {
+                let values = match value {
+                    Value::Array(values) => values.iter().collect(),
+                    other => vec![other],
+                };
+                for value in values {
+                    lines.push(format!("  {} = {}", key, value.as_str().unwrap_or("")));
+                }
+            }
+        }
+        _ => lines.push("query:    -".to_string()),
+    }
+    lines.push(format!("fragment: {}", field("fragment")));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_parts_full() {
+        let parts =
+            url_parts("https://user@example.com:8443/a/b%20c?q=hello%20world&x=1#top").unwrap();
+        assert_eq!(
+            parts,
+            json!({
+                "scheme": "https",
+                "host": "example.com",
+                "port": 8443,
+                "path": "/a/b%20c",
+                "query": { "q": "hello world", "x": "1" },
+                "fragment": "top",
+            })
+        );
+    }
+
+    #[test]
+    fn test_url_parts_defaults_and_missing() {
+        let parts = url_parts("http://example.com").unwrap();
+        assert_eq!(parts["port"], 80);
+        assert_eq!(parts["path"], "/");
+        assert!(parts["query"].is_null());
+        assert!(parts["fragment"].is_null());
+
+        // A bare `?` is an empty query, not a missing one.
+        assert_eq!(
+            url_parts("https://example.com/?").unwrap()["query"],
+            json!({})
+        );
+
+        let blank = url_parts("about:blank").unwrap();
+        assert_eq!(blank["scheme"], "about");
+        assert!(blank["host"].is_null());
+        assert!(blank["port"].is_null());
+        assert_eq!(blank["path"], "blank");
+    }
+
+    #[test]
+    fn test_url_parts_repeated_and_encoded_keys() {
+        let parts =
+            url_parts("https://example.com/s?tag=a&tag=b&tag=c%26d&name=J%C3%BCrgen+M&empty=")
+                .unwrap();
+        assert_eq!(parts["query"]["tag"], json!(["a", "b", "c&d"]));
+        assert_eq!(parts["query"]["name"], "Jürgen M");
+        assert_eq!(parts["query"]["empty"], "");
+    }
+
+    #[test]
+    fn test_url_parts_idn_host() {
+        let parts = url_parts("https://bücher.example/straße?q=ü").unwrap();
+        assert_eq!(parts["host"], "xn--bcher-kva.example");
+        assert_eq!(parts["path"], "/stra%C3%9Fe");
+        assert_eq!(parts["query"]["q"], "ü");
+    }
+
+    #[test]
+    fn test_url_parts_malformed() {
+        for raw in ["", "not a url", "/relative/path", "http://[::1"] {
+            let err = url_parts(raw).unwrap_err();
+            assert!(err.starts_with("Could not parse URL"), "{}", err);
+            assert!(query_values(raw, "q").is_empty());
+        }
+    }
+
+    #[test]
+    fn test_query_values() {
+        let url = "https://example.com/?id=42&tag=a&tag=b%20c";
+        assert_eq!(query_values(url, "id"), vec!["42"]);
+        assert_eq!(query_values(url, "tag"), vec!["a", "b c"]);
+        assert!(query_values(url, "missing").is_empty());
+        assert!(query_values(url, "ID").is_empty());
+    }
+
+    #[test]
+    fn test_format_parts_text() {
+        let parts = url_parts("https://example.com/p?tag=a&tag=b#f").unwrap();
+        assert_eq!(
+            format_parts_text(&parts),
+            "scheme:   https\nhost:     example.com\nport:     443\npath:     /p\nquery:\n  tag = a\n  tag = b\nfragment: f"
+        );
+        let bare = url_parts("about:blank").unwrap();
+        let text = format_parts_text(&bare);
+        assert!(text.contains("host:     -"), "{}", text);
+        assert!(text.contains("query:    -"), "{}", text);
+    }
+}